@@ -0,0 +1,153 @@
+//! Precomputed activation manifest
+//!
+//! `lode install` writes `.lode/activation.paths` next to the Gemfile: every
+//! load path the bundle needs (each installed gem's require paths and its
+//! built native extension directory), sorted, one absolute path per line.
+//! `lode exec --cached-setup` puts those paths on `RUBYLIB` directly instead
+//! of scanning the gem directory and reading gemspecs on every run.
+//!
+//! The header records the SHA-256 of the lockfile the manifest was written
+//! for, so a manifest outlived by a lockfile change is ignored.
+
+use crate::lockfile::Lockfile;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Manifest file, relative to the Gemfile's directory
+pub const MANIFEST_FILE: &str = ".lode/activation.paths";
+
+/// Header line prefix carrying the lockfile digest
+const DIGEST_PREFIX: &str = "# lockfile-sha256: ";
+
+/// Digest identifying the lockfile a manifest was written for
+#[must_use]
+pub fn lockfile_digest(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// Load paths of every locked gem installed in `gems_dir`, sorted
+///
+/// Includes each gem's built extensions from `extensions_dir` when present,
+/// as `RubyGems` activation would.
+#[must_use]
+pub fn load_paths(lockfile: &Lockfile, gems_dir: &Path, extensions_dir: &Path) -> Vec<PathBuf> {
+    let full_names: BTreeSet<String> = lockfile
+        .gems
+        .iter()
+        .map(|gem| format!("{}-{}", gem.name, gem.version))
+        .chain(
+            lockfile
+                .git_gems
+                .iter()
+                .map(|gem| format!("{}-{}", gem.name, gem.version)),
+        )
+        .chain(
+            lockfile
+                .path_gems
+                .iter()
+                .map(|gem| format!("{}-{}", gem.name, gem.version)),
+        )
+        .collect();
+
+    let mut paths = BTreeSet::new();
+    for full_name in &full_names {
+        let gem_dir = gems_dir.join(full_name);
+        if !gem_dir.is_dir() {
+            continue;
+        }
+        paths.extend(crate::group_manifest::require_paths(&gem_dir));
+        let extension_dir = extensions_dir.join(full_name);
+        if extension_dir.is_dir() {
+            paths.insert(extension_dir);
+        }
+    }
+    paths.into_iter().collect()
+}
+
+/// Write the manifest for the lockfile with `digest` to `path`
+///
+/// # Errors
+///
+/// Returns an error if the manifest or its directory cannot be written.
+pub fn write(path: &Path, digest: &str, paths: &[PathBuf]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut contents = format!("# Generated by `lode install`\n{DIGEST_PREFIX}{digest}\n");
+    for entry in paths {
+        contents.push_str(&entry.to_string_lossy());
+        contents.push('\n');
+    }
+    fs::write(path, contents)
+}
+
+/// Load paths from the manifest at `path`, or `None` when it was written
+/// for a lockfile other than the one with `digest`
+///
+/// # Errors
+///
+/// Returns an error if the manifest cannot be read.
+pub fn read(path: &Path, digest: &str) -> io::Result<Option<Vec<PathBuf>>> {
+    let contents = fs::read_to_string(path)?;
+    let fresh = contents
+        .lines()
+        .find_map(|line| line.strip_prefix(DIGEST_PREFIX))
+        .is_some_and(|recorded| recorded == digest);
+    if !fresh {
+        return Ok(None);
+    }
+    Ok(Some(
+        contents
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(PathBuf::from)
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::GemSpec;
+
+    #[test]
+    fn manifest_includes_extensions_and_goes_stale() -> io::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let gems_dir = temp.path().join("gems");
+        let extensions_dir = temp.path().join("extensions");
+        fs::create_dir_all(gems_dir.join("rack-3.1.7/lib"))?;
+        fs::create_dir_all(gems_dir.join("nokogiri-1.16.0/lib"))?;
+        fs::create_dir_all(extensions_dir.join("nokogiri-1.16.0"))?;
+
+        let mut lockfile = Lockfile::new();
+        for (name, version) in [("rack", "3.1.7"), ("nokogiri", "1.16.0"), ("pg", "1.5.6")] {
+            lockfile.gems.push(GemSpec::new(
+                name.to_string(),
+                version.to_string(),
+                None,
+                Vec::new(),
+                Vec::new(),
+            ));
+        }
+
+        let paths = load_paths(&lockfile, &gems_dir, &extensions_dir);
+        assert_eq!(
+            paths,
+            vec![
+                extensions_dir.join("nokogiri-1.16.0"),
+                gems_dir.join("nokogiri-1.16.0/lib"),
+                gems_dir.join("rack-3.1.7/lib"),
+            ]
+        );
+
+        let manifest = temp.path().join(MANIFEST_FILE);
+        let digest = lockfile_digest("GEM\n");
+        write(&manifest, &digest, &paths)?;
+        assert_eq!(read(&manifest, &digest)?, Some(paths));
+        assert_eq!(read(&manifest, &lockfile_digest("GEM\n  changed\n"))?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+//! Security advisories from the Ruby Advisory Database
+//!
+//! Reads a checkout of `rubysec/ruby-advisory-db`, where each advisory is
+//! `gems/<gem>/<id>.yml` listing the `patched_versions` and
+//! `unaffected_versions` requirements. A locked version is vulnerable when
+//! it satisfies neither. The checkout is cloned into the lode cache, or
+//! taken from `LODE_ADVISORY_DB` for offline and pinned setups.
+
+use crate::gem_utils::requirement_satisfied;
+use crate::git::GitManager;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Upstream advisory database repository
+pub const REPOSITORY_URL: &str = "https://github.com/rubysec/ruby-advisory-db.git";
+
+/// One advisory for one gem
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Advisory {
+    /// File stem: the CVE, GHSA or OSVDB identifier
+    #[serde(skip)]
+    pub id: String,
+    pub gem: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub cve: Option<String>,
+    #[serde(default)]
+    pub ghsa: Option<String>,
+    /// `none`, `low`, `medium`, `high` or `critical`
+    #[serde(default)]
+    pub criticality: Option<String>,
+    #[serde(default)]
+    pub patched_versions: Vec<String>,
+    #[serde(default)]
+    pub unaffected_versions: Vec<String>,
+}
+
+impl Advisory {
+    /// Whether `version` is affected
+    #[must_use]
+    pub fn is_vulnerable(&self, version: &str) -> bool {
+        !self
+            .patched_versions
+            .iter()
+            .chain(&self.unaffected_versions)
+            .any(|requirement| requirement_satisfied(version, requirement))
+    }
+
+    /// Whether any version can be upgraded to
+    #[must_use]
+    pub const fn has_patch(&self) -> bool {
+        !self.patched_versions.is_empty()
+    }
+}
+
+/// Advisories indexed by gem name
+#[derive(Debug, Clone, Default)]
+pub struct AdvisoryDatabase {
+    advisories: BTreeMap<String, Vec<Advisory>>,
+}
+
+impl AdvisoryDatabase {
+    /// Load the checkout at `LODE_ADVISORY_DB`, else clone or fetch the
+    /// upstream database into `<cache_dir>/advisory-db`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be fetched or read.
+    pub fn open(cache_dir: &Path) -> anyhow::Result<Self> {
+        let path = match crate::env_vars::lode_advisory_db() {
+            Some(path) => PathBuf::from(path),
+            None => fetch(&cache_dir.join("advisory-db"))?,
+        };
+        Ok(Self::load(&path)?)
+    }
+
+    /// Read every `gems/<gem>/<id>.yml` under `root`
+    ///
+    /// Files that fail to parse are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `gems` directory cannot be read.
+    pub fn load(root: &Path) -> std::io::Result<Self> {
+        let mut advisories: BTreeMap<String, Vec<Advisory>> = BTreeMap::new();
+        for gem_dir in fs::read_dir(root.join("gems"))?.flatten() {
+            let Ok(files) = fs::read_dir(gem_dir.path()) else {
+                continue;
+            };
+            for file in files.flatten() {
+                let path = file.path();
+                if path.extension().is_none_or(|ext| ext != "yml") {
+                    continue;
+                }
+                let Some(advisory) = fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|yaml| serde_yaml::from_str::<Advisory>(&yaml).ok())
+                else {
+                    continue;
+                };
+                let id = path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                advisories
+                    .entry(advisory.gem.clone())
+                    .or_default()
+                    .push(Advisory { id, ..advisory });
+            }
+        }
+        for list in advisories.values_mut() {
+            list.sort_by(|a, b| a.id.cmp(&b.id));
+        }
+        Ok(Self { advisories })
+    }
+
+    /// Build a database from advisories already in memory
+    #[must_use]
+    pub fn from_advisories(list: impl IntoIterator<Item = Advisory>) -> Self {
+        let mut advisories: BTreeMap<String, Vec<Advisory>> = BTreeMap::new();
+        for advisory in list {
+            advisories
+                .entry(advisory.gem.clone())
+                .or_default()
+                .push(advisory);
+        }
+        Self { advisories }
+    }
+
+    /// Advisories affecting `gem` at `version`
+    #[must_use]
+    pub fn vulnerabilities(&self, gem: &str, version: &str) -> Vec<&Advisory> {
+        self.advisories
+            .get(gem)
+            .into_iter()
+            .flatten()
+            .filter(|advisory| advisory.is_vulnerable(version))
+            .collect()
+    }
+
+    /// Number of advisories loaded
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.advisories.values().map(Vec::len).sum()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.advisories.is_empty()
+    }
+}
+
+/// Clone or fetch the upstream database, returning the checkout
+fn fetch(git_cache: &Path) -> anyhow::Result<PathBuf> {
+    let git = GitManager::new(git_cache.to_path_buf())?;
+    let revision = git.resolve_revision(REPOSITORY_URL, None, None)?;
+    Ok(git.clone_and_checkout(REPOSITORY_URL, &revision)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advisory(id: &str, patched: &[&str], unaffected: &[&str]) -> Advisory {
+        Advisory {
+            id: id.to_string(),
+            gem: "rack".to_string(),
+            patched_versions: patched.iter().map(ToString::to_string).collect(),
+            unaffected_versions: unaffected.iter().map(ToString::to_string).collect(),
+            ..Advisory::default()
+        }
+    }
+
+    #[test]
+    fn versions_outside_patched_and_unaffected_are_vulnerable() {
+        let advisory = advisory(
+            "CVE-2024-26146",
+            &["~> 2.2.8.1", ">= 3.0.9.1"],
+            &["< 2.0.0"],
+        );
+        assert!(advisory.is_vulnerable("2.2.8"));
+        assert!(advisory.is_vulnerable("3.0.9"));
+        assert!(!advisory.is_vulnerable("2.2.8.1"));
+        assert!(!advisory.is_vulnerable("3.1.0"));
+        assert!(!advisory.is_vulnerable("1.6.13"));
+    }
+
+    #[test]
+    fn load_reads_advisories_per_gem() -> std::io::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let rack = temp.path().join("gems/rack");
+        fs::create_dir_all(&rack)?;
+        fs::write(
+            rack.join("CVE-2024-26146.yml"),
+            "---\ngem: rack\ncve: 2024-26146\ntitle: Header parsing ReDoS\n\
+             criticality: medium\npatched_versions:\n  - \"~> 2.2.8.1\"\n  - \">= 3.0.9.1\"\n",
+        )?;
+        fs::write(rack.join("README.md"), "not an advisory")?;
+
+        let database = AdvisoryDatabase::load(temp.path())?;
+        assert_eq!(database.len(), 1);
+        let [found] = database
+            .vulnerabilities("rack", "3.0.0")
+            .try_into()
+            .expect("one advisory");
+        assert_eq!(found.id, "CVE-2024-26146");
+        assert_eq!(found.criticality.as_deref(), Some("medium"));
+        assert!(database.vulnerabilities("rack", "3.0.9.1").is_empty());
+        assert!(database.vulnerabilities("rails", "7.0.0").is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+//! Historical native extension build times
+//!
+//! Every successful extension build is remembered in
+//! `<cache>/build-history.json` by gem, version and machine. Installs use
+//! the history to start the slowest builds first (longest processing time
+//! first), so a concurrent build phase is not left waiting on one large
+//! compile that started last, and `lode stats builds` reports it.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Version of `build-history.json`; a file from another version is dropped
+/// and build estimates start again from no history
+const FORMAT_VERSION: u32 = 1;
+
+/// Durations kept per gem, version and machine; older ones are dropped
+const MAX_SAMPLES: usize = 5;
+
+/// Build times of one gem version on one machine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildRecord {
+    pub gem: String,
+    pub version: String,
+    /// Host and platform the build ran on (see [`current_machine`])
+    pub machine: String,
+    /// Most recent build durations in milliseconds, oldest first
+    pub samples_ms: Vec<u64>,
+    /// When the last build finished, as a Unix timestamp
+    pub last_built: i64,
+}
+
+impl BuildRecord {
+    /// Mean of the remembered build durations
+    #[must_use]
+    pub fn average(&self) -> Duration {
+        let count = self.samples_ms.len().max(1) as u64;
+        Duration::from_millis(self.samples_ms.iter().sum::<u64>() / count)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HistoryFile {
+    format: u32,
+    records: Vec<BuildRecord>,
+}
+
+/// Extension build durations, persisted in the cache directory
+#[derive(Debug, Clone)]
+pub struct BuildHistory {
+    path: PathBuf,
+    records: Vec<BuildRecord>,
+    dirty: bool,
+}
+
+impl BuildHistory {
+    /// Load `<cache_dir>/build-history.json`, starting empty if it is
+    /// missing or unreadable
+    #[must_use]
+    pub fn load(cache_dir: &Path) -> Self {
+        let path = cache_dir.join("build-history.json");
+        let records = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<HistoryFile>(&bytes).ok())
+            .filter(|file| file.format == FORMAT_VERSION)
+            .map(|file| file.records)
+            .unwrap_or_default();
+        Self {
+            path,
+            records,
+            dirty: false,
+        }
+    }
+
+    /// Every remembered gem, version and machine
+    #[must_use]
+    pub fn records(&self) -> &[BuildRecord] {
+        &self.records
+    }
+
+    /// Remember a successful build of `gem` `version` on `machine`
+    pub fn record(&mut self, gem: &str, version: &str, machine: &str, duration: Duration) {
+        let millis = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+        let now = chrono::Utc::now().timestamp();
+
+        if let Some(record) = self
+            .records
+            .iter_mut()
+            .find(|r| r.gem == gem && r.version == version && r.machine == machine)
+        {
+            record.samples_ms.push(millis);
+            let excess = record.samples_ms.len().saturating_sub(MAX_SAMPLES);
+            record.samples_ms.drain(..excess);
+            record.last_built = now;
+        } else {
+            self.records.push(BuildRecord {
+                gem: gem.to_string(),
+                version: version.to_string(),
+                machine: machine.to_string(),
+                samples_ms: vec![millis],
+                last_built: now,
+            });
+        }
+        self.dirty = true;
+    }
+
+    /// Expected build time of `gem` `version` on `machine`
+    ///
+    /// Prefers this exact version on this machine, then the most recently
+    /// built version on this machine, then the gem on any machine.
+    #[must_use]
+    pub fn estimate(&self, gem: &str, version: &str, machine: &str) -> Option<Duration> {
+        let builds = || self.records.iter().filter(move |r| r.gem == gem);
+
+        if let Some(exact) = builds().find(|r| r.version == version && r.machine == machine) {
+            return Some(exact.average());
+        }
+        if let Some(latest) = builds()
+            .filter(|r| r.machine == machine)
+            .max_by_key(|r| r.last_built)
+        {
+            return Some(latest.average());
+        }
+
+        let averages: Vec<Duration> = builds().map(BuildRecord::average).collect();
+        let count = u32::try_from(averages.len()).ok().filter(|&n| n > 0)?;
+        Some(averages.iter().sum::<Duration>() / count)
+    }
+
+    /// Reorder `items` so the slowest builds start first
+    ///
+    /// Gems without history go ahead of everything: any of them could be the
+    /// longest build. The sort is stable, so ties keep their order.
+    pub fn schedule<T>(&self, items: &mut [T], machine: &str, key: impl Fn(&T) -> (&str, &str)) {
+        items.sort_by_cached_key(|item| {
+            let (gem, version) = key(item);
+            Reverse(
+                self.estimate(gem, version, machine)
+                    .unwrap_or(Duration::MAX),
+            )
+        });
+    }
+
+    /// Persist newly recorded builds
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history file cannot be written.
+    pub fn save(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = HistoryFile {
+            format: FORMAT_VERSION,
+            records: self.records.clone(),
+        };
+        let json = serde_json::to_vec(&file).map_err(io::Error::other)?;
+        let temp = self.path.with_extension("json.tmp");
+        fs::write(&temp, json)?;
+        fs::rename(&temp, &self.path)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// This machine as recorded in the history: host name and platform
+///
+/// Build times depend on the hardware, so a laptop and a CI runner sharing a
+/// cache directory keep separate records.
+#[must_use]
+pub fn current_machine() -> String {
+    let host = std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "localhost".to_string());
+    format!("{host}/{}", crate::platform::detect_current_platform())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_estimates_and_schedules_longest_first() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let mut history = BuildHistory::load(temp.path());
+        let secs = Duration::from_secs;
+
+        history.record("nokogiri", "1.16.0", "ci/x86_64-linux", secs(90));
+        history.record("nokogiri", "1.16.0", "ci/x86_64-linux", secs(70));
+        history.record("json", "2.7.1", "ci/x86_64-linux", secs(4));
+        history.record("grpc", "1.60.0", "laptop/arm64-darwin", secs(300));
+        history.save().expect("save");
+
+        let history = BuildHistory::load(temp.path());
+        assert_eq!(history.records().len(), 3);
+        assert_eq!(
+            history.estimate("nokogiri", "1.16.0", "ci/x86_64-linux"),
+            Some(secs(80))
+        );
+        // Another version on this machine, then any machine
+        assert_eq!(
+            history.estimate("nokogiri", "1.17.0", "ci/x86_64-linux"),
+            Some(secs(80))
+        );
+        assert_eq!(
+            history.estimate("grpc", "1.60.0", "ci/x86_64-linux"),
+            Some(secs(300))
+        );
+        assert_eq!(history.estimate("pg", "1.5.0", "ci/x86_64-linux"), None);
+
+        let mut gems = vec![
+            ("json", "2.7.1"),
+            ("nokogiri", "1.16.0"),
+            ("pg", "1.5.0"),
+            ("grpc", "1.60.0"),
+        ];
+        history.schedule(&mut gems, "ci/x86_64-linux", |&(gem, version)| {
+            (gem, version)
+        });
+        let order: Vec<_> = gems.iter().map(|(gem, _)| *gem).collect();
+        assert_eq!(order, vec!["pg", "grpc", "nokogiri", "json"]);
+    }
+
+    #[test]
+    fn only_recent_samples_are_kept() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let mut history = BuildHistory::load(temp.path());
+        for secs in 1..=8 {
+            history.record("ffi", "1.16.3", "m", Duration::from_secs(secs));
+        }
+        let record = history.records().first().unwrap();
+        assert_eq!(record.samples_ms, vec![4000, 5000, 6000, 7000, 8000]);
+        assert_eq!(record.average(), Duration::from_secs(6));
+    }
+}
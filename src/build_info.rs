@@ -0,0 +1,224 @@
+//! Build toolchain capture for reproducible installs
+//!
+//! Records the Ruby, compiler and OS versions a bundle was locked with in a
+//! JSON file next to the lockfile (`Gemfile.lock.build_info.json`), so CI can
+//! check a machine matches before deploying native gems built elsewhere.
+
+use crate::lockfile::Lockfile;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Toolchain versions relevant to installing a bundle
+///
+/// Each field is the first line of the tool's version output, or `None`
+/// when the tool is missing. Rust toolchain fields are only captured for
+/// bundles that depend on `rb_sys`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    /// `RUBY_DESCRIPTION`, including patchlevel and build platform
+    pub ruby: Option<String>,
+    /// `Gem::VERSION`
+    pub rubygems: Option<String>,
+    /// C compiler (`$CC`, else `cc`)
+    pub cc: Option<String>,
+    /// `rb_sys` version from the lockfile
+    pub rb_sys: Option<String>,
+    pub cargo: Option<String>,
+    pub rustc: Option<String>,
+    /// Gem platform (e.g., `x86_64-linux`)
+    pub platform: String,
+    /// Operating system family (`linux`, `macos`, ...)
+    pub os: String,
+    /// C library (glibc or musl version line), Linux only
+    pub libc: Option<String>,
+}
+
+/// A toolchain component that differs from the recorded build
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildInfoDifference {
+    pub component: &'static str,
+    pub recorded: Option<String>,
+    pub current: Option<String>,
+}
+
+impl BuildInfo {
+    /// Inspect the current machine
+    #[must_use]
+    pub fn capture(lockfile: Option<&Lockfile>) -> Self {
+        let rb_sys = lockfile.and_then(|lockfile| {
+            lockfile
+                .gems
+                .iter()
+                .find(|gem| gem.name == "rb_sys")
+                .map(|gem| gem.version.clone())
+        });
+        let rust = |tool: &str| {
+            rb_sys
+                .as_ref()
+                .and_then(|_| first_line_of(tool, &["--version"]))
+        };
+        let cc = std::env::var("CC").unwrap_or_else(|_| String::from("cc"));
+
+        Self {
+            ruby: first_line_of("ruby", &["-e", "print RUBY_DESCRIPTION"]),
+            rubygems: first_line_of("ruby", &["-e", "print Gem::VERSION"]),
+            cc: first_line_of(&cc, &["--version"]),
+            cargo: rust("cargo"),
+            rustc: rust("rustc"),
+            rb_sys,
+            platform: crate::platform::detect_current_platform(),
+            os: std::env::consts::OS.to_string(),
+            libc: detect_libc(),
+        }
+    }
+
+    /// Location of the build info recorded for `lockfile_path`
+    #[must_use]
+    pub fn path_for_lockfile(lockfile_path: &Path) -> PathBuf {
+        let mut name = lockfile_path.as_os_str().to_os_string();
+        name.push(".build_info.json");
+        PathBuf::from(name)
+    }
+
+    /// Read recorded build info
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not valid JSON.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        serde_json::from_slice(&bytes).map_err(std::io::Error::other)
+    }
+
+    /// Write build info as pretty JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        json.push('\n');
+        std::fs::write(path, json)
+    }
+
+    /// Components of `current` that differ from this recorded build
+    #[must_use]
+    pub fn differences(&self, current: &Self) -> Vec<BuildInfoDifference> {
+        let fields = [
+            ("ruby", &self.ruby, &current.ruby),
+            ("rubygems", &self.rubygems, &current.rubygems),
+            ("cc", &self.cc, &current.cc),
+            ("rb_sys", &self.rb_sys, &current.rb_sys),
+            ("cargo", &self.cargo, &current.cargo),
+            ("rustc", &self.rustc, &current.rustc),
+            ("libc", &self.libc, &current.libc),
+        ];
+        let mut differences: Vec<BuildInfoDifference> = fields
+            .into_iter()
+            .filter(|(_, recorded, current)| recorded != current)
+            .map(|(component, recorded, current)| BuildInfoDifference {
+                component,
+                recorded: recorded.clone(),
+                current: current.clone(),
+            })
+            .collect();
+
+        for (component, recorded, current) in [
+            ("platform", &self.platform, &current.platform),
+            ("os", &self.os, &current.os),
+        ] {
+            if recorded != current {
+                differences.push(BuildInfoDifference {
+                    component,
+                    recorded: Some(recorded.clone()),
+                    current: Some(current.clone()),
+                });
+            }
+        }
+
+        differences
+    }
+}
+
+/// First non-empty line a tool prints (stdout, then stderr)
+fn first_line_of(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    [output.stdout, output.stderr]
+        .iter()
+        .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+        .find_map(|text| {
+            text.lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .map(str::to_string)
+        })
+}
+
+/// glibc prints its version to stdout; musl's loader prints to stderr and
+/// exits non-zero, so try both
+fn detect_libc() -> Option<String> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    first_line_of("ldd", &["--version"]).or_else(|| {
+        let output = Command::new("ldd").arg("--version").output().ok()?;
+        String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .map(str::trim)
+            .find(|line| line.starts_with("musl") || line.starts_with("Version"))
+            .map(str::to_string)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded() -> BuildInfo {
+        BuildInfo {
+            ruby: Some("ruby 3.3.0 (2023-12-25 revision 5124f9ac75) [x86_64-linux]".to_string()),
+            rubygems: Some("3.5.3".to_string()),
+            cc: Some("cc (GCC) 13.2.1".to_string()),
+            platform: "x86_64-linux".to_string(),
+            os: "linux".to_string(),
+            libc: Some("ldd (GNU libc) 2.38".to_string()),
+            ..BuildInfo::default()
+        }
+    }
+
+    #[test]
+    fn path_sits_next_to_lockfile() {
+        assert_eq!(
+            BuildInfo::path_for_lockfile(Path::new("app/Gemfile.lock")),
+            PathBuf::from("app/Gemfile.lock.build_info.json")
+        );
+    }
+
+    #[test]
+    fn differences_report_changed_components() {
+        let mut current = recorded();
+        assert!(recorded().differences(&current).is_empty());
+
+        current.ruby = Some("ruby 3.3.1 (2024-04-23 revision c56cd86388) [x86_64-linux]".into());
+        current.platform = "aarch64-linux".to_string();
+        let components: Vec<_> = recorded()
+            .differences(&current)
+            .into_iter()
+            .map(|difference| difference.component)
+            .collect();
+        assert_eq!(components, vec!["ruby", "platform"]);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let path = temp.path().join("Gemfile.lock.build_info.json");
+
+        recorded().save(&path).expect("save");
+        assert_eq!(BuildInfo::load(&path).expect("load"), recorded());
+    }
+}
@@ -1,9 +1,13 @@
 //! Cache statistics and management
 //!
-//! Analyzes cache directories, calculates statistics, and formats sizes in
-//! human-readable format.
+//! Analyzes cache directories, calculates statistics, checks cached `.gem`
+//! archives for corruption, and formats sizes in human-readable format.
 
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::BTreeMap;
 use std::fs;
+use std::io::Read;
 use std::path::Path;
 
 /// Cache statistics
@@ -76,6 +80,73 @@ fn walk_dir(dir: &Path, stats: &mut Stats) -> std::io::Result<()> {
     Ok(())
 }
 
+/// Check a `.gem` archive against its own `checksums.yaml.gz`
+///
+/// Reads every tar entry, so truncation or a damaged gzip stream (its CRC
+/// catches flipped bits) is reported even for gems without checksums.
+///
+/// # Errors
+///
+/// Returns a description of the first problem found.
+pub fn verify_gem_archive(path: &Path) -> Result<(), String> {
+    let file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = tar::Archive::new(file);
+    let mut contents: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+
+    for entry in archive
+        .entries()
+        .map_err(|e| format!("unreadable archive: {e}"))?
+    {
+        let mut entry = entry.map_err(|e| format!("unreadable archive: {e}"))?;
+        let name = entry
+            .path()
+            .map_err(|e| format!("unreadable archive: {e}"))?
+            .to_string_lossy()
+            .into_owned();
+        let mut bytes = Vec::new();
+        entry
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("truncated entry {name}: {e}"))?;
+        contents.insert(name, bytes);
+    }
+
+    for required in ["metadata.gz", "data.tar.gz"] {
+        let bytes = contents
+            .get(required)
+            .ok_or_else(|| format!("missing {required}"))?;
+        std::io::copy(&mut GzDecoder::new(bytes.as_slice()), &mut std::io::sink())
+            .map_err(|e| format!("corrupt {required}: {e}"))?;
+    }
+
+    let Some(checksums) = contents.get("checksums.yaml.gz") else {
+        return Ok(());
+    };
+    let mut yaml = String::new();
+    GzDecoder::new(checksums.as_slice())
+        .read_to_string(&mut yaml)
+        .map_err(|e| format!("corrupt checksums.yaml.gz: {e}"))?;
+    let recorded: BTreeMap<String, BTreeMap<String, String>> =
+        serde_yaml::from_str(&yaml).map_err(|e| format!("corrupt checksums.yaml.gz: {e}"))?;
+
+    for (algorithm, files) in &recorded {
+        let digest: fn(&[u8]) -> String = match algorithm.as_str() {
+            "SHA256" => |bytes| format!("{:x}", Sha256::digest(bytes)),
+            "SHA512" => |bytes| format!("{:x}", Sha512::digest(bytes)),
+            _ => continue,
+        };
+        for (name, expected) in files {
+            let Some(bytes) = contents.get(name) else {
+                continue;
+            };
+            if !digest(bytes).eq_ignore_ascii_case(expected) {
+                return Err(format!("{name} does not match its {algorithm} checksum"));
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Convert bytes to human-readable format using binary units (1 KiB = 1024 bytes).
 /// Examples: 512 -> "512 B", 1024 -> "1.0 KiB", 1048576 -> "1.0 MiB"
 #[must_use]
@@ -110,6 +181,90 @@ mod tests {
     use std::fs;
     use tempfile::TempDir;
 
+    /// Write a minimal `.gem`, optionally with `checksums.yaml.gz`
+    fn write_gem(path: &Path, data: &[u8], checksums: bool) {
+        use flate2::{Compression, write::GzEncoder};
+        use std::io::Write;
+
+        let gzip = |bytes: &[u8]| {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(bytes).unwrap();
+            encoder.finish().unwrap()
+        };
+        let metadata = gzip(b"--- !ruby/object:Gem::Specification\nname: rack\n");
+        let data = gzip(data);
+        let mut entries = vec![
+            ("metadata.gz", metadata.clone()),
+            ("data.tar.gz", data.clone()),
+        ];
+        if checksums {
+            let yaml = format!(
+                "---\nSHA256:\n  metadata.gz: {:x}\n  data.tar.gz: {:x}\n",
+                Sha256::digest(&metadata),
+                Sha256::digest(&data)
+            );
+            entries.push(("checksums.yaml.gz", gzip(yaml.as_bytes())));
+        }
+
+        let mut builder = tar::Builder::new(fs::File::create(path).unwrap());
+        for (name, bytes) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(bytes.len() as u64);
+            header.set_mode(0o444);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, bytes.as_slice())
+                .unwrap();
+        }
+        builder.finish().unwrap();
+    }
+
+    #[test]
+    fn verify_gem_archive_accepts_intact_gems() {
+        let tmp_dir = TempDir::new().unwrap();
+        let with_checksums = tmp_dir.path().join("rack-3.0.0.gem");
+        let without_checksums = tmp_dir.path().join("rack-2.2.8.gem");
+        write_gem(&with_checksums, b"data", true);
+        write_gem(&without_checksums, b"data", false);
+
+        assert_eq!(verify_gem_archive(&with_checksums), Ok(()));
+        assert_eq!(verify_gem_archive(&without_checksums), Ok(()));
+    }
+
+    #[test]
+    fn verify_gem_archive_detects_damage() {
+        let tmp_dir = TempDir::new().unwrap();
+        let gem = tmp_dir.path().join("rack-3.0.0.gem");
+        write_gem(&gem, b"data", true);
+
+        // Truncation
+        let bytes = fs::read(&gem).unwrap();
+        fs::write(&gem, bytes.get(..700).unwrap()).unwrap();
+        assert!(verify_gem_archive(&gem).is_err());
+
+        // Swapped contents no longer match checksums.yaml.gz
+        write_gem(&gem, b"data", true);
+        let bytes = fs::read(&gem).unwrap();
+        let mut tampered = bytes.clone();
+        let gzip_magic = [0x1f, 0x8b];
+        let data_start = bytes
+            .windows(2)
+            .enumerate()
+            .filter(|(_, window)| *window == gzip_magic)
+            .nth(1)
+            .map(|(offset, _)| offset)
+            .unwrap();
+        // The gzip mtime field, which leaves the stream itself valid
+        *tampered.get_mut(data_start + 4).unwrap() ^= 0xff;
+        fs::write(&gem, tampered).unwrap();
+        assert_eq!(
+            verify_gem_archive(&gem),
+            Err(String::from(
+                "data.tar.gz does not match its SHA256 checksum"
+            ))
+        );
+    }
+
     #[test]
     fn collect_stats_empty_dir() {
         let tmp_dir = TempDir::new().unwrap();
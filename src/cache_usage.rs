@@ -0,0 +1,170 @@
+//! Cache hit and miss counters
+//!
+//! Installs count which `.gem` files came from the download cache and which
+//! were fetched, and `lock` counts reused resolutions. The totals accumulate
+//! in `<cache>/usage.json` across runs so `lode stats` can report hit rates
+//! and how much downloading the cache saved.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Version of `usage.json`; counters from another version are discarded
+/// and counting restarts from zero
+const FORMAT_VERSION: u32 = 1;
+
+/// Running totals of cache use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageCounters {
+    /// Gems installed from an already cached `.gem`
+    pub gem_hits: u64,
+    /// Gems that had to be downloaded
+    pub gem_misses: u64,
+    /// Bytes of cached `.gem` files reused instead of downloaded
+    pub bytes_reused: u64,
+    /// Bytes downloaded into the cache
+    pub bytes_downloaded: u64,
+    /// Lock runs that reused a cached resolution
+    pub resolution_hits: u64,
+    /// Lock runs that had to resolve
+    pub resolution_misses: u64,
+}
+
+impl UsageCounters {
+    /// Share of gems served from the cache, once any were installed
+    #[must_use]
+    pub fn gem_hit_rate(&self) -> Option<f64> {
+        hit_rate(self.gem_hits, self.gem_misses)
+    }
+
+    /// Share of lock runs answered by a cached resolution
+    #[must_use]
+    pub fn resolution_hit_rate(&self) -> Option<f64> {
+        hit_rate(self.resolution_hits, self.resolution_misses)
+    }
+}
+
+#[allow(clippy::cast_precision_loss, reason = "Percentages need no precision")]
+fn hit_rate(hits: u64, misses: u64) -> Option<f64> {
+    let total = hits.saturating_add(misses);
+    (total > 0).then(|| hits as f64 / total as f64)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UsageFile {
+    format: u32,
+    counters: UsageCounters,
+}
+
+/// Cache use counters, persisted in the cache directory
+#[derive(Debug, Clone)]
+pub struct CacheUsage {
+    path: PathBuf,
+    counters: UsageCounters,
+    dirty: bool,
+}
+
+impl CacheUsage {
+    /// Load `<cache_dir>/usage.json`, starting from zero if it is missing or
+    /// unreadable
+    #[must_use]
+    pub fn load(cache_dir: &Path) -> Self {
+        let path = cache_dir.join("usage.json");
+        let counters = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<UsageFile>(&bytes).ok())
+            .filter(|file| file.format == FORMAT_VERSION)
+            .map(|file| file.counters)
+            .unwrap_or_default();
+        Self {
+            path,
+            counters,
+            dirty: false,
+        }
+    }
+
+    /// Totals so far
+    #[must_use]
+    pub const fn counters(&self) -> &UsageCounters {
+        &self.counters
+    }
+
+    /// Count one gem served from the cache (`cached`) or downloaded
+    pub const fn record_gem(&mut self, cached: bool, size: u64) {
+        let counters = &mut self.counters;
+        if cached {
+            counters.gem_hits = counters.gem_hits.saturating_add(1);
+            counters.bytes_reused = counters.bytes_reused.saturating_add(size);
+        } else {
+            counters.gem_misses = counters.gem_misses.saturating_add(1);
+            counters.bytes_downloaded = counters.bytes_downloaded.saturating_add(size);
+        }
+        self.dirty = true;
+    }
+
+    /// Count one lock run that reused a cached resolution (`cached`) or not
+    pub const fn record_resolution(&mut self, cached: bool) {
+        let counters = &mut self.counters;
+        if cached {
+            counters.resolution_hits = counters.resolution_hits.saturating_add(1);
+        } else {
+            counters.resolution_misses = counters.resolution_misses.saturating_add(1);
+        }
+        self.dirty = true;
+    }
+
+    /// Persist newly recorded counts
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the usage file cannot be written.
+    pub fn save(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = UsageFile {
+            format: FORMAT_VERSION,
+            counters: self.counters,
+        };
+        let json = serde_json::to_vec(&file).map_err(io::Error::other)?;
+        let temp = self.path.with_extension("json.tmp");
+        fs::write(&temp, json)?;
+        fs::rename(&temp, &self.path)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_accumulate_across_loads() {
+        let temp = tempfile::tempdir().expect("tempdir");
+
+        let mut usage = CacheUsage::load(temp.path());
+        assert_eq!(usage.counters().gem_hit_rate(), None);
+        usage.record_gem(true, 300);
+        usage.record_gem(false, 100);
+        usage.record_resolution(false);
+        usage.save().expect("save");
+
+        let mut usage = CacheUsage::load(temp.path());
+        usage.record_gem(true, 200);
+        usage.record_gem(true, 50);
+        usage.save().expect("save");
+
+        let counters = *CacheUsage::load(temp.path()).counters();
+        assert_eq!(counters.gem_hits, 3);
+        assert_eq!(counters.gem_misses, 1);
+        assert_eq!(counters.bytes_reused, 550);
+        assert_eq!(counters.bytes_downloaded, 100);
+        assert_eq!(counters.gem_hit_rate(), Some(0.75));
+        assert_eq!(counters.resolution_hit_rate(), Some(0.0));
+    }
+}
@@ -3,7 +3,8 @@
 //! Add a gem to the Gemfile
 
 use anyhow::{Context, Result};
-use lode::GemfileWriter;
+use futures_util::future::join_all;
+use lode::{GemfileWriter, RubyGemsClient};
 use std::fmt::Write;
 
 /// Add a gem to the Gemfile.
@@ -17,6 +18,7 @@ use std::fmt::Write;
 /// lode add rails --version "~> 7.0"
 /// lode add rspec --group test
 /// lode add bootsnap --skip-install
+/// lode add rack --version-from-lockfile --pessimistic-depth patch
 /// ```
 #[allow(
     clippy::too_many_arguments,
@@ -37,24 +39,58 @@ pub(crate) async fn run(
     path: Option<&str>,
     strict: bool,
     optimistic: bool,
+    version_from_lockfile: bool,
+    pessimistic_depth: PessimisticDepth,
     quiet: bool,
     run_lock: bool,
+    yes: bool,
+    incremental: bool,
 ) -> Result<()> {
+    super::install::refuse_in_deployment("add")?;
+
     let gemfile_path = lode::find_gemfile();
 
     if !gemfile_path.exists() {
         anyhow::bail!("Gemfile or gems.rb not found. Run `lode init` first.");
     }
 
+    // Git and path gems don't come from a registry anyone can publish to
+    if git.is_none() && github.is_none() && path.is_none() {
+        check_typosquat(gem_name, source, yes).await?;
+    }
+
     // Load Gemfile for modification
     let mut writer = GemfileWriter::load(&gemfile_path).context("Failed to load Gemfile")?;
 
-    // Apply strict or optimistic version constraint
+    // Promoting a transitive dependency: pin near the version already locked
+    let locked = locked_version(&gemfile_path, gem_name);
+    if version_from_lockfile && locked.is_none() {
+        anyhow::bail!("{gem_name} is not in the lockfile; --version-from-lockfile needs it locked");
+    }
+    if !version_from_lockfile
+        && version.is_none()
+        && !quiet
+        && let Some(ref locked) = locked
+    {
+        println!(
+            "{gem_name} is locked at {locked}; pass --version-from-lockfile to add it as \"{}\"",
+            pessimistic_constraint(locked, pessimistic_depth)
+        );
+    }
+    let version = if version_from_lockfile {
+        locked.as_deref()
+    } else {
+        version
+    };
+
+    // Apply strict, optimistic or (for a locked version) pessimistic constraint
     let version = version.map(|v| {
         if strict {
             format!("= {v}")
         } else if optimistic {
             format!(">= {v}")
+        } else if version_from_lockfile {
+            pessimistic_constraint(v, pessimistic_depth)
         } else {
             v.to_string()
         }
@@ -170,17 +206,21 @@ pub(crate) async fn run(
 
         crate::commands::lock::run(
             gemfile_path.to_str().unwrap_or("Gemfile"),
-            None,  // lockfile_path
-            &[],   // add_platforms
-            &[],   // remove_platforms
-            &[],   // update_gems
-            false, // print
-            false, // verbose
-            false, // patch
-            false, // minor
-            false, // major
-            false, // strict
-            false, // conservative
+            None,                               // lockfile_path
+            &[],                                // add_platforms
+            &[],                                // remove_platforms
+            &[],                                // update_gems
+            false,                              // release_hold
+            &std::collections::BTreeMap::new(), // constraints
+            lode::ResolutionStrategy::Latest,   // strategy
+            false,                              // print
+            false,                              // verbose
+            false,                              // patch
+            false,                              // minor
+            false,                              // major
+            false,                              // strict
+            false,                              // conservative
+            incremental,
             false, // local
             false, // pre
             None,  // bundler
@@ -188,6 +228,7 @@ pub(crate) async fn run(
             false, // add_checksums
             false, // full_index
             quiet, // quiet
+            &lode::Profiler::default(),
         )
         .await?;
 
@@ -201,6 +242,98 @@ pub(crate) async fn run(
     Ok(())
 }
 
+/// Which version segments a pessimistic (`~>`) constraint keeps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum PessimisticDepth {
+    /// `~> X.Y`: allow minor and patch updates
+    #[default]
+    Minor,
+    /// `~> X.Y.Z`: allow patch updates only
+    Patch,
+}
+
+/// Version of `gem_name` in the Gemfile's lockfile, if it is locked
+fn locked_version(gemfile_path: &std::path::Path, gem_name: &str) -> Option<String> {
+    let content = std::fs::read_to_string(lode::lockfile_for_gemfile(gemfile_path)).ok()?;
+    let lockfile = lode::Lockfile::parse(&content).ok()?;
+    lockfile
+        .gems
+        .into_iter()
+        .find(|gem| gem.name == gem_name)
+        .map(|gem| gem.version)
+}
+
+/// Pessimistic constraint around `version`, like `~> 7.1` for 7.1.3
+///
+/// Only the leading numeric segments count, so `2.0.0.rc1` gives `~> 2.0`.
+/// Missing segments are zero-filled.
+fn pessimistic_constraint(version: &str, depth: PessimisticDepth) -> String {
+    let segments = match depth {
+        PessimisticDepth::Minor => 2,
+        PessimisticDepth::Patch => 3,
+    };
+    let numeric: Vec<&str> = version
+        .split('.')
+        .take_while(|segment| !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()))
+        .collect();
+    let kept: Vec<&str> = (0..segments)
+        .map(|idx| numeric.get(idx).copied().unwrap_or("0"))
+        .collect();
+    format!("~> {}", kept.join("."))
+}
+
+/// Warn when `gem_name` is a near-miss of a popular gem, and refuse to go on
+/// without `--yes`
+///
+/// Download counts for the requested gem and its lookalikes are shown side by
+/// side; a squatted name usually has a tiny fraction of the original's. Gems
+/// with enough downloads of their own are established rather than squatted
+/// and pass without needing `--yes`.
+pub(crate) async fn check_typosquat(gem_name: &str, source: Option<&str>, yes: bool) -> Result<()> {
+    let lookalikes = lode::typosquat::lookalikes(gem_name);
+    let Some(closest) = lookalikes.first() else {
+        return Ok(());
+    };
+
+    let source = source
+        .map(String::from)
+        .or_else(lode::env_vars::gem_source)
+        .unwrap_or_else(|| lode::DEFAULT_GEM_SOURCE.to_string());
+    let client = RubyGemsClient::new(source)?;
+    let names: Vec<&str> = std::iter::once(gem_name)
+        .chain(lookalikes.iter().copied())
+        .collect();
+    let counts = join_all(names.iter().map(|name| client.fetch_gem_summary(name))).await;
+    if counts.first().is_some_and(|summary| {
+        summary
+            .as_ref()
+            .is_ok_and(|summary| summary.downloads >= lode::typosquat::ESTABLISHED_DOWNLOADS)
+    }) {
+        return Ok(());
+    }
+
+    eprintln!();
+    eprintln!("WARNING: '{gem_name}' is a near-miss of a popular gem and may be a typosquat");
+    for (name, summary) in names.iter().zip(counts) {
+        let downloads = summary.map_or_else(
+            |_| "not found".to_string(),
+            |summary| {
+                format!(
+                    "{} downloads",
+                    super::search::format_downloads(summary.downloads)
+                )
+            },
+        );
+        eprintln!("  {name:<28} {downloads}");
+    }
+    eprintln!();
+
+    if yes {
+        return Ok(());
+    }
+    anyhow::bail!("Did you mean '{closest}'? Pass --yes to use '{gem_name}' anyway")
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, reason = "Tests can panic")]
 mod tests {
@@ -228,8 +361,12 @@ mod tests {
             None,  // path
             false, // strict
             false, // optimistic
+            false, // version_from_lockfile
+            PessimisticDepth::Minor,
             false, // quiet
             false, // run_lock
+            false, // yes
+            true,  // incremental
         )
         .await;
 
@@ -238,6 +375,26 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn pessimistic_constraint_keeps_leading_segments() {
+        assert_eq!(
+            pessimistic_constraint("7.1.3", PessimisticDepth::Minor),
+            "~> 7.1"
+        );
+        assert_eq!(
+            pessimistic_constraint("7.1.3.2", PessimisticDepth::Patch),
+            "~> 7.1.3"
+        );
+        assert_eq!(
+            pessimistic_constraint("2.0.0.rc1", PessimisticDepth::Minor),
+            "~> 2.0"
+        );
+        assert_eq!(
+            pessimistic_constraint("3", PessimisticDepth::Patch),
+            "~> 3.0.0"
+        );
+    }
+
     #[tokio::test]
     async fn test_add_gem_no_gemfile() {
         let temp = TempDir::new().unwrap();
@@ -245,7 +402,8 @@ mod tests {
         std::env::set_current_dir(&temp).unwrap();
 
         let result = run(
-            "rails", None,  // version
+            "rails",
+            None,  // version
             None,  // group
             None,  // require
             None,  // source
@@ -257,8 +415,12 @@ mod tests {
             None,  // path
             false, // strict
             false, // optimistic
+            false, // version_from_lockfile
+            PessimisticDepth::Minor,
             false, // quiet
             false, // run_lock
+            false, // yes
+            true,  // incremental
         )
         .await;
 
@@ -0,0 +1,81 @@
+//! Audit command
+//!
+//! Check the locked bundle for supply-chain risks
+
+use anyhow::{Context, Result};
+use futures_util::future::join_all;
+use lode::source_audit;
+use lode::{Config, Gemfile, Lockfile, RubyGemsClient};
+use std::collections::BTreeMap;
+use std::fs;
+
+/// Run the selected checks, or all of them when none is selected
+pub(crate) async fn run(gemfile_path: Option<&str>, sources: bool, quiet: bool) -> Result<()> {
+    let all = !sources;
+    if sources || all {
+        audit_sources(gemfile_path, quiet).await?;
+    }
+    Ok(())
+}
+
+/// Flag publicly resolved gems that a private source or a pinned prefix
+/// claims (dependency confusion)
+async fn audit_sources(gemfile_path: Option<&str>, quiet: bool) -> Result<()> {
+    let gemfile_path =
+        gemfile_path.map_or_else(lode::paths::find_gemfile, std::path::PathBuf::from);
+    let lockfile_path = lode::lockfile_for_gemfile(&gemfile_path);
+    let gemfile = Gemfile::parse_file(&gemfile_path)
+        .with_context(|| format!("Failed to parse {}", gemfile_path.display()))?;
+    let content = fs::read_to_string(&lockfile_path)
+        .with_context(|| format!("Failed to read {}", lockfile_path.display()))?;
+    let lockfile = Lockfile::parse(&content)
+        .with_context(|| format!("Failed to parse {}", lockfile_path.display()))?;
+    let config = Config::load().context("Failed to load configuration")?;
+
+    let private = source_audit::private_sources(&gemfile, &config.source_pins);
+    let listings = join_all(private.iter().map(|source| async move {
+        let names = match RubyGemsClient::new(source.as_str()) {
+            Ok(client) => client.fetch_names().await.map_err(anyhow::Error::from),
+            Err(e) => Err(e),
+        };
+        (source.clone(), names)
+    }))
+    .await;
+
+    let mut private_names = BTreeMap::new();
+    let mut unreachable = 0;
+    for (source, names) in listings {
+        match names {
+            Ok(names) => {
+                private_names.insert(source, names);
+            }
+            Err(e) => {
+                unreachable += 1;
+                lode::warn!("could not list gems on {source}: {e:#}");
+            }
+        }
+    }
+
+    let findings = source_audit::audit(&lockfile, &gemfile, &config.source_pins, &private_names);
+    if findings.is_empty() {
+        if !quiet {
+            println!(
+                "No dependency confusion risks found ({} gems, {} private sources)",
+                lockfile.gems.len(),
+                private_names.len()
+            );
+        }
+        if unreachable > 0 {
+            anyhow::bail!("{unreachable} private sources could not be checked");
+        }
+        return Ok(());
+    }
+
+    eprintln!("Gems that could be shadowed by a public source:");
+    for finding in &findings {
+        eprintln!("  {finding}");
+    }
+    eprintln!();
+    eprintln!("Move each into a `source ... do` block for its private source.");
+    anyhow::bail!("{} gems failed the source audit", findings.len())
+}
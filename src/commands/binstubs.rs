@@ -17,19 +17,27 @@ struct BinstubsOptions<'a> {
     force: bool,
     _all: bool,
     _all_platforms: bool,
+    standalone: bool,
+    path: Option<&'a str>,
     lockfile_path_override: Option<&'a str>,
     gems_dir_override: Option<&'a Path>,
     bin_dir_override: Option<&'a Path>,
 }
 
+/// Standalone bundle created by `lode install --standalone`
+const STANDALONE_BUNDLE_PATH: &str = "bundle";
+
 /// Generate binstubs for specific gems.
 #[cfg(not(test))]
+#[allow(clippy::fn_params_excessive_bools)]
 pub(crate) fn run(
     gems: &[String],
     shebang: Option<&str>,
     force: bool,
     all: bool,
     all_platforms: bool,
+    standalone: bool,
+    path: Option<&str>,
 ) -> Result<()> {
     run_impl(&BinstubsOptions {
         gems,
@@ -37,6 +45,8 @@ pub(crate) fn run(
         force,
         _all: all,
         _all_platforms: all_platforms,
+        standalone,
+        path,
         lockfile_path_override: None,
         gems_dir_override: None,
         bin_dir_override: None,
@@ -45,12 +55,15 @@ pub(crate) fn run(
 
 /// Test version with optional path overrides
 #[cfg(test)]
+#[allow(clippy::fn_params_excessive_bools)]
 pub(crate) fn run(
     gems: &[String],
     shebang: Option<&str>,
     force: bool,
     all: bool,
     all_platforms: bool,
+    standalone: bool,
+    path: Option<&str>,
 ) -> Result<()> {
     run_impl(&BinstubsOptions {
         gems,
@@ -58,6 +71,8 @@ pub(crate) fn run(
         force,
         _all: all,
         _all_platforms: all_platforms,
+        standalone,
+        path,
         lockfile_path_override: None,
         gems_dir_override: None,
         bin_dir_override: None,
@@ -88,12 +103,39 @@ fn run_impl(options: &BinstubsOptions<'_>) -> Result<()> {
         lockfile.ruby_version.as_deref(),
         Some(gemfile_path),
     );
-    let base_path = Path::new(&install_path);
+    let base_path = if options.standalone {
+        let setup_rb = Path::new(STANDALONE_BUNDLE_PATH)
+            .join("bundler")
+            .join("setup.rb");
+        if options.gems_dir_override.is_none() && !setup_rb.exists() {
+            anyhow::bail!(
+                "Standalone bundle not found at {}. Run `lode install --standalone` first",
+                setup_rb.display()
+            );
+        }
+        Path::new(STANDALONE_BUNDLE_PATH)
+    } else {
+        Path::new(&install_path)
+    };
     let default_gems_dir = base_path.join("ruby").join(&ruby_version).join("gems");
     let gems_dir = options.gems_dir_override.unwrap_or(&default_gems_dir);
 
-    // Determine bin directory
-    let default_binstub_dir = Path::new("bin");
+    // Determine bin directory: --path, then the directory recorded by an
+    // earlier --path, then BUNDLE_BIN, then bin/
+    if let Some(path) = options.path {
+        remember_bin_dir(path)?;
+    }
+    let configured_bin_dir = options
+        .path
+        .map(String::from)
+        .or_else(|| cfg.bin_dir.clone())
+        .or_else(|| {
+            lode::BundleConfig::load()
+                .ok()
+                .and_then(|bundle| bundle.bin)
+        })
+        .unwrap_or_else(|| String::from("bin"));
+    let default_binstub_dir = Path::new(&configured_bin_dir);
     let binstub_dir = options.bin_dir_override.unwrap_or(default_binstub_dir);
 
     // Determine Gemfile path from lockfile (supports both Gemfile/gems.rb naming)
@@ -101,12 +143,20 @@ fn run_impl(options: &BinstubsOptions<'_>) -> Result<()> {
     let gemfile_path = gemfile_pathbuf.to_str().unwrap_or("Gemfile");
 
     // Create binstub generator
-    let generator = BinstubGenerator::new(
+    let mut generator = BinstubGenerator::new(
         Path::new(binstub_dir).to_path_buf(),
         Path::new(gemfile_path).to_path_buf(),
         options.shebang.map(String::from),
         options.force,
     );
+    if options.standalone {
+        // <bundle>/ruby/<version>/gems
+        let bundle_root = gems_dir
+            .ancestors()
+            .nth(3)
+            .unwrap_or_else(|| Path::new(STANDALONE_BUNDLE_PATH));
+        generator = generator.with_standalone(bundle_root.to_path_buf());
+    }
 
     // Filter gems from lockfile
     let target_gems: Vec<_> = if options.gems.is_empty() {
@@ -135,11 +185,16 @@ fn run_impl(options: &BinstubsOptions<'_>) -> Result<()> {
     let mut gems_with_binstubs = 0;
 
     for gem in target_gems {
-        let gem_dir = gems_dir.join(format!("{}-{}", gem.name, gem.version));
+        // Standalone bundles keep platform gems under their platform name
+        let gem_dir = if options.standalone {
+            gems_dir.join(gem.full_name_with_platform())
+        } else {
+            gems_dir.join(gem.full_name())
+        };
 
         if !gem_dir.exists() {
-            eprintln!(
-                "Warning: {name} ({version}) is not installed",
+            lode::warn!(
+                "{name} ({version}) is not installed",
                 name = gem.name,
                 version = gem.version
             );
@@ -154,8 +209,8 @@ fn run_impl(options: &BinstubsOptions<'_>) -> Result<()> {
                 }
             }
             Err(e) => {
-                eprintln!(
-                    "Warning: Failed to generate binstubs for {name}: {e}",
+                lode::warn!(
+                    "Failed to generate binstubs for {name}: {e}",
                     name = gem.name
                 );
             }
@@ -176,6 +231,27 @@ fn run_impl(options: &BinstubsOptions<'_>) -> Result<()> {
     Ok(())
 }
 
+/// Record a custom binstub directory in `.lode.toml` so later runs reuse it
+fn remember_bin_dir(path: &str) -> Result<()> {
+    let config_path = Path::new(".lode.toml");
+    // Only `bin_dir` changes
+    let mut document = super::install::project_config_document(config_path)?;
+    if !set_bin_dir(&mut document, path) {
+        return Ok(());
+    }
+    fs::write(config_path, document.to_string())
+        .with_context(|| format!("Failed to write {}", config_path.display()))
+}
+
+/// Point `bin_dir` at `path`, returning whether it changed
+fn set_bin_dir(document: &mut toml_edit::DocumentMut, path: &str) -> bool {
+    if document.get("bin_dir").and_then(toml_edit::Item::as_str) == Some(path) {
+        return false;
+    }
+    document.insert("bin_dir", toml_edit::value(path));
+    true
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, reason = "Tests can panic")]
 mod tests {
@@ -201,6 +277,8 @@ mod tests {
             force,
             _all: all,
             _all_platforms: all_platforms,
+            standalone: false,
+            path: None,
             lockfile_path_override: Some(lockfile_path),
             gems_dir_override: Some(gems_dir),
             bin_dir_override: Some(bin_dir),
@@ -334,4 +412,20 @@ BUNDLED WITH
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn bin_dir_keeps_the_rest_of_the_config() {
+        let mut document: toml_edit::DocumentMut =
+            "# team settings\ncache_dir = \"/tmp/gems\" # shared\nfuture_key = 1\n"
+                .parse()
+                .unwrap();
+
+        assert!(set_bin_dir(&mut document, "exe"));
+        assert_eq!(
+            document.to_string(),
+            "# team settings\ncache_dir = \"/tmp/gems\" # shared\nfuture_key = 1\n\
+             bin_dir = \"exe\"\n"
+        );
+        assert!(!set_bin_dir(&mut document, "exe"));
+    }
 }
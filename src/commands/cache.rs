@@ -3,7 +3,10 @@
 //! Package gems into vendor/cache directory
 
 use anyhow::{Context, Result};
-use lode::lockfile::Lockfile;
+use futures_util::stream::{self, StreamExt};
+use lode::lockfile::{GemSpec, Lockfile};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::PathBuf;
 
@@ -62,6 +65,16 @@ pub(crate) async fn run(
         .join(&ruby_version)
         .join("cache");
 
+    // Foreign-platform gems are downloaded into lode's cache, so it must exist
+    let downloader = if all_platforms {
+        Some(
+            lode::DownloadManager::new(lode_cache.clone())
+                .context("Failed to create download manager")?,
+        )
+    } else {
+        None
+    };
+
     // Check both cache locations
     let cache_locations = [lode_cache, system_gem_cache];
     let available_caches: Vec<_> = cache_locations.iter().filter(|c| c.exists()).collect();
@@ -71,9 +84,12 @@ pub(crate) async fn run(
     }
 
     // Determine which gems to cache
+    let platform_gems;
     let gems_to_cache: Vec<_> = if all_platforms {
-        // Include all gems from lockfile regardless of platform
-        lockfile.gems.iter().collect()
+        // Every gem in the lockfile, plus the published variants of each for
+        // the lockfile's platforms
+        platform_gems = platform_variants(&lockfile, quiet).await;
+        lockfile.gems.iter().chain(&platform_gems).collect()
     } else {
         // Only include gems for current platform
         lockfile
@@ -123,9 +139,22 @@ pub(crate) async fn run(
             .map(|cache| cache.join(&gem_filename))
             .find(|path| path.exists());
 
-        let Some(source_path) = source_path else {
-            missing.push(gem_filename);
-            continue;
+        let source_path = match (source_path, &downloader) {
+            (Some(path), _) => path,
+            (None, Some(downloader)) => match downloader.download_gem(gem).await {
+                Ok(path) => path,
+                Err(e) => {
+                    if !quiet {
+                        eprintln!("Failed to download {gem_filename}: {e}");
+                    }
+                    missing.push(gem_filename);
+                    continue;
+                }
+            },
+            (None, None) => {
+                missing.push(gem_filename);
+                continue;
+            }
         };
 
         // Copy gem file to vendor/cache
@@ -170,6 +199,7 @@ pub(crate) async fn run(
         }
         crate::commands::install::run(crate::commands::install::InstallOptions {
             lockfile_path: &lockfile_path,
+            gemfile: None,
             redownload: false,
             verbose: false,
             quiet: true,
@@ -182,10 +212,19 @@ pub(crate) async fn run(
             trust_policy: None,
             full_index: false,
             target_rbconfig: None,
+            arch: None,
+            path: None,
             frozen: false,
+            deployment: false,
             without_groups: vec![],
             with_groups: vec![],
+            only_groups: vec![],
+            group_profile: None,
             auto_clean: false,
+            link_path_gems: false,
+            profiler: lode::Profiler::default(),
+            dry_run: false,
+            resume: false,
         })
         .await?;
     }
@@ -193,6 +232,240 @@ pub(crate) async fn run(
     Ok(())
 }
 
+/// Platform-specific releases of the lockfile's gems for its `PLATFORMS`
+///
+/// A lockfile written on one machine often lists only the `ruby` variant of
+/// gems like `nokogiri`, or only its own platform's. For each locked gem
+/// version, the published variants matching another locked platform are
+/// returned, skipping any the lockfile already lists.
+async fn platform_variants(lockfile: &Lockfile, quiet: bool) -> Vec<GemSpec> {
+    let Ok(client) = lode::RubyGemsClient::new(lode::gem_source_url()) else {
+        return Vec::new();
+    };
+    let client = client.with_prerelease(true);
+    let wanted: BTreeSet<String> = lockfile
+        .platforms
+        .iter()
+        .filter(|platform| *platform != "ruby")
+        .map(|platform| lode::platform::normalize_platform(platform))
+        .collect();
+    if wanted.is_empty() {
+        return Vec::new();
+    }
+
+    let locked: BTreeSet<(&str, &str, &str)> = lockfile
+        .gems
+        .iter()
+        .map(|gem| {
+            (
+                gem.name.as_str(),
+                gem.version.as_str(),
+                gem.platform.as_deref().unwrap_or("ruby"),
+            )
+        })
+        .collect();
+    let releases: BTreeSet<(&str, &str)> = lockfile
+        .gems
+        .iter()
+        .map(|gem| (gem.name.as_str(), gem.version.as_str()))
+        .collect();
+
+    let published: Vec<_> = stream::iter(&releases)
+        .map(|(name, version)| {
+            let client = client.clone();
+            async move {
+                let versions = client.fetch_versions(name).await;
+                (*name, *version, versions)
+            }
+        })
+        .buffer_unordered(10)
+        .collect()
+        .await;
+
+    let mut variants = Vec::new();
+    for (name, version, versions) in published {
+        let versions = match versions {
+            Ok(versions) => versions,
+            Err(e) => {
+                if !quiet {
+                    eprintln!("Could not list platforms for {name}: {e}");
+                }
+                continue;
+            }
+        };
+        for release in versions {
+            if release.number == version
+                && release.platform != "ruby"
+                && !release.platform.is_empty()
+                && wanted.contains(&lode::platform::normalize_platform(&release.platform))
+                && !locked.contains(&(name, version, release.platform.as_str()))
+            {
+                variants.push(GemSpec::new(
+                    name.to_string(),
+                    version.to_string(),
+                    Some(release.platform),
+                    vec![],
+                    vec![],
+                ));
+            }
+        }
+    }
+    variants.sort_by(|a, b| a.full_name_with_platform().cmp(b.full_name_with_platform()));
+    variants
+}
+
+/// Check the download cache for corrupted `.gem` files
+///
+/// Each cached gem is hashed and compared with the checksum the compact
+/// index publishes for it (skipped with `--local`), then its archive is read
+/// through and checked against its own `checksums.yaml.gz`. Corrupted files
+/// move to `<cache>/quarantine` so the next install downloads them again.
+pub(crate) async fn verify(local: bool, quiet: bool) -> Result<()> {
+    let config = lode::Config::load().context("Failed to load configuration")?;
+    let cache_dir = lode::config::cache_dir(Some(&config))
+        .context("Failed to determine lode cache directory")?;
+
+    let mut gem_files: Vec<PathBuf> = fs::read_dir(&cache_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file() && path.extension().is_some_and(|extension| extension == "gem")
+        })
+        .collect();
+    gem_files.sort();
+
+    if gem_files.is_empty() {
+        if !quiet {
+            println!("No cached gems in {}", cache_dir.display());
+        }
+        return Ok(());
+    }
+
+    if !quiet {
+        println!(
+            "Verifying {} cached gems in {}...",
+            gem_files.len(),
+            cache_dir.display()
+        );
+    }
+
+    let mut digests = lode::DigestCache::load(&cache_dir);
+    let checksums = digests.sha256_all(&gem_files);
+
+    let published = if local {
+        BTreeMap::new()
+    } else {
+        published_checksums(&gem_files).await
+    };
+
+    let problems: Vec<Option<String>> = gem_files
+        .par_iter()
+        .zip(checksums)
+        .map(|(path, checksum)| {
+            let checksum = match checksum {
+                Ok(checksum) => checksum,
+                Err(e) => return Some(format!("unreadable: {e}")),
+            };
+            let stem = path.file_stem()?.to_string_lossy();
+            if let Some(expected) = published.get(stem.as_ref())
+                && !expected.eq_ignore_ascii_case(&checksum)
+            {
+                return Some(String::from("does not match the index checksum"));
+            }
+            lode::cache::verify_gem_archive(path).err()
+        })
+        .collect();
+
+    let quarantine_dir = cache_dir.join("quarantine");
+    let mut total_size = 0;
+    let mut corrupted = 0;
+    for (path, problem) in gem_files.iter().zip(&problems) {
+        total_size += fs::metadata(path).map_or(0, |metadata| metadata.len());
+        let Some(problem) = problem else {
+            continue;
+        };
+        corrupted += 1;
+        let file_name = path.file_name().unwrap_or_default();
+        fs::create_dir_all(&quarantine_dir).with_context(|| {
+            format!(
+                "Failed to create quarantine directory: {}",
+                quarantine_dir.display()
+            )
+        })?;
+        fs::rename(path, quarantine_dir.join(file_name))
+            .with_context(|| format!("Failed to quarantine {}", path.display()))?;
+        eprintln!("  {}: {problem}", file_name.to_string_lossy());
+    }
+
+    if let Err(e) = digests.save()
+        && !quiet
+    {
+        lode::warn!("Could not save checksum cache: {e}");
+    }
+
+    if !quiet {
+        let index_checked = gem_files
+            .iter()
+            .filter_map(|path| path.file_stem())
+            .filter(|stem| published.contains_key(stem.to_string_lossy().as_ref()))
+            .count();
+        println!();
+        println!(
+            "{} gems ({}) checked",
+            gem_files.len(),
+            lode::human_bytes(i64::try_from(total_size).unwrap_or(i64::MAX))
+        );
+        println!("  {index_checked} matched against index checksums");
+        println!(
+            "  {} checked against their own checksums only",
+            gem_files.len() - index_checked
+        );
+        if corrupted > 0 {
+            println!(
+                "  {corrupted} corrupted, moved to {}",
+                quarantine_dir.display()
+            );
+        } else {
+            println!("  No corrupted gems found");
+        }
+    }
+
+    Ok(())
+}
+
+/// Compact index checksums for the cached gems, keyed by file stem
+///
+/// Gems the index does not know (private sources, yanked versions) or that
+/// cannot be fetched are simply missing from the result.
+async fn published_checksums(gem_files: &[PathBuf]) -> BTreeMap<String, String> {
+    let Ok(client) = lode::RubyGemsClient::new(lode::gem_source_url()) else {
+        return BTreeMap::new();
+    };
+    let names: BTreeSet<String> = gem_files
+        .iter()
+        .filter_map(|path| path.file_stem()?.to_str())
+        .filter_map(|stem| lode::parse_gem_name(stem).map(|(name, _)| name.to_string()))
+        .collect();
+
+    stream::iter(names)
+        .map(|name| {
+            let client = client.clone();
+            async move {
+                let checksums = client.fetch_checksums(&name).await.unwrap_or_default();
+                checksums
+                    .into_iter()
+                    .map(|(version, checksum)| (format!("{name}-{version}"), checksum))
+                    .collect::<Vec<_>>()
+            }
+        })
+        .buffer_unordered(10)
+        .flat_map(stream::iter)
+        .collect()
+        .await
+}
+
 /// Check if a platform string matches the current platform
 fn is_current_platform(platform: Option<&str>) -> bool {
     let Some(platform) = platform else {
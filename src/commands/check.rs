@@ -1,11 +1,13 @@
 //! Check command
 //!
-//! Verify all gems are installed
+//! Verify all gems are installed, or with `--gemfiles` that every
+//! Appraisal-style gemfile/lockfile pair is consistent and installable here
 
 use anyhow::{Context, Result};
-use lode::{Config, config, lockfile::Lockfile};
+use lode::{Config, Gemfile, config, lockfile::Lockfile};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Verify all gems are installed
 pub(crate) fn run(lockfile_path: &str, dry_run: bool) -> Result<()> {
@@ -112,6 +114,201 @@ pub(crate) fn run(lockfile_path: &str, dry_run: bool) -> Result<()> {
     Ok(())
 }
 
+/// Result of checking one gemfile/lockfile pair
+#[derive(Debug, Default)]
+struct PairCheck {
+    /// Why the pair could not be checked at all
+    error: Option<String>,
+    /// Gemfile dependencies the lockfile does not satisfy
+    unlocked: Vec<String>,
+    /// Locked gems with no variant for the current platform
+    unsupported: Vec<String>,
+    /// Gems to install on this platform
+    total: usize,
+    /// Gems to install on this platform that are not installed
+    missing: Vec<String>,
+}
+
+impl PairCheck {
+    fn failed(error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::default()
+        }
+    }
+
+    const fn passed(&self) -> bool {
+        self.error.is_none()
+            && self.unlocked.is_empty()
+            && self.unsupported.is_empty()
+            && self.missing.is_empty()
+    }
+}
+
+/// Verify every gemfile matching `pattern` against its lockfile
+///
+/// Each pair must parse, the lockfile must satisfy the gemfile, every locked
+/// gem needs a variant for this platform, and those variants must be
+/// installed. Prints a matrix with one row per gemfile and fails if any row
+/// does.
+pub(crate) fn run_matrix(pattern: &str) -> Result<()> {
+    let gemfiles = lode::paths::expand_glob(pattern);
+    if gemfiles.is_empty() {
+        anyhow::bail!("No gemfiles match {pattern}");
+    }
+
+    let cfg = Config::load().unwrap_or_default();
+    let vendor_dir = config::vendor_dir(Some(&cfg))?;
+    let platform = lode::detect_current_platform();
+    let checks: Vec<(PathBuf, PairCheck)> = gemfiles
+        .into_iter()
+        .map(|gemfile| {
+            let check = check_pair(&gemfile, &vendor_dir, &platform);
+            (gemfile, check)
+        })
+        .collect();
+
+    print_matrix(&checks, &platform);
+
+    let failed = checks.iter().filter(|(_, check)| !check.passed()).count();
+    if failed > 0 {
+        if checks
+            .iter()
+            .all(|(_, check)| check.error.is_none() && check.unlocked.is_empty())
+        {
+            println!("\nRun `lode install --gemfile '{pattern}'` to install missing gems.");
+        }
+        anyhow::bail!("{failed} of {} gemfiles failed the check", checks.len());
+    }
+    println!(
+        "\nAll {} gemfiles are consistent and installed",
+        checks.len()
+    );
+    Ok(())
+}
+
+/// Check one gemfile against its lockfile and the installed gems
+fn check_pair(gemfile_path: &Path, vendor_dir: &Path, platform: &str) -> PairCheck {
+    let gemfile = match Gemfile::parse_file(gemfile_path) {
+        Ok(gemfile) => gemfile,
+        Err(e) => return PairCheck::failed(format!("Failed to parse gemfile: {e}")),
+    };
+    let lockfile_path = lode::paths::lockfile_for_gemfile(gemfile_path);
+    let Ok(content) = fs::read_to_string(&lockfile_path) else {
+        return PairCheck::failed(format!("No lockfile at {}", lockfile_path.display()));
+    };
+    let lockfile = match Lockfile::parse(&content) {
+        Ok(lockfile) => lockfile,
+        Err(e) => return PairCheck::failed(format!("Failed to parse lockfile: {e}")),
+    };
+
+    let mut variants: BTreeMap<&str, Vec<_>> = BTreeMap::new();
+    for gem in &lockfile.gems {
+        variants.entry(gem.name.as_str()).or_default().push(gem);
+    }
+    let mut unsupported = Vec::new();
+    let mut installable = BTreeSet::new();
+    for (name, gems) in variants {
+        let matching: Vec<_> = gems
+            .iter()
+            .filter(|gem| lode::platform_matches(&gem.platform, platform))
+            .collect();
+        if matching.is_empty() {
+            unsupported.push(name.to_string());
+        }
+        installable.extend(matching.iter().map(|gem| gem.full_name().to_string()));
+    }
+    installable.extend(
+        lockfile
+            .git_gems
+            .iter()
+            .map(|gem| format!("{}-{}", gem.name, gem.version)),
+    );
+
+    let ruby_version = config::ruby_version(lockfile.ruby_version.as_deref());
+    let gems_dir = vendor_dir.join("ruby").join(ruby_version).join("gems");
+    let mut missing: Vec<String> = installable
+        .iter()
+        .filter(|full_name| !gems_dir.join(full_name).exists())
+        .cloned()
+        .collect();
+
+    // Path gems are relative to the gemfile, not the working directory
+    let base = gemfile_path.parent().unwrap_or_else(|| Path::new("."));
+    missing.extend(
+        lockfile
+            .path_gems
+            .iter()
+            .filter(|gem| !base.join(&gem.path).exists())
+            .map(|gem| format!("{}-{} [path: {}]", gem.name, gem.version, gem.path)),
+    );
+
+    PairCheck {
+        error: None,
+        unlocked: lockfile.unlocked_dependencies(&gemfile),
+        unsupported,
+        total: installable.len() + lockfile.path_gems.len(),
+        missing,
+    }
+}
+
+fn print_matrix(checks: &[(PathBuf, PairCheck)], platform: &str) {
+    let width = checks
+        .iter()
+        .map(|(gemfile, _)| gemfile.display().to_string().len())
+        .max()
+        .unwrap_or(0)
+        .max("Gemfile".len());
+
+    println!("Checking {} gemfiles on {platform}\n", checks.len());
+    println!(
+        "{:<width$}  {:<10} {:<16} Installed",
+        "Gemfile", "Lockfile", "Platform"
+    );
+    for (gemfile, check) in checks {
+        let (lockfile, platform, installed) = if check.error.is_some() {
+            ("error".to_string(), "-".to_string(), "-".to_string())
+        } else {
+            (
+                if check.unlocked.is_empty() {
+                    "ok".to_string()
+                } else {
+                    "stale".to_string()
+                },
+                if check.unsupported.is_empty() {
+                    "ok".to_string()
+                } else {
+                    format!("{} unsupported", check.unsupported.len())
+                },
+                format!("{}/{}", check.total - check.missing.len(), check.total),
+            )
+        };
+        println!(
+            "{:<width$}  {lockfile:<10} {platform:<16} {installed}",
+            gemfile.display()
+        );
+    }
+
+    for (gemfile, check) in checks.iter().filter(|(_, check)| !check.passed()) {
+        println!("\n{}:", gemfile.display());
+        if let Some(error) = &check.error {
+            println!("  {error}");
+        }
+        if !check.unlocked.is_empty() {
+            println!("  Not in lockfile: {}", check.unlocked.join(", "));
+        }
+        if !check.unsupported.is_empty() {
+            println!(
+                "  No variant for this platform: {}",
+                check.unsupported.join(", ")
+            );
+        }
+        if !check.missing.is_empty() {
+            println!("  Not installed: {}", check.missing.join(", "));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,4 +383,40 @@ mod tests {
         // Should error because gem doesn't exist
         assert!(result.is_err());
     }
+
+    #[test]
+    fn check_pair_reports_each_problem() {
+        let temp = TempDir::new().unwrap();
+        let vendor = temp.path().join("vendor");
+        let gemfiles = temp.path().join("gemfiles");
+        fs::create_dir_all(&gemfiles).unwrap();
+        let platform = "x86_64-linux";
+
+        let lock = "GEM\n  remote: https://rubygems.org/\n  specs:\n    rack (3.1.7)\n    sqlite3 (2.0.0-arm64-darwin)\n\nPLATFORMS\n  ruby\n\nDEPENDENCIES\n  rack\n\nRUBY VERSION\n   ruby 3.3.0\n";
+        let gems_dir = vendor
+            .join("ruby")
+            .join(config::ruby_version(Some("3.3.0")))
+            .join("gems");
+        fs::create_dir_all(gems_dir.join("rack-3.1.7")).unwrap();
+
+        let good = gemfiles.join("rack.gemfile");
+        fs::write(&good, "source 'https://rubygems.org'\ngem 'rack'\n").unwrap();
+        fs::write(gemfiles.join("rack.gemfile.lock"), lock).unwrap();
+        let check = check_pair(&good, &vendor, platform);
+        assert_eq!(check.unsupported, vec!["sqlite3".to_string()]);
+        assert_eq!((check.total, check.missing.len()), (1, 0));
+        assert!(!check.passed());
+
+        let stale = gemfiles.join("rails.gemfile");
+        fs::write(&stale, "source 'https://rubygems.org'\ngem 'rails'\n").unwrap();
+        fs::write(gemfiles.join("rails.gemfile.lock"), lock).unwrap();
+        assert_eq!(
+            check_pair(&stale, &vendor, platform).unlocked,
+            vec!["rails".to_string()]
+        );
+
+        let unlocked = gemfiles.join("rake.gemfile");
+        fs::write(&unlocked, "source 'https://rubygems.org'\ngem 'rake'\n").unwrap();
+        assert!(check_pair(&unlocked, &vendor, platform).error.is_some());
+    }
 }
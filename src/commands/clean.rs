@@ -186,7 +186,7 @@ fn calculate_dir_size(path: &std::path::Path) -> u64 {
 }
 
 /// Format bytes into human-readable string
-fn format_bytes(bytes: u64) -> String {
+pub(crate) fn format_bytes(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
 
     if bytes == 0 {
@@ -0,0 +1,108 @@
+//! Complete command
+//!
+//! Candidates for the dynamic parts of shell completion. The scripts from
+//! `lode completion` run the hidden `lode complete-candidates <kind> [prefix]` as
+//! the user presses tab, so gem names, config keys and group names come
+//! from the current project rather than from when the script was generated.
+
+use anyhow::Result;
+use lode::{Gemfile, lockfile::Lockfile};
+use std::collections::BTreeSet;
+use std::fs;
+
+/// What `lode complete-candidates` lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum CompletionKind {
+    /// Gems in the bundle (`info`, `open`, `remove`, `binstubs`)
+    Gems,
+    /// Configuration keys (`config`)
+    ConfigKeys,
+    /// Gemfile groups (`--only`, `--without`, `--group`)
+    Groups,
+}
+
+/// Print the candidates of `kind` starting with `prefix`, one per line
+///
+/// Completion must never disturb the prompt, so an unreadable Gemfile or
+/// lockfile just yields no candidates.
+#[allow(
+    clippy::unnecessary_wraps,
+    reason = "Result type maintained for consistency with command signature pattern"
+)]
+pub(crate) fn run(kind: CompletionKind, prefix: Option<&str>) -> Result<()> {
+    let prefix = prefix.unwrap_or_default();
+    let candidates = match kind {
+        CompletionKind::Gems => gem_names(),
+        CompletionKind::ConfigKeys => config_keys(),
+        CompletionKind::Groups => group_names(),
+    };
+    for candidate in candidates.iter().filter(|c| c.starts_with(prefix)) {
+        println!("{candidate}");
+    }
+    Ok(())
+}
+
+/// Gems locked in `Gemfile.lock`, or the Gemfile's gems before the first lock
+fn gem_names() -> BTreeSet<String> {
+    let lockfile = fs::read_to_string(lode::paths::find_lockfile())
+        .ok()
+        .and_then(|content| Lockfile::parse(&content).ok());
+    if let Some(lockfile) = lockfile {
+        return lockfile
+            .gems
+            .into_iter()
+            .map(|gem| gem.name)
+            .chain(lockfile.git_gems.into_iter().map(|gem| gem.name))
+            .chain(lockfile.path_gems.into_iter().map(|gem| gem.name))
+            .collect();
+    }
+
+    Gemfile::parse_file(lode::paths::find_gemfile())
+        .map(|gemfile| gemfile.gems.into_iter().map(|gem| gem.name).collect())
+        .unwrap_or_default()
+}
+
+/// Groups named in the Gemfile, plus the implicit `default`
+fn group_names() -> BTreeSet<String> {
+    let mut groups: BTreeSet<String> = Gemfile::parse_file(lode::paths::find_gemfile())
+        .map(|gemfile| {
+            gemfile
+                .gems
+                .into_iter()
+                .flat_map(|gem| gem.groups)
+                .collect()
+        })
+        .unwrap_or_default();
+    groups.insert("default".to_string());
+    groups
+}
+
+/// Known configuration keys
+///
+/// Keys with placeholders complete up to the placeholder (`local.`).
+fn config_keys() -> BTreeSet<String> {
+    super::config::COMMON_KEYS
+        .iter()
+        .filter_map(|(key, _)| key.split_whitespace().next())
+        .map(|key| {
+            key.split_inclusive('.')
+                .take_while(|segment| !segment.chars().any(|c| c.is_ascii_uppercase()))
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_keys_stop_at_placeholders() {
+        let keys = config_keys();
+        assert!(keys.contains("vendor_dir"));
+        assert!(keys.contains("gem.no_document"));
+        assert!(keys.contains("local."));
+        assert!(keys.contains("profile."));
+        assert!(!keys.iter().any(|key| key.contains("NAME")));
+    }
+}
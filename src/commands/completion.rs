@@ -1,6 +1,11 @@
 //! Completion command
 //!
 //! Generate shell completion scripts
+//!
+//! The static script from `clap_complete` covers commands and flags. For
+//! bash, zsh and fish a wrapper is appended that asks `lode complete-candidates` for
+//! installed gem names, config keys and Gemfile groups at the point they
+//! are needed.
 
 use anyhow::Result;
 use clap::CommandFactory;
@@ -34,21 +39,99 @@ pub(crate) fn run(shell: Shell) -> Result<()> {
     let mut cmd = crate::Cli::command();
 
     generate(shell, &mut cmd, "lode", &mut io::stdout());
+    if let Some(script) = dynamic_script(shell) {
+        print!("{script}");
+    }
 
     Ok(())
 }
 
+/// Wrapper completing gem names, config keys and groups via `lode complete-candidates`
+const fn dynamic_script(shell: Shell) -> Option<&'static str> {
+    match shell {
+        Shell::Bash => Some(BASH_DYNAMIC),
+        Shell::Zsh => Some(ZSH_DYNAMIC),
+        Shell::Fish => Some(FISH_DYNAMIC),
+        _ => None,
+    }
+}
+
+const BASH_DYNAMIC: &str = r#"
+_lode_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" kind=""
+    if [[ $COMP_CWORD -ge 2 ]]; then
+        case "${COMP_WORDS[1]}" in
+            info|open) [[ $COMP_CWORD -eq 2 ]] && kind=gems ;;
+            remove|binstubs) kind=gems ;;
+            config) [[ $COMP_CWORD -eq 2 ]] && kind=config-keys ;;
+        esac
+        case "${COMP_WORDS[COMP_CWORD-1]}" in
+            --only|--without|--group|-g) kind=groups ;;
+        esac
+    fi
+    if [[ -n $kind && $cur != -* ]]; then
+        COMPREPLY=($(compgen -W "$(lode complete-candidates "$kind" "$cur" 2>/dev/null)" -- "$cur"))
+        return 0
+    fi
+    _lode "$@"
+}
+
+complete -F _lode_dynamic -o nosort -o bashdefault -o default lode
+"#;
+
+const ZSH_DYNAMIC: &str = r#"
+_lode_dynamic() {
+    local kind
+    if (( CURRENT > 2 )); then
+        case ${words[2]} in
+            info|open) (( CURRENT == 3 )) && kind=gems ;;
+            remove|binstubs) kind=gems ;;
+            config) (( CURRENT == 3 )) && kind=config-keys ;;
+        esac
+        case ${words[CURRENT-1]} in
+            --only|--without|--group|-g) kind=groups ;;
+        esac
+    fi
+    if [[ -n $kind && ${words[CURRENT]} != -* ]]; then
+        local -a candidates
+        candidates=(${(f)"$(lode complete-candidates $kind ${words[CURRENT]} 2>/dev/null)"})
+        compadd -a candidates
+        return
+    fi
+    _lode "$@"
+}
+
+compdef _lode_dynamic lode
+"#;
+
+const FISH_DYNAMIC: &str = r#"
+complete -c lode -n "__fish_seen_subcommand_from info open remove binstubs" -f -a "(lode complete-candidates gems (commandline -ct))"
+complete -c lode -n "__fish_seen_subcommand_from config" -f -a "(lode complete-candidates config-keys (commandline -ct))"
+complete -c lode -n "__fish_prev_arg_in --only --without --group -g" -f -a "(lode complete-candidates groups (commandline -ct))"
+"#;
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, reason = "Tests can panic")]
 mod tests {
     use super::*;
 
     // Note: These tests require large stack due to 55+ command CLI structure
-    // Run with: RUST_MIN_STACK=4194304 cargo test -- --ignored
-    // Or: RUST_MIN_STACK=4194304 cargo nextest run --run-ignored ignored-only
+    // Run with: RUST_MIN_STACK=8388608 cargo test -- --ignored
+    // Or: RUST_MIN_STACK=8388608 cargo nextest run --run-ignored ignored-only
+
+    #[test]
+    fn dynamic_scripts_call_complete() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            assert!(
+                dynamic_script(shell)
+                    .is_some_and(|script| script.contains("lode complete-candidates"))
+            );
+        }
+        assert!(dynamic_script(Shell::PowerShell).is_none());
+    }
 
     #[test]
-    #[ignore = "requires large stack (run with RUST_MIN_STACK=4194304)"]
+    #[ignore = "requires large stack (run with RUST_MIN_STACK=8388608)"]
     fn completion_bash() {
         // Just verify it doesn't panic
         let result = run(Shell::Bash);
@@ -56,21 +139,21 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "requires large stack (run with RUST_MIN_STACK=4194304)"]
+    #[ignore = "requires large stack (run with RUST_MIN_STACK=8388608)"]
     fn completion_zsh() {
         let result = run(Shell::Zsh);
         assert!(result.is_ok());
     }
 
     #[test]
-    #[ignore = "requires large stack (run with RUST_MIN_STACK=4194304)"]
+    #[ignore = "requires large stack (run with RUST_MIN_STACK=8388608)"]
     fn completion_fish() {
         let result = run(Shell::Fish);
         assert!(result.is_ok());
     }
 
     #[test]
-    #[ignore = "requires large stack (run with RUST_MIN_STACK=4194304)"]
+    #[ignore = "requires large stack (run with RUST_MIN_STACK=8388608)"]
     fn completion_powershell() {
         let result = run(Shell::PowerShell);
         assert!(result.is_ok());
@@ -9,9 +9,87 @@
 
 use anyhow::{Context, Result};
 use lode::Config;
+use lode::config::GroupProfile;
 use std::fs;
 use std::path::PathBuf;
 
+/// Settings listed by `lode config` and completed by `lode complete-candidates`
+///
+/// Uppercase segments are placeholders (`local.GEM_NAME`).
+pub(crate) const COMMON_KEYS: &[(&str, &str)] = &[
+    ("vendor_dir (or path)", "Installation path for gems"),
+    ("cache_dir", "Cache directory for downloaded gems"),
+    ("gemfile", "Custom Gemfile path"),
+    ("bin_dir", "Directory for generated binstubs"),
+    ("local.GEM_NAME", "Use a local git checkout for a git gem"),
+    (
+        "disable_local_branch_check",
+        "Skip branch check for local overrides",
+    ),
+    (
+        "ignore_funding_requests",
+        "Hide funding requests after install",
+    ),
+    (
+        "silence_deprecations",
+        "Skip deprecated/unmaintained gem notices",
+    ),
+    (
+        "disable_multisource",
+        "Reject Gemfiles with several global sources",
+    ),
+    (
+        "disable_shared_gems",
+        "Hide user and system gems from the bundle",
+    ),
+    (
+        "source_pin.PREFIX",
+        "Require gems named PREFIX* to come from a source",
+    ),
+    (
+        "insecure_sources",
+        "Hosts allowed to serve gems over plain http://",
+    ),
+    (
+        "allow_insecure_sources",
+        "Warn about other http:// sources instead of refusing",
+    ),
+    (
+        "exec_unset_env",
+        "Extra variables `lode exec --isolate` removes",
+    ),
+    (
+        "fail_on_case_collisions",
+        "Refuse gems with files differing only in case",
+    ),
+    (
+        "force_ruby_platform.GEM_NAME",
+        "Use the ruby platform variant of a gem",
+    ),
+    ("build.GEM_NAME", "Extra extconf.rb arguments for a gem"),
+    (
+        "hold.GEM_NAME",
+        "Keep a gem at its locked version (see `lode hold`)",
+    ),
+    ("locale", "Message language: en or ja (default from LANG)"),
+    (
+        "max_requests_per_second",
+        "Ceiling on requests per second to each gem server",
+    ),
+    (
+        "gem.no_document",
+        "Skip rdoc/ri for gem-install and gem-update",
+    ),
+    (
+        "profile.NAME.with",
+        "Groups `lode install --group-profile NAME` adds",
+    ),
+    (
+        "profile.NAME.without",
+        "Groups `lode install --group-profile NAME` skips",
+    ),
+];
+
 /// Get and set Bundler configuration options
 ///
 /// This command manages Lode/Bundler configuration settings.
@@ -55,9 +133,9 @@ pub(crate) fn run(
         println!("  lode config <key> --delete --local  # Delete local configuration key");
         println!();
         println!("Common configuration keys:");
-        println!("  vendor_dir (or path) # Installation path for gems");
-        println!("  cache_dir            # Cache directory for downloaded gems");
-        println!("  gemfile              # Custom Gemfile path");
+        for (key, description) in COMMON_KEYS {
+            println!("  {key:<20} # {description}");
+        }
         Ok(())
     }
 }
@@ -66,10 +144,105 @@ pub(crate) fn run(
 fn get_config(key: &str) -> Result<()> {
     let config = Config::load().context("Failed to load configuration")?;
 
+    let local_override;
+    let branch_check;
+    let toggle;
+    let groups;
     let value = match key {
         "vendor_dir" | "path" => config.vendor_dir.as_deref(),
         "cache_dir" => config.cache_dir.as_deref(),
         "gemfile" => config.gemfile.as_deref(),
+        "bin_dir" | "bin" => config.bin_dir.as_deref(),
+        "disable_local_branch_check" => {
+            branch_check = lode::config::local_branch_check_disabled(Some(&config)).to_string();
+            Some(branch_check.as_str())
+        }
+        "ignore_funding_requests" => {
+            toggle = lode::config::funding_requests_ignored(Some(&config)).to_string();
+            Some(toggle.as_str())
+        }
+        "silence_deprecations" => {
+            toggle = lode::config::deprecations_silenced(Some(&config)).to_string();
+            Some(toggle.as_str())
+        }
+        "disable_multisource" => {
+            toggle = lode::config::multisource_disabled(Some(&config)).to_string();
+            Some(toggle.as_str())
+        }
+        "disable_shared_gems" => {
+            toggle = lode::config::shared_gems_disabled(Some(&config)).to_string();
+            Some(toggle.as_str())
+        }
+        "insecure_sources" => {
+            groups = Some(config.insecure_sources.join(", ")).filter(|hosts| !hosts.is_empty());
+            groups.as_deref()
+        }
+        "allow_insecure_sources" => {
+            toggle = config.allow_insecure_sources.unwrap_or(false).to_string();
+            Some(toggle.as_str())
+        }
+        "fail_on_case_collisions" => {
+            toggle = lode::config::case_collisions_fatal(Some(&config)).to_string();
+            Some(toggle.as_str())
+        }
+        "exec_unset_env" => {
+            groups = Some(config.exec_unset_env.join(", ")).filter(|names| !names.is_empty());
+            groups.as_deref()
+        }
+        "locale" => config.locale.as_deref(),
+        "max_requests_per_second" => {
+            toggle = lode::config::max_requests_per_second(Some(&config))
+                .map(|rate| rate.to_string())
+                .unwrap_or_default();
+            Some(toggle.as_str()).filter(|rate| !rate.is_empty())
+        }
+        "gem.no_document" => {
+            toggle = lode::config::no_document(Some(&config)).to_string();
+            Some(toggle.as_str())
+        }
+        _ if key.starts_with("source_pin.") => key
+            .strip_prefix("source_pin.")
+            .and_then(|prefix| config.source_pins.get(prefix))
+            .map(String::as_str),
+        _ if key.starts_with("build.") => key
+            .strip_prefix("build.")
+            .and_then(|gem| config.build.get(gem))
+            .map(String::as_str),
+        _ if key.starts_with("hold.") => key
+            .strip_prefix("hold.")
+            .and_then(|gem| config.hold.get(gem))
+            .map(|reason| {
+                if reason.is_empty() {
+                    "held"
+                } else {
+                    reason.as_str()
+                }
+            }),
+        _ if key.starts_with("force_ruby_platform.") => {
+            toggle = key
+                .strip_prefix("force_ruby_platform.")
+                .and_then(|gem| config.force_ruby_platform.get(gem))
+                .map_or_else(String::new, ToString::to_string);
+            (!toggle.is_empty()).then_some(toggle.as_str())
+        }
+        _ if GroupProfile::parse_key(key).is_some() => {
+            groups = GroupProfile::parse_key(key).and_then(|(name, field)| {
+                config
+                    .profiles
+                    .get(name)
+                    .map(|profile| profile.groups(field).join(", "))
+                    .filter(|groups| !groups.is_empty())
+            });
+            groups.as_deref()
+        }
+        _ if key.starts_with("local.") => {
+            local_override = key.strip_prefix("local.").and_then(|gem| {
+                lode::config::local_overrides(Some(&config))
+                    .get(gem)
+                    .map(|path| path.display().to_string())
+            });
+            local_override.as_deref()
+        }
         _ => {
             println!("Unknown configuration key: {key}");
             println!("Run `lode config` for list of available keys");
@@ -116,6 +289,157 @@ fn set_config(key: &str, value: &str, local: bool) -> Result<()> {
             config.gemfile = Some(value.to_string());
             println!("Set gemfile to: {value}");
         }
+        "bin_dir" | "bin" => {
+            config.bin_dir = Some(value.to_string());
+            println!("Set bin_dir to: {value}");
+        }
+        "disable_local_branch_check" => {
+            let disabled = matches!(value.to_lowercase().as_str(), "true" | "1" | "yes");
+            config.disable_local_branch_check = Some(disabled);
+            println!("Set disable_local_branch_check to: {disabled}");
+        }
+        "ignore_funding_requests" => {
+            let ignored = matches!(value.to_lowercase().as_str(), "true" | "1" | "yes");
+            config.ignore_funding_requests = Some(ignored);
+            println!("Set ignore_funding_requests to: {ignored}");
+        }
+        "silence_deprecations" => {
+            let silenced = matches!(value.to_lowercase().as_str(), "true" | "1" | "yes");
+            config.silence_deprecations = Some(silenced);
+            println!("Set silence_deprecations to: {silenced}");
+        }
+        "disable_multisource" => {
+            let disabled = matches!(value.to_lowercase().as_str(), "true" | "1" | "yes");
+            config.disable_multisource = Some(disabled);
+            println!("Set disable_multisource to: {disabled}");
+        }
+        "disable_shared_gems" => {
+            let disabled = matches!(value.to_lowercase().as_str(), "true" | "1" | "yes");
+            config.disable_shared_gems = Some(disabled);
+            println!("Set disable_shared_gems to: {disabled}");
+        }
+        "insecure_sources" => {
+            config.insecure_sources = value
+                .split([',', ' '])
+                .filter(|host| !host.is_empty())
+                .map(str::to_string)
+                .collect();
+            println!(
+                "Allowed insecure sources: {}",
+                config.insecure_sources.join(", ")
+            );
+        }
+        "allow_insecure_sources" => {
+            let allowed = matches!(value.to_lowercase().as_str(), "true" | "1" | "yes");
+            config.allow_insecure_sources = Some(allowed);
+            println!("Set allow_insecure_sources to: {allowed}");
+        }
+        "fail_on_case_collisions" => {
+            let fatal = matches!(value.to_lowercase().as_str(), "true" | "1" | "yes");
+            config.fail_on_case_collisions = Some(fatal);
+            println!("Set fail_on_case_collisions to: {fatal}");
+        }
+        "exec_unset_env" => {
+            config.exec_unset_env = value
+                .split([',', ' '])
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect();
+            println!(
+                "lode exec --isolate removes: {}",
+                config.exec_unset_env.join(", ")
+            );
+        }
+        "locale" => {
+            let locale = lode::messages::Locale::parse(value).ok_or_else(|| {
+                anyhow::anyhow!("Unsupported locale: {value} (supported: en, ja)")
+            })?;
+            config.locale = Some(locale.tag().to_string());
+            println!("Set locale to: {}", locale.tag());
+        }
+        "max_requests_per_second" => {
+            let rate: f64 = value
+                .parse()
+                .ok()
+                .filter(|rate: &f64| rate.is_finite() && *rate > 0.0)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("max_requests_per_second must be a positive number")
+                })?;
+            config.max_requests_per_second = Some(rate);
+            println!("Set max_requests_per_second to: {rate}");
+        }
+        "gem.no_document" => {
+            let skipped = matches!(value.to_lowercase().as_str(), "true" | "1" | "yes");
+            config.gem.no_document = Some(skipped);
+            println!("Set gem.no_document to: {skipped}");
+        }
+        _ if key.starts_with("local.") => {
+            let gem = key.trim_start_matches("local.");
+            if gem.is_empty() {
+                anyhow::bail!("Missing gem name in {key} (use local.GEM_NAME)");
+            }
+            config.local.insert(gem.to_string(), value.to_string());
+            println!("Set local override for {gem} to: {value}");
+        }
+        _ if key.starts_with("source_pin.") => {
+            let prefix = key.trim_start_matches("source_pin.");
+            if prefix.is_empty() {
+                anyhow::bail!("Missing gem name prefix in {key} (use source_pin.PREFIX)");
+            }
+            config
+                .source_pins
+                .insert(prefix.to_string(), value.to_string());
+            println!("Pinned gems starting with {prefix} to: {value}");
+        }
+        _ if key.starts_with("build.") => {
+            let gem = key.trim_start_matches("build.");
+            if gem.is_empty() {
+                anyhow::bail!("Missing gem name in {key} (use build.GEM_NAME)");
+            }
+            config.build.insert(gem.to_string(), value.to_string());
+            println!("Set build flags for {gem} to: {value}");
+        }
+        _ if key.starts_with("hold.") => {
+            let gem = key.trim_start_matches("hold.");
+            if gem.is_empty() {
+                anyhow::bail!("Missing gem name in {key} (use hold.GEM_NAME)");
+            }
+            config.hold.insert(gem.to_string(), value.to_string());
+            println!("Holding {gem}: {value}");
+        }
+        _ if key.starts_with("force_ruby_platform.") => {
+            let gem = key.trim_start_matches("force_ruby_platform.");
+            if gem.is_empty() {
+                anyhow::bail!("Missing gem name in {key} (use force_ruby_platform.GEM_NAME)");
+            }
+            let forced = matches!(value.to_lowercase().as_str(), "true" | "1" | "yes");
+            config.force_ruby_platform.insert(gem.to_string(), forced);
+            println!("Set force_ruby_platform for {gem} to: {forced}");
+            if forced {
+                println!("Run `lode lock` to lock the ruby platform variant of {gem}");
+            }
+        }
+        _ if key.starts_with("profile.") => {
+            let Some((name, field)) = GroupProfile::parse_key(key) else {
+                anyhow::bail!(
+                    "Invalid profile key {key} (use profile.NAME.with or profile.NAME.without)"
+                );
+            };
+            let groups: Vec<String> = value
+                .split([',', ':', ' '])
+                .filter(|group| !group.is_empty())
+                .map(str::to_string)
+                .collect();
+            println!(
+                "Set {field} groups of profile {name} to: {}",
+                groups.join(", ")
+            );
+            *config
+                .profiles
+                .entry(name.to_string())
+                .or_default()
+                .groups_mut(field) = groups;
+        }
         _ => {
             anyhow::bail!("Unknown configuration key: {key}");
         }
@@ -184,6 +508,50 @@ fn delete_config(key: &str, local: bool) -> Result<()> {
                 false
             }
         }
+        "bin_dir" | "bin" => config.bin_dir.take().is_some(),
+        "disable_local_branch_check" => config.disable_local_branch_check.take().is_some(),
+        "ignore_funding_requests" => config.ignore_funding_requests.take().is_some(),
+        "silence_deprecations" => config.silence_deprecations.take().is_some(),
+        "disable_multisource" => config.disable_multisource.take().is_some(),
+        "disable_shared_gems" => config.disable_shared_gems.take().is_some(),
+        "insecure_sources" => !std::mem::take(&mut config.insecure_sources).is_empty(),
+        "allow_insecure_sources" => config.allow_insecure_sources.take().is_some(),
+        "fail_on_case_collisions" => config.fail_on_case_collisions.take().is_some(),
+        "exec_unset_env" => !std::mem::take(&mut config.exec_unset_env).is_empty(),
+        "locale" => config.locale.take().is_some(),
+        "max_requests_per_second" => config.max_requests_per_second.take().is_some(),
+        "gem.no_document" => config.gem.no_document.take().is_some(),
+        _ if key.starts_with("local.") => config
+            .local
+            .remove(key.trim_start_matches("local."))
+            .is_some(),
+        _ if key.starts_with("source_pin.") => config
+            .source_pins
+            .remove(key.trim_start_matches("source_pin."))
+            .is_some(),
+        _ if key.starts_with("build.") => config
+            .build
+            .remove(key.trim_start_matches("build."))
+            .is_some(),
+        _ if key.starts_with("hold.") => config
+            .hold
+            .remove(key.trim_start_matches("hold."))
+            .is_some(),
+        _ if key.starts_with("force_ruby_platform.") => config
+            .force_ruby_platform
+            .remove(key.trim_start_matches("force_ruby_platform."))
+            .is_some(),
+        _ if GroupProfile::parse_key(key).is_some() => {
+            let (name, field) = GroupProfile::parse_key(key).unwrap_or_default();
+            let deleted = config
+                .profiles
+                .get_mut(name)
+                .is_some_and(|profile| !std::mem::take(profile.groups_mut(field)).is_empty());
+            config
+                .profiles
+                .retain(|_, profile| *profile != GroupProfile::default());
+            deleted
+        }
         _ => {
             anyhow::bail!("Unknown configuration key: {key}");
         }
@@ -211,7 +579,13 @@ fn list_config(local_only: bool) -> Result<()> {
 
     println!("Configuration:");
     println!();
+    print_settings(&config);
+    println!();
+    print_config_paths(local_only)
+}
 
+/// Print each setting that is set
+fn print_settings(config: &Config) {
     if let Some(vendor_dir) = &config.vendor_dir {
         println!("  vendor_dir: {vendor_dir}");
     }
@@ -224,9 +598,90 @@ fn list_config(local_only: bool) -> Result<()> {
         println!("  gemfile:    {gemfile}");
     }
 
-    println!();
+    if let Some(bin_dir) = &config.bin_dir {
+        println!("  bin_dir:    {bin_dir}");
+    }
+
+    for (gem, path) in lode::config::local_overrides(Some(config)) {
+        println!("  local.{gem}: {}", path.display());
+    }
+
+    if lode::config::local_branch_check_disabled(Some(config)) {
+        println!("  disable_local_branch_check: true");
+    }
+
+    if lode::config::funding_requests_ignored(Some(config)) {
+        println!("  ignore_funding_requests: true");
+    }
+
+    if lode::config::deprecations_silenced(Some(config)) {
+        println!("  silence_deprecations: true");
+    }
+
+    if lode::config::multisource_disabled(Some(config)) {
+        println!("  disable_multisource: true");
+    }
+
+    if lode::config::shared_gems_disabled(Some(config)) {
+        println!("  disable_shared_gems: true");
+    }
+
+    for (prefix, source) in &config.source_pins {
+        println!("  source_pin.{prefix}: {source}");
+    }
+
+    if !config.insecure_sources.is_empty() {
+        println!("  insecure_sources: {}", config.insecure_sources.join(", "));
+    }
+
+    if config.allow_insecure_sources == Some(true) {
+        println!("  allow_insecure_sources: true");
+    }
+
+    if lode::config::case_collisions_fatal(Some(config)) {
+        println!("  fail_on_case_collisions: true");
+    }
 
-    // Show config file location
+    if let Some(rate) = config.max_requests_per_second {
+        println!("  max_requests_per_second: {rate}");
+    }
+
+    if !config.exec_unset_env.is_empty() {
+        println!("  exec_unset_env: {}", config.exec_unset_env.join(", "));
+    }
+
+    for (gem, forced) in &config.force_ruby_platform {
+        println!("  force_ruby_platform.{gem}: {forced}");
+    }
+
+    for (gem, flags) in &config.build {
+        println!("  build.{gem}: {flags}");
+    }
+
+    for (gem, reason) in &config.hold {
+        println!("  hold.{gem}: {reason}");
+    }
+
+    if let Some(locale) = &config.locale {
+        println!("  locale: {locale}");
+    }
+
+    if lode::config::no_document(Some(config)) {
+        println!("  gem.no_document: true");
+    }
+
+    for (name, profile) in &config.profiles {
+        for field in ["with", "without"] {
+            let groups = profile.groups(field);
+            if !groups.is_empty() {
+                println!("  profile.{name}.{field}: {}", groups.join(", "));
+            }
+        }
+    }
+}
+
+/// Print where each configuration layer is read from
+fn print_config_paths(local_only: bool) -> Result<()> {
     if local_only {
         let local_path = get_local_config_path()?;
         if local_path.exists() {
@@ -235,6 +690,12 @@ fn list_config(local_only: bool) -> Result<()> {
             println!("No local config found");
         }
     } else {
+        for site_path in Config::site_config_paths() {
+            if site_path.exists() {
+                println!("Site config:   {}", site_path.display());
+            }
+        }
+
         let global_path = get_global_config_path()?;
         println!("Global config: {}", global_path.display());
 
@@ -3,6 +3,7 @@
 //! List all files in an installed gem
 
 use anyhow::{Context, Result};
+use lode::gem_store::{GemPath, GemQuery};
 use lode::{Config, config};
 use std::path::{Path, PathBuf};
 
@@ -13,13 +14,15 @@ pub(crate) struct ContentsOptions {
     pub lib_only: bool,
     pub prefix: bool,
     pub show_install_dir: bool,
+    /// Compare installed files against the cached .gem instead of listing
+    pub diff: bool,
 }
 
 /// List all files in an installed gem.
 ///
 /// Searches for the gem in:
 /// 1. Vendor directory (project gems)
-/// 2. System gem directory
+/// 2. Every `GEM_PATH` directory
 pub(crate) fn run(
     gems: &[String],
     version: Option<&str>,
@@ -52,16 +55,18 @@ pub(crate) fn run(
             continue;
         }
 
+        if options.diff {
+            let cache_dirs: Vec<PathBuf> = config::cache_dir(Some(&config)).into_iter().collect();
+            print_contents_diff(gem_name, &gem_dir, &cache_dirs)?;
+            continue;
+        }
+
         // List all files recursively
         let mut files = list_files_recursive(&gem_dir)?;
 
         // Filter for lib_only if requested
         if options.lib_only {
-            files.retain(|f| {
-                f.strip_prefix(&gem_dir)
-                    .map(|p| p.starts_with("lib"))
-                    .unwrap_or(false)
-            });
+            files.retain(|f| f.strip_prefix(&gem_dir).is_ok_and(|p| p.starts_with("lib")));
         }
 
         if files.is_empty() {
@@ -89,6 +94,42 @@ pub(crate) fn run(
     Ok(())
 }
 
+/// Print modified, missing, and extra files relative to the packaged gem
+///
+/// Shared with `gem-contents --diff`.
+pub(crate) fn print_contents_diff(
+    gem_name: &str,
+    gem_dir: &Path,
+    cache_dirs: &[PathBuf],
+) -> Result<()> {
+    let gem_path = lode::install::find_cached_gem(gem_dir, cache_dirs).with_context(|| {
+        format!(
+            "No cached .gem found for {}; cannot compare against the package",
+            gem_dir.display()
+        )
+    })?;
+
+    let diff = lode::install::diff_installed_gem(&gem_path, gem_dir, gem_name)
+        .with_context(|| format!("Failed to compare {gem_name} with {}", gem_path.display()))?;
+
+    if diff.is_clean() {
+        println!("{gem_name}: no differences from {}", gem_path.display());
+        return Ok(());
+    }
+
+    for path in &diff.modified {
+        println!("modified: {}", path.display());
+    }
+    for path in &diff.missing {
+        println!("missing:  {}", path.display());
+    }
+    for path in &diff.extra {
+        println!("extra:    {}", path.display());
+    }
+
+    Ok(())
+}
+
 /// Get all installed gems
 fn get_all_installed_gems(config: &Config) -> Result<Vec<String>> {
     let vendor_dir = config::vendor_dir(Some(config))?;
@@ -170,16 +211,19 @@ fn find_gem_directory(
         }
     }
 
-    // Try system gem directory
-    let system_gem_dir = lode::get_system_gem_dir(&ruby_ver);
-    if system_gem_dir.exists()
-        && let Some(gem_dir) = find_matching_gem(&system_gem_dir, gem_name, version)?
+    // Try every GEM_PATH directory, newest matching version first
+    let gem_path = GemPath::from_env(&config::ruby_version(None));
+    let installed = gem_path.query(&GemQuery::new().with_name(gem_name))?;
+    if let Some(gem) = installed
+        .into_iter()
+        .rev()
+        .find(|gem| version.is_none_or(|v| gem.version == v))
     {
-        return Ok(gem_dir);
+        return Ok(gem.path);
     }
 
     // Not found
-    anyhow::bail!("Gem '{gem_name}' not found in vendor or system directories");
+    anyhow::bail!("Gem '{gem_name}' not found in vendor directory or GEM_PATH");
 }
 
 /// Find a gem directory matching the name and optional version
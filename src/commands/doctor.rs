@@ -6,14 +6,22 @@
 //! - Mismatched platforms
 //! - Uninstalled gems
 //! - Missing dependencies
+//!
+//! With `--remote` it instead checks the lockfile against its sources:
+//! yanked versions, checksums the registry disagrees with, platforms no
+//! longer published, and git revisions no branch or tag reaches.
 
 use anyhow::{Context, Result};
+use futures_util::stream::{self, StreamExt};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 use lode::config::Config;
-use lode::lockfile::Lockfile;
+use lode::lockfile::{GemSpec, Lockfile};
 use lode::platform;
+use lode::{Gemfile, RubyGemsClient, RubyGemsError};
 
 /// Run the doctor command to diagnose common problems.
 #[allow(clippy::cognitive_complexity)]
@@ -214,12 +222,320 @@ pub(crate) fn run(gemfile_path: Option<&str>, quiet: bool) -> Result<()> {
     }
 }
 
+/// What a source says is wrong with a lockfile entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum RemoteIssueKind {
+    /// The locked version is no longer published
+    Yanked,
+    /// The registry's checksum differs from the locked one
+    ChecksumMismatch,
+    /// The version is published, but not for the locked platform
+    PlatformMissing,
+    /// No branch or tag of the repository reaches the locked revision
+    UnreachableRevision,
+    /// The source could not be queried
+    Unchecked,
+}
+
+/// A lockfile entry that does not match its source
+#[derive(Debug, Serialize)]
+struct RemoteIssue {
+    gem: String,
+    version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    platform: Option<String>,
+    source: String,
+    kind: RemoteIssueKind,
+    detail: String,
+}
+
+/// Result of `lode doctor --remote`, printed as-is with `--json`
+#[derive(Debug, Serialize)]
+struct RemoteReport {
+    gems_checked: usize,
+    git_checked: usize,
+    issues: Vec<RemoteIssue>,
+}
+
+/// Check every locked gem against the source it resolves from
+///
+/// Registry gems are compared with the compact index, git gems with the
+/// branches and tags of their repository. Fails when any entry does not
+/// match, so CI can gate on it.
+pub(crate) async fn run_remote(gemfile_path: Option<&str>, json: bool) -> Result<()> {
+    let gemfile_path = gemfile_path.map_or_else(lode::paths::find_gemfile, PathBuf::from);
+    let lockfile_path = lode::lockfile_for_gemfile(&gemfile_path);
+    let content = fs::read_to_string(&lockfile_path)
+        .with_context(|| format!("Failed to read {}", lockfile_path.display()))?;
+    let lockfile = Lockfile::parse(&content)
+        .with_context(|| format!("Failed to parse {}", lockfile_path.display()))?;
+    let gemfile = Gemfile::parse_file(&gemfile_path).ok();
+    let cfg = Config::load().context("Failed to load config")?;
+
+    if !json {
+        println!(
+            "Checking {} against its sources...",
+            lockfile_path.display()
+        );
+    }
+
+    // Gems from source plugins have no compact index to compare with
+    let plugin_gems: BTreeSet<&str> = lockfile
+        .plugin_sources
+        .iter()
+        .flat_map(|source| source.gems.iter().map(String::as_str))
+        .collect();
+    let gems: Vec<(&GemSpec, String)> = lockfile
+        .gems
+        .iter()
+        .filter(|gem| !plugin_gems.contains(gem.name.as_str()))
+        .map(|gem| {
+            let source = gemfile
+                .as_ref()
+                .map_or(lode::DEFAULT_GEM_SOURCE, |gemfile| {
+                    lode::source_audit::resolved_source(gemfile, &gem.name)
+                });
+            (gem, source.to_string())
+        })
+        .collect();
+
+    let published = published_versions(&gems).await;
+    let mut issues: Vec<RemoteIssue> = gems
+        .iter()
+        .filter_map(|(gem, source)| {
+            let (kind, detail) = match published.get(&(source.clone(), gem.name.clone()))? {
+                Ok(versions) => compare_with_registry(gem, versions)?,
+                Err(RubyGemsError::GemNotFound { .. }) => (
+                    RemoteIssueKind::Yanked,
+                    format!("{} is not published on {source}", gem.name),
+                ),
+                Err(e) => (RemoteIssueKind::Unchecked, e.to_string()),
+            };
+            Some(RemoteIssue {
+                gem: gem.name.clone(),
+                version: gem.version.clone(),
+                platform: gem.platform.clone(),
+                source: source.clone(),
+                kind,
+                detail,
+            })
+        })
+        .collect();
+
+    let git = lode::GitManager::new(lode::config::cache_dir(Some(&cfg))?.join("git"))?;
+    let mut git_checked = BTreeSet::new();
+    for gem in &lockfile.git_gems {
+        if !git_checked.insert((gem.repository.as_str(), gem.revision.as_str())) {
+            continue;
+        }
+        let (kind, detail) = match git.revision_reachable(&gem.repository, &gem.revision) {
+            Ok(true) => continue,
+            Ok(false) => (
+                RemoteIssueKind::UnreachableRevision,
+                format!("no branch or tag reaches revision {}", gem.revision),
+            ),
+            Err(e) => (RemoteIssueKind::Unchecked, e.to_string()),
+        };
+        issues.push(RemoteIssue {
+            gem: gem.name.clone(),
+            version: gem.version.clone(),
+            platform: None,
+            source: gem.repository.clone(),
+            kind,
+            detail,
+        });
+    }
+
+    let report = RemoteReport {
+        gems_checked: gems.len(),
+        git_checked: git_checked.len(),
+        issues,
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_remote_report(&report);
+    }
+
+    if report.issues.is_empty() {
+        Ok(())
+    } else {
+        anyhow::bail!(
+            "{} lockfile entries do not match their sources",
+            report.issues.len()
+        )
+    }
+}
+
+/// Published `version[-platform]` checksums, keyed by source and gem name
+async fn published_versions(
+    gems: &[(&GemSpec, String)],
+) -> BTreeMap<(String, String), Result<BTreeMap<String, String>, RubyGemsError>> {
+    let lookups: BTreeSet<(String, String)> = gems
+        .iter()
+        .map(|(gem, source)| (source.clone(), gem.name.clone()))
+        .collect();
+
+    let mut clients: BTreeMap<String, RubyGemsClient> = BTreeMap::new();
+    for source in lookups.iter().map(|(source, _)| source) {
+        if !clients.contains_key(source)
+            && let Ok(client) = RubyGemsClient::new(source.as_str())
+        {
+            clients.insert(source.clone(), client);
+        }
+    }
+
+    stream::iter(lookups)
+        .filter_map(|(source, name)| {
+            let client = clients.get(&source).cloned();
+            std::future::ready(client.map(|client| (client, source, name)))
+        })
+        .map(|(client, source, name)| async move {
+            let checksums = client.fetch_checksums(&name).await;
+            ((source, name), checksums)
+        })
+        .buffer_unordered(10)
+        .collect()
+        .await
+}
+
+/// How a locked gem differs from what its registry publishes, if it does
+///
+/// `published` maps `version[-platform]` to its SHA-256, as read from the
+/// compact index.
+fn compare_with_registry(
+    gem: &GemSpec,
+    published: &BTreeMap<String, String>,
+) -> Option<(RemoteIssueKind, String)> {
+    let platform = gem.platform.as_deref().unwrap_or("ruby");
+    let builds: Vec<(&str, &String)> = published
+        .iter()
+        .filter_map(|(key, checksum)| {
+            let rest = key.strip_prefix(gem.version.as_str())?;
+            if rest.is_empty() {
+                Some(("ruby", checksum))
+            } else {
+                rest.strip_prefix('-').map(|platform| (platform, checksum))
+            }
+        })
+        .collect();
+
+    if builds.is_empty() {
+        return Some((
+            RemoteIssueKind::Yanked,
+            format!("{} {} is no longer published", gem.name, gem.version),
+        ));
+    }
+
+    let wanted = platform::normalize_platform(platform);
+    let Some((_, remote)) = builds
+        .iter()
+        .find(|(published, _)| platform::normalize_platform(published) == wanted)
+    else {
+        let available: Vec<&str> = builds.iter().map(|(platform, _)| *platform).collect();
+        return Some((
+            RemoteIssueKind::PlatformMissing,
+            format!(
+                "{} {} is published for {}, not {platform}",
+                gem.name,
+                gem.version,
+                available.join(", ")
+            ),
+        ));
+    };
+
+    match &gem.checksum {
+        Some(locked) if !locked.eq_ignore_ascii_case(remote) => Some((
+            RemoteIssueKind::ChecksumMismatch,
+            format!("locked sha256={locked}, registry has sha256={remote}"),
+        )),
+        _ => None,
+    }
+}
+
+/// Human-readable `--remote` results
+fn print_remote_report(report: &RemoteReport) {
+    println!();
+    for issue in &report.issues {
+        let platform = issue
+            .platform
+            .as_ref()
+            .map(|platform| format!("-{platform}"))
+            .unwrap_or_default();
+        eprintln!(
+            "{} ({}{platform}): {}",
+            issue.gem, issue.version, issue.detail
+        );
+    }
+    if !report.issues.is_empty() {
+        println!();
+    }
+    println!(
+        "Checked {} gems and {} git revisions: {} issues",
+        report.gems_checked,
+        report.git_checked,
+        report.issues.len()
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
     use tempfile::TempDir;
 
+    fn published(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(key, checksum)| ((*key).to_string(), (*checksum).to_string()))
+            .collect()
+    }
+
+    fn locked(version: &str, platform: Option<&str>, checksum: Option<&str>) -> GemSpec {
+        let mut gem = GemSpec::new(
+            "nokogiri".to_string(),
+            version.to_string(),
+            platform.map(str::to_string),
+            Vec::new(),
+            Vec::new(),
+        );
+        gem.checksum = checksum.map(str::to_string);
+        gem
+    }
+
+    #[test]
+    fn registry_comparison_finds_each_kind_of_drift() {
+        let index = published(&[
+            ("1.16.0", "aaa"),
+            ("1.16.0-arm64-darwin", "bbb"),
+            ("1.16.1", "ccc"),
+        ]);
+
+        assert_eq!(
+            compare_with_registry(&locked("1.16.0", None, Some("AAA")), &index),
+            None
+        );
+        assert_eq!(
+            compare_with_registry(&locked("1.16.0", Some("arm64-darwin"), Some("bbb")), &index),
+            None
+        );
+        assert_eq!(
+            compare_with_registry(&locked("1.15.9", None, None), &index).map(|(kind, _)| kind),
+            Some(RemoteIssueKind::Yanked)
+        );
+        assert_eq!(
+            compare_with_registry(&locked("1.16.0", None, Some("fff")), &index)
+                .map(|(kind, _)| kind),
+            Some(RemoteIssueKind::ChecksumMismatch)
+        );
+        assert_eq!(
+            compare_with_registry(&locked("1.16.1", Some("x86_64-linux"), None), &index)
+                .map(|(kind, _)| kind),
+            Some(RemoteIssueKind::PlatformMissing)
+        );
+    }
+
     #[test]
     fn doctor_missing_gemfile() {
         let temp = TempDir::new().unwrap();
@@ -5,61 +5,68 @@
 //! Bundler version, platform, and environment variables.
 
 use std::env;
+use std::fmt::Write as _;
 use std::process::Command;
 
 /// Display environment information
 pub(crate) fn run() {
-    println!("## Environment");
-    println!();
+    print!("{}", report());
+}
+
+/// Environment information as Markdown, shared with `lode issue`
+pub(crate) fn report() -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "## Environment");
+    out.push('\n');
 
     // Lode version
-    println!("Lode       {}", env!("CARGO_PKG_VERSION"));
-    println!();
+    let _ = writeln!(out, "Lode       {}", env!("CARGO_PKG_VERSION"));
+    out.push('\n');
 
     // Ruby version
     if let Ok(output) = Command::new("ruby").arg("--version").output() {
         if output.status.success()
             && let Ok(version) = String::from_utf8(output.stdout)
         {
-            println!("Ruby       {}", version.trim());
+            let _ = writeln!(out, "Ruby       {}", version.trim());
         }
     } else {
-        println!("Ruby       not found");
+        let _ = writeln!(out, "Ruby       not found");
     }
-    println!();
+    out.push('\n');
 
     // RubyGems version
     if let Ok(output) = Command::new("gem").arg("--version").output() {
         if output.status.success()
             && let Ok(version) = String::from_utf8(output.stdout)
         {
-            println!("RubyGems   {}", version.trim());
+            let _ = writeln!(out, "RubyGems   {}", version.trim());
         }
     } else {
-        println!("RubyGems   not found");
+        let _ = writeln!(out, "RubyGems   not found");
     }
-    println!();
+    out.push('\n');
 
     // Bundler version (if available)
     if let Ok(output) = Command::new("bundle").arg("--version").output()
         && output.status.success()
         && let Ok(version) = String::from_utf8(output.stdout)
     {
-        println!("{}", version.trim());
+        let _ = writeln!(out, "{}", version.trim());
     }
-    println!();
+    out.push('\n');
 
     // Platform
-    println!("## Platform");
-    println!();
-    println!("OS         {}", env::consts::OS);
-    println!("Arch       {}", env::consts::ARCH);
-    println!("Family     {}", env::consts::FAMILY);
-    println!();
+    let _ = writeln!(out, "## Platform");
+    out.push('\n');
+    let _ = writeln!(out, "OS         {}", env::consts::OS);
+    let _ = writeln!(out, "Arch       {}", env::consts::ARCH);
+    let _ = writeln!(out, "Family     {}", env::consts::FAMILY);
+    out.push('\n');
 
     // Relevant environment variables
-    println!("## Environment Variables");
-    println!();
+    let _ = writeln!(out, "## Environment Variables");
+    out.push('\n');
 
     let env_vars = [
         "GEM_HOME",
@@ -74,9 +81,11 @@ pub(crate) fn run() {
 
     for var in &env_vars {
         if let Ok(value) = env::var(var) {
-            println!("{var:<20} {value}");
+            let _ = writeln!(out, "{var:<20} {value}");
         }
     }
+
+    out
 }
 
 #[cfg(test)]
@@ -5,15 +5,107 @@
 use anyhow::{Context, Result};
 use lode::{Config, config, lockfile::Lockfile};
 use std::env;
+use std::fmt::Write as _;
 use std::fs;
+use std::path::Path;
 use std::process::Command;
+use std::time::Instant;
+
+/// Directory under the bundle's gem root holding the exec setup script
+const SETUP_DIR: &str = ".lode";
+
+/// Feature name of the activation check, loaded with `RUBYOPT=-r...`
+const ACTIVATION_CHECK: &str = "lode_activation_check";
+
+/// Variables `--isolate` never passes through from the parent environment,
+/// as they can put gems outside the bundle on the load path
+const ISOLATED_VARS: [&str; 4] = ["GEM_HOME", "GEM_PATH", "RUBYOPT", "RUBYLIB"];
 
 /// Run a command with the lode-managed gem environment
-pub(crate) fn run(command: &[String], lockfile_path: &str) -> Result<()> {
+///
+/// With `only_groups`, only the gems those Gemfile groups need are put on
+/// the load path, read from the manifests `lode install` writes. With
+/// `cached_setup`, the load path comes from the activation manifest
+/// `lode install` writes instead of a scan of the gem directory.
+///
+/// With `isolate`, the child does not inherit [`ISOLATED_VARS`] or the
+/// variables listed in the `exec_unset_env` config, so only the bundle's
+/// gems are visible, as on a clean CI machine.
+pub(crate) fn run(
+    command: &[String],
+    lockfile_path: &str,
+    only_groups: &[String],
+    cached_setup: bool,
+    isolate: bool,
+) -> Result<()> {
     if command.is_empty() {
         anyhow::bail!("No command specified. Usage: lode exec -- <command> [args...]");
     }
 
+    let bundle_env = bundle_env(lockfile_path, only_groups, cached_setup, isolate)?;
+
+    // Prepare environment variables
+    let first_cmd = command.first().context("Command cannot be empty")?;
+    let mut cmd = Command::new(first_cmd);
+
+    // Add command arguments
+    if let Some(args) = command.get(1..) {
+        cmd.args(args);
+    }
+    if isolate {
+        for name in isolated_vars(&Config::load().unwrap_or_default()) {
+            lode::debug!("isolate: removing {name} from the environment");
+            cmd.env_remove(name);
+        }
+    }
+    let originals = lode::env_preserver::originals(&bundle_env, |name| env::var(name).ok());
+    cmd.envs(bundle_env);
+    cmd.envs(originals);
+
+    // Execute the command
+    let status = cmd
+        .status()
+        .with_context(|| format!("Failed to execute command: {first_cmd}"))?;
+
+    // Exit with the same code as the command
+    if !status.success() {
+        std::process::exit(lode::exit_code::child_exit_code(status));
+    }
+
+    Ok(())
+}
+
+/// Variables removed from the child's environment by `--isolate`
+fn isolated_vars(cfg: &Config) -> Vec<String> {
+    let mut names: Vec<String> = ISOLATED_VARS.iter().map(ToString::to_string).collect();
+    for name in &cfg.exec_unset_env {
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+    names
+}
+
+/// Environment variables that activate the bundle for a child process
+///
+/// Sets `GEM_HOME`/`GEM_PATH` to the vendor directory, `BUNDLE_GEMFILE`,
+/// `PATH` with the bundle's bin directory first, and `RUBYLIB` with every
+/// installed gem's lib directory (local git overrides first), or only the
+/// load paths listed for `only_groups` when it is not empty, or those of a
+/// fresh activation manifest with `cached_setup`. `RUBYOPT` loads an
+/// [`activation_check`] after any existing `-r` flags. With `isolate`, the
+/// existing `GEM_PATH`, `RUBYOPT` and `RUBYLIB` are not carried over;
+/// `disable_shared_gems` drops just the existing `GEM_PATH`.
+pub(crate) fn bundle_env(
+    lockfile_path: &str,
+    only_groups: &[String],
+    cached_setup: bool,
+    isolate: bool,
+) -> Result<Vec<(&'static str, String)>> {
+    let inherited = |name: &str| {
+        if isolate { None } else { env::var(name).ok() }
+    };
+
     // Read and parse lockfile to get Ruby version
     let content = fs::read_to_string(lockfile_path)
         .with_context(|| format!("Failed to read lockfile: {lockfile_path}"))?;
@@ -33,29 +125,24 @@ pub(crate) fn run(command: &[String], lockfile_path: &str) -> Result<()> {
     let gems_dir = gems_root.join("gems");
     let bin_dir = gems_root.join("bin");
 
-    // Prepare environment variables
-    let first_cmd = command.first().context("Command cannot be empty")?;
-    let mut cmd = Command::new(first_cmd);
-
-    // Add command arguments
-    if let Some(args) = command.get(1..) {
-        cmd.args(args);
-    }
+    let mut vars = Vec::new();
 
     // Set GEM_HOME to our vendor directory
-    cmd.env("GEM_HOME", &gems_root);
+    vars.push(("GEM_HOME", gems_root.display().to_string()));
 
-    // Set GEM_PATH to include our vendor directory
-    let gem_path = env::var("GEM_PATH").map_or_else(
-        |_| gems_root.display().to_string(),
+    // Set GEM_PATH to include our vendor directory, and only that when the
+    // bundle is isolated from user and system gems
+    let shared_gems = !config::shared_gems_disabled(Some(&cfg));
+    let gem_path = inherited("GEM_PATH").filter(|_| shared_gems).map_or_else(
+        || gems_root.display().to_string(),
         |existing_path| format!("{}:{existing_path}", gems_root.display()),
     );
-    cmd.env("GEM_PATH", gem_path);
+    vars.push(("GEM_PATH", gem_path));
 
     // Set BUNDLE_GEMFILE to absolute path (supports both Gemfile and gems.rb)
     let gemfile_path = env::current_dir()?.join(lode::paths::find_gemfile());
     if gemfile_path.exists() {
-        cmd.env("BUNDLE_GEMFILE", gemfile_path);
+        vars.push(("BUNDLE_GEMFILE", gemfile_path.display().to_string()));
     }
 
     // Prepend bin directory to PATH
@@ -64,15 +151,74 @@ pub(crate) fn run(command: &[String], lockfile_path: &str) -> Result<()> {
             |_| bin_dir.display().to_string(),
             |existing_path| format!("{}:{existing_path}", bin_dir.display()),
         );
-        cmd.env("PATH", path);
+        vars.push(("PATH", path));
     }
 
     // Set RUBYLIB to include gem lib directories (for require to work)
     let mut ruby_lib_paths = Vec::new();
-    if gems_dir.exists() {
+
+    // Local git overrides load straight from the checkout, ahead of installed copies
+    let local_overrides = config::local_overrides(Some(&cfg));
+    let check_branch = !config::local_branch_check_disabled(Some(&cfg));
+    let mut overridden = Vec::new();
+    for git_gem in &lockfile.git_gems {
+        if let Some(local_path) = local_overrides.get(&git_gem.name) {
+            lode::git::verify_local_override(
+                &git_gem.name,
+                local_path,
+                git_gem.branch.as_deref(),
+                check_branch,
+            )?;
+            ruby_lib_paths.push(local_path.join("lib").display().to_string());
+            overridden.push(format!("{}-{}", git_gem.name, git_gem.version));
+        }
+    }
+
+    let started = Instant::now();
+    let cached = if cached_setup && only_groups.is_empty() {
+        cached_load_paths(lockfile_path, &content)
+    } else {
+        None
+    };
+
+    if !only_groups.is_empty() {
+        let manifest_dir = Path::new(lockfile_path)
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(lode::group_manifest::MANIFEST_DIR);
+        let paths = lode::group_manifest::read(&manifest_dir, only_groups)
+            .context("Run `lode install` to write group manifests")?;
+        ruby_lib_paths.extend(
+            paths
+                .iter()
+                .filter(|path| {
+                    !overridden
+                        .iter()
+                        .any(|name| path.starts_with(gems_dir.join(name)))
+                })
+                .map(|path| path.display().to_string()),
+        );
+    } else if let Some(paths) = &cached {
+        ruby_lib_paths.extend(
+            paths
+                .iter()
+                .filter(|path| {
+                    !overridden
+                        .iter()
+                        .any(|name| path.starts_with(gems_dir.join(name)))
+                })
+                .map(|path| path.display().to_string()),
+        );
+    } else if gems_dir.exists() {
         // Add all gem lib directories to RUBYLIB
         if let Ok(entries) = fs::read_dir(&gems_dir) {
             for entry in entries.flatten() {
+                if overridden
+                    .iter()
+                    .any(|name| entry.file_name().to_str() == Some(name.as_str()))
+                {
+                    continue;
+                }
                 let gem_lib = entry.path().join("lib");
                 if gem_lib.is_dir() {
                     ruby_lib_paths.push(gem_lib.display().to_string());
@@ -81,27 +227,132 @@ pub(crate) fn run(command: &[String], lockfile_path: &str) -> Result<()> {
         }
     }
 
+    let source = if !only_groups.is_empty() {
+        "group manifests"
+    } else if cached.is_some() {
+        "activation manifest"
+    } else {
+        "gem directory scan"
+    };
+    lode::debug!("load path setup took {:?} ({source})", started.elapsed());
+
+    // Stop Ruby early if a locked gem is already active at another version
+    let setup_dir = gems_root.join(SETUP_DIR);
+    if write_activation_check(&setup_dir, &lockfile).is_ok() {
+        ruby_lib_paths.insert(0, setup_dir.display().to_string());
+        let require = format!("-r{ACTIVATION_CHECK}");
+        let rubyopt = inherited("RUBYOPT").map_or_else(
+            || require.clone(),
+            |existing| format!("{existing} {require}"),
+        );
+        vars.push(("RUBYOPT", rubyopt));
+    }
+
     if !ruby_lib_paths.is_empty() {
         let joined = ruby_lib_paths.join(":");
-        let rubylib = env::var("RUBYLIB").map_or_else(
-            |_| joined.clone(),
+        let rubylib = inherited("RUBYLIB").map_or_else(
+            || joined.clone(),
             |existing_lib| format!("{joined}:{existing_lib}"),
         );
-        cmd.env("RUBYLIB", rubylib);
+        vars.push(("RUBYLIB", rubylib));
     }
 
-    // Execute the command
-    let status = cmd
-        .status()
-        .with_context(|| format!("Failed to execute command: {first_cmd}"))?;
+    Ok(vars)
+}
 
-    // Exit with the same code as the command
-    if !status.success() {
-        let code = status.code().unwrap_or(1);
-        std::process::exit(code);
+/// Load paths from the activation manifest next to `lockfile_path`, if it
+/// was written for this lockfile `content`
+///
+/// Warns and returns `None` when the manifest is missing or stale, so the
+/// caller can fall back to scanning the gem directory.
+fn cached_load_paths(lockfile_path: &str, content: &str) -> Option<Vec<std::path::PathBuf>> {
+    let manifest = Path::new(lockfile_path)
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(lode::activation::MANIFEST_FILE);
+    match lode::activation::read(&manifest, &lode::activation::lockfile_digest(content)) {
+        Ok(Some(paths)) => Some(paths),
+        Ok(None) => {
+            lode::warn!(
+                "{} is out of date with the lockfile; run `lode install` to refresh it",
+                manifest.display()
+            );
+            None
+        }
+        Err(e) => {
+            lode::warn!(
+                "could not read {} ({e}); run `lode install` to write it",
+                manifest.display()
+            );
+            None
+        }
     }
+}
 
-    Ok(())
+/// Write the activation check for `lockfile` into `dir`
+fn write_activation_check(dir: &Path, lockfile: &Lockfile) -> Result<()> {
+    let locked = lockfile
+        .gems
+        .iter()
+        .map(|gem| (gem.name.as_str(), gem.version.as_str()))
+        .chain(
+            lockfile
+                .git_gems
+                .iter()
+                .map(|gem| (gem.name.as_str(), gem.version.as_str())),
+        )
+        .chain(
+            lockfile
+                .path_gems
+                .iter()
+                .map(|gem| (gem.name.as_str(), gem.version.as_str())),
+        );
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join(format!("{ACTIVATION_CHECK}.rb"));
+    fs::write(&path, activation_check(locked))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Ruby preamble that aborts when a locked gem is already activated at a
+/// different version, as Bundler does
+///
+/// `RUBYOPT` flags, `-r` options and default gems Ruby loads at boot
+/// (like `did_you_mean`) can activate a gem before the bundle is set up.
+/// Ruby would then silently run with the wrong version.
+fn activation_check<'a>(locked: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut script = String::from(
+        "# Generated by `lode exec`; checks that no gem is already activated\n\
+         # at a version other than the one in Gemfile.lock.\n\
+         locked = {\n",
+    );
+    for (name, version) in locked {
+        let _ = writeln!(script, "  {name:?} => {version:?},");
+    }
+    script.push_str(
+        r##"}.freeze
+
+conflicts = Gem.loaded_specs.values.select do |spec|
+  locked.key?(spec.name) && spec.version.to_s != locked[spec.name]
+end
+
+unless conflicts.empty?
+  conflicts.each do |spec|
+    wanted = locked[spec.name]
+    warn "lode: You have already activated #{spec.name} #{spec.version}, " \
+         "but your Gemfile requires #{spec.name} #{wanted}."
+    if spec.respond_to?(:default_gem?) && spec.default_gem?
+      warn "  #{spec.name} is a default gem loaded by Ruby itself. Allow " \
+           "#{spec.version} in the Gemfile and run `lode update #{spec.name}`."
+    else
+      warn "  Remove whatever loads it early (check RUBYOPT for -r flags), or " \
+           "allow #{spec.version} in the Gemfile and run `lode update #{spec.name}`."
+    end
+  end
+  exit 1
+end
+"##,
+    );
+    script
 }
 
 #[cfg(test)]
@@ -111,14 +362,63 @@ mod tests {
 
     #[test]
     fn exec_empty_command() {
-        let result = run(&[], "Gemfile.lock");
+        let result = run(&[], "Gemfile.lock", &[], false, false);
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("No command"));
     }
 
     #[test]
     fn exec_nonexistent_lockfile() {
-        let result = run(&["echo".to_string()], "/nonexistent/Gemfile.lock");
+        let result = run(
+            &["echo".to_string()],
+            "/nonexistent/Gemfile.lock",
+            &[],
+            false,
+            false,
+        );
         assert!(result.is_err());
     }
+
+    #[test]
+    fn isolation_adds_configured_vars() {
+        let cfg = Config {
+            exec_unset_env: vec!["RUBY_GC_HEAP_INIT_SLOTS".to_string(), "RUBYLIB".to_string()],
+            ..Config::default()
+        };
+        assert_eq!(
+            isolated_vars(&cfg),
+            [
+                "GEM_HOME",
+                "GEM_PATH",
+                "RUBYOPT",
+                "RUBYLIB",
+                "RUBY_GC_HEAP_INIT_SLOTS"
+            ]
+        );
+    }
+
+    #[test]
+    fn activation_check_lists_locked_versions() {
+        let script = activation_check([("rack", "3.1.7"), ("did_you_mean", "1.6.3")]);
+        assert!(script.contains("  \"rack\" => \"3.1.7\",\n"));
+        assert!(script.contains("  \"did_you_mean\" => \"1.6.3\",\n"));
+        assert!(script.contains("You have already activated"));
+    }
+
+    #[test]
+    fn activation_check_aborts_on_conflicting_version() {
+        if Command::new("ruby").arg("--version").output().is_err() {
+            return;
+        }
+        let temp = tempfile::TempDir::new().unwrap();
+        let script = temp.path().join("check.rb");
+        fs::write(&script, activation_check([("did_you_mean", "0.0.1")])).unwrap();
+
+        let output = Command::new("ruby").arg(&script).output().unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("already activated") {
+            assert!(!output.status.success());
+            assert!(stderr.contains("your Gemfile requires did_you_mean 0.0.1"));
+        }
+    }
 }
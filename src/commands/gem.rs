@@ -85,7 +85,7 @@ pub(crate) fn run(
         .args(["init", gem_dir.to_str().unwrap_or(gem_name)])
         .output()
     {
-        eprintln!("Warning: Failed to initialize git repository: {e}");
+        lode::warn!("Failed to initialize git repository: {e}");
     }
 
     println!("      create  {gem_name}/Gemfile");
@@ -1,26 +1,64 @@
 //! Build command
 //!
 //! Build a gem from a gemspec
+//!
+//! Builds are reproducible: `SOURCE_DATE_EPOCH` (or the time of the last git
+//! commit) fixes every timestamp, and the built `.gem` is rewritten with
+//! sorted entries and normalized owners and permissions, so the same source
+//! produces the same bytes on every machine.
 
 use anyhow::{Context, Result};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use lode::Gemspec;
+use sha2::{Digest, Sha256, Sha512};
 use std::fs;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Environment variable `RubyGems` reads build timestamps from
+const SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
+
+/// Gemspec written into the staging directory of a native build
+const NATIVE_GEMSPEC: &str = "native.gemspec";
+
+/// Evaluated source gemspec the native gemspec is derived from
+const SOURCE_SPEC_YAML: &str = "source.gemspec.yml";
+
+/// File list of the native gem, one path per line
+const NATIVE_FILES: &str = "native.files";
+
+/// File types of compiled extensions
+const BINARY_EXTENSIONS: [&str; 3] = ["so", "bundle", "dll"];
+
+/// Options for `lode gem-build`
+#[derive(Debug, Default)]
+pub(crate) struct BuildOptions<'a> {
+    /// Gemspec to build (found in `directory` when omitted)
+    pub gemspec: Option<&'a str>,
+    /// Target platform; anything but `ruby` builds a native gem
+    pub platform: Option<&'a str>,
+    pub force: bool,
+    pub strict: bool,
+    /// File name for the built gem
+    pub output: Option<&'a str>,
+    pub directory: Option<&'a str>,
+    /// Directory of precompiled binaries laid out like the gem (`lib/foo/3.3/foo.so`)
+    pub prebuilt: Option<&'a str>,
+    /// `rbconfig.rb` of the target platform, for cross-compiling extensions
+    pub target_rbconfig: Option<&'a str>,
+}
+
 /// Build a gem from a gemspec file with full flag support.
-pub(crate) fn run_with_options(
-    gemspec: Option<&str>,
-    platform: Option<&str>,
-    force: bool,
-    strict: bool,
-    output: Option<&str>,
-    directory: Option<&str>,
-) -> Result<()> {
+pub(crate) fn run_with_options(options: &BuildOptions<'_>) -> Result<()> {
     // Determine working directory
-    let work_dir = directory.map_or_else(|| PathBuf::from("."), PathBuf::from);
+    let work_dir = options
+        .directory
+        .map_or_else(|| PathBuf::from("."), PathBuf::from);
 
     // Find gemspec file
-    let gemspec_path = if let Some(path) = gemspec {
+    let gemspec_path = if let Some(path) = options.gemspec {
         PathBuf::from(path)
     } else {
         find_gemspec(&work_dir)?
@@ -35,34 +73,75 @@ pub(crate) fn run_with_options(
         .and_then(|n| n.to_str())
         .context("Invalid gemspec filename")?;
 
+    let native_platform = options
+        .platform
+        .filter(|platform| !platform.is_empty() && *platform != "ruby");
+    let spec = if options.strict || native_platform.is_some() {
+        Some(evaluate_gemspec(&gemspec_path, &work_dir)?)
+    } else {
+        None
+    };
+
+    if options.strict
+        && let Some((spec, _)) = &spec
+    {
+        let problems = validate_files(spec, &work_dir);
+        if !problems.is_empty() {
+            anyhow::bail!(
+                "Gemspec files failed strict validation:\n  {}",
+                problems.join("\n  ")
+            );
+        }
+    }
+
+    let epoch = source_date_epoch(&work_dir);
+
+    // Native gems are built from a staging copy of the sources plus binaries
+    let staging = match (native_platform, &spec) {
+        (Some(platform), Some((spec, yaml))) => {
+            Some(stage_native_gem(spec, yaml, platform, &work_dir, options)?)
+        }
+        _ => None,
+    };
+
     println!("  Successfully built RubyGem");
     println!("  Name: {gemspec_filename}");
 
     // Build the gem build command
     let mut cmd = Command::new("gem");
-    cmd.arg("build").arg(&gemspec_path);
+    if let Some(staging) = &staging {
+        cmd.arg("build")
+            .arg(NATIVE_GEMSPEC)
+            .current_dir(staging.path());
+    } else {
+        cmd.arg("build").arg(&gemspec_path);
+
+        // Add platform flag
+        if let Some(plat) = options.platform {
+            cmd.arg("--platform").arg(plat);
+        }
+
+        // Add output flag
+        if let Some(out) = options.output {
+            cmd.arg("--output").arg(out);
+        }
 
-    // Add platform flag
-    if let Some(plat) = platform {
-        cmd.arg("--platform").arg(plat);
+        // Set working directory if specified
+        if let Some(dir) = options.directory {
+            cmd.current_dir(dir);
+        }
     }
 
     // Add validation flags
-    if force {
+    if options.force {
         cmd.arg("--force");
     }
-    if strict {
+    if options.strict {
         cmd.arg("--strict");
     }
 
-    // Add output flag
-    if let Some(out) = output {
-        cmd.arg("--output").arg(out);
-    }
-
-    // Set working directory if specified
-    if let Some(dir) = directory {
-        cmd.current_dir(dir);
+    if let Some(epoch) = epoch {
+        cmd.env(SOURCE_DATE_EPOCH, epoch.to_string());
     }
 
     // Execute the command
@@ -82,9 +161,432 @@ pub(crate) fn run_with_options(
         print!("{stdout}");
     }
 
+    let built = if let Some(staging) = &staging {
+        let file = built_gem_file(&stdout).context("gem build did not report the gem it built")?;
+        let destination = work_dir.join(options.output.unwrap_or(&file));
+        fs::copy(staging.path().join(&file), &destination)
+            .with_context(|| format!("Failed to write {}", destination.display()))?;
+        Some(destination)
+    } else {
+        options
+            .output
+            .map(str::to_string)
+            .or_else(|| built_gem_file(&stdout))
+            .map(|file| work_dir.join(file))
+    };
+    if let Some(gem_path) = built {
+        normalize_gem(&gem_path, epoch)
+            .with_context(|| format!("Failed to normalize {}", gem_path.display()))?;
+    }
+
     Ok(())
 }
 
+/// `SOURCE_DATE_EPOCH` from the environment, else the last git commit time
+fn source_date_epoch(work_dir: &Path) -> Option<u64> {
+    if let Ok(value) = std::env::var(SOURCE_DATE_EPOCH) {
+        return value.trim().parse().ok();
+    }
+
+    let repo = git2::Repository::discover(work_dir).ok()?;
+    let commit = repo.head().ok()?.peel_to_commit().ok()?;
+    u64::try_from(commit.time().seconds()).ok()
+}
+
+/// The `File:` line `gem build` prints for the gem it wrote
+fn built_gem_file(stdout: &str) -> Option<String> {
+    stdout
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("File:"))
+        .map(|file| file.trim().to_string())
+}
+
+/// Prepare a staging directory for a platform gem
+///
+/// Mirrors rake-compiler's native gems: extension sources are left out, the
+/// binaries from `--prebuilt` (or compiled here for the target) are added,
+/// and the gemspec gets the target platform and no `extensions`.
+fn stage_native_gem(
+    spec: &Gemspec,
+    yaml: &str,
+    platform: &str,
+    work_dir: &Path,
+    options: &BuildOptions<'_>,
+) -> Result<tempfile::TempDir> {
+    if !platform
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    {
+        anyhow::bail!("Invalid platform: {platform}");
+    }
+
+    let staging = tempfile::TempDir::new().context("Failed to create staging directory")?;
+    copy_files(work_dir, staging.path(), &spec.files)?;
+
+    let binaries = if let Some(prebuilt) = options.prebuilt {
+        let prebuilt = Path::new(prebuilt);
+        let binaries = relative_files(prebuilt).with_context(|| {
+            format!("Failed to read prebuilt binaries in {}", prebuilt.display())
+        })?;
+        copy_files(prebuilt, staging.path(), &binaries)?;
+        binaries
+    } else {
+        compile_extensions(spec, platform, staging.path(), options.target_rbconfig)?
+    };
+    if binaries.is_empty() {
+        anyhow::bail!("No prebuilt binaries found for {platform}");
+    }
+
+    let ext_dirs = extension_dirs(&spec.extensions);
+    let mut files: Vec<String> = spec
+        .files
+        .iter()
+        .filter(|file| !is_extension_source(file, &ext_dirs))
+        .chain(&binaries)
+        .cloned()
+        .collect();
+    files.sort();
+    files.dedup();
+
+    fs::write(staging.path().join(SOURCE_SPEC_YAML), yaml)?;
+    fs::write(staging.path().join(NATIVE_FILES), files.join("\n"))?;
+    fs::write(
+        staging.path().join(NATIVE_GEMSPEC),
+        native_gemspec(platform),
+    )?;
+
+    Ok(staging)
+}
+
+/// Compile the gem's extension in `staging` for `platform`
+///
+/// Building for another platform needs the target's `rbconfig.rb`.
+/// Returns the compiled binaries relative to `staging`.
+fn compile_extensions(
+    spec: &Gemspec,
+    platform: &str,
+    staging: &Path,
+    target_rbconfig: Option<&str>,
+) -> Result<Vec<String>> {
+    let current = lode::platform::detect_current_platform();
+    if target_rbconfig.is_none() && platform != current {
+        anyhow::bail!(
+            "Cannot compile extensions for {platform} on {current}; \
+             pass --prebuilt with precompiled binaries or --target-rbconfig to cross-compile"
+        );
+    }
+
+    let is_binary = |file: &String| {
+        Path::new(file)
+            .extension()
+            .is_some_and(|ext| BINARY_EXTENSIONS.iter().any(|binary| ext == *binary))
+    };
+    let before: Vec<String> = relative_files(staging)?
+        .into_iter()
+        .filter(is_binary)
+        .collect();
+
+    let mut builder =
+        lode::ExtensionBuilder::new(false, false, target_rbconfig.map(str::to_string));
+    let result = builder
+        .build_if_needed(&spec.name, staging, None)
+        .with_context(|| format!("{} has no extensions to compile", spec.name))?;
+    if !result.success {
+        anyhow::bail!(
+            "Failed to compile extensions: {}\n{}",
+            result.error.unwrap_or_default(),
+            result.output
+        );
+    }
+
+    Ok(relative_files(staging)?
+        .into_iter()
+        .filter(|file| is_binary(file) && !before.contains(file))
+        .collect())
+}
+
+/// Directories holding extension sources (`ext/foo` for `ext/foo/extconf.rb`)
+fn extension_dirs(extensions: &[String]) -> Vec<PathBuf> {
+    extensions
+        .iter()
+        .map(|extension| {
+            Path::new(extension)
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map_or_else(|| PathBuf::from(extension), Path::to_path_buf)
+        })
+        .collect()
+}
+
+fn is_extension_source(file: &str, ext_dirs: &[PathBuf]) -> bool {
+    ext_dirs.iter().any(|dir| Path::new(file).starts_with(dir))
+}
+
+/// Ruby gemspec that turns the evaluated source spec into a platform gem
+fn native_gemspec(platform: &str) -> String {
+    format!(
+        "# Generated by lode gem-build for the {platform} platform\n\
+         spec = Gem::Specification.from_yaml(File.read(File.expand_path('{SOURCE_SPEC_YAML}', __dir__)))\n\
+         spec.platform = Gem::Platform.new('{platform}')\n\
+         spec.extensions = []\n\
+         spec.files = File.readlines(File.expand_path('{NATIVE_FILES}', __dir__), chomp: true)\n\
+         spec\n"
+    )
+}
+
+/// Copy `files` (relative paths) from `from` to `to`, skipping missing ones
+fn copy_files(from: &Path, to: &Path, files: &[String]) -> Result<()> {
+    for file in files {
+        let source = from.join(file);
+        if !source.is_file() {
+            continue;
+        }
+        let destination = to.join(file);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&source, &destination)
+            .with_context(|| format!("Failed to copy {}", source.display()))?;
+    }
+    Ok(())
+}
+
+/// Every file under `dir`, as sorted `/`-separated relative paths
+fn relative_files(dir: &Path) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if let Ok(relative) = path.strip_prefix(dir) {
+                let parts: Vec<_> = relative
+                    .components()
+                    .map(|part| part.as_os_str().to_string_lossy())
+                    .collect();
+                files.push(parts.join("/"));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Evaluate a Ruby gemspec in `work_dir`, returning it parsed and as YAML
+fn evaluate_gemspec(gemspec_path: &Path, work_dir: &Path) -> Result<(Gemspec, String)> {
+    let gemspec_path = fs::canonicalize(gemspec_path)
+        .with_context(|| format!("Failed to resolve {}", gemspec_path.display()))?;
+    let output = Command::new("ruby")
+        .args(["-e", "print Gem::Specification.load(ARGV[0]).to_yaml"])
+        .arg(&gemspec_path)
+        .current_dir(work_dir)
+        .output()
+        .context("Failed to run ruby to evaluate the gemspec")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to evaluate {}:\n{}",
+            gemspec_path.display(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let yaml = String::from_utf8_lossy(&output.stdout).into_owned();
+    let spec = Gemspec::parse(&yaml)
+        .with_context(|| format!("Failed to parse {}", gemspec_path.display()))?;
+    Ok((spec, yaml))
+}
+
+/// Problems with the gemspec's `files` list: entries that do not exist, and
+/// entries git does not track when the gem lives in a git repository
+fn validate_files(spec: &Gemspec, work_dir: &Path) -> Vec<String> {
+    let repo = git2::Repository::discover(work_dir).ok();
+    let repo_root = repo
+        .as_ref()
+        .and_then(git2::Repository::workdir)
+        .and_then(|root| fs::canonicalize(root).ok());
+    let work_dir = fs::canonicalize(work_dir).unwrap_or_else(|_| work_dir.to_path_buf());
+
+    file_problems(&work_dir, &spec.files, |file| {
+        let (Some(repo), Some(root)) = (&repo, &repo_root) else {
+            return true;
+        };
+        work_dir
+            .join(file)
+            .strip_prefix(root)
+            .ok()
+            .and_then(|relative| repo.status_file(relative).ok())
+            .is_some_and(|status| !status.intersects(git2::Status::WT_NEW | git2::Status::IGNORED))
+    })
+}
+
+fn file_problems(
+    work_dir: &Path,
+    files: &[String],
+    is_tracked: impl Fn(&str) -> bool,
+) -> Vec<String> {
+    files
+        .iter()
+        .filter_map(|file| {
+            if !work_dir.join(file).exists() {
+                Some(format!(
+                    "{file} is listed in the gemspec but does not exist"
+                ))
+            } else if !is_tracked(file) {
+                Some(format!("{file} is not tracked by git"))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Rewrite a built gem so its bytes depend only on its contents
+///
+/// Entries of `data.tar.gz` are sorted by path, owned by root, mode 0644 or
+/// 0755, and stamped with `epoch`; every gzip stream gets the same fixed
+/// header and `checksums.yaml.gz` is regenerated. Signed gems are left as
+/// built, since rewriting them would invalidate their signatures.
+fn normalize_gem(gem_path: &Path, epoch: Option<u64>) -> Result<()> {
+    let mut entries: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut archive = tar::Archive::new(fs::File::open(gem_path)?);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        entries.push((name, bytes));
+    }
+
+    if entries
+        .iter()
+        .any(|(name, _)| Path::new(name).extension().is_some_and(|ext| ext == "sig"))
+    {
+        println!("  Signed gem left as built; reproducible normalization skipped");
+        return Ok(());
+    }
+
+    let mtime = epoch.unwrap_or(0);
+    let mut normalized: Vec<(&str, Vec<u8>)> = Vec::new();
+    for (name, bytes) in &entries {
+        match name.as_str() {
+            "metadata.gz" => normalized.push(("metadata.gz", regzip(&gunzip(bytes)?, mtime)?)),
+            "data.tar.gz" => {
+                normalized.push((
+                    "data.tar.gz",
+                    normalize_data(&gunzip(bytes)?, epoch, mtime)?,
+                ));
+            }
+            _ => {}
+        }
+    }
+    normalized.push((
+        "checksums.yaml.gz",
+        regzip(&checksums_yaml(&normalized), mtime)?,
+    ));
+
+    let temp_path = gem_path.with_extension("gem.tmp");
+    let mut gem = tar::Builder::new(fs::File::create(&temp_path)?);
+    for (name, bytes) in &normalized {
+        let mut header = entry_header(bytes.len() as u64, 0o444, mtime);
+        gem.append_data(&mut header, name, bytes.as_slice())?;
+    }
+    gem.into_inner()?.sync_all()?;
+    fs::rename(&temp_path, gem_path)?;
+
+    Ok(())
+}
+
+/// Sort and normalize the entries of an uncompressed `data.tar`
+fn normalize_data(tar_bytes: &[u8], epoch: Option<u64>, mtime: u64) -> Result<Vec<u8>> {
+    struct Entry {
+        path: String,
+        header: tar::Header,
+        link: Option<PathBuf>,
+        bytes: Vec<u8>,
+    }
+
+    let mut entries = Vec::new();
+    let mut archive = tar::Archive::new(tar_bytes);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let link = entry.link_name()?.map(std::borrow::Cow::into_owned);
+        let header = entry.header().clone();
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        entries.push(Entry {
+            path,
+            header,
+            link,
+            bytes,
+        });
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut data = tar::Builder::new(Vec::new());
+    for entry in entries {
+        let executable = entry.header.mode().is_ok_and(|mode| mode & 0o111 != 0);
+        let mode = if executable { 0o755 } else { 0o644 };
+        // Without an epoch the timestamps gem build chose are all we have
+        let entry_mtime = match epoch {
+            Some(_) => mtime,
+            None => entry.header.mtime().unwrap_or(0),
+        };
+        let mut header = entry_header(entry.bytes.len() as u64, mode, entry_mtime);
+        header.set_entry_type(entry.header.entry_type());
+        match entry.link {
+            Some(target) => {
+                header.set_size(0);
+                data.append_link(&mut header, &entry.path, target)?;
+            }
+            None => data.append_data(&mut header, &entry.path, entry.bytes.as_slice())?,
+        }
+    }
+
+    regzip(&data.into_inner()?, mtime)
+}
+
+fn entry_header(size: u64, mode: u32, mtime: u64) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(size);
+    header.set_mode(mode);
+    header.set_mtime(mtime);
+    header.set_uid(0);
+    header.set_gid(0);
+    header.set_cksum();
+    header
+}
+
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    GzDecoder::new(bytes).read_to_end(&mut out)?;
+    Ok(out)
+}
+
+/// Gzip with a fixed header: no file name and the given mtime
+fn regzip(bytes: &[u8], mtime: u64) -> Result<Vec<u8>> {
+    let mut encoder = flate2::GzBuilder::new()
+        .mtime(u32::try_from(mtime).unwrap_or(u32::MAX))
+        .write(Vec::new(), Compression::best());
+    encoder.write_all(bytes)?;
+    Ok(encoder.finish()?)
+}
+
+/// `checksums.yaml` in the layout `RubyGems` writes
+fn checksums_yaml(entries: &[(&str, Vec<u8>)]) -> Vec<u8> {
+    use std::fmt::Write as _;
+
+    let mut yaml = String::from("---\nSHA256:\n");
+    for (name, bytes) in entries {
+        writeln!(yaml, "  {name}: {:x}", Sha256::digest(bytes)).ok();
+    }
+    yaml.push_str("SHA512:\n");
+    for (name, bytes) in entries {
+        writeln!(yaml, "  {name}: {:x}", Sha512::digest(bytes)).ok();
+    }
+    yaml.into_bytes()
+}
+
 /// Find .gemspec file in a directory
 fn find_gemspec(dir: &Path) -> Result<std::path::PathBuf> {
     let entries = fs::read_dir(dir).context("Failed to read directory")?;
@@ -245,6 +747,158 @@ end
         assert!(result.is_err());
     }
 
+    #[test]
+    fn built_gem_file_from_gem_build_output() {
+        let stdout = "  Successfully built RubyGem\n  Name: demo\n  Version: 1.0.0\n  File: demo-1.0.0.gem\n";
+        assert_eq!(built_gem_file(stdout), Some("demo-1.0.0.gem".to_string()));
+        assert_eq!(built_gem_file("nothing here"), None);
+    }
+
+    #[test]
+    fn file_problems_reports_missing_and_untracked() {
+        let temp_dir = tempfile::TempDir::new().expect("create temp dir");
+        fs::create_dir_all(temp_dir.path().join("lib")).expect("create lib");
+        fs::write(temp_dir.path().join("lib/demo.rb"), "").expect("write lib");
+        fs::write(temp_dir.path().join("scratch.rb"), "").expect("write scratch");
+
+        let files = [
+            "lib/demo.rb".to_string(),
+            "scratch.rb".to_string(),
+            "README.md".to_string(),
+        ];
+        let problems = file_problems(temp_dir.path(), &files, |file| file != "scratch.rb");
+
+        assert_eq!(
+            problems,
+            vec![
+                "scratch.rb is not tracked by git".to_string(),
+                "README.md is listed in the gemspec but does not exist".to_string(),
+            ]
+        );
+    }
+
+    fn write_gem(path: &Path, files: &[(&str, u32, u64)]) {
+        let mut data = tar::Builder::new(Vec::new());
+        for (name, mode, mtime) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(4);
+            header.set_mode(*mode);
+            header.set_mtime(*mtime);
+            header.set_uid(1000);
+            header.set_cksum();
+            data.append_data(&mut header, name, b"puts".as_slice())
+                .expect("append file");
+        }
+        let data = regzip(
+            &data.into_inner().expect("finish data"),
+            files.iter().map(|(_, _, mtime)| *mtime).max().unwrap_or(0),
+        )
+        .expect("gzip data");
+        let metadata =
+            regzip(b"--- !ruby/object:Gem::Specification\nname: demo\n", 7).expect("gzip metadata");
+
+        let mut gem = tar::Builder::new(fs::File::create(path).expect("create gem"));
+        for (name, bytes) in [("metadata.gz", metadata), ("data.tar.gz", data)] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(bytes.len() as u64);
+            header.set_mode(0o600);
+            header.set_cksum();
+            gem.append_data(&mut header, name, bytes.as_slice())
+                .expect("append entry");
+        }
+        gem.finish().expect("finish gem");
+    }
+
+    #[test]
+    fn normalize_gem_is_independent_of_order_modes_and_times() {
+        let temp_dir = tempfile::TempDir::new().expect("create temp dir");
+        let first = temp_dir.path().join("first.gem");
+        let second = temp_dir.path().join("second.gem");
+        write_gem(
+            &first,
+            &[("lib/demo.rb", 0o664, 100), ("exe/demo", 0o775, 200)],
+        );
+        write_gem(
+            &second,
+            &[("exe/demo", 0o700, 300), ("lib/demo.rb", 0o600, 400)],
+        );
+
+        normalize_gem(&first, Some(1_700_000_000)).expect("normalize first");
+        normalize_gem(&second, Some(1_700_000_000)).expect("normalize second");
+
+        let first_bytes = fs::read(&first).expect("read first");
+        assert_eq!(first_bytes, fs::read(&second).expect("read second"));
+
+        let mut archive = tar::Archive::new(first_bytes.as_slice());
+        let names: Vec<String> = archive
+            .entries()
+            .expect("entries")
+            .map(|entry| {
+                entry
+                    .expect("entry")
+                    .path()
+                    .expect("path")
+                    .display()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(names, ["metadata.gz", "data.tar.gz", "checksums.yaml.gz"]);
+    }
+
+    #[test]
+    fn native_staging_swaps_extension_sources_for_binaries() {
+        let project = tempfile::TempDir::new().expect("create project dir");
+        for file in ["lib/demo.rb", "ext/demo/extconf.rb", "ext/demo/demo.c"] {
+            let path = project.path().join(file);
+            fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+            fs::write(path, "").expect("write file");
+        }
+        let prebuilt = tempfile::TempDir::new().expect("create prebuilt dir");
+        fs::create_dir_all(prebuilt.path().join("lib/demo/3.3")).expect("create binary dir");
+        fs::write(prebuilt.path().join("lib/demo/3.3/demo.so"), "ELF").expect("write binary");
+
+        let spec = Gemspec {
+            name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            extensions: vec!["ext/demo/extconf.rb".to_string()],
+            files: vec![
+                "ext/demo/demo.c".to_string(),
+                "ext/demo/extconf.rb".to_string(),
+                "lib/demo.rb".to_string(),
+            ],
+            ..Gemspec::default()
+        };
+        let prebuilt_dir = prebuilt.path().display().to_string();
+        let options = BuildOptions {
+            prebuilt: Some(&prebuilt_dir),
+            ..BuildOptions::default()
+        };
+
+        let staging = stage_native_gem(&spec, "--- {}\n", "x86_64-linux", project.path(), &options)
+            .expect("stage native gem");
+
+        assert_eq!(
+            fs::read_to_string(staging.path().join(NATIVE_FILES)).expect("read file list"),
+            "lib/demo.rb\nlib/demo/3.3/demo.so"
+        );
+        assert!(staging.path().join("lib/demo/3.3/demo.so").is_file());
+        let gemspec =
+            fs::read_to_string(staging.path().join(NATIVE_GEMSPEC)).expect("read gemspec");
+        assert!(gemspec.contains("spec.platform = Gem::Platform.new('x86_64-linux')"));
+        assert!(gemspec.contains("spec.extensions = []"));
+
+        assert!(
+            stage_native_gem(
+                &spec,
+                "",
+                "x86_64-linux'; system('id')",
+                project.path(),
+                &options
+            )
+            .is_err()
+        );
+    }
+
     #[test]
     fn test_build_workflow_basic_build() {
         let gemspec_path = "my-gem.gemspec";
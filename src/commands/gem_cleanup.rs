@@ -3,10 +3,13 @@
 //! Remove old gem versions
 
 use anyhow::{Context, Result};
+use lode::docs::DocLayout;
+use lode::gem_store::{Dependent, GemStore, ReverseDependencyIndex};
+use lode::gem_utils::requirement_satisfied;
 use lode::{Config, config, get_system_gem_dir, parse_gem_name};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Options for gem cleanup command
 #[derive(Debug, Default)]
@@ -110,6 +113,7 @@ pub(crate) fn run(options: &CleanupOptions) -> Result<()> {
         if !options.quiet {
             println!("No gems found to clean up");
         }
+        clean_orphaned_docs(&gem_dir, &[], options);
         return Ok(());
     }
 
@@ -119,36 +123,20 @@ pub(crate) fn run(options: &CleanupOptions) -> Result<()> {
         gem_groups.entry(gem.name.clone()).or_default().push(gem);
     }
 
-    // For each group, find old versions to remove
-    let mut gems_to_remove = Vec::new();
-    let mut gems_to_keep = Vec::new();
-
-    for (_name, mut gems) in gem_groups {
-        if gems.len() <= 1 {
-            // Only one version, keep it
-            gems_to_keep.extend(gems);
-            continue;
-        }
+    // Old versions that other installed gems still require are kept
+    let store = GemStore::with_path(gem_dir.clone());
+    let reverse_deps = store.reverse_dependencies().ok();
 
-        // Sort by version (newest first)
-        gems.sort_by(|a, b| version_compare(&b.version, &a.version));
-
-        // Keep the latest version
-        if let Some(latest) = gems.first().cloned() {
-            gems_to_keep.push(latest);
-        }
-
-        // Mark the rest for removal
-        for gem in gems.iter().skip(1) {
-            gems_to_remove.push(gem.clone());
-        }
-    }
+    // For each group, find old versions to remove
+    let (gems_to_remove, gems_to_keep) =
+        partition_old_versions(gem_groups, reverse_deps.as_ref(), options);
 
     if gems_to_remove.is_empty() {
         if !options.quiet {
             println!("No old gem versions to clean up");
             println!("   {} gem(s) installed", gems_to_keep.len());
         }
+        clean_orphaned_docs(&gem_dir, &[], options);
         return Ok(());
     }
 
@@ -183,6 +171,10 @@ pub(crate) fn run(options: &CleanupOptions) -> Result<()> {
             }
         }
 
+        if removed_count > 0 {
+            store.invalidate_reverse_dependencies();
+        }
+
         if !options.quiet {
             println!("Cleaned up {removed_count} gem version(s)");
             println!("   {} gem(s) remaining", gems_to_keep.len());
@@ -191,9 +183,143 @@ pub(crate) fn run(options: &CleanupOptions) -> Result<()> {
         println!("Dry run complete - no gems were deleted");
     }
 
+    clean_orphaned_docs(&gem_dir, &gems_to_remove, options);
+
     Ok(())
 }
 
+/// Remove documentation whose gem is no longer installed
+///
+/// A dry run also lists the documentation of versions it would remove.
+fn clean_orphaned_docs(gem_dir: &Path, removing: &[GemInfo], options: &CleanupOptions) {
+    let layout = DocLayout::for_gems_dir(gem_dir);
+    let mut orphans = layout.orphans(gem_dir);
+    if options.dry_run {
+        orphans.extend(
+            removing
+                .iter()
+                .filter_map(|gem| gem.path.file_name())
+                .map(|full_name| layout.dir(&full_name.to_string_lossy()))
+                .filter(|dir| dir.exists()),
+        );
+    }
+    orphans.retain(|dir| {
+        options.gems.is_empty()
+            || dir
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(parse_gem_name)
+                .is_some_and(|(name, _)| options.gems.iter().any(|gem| gem == name))
+    });
+    orphans.sort();
+    orphans.dedup();
+    if orphans.is_empty() {
+        return;
+    }
+
+    if !options.quiet {
+        println!(
+            "\n{} orphaned documentation director{}:",
+            orphans.len(),
+            if orphans.len() == 1 { "y" } else { "ies" }
+        );
+    }
+    let mut removed = 0;
+    for dir in &orphans {
+        if options.dry_run {
+            if !options.quiet {
+                println!("  {}", dir.display());
+            }
+            continue;
+        }
+        match fs::remove_dir_all(dir) {
+            Ok(()) => {
+                removed += 1;
+                if options.verbose {
+                    println!("  Removed: {}", dir.display());
+                }
+            }
+            Err(err) => eprintln!("  Failed to remove {}: {err}", dir.display()),
+        }
+    }
+    if !options.dry_run && !options.quiet {
+        println!(
+            "Removed {removed} orphaned documentation director{}",
+            if removed == 1 { "y" } else { "ies" }
+        );
+    }
+}
+
+/// Split each gem's versions into (remove, keep): the newest version is kept,
+/// as is any older version that an installed gem still requires
+fn partition_old_versions(
+    gem_groups: HashMap<String, Vec<GemInfo>>,
+    reverse_deps: Option<&ReverseDependencyIndex>,
+    options: &CleanupOptions,
+) -> (Vec<GemInfo>, Vec<GemInfo>) {
+    let mut gems_to_remove = Vec::new();
+    let mut gems_to_keep = Vec::new();
+
+    for (_name, mut gems) in gem_groups {
+        if gems.len() <= 1 {
+            // Only one version, keep it
+            gems_to_keep.extend(gems);
+            continue;
+        }
+
+        // Sort by version (newest first)
+        gems.sort_by(|a, b| version_compare(&b.version, &a.version));
+
+        // Keep the latest version
+        let Some(latest) = gems.first().cloned() else {
+            continue;
+        };
+
+        // Mark the rest for removal unless a dependent needs that exact range
+        for gem in gems.iter().skip(1) {
+            let required_by = reverse_deps.and_then(|index| {
+                dependent_needing(index, gem, &latest, options.check_development)
+            });
+
+            if let Some(dependent) = required_by {
+                if options.verbose {
+                    println!(
+                        "Keeping {} ({}): required by {}-{} ({})",
+                        gem.name,
+                        gem.version,
+                        dependent.name,
+                        dependent.version,
+                        dependent.requirement
+                    );
+                }
+                gems_to_keep.push(gem.clone());
+            } else {
+                gems_to_remove.push(gem.clone());
+            }
+        }
+        gems_to_keep.push(latest);
+    }
+
+    (gems_to_remove, gems_to_keep)
+}
+
+/// First installed gem whose requirement is met by `gem` but not by `latest`
+fn dependent_needing<'a>(
+    index: &'a ReverseDependencyIndex,
+    gem: &GemInfo,
+    latest: &GemInfo,
+    include_development: bool,
+) -> Option<&'a Dependent> {
+    index
+        .dependents_of(&gem.name)
+        .iter()
+        .filter(|d| include_development || !d.development)
+        .find(|d| {
+            requirement_satisfied(&gem.version, &d.requirement)
+                && !requirement_satisfied(&latest.version, &d.requirement)
+        })
+}
+
 /// Compare two version strings
 fn version_compare(a: &str, b: &str) -> std::cmp::Ordering {
     use std::cmp::Ordering;
@@ -407,4 +533,23 @@ mod tests {
         assert!(opts.verbose);
         assert!(opts.check_development);
     }
+
+    #[test]
+    fn clean_orphaned_docs_removes_docs_without_gems() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let gems = temp.path().join("gems");
+        fs::create_dir_all(gems.join("rack-3.1.7")).unwrap();
+        let layout = DocLayout::for_gems_dir(&gems);
+        for full_name in ["rack-3.1.7", "rack-2.2.9", "rake-13.2.1"] {
+            fs::create_dir_all(layout.ri_dir(full_name)).unwrap();
+        }
+
+        let mut opts = minimal_cleanup_options();
+        opts.quiet = true;
+        opts.gems = vec!["rack".to_string()];
+        clean_orphaned_docs(&gems, &[], &opts);
+        assert!(layout.dir("rack-3.1.7").exists());
+        assert!(!layout.dir("rack-2.2.9").exists());
+        assert!(layout.dir("rake-13.2.1").exists());
+    }
 }
@@ -3,7 +3,7 @@
 //! List all files in an installed gem
 
 use anyhow::{Context, Result};
-use lode::gem_store::GemStore;
+use lode::gem_store::{GemPath, GemQuery, GemStore};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -17,6 +17,7 @@ pub(crate) struct ContentsOptions {
     pub lib_only: bool,
     pub prefix: bool,
     pub show_install_dir: bool,
+    pub diff: bool,
     pub verbose: bool,
     pub quiet: bool,
     pub silent: bool,
@@ -49,23 +50,22 @@ pub(crate) fn run(opts: &ContentsOptions) -> Result<()> {
         return list_all_gems_from_stores(&stores, opts);
     }
 
-    // Find matching gems across all stores
-    let mut matching_gems = Vec::new();
-    for store in &stores {
-        if let Ok(gems) = store.find_gem_by_name(&opts.gem_name) {
-            matching_gems.extend(gems);
-        }
-    }
+    // Find matching gems across all stores, earlier stores taking precedence
+    let gem_path = GemPath::new(stores);
+    let matching_gems = gem_path
+        .query(&GemQuery::new().with_name(&opts.gem_name))
+        .unwrap_or_default();
 
     if matching_gems.is_empty() {
         anyhow::bail!("Gem '{}' not found", opts.gem_name);
     }
 
-    // If version specified, find that specific version
+    // If version specified, find the newest version satisfying it
     let gem = if let Some(ref v) = opts.version {
         matching_gems
             .iter()
-            .find(|g| g.version == *v)
+            .filter(|g| lode::gem_utils::requirement_satisfied(&g.version, v))
+            .max_by(|a, b| lode::gem_utils::compare_versions(&a.version, &b.version))
             .with_context(|| format!("Version '{v}' of gem '{}' not found", opts.gem_name))?
     } else {
         // Use the latest version (last in sorted list)
@@ -78,6 +78,10 @@ pub(crate) fn run(opts: &ContentsOptions) -> Result<()> {
         return Ok(());
     }
 
+    if opts.diff {
+        return super::contents::print_contents_diff(&gem.name, &gem.path, &[]);
+    }
+
     // List all files recursively
     let mut files = list_files_recursive(&gem.path)?;
 
@@ -247,6 +251,7 @@ mod tests {
             lib_only: false,
             prefix: false,
             show_install_dir: false,
+            diff: false,
             verbose: false,
             quiet: false,
             silent: false,
@@ -271,6 +276,7 @@ mod tests {
             lib_only: false,
             prefix: false,
             show_install_dir: false,
+            diff: false,
             verbose: false,
             quiet: false,
             silent: false,
@@ -289,6 +295,7 @@ mod tests {
             lib_only: false,
             prefix: false,
             show_install_dir: false,
+            diff: false,
             verbose: false,
             quiet: false,
             silent: false,
@@ -307,6 +314,7 @@ mod tests {
             lib_only: false,
             prefix: false,
             show_install_dir: false,
+            diff: false,
             verbose: false,
             quiet: false,
             silent: false,
@@ -326,6 +334,7 @@ mod tests {
             lib_only: false,
             prefix: false,
             show_install_dir: false,
+            diff: false,
             verbose: false,
             quiet: false,
             silent: false,
@@ -345,6 +354,7 @@ mod tests {
             lib_only: true,
             prefix: false,
             show_install_dir: false,
+            diff: false,
             verbose: false,
             quiet: false,
             silent: false,
@@ -363,6 +373,7 @@ mod tests {
             lib_only: false,
             prefix: true,
             show_install_dir: false,
+            diff: false,
             verbose: false,
             quiet: false,
             silent: false,
@@ -381,6 +392,7 @@ mod tests {
             lib_only: false,
             prefix: false,
             show_install_dir: true,
+            diff: false,
             verbose: false,
             quiet: false,
             silent: false,
@@ -399,6 +411,7 @@ mod tests {
             lib_only: false,
             prefix: false,
             show_install_dir: false,
+            diff: false,
             verbose: true,
             quiet: true,
             silent: false,
@@ -419,6 +432,7 @@ mod tests {
             lib_only: true,
             prefix: true,
             show_install_dir: false,
+            diff: false,
             verbose: true,
             quiet: false,
             silent: false,
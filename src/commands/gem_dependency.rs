@@ -4,7 +4,7 @@
 
 use anyhow::{Context, Result};
 use lode::{Config, RubyGemsClient, gem_store::GemStore, parse_gem_name};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -42,11 +42,8 @@ pub(crate) struct DependencyOptions {
     /// When set or when the pattern might match multiple gems, uses bulk index for efficiency.
     pub bulk_threshold: Option<usize>,
 
-    /// Clear sources (use only explicit --source)
-    pub clear_sources: bool,
-
-    /// Source URL
-    pub source: Option<String>,
+    /// Remote sources in priority order, after `-s` and `--clear-sources`
+    pub sources: Vec<String>,
 
     /// HTTP proxy URL (overrides `HTTP_PROXY` env var)
     pub http_proxy: Option<String>,
@@ -74,8 +71,7 @@ impl Default for DependencyOptions {
             remote: false,
             both: false,
             bulk_threshold: None,
-            clear_sources: false,
-            source: None,
+            sources: Vec::new(),
             http_proxy: None,
             verbose: false,
             quiet: false,
@@ -158,7 +154,7 @@ fn show_local_dependencies(options: &DependencyOptions) -> Result<bool> {
 
             // Filter by version if specified
             if let Some(ref req_version) = options.version
-                && version != req_version
+                && !lode::gem_utils::requirement_satisfied(version, req_version)
             {
                 continue;
             }
@@ -180,7 +176,7 @@ fn show_local_dependencies(options: &DependencyOptions) -> Result<bool> {
 
     // Show reverse dependencies if requested
     if options.reverse_dependencies {
-        show_reverse_dependencies(&matching_gems, &gem_dir, options)?;
+        show_reverse_dependencies(&matching_gems, &store, options)?;
     } else {
         // Show forward dependencies
         for gem in &matching_gems {
@@ -211,15 +207,10 @@ fn should_use_bulk_search(pattern: &str, bulk_threshold: Option<usize>) -> bool
 
 /// Show dependencies for remote gems
 async fn show_remote_dependencies(options: &DependencyOptions) -> Result<bool> {
-    // When --clear-sources is used, require an explicit --source
-    let source = if options.clear_sources {
-        options
-            .source
-            .as_deref()
-            .ok_or_else(|| anyhow::anyhow!("--clear-sources requires --source to be specified"))?
-    } else {
-        options.source.as_deref().unwrap_or("https://rubygems.org")
-    };
+    let source = options
+        .sources
+        .first()
+        .map_or(lode::RUBYGEMS_ORG_URL, String::as_str);
 
     let client = RubyGemsClient::new_with_proxy(source, options.http_proxy.as_deref())?;
 
@@ -315,7 +306,7 @@ fn show_gem_dependencies(
 
             // Filter by version
             if let Some(ref req_version) = options.version
-                && &v.number != req_version
+                && !lode::gem_utils::requirement_satisfied(&v.number, req_version)
             {
                 return false;
             }
@@ -371,52 +362,41 @@ fn show_gem_dependencies(
 }
 
 /// Show reverse dependencies (which gems depend on the specified gems)
+///
+/// Uses the store's cached reverse dependency index rather than re-reading
+/// every installed gemspec.
 fn show_reverse_dependencies(
     target_gems: &[GemWithDeps],
-    gem_dir: &PathBuf,
+    store: &GemStore,
     options: &DependencyOptions,
 ) -> Result<()> {
     if options.silent {
         return Ok(());
     }
 
-    // Build map of gem names from targets
-    let target_names: HashSet<String> = target_gems.iter().map(|g| g.name.clone()).collect();
-
-    // Scan all gems to find reverse dependencies
-    let entries = fs::read_dir(gem_dir)
-        .with_context(|| format!("Failed to read gem directory: {}", gem_dir.display()))?;
-
-    let mut reverse_deps: HashMap<String, Vec<(String, String)>> = HashMap::new();
-
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if !path.is_dir() {
-            continue;
-        }
+    let index = store
+        .reverse_dependencies()
+        .context("Failed to build reverse dependency index")?;
 
-        if let Some(dir_name) = path.file_name().and_then(|n| n.to_str())
-            && let Some((name, version)) = parse_gem_name(dir_name)
-        {
-            let deps = read_dependencies_from_gemspec(&path);
-
-            // Check if this gem depends on any of our targets
-            for dep in deps {
-                if target_names.contains(&dep.name) {
-                    reverse_deps
-                        .entry(dep.name.clone())
-                        .or_default()
-                        .push((name.to_string(), version.to_string()));
-                }
-            }
-        }
-    }
+    let reverse_deps: HashMap<&str, Vec<(String, String)>> = target_gems
+        .iter()
+        .map(|gem| {
+            let dependents = index
+                .dependents_of(&gem.name)
+                .iter()
+                .filter(|d| !d.development)
+                .map(|d| (d.name.clone(), d.version.clone()))
+                .collect();
+            (gem.name.as_str(), dependents)
+        })
+        .filter(|(_, dependents): &(&str, Vec<_>)| !dependents.is_empty())
+        .collect();
 
     // Display results
     for gem in target_gems {
         if options.pipe {
             println!("{} --version {}", gem.name, gem.version);
-            if let Some(rdeps) = reverse_deps.get(&gem.name) {
+            if let Some(rdeps) = reverse_deps.get(gem.name.as_str()) {
                 for (dep_name, dep_version) in rdeps {
                     println!("  {dep_name} ({dep_version})");
                 }
@@ -424,7 +404,7 @@ fn show_reverse_dependencies(
         } else {
             println!("Gem {} ({})", gem.name, gem.version);
 
-            if let Some(rdeps) = reverse_deps.get(&gem.name) {
+            if let Some(rdeps) = reverse_deps.get(gem.name.as_str()) {
                 println!("  Used by:");
                 for (dep_name, dep_version) in rdeps {
                     println!("    {dep_name} ({dep_version})");
@@ -1,11 +1,20 @@
 //! Environment command
 //!
-//! Display gem environment information
+//! Display gem environment information in the format of `gem environment`.
+//! Scripts and version managers parse single queries like
+//! `gem env gemdir`, so those print exactly what `RubyGems` prints.
 
 use anyhow::{Context, Result};
-use lode::{Config, config, get_system_gem_dir};
+use lode::{Config, config, ruby};
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Separator for `GEM_PATH` and the `gempath` query
+const PATH_SEPARATOR: &str = ruby::GEM_PATH_SEPARATOR;
+
+/// Default remote source when none is configured
+const DEFAULT_SOURCE: &str = "https://rubygems.org/";
 
 /// Options for gem environment command
 #[derive(Debug, Default)]
@@ -18,71 +27,87 @@ pub(crate) struct EnvironmentOptions {
 
     /// Quiet mode
     pub quiet: bool,
+
+    /// `.gemrc` to use instead of `~/.gemrc`
+    pub config_file: Option<String>,
+
+    /// Skip loading `.gemrc`
+    pub norc: bool,
+}
+
+/// Everything `gem environment` reports
+#[derive(Debug)]
+struct GemEnv {
+    /// `Gem.dir`, where gems are installed
+    home: PathBuf,
+    /// `Gem.user_dir`
+    user_dir: PathBuf,
+    /// `Gem.path`, where gems are loaded from
+    path: Vec<PathBuf>,
+    /// `Gem.platforms`
+    platforms: Vec<String>,
+    /// `Gem.sources`
+    sources: Vec<String>,
+}
+
+impl GemEnv {
+    /// Gather the environment for `ruby_ver` from the process environment
+    fn detect(config: &Config, ruby_ver: &str) -> Self {
+        Self {
+            path: ruby::gem_path(ruby_ver),
+            home: ruby::gem_home(ruby_ver),
+            user_dir: ruby::user_gem_dir(ruby_ver),
+            platforms: vec![
+                "ruby".to_string(),
+                lode::platform::detect_current_platform(),
+            ],
+            sources: remote_sources(config),
+        }
+    }
+
+    /// Answer a `gem environment <query>`, matched by prefix like `RubyGems`
+    fn query(&self, var: &str) -> Result<String> {
+        let matches = |names: &[&str]| names.iter().any(|name| var.starts_with(name));
+        let answer = if matches(&["version"]) {
+            env!("CARGO_PKG_VERSION").to_string()
+        } else if matches(&["gemdir", "gemhome", "home", "GEM_HOME"]) {
+            self.home.display().to_string()
+        } else if matches(&["gempath", "path", "GEM_PATH"]) {
+            join_paths(&self.path)
+        } else if matches(&["user_gemdir", "user_gemhome"]) {
+            self.user_dir.display().to_string()
+        } else if matches(&["remotesources"]) {
+            self.sources.join("\n")
+        } else if matches(&["platform"]) {
+            self.platforms.join(PATH_SEPARATOR)
+        } else {
+            anyhow::bail!("Unknown environment option [{var}]");
+        };
+        Ok(answer)
+    }
 }
 
 /// Display `RubyGems` environment information
 pub(crate) fn run(options: EnvironmentOptions) -> Result<()> {
-    let config = Config::load().context("Failed to load configuration")?;
+    let config = Config::load_with_options(options.config_file.as_deref(), options.norc)
+        .context("Failed to load configuration")?;
     let ruby_ver = config::ruby_version(None);
+    let gem_env = GemEnv::detect(&config, &ruby_ver);
 
     // If specific variable requested, show only that
     if let Some(var) = options.variable {
-        return show_variable(&var, &config, &ruby_ver);
+        println!("{}", gem_env.query(&var)?);
+        return Ok(());
     }
 
     // Show full environment
-    show_full_environment(&config, &ruby_ver, &options);
-
-    Ok(())
-}
-
-/// Show specific environment variable
-fn show_variable(var: &str, config: &Config, ruby_ver: &str) -> Result<()> {
-    match var.to_lowercase().as_str() {
-        "gemdir" => {
-            let gem_dir = get_system_gem_dir(ruby_ver);
-            println!("{}", gem_dir.display());
-        }
-        "gempath" | "path" => {
-            let gem_paths = get_gem_paths(ruby_ver);
-            let path_str = gem_paths
-                .iter()
-                .map(|p| p.display().to_string())
-                .collect::<Vec<_>>()
-                .join(":");
-            println!("{path_str}");
-        }
-        "version" => {
-            println!("{}", env!("CARGO_PKG_VERSION"));
-        }
-        "remotesources" => {
-            let sources = get_remote_sources(config);
-            for source in sources {
-                println!("{source}");
-            }
-        }
-        "platform" => {
-            let platform = get_platform_string();
-            println!("ruby:{platform}");
-        }
-        "home" | "gemhome" => {
-            let home = get_gem_home(ruby_ver);
-            println!("{}", home.display());
-        }
-        "user_gemhome" | "user_gemdir" => {
-            let user_dir = get_user_gem_dir(ruby_ver);
-            println!("{}", user_dir.display());
-        }
-        _ => {
-            anyhow::bail!("Unknown environment variable: {var}");
-        }
-    }
+    show_full_environment(&gem_env, &config, &options);
 
     Ok(())
 }
 
 /// Show full environment information
-fn show_full_environment(config: &Config, ruby_ver: &str, options: &EnvironmentOptions) {
+fn show_full_environment(gem_env: &GemEnv, config: &Config, options: &EnvironmentOptions) {
     // In quiet mode, suppress all output
     if options.quiet {
         return;
@@ -90,113 +115,162 @@ fn show_full_environment(config: &Config, ruby_ver: &str, options: &EnvironmentO
 
     println!("RubyGems Environment:");
     println!("  - RUBYGEMS VERSION: {}", env!("CARGO_PKG_VERSION"));
-    println!("  - RUBY VERSION: {}", get_ruby_version_full());
+    println!("  - RUBY VERSION: {}", ruby_description(gem_env));
+    println!("  - INSTALLATION DIRECTORY: {}", gem_env.home.display());
     println!(
-        "  - INSTALLATION DIRECTORY: {}",
-        get_system_gem_dir(ruby_ver).display()
+        "  - USER INSTALLATION DIRECTORY: {}",
+        gem_env.user_dir.display()
     );
-
-    let user_dir = get_user_gem_dir(ruby_ver);
-    println!("  - USER INSTALLATION DIRECTORY: {}", user_dir.display());
-
-    let bin_dir = get_bin_dir(ruby_ver);
-    println!("  - RUBY EXECUTABLE: {}", get_ruby_executable());
-    println!("  - EXECUTABLE DIRECTORY: {}", bin_dir.display());
-
+    let ruby = find_executable("ruby");
     println!(
-        "  - SPEC CACHE DIRECTORY: {}",
-        get_spec_cache_dir().display()
+        "  - RUBY EXECUTABLE: {}",
+        ruby.as_deref()
+            .unwrap_or_else(|| Path::new("ruby"))
+            .display()
     );
+    if let Some(git) = find_executable("git") {
+        println!("  - GIT EXECUTABLE: {}", git.display());
+    }
+    println!(
+        "  - EXECUTABLE DIRECTORY: {}",
+        bin_dir(&gem_env.home, ruby.as_deref()).display()
+    );
+    println!("  - SPEC CACHE DIRECTORY: {}", spec_cache_dir().display());
     println!(
         "  - SYSTEM CONFIGURATION DIRECTORY: {}",
         get_system_config_dir().display()
     );
 
-    // Gem paths
     println!("  - RUBYGEMS PLATFORMS:");
-    for platform in get_platforms() {
-        println!("    - {platform}");
+    for platform in &gem_env.platforms {
+        println!("     - {platform}");
     }
 
-    // Remote sources
     println!("  - GEM PATHS:");
-    for path in get_gem_paths(ruby_ver) {
+    for path in &gem_env.path {
         println!("     - {}", path.display());
     }
 
     println!("  - GEM CONFIGURATION:");
-    if let Ok(cache_dir) = config::cache_dir(Some(config)) {
-        println!("     - :cachedir => {:?}", cache_dir.to_string_lossy());
+    for line in gem_configuration(&config.gemrc) {
+        println!("     - {line}");
     }
-    println!("     - :concurrent_downloads => 8");
 
     println!("  - REMOTE SOURCES:");
-    for source in get_remote_sources(config) {
+    for source in &gem_env.sources {
         println!("     - {source}");
     }
 
     println!("  - SHELL PATH:");
-    if let Ok(path_var) = env::var("PATH") {
-        for path in path_var.split(':') {
-            println!("     - {path}");
+    if let Some(path_var) = env::var_os("PATH") {
+        for path in env::split_paths(&path_var) {
+            println!("     - {}", path.display());
         }
     }
 
     if options.verbose {
         println!("\n  - ENVIRONMENT VARIABLES:");
-        if let Ok(gem_home) = env::var("GEM_HOME") {
-            println!("     - GEM_HOME: {gem_home}");
-        }
-        if let Ok(gem_path) = env::var("GEM_PATH") {
-            println!("     - GEM_PATH: {gem_path}");
-        }
-        if let Ok(gem_spec_cache) = env::var("GEM_SPEC_CACHE") {
-            println!("     - GEM_SPEC_CACHE: {gem_spec_cache}");
+        for name in ["GEM_HOME", "GEM_PATH", "GEM_SPEC_CACHE", "GEMRC"] {
+            if let Ok(value) = env::var(name) {
+                println!("     - {name}: {value}");
+            }
         }
     }
 }
 
-/// Get Ruby version string
-fn get_ruby_version_full() -> String {
-    let version = config::ruby_version(None);
-    format!("{} ({})", version, get_platform_string())
+/// Paths joined with the platform separator
+fn join_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(PATH_SEPARATOR)
 }
 
-/// Get Ruby executable path
-fn get_ruby_executable() -> String {
-    use std::process::Command;
-
-    // Try to get ruby path from 'which ruby' command
-    if let Ok(output) = Command::new("which").arg("ruby").output()
-        && output.status.success()
-        && let Ok(path) = String::from_utf8(output.stdout)
-    {
-        return path.trim().to_string();
+/// `Gem.sources`: `.gemrc` `:sources:`, else lode's configured sources,
+/// else rubygems.org
+fn remote_sources(config: &Config) -> Vec<String> {
+    if !config.gemrc.sources.is_empty() {
+        return config.gemrc.sources.clone();
     }
+    let sources: Vec<String> = config
+        .gem_sources
+        .iter()
+        .map(|source| source.url.clone())
+        .collect();
+    if sources.is_empty() {
+        vec![DEFAULT_SOURCE.to_string()]
+    } else {
+        sources
+    }
+}
 
-    String::from("ruby")
+/// `Gem.configuration` entries: the defaults, then `.gemrc` settings
+fn gem_configuration(gemrc: &lode::gemrc::Gemrc) -> Vec<String> {
+    let mut lines = vec![
+        ":update_sources => true".to_string(),
+        format!(":verbose => {}", gemrc.verbose.unwrap_or(true)),
+        format!(":backtrace => {}", gemrc.backtrace.unwrap_or(true)),
+        ":bulk_threshold => 1000".to_string(),
+    ];
+    if !gemrc.sources.is_empty() {
+        lines.push(format!(":sources => {:?}", gemrc.sources));
+    }
+    for (command, args) in &gemrc.args {
+        lines.push(format!("{command:?} => {:?}", args.join(" ")));
+    }
+    lines
 }
 
-/// Get gem home directory
-fn get_gem_home(ruby_ver: &str) -> PathBuf {
-    env::var("GEM_HOME").map_or_else(|_| get_system_gem_dir(ruby_ver), PathBuf::from)
+/// `RUBY_DESCRIPTION`-style line, like `3.3.6 (2024-11-05 patchlevel 108) [x86_64-linux]`
+fn ruby_description(gem_env: &GemEnv) -> String {
+    Command::new("ruby")
+        .args([
+            "-e",
+            "print RUBY_VERSION, ' (', RUBY_RELEASE_DATE, ' patchlevel ', RUBY_PATCHLEVEL, ') [', RUBY_PLATFORM, ']'",
+        ])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map_or_else(
+            || {
+                format!(
+                    "{} [{}]",
+                    config::ruby_version(None),
+                    gem_env.platforms.last().map_or("ruby", String::as_str)
+                )
+            },
+            |output| String::from_utf8_lossy(&output.stdout).into_owned(),
+        )
 }
 
-/// Get user gem directory
-fn get_user_gem_dir(ruby_ver: &str) -> PathBuf {
-    let home = env::var("HOME").unwrap_or_else(|_| String::from("/tmp"));
-    PathBuf::from(home).join(".gem").join("ruby").join(ruby_ver)
+/// First `name` on `PATH`
+fn find_executable(name: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
 }
 
-/// Get binary directory
-fn get_bin_dir(ruby_ver: &str) -> PathBuf {
-    get_system_gem_dir(ruby_ver).join("bin")
+/// `Gem.bindir`: `GEM_HOME/bin`, or Ruby's own bin directory for its
+/// default gem home
+fn bin_dir(home: &Path, ruby: Option<&Path>) -> PathBuf {
+    let custom_home = env::var_os("GEM_HOME").is_some_and(|home| !home.is_empty());
+    ruby.and_then(Path::parent)
+        .filter(|_| !custom_home)
+        .map_or_else(|| home.join("bin"), Path::to_path_buf)
 }
 
-/// Get spec cache directory
-fn get_spec_cache_dir() -> PathBuf {
-    let home = env::var("HOME").unwrap_or_else(|_| String::from("/tmp"));
-    PathBuf::from(home).join(".gem").join("specs")
+/// `GEM_SPEC_CACHE`, else `~/.gem/specs`
+fn spec_cache_dir() -> PathBuf {
+    env::var("GEM_SPEC_CACHE").map_or_else(
+        |_| {
+            dirs::home_dir()
+                .unwrap_or_else(env::temp_dir)
+                .join(".gem")
+                .join("specs")
+        },
+        PathBuf::from,
+    )
 }
 
 /// Get system configuration directory
@@ -210,82 +284,27 @@ fn get_system_config_dir() -> PathBuf {
     }
 }
 
-/// Get supported platforms
-fn get_platforms() -> Vec<String> {
-    let mut platforms = vec!["ruby".to_string()];
-
-    let platform = get_platform_string();
-    if !platform.is_empty() {
-        platforms.push(platform);
-    }
-
-    platforms
-}
-
-/// Get platform string
-fn get_platform_string() -> String {
-    let os = if cfg!(target_os = "macos") {
-        "darwin"
-    } else if cfg!(target_os = "linux") {
-        "linux"
-    } else if cfg!(target_os = "windows") {
-        "mingw32"
-    } else {
-        "unknown"
-    };
-
-    let arch = if cfg!(target_arch = "x86_64") {
-        "x86_64"
-    } else if cfg!(target_arch = "aarch64") {
-        "arm64"
-    } else {
-        env::consts::ARCH
-    };
-
-    format!("{arch}-{os}")
-}
-
-/// Get gem paths
-fn get_gem_paths(ruby_ver: &str) -> Vec<PathBuf> {
-    let mut paths = Vec::new();
-
-    // GEM_PATH environment variable
-    if let Ok(gem_path) = env::var("GEM_PATH") {
-        for path in gem_path.split(':') {
-            paths.push(PathBuf::from(path));
-        }
-        return paths;
-    }
-
-    // Default paths
-    paths.push(get_system_gem_dir(ruby_ver));
-    paths.push(get_user_gem_dir(ruby_ver));
-
-    paths
-}
-
-/// Get remote sources
-fn get_remote_sources(config: &Config) -> Vec<String> {
-    let mut sources = Vec::new();
-
-    // Add sources from configuration
-    for source in &config.gem_sources {
-        sources.push(source.url.clone());
-    }
-
-    // Add default RubyGems.org if no sources configured
-    if sources.is_empty() {
-        sources.push("https://rubygems.org/".to_string());
-    }
-
-    sources
-}
-
 #[cfg(test)]
 #[allow(clippy::unwrap_used, reason = "Tests can panic")]
 mod tests {
     use super::*;
 
+    fn sample_env() -> GemEnv {
+        GemEnv {
+            home: PathBuf::from("/usr/lib/ruby/gems/3.3.0"),
+            user_dir: PathBuf::from("/home/me/.gem/ruby/3.3.0"),
+            path: vec![
+                PathBuf::from("/home/me/.gem/ruby/3.3.0"),
+                PathBuf::from("/usr/lib/ruby/gems/3.3.0"),
+            ],
+            platforms: vec!["ruby".to_string(), "x86_64-linux".to_string()],
+            sources: vec![
+                "https://gems.example.com/".to_string(),
+                DEFAULT_SOURCE.to_string(),
+            ],
+        }
+    }
+
     #[test]
     fn environment_options_default() {
         let options = EnvironmentOptions::default();
@@ -295,23 +314,61 @@ mod tests {
     }
 
     #[test]
-    fn test_get_platform_string() {
-        let platform = get_platform_string();
-        assert!(!platform.is_empty());
-        assert!(platform.contains('-'));
+    #[cfg(not(windows))]
+    fn query_matches_rubygems_output() {
+        let gem_env = sample_env();
+        for var in ["gemdir", "gemhome", "home", "GEM_HOME"] {
+            assert_eq!(gem_env.query(var).unwrap(), "/usr/lib/ruby/gems/3.3.0");
+        }
+        assert_eq!(
+            gem_env.query("gempath").unwrap(),
+            "/home/me/.gem/ruby/3.3.0:/usr/lib/ruby/gems/3.3.0"
+        );
+        assert_eq!(
+            gem_env.query("user_gemdir").unwrap(),
+            "/home/me/.gem/ruby/3.3.0"
+        );
+        assert_eq!(gem_env.query("platform").unwrap(), "ruby:x86_64-linux");
+        assert_eq!(
+            gem_env.query("remotesources").unwrap(),
+            "https://gems.example.com/\nhttps://rubygems.org/"
+        );
+        assert_eq!(gem_env.query("version").unwrap(), env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            gem_env.query("bogus").unwrap_err().to_string(),
+            "Unknown environment option [bogus]"
+        );
     }
 
     #[test]
-    fn test_get_platforms() {
-        let platforms = get_platforms();
-        assert!(!platforms.is_empty());
-        assert!(platforms.contains(&"ruby".to_string()));
+    #[cfg(not(windows))]
+    fn gem_path_follows_gem_path_variable() {
+        let home = Path::new("/gems/home");
+        let defaults = [PathBuf::from("/user"), PathBuf::from("/default")];
+        let paths = |var| join_paths(&ruby::resolve_gem_path(var, home, &defaults));
+
+        assert_eq!(paths(None), "/user:/default:/gems/home");
+        assert_eq!(paths(Some("/a:/b")), "/a:/b:/gems/home");
+        assert_eq!(paths(Some("/a:")), "/a:/user:/default:/gems/home");
+        assert_eq!(paths(Some("/gems/home:/a")), "/gems/home:/a");
     }
 
     #[test]
-    fn test_get_gem_paths() {
-        let ruby_ver = "3.3.0";
-        let paths = get_gem_paths(ruby_ver);
-        assert!(!paths.is_empty());
+    fn gem_configuration_lists_gemrc_settings() {
+        let gemrc = lode::gemrc::Gemrc::parse(
+            ":backtrace: false\n:sources:\n- https://gems.example.com/\ngem: --no-document\n",
+        )
+        .unwrap();
+        assert_eq!(
+            gem_configuration(&gemrc),
+            [
+                ":update_sources => true",
+                ":verbose => true",
+                ":backtrace => false",
+                ":bulk_threshold => 1000",
+                ":sources => [\"https://gems.example.com/\"]",
+                "\"gem\" => \"--no-document\"",
+            ]
+        );
     }
 }
@@ -3,50 +3,105 @@
 //! Download a gem without installing
 
 use anyhow::{Context, Result};
-use lode::{DownloadManager, RubyGemsClient, config};
+use lode::gem_utils::{
+    compare_versions, is_valid_requirement, requirement_allows_prerelease, requirement_satisfied,
+};
+use lode::platform::{detect_current_platform, normalize_platform, platform_matches};
+use lode::{DownloadManager, GemVersion, RubyGemsClient, config};
 use std::fs;
 use std::path::PathBuf;
 
+/// Options for `gem fetch`
+#[derive(Debug, Default)]
+pub(crate) struct FetchOptions<'a> {
+    /// Gem name to download
+    pub gem: &'a str,
+    /// Exact version or requirement (e.g. `~> 2.0`)
+    pub version: Option<&'a str>,
+    /// Directory to copy the downloaded gem into
+    pub output_dir: Option<&'a str>,
+    /// Platform variant to fetch (defaults to the current platform)
+    pub platform: Option<&'a str>,
+    /// Allow prerelease versions
+    pub prerelease: bool,
+    /// Suggest alternatives when nothing matches
+    pub suggestions: bool,
+    /// Sources to query in priority order, after `-s` and `--clear-sources`
+    pub sources: &'a [String],
+    /// HTTP proxy override
+    pub http_proxy: Option<&'a str>,
+    /// Suppress progress output
+    pub quiet: bool,
+}
+
 /// Download a gem without installing it
-pub(crate) async fn run(
-    gem_name: &str,
-    version: Option<&str>,
-    output_dir: Option<&str>,
-) -> Result<()> {
-    // 1. Fetch gem versions from RubyGems
-    let client = RubyGemsClient::new(lode::RUBYGEMS_ORG_URL)?;
-    let versions = client
-        .fetch_versions(gem_name)
-        .await
-        .context(format!("Failed to fetch versions for gem '{gem_name}'"))?;
+pub(crate) async fn run(options: &FetchOptions<'_>) -> Result<()> {
+    let gem_name = options.gem;
+    if let Some(requirement) = options.version
+        && !is_valid_requirement(requirement)
+    {
+        anyhow::bail!("Invalid version requirement '{requirement}'");
+    }
+    let sources = options.sources;
+    let platform = options
+        .platform
+        .map_or_else(detect_current_platform, str::to_string);
 
-    if versions.is_empty() {
-        anyhow::bail!("Gem '{gem_name}' not found on RubyGems.org");
+    // 1. Fetch gem versions from the first source that knows the gem
+    let mut found = None;
+    for source in sources {
+        let client = RubyGemsClient::new_with_proxy(
+            source.as_str(),
+            options.http_proxy.filter(|p| !p.is_empty()),
+        )?
+        .with_prerelease(true);
+        if let Ok(versions) = client.fetch_versions(gem_name).await
+            && !versions.is_empty()
+        {
+            found = Some((source.clone(), versions));
+            break;
+        }
     }
 
-    // 2. Find matching version
-    let selected_version = if let Some(v) = version {
-        versions
-            .iter()
-            .find(|ver| ver.number == v)
-            .context(format!("Version '{v}' not found for gem '{gem_name}'"))?
-    } else {
-        // Use latest version
-        versions
-            .first()
-            .context(format!("No suitable version found for gem '{gem_name}'"))?
+    let Some((source, versions)) = found else {
+        if options.suggestions {
+            eprintln!("Try `lode gem-search {gem_name}` to find similarly named gems");
+        }
+        anyhow::bail!("Gem '{gem_name}' not found in {}", sources.join(", "));
     };
 
-    println!("Fetching {} ({})...", gem_name, selected_version.number);
+    // 2. Find matching version and platform variant
+    let Some(selected) = select_version(&versions, options.version, options.prerelease, &platform)
+    else {
+        if options.suggestions {
+            let available = candidate_numbers(&versions, options.prerelease);
+            if !available.is_empty() {
+                eprintln!("Available versions: {}", available.join(", "));
+            }
+        }
+        anyhow::bail!(
+            "No version of '{gem_name}' matching '{}' for {platform}",
+            options.version.unwrap_or(">= 0")
+        );
+    };
+
+    let gem_platform = (selected.platform != "ruby" && !selected.platform.is_empty())
+        .then(|| selected.platform.clone());
+    if !options.quiet {
+        match &gem_platform {
+            Some(p) => println!("Fetching {gem_name} ({}-{p})...", selected.number),
+            None => println!("Fetching {} ({})...", gem_name, selected.number),
+        }
+    }
 
     // 3. Download gem
     let cache_dir = config::cache_dir(None).context("Failed to get cache directory")?;
-    let dm = DownloadManager::new(cache_dir)?;
+    let dm = DownloadManager::with_sources(cache_dir, vec![source])?;
 
     let spec = lode::GemSpec::new(
         gem_name.to_string(),
-        selected_version.number.clone(),
-        None, // No platform for pure Ruby gems
+        selected.number.clone(),
+        gem_platform,
         vec![],
         vec![],
     );
@@ -57,7 +112,7 @@ pub(crate) async fn run(
         .context("Failed to download gem")?;
 
     // 4. Copy to output directory if specified
-    let final_path = if let Some(dir) = output_dir {
+    let final_path = if let Some(dir) = options.output_dir {
         let output_path = PathBuf::from(dir);
         fs::create_dir_all(&output_path).context(format!(
             "Failed to create directory: {}",
@@ -74,19 +129,135 @@ pub(crate) async fn run(
         gem_path
     };
 
-    println!("Downloaded: {}", final_path.display());
-    println!(
-        "Successfully fetched {} ({})",
-        gem_name, selected_version.number
-    );
+    if !options.quiet {
+        println!("Downloaded: {}", final_path.display());
+        println!("Successfully fetched {}", spec.full_name_with_platform());
+    }
 
     Ok(())
 }
 
+/// Pick the newest version matching the requirement, preferring a native
+/// build for `platform` over the pure-Ruby gem of the same version
+///
+/// An exact version or a requirement naming a prerelease admits prereleases
+/// even without `--prerelease`, like `gem fetch -v 2.0.0.rc1`.
+fn select_version<'a>(
+    versions: &'a [GemVersion],
+    requirement: Option<&str>,
+    prerelease: bool,
+    platform: &str,
+) -> Option<&'a GemVersion> {
+    let allow_pre = prerelease || requirement.is_some_and(requirement_allows_prerelease);
+    let target = normalize_platform(platform);
+
+    let mut candidates: Vec<&GemVersion> = versions
+        .iter()
+        .filter(|v| allow_pre || !is_prerelease(&v.number))
+        .filter(|v| requirement.is_none_or(|req| requirement_satisfied(&v.number, req)))
+        .filter(|v| platform_rank(&v.platform, &target).is_some())
+        .collect();
+
+    candidates.sort_by(|a, b| {
+        compare_versions(&b.number, &a.number).then_with(|| {
+            platform_rank(&a.platform, &target).cmp(&platform_rank(&b.platform, &target))
+        })
+    });
+    candidates.first().copied()
+}
+
+/// 0 for a build matching `target`, 1 for pure Ruby, `None` if unusable
+fn platform_rank(gem_platform: &str, target: &str) -> Option<u8> {
+    if gem_platform.is_empty() || gem_platform == "ruby" {
+        Some(1)
+    } else if target != "ruby" && platform_matches(&Some(gem_platform.to_string()), target) {
+        Some(0)
+    } else {
+        None
+    }
+}
+
+/// Distinct version numbers for suggestions, newest first
+fn candidate_numbers(versions: &[GemVersion], prerelease: bool) -> Vec<String> {
+    let mut numbers: Vec<String> = versions
+        .iter()
+        .filter(|v| prerelease || !is_prerelease(&v.number))
+        .map(|v| v.number.clone())
+        .collect();
+    numbers.sort_by(|a, b| compare_versions(b, a));
+    numbers.dedup();
+    numbers.truncate(5);
+    numbers
+}
+
+/// `RubyGems` treats any version containing a letter as a prerelease
+fn is_prerelease(version: &str) -> bool {
+    version.chars().any(|c| c.is_ascii_alphabetic())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn gem_version(number: &str, platform: &str) -> GemVersion {
+        GemVersion {
+            number: number.to_string(),
+            platform: platform.to_string(),
+            ruby_version: None,
+            rubygems_version: None,
+            created_at: None,
+            downloads_count: None,
+            summary: None,
+            dependencies: lode::Dependencies::default(),
+        }
+    }
+
+    #[test]
+    fn select_version_prefers_native_platform() {
+        let versions = vec![
+            gem_version("1.16.0", "ruby"),
+            gem_version("1.16.0", "x86_64-linux-gnu"),
+            gem_version("1.16.0", "arm64-darwin"),
+            gem_version("1.15.0", "ruby"),
+        ];
+
+        let linux = select_version(&versions, None, false, "x86_64-linux").unwrap();
+        assert_eq!(linux.platform, "x86_64-linux-gnu");
+
+        let ruby = select_version(&versions, None, false, "ruby").unwrap();
+        assert_eq!(ruby.platform, "ruby");
+
+        let java = select_version(&versions, None, false, "universal-java").unwrap();
+        assert_eq!(
+            (java.number.as_str(), java.platform.as_str()),
+            ("1.16.0", "ruby")
+        );
+    }
+
+    #[test]
+    fn select_version_honors_requirements_and_prereleases() {
+        let versions = vec![
+            gem_version("3.0.0.beta1", "ruby"),
+            gem_version("2.1.0", "ruby"),
+            gem_version("2.0.5", "ruby"),
+            gem_version("1.9.0", "ruby"),
+        ];
+
+        let latest = select_version(&versions, None, false, "ruby").unwrap();
+        assert_eq!(latest.number, "2.1.0");
+
+        let pre = select_version(&versions, None, true, "ruby").unwrap();
+        assert_eq!(pre.number, "3.0.0.beta1");
+
+        let pessimistic = select_version(&versions, Some("~> 2.0.0"), false, "ruby").unwrap();
+        assert_eq!(pessimistic.number, "2.0.5");
+
+        let exact_pre = select_version(&versions, Some("3.0.0.beta1"), false, "ruby").unwrap();
+        assert_eq!(exact_pre.number, "3.0.0.beta1");
+
+        assert!(select_version(&versions, Some("~> 4.0"), false, "ruby").is_none());
+    }
+
     /// Test validation of gem names
     fn validate_gem_name(name: &str) -> bool {
         !name.is_empty()
@@ -138,11 +138,8 @@ pub(crate) struct InfoOptions {
     /// Threshold for switching to bulk synchronization (not used in gem info)
     pub bulk_threshold: usize,
 
-    /// Clear the gem sources
-    pub clear_sources: bool,
-
-    /// Append URL to list of remote gem sources
-    pub source: Option<String>,
+    /// Remote sources in priority order, after `-s` and `--clear-sources`
+    pub sources: Vec<String>,
 
     /// Use HTTP proxy for remote operations
     pub http_proxy: Option<String>,
@@ -184,8 +181,7 @@ impl Default for InfoOptions {
             remote: false,
             both: false,
             bulk_threshold: 1000,
-            clear_sources: false,
-            source: None,
+            sources: Vec::new(),
             http_proxy: None,
             verbose: false,
             quiet: false,
@@ -221,13 +217,8 @@ pub(crate) async fn run(options: InfoOptions) -> Result<()> {
         );
     }
 
-    // Handle --clear-sources flag
-    if options.clear_sources {
-        // --clear-sources in gem info silently clears sources and continues
-        // No special output is printed, the query continues normally
-        if options.debug {
-            eprintln!("DEBUG: --clear-sources flag set (sources cleared)");
-        }
+    if options.debug {
+        eprintln!("DEBUG: Sources: {}", options.sources.join(", "));
     }
 
     // Handle --bulk-threshold flag
@@ -238,17 +229,10 @@ pub(crate) async fn run(options: InfoOptions) -> Result<()> {
         );
     }
 
-    // Handle custom source (override RUBYGEMS_HOST temporarily)
-    if options.source.is_some() && options.verbose {
-        eprintln!(
-            "Warning: --source is not fully supported yet. Use RUBYGEMS_HOST environment variable instead."
-        );
-    }
-
     // Handle http-proxy (use HTTP_PROXY environment variable)
     if options.http_proxy.is_some() && options.verbose {
-        eprintln!(
-            "Warning: --http-proxy is not fully supported yet. Use HTTP_PROXY environment variable instead."
+        lode::warn!(
+            "--http-proxy is not fully supported yet. Use HTTP_PROXY environment variable instead."
         );
     }
 
@@ -322,7 +306,7 @@ fn show_local_gem_info(options: &InfoOptions) -> Result<bool> {
 
         // Filter by specific version if requested
         if let Some(ref req_version) = options.version
-            && gem.version != *req_version
+            && !lode::gem_utils::requirement_satisfied(&gem.version, req_version)
         {
             continue;
         }
@@ -374,9 +358,13 @@ fn show_local_gem_info(options: &InfoOptions) -> Result<bool> {
     Ok(!matching_gems.is_empty())
 }
 
-/// Show information for remote gems (RubyGems.org or `RUBYGEMS_HOST`)
+/// Show information for remote gems from the primary source
 async fn show_remote_gem_info(options: &InfoOptions) -> Result<bool> {
-    let host = lode::env_vars::rubygems_host();
+    let host = options
+        .sources
+        .first()
+        .cloned()
+        .unwrap_or_else(lode::env_vars::rubygems_host);
     let url = format!("{}/api/v1/gems/{}.json", host, options.gem);
 
     if options.debug {
@@ -697,8 +685,7 @@ end
         assert!(!opts.remote);
         assert!(!opts.both);
         assert_eq!(opts.bulk_threshold, 1000);
-        assert!(!opts.clear_sources);
-        assert!(opts.source.is_none());
+        assert!(opts.sources.is_empty());
         assert!(opts.http_proxy.is_none());
         assert!(!opts.verbose);
         assert!(!opts.quiet);
@@ -790,9 +777,9 @@ end
     #[test]
     fn test_info_options_source_url() {
         let mut opts = InfoOptions::default();
-        assert!(opts.source.is_none());
-        opts.source = Some("https://gems.example.com".to_string());
-        assert_eq!(opts.source, Some("https://gems.example.com".to_string()));
+        assert!(opts.sources.is_empty());
+        opts.sources = vec!["https://gems.example.com".to_string()];
+        assert_eq!(opts.sources, vec!["https://gems.example.com"]);
     }
 
     #[test]
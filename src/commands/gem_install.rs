@@ -4,9 +4,12 @@
 
 use anyhow::{Context, Result};
 use futures_util::future::BoxFuture;
+use lode::docs::{self, DocOutcome, DocPolicy};
 use lode::gem_store::GemStore;
 use lode::trust_policy::TrustPolicy;
-use lode::{DownloadManager, ExtensionBuilder, GemSpec, Resolver, RubyGemsClient, config};
+use lode::{
+    Config, DownloadManager, ExtensionBuilder, GemSpec, Gemspec, RubyGemsClient, config, gem_utils,
+};
 use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -51,8 +54,8 @@ pub(crate) struct InstallOptions {
     pub remote: bool,
     pub both: bool,
     pub bulk_threshold: Option<usize>,
-    pub clear_sources: bool,
-    pub source: Option<String>,
+    /// Remote sources in priority order, after `-s` and `--clear-sources`
+    pub sources: Vec<String>,
     pub http_proxy: Option<String>,
     // Common Options
     pub verbose: bool,
@@ -62,6 +65,8 @@ pub(crate) struct InstallOptions {
     pub backtrace: bool,
     pub debug: bool,
     pub norc: bool,
+    /// Install names that look like typos of popular gems
+    pub yes: bool,
 }
 
 /// Install gems with all specified options
@@ -106,16 +111,8 @@ pub(crate) async fn run(mut options: InstallOptions) -> Result<()> {
         }
     }
 
-    // Handle --clear-sources flag
-    if options.clear_sources {
-        if options.debug {
-            eprintln!("DEBUG: --clear-sources flag set");
-        }
-        // Note: lode uses RubyGemsClient which doesn't have persistent sources
-        // This flag is accepted for compatibility but has no effect
-        if options.verbose {
-            println!("Note: Source clearing not applicable (lode doesn't persist sources)");
-        }
+    if options.debug {
+        eprintln!("DEBUG: Sources: {}", options.sources.join(", "));
     }
 
     // Handle --bulk-threshold flag
@@ -159,6 +156,16 @@ pub(crate) async fn run(mut options: InstallOptions) -> Result<()> {
         return explain_install(&options).await;
     }
 
+    // Near-misses of popular gem names need --yes
+    let is_gem_file = |name: &&String| {
+        Path::new(name.as_str())
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("gem"))
+    };
+    for gem_name in options.gems.iter().filter(|name| !is_gem_file(name)) {
+        super::add::check_typosquat(gem_name, primary_source(&options), options.yes).await?;
+    }
+
     // Determine install directory
     let install_dir = determine_install_dir(&options)?;
 
@@ -180,7 +187,7 @@ pub(crate) async fn run(mut options: InstallOptions) -> Result<()> {
     };
 
     // Initialize RubyGems client
-    let source_url = options.source.as_deref().unwrap_or(lode::RUBYGEMS_ORG_URL);
+    let source_url = primary_source(&options).unwrap_or(lode::RUBYGEMS_ORG_URL);
     let client = RubyGemsClient::new_with_proxy(source_url, options.http_proxy.as_deref())?;
 
     // Process each gem (with dependency resolution)
@@ -271,9 +278,11 @@ fn install_gem_with_dependencies<'a>(
             Ok(meta) => meta,
             Err(e) => {
                 if options.verbose {
-                    eprintln!(
-                        "Warning: Could not fetch metadata for {} ({}): {}",
-                        spec.name, spec.version, e
+                    lode::warn!(
+                        "Could not fetch metadata for {} ({}): {}",
+                        spec.name,
+                        spec.version,
+                        e
                     );
                     eprintln!("  Skipping dependency installation and post-install message");
                 }
@@ -387,8 +396,7 @@ async fn install_single_gem(
     }
 
     // 2. Select version based on requirements
-    let selected_version =
-        select_gem_version(gem_name, version_requirement, &versions, options, client)?;
+    let selected_version = select_gem_version(gem_name, version_requirement, &versions, options)?;
 
     if options.verbose {
         println!("Selected {} version {}", gem_name, selected_version.number);
@@ -433,7 +441,7 @@ async fn install_single_gem(
 
     // 6. Download gem
     let cache_dir = config::cache_dir(None)?;
-    let mut dm = DownloadManager::new(cache_dir)?;
+    let mut dm = DownloadManager::with_sources(cache_dir, options.sources.clone())?;
 
     // Configure local/remote mode
     if options.both {
@@ -480,8 +488,13 @@ async fn install_single_gem(
     let gem_install_dir = install_dir.join(format!("{}-{}", spec.name, spec.version));
     extract_gem(&gem_path, &gem_install_dir)?;
 
-    // 9. Build extensions if present
-    if has_extensions(&gem_install_dir) {
+    // 9. Build extensions if present (the packaged gemspec is authoritative)
+    let gemspec = Gemspec::from_gem_file(&gem_path).ok();
+    let declares_extensions = gemspec.as_ref().map_or_else(
+        || has_extensions(&gem_install_dir),
+        |gemspec| !gemspec.extensions.is_empty(),
+    );
+    if declares_extensions {
         if options.verbose {
             println!("Building native extensions for {}...", spec.name);
         }
@@ -490,11 +503,22 @@ async fn install_single_gem(
 
     // 10. Install executables
     if let Some(bindir) = &options.bindir {
-        install_executables(&gem_install_dir, bindir, options)?;
+        install_executables(&gem_install_dir, gemspec.as_ref(), bindir, options)?;
     }
 
     // 11. Generate documentation
-    generate_documentation(&gem_install_dir, &spec, options)?;
+    generate_documentation(&gem_install_dir, options)?;
+
+    // 12. Register a RubyGems plugin (`lib/rubygems_plugin.rb`)
+    if let Some(gem_home) = install_dir.parent()
+        && let Some(loader) = lode::rubygems_plugins::regenerate(gem_home, &spec.name)?
+        && options.verbose
+    {
+        println!("Registered RubyGems plugin: {}", loader.display());
+    }
+
+    // The store's reverse dependency index no longer reflects the gem directory
+    GemStore::with_path(install_dir.to_path_buf()).invalidate_reverse_dependencies();
 
     // Note: Post-install messages are displayed in install_gem_with_dependencies()
 
@@ -624,168 +648,89 @@ fn build_extensions(gem_dir: &Path, options: &InstallOptions) -> Result<()> {
     if let Some(result) = builder.build_if_needed(gem_name, gem_dir, platform)
         && !result.success
     {
-        anyhow::bail!("Failed to build native extensions: {}", result.output);
+        return Err(lode::exit_code::ExitCode::ExtensionBuild.error(format!(
+            "Failed to build native extensions: {}",
+            result.output
+        )));
     }
 
     Ok(())
 }
 
-/// Parse documentation types from --document flag
-fn parse_doc_types(
-    doc_format: Option<&str>,
-    verbose: bool,
-) -> std::collections::HashSet<&'static str> {
-    let mut types = std::collections::HashSet::new();
-
-    if let Some(formats) = doc_format {
-        for format in formats.split(',') {
-            match format.trim() {
-                "rdoc" => {
-                    types.insert("rdoc");
-                }
-                "ri" => {
-                    types.insert("ri");
-                }
-                _ => {
-                    if verbose {
-                        println!("  Unknown documentation format: {format}");
-                    }
-                }
-            }
-        }
-    } else {
-        // Default: generate both rdoc and ri if --document is not specified
-        types.insert("rdoc");
-        types.insert("ri");
-    }
-
-    types
+/// Generate documentation for an installed gem, as [`DocPolicy::resolve`] decides
+fn generate_documentation(gem_dir: &Path, options: &InstallOptions) -> Result<()> {
+    let config = Config::load_with_options(options.config_file.as_deref(), options.norc).ok();
+    let policy = DocPolicy::resolve(
+        options.document.as_deref(),
+        options.no_document,
+        config.as_ref(),
+        "install",
+    );
+    document_gem(
+        gem_dir,
+        policy,
+        options.document.as_deref(),
+        options.verbose,
+        options.quiet || options.silent,
+    )
 }
 
-/// Generate documentation for a gem using `RDoc`
-fn generate_documentation(gem_dir: &Path, spec: &GemSpec, options: &InstallOptions) -> Result<()> {
-    // Skip if --no-document
-    if options.no_document {
-        return Ok(());
-    }
-
-    let lib_dir = gem_dir.join("lib");
-    if !lib_dir.exists() {
-        if options.verbose {
-            println!("  No lib directory found, skipping documentation");
+/// Run rdoc for a freshly installed gem
+///
+/// rdoc failures are reported in verbose mode but never fail the install.
+pub(super) fn document_gem(
+    gem_dir: &Path,
+    policy: DocPolicy,
+    document: Option<&str>,
+    verbose: bool,
+    quiet: bool,
+) -> Result<()> {
+    if verbose {
+        for format in document
+            .map(|formats| DocPolicy::parse(formats).1)
+            .unwrap_or_default()
+        {
+            println!("  Unknown documentation format: {format}");
         }
-        return Ok(());
-    }
-
-    // Determine what documentation types to generate
-    let doc_types = parse_doc_types(options.document.as_deref(), options.verbose);
-
-    // If no valid documentation types after parsing, skip
-    if doc_types.is_empty() {
-        if options.verbose {
-            println!("  No valid documentation types specified, skipping documentation");
+        if !policy.is_empty() {
+            println!("  Generating {} documentation...", policy.describe());
         }
-        return Ok(());
     }
 
-    // Determine documentation output directory (for rdoc HTML output)
-    let doc_dir = gem_dir
-        .parent()
-        .context("Invalid gem directory")?
-        .parent()
-        .context("Invalid gem directory structure")?
-        .join("doc")
-        .join(format!("{}-{}", spec.name, spec.version));
-
-    if options.verbose {
-        let types_str = if doc_types.contains("rdoc") && doc_types.contains("ri") {
-            "rdoc and ri"
-        } else if doc_types.contains("rdoc") {
-            "rdoc"
-        } else {
-            "ri"
-        };
-        println!("  Generating {types_str} documentation...");
-    }
-
-    // Create documentation directory if rdoc HTML output is needed
-    if doc_types.contains("rdoc") {
-        fs::create_dir_all(&doc_dir).context("Failed to create documentation directory")?;
-    }
-
-    // Run rdoc to generate documentation
-    let mut cmd = std::process::Command::new("rdoc");
-
-    // Add rdoc HTML output flag if requested
-    if doc_types.contains("rdoc") {
-        cmd.arg("--op").arg(&doc_dir);
-    }
-
-    // Add ri database generation flag if requested
-    if doc_types.contains("ri") {
-        cmd.arg("--ri");
-    }
-
-    // Add the source directory to document
-    cmd.arg(&lib_dir);
-
-    if options.quiet || options.silent {
-        cmd.stdout(std::process::Stdio::null());
-        cmd.stderr(std::process::Stdio::null());
-    }
-
-    // Execute rdoc
-    let output = cmd.output();
-
-    match output {
-        Ok(output) => {
-            if !output.status.success() {
-                if options.verbose {
-                    eprintln!(
-                        "  Warning: Documentation generation failed (rdoc exit code {})",
-                        output.status
-                    );
-                    if !output.stderr.is_empty() {
-                        eprintln!("  rdoc error: {}", String::from_utf8_lossy(&output.stderr));
-                    }
-                }
-                // Don't fail installation if documentation generation fails
-                return Ok(());
-            }
-
-            if options.verbose {
-                println!("  Documentation generated successfully");
-            }
-        }
-        Err(e) => {
-            if options.verbose {
-                eprintln!(
-                    "  Warning: Could not run rdoc ({e}). Skipping documentation generation."
-                );
-                eprintln!("  Install rdoc with: gem install rdoc");
-            }
-            // Don't fail installation if rdoc is not available
+    match docs::generate(gem_dir, policy, true, quiet)? {
+        DocOutcome::Generated if verbose => println!("  Documentation generated successfully"),
+        DocOutcome::Skipped(reason) if verbose => println!("  Skipping documentation: {reason}"),
+        DocOutcome::Failed(reason) if verbose => {
+            eprintln!("  Warning: Documentation generation failed: {reason}");
         }
+        _ => {}
     }
-
     Ok(())
 }
 
 /// Install gem executables to bin directory
-fn install_executables(gem_dir: &Path, bindir: &str, options: &InstallOptions) -> Result<()> {
-    let bin_src = gem_dir.join("bin");
-    if !bin_src.exists() {
+///
+/// Installs the executables declared in the gemspec (from its `bindir`),
+/// falling back to everything in `bin/` when no gemspec is available.
+fn install_executables(
+    gem_dir: &Path,
+    gemspec: Option<&Gemspec>,
+    bindir: &str,
+    options: &InstallOptions,
+) -> Result<()> {
+    let executables = match gemspec {
+        Some(gemspec) => gemspec.executable_paths(gem_dir),
+        None => lode::gemspec::scan_bin_dir(&gem_dir.join("bin"))
+            .context("Failed to read bin directory")?,
+    };
+    if executables.is_empty() {
         return Ok(());
     }
 
     let bin_dest = PathBuf::from(bindir);
     fs::create_dir_all(&bin_dest).context("Failed to create bin directory")?;
 
-    for entry in fs::read_dir(&bin_src).context("Failed to read bin directory")? {
-        let entry = entry?;
-        let file_name = entry.file_name();
-        let src_path = entry.path();
-
+    for (file_name, src_path) in executables {
         // Apply format_executable if requested (adds gem name as suffix)
         let dest_filename = if options.format_executable {
             // Extract gem name and version from gem_dir
@@ -796,10 +741,9 @@ fn install_executables(gem_dir: &Path, bindir: &str, options: &InstallOptions) -
 
             // Format: <executable>-<gem-name-version>
             // E.g., "rake" becomes "rake-rake-13.0.1"
-            let base_name = file_name.to_str().unwrap_or("unknown");
-            format!("{base_name}-{gem_name_version}")
+            format!("{file_name}-{gem_name_version}")
         } else {
-            file_name.to_string_lossy().to_string()
+            file_name
         };
 
         let dest_path = bin_dest.join(&dest_filename);
@@ -863,11 +807,16 @@ load File.join(gem_dir, 'bin', '{}')
     Ok(())
 }
 
+/// Source queried for gem metadata; downloads fall back through the rest
+fn primary_source(options: &InstallOptions) -> Option<&str> {
+    options.sources.first().map(String::as_str)
+}
+
 /// Explain what would be installed without actually installing
 async fn explain_install(options: &InstallOptions) -> Result<()> {
     println!("Gems that would be installed:");
 
-    let source_url = options.source.as_deref().unwrap_or(lode::RUBYGEMS_ORG_URL);
+    let source_url = primary_source(options).unwrap_or(lode::RUBYGEMS_ORG_URL);
     let client = RubyGemsClient::new_with_proxy(source_url, options.http_proxy.as_deref())?;
 
     for gem_name in &options.gems {
@@ -882,7 +831,7 @@ async fn explain_install(options: &InstallOptions) -> Result<()> {
         }
 
         // Apply version filtering using the same logic as install
-        let selected_version = select_gem_version(gem_name, None, &versions, options, &client)?;
+        let selected_version = select_gem_version(gem_name, None, &versions, options)?;
 
         println!("  - {} ({})", gem_name, selected_version.number);
 
@@ -900,30 +849,22 @@ fn select_gem_version(
     version_requirement: Option<&str>,
     versions: &[lode::rubygems_client::GemVersion],
     options: &InstallOptions,
-    client: &RubyGemsClient,
 ) -> Result<lode::rubygems_client::GemVersion> {
     let mut filtered_versions = versions.to_vec();
 
     // Filter by version constraint (use parameter first, then options)
     let version_req = version_requirement.or(options.version.as_deref());
     if let Some(version_req) = version_req {
-        // Parse version requirement using Resolver (supports ~>, >=, <, etc.)
-        let resolver = Resolver::new(client.clone());
-        let range = resolver
-            .parse_version_requirement(gem_name, version_req)
-            .context(format!(
-                "Invalid version requirement '{version_req}' for gem '{gem_name}'"
-            ))?;
-
-        // Filter versions that match the requirement
-        filtered_versions.retain(|v| {
-            Resolver::parse_semantic_version(&v.number)
-                .is_ok_and(|sem_ver| range.contains(&sem_ver))
-        });
+        if !gem_utils::is_valid_requirement(version_req) {
+            anyhow::bail!("Invalid version requirement '{version_req}' for gem '{gem_name}'");
+        }
+        filtered_versions.retain(|v| gem_utils::requirement_satisfied(&v.number, version_req));
     }
 
-    // Filter by prerelease (check if version contains "-" which indicates prerelease)
-    if !options.prerelease {
+    // Prereleases only match when requested or named by the requirement
+    let allow_prerelease =
+        options.prerelease || version_req.is_some_and(gem_utils::requirement_allows_prerelease);
+    if !allow_prerelease {
         filtered_versions.retain(|v| !is_prerelease(&v.number));
     }
 
@@ -932,18 +873,16 @@ fn select_gem_version(
         filtered_versions.retain(|v| v.platform == *platform);
     }
 
-    if filtered_versions.is_empty() {
-        anyhow::bail!(
-            "No matching version found for gem '{}' with constraints: version={:?}, prerelease={}, platform={:?}",
-            gem_name,
-            version_req,
-            options.prerelease,
-            options.platform
-        );
-    }
-
-    // Return first (latest) version from filtered list (cloned)
-    Ok(filtered_versions.first().unwrap().clone())
+    // Newest matching version wins
+    filtered_versions
+        .into_iter()
+        .max_by(|a, b| gem_utils::compare_versions(&a.number, &b.number))
+        .with_context(|| {
+            format!(
+                "No matching version found for gem '{}' with constraints: version={:?}, prerelease={}, platform={:?}",
+                gem_name, version_req, options.prerelease, options.platform
+            )
+        })
 }
 
 /// Create a lock file with installed gem versions
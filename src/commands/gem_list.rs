@@ -3,8 +3,14 @@
 //! List installed gems
 
 use anyhow::{Context, Result};
-use lode::gem_store::GemStore;
-use lode::{Config, RubyGemsClient};
+use futures_util::future::join_all;
+use lode::gem_store::{GemPath, GemQuery};
+use lode::gem_utils::{
+    compare_versions, is_valid_requirement, requirement_allows_prerelease, requirement_satisfied,
+};
+use lode::rubygems_client::{BulkGemSpec, SEARCH_PAGE_SIZE, SearchHit};
+use lode::{Config, RubyGemsClient, config};
+use regex::{Regex, RegexBuilder};
 use std::process;
 
 /// Options for gem list command
@@ -16,6 +22,8 @@ pub(crate) struct ListOptions<'a> {
     pub details: bool,
     pub versions: bool,
     pub all: bool,
+    /// List every copy in every `GEM_PATH` directory with its location
+    pub all_paths: bool,
     pub exact: bool,
     pub prerelease: bool,
     pub update_sources: bool,
@@ -24,8 +32,10 @@ pub(crate) struct ListOptions<'a> {
     pub remote: bool,
     pub both: bool,
     pub bulk_threshold: usize,
-    pub clear_sources: bool,
-    pub source: Option<&'a str>,
+    /// Narrow candidate names with the search API instead of `/names`
+    pub search_api: bool,
+    /// Remote sources in priority order, after `-s` and `--clear-sources`
+    pub sources: Vec<String>,
     pub http_proxy: Option<&'a str>,
     pub verbose: bool,
     pub quiet: bool,
@@ -61,13 +71,8 @@ pub(crate) async fn run(options: ListOptions<'_>) -> Result<()> {
         );
     }
 
-    // Handle --clear-sources flag
-    if options.clear_sources {
-        // --clear-sources silently clears sources and continues listing
-        // No special output is printed
-        if options.debug {
-            eprintln!("DEBUG: --clear-sources flag set (sources cleared)");
-        }
+    if options.debug {
+        eprintln!("DEBUG: Sources: {}", options.sources.join(", "));
     }
 
     // Handle --bulk-threshold flag
@@ -121,7 +126,7 @@ pub(crate) async fn run(options: ListOptions<'_>) -> Result<()> {
 
 /// Check if a gem is installed (--installed flag)
 fn check_installed(options: &ListOptions<'_>) -> Result<()> {
-    let store = GemStore::new()?;
+    let gem_path = GemPath::from_env(&config::ruby_version(None));
     let pattern = options.pattern.unwrap_or("");
 
     if pattern.is_empty() {
@@ -130,18 +135,19 @@ fn check_installed(options: &ListOptions<'_>) -> Result<()> {
     }
 
     // Find matching gems
-    let gems = store.find_gems(Some(pattern))?;
-
-    // Filter by exact match if requested
-    let gems: Vec<_> = if options.exact {
-        gems.into_iter().filter(|g| g.name == pattern).collect()
+    let query = if options.exact {
+        GemQuery::new().with_name(pattern)
     } else {
-        gems
+        GemQuery::new().with_pattern(pattern)
     };
+    let gems = gem_path.query(&query)?;
 
     // Filter by version if specified
     let gems: Vec<_> = if let Some(version) = options.version {
-        gems.into_iter().filter(|g| g.version == version).collect()
+        ensure_valid_requirement(version)?;
+        gems.into_iter()
+            .filter(|g| requirement_satisfied(&g.version, version))
+            .collect()
     } else {
         gems
     };
@@ -181,18 +187,25 @@ fn list_local_gems(options: &ListOptions<'_>) -> Result<()> {
         return Ok(());
     }
 
-    let store = GemStore::new()?;
-    let mut gems = store.find_gems(options.pattern)?;
+    let gem_path = GemPath::from_env(&config::ruby_version(None));
+    let mut query = match options.pattern {
+        Some(pattern) if options.exact => GemQuery::new().with_name(pattern),
+        Some(pattern) => GemQuery::new().with_pattern(pattern),
+        None => GemQuery::new(),
+    };
+    if options.all_paths {
+        query = query.with_shadowed();
+    }
+    let mut gems = gem_path.query(&query)?;
 
-    // Filter by exact match if requested
-    if options.exact
-        && let Some(pattern) = options.pattern
-    {
-        gems.retain(|g| g.name == pattern);
+    // Filter by version requirement if specified
+    if let Some(version) = options.version {
+        ensure_valid_requirement(version)?;
+        gems.retain(|g| requirement_satisfied(&g.version, version));
     }
 
     // Filter by prerelease
-    if !options.prerelease {
+    if !options.prerelease && !options.version.is_some_and(requirement_allows_prerelease) {
         gems.retain(|g| !is_prerelease(&g.version));
     }
 
@@ -227,6 +240,9 @@ fn list_local_gems(options: &ListOptions<'_>) -> Result<()> {
         for name in names {
             println!("{name}");
         }
+    } else if options.all_paths {
+        // Show every copy and the directory it lives in
+        display_all_paths(&gems, options);
     } else if options.details {
         // Show detailed information
         display_detailed_gems(&gems, options);
@@ -246,6 +262,12 @@ fn list_local_gems(options: &ListOptions<'_>) -> Result<()> {
 }
 
 /// List remote gems from RubyGems.org
+///
+/// Patterns are regular expressions matched case-insensitively, as in
+/// `gem list --remote`. Candidate names come from the compact index `/names`
+/// list (or, with `--search-api`, the search API) and are matched here.
+/// Versions are then fetched per matching gem; past `--bulk-threshold`
+/// matches, the full index is read once instead.
 async fn list_remote_gems(options: &ListOptions<'_>) -> Result<()> {
     if options.silent {
         return Ok(());
@@ -260,33 +282,27 @@ async fn list_remote_gems(options: &ListOptions<'_>) -> Result<()> {
         return Ok(());
     }
 
-    // Use custom source if provided, otherwise default to RUBYGEMS_HOST env var (or rubygems.org)
-    let base_url = options.source.map_or_else(
-        lode::env_vars::rubygems_host,
-        std::string::ToString::to_string,
-    );
+    let matcher = NameMatcher::new(pattern, options.exact)?;
 
-    // Create RubyGemsClient with optional proxy
-    let client = RubyGemsClient::new_with_proxy(&base_url, options.http_proxy)?;
+    // Query the primary source, otherwise RUBYGEMS_HOST (or rubygems.org)
+    let base_url = options
+        .sources
+        .first()
+        .cloned()
+        .unwrap_or_else(lode::env_vars::rubygems_host);
 
-    // Use bulk index for remote listing (more efficient for pattern matching)
-    let bulk_results = client
-        .search_bulk_index(pattern, options.prerelease)
-        .await
-        .context("Failed to search bulk gem index")?;
+    // Create RubyGemsClient with optional proxy
+    let client = RubyGemsClient::new_with_proxy(&base_url, options.http_proxy)?
+        .with_prerelease(options.prerelease);
 
-    // Filter by exact match if requested
-    let mut results: Vec<_> = if options.exact {
-        bulk_results
-            .into_iter()
-            .filter(|g| g.name == pattern)
-            .collect()
-    } else {
-        bulk_results
-    };
+    let mut results = remote_specs(&client, &matcher, options).await?;
 
-    // Sort by name for consistent output
-    results.sort_by(|a, b| a.name.cmp(&b.name));
+    // Sort by name, newest version first
+    results.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| compare_versions(&b.version, &a.version))
+    });
 
     if results.is_empty() {
         if !options.quiet {
@@ -357,6 +373,257 @@ async fn list_remote_gems(options: &ListOptions<'_>) -> Result<()> {
     Ok(())
 }
 
+/// Gem names a `gem-list` pattern selects
+#[derive(Debug)]
+enum NameMatcher {
+    /// `--exact`: only this name
+    Exact(String),
+    /// A case-insensitive regular expression
+    Pattern(Regex),
+}
+
+impl NameMatcher {
+    fn new(pattern: &str, exact: bool) -> Result<Self> {
+        if exact {
+            return Ok(Self::Exact(pattern.to_string()));
+        }
+        RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map(Self::Pattern)
+            .with_context(|| format!("Invalid pattern: {pattern}"))
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::Exact(exact) => name == exact,
+            Self::Pattern(regex) => regex.is_match(name),
+        }
+    }
+
+    /// Query for the search API, if the pattern has a literal to search for
+    fn search_term(&self) -> Option<String> {
+        match self {
+            Self::Exact(name) => Some(name.clone()),
+            Self::Pattern(regex) => search_term(regex.as_str()),
+        }
+    }
+}
+
+/// Longest literal run every match must contain, lowercased
+///
+/// Runs end at metacharacters. A character followed by `?`, `*` or `{n,m}`
+/// may be absent and is dropped, and text inside groups is skipped. An
+/// alternation can avoid any run, so such patterns have no term. Runs
+/// shorter than two characters are too broad to search for.
+fn search_term(pattern: &str) -> Option<String> {
+    let mut longest = String::new();
+    let mut run = String::new();
+    let mut depth = 0_usize;
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '|' => return None,
+            '\\' => match chars.next() {
+                Some(escaped) if escaped.is_ascii_punctuation() && depth == 0 => {
+                    run.push(escaped);
+                    continue;
+                }
+                _ => {}
+            },
+            '?' | '*' => {
+                run.pop();
+            }
+            '{' => {
+                run.pop();
+                chars.by_ref().find(|&c| c == '}');
+            }
+            '[' => {
+                chars.by_ref().find(|&c| c == ']');
+            }
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            '.' | '^' | '$' | '+' => {}
+            _ if depth == 0 => {
+                run.push(c);
+                continue;
+            }
+            _ => {}
+        }
+        keep_longest(&mut longest, &mut run);
+    }
+    keep_longest(&mut longest, &mut run);
+
+    (longest.chars().count() >= 2).then(|| longest.to_lowercase())
+}
+
+/// Move `run` into `longest` if it is longer, leaving `run` empty
+fn keep_longest(longest: &mut String, run: &mut String) {
+    if run.chars().count() > longest.chars().count() {
+        *longest = std::mem::take(run);
+    } else {
+        run.clear();
+    }
+}
+
+/// Every remote version `matcher` selects
+///
+/// Search API hits stand for their latest release; otherwise, and for
+/// `--all` and `--prerelease`, version lists are fetched per gem. Without
+/// `--all`, only the newest version of each platform is kept.
+async fn remote_specs(
+    client: &RubyGemsClient,
+    matcher: &NameMatcher,
+    options: &ListOptions<'_>,
+) -> Result<Vec<BulkGemSpec>> {
+    let candidates = match matcher.search_term() {
+        Some(term) if options.search_api => {
+            search_matches(client, &term, matcher, options.bulk_threshold).await?
+        }
+        _ => name_matches(client, matcher, options.bulk_threshold).await,
+    };
+    let Some(candidates) = candidates else {
+        lode::debug!("listing remote gems from the full index");
+        let index = client
+            .fetch_bulk_index(options.prerelease)
+            .await
+            .context("Failed to fetch the full gem index")?;
+        return Ok(index
+            .into_iter()
+            .filter(|spec| matcher.matches(&spec.name))
+            .collect());
+    };
+
+    if !options.all && !options.prerelease {
+        let mut specs = Vec::new();
+        let mut unversioned = Vec::new();
+        for candidate in candidates {
+            match candidate {
+                Candidate::Hit(hit) => specs.push(BulkGemSpec {
+                    name: hit.name,
+                    version: hit.version,
+                    platform: hit.platform,
+                }),
+                Candidate::Name(name) => unversioned.push(name),
+            }
+        }
+        specs.extend(version_specs(client, &unversioned, false, false).await?);
+        return Ok(specs);
+    }
+
+    let names: Vec<String> = candidates.into_iter().map(Candidate::into_name).collect();
+    version_specs(client, &names, options.all, options.prerelease).await
+}
+
+/// A remote gem selected for listing
+#[derive(Debug)]
+enum Candidate {
+    /// A search API hit, which carries its latest version
+    Hit(SearchHit),
+    /// A name from `/names`, whose versions are still to be fetched
+    Name(String),
+}
+
+impl Candidate {
+    fn into_name(self) -> String {
+        match self {
+            Self::Hit(hit) => hit.name,
+            Self::Name(name) => name,
+        }
+    }
+}
+
+/// Versions of each gem in `names`: every one if `all`, else the latest
+/// for each platform, `ruby` first; prereleases only if `prerelease`, as in
+/// the full index
+async fn version_specs(
+    client: &RubyGemsClient,
+    names: &[String],
+    all: bool,
+    prerelease: bool,
+) -> Result<Vec<BulkGemSpec>> {
+    let lists = join_all(names.iter().map(|name| client.fetch_versions(name))).await;
+    let mut specs = Vec::new();
+    for (name, versions) in names.iter().zip(lists) {
+        let versions = versions.with_context(|| format!("Failed to fetch versions of {name}"))?;
+        let mut versions: Vec<BulkGemSpec> = versions
+            .into_iter()
+            .filter(|version| is_prerelease(&version.number) == prerelease)
+            .map(|version| BulkGemSpec {
+                name: name.clone(),
+                version: version.number,
+                platform: if version.platform.is_empty() {
+                    "ruby".to_string()
+                } else {
+                    version.platform
+                },
+            })
+            .collect();
+        if !all {
+            versions.sort_by(|a, b| {
+                compare_versions(&b.version, &a.version)
+                    .then_with(|| (b.platform == "ruby").cmp(&(a.platform == "ruby")))
+            });
+            let mut platforms = std::collections::HashSet::new();
+            versions.retain(|spec| platforms.insert(spec.platform.clone()));
+        }
+        specs.extend(versions);
+    }
+    Ok(specs)
+}
+
+/// Names from the source's `/names` list that match, or `None` past
+/// `threshold` matches or when the source has no compact index
+async fn name_matches(
+    client: &RubyGemsClient,
+    matcher: &NameMatcher,
+    threshold: usize,
+) -> Option<Vec<Candidate>> {
+    let names = match client.fetch_names().await {
+        Ok(names) => names,
+        Err(e) => {
+            lode::debug!("no /names list ({e}), falling back to the full index");
+            return None;
+        }
+    };
+    let matches: Vec<Candidate> = names
+        .into_iter()
+        .filter(|name| matcher.matches(name))
+        .map(Candidate::Name)
+        .collect();
+    if matches.len() > threshold {
+        lode::debug!("{} gems match, more than {threshold}", matches.len());
+        return None;
+    }
+    Some(matches)
+}
+
+/// Search hits whose names match, or `None` past `threshold` results
+async fn search_matches(
+    client: &RubyGemsClient,
+    term: &str,
+    matcher: &NameMatcher,
+    threshold: usize,
+) -> Result<Option<Vec<Candidate>>> {
+    let mut hits = Vec::new();
+    for page in 1..=threshold.div_ceil(SEARCH_PAGE_SIZE) {
+        let found = client
+            .search(term, page)
+            .await
+            .with_context(|| format!("Failed to search for {term}"))?;
+        let last_page = found.len() < SEARCH_PAGE_SIZE;
+        hits.extend(found.into_iter().filter(|hit| matcher.matches(&hit.name)));
+        if last_page {
+            hits.sort_by(|a, b| a.name.cmp(&b.name));
+            hits.dedup_by(|a, b| a.name == b.name);
+            return Ok(Some(hits.into_iter().map(Candidate::Hit).collect()));
+        }
+    }
+    lode::debug!("more than {threshold} gems match {term}");
+    Ok(None)
+}
+
 /// Display gems with detailed information
 fn display_detailed_gems(gems: &[lode::gem_store::InstalledGem], _options: &ListOptions<'_>) {
     let mut current_name: Option<String> = None;
@@ -396,7 +663,9 @@ fn display_detailed_gems(gems: &[lode::gem_store::InstalledGem], _options: &List
                 }
             }
 
-            println!("    Installed at: {}", gem.path.display());
+            if let Some(gem_home) = gem.gem_home() {
+                println!("    Installed at: {}", gem_home.display());
+            }
 
             if gem.platform != "ruby" {
                 println!("    Platform: {}", gem.platform);
@@ -428,22 +697,49 @@ fn display_all_versions(gems: &[lode::gem_store::InstalledGem], options: &ListOp
     }
 }
 
-/// Display only the latest version of each gem (default behavior)
-fn display_latest_versions(gems: &[lode::gem_store::InstalledGem], _options: &ListOptions<'_>) {
-    let mut seen: std::collections::HashSet<String> = std::collections::HashSet::new();
+/// Display every installed copy grouped by gem, with its `GEM_PATH` entry
+fn display_all_paths(gems: &[lode::gem_store::InstalledGem], options: &ListOptions<'_>) {
+    let mut current_name: Option<String> = None;
 
     for gem in gems {
-        if seen.insert(gem.name.clone()) {
-            // First occurrence (latest version due to GemStore sorting)
-            if gem.platform == "ruby" {
-                println!("{} ({})", gem.name, gem.version);
-            } else {
-                println!("{} ({}, {})", gem.name, gem.version, gem.platform);
+        if current_name.as_ref() != Some(&gem.name) {
+            current_name = Some(gem.name.clone());
+            if !options.quiet {
+                println!("\n{}", gem.name);
             }
         }
+
+        let location = gem.gem_home().map_or_else(
+            || gem.path.display().to_string(),
+            |home| home.display().to_string(),
+        );
+        if gem.platform == "ruby" {
+            println!("    {} [{location}]", gem.version);
+        } else {
+            println!("    {} ({}) [{location}]", gem.version, gem.platform);
+        }
+    }
+}
+
+/// Display only the latest version of each gem (default behavior)
+fn display_latest_versions(gems: &[lode::gem_store::InstalledGem], _options: &ListOptions<'_>) {
+    for gem in lode::gem_store::latest_versions(gems.to_vec()) {
+        if gem.platform == "ruby" {
+            println!("{} ({})", gem.name, gem.version);
+        } else {
+            println!("{} ({}, {})", gem.name, gem.version, gem.platform);
+        }
     }
 }
 
+/// Reject malformed `-v` requirements before filtering
+fn ensure_valid_requirement(requirement: &str) -> Result<()> {
+    if !is_valid_requirement(requirement) {
+        anyhow::bail!("Invalid version requirement '{requirement}'");
+    }
+    Ok(())
+}
+
 /// Check if a version string is a prerelease
 fn is_prerelease(version: &str) -> bool {
     version.contains('-')
@@ -482,4 +778,190 @@ mod tests {
         assert!(!is_prerelease("1.2.3"));
         assert!(!is_prerelease("10.0.0"));
     }
+
+    /// Finds the literal a search API query must contain
+    #[test]
+    fn search_term_from_patterns() {
+        assert_eq!(search_term("^rails").as_deref(), Some("rails"));
+        assert_eq!(search_term("^Rack-.*test$").as_deref(), Some("rack-"));
+        assert_eq!(search_term("active_?record").as_deref(), Some("active"));
+        assert_eq!(search_term("(ab)?cdef").as_deref(), Some("cdef"));
+        assert_eq!(search_term("net\\.http").as_deref(), Some("net.http"));
+        assert_eq!(search_term("rack|rails"), None);
+        assert_eq!(search_term("^a.*"), None);
+    }
+
+    /// Patterns match case-insensitively unless exact
+    #[test]
+    fn name_matcher_modes() {
+        let pattern = NameMatcher::new("^RAILS", false).unwrap();
+        assert!(pattern.matches("rails-html-sanitizer"));
+        assert!(!pattern.matches("sprockets-rails"));
+
+        let exact = NameMatcher::new("rails", true).unwrap();
+        assert!(exact.matches("rails"));
+        assert!(!exact.matches("rails-html-sanitizer"));
+
+        assert!(NameMatcher::new("(", false).is_err());
+    }
+
+    /// Serve `/names` and version lists, recording each request path
+    async fn serve_index(paths: std::sync::Arc<std::sync::Mutex<Vec<String>>>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while let Ok(count @ 1..) = stream.read(&mut buffer).await {
+                    request.extend_from_slice(buffer.get(..count).unwrap_or_default());
+                    if request.windows(4).any(|window| window == b"\r\n\r\n") {
+                        break;
+                    }
+                }
+                let request = String::from_utf8_lossy(&request);
+                let path = request
+                    .split_whitespace()
+                    .nth(1)
+                    .unwrap_or_default()
+                    .to_string();
+                let body = match path.as_str() {
+                    "/names" => "---\nrack\nrails\nrails-html-sanitizer\n".to_string(),
+                    "/api/v1/versions/rails.json" => {
+                        r#"[{"number":"8.0.1","platform":"ruby"},{"number":"8.0.0","platform":"ruby"}]"#
+                            .to_string()
+                    }
+                    "/api/v1/versions/rails-html-sanitizer.json" => {
+                        r#"[{"number":"1.6.2","platform":"ruby"}]"#.to_string()
+                    }
+                    "/api/v1/versions/rack.json" => [
+                        r#"[{"number":"3.2.0.rc1","platform":"ruby"},"#,
+                        r#"{"number":"3.1.0","platform":"java"},"#,
+                        r#"{"number":"3.1.0","platform":"ruby"},"#,
+                        r#"{"number":"3.1.0.beta1","platform":"ruby"},"#,
+                        r#"{"number":"3.0.0","platform":"x86_64-linux"},"#,
+                        r#"{"number":"3.0.0","platform":"ruby"}]"#,
+                    ]
+                    .concat(),
+                    _ => String::new(),
+                };
+                paths.lock().unwrap().push(path);
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                stream.write_all(head.as_bytes()).await.ok();
+                stream.write_all(body.as_bytes()).await.ok();
+            }
+        });
+        format!("http://{address}")
+    }
+
+    /// Quiet remote listing options against `base_url`
+    fn list_options(base_url: &str) -> ListOptions<'static> {
+        ListOptions {
+            pattern: None,
+            installed: None,
+            version: None,
+            details: false,
+            versions: false,
+            all: false,
+            all_paths: false,
+            exact: false,
+            prerelease: false,
+            update_sources: false,
+            local: false,
+            remote: true,
+            both: false,
+            bulk_threshold: 1000,
+            search_api: false,
+            sources: vec![base_url.to_string()],
+            http_proxy: None,
+            verbose: false,
+            quiet: true,
+            silent: false,
+            config_file: None,
+            backtrace: false,
+            debug: false,
+            norc: true,
+        }
+    }
+
+    /// Names come from `/names`, so a pattern inside a word still matches
+    /// and the search API is never asked
+    #[tokio::test]
+    async fn remote_names_match_inside_words() {
+        let paths = std::sync::Arc::default();
+        let base_url = serve_index(std::sync::Arc::clone(&paths)).await;
+        let client = RubyGemsClient::new(&base_url).unwrap();
+        let matcher = NameMatcher::new("ails", false).unwrap();
+        let options = ListOptions {
+            pattern: Some("ails"),
+            ..list_options(&base_url)
+        };
+
+        let mut specs = remote_specs(&client, &matcher, &options).await.unwrap();
+        specs.sort_by(|a, b| a.name.cmp(&b.name));
+        let listed: Vec<(&str, &str)> = specs
+            .iter()
+            .map(|spec| (spec.name.as_str(), spec.version.as_str()))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![("rails", "8.0.1"), ("rails-html-sanitizer", "1.6.2")]
+        );
+
+        let paths = paths.lock().unwrap().clone();
+        assert_eq!(paths.first().map(String::as_str), Some("/names"));
+        assert!(!paths.iter().any(|path| path.contains("search")));
+    }
+
+    /// Without `--all`, each platform's newest version is listed, `ruby`
+    /// first; `--prerelease` lists only the newest prerelease
+    #[tokio::test]
+    async fn remote_latest_per_platform() {
+        let base_url = serve_index(std::sync::Arc::default()).await;
+        let matcher = NameMatcher::new("rack", true).unwrap();
+        let listed = |specs: Vec<BulkGemSpec>| {
+            specs
+                .into_iter()
+                .map(|spec| (spec.version, spec.platform))
+                .collect::<Vec<_>>()
+        };
+
+        let client = RubyGemsClient::new(&base_url).unwrap();
+        let specs = version_specs(&client, &["rack".to_string()], false, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            listed(specs),
+            [
+                ("3.1.0".to_string(), "ruby".to_string()),
+                ("3.1.0".to_string(), "java".to_string()),
+                ("3.0.0".to_string(), "x86_64-linux".to_string()),
+            ]
+        );
+
+        let client = RubyGemsClient::new(&base_url)
+            .unwrap()
+            .with_prerelease(true);
+        let specs = remote_specs(
+            &client,
+            &matcher,
+            &ListOptions {
+                pattern: Some("rack"),
+                exact: true,
+                prerelease: true,
+                ..list_options(&base_url)
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            listed(specs),
+            [("3.2.0.rc1".to_string(), "ruby".to_string())]
+        );
+    }
 }
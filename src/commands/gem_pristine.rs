@@ -262,6 +262,11 @@ fn restore_gem(
     cache_dir: &std::path::Path,
     options: &PristineOptions,
 ) -> Result<()> {
+    // Only rewrite the plugin loader, which needs no cached .gem
+    if options.only_plugins {
+        return restore_plugin(gem, options);
+    }
+
     // Find cached .gem file
     let gem_file = format!("{}-{}.gem", gem.name, gem.version);
     let cached_gem_path = cache_dir.join(&gem_file);
@@ -286,24 +291,13 @@ fn restore_gem(
         return Ok(());
     }
 
-    // Only restore plugins
-    if options.only_plugins {
-        if options.verbose {
-            println!("    Restoring plugins only...");
-        }
-        extract_specific_directories(&cached_gem_path, &gem.path, &["plugins/"], options)?;
-        if options.verbose {
-            println!("    Plugins restored");
-        }
-        return Ok(());
-    }
-
     // Extract gem file over existing installation
     if options.verbose {
         println!("    Extracting from cache: {}", cached_gem_path.display());
     }
 
     extract_gem_to_directory(&cached_gem_path, &gem.path, options)?;
+    restore_plugin(gem, options)?;
 
     // Rebuild extensions if requested
     if options.extensions || options.only_missing_extensions {
@@ -357,6 +351,21 @@ fn restore_gem(
     Ok(())
 }
 
+/// Rewrite the gem's `RubyGems` plugin loader in `<gem_home>/plugins`
+fn restore_plugin(gem: &GemInfo, options: &PristineOptions) -> Result<()> {
+    let Some(gem_home) = gem.path.parent().and_then(Path::parent) else {
+        return Ok(());
+    };
+    let loader = lode::rubygems_plugins::regenerate(gem_home, &gem.name)?;
+    if options.verbose {
+        match loader {
+            Some(loader) => println!("    Restored plugin loader: {}", loader.display()),
+            None => println!("    No plugins to restore"),
+        }
+    }
+    Ok(())
+}
+
 /// Extract a .gem file to a directory
 fn extract_gem_to_directory(
     gem_path: &PathBuf,
@@ -427,8 +436,8 @@ fn extract_gem_to_directory(
 
 /// Extract specific directories from a .gem file
 ///
-/// Used for --only-executables and --only-plugins flags to restore only specific
-/// parts of a gem installation.
+/// Used for the --only-executables flag to restore only specific parts of a
+/// gem installation.
 fn extract_specific_directories(
     gem_path: &Path,
     dest_dir: &Path,
@@ -1,64 +1,146 @@
 //! Rdoc command
 //!
-//! Generate `RDoc` documentation for installed gems
+//! Generate `RDoc` documentation for installed gems, one at a time or with
+//! `--all` for every installed gem. Output goes to the standard `RubyGems`
+//! layout described in [`lode::docs`].
 
 use anyhow::{Context, Result};
-use lode::gem_store::GemStore;
+use indicatif::{ProgressBar, ProgressStyle};
+use lode::docs::{self, DocLayout, DocOutcome, DocPolicy};
+use lode::gem_store::{GemStore, InstalledGem};
 use std::process::Command;
 
-/// Generate `RDoc` documentation for a gem
-pub(crate) fn run(gem: Option<&str>) -> Result<()> {
-    let gem_name = gem.context("Gem name required. Usage: lode gem-rdoc <GEM>")?;
+/// Options for gem rdoc command
+#[derive(Debug)]
+pub(crate) struct RdocOptions {
+    /// Gem to document, unless `all` is set
+    pub gem: Option<String>,
+    /// Document every installed gem
+    pub all: bool,
+    /// Specific installed version to document
+    pub version: Option<String>,
+    /// Which kinds of documentation to generate
+    pub policy: DocPolicy,
+    /// Regenerate documentation that already exists
+    pub overwrite: bool,
+    pub quiet: bool,
+}
+
+/// Generate `RDoc` documentation for a gem or for all installed gems
+pub(crate) fn run(options: &RdocOptions) -> Result<()> {
+    if options.policy.is_empty() {
+        anyhow::bail!("Nothing to generate: both --no-rdoc and --no-ri were given");
+    }
+
+    // Check if rdoc is available
+    if Command::new("rdoc").arg("--version").output().is_err() {
+        anyhow::bail!("rdoc command not found. Install it with: gem install rdoc");
+    }
 
     let store = GemStore::new()?;
-    let gems = store.find_gem_by_name(gem_name)?;
+    if options.all {
+        return run_all(&store.list_gems()?, options);
+    }
 
-    if gems.is_empty() {
-        anyhow::bail!("Gem '{gem_name}' not found");
+    let gem_name = options
+        .gem
+        .as_deref()
+        .context("Gem name required. Usage: lode gem-rdoc <GEM> or lode gem-rdoc --all")?;
+    let mut gems = store.find_gem_by_name(gem_name)?;
+    if let Some(version) = &options.version {
+        gems.retain(|gem| &gem.version == version);
     }
 
     // Use the latest version if multiple are installed
-    let gem_info = gems
-        .last()
-        .context(format!("No versions found for gem '{gem_name}'"))?;
-
-    println!(
-        "Generating RDoc for {} ({})...",
-        gem_info.name, gem_info.version
-    );
+    let gem_info = gems.pop().with_context(|| {
+        options.version.as_ref().map_or_else(
+            || format!("Gem '{gem_name}' not found"),
+            |version| format!("Gem '{gem_name}' ({version}) not found"),
+        )
+    })?;
 
-    // Check if rdoc is available
-    let rdoc_check = Command::new("rdoc").arg("--version").output();
+    if !options.quiet {
+        println!(
+            "Generating {} documentation for {} ({})...",
+            options.policy.describe(),
+            gem_info.name,
+            gem_info.version
+        );
+    }
 
-    if rdoc_check.is_err() {
-        anyhow::bail!("rdoc command not found. Install it with: gem install rdoc");
+    match docs::generate(&gem_info.path, options.policy, options.overwrite, true)? {
+        DocOutcome::Generated => {
+            if !options.quiet {
+                let rdoc_dir =
+                    DocLayout::for_gem_dir(&gem_info.path).rdoc_dir(&full_name(&gem_info));
+                println!("Documentation generated in {}", rdoc_dir.display());
+                if options.policy.rdoc {
+                    println!("View with: open {}/index.html", rdoc_dir.display());
+                }
+            }
+            Ok(())
+        }
+        DocOutcome::Skipped(reason) => {
+            if !options.quiet {
+                println!("Skipped: {reason} (use --overwrite to regenerate)");
+            }
+            Ok(())
+        }
+        DocOutcome::Failed(reason) => anyhow::bail!("{reason}"),
     }
+}
 
-    // Generate documentation
-    let status = Command::new("rdoc")
-        .arg("--ri")
-        .arg("--op")
-        .arg(format!("doc/{}", gem_info.name))
-        .current_dir(&gem_info.path)
-        .status()
-        .context("Failed to run rdoc command")?;
+/// Document every installed gem, with a progress bar
+fn run_all(gems: &[InstalledGem], options: &RdocOptions) -> Result<()> {
+    let progress = if options.quiet {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(gems.len() as u64)
+    };
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}")
+            .unwrap_or_else(|_| ProgressStyle::default_bar())
+            .progress_chars("#>-"),
+    );
 
-    if !status.success() {
-        anyhow::bail!("rdoc command failed with status: {status}");
+    let mut generated = 0;
+    let mut skipped = 0;
+    let mut failures = Vec::new();
+    for gem in gems {
+        progress.set_message(full_name(gem));
+        match docs::generate(&gem.path, options.policy, options.overwrite, true) {
+            Ok(DocOutcome::Generated) => generated += 1,
+            Ok(DocOutcome::Skipped(_)) => skipped += 1,
+            Ok(DocOutcome::Failed(reason)) => failures.push((full_name(gem), reason)),
+            Err(e) => failures.push((full_name(gem), e.to_string())),
+        }
+        progress.inc(1);
     }
+    progress.finish_and_clear();
 
-    println!(
-        "Documentation generated in {}/doc/{}",
-        gem_info.path.display(),
-        gem_info.name
-    );
-    println!(
-        "View with: open {}/doc/{}/index.html",
-        gem_info.path.display(),
-        gem_info.name
-    );
+    for (gem, reason) in &failures {
+        eprintln!("  FAIL {gem} - {reason}");
+    }
+    if !options.quiet {
+        println!(
+            "Generated documentation for {generated} gem(s), skipped {skipped}, failed {}",
+            failures.len()
+        );
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        anyhow::bail!("Documentation failed for {} gem(s)", failures.len())
+    }
+}
 
-    Ok(())
+/// Directory name of an installed gem, like `nokogiri-1.16.0-x86_64-linux`
+fn full_name(gem: &InstalledGem) -> String {
+    gem.path.file_name().map_or_else(
+        || format!("{}-{}", gem.name, gem.version),
+        |name| name.to_string_lossy().into_owned(),
+    )
 }
 
 #[cfg(test)]
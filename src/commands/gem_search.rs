@@ -2,9 +2,12 @@
 //!
 //! Search for gems locally and on RubyGems.org
 
+use super::search::{format_downloads, format_release};
 use anyhow::{Context, Result};
+use futures_util::stream::{self, StreamExt};
 use lode::gem_store::GemStore;
 use lode::{Config, RubyGemsClient};
+use std::collections::HashMap;
 
 /// Options for gem search command
 pub(crate) struct SearchOptions {
@@ -22,8 +25,8 @@ pub(crate) struct SearchOptions {
     pub remote: bool,
     pub both: bool,
     pub bulk_threshold: usize,
-    pub clear_sources: bool,
-    pub source: Option<String>,
+    /// Remote sources in priority order, after `-s` and `--clear-sources`
+    pub sources: Vec<String>,
     pub http_proxy: Option<String>,
     pub verbose: bool,
     pub quiet: bool,
@@ -59,13 +62,8 @@ pub(crate) async fn run(options: SearchOptions) -> Result<()> {
         );
     }
 
-    // Handle --clear-sources flag
-    if options.clear_sources {
-        // --clear-sources in gem search silently clears sources and continues
-        // No special output is printed, the search continues normally
-        if options.debug {
-            eprintln!("DEBUG: --clear-sources flag set (sources cleared)");
-        }
+    if options.debug {
+        eprintln!("DEBUG: Sources: {}", options.sources.join(", "));
     }
 
     // Handle --bulk-threshold flag
@@ -150,7 +148,7 @@ fn check_installed(options: &SearchOptions) -> Result<()> {
     let matches: Vec<_> = if let Some(ref version_req) = options.version {
         matches
             .into_iter()
-            .filter(|g| g.version == *version_req)
+            .filter(|g| lode::gem_utils::requirement_satisfied(&g.version, version_req))
             .collect()
     } else {
         matches
@@ -260,10 +258,11 @@ async fn search_remote_gems(options: &SearchOptions) -> Result<bool> {
         _ => return Ok(false),
     };
 
-    // Use custom source if provided, otherwise use default
+    // Query the primary source, otherwise use default
     let base_url = options
-        .source
-        .clone()
+        .sources
+        .first()
+        .cloned()
         .unwrap_or_else(lode::env_vars::rubygems_host);
 
     if options.debug {
@@ -331,6 +330,19 @@ async fn search_remote_gems(options: &SearchOptions) -> Result<bool> {
         let mut gem_names: Vec<_> = gems_by_name.keys().cloned().collect();
         gem_names.sort();
 
+        // --details adds download counts and release age from the gem API
+        let summaries: HashMap<_, _> = if options.details && !options.versions && !options.all {
+            stream::iter(&gem_names)
+                .map(|name| async { (name.as_str(), client.fetch_gem_summary(name).await.ok()) })
+                .buffer_unordered(8)
+                .filter_map(|(name, summary)| async move { Some((name, summary?)) })
+                .collect()
+                .await
+        } else {
+            HashMap::new()
+        };
+        let now = chrono::Utc::now();
+
         for gem_name in &gem_names {
             let versions = gems_by_name
                 .get(gem_name)
@@ -358,6 +370,22 @@ async fn search_remote_gems(options: &SearchOptions) -> Result<bool> {
                         println!("{} ({}, {})", gem_name, latest.version, latest.platform);
                     }
                 }
+                if let Some(summary) = summaries.get(gem_name.as_str()) {
+                    println!(
+                        "    Downloads: {} total, {} for {}",
+                        format_downloads(summary.downloads),
+                        format_downloads(summary.version_downloads),
+                        summary.version
+                    );
+                    if let Some(released) = summary
+                        .version_created_at
+                        .as_deref()
+                        .and_then(|created_at| format_release(created_at, now))
+                    {
+                        println!("    Released: {released}");
+                    }
+                    println!();
+                }
             }
         }
     }
@@ -82,7 +82,7 @@ fn read_password() -> Result<String> {
         read_password_hidden()?
     } else {
         // On Windows or if hiding fails, just read normally with a warning
-        eprintln!("Warning: Password will be visible");
+        lode::warn!("Password will be visible");
         let mut pass = String::new();
         io::stdin().read_line(&mut pass)?;
         pass
@@ -110,11 +110,9 @@ fn read_password_hidden() -> Result<String> {
                         print!("*");
                         io::stdout().flush()?;
                     }
-                    KeyCode::Backspace => {
-                        if password.pop().is_some() {
-                            print!("\u{8} \u{8}"); // Backspace, space, backspace
-                            io::stdout().flush()?;
-                        }
+                    KeyCode::Backspace if password.pop().is_some() => {
+                        print!("\u{8} \u{8}"); // Backspace, space, backspace
+                        io::stdout().flush()?;
                     }
                     _ => {}
                 }
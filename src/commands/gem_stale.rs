@@ -146,17 +146,15 @@ mod tests {
         assert_eq!(days_since_access(&now), 0);
 
         let two_days_ago = now
-            .checked_sub(std::time::Duration::from_secs(2 * 86_400))
+            .checked_sub(std::time::Duration::from_hours(2 * 24))
             .unwrap();
         assert_eq!(days_since_access(&two_days_ago), 2);
 
-        let one_hour_ago = now
-            .checked_sub(std::time::Duration::from_secs(3600))
-            .unwrap();
+        let one_hour_ago = now.checked_sub(std::time::Duration::from_hours(1)).unwrap();
         assert_eq!(days_since_access(&one_hour_ago), 0);
 
         let thirty_days_ago = now
-            .checked_sub(std::time::Duration::from_secs(30 * 86_400))
+            .checked_sub(std::time::Duration::from_hours(30 * 24))
             .unwrap();
         assert_eq!(days_since_access(&thirty_days_ago), 30);
     }
@@ -1,10 +1,17 @@
 //! Uninstall command
 //!
-//! Remove installed gems
+//! Remove installed gems. As with `gem uninstall`, several installed
+//! versions and no `-v` or `--all` bring up a numbered picker, and removing
+//! the last version asks before deleting its executables unless `-x` or
+//! `--no-executables` answered already.
 
 use anyhow::{Context, Result, anyhow};
-use lode::{Config, gem_store::GemStore};
+use lode::Config;
+use lode::gem_store::{Dependent, GemStore, InstalledGem};
+use lode::gem_utils::{compare_versions, is_valid_requirement, requirement_satisfied};
+use lode::gemspec::Gemspec;
 use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
 
 /// Options for gem uninstall command
 #[derive(Debug, Default)]
@@ -13,7 +20,8 @@ pub(crate) struct UninstallOptions {
     pub ignore_dependencies: bool,
     /// Check development dependencies while uninstalling
     pub check_development: bool,
-    pub executables: bool,
+    /// Remove executables (`-x`), keep them (`--no-executables`), or ask
+    pub executables: Option<bool>,
     /// Directory to uninstall gem from (custom gem directory)
     pub install_dir: Option<String>,
     /// Directory to remove executables from
@@ -111,9 +119,9 @@ fn create_user_gem_store() -> Result<GemStore> {
 }
 
 /// Uninstall a single gem, respecting the provided options
-#[allow(clippy::collapsible_if)]
 fn uninstall_gem(store: &GemStore, gem_name: &str, options: &UninstallOptions) -> Result<u32> {
     let mut matching_gems = store.find_gem_by_name(gem_name)?;
+    let installed_count = matching_gems.len();
 
     if matching_gems.is_empty() {
         return Err(anyhow!("Gem '{gem_name}' is not installed"));
@@ -121,7 +129,10 @@ fn uninstall_gem(store: &GemStore, gem_name: &str, options: &UninstallOptions) -
 
     // Filter by version if specified
     if let Some(ref version) = options.version {
-        matching_gems.retain(|g| &g.version == version);
+        if !is_valid_requirement(version) {
+            return Err(anyhow!("Invalid version requirement '{version}'"));
+        }
+        matching_gems.retain(|g| requirement_satisfied(&g.version, version));
         if matching_gems.is_empty() {
             return Err(anyhow!(
                 "Gem '{gem_name}' version '{version}' is not installed"
@@ -139,73 +150,67 @@ fn uninstall_gem(store: &GemStore, gem_name: &str, options: &UninstallOptions) -
         }
     }
 
-    // Warn about dependencies unless --ignore-dependencies is set
-    if !options.ignore_dependencies {
-        // Check if a Gemfile.lock exists, which might list this gem as a dependency
-        let lockfile_path = std::path::Path::new("Gemfile.lock");
-        if lockfile_path.exists() {
-            eprintln!(
-                "WARNING: Gem '{gem_name}' may be required by other gems. Use --ignore-dependencies to skip this check.",
-            );
-        }
+    // A version requirement or --all removes every match; otherwise ask which
+    if !options.all && options.version.is_none() && matching_gems.len() > 1 {
+        matching_gems = pick_versions(matching_gems, &mut io::stdin().lock(), &mut io::stdout())?;
     }
 
-    // Check for dependent gems if --abort-on-dependent flag is set
-    if options.abort_on_dependent {
-        // Check Gemfile.lock for dependencies on this gem
-        let lockfile_path = std::path::Path::new("Gemfile.lock");
-        if lockfile_path.exists() {
-            if let Ok(lockfile_content) = fs::read_to_string(lockfile_path) {
-                // Simple check: look for the gem name in the lockfile
-                // Full dependency graph analysis would be more complex
-                if lockfile_content.contains(&format!("\n  {gem_name} (")) {
-                    return Err(anyhow!(
-                        "Gem '{gem_name}' is depended on by other gems. Use --ignore-dependencies to force uninstall.",
-                    ));
-                }
+    // Check installed gems that still need this gem unless --ignore-dependencies is set
+    // (development dependents only count with --check-development)
+    if !options.ignore_dependencies {
+        let broken = dependents_left_unsatisfied(
+            store,
+            gem_name,
+            &matching_gems,
+            options.check_development,
+        )?;
+        if !broken.is_empty() {
+            let list = broken
+                .iter()
+                .map(|d| format!("{}-{} ({} {})", d.name, d.version, gem_name, d.requirement))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            // --abort-on-dependent refuses outright; otherwise warn and proceed
+            if options.abort_on_dependent {
+                return Err(anyhow!(
+                    "Gem '{gem_name}' is depended on by: {list}. Use --ignore-dependencies to force uninstall.",
+                ));
             }
+            eprintln!("WARNING: Removing {gem_name} will break: {list}");
         }
     }
 
-    // Check development dependencies if --check-development flag is set
-    if options.check_development {
-        // Look for .gemspec files in current directory and check development dependencies
-        if let Ok(entries) = fs::read_dir(".") {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                    if filename.ends_with(".gemspec") {
-                        // Note: Full .gemspec parsing would require more complex logic
-                        // For now, note that we're checking development dependencies
-                        eprintln!(
-                            "Note: Checking development dependencies in {filename} for {gem_name}"
-                        );
-                    }
-                }
-            }
+    // Executables go with the last installed version, as in RubyGems
+    if matching_gems.len() == installed_count {
+        let mut executables: Vec<String> = matching_gems
+            .iter()
+            .flat_map(installed_executables)
+            .collect();
+        executables.sort();
+        executables.dedup();
+
+        let remove = !executables.is_empty()
+            && match options.executables {
+                Some(remove) => remove,
+                None => ask_remove_executables(&executables)?,
+            };
+        if remove {
+            remove_executables(
+                &executables,
+                options.bindir.as_deref(),
+                options.format_executable,
+            )?;
+        } else if !executables.is_empty() {
+            println!("Executables and scripts will remain installed.");
         }
     }
 
-    // If --all is not specified and there are multiple versions, only uninstall the newest
-    if !options.all && matching_gems.len() > 1 {
-        // Sort by version and keep only the latest
-        matching_gems.sort_by(|a, b| {
-            // Parse versions as semantic versions for proper sorting
-            let a_parts: Vec<u32> = a
-                .version
-                .split('.')
-                .filter_map(|s| s.parse().ok())
-                .collect();
-            let b_parts: Vec<u32> = b
-                .version
-                .split('.')
-                .filter_map(|s| s.parse().ok())
-                .collect();
-
-            b_parts.cmp(&a_parts)
-        });
-        matching_gems.truncate(1);
-    }
+    let mut gem_homes: Vec<std::path::PathBuf> = matching_gems
+        .iter()
+        .filter_map(|gem| gem.gem_home().map(std::path::Path::to_path_buf))
+        .collect();
+    gem_homes.dedup();
 
     // Uninstall all selected gems
     let mut uninstalled_count = 0;
@@ -216,15 +221,6 @@ fn uninstall_gem(store: &GemStore, gem_name: &str, options: &UninstallOptions) -
             version = gem.version
         );
 
-        // Remove executables if --executables flag is set
-        if options.executables {
-            remove_executables(
-                &gem.name,
-                options.bindir.as_deref(),
-                options.format_executable,
-            )?;
-        }
-
         // Remove the gem directory
         fs::remove_dir_all(&gem.path).with_context(|| {
             format!(
@@ -241,12 +237,112 @@ fn uninstall_gem(store: &GemStore, gem_name: &str, options: &UninstallOptions) -
         uninstalled_count += 1;
     }
 
+    if uninstalled_count > 0 {
+        store.invalidate_reverse_dependencies();
+        // The plugin loader moves to the newest remaining version, or goes
+        for gem_home in &gem_homes {
+            lode::rubygems_plugins::regenerate(gem_home, gem_name)?;
+        }
+    }
+
     Ok(uninstalled_count)
 }
 
-/// Remove executables for a gem from the bin directory
-fn remove_executables(
+/// Ask which of several installed versions to remove, like `gem uninstall`
+///
+/// Versions are listed oldest first with a final "All versions" entry. The
+/// answer is read even when `input` is not a terminal, so scripts can pipe
+/// in a choice.
+fn pick_versions(
+    mut gems: Vec<InstalledGem>,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> Result<Vec<InstalledGem>> {
+    gems.sort_by(|a, b| compare_versions(&a.version, &b.version));
+
+    writeln!(output)?;
+    writeln!(output, "Select gem to uninstall:")?;
+    for (number, gem) in gems.iter().enumerate() {
+        writeln!(output, " {}. {}", number + 1, gem.full_name())?;
+    }
+    let all = gems.len() + 1;
+    writeln!(output, " {all}. All versions")?;
+    write!(output, "> ")?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    match answer.trim().parse::<usize>() {
+        Ok(choice) if choice == all => Ok(gems),
+        Ok(choice) if (1..all).contains(&choice) => Ok(vec![gems.swap_remove(choice - 1)]),
+        _ => Err(anyhow!("Must enter a number [1-{all}]")),
+    }
+}
+
+/// Executables declared by an installed gem's spec
+fn installed_executables(gem: &InstalledGem) -> Vec<String> {
+    Gemspec::load_installed(&gem.path)
+        .map(|spec| spec.executables)
+        .unwrap_or_default()
+}
+
+/// Ask whether to remove `executables` along with the gem
+///
+/// Like `RubyGems`, the answer defaults to yes without a terminal to ask on.
+fn ask_remove_executables(executables: &[String]) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        return Ok(true);
+    }
+    confirm_executables(executables, &mut io::stdin().lock(), &mut io::stdout())
+}
+
+fn confirm_executables(
+    executables: &[String],
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> Result<bool> {
+    writeln!(output, "Remove executables:")?;
+    writeln!(output, "\t{}", executables.join(", "))?;
+    writeln!(output)?;
+    write!(output, "in addition to the gem? [Yn]  ")?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(!matches!(answer.trim().to_lowercase().as_str(), "n" | "no"))
+}
+
+/// Installed gems whose requirement on `gem_name` would no longer be
+/// met by any remaining version once `removing` is uninstalled
+fn dependents_left_unsatisfied(
+    store: &GemStore,
     gem_name: &str,
+    removing: &[InstalledGem],
+    include_development: bool,
+) -> Result<Vec<Dependent>> {
+    let index = store.reverse_dependencies()?;
+    let remaining: Vec<_> = store
+        .find_gem_by_name(gem_name)?
+        .into_iter()
+        .filter(|g| !removing.iter().any(|r| r.path == g.path))
+        .collect();
+
+    Ok(index
+        .dependents_of(gem_name)
+        .iter()
+        .filter(|dependent| include_development || !dependent.development)
+        .filter(|dependent| {
+            !remaining
+                .iter()
+                .any(|g| requirement_satisfied(&g.version, &dependent.requirement))
+        })
+        .cloned()
+        .collect())
+}
+
+/// Remove a gem's executables from the bin directory
+fn remove_executables(
+    executables: &[String],
     custom_bindir: Option<&str>,
     format_executable: bool,
 ) -> Result<()> {
@@ -265,38 +361,70 @@ fn remove_executables(
             .join("bin")
     };
 
-    let user_bin_dir = bin_dir;
-
-    // Only attempt to read directory if it exists
-    if user_bin_dir.exists() {
-        // Handle potential I/O errors when reading the directory
-        #[allow(
-            clippy::collapsible_if,
-            reason = "Nested ifs check different conditions: existence vs I/O errors"
-        )]
-        if let Ok(entries) = fs::read_dir(&user_bin_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                    let should_remove = if format_executable {
-                        // With --format-executable, match Ruby's prefix/suffix convention
-                        // Typically: gem_name, gem_name-VERSION, gem_name.rb, etc.
-                        file_name == gem_name
-                            || file_name.starts_with(&format!("{gem_name}-"))
-                            || file_name.starts_with(&format!("{gem_name}."))
-                    } else {
-                        // Without --format-executable, simple prefix matching
-                        file_name.starts_with(gem_name) || file_name == gem_name
-                    };
-
-                    if should_remove {
-                        // Remove files that match gem name pattern
-                        drop(fs::remove_file(&path));
-                    }
-                }
-            }
+    // A missing or unreadable bin directory has nothing to remove
+    let Ok(entries) = fs::read_dir(&bin_dir) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let should_remove = executables.iter().any(|exe| {
+            // With --format-executable, match Ruby's prefix/suffix convention
+            // (`rake`, `rake-3.3`, `rake.rb`)
+            file_name == exe
+                || (format_executable
+                    && (file_name.starts_with(&format!("{exe}-"))
+                        || file_name.starts_with(&format!("{exe}."))))
+        });
+        if should_remove {
+            println!("Removing {file_name}");
+            drop(fs::remove_file(&path));
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installed(version: &str) -> InstalledGem {
+        InstalledGem {
+            name: "rake".to_string(),
+            version: version.to_string(),
+            platform: "ruby".to_string(),
+            path: format!("/gems/rake-{version}").into(),
+        }
+    }
+
+    #[test]
+    fn picker_lists_versions_and_all() {
+        let gems = || vec![installed("13.1.0"), installed("12.3.3")];
+        let mut output = Vec::new();
+
+        let picked = pick_versions(gems(), &mut &b"1\n"[..], &mut output).unwrap();
+        assert_eq!(picked.len(), 1);
+        assert_eq!(picked.first().unwrap().version, "12.3.3");
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\nSelect gem to uninstall:\n 1. rake-12.3.3\n 2. rake-13.1.0\n 3. All versions\n> "
+        );
+
+        let all = pick_versions(gems(), &mut &b"3\n"[..], &mut Vec::new()).unwrap();
+        assert_eq!(all.len(), 2);
+        assert!(pick_versions(gems(), &mut &b"4\n"[..], &mut Vec::new()).is_err());
+        assert!(pick_versions(gems(), &mut &b""[..], &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn executable_confirmation_defaults_to_yes() {
+        let exes = vec!["rake".to_string()];
+        let mut output = Vec::new();
+        assert!(confirm_executables(&exes, &mut &b"\n"[..], &mut output).unwrap());
+        assert!(String::from_utf8(output).unwrap().contains("\trake\n"));
+        assert!(!confirm_executables(&exes, &mut &b"n\n"[..], &mut Vec::new()).unwrap());
+    }
+}
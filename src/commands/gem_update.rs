@@ -3,9 +3,10 @@
 //! Update installed gems to their latest versions
 
 use anyhow::{Context, Result};
+use lode::docs::DocPolicy;
 use lode::gem_store::GemStore;
 use lode::trust_policy::TrustPolicy;
-use lode::{Config, DownloadManager, ExtensionBuilder, GemSpec, RubyGemsClient, config};
+use lode::{Config, DownloadManager, ExtensionBuilder, GemSpec, Gemspec, RubyGemsClient, config};
 use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -46,8 +47,8 @@ pub(crate) struct UpdateOptions {
     pub remote: bool,
     pub both: bool,
     pub bulk_threshold: Option<usize>,
-    pub clear_sources: bool,
-    pub source: Option<String>,
+    /// Remote sources in priority order, after `-s` and `--clear-sources`
+    pub sources: Vec<String>,
     pub http_proxy: Option<String>,
     pub verbose: bool,
     pub quiet: bool,
@@ -68,7 +69,7 @@ pub(crate) async fn run(options: UpdateOptions) -> Result<()> {
     }
 
     // Load config with custom options
-    let _config = Config::load_with_options(options.config_file.as_deref(), options.norc)?;
+    let config = Config::load_with_options(options.config_file.as_deref(), options.norc)?;
 
     // Emit deprecation warning for --default flag
     if options.default {
@@ -77,13 +78,8 @@ pub(crate) async fn run(options: UpdateOptions) -> Result<()> {
         );
     }
 
-    // Handle --clear-sources flag
-    if options.clear_sources {
-        // --clear-sources in gem update silently clears sources and continues
-        // No special output is printed, the update continues normally
-        if options.debug {
-            eprintln!("DEBUG: --clear-sources flag set (sources cleared)");
-        }
+    if options.debug {
+        eprintln!("DEBUG: Sources: {}", options.sources.join(", "));
     }
 
     // Handle --system flag to update RubyGems itself
@@ -214,18 +210,18 @@ pub(crate) async fn run(options: UpdateOptions) -> Result<()> {
     }
 
     let cache_dir = config::cache_dir(None).context("Failed to get cache directory")?;
-    let dm = DownloadManager::new(cache_dir)?;
+    let dm = DownloadManager::with_sources(cache_dir, options.sources.clone())?;
 
     // Determine search scope based on --local/--remote/--both
     let search_local = options.local && !options.remote && !options.both;
     let search_remote = options.remote || options.both || !options.local;
 
-    // Create custom RubyGems client with optional source and proxy
+    // Query the primary source, with optional proxy
     let base_url = options
-        .source
-        .clone()
-        .unwrap_or_else(|| lode::RUBYGEMS_ORG_URL.to_string());
-    let client = RubyGemsClient::new_with_proxy(&base_url, options.http_proxy.as_deref())?;
+        .sources
+        .first()
+        .map_or(lode::RUBYGEMS_ORG_URL, String::as_str);
+    let client = RubyGemsClient::new_with_proxy(base_url, options.http_proxy.as_deref())?;
 
     // Determine bulk API threshold (default: 1000 gems)
     let bulk_threshold = options.bulk_threshold.unwrap_or(1000);
@@ -332,6 +328,18 @@ pub(crate) async fn run(options: UpdateOptions) -> Result<()> {
                                 // Download the gem
                                 match dm.download_gem(&spec).await {
                                     Ok(gem_path) => {
+                                        // Verify signature before anything is written
+                                        if trust_policy != TrustPolicy::NoSecurity
+                                            && let Err(e) =
+                                                verify_gem_signature(&gem_path, trust_policy)
+                                        {
+                                            if !options.silent {
+                                                eprintln!("Failed to verify {gem_name}: {e}");
+                                            }
+                                            skipped_count += 1;
+                                            continue;
+                                        }
+
                                         // Extract to determined directory
                                         let gem_dir = install_dir.join(format!(
                                             "{}-{}",
@@ -347,18 +355,6 @@ pub(crate) async fn run(options: UpdateOptions) -> Result<()> {
                                             }
                                             skipped_count += 1;
                                         } else {
-                                            // Verify signature if trust policy enabled
-                                            if trust_policy != TrustPolicy::NoSecurity
-                                                && verify_gem_signature(&gem_path, trust_policy)
-                                                    .is_err()
-                                            {
-                                                if !options.silent {
-                                                    eprintln!("Failed to verify {gem_name}");
-                                                }
-                                                skipped_count += 1;
-                                                continue;
-                                            }
-
                                             // Build extensions if present
                                             if has_extensions(&gem_dir) && !options.force {
                                                 if options.verbose {
@@ -371,12 +367,17 @@ pub(crate) async fn run(options: UpdateOptions) -> Result<()> {
 
                                             // Install executables if bindir specified
                                             if let Some(bindir) = &options.bindir {
+                                                let gemspec =
+                                                    Gemspec::from_gem_file(&gem_path).ok();
                                                 match install_executables(
-                                                    &gem_dir, bindir, &options,
+                                                    &gem_dir,
+                                                    gemspec.as_ref(),
+                                                    bindir,
+                                                    &options,
                                                 ) {
                                                     Err(e) if options.verbose => {
-                                                        eprintln!(
-                                                            "Warning: Failed to install executables: {e}"
+                                                        lode::warn!(
+                                                            "Failed to install executables: {e}"
                                                         );
                                                     }
                                                     _ => {}
@@ -384,7 +385,7 @@ pub(crate) async fn run(options: UpdateOptions) -> Result<()> {
                                             }
 
                                             // Generate documentation
-                                            generate_documentation(&gem_dir, &spec, &options)?;
+                                            generate_documentation(&gem_dir, &config, &options)?;
 
                                             // Install development dependencies if requested
                                             if (options.development_all
@@ -567,35 +568,6 @@ fn extract_gem(gem_path: &std::path::PathBuf, install_dir: &std::path::PathBuf)
     Ok(())
 }
 
-/// Parse documentation types from --document flag
-fn parse_doc_types(doc_format: Option<&str>, verbose: bool) -> HashSet<&'static str> {
-    let mut types = HashSet::new();
-
-    if let Some(formats) = doc_format {
-        for format in formats.split(',') {
-            match format.trim() {
-                "rdoc" => {
-                    types.insert("rdoc");
-                }
-                "ri" => {
-                    types.insert("ri");
-                }
-                _ => {
-                    if verbose {
-                        println!("  Unknown documentation format: {format}");
-                    }
-                }
-            }
-        }
-    } else {
-        // Default: generate both rdoc and ri if --document is not specified
-        types.insert("rdoc");
-        types.insert("ri");
-    }
-
-    types
-}
-
 /// Check if gem has native extensions
 fn has_extensions(gem_dir: &Path) -> bool {
     let ext_dir = gem_dir.join("ext");
@@ -619,27 +591,38 @@ fn build_extensions(gem_dir: &Path, options: &UpdateOptions) -> Result<()> {
     if let Some(result) = builder.build_if_needed(gem_name, gem_dir, platform)
         && !result.success
     {
-        anyhow::bail!("Failed to build native extensions: {}", result.output);
+        return Err(lode::exit_code::ExitCode::ExtensionBuild.error(format!(
+            "Failed to build native extensions: {}",
+            result.output
+        )));
     }
 
     Ok(())
 }
 
 /// Install gem executables to bin directory
-fn install_executables(gem_dir: &Path, bindir: &str, options: &UpdateOptions) -> Result<()> {
-    let bin_src = gem_dir.join("bin");
-    if !bin_src.exists() {
+///
+/// Installs the executables declared in the gemspec (from its `bindir`),
+/// falling back to everything in `bin/` when no gemspec is available.
+fn install_executables(
+    gem_dir: &Path,
+    gemspec: Option<&Gemspec>,
+    bindir: &str,
+    options: &UpdateOptions,
+) -> Result<()> {
+    let executables = match gemspec {
+        Some(gemspec) => gemspec.executable_paths(gem_dir),
+        None => lode::gemspec::scan_bin_dir(&gem_dir.join("bin"))
+            .context("Failed to read bin directory")?,
+    };
+    if executables.is_empty() {
         return Ok(());
     }
 
     let bin_dest = PathBuf::from(bindir);
     fs::create_dir_all(&bin_dest).context("Failed to create bin directory")?;
 
-    for entry in fs::read_dir(&bin_src).context("Failed to read bin directory")? {
-        let entry = entry?;
-        let file_name = entry.file_name();
-        let src_path = entry.path();
-
+    for (file_name, src_path) in executables {
         // Apply format_executable if requested (adds gem name as suffix)
         let dest_filename = if options.format_executable {
             let gem_name_version = gem_dir
@@ -647,10 +630,9 @@ fn install_executables(gem_dir: &Path, bindir: &str, options: &UpdateOptions) ->
                 .and_then(|n| n.to_str())
                 .unwrap_or("unknown");
 
-            let base_name = file_name.to_str().unwrap_or("unknown");
-            format!("{base_name}-{gem_name_version}")
+            format!("{file_name}-{gem_name_version}")
         } else {
-            file_name.to_string_lossy().to_string()
+            file_name
         };
 
         let dest_path = bin_dest.join(&dest_filename);
@@ -712,113 +694,21 @@ load File.join(gem_dir, 'bin', '{}')
     Ok(())
 }
 
-/// Generate documentation for a gem using `RDoc`
-fn generate_documentation(gem_dir: &Path, spec: &GemSpec, options: &UpdateOptions) -> Result<()> {
-    // Skip if --no-document
-    if options.no_document {
-        return Ok(());
-    }
-
-    let lib_dir = gem_dir.join("lib");
-    if !lib_dir.exists() {
-        if options.verbose {
-            println!("  No lib directory found, skipping documentation");
-        }
-        return Ok(());
-    }
-
-    // Determine what documentation types to generate
-    let doc_types = parse_doc_types(options.document.as_deref(), options.verbose);
-
-    // If no valid documentation types after parsing, skip
-    if doc_types.is_empty() {
-        if options.verbose {
-            println!("  No valid documentation types specified, skipping documentation");
-        }
-        return Ok(());
-    }
-
-    // Determine documentation output directory (for rdoc HTML output)
-    let doc_dir = gem_dir
-        .parent()
-        .context("Invalid gem directory")?
-        .parent()
-        .context("Invalid gem directory structure")?
-        .join("doc")
-        .join(format!("{}-{}", spec.name, spec.version));
-
-    if options.verbose {
-        let types_str = if doc_types.contains("rdoc") && doc_types.contains("ri") {
-            "rdoc and ri"
-        } else if doc_types.contains("rdoc") {
-            "rdoc"
-        } else {
-            "ri"
-        };
-        println!("  Generating {types_str} documentation...");
-    }
-
-    // Create documentation directory if rdoc HTML output is needed
-    if doc_types.contains("rdoc") {
-        fs::create_dir_all(&doc_dir).context("Failed to create documentation directory")?;
-    }
-
-    // Run rdoc to generate documentation
-    let mut cmd = std::process::Command::new("rdoc");
-
-    // Add rdoc HTML output flag if requested
-    if doc_types.contains("rdoc") {
-        cmd.arg("--op").arg(&doc_dir);
-    }
-
-    // Add ri database generation flag if requested
-    if doc_types.contains("ri") {
-        cmd.arg("--ri");
-    }
-
-    // Add the source directory to document
-    cmd.arg(&lib_dir);
-
-    if options.quiet || options.silent {
-        cmd.stdout(std::process::Stdio::null());
-        cmd.stderr(std::process::Stdio::null());
-    }
-
-    // Execute rdoc
-    let output = cmd.output();
-
-    match output {
-        Ok(output) => {
-            if !output.status.success() {
-                if options.verbose {
-                    eprintln!(
-                        "  Warning: Documentation generation failed (rdoc exit code {})",
-                        output.status
-                    );
-                    if !output.stderr.is_empty() {
-                        eprintln!("  rdoc error: {}", String::from_utf8_lossy(&output.stderr));
-                    }
-                }
-                // Don't fail installation if documentation generation fails
-                return Ok(());
-            }
-
-            if options.verbose {
-                println!("  Documentation generated successfully");
-            }
-        }
-        Err(e) => {
-            if options.verbose {
-                eprintln!(
-                    "  Warning: Could not run rdoc ({e}). Skipping documentation generation."
-                );
-                eprintln!("  Install rdoc with: gem install rdoc");
-            }
-            // Don't fail installation if rdoc is not available
-        }
-    }
-
-    Ok(())
+/// Generate documentation for an updated gem, as [`DocPolicy::resolve`] decides
+fn generate_documentation(gem_dir: &Path, config: &Config, options: &UpdateOptions) -> Result<()> {
+    let policy = DocPolicy::resolve(
+        options.document.as_deref(),
+        options.no_document,
+        Some(config),
+        "update",
+    );
+    super::gem_install::document_gem(
+        gem_dir,
+        policy,
+        options.document.as_deref(),
+        options.verbose,
+        options.quiet || options.silent,
+    )
 }
 
 /// Verify gem signature using trust policy
@@ -1,11 +1,24 @@
 //! Which command
 //!
-//! Find the location of a library file
+//! Find the file `require` would load, emulating `RubyGems`' require: a
+//! feature owned by a default gem activates the newest installed copy of that
+//! gem, then `$LOAD_PATH` is searched, then installed gems in
+//! `Gem::Specification` order.
 
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use lode::config;
+use lode::gem_store::{GemPath, GemQuery};
+use lode::gem_utils::compare_versions;
+use lode::gemspec::Gemspec;
+use std::cmp::Ordering;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Shared library extensions `require` tries after `.rb`
+const DLEXTS: &[&str] = &["so", "bundle", "dylib"];
+
 /// Options for the gem which command
 #[derive(Debug)]
 pub(crate) struct WhichOptions {
@@ -27,17 +40,21 @@ pub(crate) fn run(files: &[String], options: &WhichOptions) -> Result<()> {
         anyhow::bail!("Please specify at least one file to find");
     }
 
-    // Get Ruby's load path
-    let load_path = get_ruby_load_path()?;
+    let load_path = LoadPath::detect()?;
 
     if options.verbose && !options.quiet && !options.silent {
-        println!("Searching in {} directories", load_path.len());
+        println!(
+            "Searching {} load path directories, {} installed gems, and {} default gems",
+            load_path.rubylib.len() + load_path.ruby.len(),
+            load_path.gems.len(),
+            load_path.default_gems.len()
+        );
     }
 
     let mut found_any = false;
 
     for file in files {
-        let matches = find_file_in_load_path(file, &load_path, options);
+        let matches = load_path.find(file, options.gems_first);
 
         if matches.is_empty() {
             if !options.silent {
@@ -49,7 +66,7 @@ pub(crate) fn run(files: &[String], options: &WhichOptions) -> Result<()> {
             // Only print results if not silent
             if !options.silent {
                 if options.all {
-                    // Show all matches
+                    // Show every copy, the one `require` loads first
                     for path in matches {
                         println!("{}", path.display());
                     }
@@ -69,147 +86,226 @@ pub(crate) fn run(files: &[String], options: &WhichOptions) -> Result<()> {
     Ok(())
 }
 
-/// Get Ruby's load path ($`LOAD_PATH`) and all gem library paths
-fn get_ruby_load_path() -> Result<Vec<PathBuf>> {
-    // Get both $LOAD_PATH and all gem lib directories using Gem.find_files approach
-    let ruby_code = r"
-require 'rubygems'
-# Get all gem lib directories
-gem_paths = Gem::Specification.map { |spec| spec.full_require_paths }.flatten.uniq
-# Combine with $LOAD_PATH (which includes non-gem dirs)
-all_paths = ($LOAD_PATH + gem_paths).uniq
-puts all_paths
-";
-
-    let output = Command::new("ruby")
-        .args(["-e", ruby_code])
-        .output()
-        .context("Failed to execute ruby command to get load path")?;
-
-    if !output.status.success() {
-        anyhow::bail!(
-            "Ruby command failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
-    }
-
-    let paths = String::from_utf8(output.stdout)
-        .context("Invalid UTF-8 in Ruby output")?
-        .lines()
-        .map(|line| PathBuf::from(line.trim()))
-        .filter(|path| path.exists())
-        .collect();
+/// An installed copy of a gem and the directories it adds to `$LOAD_PATH`
+#[derive(Debug)]
+struct GemEntry {
+    name: String,
+    version: String,
+    require_dirs: Vec<PathBuf>,
+}
 
-    Ok(paths)
+/// A default gem shipped with the interpreter; its files live in Ruby's
+/// own library directories
+#[derive(Debug)]
+struct DefaultGem {
+    name: String,
+    version: String,
+    /// Files relative to the library directory (`json.rb`, `json/ext.rb`)
+    files: Vec<String>,
 }
 
-/// Find a file in the Ruby load path
-fn find_file_in_load_path(
-    file: &str,
-    load_path: &[PathBuf],
-    options: &WhichOptions,
-) -> Vec<PathBuf> {
-    let mut matches = Vec::new();
+/// Everything `require` consults, in precedence order
+#[derive(Debug, Default)]
+struct LoadPath {
+    /// `RUBYLIB` entries, ahead of anything gems add
+    rubylib: Vec<PathBuf>,
+    /// The interpreter's own `$LOAD_PATH` (`site_ruby`, `vendor_ruby`, stdlib)
+    ruby: Vec<PathBuf>,
+    default_gems: Vec<DefaultGem>,
+    /// Every installed copy, by name then newest version first, earlier
+    /// `GEM_PATH` directories first for the same version
+    gems: Vec<GemEntry>,
+}
 
-    // Normalize the file path - remove leading .rb if present for searching
-    let file_base = file.strip_suffix(".rb").unwrap_or(file);
+impl LoadPath {
+    /// Gather the load path from the interpreter and `GEM_PATH`
+    fn detect() -> Result<Self> {
+        let rubylib: Vec<PathBuf> = env::var_os("RUBYLIB")
+            .map(|var| env::split_paths(&var).collect())
+            .unwrap_or_default();
+        let (rubylib, ruby) = interpreter_load_path()?
+            .into_iter()
+            .partition(|dir| rubylib.contains(dir));
+
+        let gem_path = GemPath::from_env(&config::ruby_version(None));
+        let mut gems: Vec<GemEntry> = gem_path
+            .query(&GemQuery::new().with_shadowed())?
+            .into_iter()
+            .map(|gem| GemEntry {
+                require_dirs: require_dirs(&gem.path),
+                name: gem.name,
+                version: gem.version,
+            })
+            .collect();
+        gems.sort_by(|a, b| {
+            a.name
+                .cmp(&b.name)
+                .then_with(|| compare_versions(&b.version, &a.version))
+        });
+
+        let default_gems = gem_path
+            .stores()
+            .iter()
+            .filter_map(|store| store.gem_dir().parent())
+            .flat_map(|home| default_gems(&home.join("specifications").join("default")))
+            .collect();
+
+        Ok(Self {
+            rubylib,
+            ruby,
+            default_gems,
+            gems,
+        })
+    }
 
-    if options.gems_first {
-        // Search gems first, then non-gems
-        let (gem_paths, non_gem_paths) = split_gem_and_non_gem_paths(load_path);
+    /// Every file `feature` can resolve to, the one `require` loads first
+    fn find(&self, feature: &str, gems_first: bool) -> Vec<PathBuf> {
+        let candidates = feature_files(feature);
+
+        // Requiring a default gem's file activates the newest copy of that
+        // gem, which may be a newer release installed as a regular gem
+        let activated = self
+            .default_gems
+            .iter()
+            .find(|gem| gem.files.iter().any(|file| candidates.contains(file)))
+            .and_then(|default| {
+                self.gems.iter().find(|gem| {
+                    gem.name == default.name
+                        && compare_versions(&gem.version, &default.version) == Ordering::Greater
+                })
+            })
+            .map(|gem| gem.require_dirs.as_slice())
+            .unwrap_or_default();
+
+        let load_path = self.rubylib.iter().chain(activated).chain(&self.ruby);
+        let installed = self.gems.iter().flat_map(|gem| &gem.require_dirs);
+        let search: Vec<&PathBuf> = if gems_first {
+            installed.chain(load_path).collect()
+        } else {
+            load_path.chain(installed).collect()
+        };
 
-        search_paths(&gem_paths, file, file_base, &mut matches, options);
-        if !options.all && !matches.is_empty() {
-            return matches;
+        let mut matches: Vec<PathBuf> = Vec::new();
+        for dir in search {
+            for file in &candidates {
+                let path = dir.join(file);
+                if path.is_file() && !matches.contains(&path) {
+                    matches.push(path);
+                }
+            }
         }
-
-        search_paths(&non_gem_paths, file, file_base, &mut matches, options);
-    } else {
-        // Search all paths in order
-        search_paths(load_path, file, file_base, &mut matches, options);
+        matches
     }
-
-    matches
 }
 
-/// Split paths into gem and non-gem paths
-fn split_gem_and_non_gem_paths(paths: &[PathBuf]) -> (Vec<PathBuf>, Vec<PathBuf>) {
-    let mut gem_paths = Vec::new();
-    let mut non_gem_paths = Vec::new();
-
-    for path in paths {
-        let path_str = path.to_string_lossy();
-        if path_str.contains("/gems/") || path_str.contains("/bundler/gems/") {
-            gem_paths.push(path.clone());
-        } else {
-            non_gem_paths.push(path.clone());
-        }
+/// File names `require feature` tries, in order
+fn feature_files(feature: &str) -> Vec<String> {
+    let has_ext = Path::new(feature)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext == "rb" || DLEXTS.contains(&ext));
+    if has_ext {
+        return vec![feature.to_string()];
     }
 
-    (gem_paths, non_gem_paths)
+    std::iter::once(format!("{feature}.rb"))
+        .chain(DLEXTS.iter().map(|ext| format!("{feature}.{ext}")))
+        .collect()
 }
 
-/// Search for a file in the given paths
-fn search_paths(
-    paths: &[PathBuf],
-    original_file: &str,
-    file_base: &str,
-    matches: &mut Vec<PathBuf>,
-    options: &WhichOptions,
-) {
-    for dir in paths {
-        // Try exact match first (if user specified .rb)
-        if std::path::Path::new(original_file)
-            .extension()
-            .is_some_and(|ext| ext.eq_ignore_ascii_case("rb"))
+/// `full_require_paths` for an installed gem: its built extensions, then
+/// the gemspec's `require_paths`
+fn require_dirs(gem_dir: &Path) -> Vec<PathBuf> {
+    let spec = Gemspec::spec_path_for_gem_dir(gem_dir)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|source| {
+            Gemspec::parse(&source)
+                .or_else(|_| Gemspec::parse_ruby(&source))
+                .ok()
+        });
+    let require_paths = spec.map_or_else(|| vec!["lib".to_string()], |spec| spec.require_paths);
+
+    let mut dirs = extension_dirs(gem_dir);
+    dirs.extend(require_paths.iter().map(|path| gem_dir.join(path)));
+    dirs
+}
+
+/// `extensions/<platform>/<abi>/<full_name>` directories for a gem
+fn extension_dirs(gem_dir: &Path) -> Vec<PathBuf> {
+    let (Some(full_name), Some(home)) =
+        (gem_dir.file_name(), gem_dir.parent().and_then(Path::parent))
+    else {
+        return Vec::new();
+    };
+
+    let mut dirs = Vec::new();
+    for platform in fs::read_dir(home.join("extensions"))
+        .into_iter()
+        .flatten()
+        .flatten()
+    {
+        for abi in fs::read_dir(platform.path())
+            .into_iter()
+            .flatten()
+            .flatten()
         {
-            let candidate = dir.join(original_file);
-            if candidate.exists() && candidate.is_file() {
-                matches.push(candidate);
-                if !options.all {
-                    return;
-                }
+            let dir = abi.path().join(full_name);
+            if dir.is_dir() {
+                dirs.push(dir);
             }
         }
+    }
+    dirs
+}
 
-        // Try with .rb extension
-        let candidate_rb = dir.join(format!("{file_base}.rb"));
-        if candidate_rb.exists() && candidate_rb.is_file() {
-            matches.push(candidate_rb);
-            if !options.all {
-                return;
-            }
-        }
+/// Default gem specs in a `specifications/default` directory
+fn default_gems(spec_dir: &Path) -> Vec<DefaultGem> {
+    let Ok(entries) = fs::read_dir(spec_dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "gemspec"))
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|source| Gemspec::parse_ruby(&source).ok())
+        .map(|spec| DefaultGem {
+            name: spec.name,
+            version: spec.version,
+            files: spec.files,
+        })
+        .collect()
+}
 
-        // Try as directory with file_base.rb inside
-        let candidate_dir = dir.join(file_base).join(format!(
-            "{}.rb",
-            file_base.split('/').next_back().unwrap_or(file_base)
-        ));
-        if candidate_dir.exists() && candidate_dir.is_file() {
-            matches.push(candidate_dir);
-            if !options.all {
-                return;
-            }
-        }
+/// The interpreter's `$LOAD_PATH` without `RubyGems`
+fn interpreter_load_path() -> Result<Vec<PathBuf>> {
+    let output = Command::new("ruby")
+        .args(["--disable-gems", "-e", "puts $LOAD_PATH"])
+        .output()
+        .context("Failed to execute ruby command to get load path")?;
 
-        // Try shared libraries (.so, .bundle, .dylib)
-        for ext in &["so", "bundle", "dylib"] {
-            let candidate_so = dir.join(format!("{file_base}.{ext}"));
-            if candidate_so.exists() && candidate_so.is_file() {
-                matches.push(candidate_so);
-                if !options.all {
-                    return;
-                }
-            }
-        }
+    if !output.status.success() {
+        anyhow::bail!(
+            "Ruby command failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
     }
+
+    let paths = String::from_utf8(output.stdout)
+        .context("Invalid UTF-8 in Ruby output")?
+        .lines()
+        .map(|line| PathBuf::from(line.trim()))
+        .filter(|path| path.exists())
+        .collect();
+
+    Ok(paths)
 }
 
 #[cfg(test)]
 #[allow(clippy::unwrap_used, reason = "Tests can panic")]
 mod tests {
     use super::*;
+    use tempfile::TempDir;
 
     /// Test that run requires at least one file
     #[test]
@@ -225,45 +321,92 @@ mod tests {
         assert!(result.is_err(), "Should error with no files");
     }
 
-    /// Test that `get_ruby_load_path` returns paths
+    /// Test that `interpreter_load_path` returns paths
     #[test]
-    fn test_get_ruby_load_path() {
-        let load_path = get_ruby_load_path();
+    fn test_interpreter_load_path() {
+        let load_path = interpreter_load_path();
         assert!(load_path.is_ok(), "Should get Ruby load path");
         let paths = load_path.unwrap();
         assert!(!paths.is_empty(), "Load path should not be empty");
     }
 
-    /// Test `split_gem_and_non_gem_paths`
-    #[test]
-    fn test_split_gem_and_non_gem_paths() {
-        let paths = vec![
-            PathBuf::from("/usr/lib/ruby/3.5.0"),
-            PathBuf::from("/home/user/.gem/ruby/3.5.0/gems/rake-13.0.0/lib"),
-            PathBuf::from("/usr/lib/ruby/site_ruby"),
-            PathBuf::from("/home/user/.gem/ruby/3.5.0/gems/json-2.7.0/lib"),
-        ];
-
-        let (gem_paths, non_gem_paths) = split_gem_and_non_gem_paths(&paths);
-
-        assert_eq!(gem_paths.len(), 2, "Should find 2 gem paths");
-        assert_eq!(non_gem_paths.len(), 2, "Should find 2 non-gem paths");
-    }
-
-    /// Test `find_file_in_load_path` with empty load path
+    /// Test `find` with an empty load path
     #[test]
     fn test_find_file_empty_load_path() {
-        let options = WhichOptions {
-            all: false,
-            gems_first: false,
-            verbose: false,
-            quiet: true,
-            silent: true,
-        };
-        let result = find_file_in_load_path("rake", &[], &options);
+        let result = LoadPath::default().find("rake", false);
         assert!(
             result.is_empty(),
             "Should find no matches with empty load path"
         );
     }
+
+    #[test]
+    fn feature_files_follow_require() {
+        assert_eq!(
+            feature_files("json").get(..2),
+            Some(&["json.rb".to_string(), "json.so".to_string()][..])
+        );
+        assert_eq!(feature_files("json.rb"), ["json.rb"]);
+        assert_eq!(feature_files("json/ext/parser.so"), ["json/ext/parser.so"]);
+        assert_eq!(
+            feature_files("net/http").first().map(String::as_str),
+            Some("net/http.rb")
+        );
+    }
+
+    /// Lay out `rel` under `root` as an empty file and return its path
+    fn touch(root: &Path, rel: &str) -> PathBuf {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "").unwrap();
+        path
+    }
+
+    #[test]
+    fn default_gem_activation_and_shadowed_copies() {
+        let root = TempDir::new().unwrap();
+        let stdlib = root.path().join("stdlib");
+        let stdlib_json = touch(&stdlib, "json.rb");
+        let newer = touch(root.path(), "gems/json-2.9.0/lib/json.rb");
+        let older = touch(root.path(), "gems/json-2.1.0/lib/json.rb");
+        let rake = touch(root.path(), "gems/rake-13.0.0/lib/rake.rb");
+
+        let gem = |name: &str, version: &str| GemEntry {
+            name: name.to_string(),
+            version: version.to_string(),
+            require_dirs: vec![
+                root.path()
+                    .join(format!("gems/{name}-{version}"))
+                    .join("lib"),
+            ],
+        };
+        let mut load_path = LoadPath {
+            ruby: vec![stdlib],
+            default_gems: vec![DefaultGem {
+                name: "json".to_string(),
+                version: "2.7.1".to_string(),
+                files: vec!["json.rb".to_string()],
+            }],
+            gems: vec![
+                gem("json", "2.9.0"),
+                gem("json", "2.1.0"),
+                gem("rake", "13.0.0"),
+            ],
+            ..LoadPath::default()
+        };
+
+        // A newer installed json wins over the default gem
+        assert_eq!(
+            load_path.find("json", false),
+            vec![newer, stdlib_json.clone(), older.clone()]
+        );
+
+        // Without it, the default gem's copy in the stdlib wins
+        load_path.gems.remove(0);
+        assert_eq!(load_path.find("json", false), vec![stdlib_json, older]);
+
+        // Non-default gems are found after `$LOAD_PATH`
+        assert_eq!(load_path.find("rake", false), vec![rake.clone()]);
+        assert_eq!(load_path.find("rake", true), vec![rake]);
+    }
 }
@@ -0,0 +1,508 @@
+//! Help command
+//!
+//! `lode help COMMAND` prints clap's help for a command. With `--examples`
+//! it also renders extended documentation embedded in the binary: usage
+//! patterns, the `lode config` keys that change the command's behavior, and
+//! the environment variables it honors. Everything works offline.
+
+use anyhow::Result;
+use clap::CommandFactory;
+use std::fmt::Write;
+
+/// Extended documentation for one command
+struct CommandDoc {
+    name: &'static str,
+    /// `(command line, what it does)` pairs
+    examples: &'static [(&'static str, &'static str)],
+    /// `lode config` keys read by the command
+    config_keys: &'static [&'static str],
+    /// Environment variables read by the command
+    env_vars: &'static [&'static str],
+}
+
+/// Extended documentation registry, one entry per documented command
+const COMMAND_DOCS: &[CommandDoc] = &[
+    CommandDoc {
+        name: "install",
+        examples: &[
+            ("lode install", "Install everything in Gemfile.lock"),
+            ("lode install --jobs 8", "Download and build with 8 workers"),
+            (
+                "lode install --only test",
+                "Install just the test group and its dependencies",
+            ),
+            (
+                "lode install --local",
+                "Install from cached .gem files without network access",
+            ),
+            (
+                "lode install --dry-run",
+                "Show what would be installed without changing anything",
+            ),
+            (
+                "lode install --path vendor/bundle",
+                "Install into an isolated project directory and remember it",
+            ),
+            (
+                "lode install --arch arm64",
+                "Build native extensions for arm64 from an x86_64 Ruby under Rosetta",
+            ),
+        ],
+        config_keys: &[
+            "vendor_dir",
+            "cache_dir",
+            "gemfile",
+            "gem_sources",
+            "insecure_sources",
+            "allow_insecure_sources",
+            "disable_shared_gems",
+            "fail_on_case_collisions",
+            "max_requests_per_second",
+            "build.GEM_NAME",
+        ],
+        env_vars: &[
+            "BUNDLE_GEMFILE",
+            "BUNDLE_PATH",
+            "BUNDLE_JOBS",
+            "BUNDLE_RETRY",
+            "BUNDLE_WITH",
+            "BUNDLE_WITHOUT",
+            "BUNDLE_ONLY",
+            "BUNDLE_FROZEN",
+            "BUNDLE_DEPLOYMENT",
+            "BUNDLE_FORCE_RUBY_PLATFORM",
+            "MAKE",
+            "CC",
+            "CFLAGS",
+        ],
+    },
+    CommandDoc {
+        name: "update",
+        examples: &[
+            (
+                "lode update",
+                "Update every gem to the newest allowed version",
+            ),
+            ("lode update rack", "Update rack and the gems only it needs"),
+            (
+                "lode update --conservative rails",
+                "Update rails without touching its shared dependencies",
+            ),
+            (
+                "lode update --patch --strict",
+                "Take patch releases only, never beyond them",
+            ),
+        ],
+        config_keys: &["vendor_dir", "gemfile", "gem_sources"],
+        env_vars: &[
+            "BUNDLE_GEMFILE",
+            "BUNDLE_JOBS",
+            "BUNDLE_RETRY",
+            "BUNDLE_PREFER_PATCH",
+        ],
+    },
+    CommandDoc {
+        name: "lock",
+        examples: &[
+            ("lode lock", "Resolve the Gemfile and write Gemfile.lock"),
+            (
+                "lode lock --add-platform x86_64-linux",
+                "Also lock gems for x86_64-linux",
+            ),
+            (
+                "lode lock --update rack",
+                "Re-resolve rack while keeping other versions",
+            ),
+            (
+                "lode lock --print",
+                "Print the lockfile instead of writing it",
+            ),
+        ],
+        config_keys: &["gemfile", "gem_sources", "source_pin.PREFIX"],
+        env_vars: &["BUNDLE_GEMFILE", "BUNDLE_FROZEN", "BUNDLE_PREFER_PATCH"],
+    },
+    CommandDoc {
+        name: "exec",
+        examples: &[
+            ("lode exec rspec", "Run rspec with the bundle's gems loaded"),
+            (
+                "lode exec --only-group test -- rake spec",
+                "Load only the test group's gems",
+            ),
+            (
+                "lode exec --cached-setup rails server",
+                "Skip load path discovery using the install-time manifest",
+            ),
+            (
+                "lode exec --isolate rake test",
+                "Ignore inherited GEM_PATH, RUBYOPT and RUBYLIB, like CI",
+            ),
+        ],
+        config_keys: &["vendor_dir", "gemfile", "exec_unset_env"],
+        env_vars: &["BUNDLE_GEMFILE", "BUNDLE_PATH", "RUBYOPT", "RUBYLIB"],
+    },
+    CommandDoc {
+        name: "with-unbundled-env",
+        examples: &[(
+            "lode with-unbundled-env -- gem list",
+            "List system gems from inside `lode exec` or `lode shell`",
+        )],
+        config_keys: &[],
+        env_vars: &[
+            "BUNDLER_ORIG_PATH",
+            "BUNDLER_ORIG_GEM_PATH",
+            "BUNDLER_ORIG_RUBYOPT",
+        ],
+    },
+    CommandDoc {
+        name: "add",
+        examples: &[
+            ("lode add rack", "Add rack with an optimistic requirement"),
+            ("lode add rspec --group test", "Add rspec to the test group"),
+            (
+                "lode add rails --version '~> 7.1' --skip-install",
+                "Add rails and lock it without installing",
+            ),
+        ],
+        config_keys: &["gemfile", "gem_sources"],
+        env_vars: &["BUNDLE_GEMFILE"],
+    },
+    CommandDoc {
+        name: "remove",
+        examples: &[
+            (
+                "lode remove rack",
+                "Remove rack from the Gemfile and relock",
+            ),
+            ("lode remove rspec pry", "Remove several gems at once"),
+        ],
+        config_keys: &["gemfile"],
+        env_vars: &["BUNDLE_GEMFILE"],
+    },
+    CommandDoc {
+        name: "outdated",
+        examples: &[
+            ("lode outdated", "List gems with newer releases"),
+            (
+                "lode outdated --patch",
+                "Only show gems with newer patch releases",
+            ),
+            (
+                "lode outdated --parseable",
+                "Print one machine-readable line per gem",
+            ),
+            (
+                "lode outdated --fail-level minor",
+                "Exit nonzero in CI when a minor or major release is out",
+            ),
+        ],
+        config_keys: &["gem_sources", "hold.GEM_NAME"],
+        env_vars: &["BUNDLE_GEMFILE"],
+    },
+    CommandDoc {
+        name: "hold",
+        examples: &[
+            (
+                "lode hold rack --reason \"3.1.0 breaks uploads\"",
+                "Keep rack at its locked version",
+            ),
+            ("lode hold", "List held gems and why"),
+            ("lode hold --release rack", "Let rack update again"),
+        ],
+        config_keys: &["hold.GEM_NAME"],
+        env_vars: &[],
+    },
+    CommandDoc {
+        name: "check",
+        examples: &[
+            ("lode check", "Verify every locked gem is installed"),
+            (
+                "lode check --gemfile gems.rb",
+                "Check the bundle for another Gemfile",
+            ),
+        ],
+        config_keys: &["vendor_dir", "gemfile"],
+        env_vars: &["BUNDLE_GEMFILE", "BUNDLE_PATH"],
+    },
+    CommandDoc {
+        name: "doctor",
+        examples: &[
+            ("lode doctor", "Diagnose the installed bundle"),
+            (
+                "lode doctor --remote",
+                "Find yanked gems, checksum drift and unreachable git revisions",
+            ),
+            (
+                "lode doctor --remote --json",
+                "Report lockfile drift as JSON for CI",
+            ),
+        ],
+        config_keys: &["vendor_dir", "cache_dir"],
+        env_vars: &["BUNDLE_PATH"],
+    },
+    CommandDoc {
+        name: "cache",
+        examples: &[
+            ("lode cache", "Copy locked .gem files into vendor/cache"),
+            (
+                "lode cache --all-platforms",
+                "Cache gems for every locked platform",
+            ),
+        ],
+        config_keys: &["cache_dir", "vendor_dir"],
+        env_vars: &[
+            "BUNDLE_CACHE_PATH",
+            "BUNDLE_CACHE_ALL",
+            "BUNDLE_CACHE_ALL_PLATFORMS",
+            "BUNDLE_NO_INSTALL",
+        ],
+    },
+    CommandDoc {
+        name: "clean",
+        examples: &[
+            (
+                "lode clean --dry-run",
+                "List gems that are no longer locked",
+            ),
+            ("lode clean --force", "Remove them without asking"),
+        ],
+        config_keys: &["vendor_dir"],
+        env_vars: &["BUNDLE_PATH", "BUNDLE_CLEAN"],
+    },
+    CommandDoc {
+        name: "config",
+        examples: &[
+            ("lode config --list", "Show every setting"),
+            (
+                "lode config vendor_dir vendor/bundle",
+                "Install gems under vendor/bundle",
+            ),
+            (
+                "lode config --delete cache_dir",
+                "Return to the default cache",
+            ),
+        ],
+        config_keys: &[
+            "vendor_dir",
+            "cache_dir",
+            "gemfile",
+            "bin_dir",
+            "locale",
+            "gem.no_document",
+        ],
+        env_vars: &[
+            "BUNDLE_APP_CONFIG",
+            "BUNDLE_USER_HOME",
+            "BUNDLE_IGNORE_CONFIG",
+        ],
+    },
+    CommandDoc {
+        name: "resolve",
+        examples: &[
+            ("lode resolve", "Print what the Gemfile would lock to"),
+            (
+                "lode resolve --add 'rails >= 7.1' --json",
+                "Resolve with an extra requirement and print JSON",
+            ),
+            (
+                "cat Gemfile | lode resolve -",
+                "Resolve a Gemfile from stdin",
+            ),
+        ],
+        config_keys: &["gem_sources", "cache_dir"],
+        env_vars: &["BUNDLE_GEMFILE", "GEM_SOURCE"],
+    },
+    CommandDoc {
+        name: "info",
+        examples: &[
+            (
+                "lode info rack",
+                "Show rack's latest release and dependencies",
+            ),
+            (
+                "lode info rack --path",
+                "Print where the locked rack is installed",
+            ),
+            (
+                "lode info rack --spec-json",
+                "Export the locked rack gemspec as JSON",
+            ),
+        ],
+        config_keys: &["vendor_dir", "cache_dir"],
+        env_vars: &["BUNDLE_GEMFILE"],
+    },
+    CommandDoc {
+        name: "test-install",
+        examples: &[
+            (
+                "lode test-install pkg/mygem-1.0.0.gem",
+                "Install the gem and its dependencies into a scratch prefix and load it",
+            ),
+            (
+                "lode test-install mygem-1.0.0.gem --keep",
+                "Keep the scratch prefix to investigate a failure",
+            ),
+        ],
+        config_keys: &["cache_dir"],
+        env_vars: &["GEM_SOURCE"],
+    },
+];
+
+/// Show help for `command`, with extended docs when `examples` is set
+///
+/// # Errors
+///
+/// Returns an error if `command` is not a lode command or help cannot be
+/// written to stdout.
+pub(crate) fn run(command: Option<&str>, examples: bool) -> Result<()> {
+    let mut cli = crate::Cli::command();
+    let Some(name) = command else {
+        cli.print_long_help()?;
+        println!();
+        println!("Run `lode help COMMAND --examples` for examples, config keys, and env vars.");
+        return Ok(());
+    };
+
+    let Some(subcommand) = cli.find_subcommand_mut(name) else {
+        anyhow::bail!("Unknown command `{name}`. Run `lode help` to list commands.");
+    };
+    let canonical = subcommand.get_name().to_string();
+
+    if !examples {
+        subcommand
+            .clone()
+            .bin_name(format!("lode {canonical}"))
+            .print_long_help()?;
+        return Ok(());
+    }
+
+    match COMMAND_DOCS.iter().find(|doc| doc.name == canonical) {
+        Some(doc) => print!("{}", render(doc)),
+        None => println!(
+            "No extended documentation for `{canonical}` yet; see `lode help {canonical}`."
+        ),
+    }
+    Ok(())
+}
+
+/// Render a command's extended documentation
+fn render(doc: &CommandDoc) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(out, "EXAMPLES");
+    for (command_line, description) in doc.examples {
+        let _ = writeln!(out, "  {command_line}");
+        let _ = writeln!(out, "      {description}");
+    }
+
+    if !doc.config_keys.is_empty() {
+        let _ = writeln!(out, "\nCONFIG KEYS (lode config KEY VALUE)");
+        for key in doc.config_keys {
+            let _ = writeln!(out, "  {key}");
+        }
+    }
+
+    if !doc.env_vars.is_empty() {
+        let _ = writeln!(out, "\nENVIRONMENT");
+        for var in doc.env_vars {
+            let _ = writeln!(out, "  {var}");
+        }
+    }
+
+    out
+}
+
+/// A command's extended documentation as man page sections, if it has any
+pub(crate) fn render_roff(name: &str) -> Option<String> {
+    let doc = COMMAND_DOCS.iter().find(|doc| doc.name == name)?;
+    let mut out = String::new();
+
+    let _ = writeln!(out, ".SH EXAMPLES");
+    for (command_line, description) in doc.examples {
+        let _ = writeln!(
+            out,
+            ".TP\n\\fB{}\\fR\n{}",
+            roff_escape(command_line),
+            roff_escape(description)
+        );
+    }
+
+    for (title, items) in [
+        ("\"CONFIG KEYS\"", doc.config_keys),
+        ("ENVIRONMENT", doc.env_vars),
+    ] {
+        if !items.is_empty() {
+            let _ = writeln!(out, ".SH {title}\n.nf");
+            for item in items {
+                let _ = writeln!(out, "{}", roff_escape(item));
+            }
+            let _ = writeln!(out, ".fi");
+        }
+    }
+
+    Some(out)
+}
+
+/// `text` safe to place on a roff text line
+fn roff_escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with(['.', '\'']) {
+        format!("\\&{escaped}")
+    } else {
+        escaped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Building the full CLI needs a large stack, as for `lode completion`
+    #[test]
+    #[ignore = "requires large stack (run with RUST_MIN_STACK=8388608)"]
+    fn registry_names_real_commands() {
+        let cli = crate::Cli::command();
+        for doc in COMMAND_DOCS {
+            assert!(
+                cli.find_subcommand(doc.name).is_some(),
+                "`{}` is not a lode command",
+                doc.name
+            );
+            assert!(!doc.examples.is_empty(), "`{}` has no examples", doc.name);
+            for (command_line, _) in doc.examples {
+                assert!(
+                    command_line.contains(&format!("lode {}", doc.name)),
+                    "example `{command_line}` does not run `{}`",
+                    doc.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn render_lists_every_section() {
+        let doc = COMMAND_DOCS
+            .iter()
+            .find(|doc| doc.name == "install")
+            .unwrap();
+        let text = render(doc);
+        assert!(text.starts_with("EXAMPLES\n  lode install\n"));
+        assert!(text.contains("\nCONFIG KEYS (lode config KEY VALUE)\n  vendor_dir\n"));
+        assert!(text.contains("\nENVIRONMENT\n  BUNDLE_GEMFILE\n"));
+    }
+
+    #[test]
+    fn roff_sections_escape_text() {
+        let roff = render_roff("install").unwrap();
+        assert!(roff.starts_with(".SH EXAMPLES\n.TP\n\\fBlode install\\fR\n"));
+        assert!(roff.contains("\\fBlode install \\-\\-jobs 8\\fR"));
+        assert!(roff.contains(".SH \"CONFIG KEYS\"\n.nf\nvendor_dir\n"));
+        assert!(render_roff("definitely-not-a-command").is_none());
+    }
+
+    #[test]
+    #[ignore = "requires large stack (run with RUST_MIN_STACK=8388608)"]
+    fn unknown_command_is_an_error() {
+        assert!(run(Some("definitely-not-a-command"), true).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! Hold command
+//!
+//! Keep gems at their locked versions. Holds live in `.lode.toml`
+//! (`hold.GEM_NAME`, with the reason as its value): `update` and `outdated`
+//! skip held gems, and `lock --update` keeps them locked unless given
+//! `--release-hold`.
+
+use anyhow::{Context, Result};
+use lode::Config;
+use std::fs;
+use std::path::Path;
+
+/// Project config holds are recorded in
+const CONFIG_PATH: &str = ".lode.toml";
+
+/// Hold `gems`, release them, or list the holds when no gem is given
+pub(crate) fn run(gems: &[String], reason: Option<&str>, release: bool) -> Result<()> {
+    if gems.is_empty() {
+        return list();
+    }
+
+    let config_path = Path::new(CONFIG_PATH);
+    // Only the `[hold]` table changes
+    let mut document = super::install::project_config_document(config_path)?;
+    for message in edit_holds(&mut document, gems, reason, release)? {
+        println!("{message}");
+    }
+
+    fs::write(config_path, document.to_string())
+        .with_context(|| format!("Failed to write {}", config_path.display()))
+}
+
+/// Add or remove `gems` in the document's `[hold]` table, describing each
+/// change
+fn edit_holds(
+    document: &mut toml_edit::DocumentMut,
+    gems: &[String],
+    reason: Option<&str>,
+    release: bool,
+) -> Result<Vec<String>> {
+    let reason = reason.unwrap_or_default();
+    let holds = document
+        .entry("hold")
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut()
+        .with_context(|| format!("`hold` in {CONFIG_PATH} is not a table"))?;
+
+    Ok(gems
+        .iter()
+        .map(|gem| {
+            if !release {
+                holds.insert(gem, toml_edit::value(reason));
+                format!("Holding {gem}{}", describe(reason))
+            } else if holds.remove(gem).is_some() {
+                format!("Released hold on {gem}")
+            } else {
+                format!("{gem} is not held")
+            }
+        })
+        .collect())
+}
+
+/// Print every held gem with its reason
+fn list() -> Result<()> {
+    let config = Config::load().context("Failed to load configuration")?;
+    let holds = lode::config::held_gems(Some(&config));
+    if holds.is_empty() {
+        println!("No gems are held");
+        return Ok(());
+    }
+    for (gem, reason) in &holds {
+        println!("{gem}{}", describe(reason));
+    }
+    Ok(())
+}
+
+/// ` (reason)`, or nothing when no reason was given
+pub(crate) fn describe(reason: &str) -> String {
+    if reason.is_empty() {
+        String::new()
+    } else {
+        format!(" ({reason})")
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "Tests can panic")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_keep_the_rest_of_the_config() {
+        let mut document: toml_edit::DocumentMut =
+            "# team settings\ncache_dir = \"/tmp/gems\" # shared\nfuture_key = 1\n"
+                .parse()
+                .unwrap();
+
+        let messages =
+            edit_holds(&mut document, &["rack".to_string()], Some("CVE"), false).unwrap();
+        assert_eq!(messages, ["Holding rack (CVE)"]);
+        assert_eq!(
+            document.to_string(),
+            "# team settings\ncache_dir = \"/tmp/gems\" # shared\nfuture_key = 1\n\n\
+             [hold]\nrack = \"CVE\"\n"
+        );
+
+        let messages = edit_holds(
+            &mut document,
+            &["rack".to_string(), "rails".to_string()],
+            None,
+            true,
+        )
+        .unwrap();
+        assert_eq!(messages, ["Released hold on rack", "rails is not held"]);
+        assert!(document.to_string().starts_with("# team settings\n"));
+        assert!(!document.to_string().contains("rack"));
+    }
+}
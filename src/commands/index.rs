@@ -0,0 +1,21 @@
+//! Index command
+//!
+//! Generate the index files a gem server publishes, so a directory of gems
+//! can be served by any static file server or used as a `file://` source
+
+use anyhow::Result;
+use lode::index_generator;
+use std::path::Path;
+
+/// Write all indexes for the gems in `dir/gems/`
+pub(crate) fn generate(dir: &str) -> Result<()> {
+    let summary = index_generator::generate(Path::new(dir))?;
+    println!(
+        "Indexed {} versions of {} gems in {dir}",
+        summary.versions, summary.gems
+    );
+    if summary.skipped > 0 {
+        println!("Skipped {} unreadable .gem files", summary.skipped);
+    }
+    Ok(())
+}
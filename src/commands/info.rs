@@ -2,12 +2,30 @@
 //!
 //! Show gem information
 
+use super::search::{format_downloads, format_release};
 use anyhow::{Context, Result};
-use lode::{Config, RubyGemsClient, config, lockfile::Lockfile};
+use lode::{Config, Gemspec, RubyGemsClient, config, lockfile::Lockfile};
 use std::fs;
 
+/// Machine-readable format for `--spec-json` and `--spec-yaml`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SpecFormat {
+    Json,
+    Yaml,
+}
+
 /// Show detailed information about a gem from RubyGems.org or its installation path
-pub(crate) async fn run(gem_name: &str, show_path: bool, show_version: bool) -> Result<()> {
+pub(crate) async fn run(
+    gem_name: &str,
+    show_path: bool,
+    show_version: bool,
+    spec_format: Option<SpecFormat>,
+) -> Result<()> {
+    // If --spec-json or --spec-yaml is used, export the locked gemspec
+    if let Some(format) = spec_format {
+        return show_gem_spec(gem_name, format).await;
+    }
+
     // If --path flag is used, show the installation path
     if show_path {
         return show_gem_path(gem_name);
@@ -42,6 +60,24 @@ pub(crate) async fn run(gem_name: &str, show_path: bool, show_version: bool) ->
 
     println!("Platform: {}", latest.platform);
 
+    // Download counts are informational; a failed lookup isn't fatal
+    let now = chrono::Utc::now();
+    if let Ok(summary) = client.fetch_gem_summary(gem_name).await {
+        println!(
+            "Downloads: {} total, {} for {}",
+            format_downloads(summary.downloads),
+            format_downloads(summary.version_downloads),
+            summary.version
+        );
+    }
+    if let Some(released) = latest
+        .created_at
+        .as_deref()
+        .and_then(|created_at| format_release(created_at, now))
+    {
+        println!("Released: {released}");
+    }
+
     if let Some(ruby_version) = &latest.ruby_version {
         println!("Required Ruby Version: {ruby_version}");
     }
@@ -83,7 +119,18 @@ pub(crate) async fn run(gem_name: &str, show_path: bool, show_version: bool) ->
         let display_count = versions.len().min(10);
         for version in versions.iter().skip(1).take(display_count - 1) {
             let number = &version.number;
-            println!("  {number}");
+            let mut details = Vec::new();
+            if let Some(date) = version.created_at.as_deref().and_then(|c| c.get(..10)) {
+                details.push(date.to_string());
+            }
+            if let Some(downloads) = version.downloads_count {
+                details.push(format!("{} downloads", format_downloads(downloads)));
+            }
+            if details.is_empty() {
+                println!("  {number}");
+            } else {
+                println!("  {number} ({})", details.join(", "));
+            }
         }
         let total = versions.len();
         if total > 10 {
@@ -95,15 +142,64 @@ pub(crate) async fn run(gem_name: &str, show_path: bool, show_version: bool) ->
     Ok(())
 }
 
-/// Show just the version of a gem from the lockfile
-fn show_gem_version(gem_name: &str) -> Result<()> {
-    // Find and read lockfile
+/// Print the full gemspec of the locked version as JSON or YAML
+///
+/// Reads the spec from the cached `.gem` when one is available, otherwise
+/// from the `RubyGems` API (which lacks file lists and metadata).
+async fn show_gem_spec(gem_name: &str, format: SpecFormat) -> Result<()> {
+    let lockfile = read_lockfile()?;
+    let gem = lockfile
+        .gems
+        .iter()
+        .find(|g| g.name == gem_name)
+        .with_context(|| format!("Gem '{gem_name}' not found in lockfile"))?;
+
+    let cfg = Config::load().unwrap_or_default();
+    let ruby_version = config::ruby_version(lockfile.ruby_version.as_deref());
+    let gem_dir = config::vendor_dir(Some(&cfg))?
+        .join("ruby")
+        .join(&ruby_version)
+        .join("gems")
+        .join(gem.full_name_with_platform());
+    let cache_dirs: Vec<_> = config::cache_dir(Some(&cfg)).into_iter().collect();
+
+    let cached_spec = lode::install::find_cached_gem(&gem_dir, &cache_dirs)
+        .and_then(|gem_path| Gemspec::from_gem_file(&gem_path).ok());
+    let spec = if let Some(spec) = cached_spec {
+        spec
+    } else {
+        let metadata = RubyGemsClient::new(lode::DEFAULT_GEM_SOURCE)?
+            .fetch_gem_info(gem_name, &gem.version)
+            .await
+            .with_context(|| format!("Failed to fetch gemspec for {gem_name} {}", gem.version))?;
+        Gemspec::from(&metadata)
+    };
+
+    print!("{}", render_spec(&spec, format)?);
+    Ok(())
+}
+
+/// Serialize a gemspec in the requested export format
+fn render_spec(spec: &Gemspec, format: SpecFormat) -> Result<String> {
+    Ok(match format {
+        SpecFormat::Json => format!("{}\n", serde_json::to_string_pretty(spec)?),
+        SpecFormat::Yaml => serde_yaml::to_string(spec)?,
+    })
+}
+
+/// Read and parse the project lockfile
+fn read_lockfile() -> Result<Lockfile> {
     let lockfile_path = lode::paths::find_lockfile();
     let content = fs::read_to_string(&lockfile_path)
         .with_context(|| format!("Failed to read lockfile: {}", lockfile_path.display()))?;
 
-    let lockfile = Lockfile::parse(&content)
-        .with_context(|| format!("Failed to parse lockfile: {}", lockfile_path.display()))?;
+    Lockfile::parse(&content)
+        .with_context(|| format!("Failed to parse lockfile: {}", lockfile_path.display()))
+}
+
+/// Show just the version of a gem from the lockfile
+fn show_gem_version(gem_name: &str) -> Result<()> {
+    let lockfile = read_lockfile()?;
 
     // Find the gem in the lockfile
     let gem = lockfile
@@ -120,13 +216,7 @@ fn show_gem_version(gem_name: &str) -> Result<()> {
 
 /// Show the installation path of a gem
 fn show_gem_path(gem_name: &str) -> Result<()> {
-    // Find and read lockfile
-    let lockfile_path = lode::paths::find_lockfile();
-    let content = fs::read_to_string(&lockfile_path)
-        .with_context(|| format!("Failed to read lockfile: {}", lockfile_path.display()))?;
-
-    let lockfile = Lockfile::parse(&content)
-        .with_context(|| format!("Failed to parse lockfile: {}", lockfile_path.display()))?;
+    let lockfile = read_lockfile()?;
 
     // Find the gem in the lockfile
     let gem = lockfile
@@ -163,13 +253,40 @@ mod tests {
     #[tokio::test]
     #[ignore = "Requires network access to rubygems.org"]
     async fn test_info_rack() {
-        let result = run("rack", false, false).await;
+        let result = run("rack", false, false, None).await;
         assert!(result.is_ok());
     }
 
     #[tokio::test]
     async fn test_info_nonexistent() {
-        let result = run("this-gem-definitely-does-not-exist-12345", false, false).await;
+        let result = run(
+            "this-gem-definitely-does-not-exist-12345",
+            false,
+            false,
+            None,
+        )
+        .await;
         assert!(result.is_err());
     }
+
+    #[test]
+    fn render_spec_exports_json_and_yaml() {
+        let spec = Gemspec::parse("name: rake\nversion: 13.2.1\nlicenses:\n- MIT\n").unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render_spec(&spec, SpecFormat::Json).unwrap()).unwrap();
+        assert_eq!(
+            json.pointer("/name").and_then(serde_json::Value::as_str),
+            Some("rake")
+        );
+        assert_eq!(
+            json.pointer("/licenses/0")
+                .and_then(serde_json::Value::as_str),
+            Some("MIT")
+        );
+
+        let yaml = render_spec(&spec, SpecFormat::Yaml).unwrap();
+        assert!(yaml.contains("version: 13.2.1"));
+        assert!(yaml.contains("require_paths:\n- lib"));
+    }
 }
@@ -3,23 +3,29 @@
 //! Download and install all gems from Gemfile.lock
 
 use anyhow::{Context, Result};
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use lode::exit_code::ExitCode;
+use lode::messages::{self, Message};
 use lode::{
     BinstubGenerator, Config, DownloadManager, ExtensionBuilder, Gemfile, GitManager, Lockfile,
-    StandaloneBundle, StandaloneGem, StandaloneOptions, config,
+    Phase, Profiler, StandaloneBundle, StandaloneGem, StandaloneOptions, config,
 };
 use rayon::prelude::*;
-use std::io::Write;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 
 /// Configuration for the install command
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(clippy::struct_excessive_bools)]
 pub(crate) struct InstallOptions<'a> {
     /// Path to Gemfile.lock
     pub lockfile_path: &'a str,
+    /// Gemfile to read instead of the one found in the working directory
+    pub gemfile: Option<&'a Path>,
     /// Re-download gems even if cached
     pub redownload: bool,
     /// Enable verbose output
@@ -44,14 +50,88 @@ pub(crate) struct InstallOptions<'a> {
     pub full_index: bool,
     /// Alternative rbconfig path for cross compilation
     pub target_rbconfig: Option<&'a str>,
+    /// CPU architecture(s) to build native extensions for, overriding the
+    /// arch of the running Ruby
+    pub arch: Option<&'a str>,
+    /// Install into this directory, isolated from user and system gems, and
+    /// remember it for the project
+    pub path: Option<&'a str>,
     /// Frozen mode - disallow Gemfile changes without lockfile update
     pub frozen: bool,
+    /// Deployment mode - require a lockfile matching the Gemfile (`BUNDLE_DEPLOYMENT`)
+    pub deployment: bool,
     /// Groups to exclude from installation (`BUNDLE_WITHOUT`)
     pub without_groups: Vec<String>,
     /// Groups to explicitly include (`BUNDLE_WITH`)
     pub with_groups: Vec<String>,
+    /// The only groups to install, with their dependencies, overriding the
+    /// two lists above (`BUNDLE_ONLY`)
+    pub only_groups: Vec<String>,
+    /// Named group profile from config, replacing the with/without lists
+    pub group_profile: Option<&'a str>,
     /// Auto-clean after install (`BUNDLE_CLEAN`)
     pub auto_clean: bool,
+    /// Symlink path gems instead of copying them
+    pub link_path_gems: bool,
+    /// Records phase and per-gem timings for `--profile`
+    pub profiler: Profiler,
+    /// Print what would be installed without touching the filesystem
+    pub dry_run: bool,
+    /// Continue an interrupted install from its `.lode/state` journal
+    pub resume: bool,
+}
+
+/// Install every gemfile matching `pattern` in turn
+///
+/// Each gemfile installs from its own lockfile; downloads land in the shared
+/// cache, so gems common to several gemfiles are fetched once. A failing
+/// gemfile does not stop the rest.
+pub(crate) async fn run_gemfiles(options: InstallOptions<'_>, pattern: &str) -> Result<()> {
+    let gemfiles = lode::paths::expand_glob(pattern);
+    if gemfiles.is_empty() {
+        anyhow::bail!("No gemfiles match {pattern}");
+    }
+
+    let mut failed = Vec::new();
+    for (index, gemfile) in gemfiles.iter().enumerate() {
+        let lockfile_path = lode::paths::lockfile_for_gemfile(gemfile);
+        let lockfile_path = lockfile_path.to_string_lossy();
+        if !options.quiet {
+            println!(
+                "==> {} ({}/{})",
+                gemfile.display(),
+                index + 1,
+                gemfiles.len()
+            );
+        }
+        let run_options = InstallOptions {
+            lockfile_path: &lockfile_path,
+            gemfile: Some(gemfile),
+            // The worker pool is global and can only be configured once
+            workers: options.workers.filter(|_| index == 0),
+            ..options.clone()
+        };
+        if let Err(e) = Box::pin(run(run_options)).await {
+            lode::warn!("{}: {e:#}", gemfile.display());
+            failed.push(gemfile.display().to_string());
+        }
+        if !options.quiet {
+            println!();
+        }
+    }
+
+    if !failed.is_empty() {
+        anyhow::bail!(
+            "{} of {} gemfiles failed to install: {}",
+            failed.len(),
+            gemfiles.len(),
+            failed.join(", ")
+        );
+    }
+    if !options.quiet {
+        println!("Installed {} gemfiles", gemfiles.len());
+    }
+    Ok(())
 }
 
 /// Run the install command
@@ -74,26 +154,59 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
     }
 
     // 1. Load configuration
-    let cfg = Config::load().context("Failed to load configuration")?;
+    let mut cfg = Config::load().context("Failed to load configuration")?;
+
+    // `--path` switches the project to an isolated gem directory
+    if let Some(path) = options.path {
+        if !options.dry_run {
+            remember_install_path(path, options.quiet)?;
+        }
+        cfg.vendor_dir = Some(path.to_string());
+        cfg.disable_shared_gems = Some(true);
+    }
+
+    // Gemfile plugins install before any gem, as with `bundle install`
+    if !options.dry_run {
+        let gemfile_path = options
+            .gemfile
+            .map_or_else(lode::paths::find_gemfile, Path::to_path_buf);
+        if let Ok(gemfile) = Gemfile::parse_file(&gemfile_path)
+            && !gemfile.plugins.is_empty()
+        {
+            super::plugin::install_declared(&gemfile.plugins).await?;
+        }
+    }
 
     if options.verbose {
         println!("Loading lockfile from {}...", options.lockfile_path);
     }
 
+    // Deployment refuses a missing or stale lockfile before reading it
+    if options.deployment {
+        check_deployment_mode(options.lockfile_path, options.verbose)?;
+    }
+
     // 2. Parse lockfile
-    let lockfile_content = tokio::fs::read_to_string(options.lockfile_path)
-        .await
-        .context("Failed to read lockfile")?;
+    let lockfile_content = match tokio::fs::read_to_string(options.lockfile_path).await {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(ExitCode::Lockfile.error(format!(
+                "Lockfile not found: {} (run `lode lock` to create it)",
+                options.lockfile_path
+            )));
+        }
+        read => read.context("Failed to read lockfile")?,
+    };
 
     let lockfile = Lockfile::parse(&lockfile_content).context("Failed to parse lockfile")?;
 
     // Destructure remaining options for easier access in the rest of the function
     let InstallOptions {
         lockfile_path,
+        gemfile: gemfile_path,
         redownload,
         verbose,
         quiet,
-        workers: _,
+        workers,
         local,
         prefer_local,
         retry,
@@ -102,11 +215,51 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
         trust_policy,
         full_index,
         target_rbconfig,
+        arch,
+        path: _,
         frozen,
+        deployment,
         without_groups,
         with_groups,
+        only_groups,
+        group_profile,
         auto_clean,
+        link_path_gems,
+        profiler,
+        dry_run,
+        resume,
     } = options;
+    let gemfile_path = gemfile_path.map_or_else(lode::paths::find_gemfile, Path::to_path_buf);
+
+    // A group profile replaces configured groups; deployment still skips
+    // development and test
+    let (without_groups, with_groups) = if let Some(name) = group_profile {
+        let profile = config::group_profile(Some(&cfg), name)?;
+        let mut without = profile.without;
+        if deployment {
+            for group in ["development", "test"] {
+                if !without.iter().any(|g| g == group) {
+                    without.push(group.to_string());
+                }
+            }
+        }
+        if verbose {
+            println!("Using group profile {name}");
+        }
+        (without, profile.with)
+    } else {
+        (without_groups, with_groups)
+    };
+
+    // A forced reinstall also distrusts cached resolutions
+    if redownload
+        && !dry_run
+        && let Ok(cache_dir) = config::cache_dir(Some(&cfg))
+        && let Err(e) = lode::ResolutionCache::new(&cache_dir).clear()
+        && verbose
+    {
+        println!("Could not clear resolution cache: {e}");
+    }
 
     // 3. Check frozen mode - Gemfile must not have changed without updating lockfile
     if frozen {
@@ -138,18 +291,17 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
     };
 
     // Download and cache full index if requested
-    let _full_index_data = if full_index {
+    let _full_index_data = if full_index && !dry_run {
+        let _span = profiler.phase(Phase::Metadata);
         if verbose {
             println!("Downloading and parsing full RubyGems index...");
         }
 
         // Load sources from Gemfile if available
-        let source = Gemfile::parse_file(lode::paths::find_gemfile())
-            .as_ref()
-            .map_or_else(
-                |_| lode::DEFAULT_GEM_SOURCE.to_string(),
-                |gemfile| gemfile.source.clone(),
-            );
+        let source = Gemfile::parse_file(&gemfile_path).as_ref().map_or_else(
+            |_| lode::DEFAULT_GEM_SOURCE.to_string(),
+            |gemfile| gemfile.source.clone(),
+        );
 
         // Check if we have a cached index
         let cache_dir = lode::config::cache_dir(None)?;
@@ -171,13 +323,13 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
                 if !quiet {
                     println!("Cached index invalid, downloading fresh index...");
                 }
-                let idx = lode::FullIndex::download_and_parse(&source).await?;
+                let idx = download_full_index(&source, &cache_dir, quiet).await?;
                 idx.save_to_cache(&index_cache_path)?;
                 idx
             }
         } else {
             // Download fresh index
-            let idx = lode::FullIndex::download_and_parse(&source).await?;
+            let idx = download_full_index(&source, &cache_dir, quiet).await?;
             if verbose {
                 println!(
                     "Downloaded {} gems with {} versions",
@@ -215,16 +367,30 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
 
     if lockfile.gems.is_empty() {
         if !quiet {
-            println!("No gems found in lockfile.");
+            println!("{}", messages::text(Message::NoGemsInLockfile));
         }
         return Ok(());
     }
 
     // 3. Load Gemfile for sources (supports Gemfile and gems.rb)
-    let gemfile = Gemfile::parse_file(lode::paths::find_gemfile()).ok();
+    let gemfile = Gemfile::parse_file(&gemfile_path).ok();
+    if let Some(gf) = &gemfile {
+        super::lock::check_sources(gf, config::multisource_disabled(Some(&cfg)), quiet)?;
+    }
 
-    // 4. Filter gems by groups (without/with group support)
-    let gems_to_install = if !without_groups.is_empty() || !with_groups.is_empty() {
+    // 4. Filter gems by groups (only, or without/with group support)
+    let gems_to_install = if !only_groups.is_empty() {
+        if let Some(ref gf) = gemfile {
+            filter_gems_by_only_groups(&lockfile, gf, &only_groups, verbose)
+        } else {
+            if verbose {
+                println!(
+                    "Warning: Group filtering requested but no Gemfile found, installing all gems"
+                );
+            }
+            lockfile.gems.clone()
+        }
+    } else if !without_groups.is_empty() || !with_groups.is_empty() {
         if let Some(ref gf) = gemfile {
             filter_gems_by_groups(&lockfile.gems, gf, &without_groups, &with_groups, verbose)
         } else {
@@ -240,7 +406,7 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
     };
 
     if gems_to_install.is_empty() {
-        println!("No gems to install after filtering.");
+        println!("{}", messages::text(Message::NoGemsAfterFiltering));
         return Ok(());
     }
 
@@ -266,15 +432,27 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
         },
     );
 
-    if verbose && sources.len() > 1 {
+    let sources_count = sources.len();
+    if verbose && sources_count > 1 {
         println!("Gem sources: {}", sources.join(", "));
     }
 
     let max_retries = retry.unwrap_or(0);
-    let dm = Arc::new(
+    // Counts downloaded bytes; drawn once the download total is known
+    let byte_progress = ProgressBar::hidden();
+    let dm = if dry_run {
+        DownloadManager::for_planning(cache_dir, sources, max_retries)
+    } else {
         DownloadManager::with_sources_and_retry(cache_dir, sources, max_retries)
-            .context("Failed to create download manager")?
-            .with_skip_cache(no_cache),
+    };
+    let dm = Arc::new(
+        dm.context("Failed to create download manager")?
+            .with_skip_cache(no_cache)
+            .with_local_only(dry_run && local)
+            .with_progress({
+                let byte_progress = byte_progress.clone();
+                move |bytes| byte_progress.inc(bytes)
+            }),
     );
 
     // 6. Filter gems by platform (after group filtering)
@@ -284,6 +462,7 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
         .into_iter()
         .filter(|gem| lode::platform_matches(&gem.platform, &current_platform))
         .collect();
+    let gems = force_ruby_platform(gems, &config::ruby_platform_gems(Some(&cfg)), quiet);
 
     if verbose {
         println!(
@@ -296,20 +475,30 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
 
     // 6. Create extension builder and binstub generator
     let mut extension_builder =
-        ExtensionBuilder::new(false, verbose, target_rbconfig.map(String::from));
+        ExtensionBuilder::new(false, verbose, target_rbconfig.map(String::from))
+            .with_arch(arch.map(String::from));
+    if !quiet && !dry_run {
+        warn_arch_mismatch(arch);
+    }
     let mut build_results = Vec::with_capacity(gems.len());
 
     let bin_dir = vendor_dir.join("ruby").join(&ruby_ver).join("bin");
-    let gemfile_path = lode::paths::find_gemfile(); // Supports Gemfile and gems.rb
-    let binstub_generator = BinstubGenerator::new(bin_dir, gemfile_path, None, false);
+    let gems_root = vendor_dir.join("ruby").join(&ruby_ver).join("gems");
+    let binstub_generator = BinstubGenerator::new(bin_dir, gemfile_path.clone(), None, false);
     let mut binstub_count = 0;
 
+    if dry_run {
+        let plan = plan_install(&gems, &gems_root, redownload, &dm).await;
+        print_install_plan(&plan, &lockfile);
+        return Ok(());
+    }
+
     // 7. Phase 1: Parallel download all gems
     let total_gems = gems.len();
     let mut skipped_count = 0;
 
     if !quiet {
-        println!("Installing {total_gems} gems...");
+        println!("{}", lode::tr!(Message::InstallingGems, count = total_gems));
     }
 
     // Save a copy of all gems for standalone bundle creation later
@@ -319,6 +508,10 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
     // causing standalone bundles to be empty when all gems were already cached.
     let all_gems_for_standalone = gems.clone();
 
+    // An interrupted install's journal, when resuming it
+    let project_dir = gemfile_path.parent().unwrap_or_else(|| Path::new(""));
+    let resumed = resumable_journal(project_dir, &lockfile_content, resume, quiet);
+
     // Filter out already-installed gems (unless redownload flag is set)
     let gems_to_process: Vec<_> = if redownload {
         // Redownload all gems
@@ -336,6 +529,18 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
                     .join("gems")
                     .join(gem.full_name());
 
+                // A resumed install redoes the gems it had not finished,
+                // whatever an interrupted extraction left behind
+                if let Some(journal) = &resumed
+                    && journal.is_pending(gem.full_name())
+                    && !journal.is_complete(gem.full_name())
+                {
+                    if gem_install_dir.exists() {
+                        fs::remove_dir_all(&gem_install_dir).ok();
+                    }
+                    return true;
+                }
+
                 if gem_install_dir.exists() {
                     skipped_count += 1;
                     false
@@ -346,12 +551,41 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
             .collect()
     };
 
+    // Resuming keeps the interrupted install's journal; otherwise start one
+    let mut journal = match resumed {
+        Some(journal) => journal,
+        None => lode::install_state::InstallJournal::begin(
+            project_dir,
+            &lockfile_content,
+            gems_to_process
+                .iter()
+                .map(|gem| gem.full_name().to_string()),
+        )
+        .context("Failed to write install state")?,
+    };
+
     if gems_to_process.is_empty() {
         if !quiet {
-            println!("All gems already installed!");
+            println!("{}", messages::text(Message::AllGemsInstalled));
         }
         // If standalone bundle requested, continue to create it even if all gems already installed
         if standalone.is_none() {
+            write_group_manifests(
+                &lockfile,
+                gemfile.as_ref(),
+                &gemfile_path,
+                &gems_root,
+                verbose,
+            );
+            write_activation_manifest(
+                &lockfile,
+                &lockfile_content,
+                &gemfile_path,
+                &gems_root,
+                &ruby_ver,
+                verbose,
+            );
+            journal.finish().context("Failed to remove install state")?;
             return Ok(());
         }
     }
@@ -406,15 +640,88 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
         }
     }
 
-    // Create download tasks for all gems
+    let downloads = profiler.phase(Phase::Download);
+
+    // Gems locked from typed sources are fetched by their source provider
+    // into the download cache, where the download manager picks them up
+    if !lockfile.plugin_sources.is_empty() {
+        let routes = lode::SourceRoutes::from_lockfile(
+            &lockfile,
+            &lode::SourceProviders::builtin(dm.cache_dir()),
+        )
+        .context("Failed to set up gem sources")?;
+        for gem in &gems_to_process {
+            let _span = profiler.gem(Phase::Download, gem.full_name());
+            if let Some(fetched) = routes.fetch_into_cache(gem, dm.cache_dir()).await {
+                fetched?;
+            }
+        }
+    }
+
+    // Sizes of the gems to fetch, so progress can be shown in bytes
+    let progress = InstallProgress::new(verbose, quiet);
+    let show_progress = progress.shows_bars();
+    let expected_sizes: Vec<Option<u64>> = if show_progress && !local {
+        futures_util::future::join_all(gems_to_process.iter().map(|gem| {
+            let dm = Arc::clone(&dm);
+            async move {
+                if dm.cached_gem(gem).is_some() {
+                    Some(0)
+                } else {
+                    dm.gem_size(gem).await
+                }
+            }
+        }))
+        .await
+    } else {
+        vec![Some(0); gems_to_process.len()]
+    };
+
+    let pb_download = if show_progress {
+        byte_progress.set_length(expected_sizes.iter().flatten().sum());
+        byte_progress.set_style(
+            ProgressStyle::default_bar()
+                .template(
+                    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] \
+                     {bytes}/{total_bytes} ({bytes_per_sec}, ETA {eta}) {msg}",
+                )
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+        byte_progress.set_message("Downloading...");
+        Some(progress.overall(byte_progress))
+    } else {
+        None
+    };
+
+    // Create download tasks for all gems, `--jobs` of them at a time
     let num_gems_to_process = gems_to_process.len();
     let mut download_tasks = Vec::with_capacity(num_gems_to_process);
+    let download_slots = Arc::new(tokio::sync::Semaphore::new(
+        workers.unwrap_or(DEFAULT_DOWNLOAD_JOBS).max(1),
+    ));
 
-    for gem in gems_to_process {
+    for (gem, expected_size) in gems_to_process
+        .into_iter()
+        .zip(expected_sizes.iter().copied())
+    {
         let dm_clone = Arc::clone(&dm);
-
-        let task =
-            tokio::spawn(async move { dm_clone.download_gem(&gem).await.map(|path| (gem, path)) });
+        let profiler = profiler.clone();
+        let progress = progress.clone();
+        let download_slots = Arc::clone(&download_slots);
+
+        let task = tokio::spawn(async move {
+            let _slot = download_slots.acquire().await;
+            let _span = profiler.gem(Phase::Download, gem.full_name());
+            let cached = dm_clone.cached_gem(&gem).is_some();
+            let _line = (!cached).then(|| progress.worker("Fetching", gem.full_name()));
+            let started = Instant::now();
+            dm_clone.download_gem(&gem).await.map(|path| {
+                let size = fs::metadata(&path).map_or(0, |metadata| metadata.len());
+                let fetched_in = (!cached).then(|| started.elapsed());
+                (gem, path, size, fetched_in, expected_size)
+            })
+        });
 
         download_tasks.push(task);
     }
@@ -424,32 +731,44 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
         println!("Downloading {num_gems_to_process} gems in parallel...");
     }
 
-    let pb_download = if verbose || quiet {
-        None
-    } else {
-        let progress = ProgressBar::new(download_tasks.len() as u64);
-        progress.set_style(
-            ProgressStyle::default_bar()
-                .template(
-                    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}",
-                )
-                .unwrap()
-                .progress_chars("#>-"),
-        );
-        progress.set_message("Downloading...");
-        Some(progress)
-    };
-
     let mut downloaded_gems = Vec::with_capacity(download_tasks.len());
+    let mut downloaded_bytes = 0;
+    let mut cached_bytes = 0;
+    let mut usage = lode::CacheUsage::load(dm.cache_dir());
 
     for task in download_tasks {
         match task.await {
-            Ok(Ok((gem, cache_path))) => {
+            Ok(Ok((gem, cache_path, size, fetched_in, expected_size))) => {
+                let detail = fetched_in.map_or_else(
+                    || "from cache".to_string(),
+                    |elapsed| {
+                        format!(
+                            "{}, {}",
+                            super::clean::format_bytes(size),
+                            transfer_rate(size, elapsed)
+                        )
+                    },
+                );
+                if fetched_in.is_some() {
+                    downloaded_bytes += size;
+                } else {
+                    cached_bytes += size;
+                }
+                usage.record_gem(fetched_in.is_none(), size);
                 if verbose {
-                    println!("  Downloaded {}", gem.full_name());
+                    match dm.served_by(&gem) {
+                        Some(source) if sources_count > 1 && fetched_in.is_some() => {
+                            println!("  Downloaded {} from {source} ({detail})", gem.full_name());
+                        }
+                        _ => println!("  Downloaded {} ({detail})", gem.full_name()),
+                    }
                 }
                 if let Some(ref pb) = pb_download {
-                    pb.inc(1);
+                    // Sources that reported no size grow the total as they finish
+                    if expected_size.is_none() {
+                        pb.inc_length(size);
+                    }
+                    pb.set_message(format!("{} ({detail})", gem.full_name()));
                 }
                 downloaded_gems.push((gem, cache_path));
             }
@@ -471,6 +790,34 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
     if let Some(pb) = pb_download {
         pb.finish_with_message("Downloads complete!");
     }
+    drop(downloads);
+
+    if let Err(e) = usage.save() {
+        lode::debug!("could not save cache usage counters: {e}");
+    }
+
+    // Which sources served the downloads, and which were failing
+    if sources_count > 1 && !quiet {
+        let health = dm.source_health();
+        if verbose || health.iter().any(|source| source.failures > 0) {
+            println!("Source health:");
+            for source in &health {
+                let latency = source
+                    .average_latency()
+                    .map(|latency| format!(", avg {:.2}s", latency.as_secs_f64()))
+                    .unwrap_or_default();
+                let demoted = if source.is_demoted() {
+                    " (demoted)"
+                } else {
+                    ""
+                };
+                println!(
+                    "  {}: {} gems{latency}, {} failures{demoted}",
+                    source.source, source.served, source.failures
+                );
+            }
+        }
+    }
 
     // 7.5. Verify gem signatures if trust policy is enabled
     if let Some(ref verifier) = gem_verifier {
@@ -486,11 +833,10 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
                     }
                 }
                 Err(e) => {
-                    return Err(anyhow::anyhow!(
-                        "Gem verification failed for {}: {}",
-                        gem.full_name(),
-                        e
-                    ));
+                    return Err(ExitCode::Verification.error(format!(
+                        "Gem verification failed for {}: {e}",
+                        gem.full_name()
+                    )));
                 }
             }
         }
@@ -505,11 +851,9 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
         println!("\nExtracting {} gems...", downloaded_gems.len());
     }
 
-    let pb_install = if verbose {
-        None
-    } else {
-        let progress = ProgressBar::new(downloaded_gems.len() as u64);
-        progress.set_style(
+    let pb_install = show_progress.then(|| {
+        let bar = ProgressBar::new(downloaded_gems.len() as u64);
+        bar.set_style(
             ProgressStyle::default_bar()
                 .template(
                     "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} {msg}",
@@ -517,28 +861,34 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
                 .unwrap()
                 .progress_chars("#>-"),
         );
-        progress.set_message("Installing...");
-        Some(progress)
-    };
+        bar.set_message("Installing...");
+        progress.overall(bar)
+    });
 
     // Parallel extraction
-    let install_results: Vec<_> = downloaded_gems
+    let collisions = lode::install::CaseCollisions::from_config(Some(&cfg));
+    let extraction = profiler.phase(Phase::Extraction);
+    let mut install_results: Vec<_> = downloaded_gems
         .par_iter()
         .map(|(gem, cache_path)| {
-            let result = lode::install::install_gem(gem, cache_path, &vendor_dir, &ruby_ver);
+            let _span = profiler.gem(Phase::Extraction, gem.full_name());
+            let _line = progress.worker("Installing", gem.full_name());
+            let result =
+                lode::install::install_gem(gem, cache_path, &vendor_dir, &ruby_ver, collisions);
             if let Some(ref pb) = pb_install {
                 pb.inc(1);
             }
-            (gem, result)
+            (gem, cache_path, result)
         })
         .collect();
 
     if let Some(pb) = pb_install {
         pb.finish_with_message("Installation complete!");
     }
+    drop(extraction);
 
     // Check for installation errors
-    for (gem, result) in &install_results {
+    for (gem, _, result) in &install_results {
         if let Err(e) = result {
             return Err(anyhow::anyhow!("Failed to install {}: {}", gem.name, e));
         }
@@ -551,7 +901,14 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
         println!("\nBuilding extensions and binstubs...");
     }
 
-    for (gem, _) in &install_results {
+    // Start the historically slowest builds first
+    let mut build_history = lode::BuildHistory::load(dm.cache_dir());
+    let machine = lode::build_history::current_machine();
+    build_history.schedule(&mut install_results, &machine, |(gem, _, _)| {
+        (gem.name.as_str(), gem.version.as_str())
+    });
+
+    for (gem, cache_path, _) in &install_results {
         let gem_install_dir = vendor_dir
             .join("ruby")
             .join(&ruby_ver)
@@ -559,9 +916,39 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
             .join(gem.full_name());
 
         // Build extension if needed
-        if let Some(build_result) =
-            extension_builder.build_if_needed(&gem.name, &gem_install_dir, gem.platform.as_deref())
-        {
+        let span = profiler.gem(Phase::Extensions, gem.full_name());
+        let extension = lode::Gemspec::load_for_gem_dir(&gem_install_dir).map_or_else(
+            || {
+                lode::extensions::detect_extension(
+                    &gem_install_dir,
+                    &gem.name,
+                    gem.platform.as_deref(),
+                )
+            },
+            |spec| {
+                lode::extensions::detect_extension_from_spec(
+                    &gem_install_dir,
+                    &spec.extensions,
+                    gem.platform.as_deref(),
+                )
+            },
+        );
+        let line = extension.needs_building().then(|| {
+            progress.worker(
+                "Compiling",
+                &format!("{} ({})", gem.full_name(), extension.description()),
+            )
+        });
+        let built =
+            extension_builder.build_if_needed(&gem.name, &gem_install_dir, gem.platform.as_deref());
+        drop(line);
+        drop(span);
+        // A failed build stays unfinished so `--resume` retries it
+        let extension_ok = built.as_ref().is_none_or(|result| result.success);
+        if let Some(build_result) = built {
+            if build_result.success {
+                build_history.record(&gem.name, &gem.version, &machine, build_result.duration);
+            }
             if verbose {
                 if build_result.success {
                     println!(
@@ -581,7 +968,10 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
         }
 
         // Generate binstubs if gem has executables
-        match binstub_generator.generate(&gem.name, &gem_install_dir) {
+        let span = profiler.gem(Phase::Binstubs, gem.full_name());
+        let generated = binstub_generator.generate(&gem.name, &gem_install_dir);
+        drop(span);
+        match generated {
             Ok(count) if count > 0 => {
                 if verbose {
                     println!("Generated {} binstub(s) for {}", count, gem.name);
@@ -595,6 +985,16 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
                 }
             }
         }
+
+        if extension_ok {
+            journal
+                .record(gem.full_name(), cache_path)
+                .context("Failed to write install state")?;
+        }
+    }
+
+    if let Err(e) = build_history.save() {
+        lode::debug!("could not save extension build history: {e}");
     }
 
     // 8. Install path gems (if any)
@@ -611,7 +1011,13 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
                 );
             }
 
-            match lode::install::install_path_gem(path_gem, &vendor_dir, &ruby_ver) {
+            let result = if link_path_gems {
+                lode::install::link_path_gem(path_gem, &vendor_dir, &ruby_ver)
+            } else {
+                lode::install::install_path_gem(path_gem, &vendor_dir, &ruby_ver)
+            };
+
+            match result {
                 Ok(()) => {
                     installed_count += 1;
 
@@ -622,9 +1028,11 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
                         .join("gems")
                         .join(format!("{}-{}", path_gem.name, path_gem.version));
 
-                    if let Some(build_result) =
-                        extension_builder.build_if_needed(&path_gem.name, &gem_install_dir, None)
-                    {
+                    let span = profiler.gem(Phase::Extensions, &path_gem.name);
+                    let built =
+                        extension_builder.build_if_needed(&path_gem.name, &gem_install_dir, None);
+                    drop(span);
+                    if let Some(build_result) = built {
                         if verbose {
                             if build_result.success {
                                 println!(
@@ -642,7 +1050,10 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
                     }
 
                     // Generate binstubs if gem has executables
-                    match binstub_generator.generate(&path_gem.name, &gem_install_dir) {
+                    let span = profiler.gem(Phase::Binstubs, &path_gem.name);
+                    let generated = binstub_generator.generate(&path_gem.name, &gem_install_dir);
+                    drop(span);
+                    match generated {
                         Ok(count) if count > 0 => {
                             if verbose {
                                 println!("    Generated {count} binstub(s)");
@@ -682,6 +1093,8 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
         // Create git manager
         let git_cache_dir = config::cache_dir(Some(&cfg))?.join("git");
         let git_manager = GitManager::new(git_cache_dir).context("Failed to create git manager")?;
+        let local_overrides = config::local_overrides(Some(&cfg));
+        let check_branch = !config::local_branch_check_disabled(Some(&cfg));
 
         for git_gem in &lockfile.git_gems {
             if verbose {
@@ -694,8 +1107,28 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
                 );
             }
 
-            // Clone and checkout
-            match git_manager.clone_and_checkout(&git_gem.repository, &git_gem.revision) {
+            // Use a local override checkout, or clone and checkout the locked revision
+            let checkout = local_overrides.get(&git_gem.name).map_or_else(
+                || git_manager.clone_and_checkout(&git_gem.repository, &git_gem.revision),
+                |local_path| {
+                    let revision = lode::git::verify_local_override(
+                        &git_gem.name,
+                        local_path,
+                        git_gem.branch.as_deref(),
+                        check_branch,
+                    )?;
+                    if verbose && revision != git_gem.revision {
+                        println!(
+                            "Using local override at {} ({})",
+                            local_path.display(),
+                            revision.chars().take(8).collect::<String>()
+                        );
+                    }
+                    Ok(local_path.clone())
+                },
+            );
+
+            match checkout {
                 Ok(source_dir) => {
                     if verbose {
                         println!("Checked out to {}", source_dir.display());
@@ -707,6 +1140,7 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
                         &source_dir,
                         &vendor_dir,
                         &ruby_ver,
+                        collisions,
                     ) {
                         Ok(()) => {
                             installed_count += 1;
@@ -718,11 +1152,14 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
                                 .join("gems")
                                 .join(format!("{}-{}", git_gem.name, git_gem.version));
 
-                            if let Some(build_result) = extension_builder.build_if_needed(
+                            let span = profiler.gem(Phase::Extensions, &git_gem.name);
+                            let built = extension_builder.build_if_needed(
                                 &git_gem.name,
                                 &gem_install_dir,
                                 None,
-                            ) {
+                            );
+                            drop(span);
+                            if let Some(build_result) = built {
                                 if verbose {
                                     if build_result.success {
                                         println!(
@@ -743,7 +1180,11 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
                             }
 
                             // Generate binstubs if gem has executables
-                            match binstub_generator.generate(&git_gem.name, &gem_install_dir) {
+                            let span = profiler.gem(Phase::Binstubs, &git_gem.name);
+                            let generated =
+                                binstub_generator.generate(&git_gem.name, &gem_install_dir);
+                            drop(span);
+                            match generated {
                                 Ok(count) if count > 0 => {
                                     if verbose {
                                         println!("Generated {count} binstub(s)");
@@ -779,16 +1220,45 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
         }
     }
 
+    write_group_manifests(
+        &lockfile,
+        gemfile.as_ref(),
+        &gemfile_path,
+        &gems_root,
+        verbose,
+    );
+    write_activation_manifest(
+        &lockfile,
+        &lockfile_content,
+        &gemfile_path,
+        &gems_root,
+        &ruby_ver,
+        verbose,
+    );
+
     let elapsed = start_time.elapsed();
 
     // 10. Print summary
     println!(
-        "\nInstalled {} gems ({} skipped) to {} in {:.2}s",
-        installed_count,
-        skipped_count,
-        vendor_dir.display(),
-        elapsed.as_secs_f64()
+        "\n{}",
+        lode::tr!(
+            Message::InstallSummary,
+            count = installed_count,
+            skipped = skipped_count,
+            path = vendor_dir.display(),
+            seconds = format!("{:.2}", elapsed.as_secs_f64()),
+        )
     );
+    if downloaded_bytes > 0 || cached_bytes > 0 {
+        println!(
+            "{}",
+            lode::tr!(
+                Message::DownloadSummary,
+                downloaded = super::clean::format_bytes(downloaded_bytes),
+                cached = super::clean::format_bytes(cached_bytes),
+            )
+        );
+    }
 
     // Report extension build results
     if !build_results.is_empty() {
@@ -821,6 +1291,21 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
         println!("Binstubs: {binstub_count} binstub(s) generated");
     }
 
+    // Report funding requests and deprecated or unmaintained gems
+    if !quiet {
+        let source = gemfile
+            .as_ref()
+            .map_or(lode::DEFAULT_GEM_SOURCE, |gf| gf.source.as_str());
+        report_gem_notices(
+            &all_gems_for_standalone,
+            &vendor_dir.join("ruby").join(&ruby_ver),
+            &cfg,
+            source,
+            local,
+        )
+        .await;
+    }
+
     // 10. Auto-clean if BUNDLE_CLEAN is enabled
     if auto_clean {
         if verbose {
@@ -835,7 +1320,7 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
             }
             Err(e) => {
                 if verbose {
-                    eprintln!("Warning: Auto-clean failed: {e}");
+                    lode::warn!("Auto-clean failed: {e}");
                 }
             }
         }
@@ -944,6 +1429,11 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
         bundle
             .generate_setup_rb(&filtered_gems)
             .context("Failed to generate setup.rb")?;
+        if let Some(ref gf) = gemfile {
+            bundle
+                .generate_require_rb(&filtered_gems, gf)
+                .context("Failed to generate require.rb")?;
+        }
 
         println!("OK Standalone bundle created in ./bundle");
         println!("  -> {} gems included", filtered_gems.len());
@@ -953,93 +1443,1045 @@ pub(crate) async fn run(options: InstallOptions<'_>) -> Result<()> {
         println!();
         println!("Usage:");
         println!("  ruby -r ./bundle/bundler/setup.rb your_script.rb");
+        if gemfile.is_some() {
+            println!("  ruby -r ./bundle/bundler/require.rb your_script.rb  # for Bundler.require");
+        }
+    }
+
+    let failed_builds: Vec<&str> = build_results
+        .iter()
+        .filter(|result| !result.success)
+        .map(|result| result.gem_name.as_str())
+        .collect();
+    if !failed_builds.is_empty() {
+        return Err(ExitCode::ExtensionBuild.error(format!(
+            "Failed to build native extensions for {} (retry with `lode install --resume`)",
+            failed_builds.join(", ")
+        )));
     }
 
+    journal.finish().context("Failed to remove install state")?;
+
     Ok(())
 }
 
-/// Check frozen mode - ensure Gemfile hasn't changed without updating lockfile
-fn check_frozen_mode(lockfile_path: &str, verbose: bool) -> Result<()> {
-    // Determine Gemfile path from lockfile path
-    let gemfile_path = if std::path::Path::new(lockfile_path)
-        .extension()
-        .is_some_and(|ext| ext.eq_ignore_ascii_case("lock"))
-    {
-        lockfile_path.trim_end_matches(".lock")
-    } else {
-        "Gemfile"
-    };
-
-    // Check if Gemfile exists
-    let gemfile_pathbuf = std::path::Path::new(gemfile_path);
-    if !gemfile_pathbuf.exists() {
-        // No Gemfile, nothing to check
-        return Ok(());
+/// The journal to resume from, reporting why there is none
+///
+/// Without `--resume` an interrupted install's journal is only mentioned;
+/// one written for a different lockfile is never resumed.
+fn resumable_journal(
+    project_dir: &Path,
+    lockfile_content: &str,
+    resume: bool,
+    quiet: bool,
+) -> Option<lode::install_state::InstallJournal> {
+    let journal = lode::install_state::InstallJournal::load(project_dir);
+    if !resume {
+        if journal.is_some() && !quiet {
+            println!(
+                "A previous install was interrupted; run `lode install --resume` to continue it"
+            );
+        }
+        return None;
+    }
+    match journal {
+        Some(journal) if journal.matches_lockfile(lockfile_content) => {
+            if !quiet {
+                println!(
+                    "Resuming interrupted install ({} gems already finished)",
+                    journal.completed_count()
+                );
+            }
+            Some(journal)
+        }
+        Some(_) => {
+            lode::warn!("The lockfile changed since the interrupted install; starting over");
+            None
+        }
+        None => {
+            if !quiet {
+                println!("No interrupted install to resume");
+            }
+            None
+        }
     }
+}
 
-    // Get modification times
-    let lockfile_metadata = std::fs::metadata(lockfile_path)
-        .context("Lockfile not found - frozen mode requires an existing lockfile")?;
-    let gemfile_metadata =
-        std::fs::metadata(gemfile_path).context("Failed to read Gemfile metadata")?;
+/// How often `install --watch` polls for changes
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(500);
 
-    let lockfile_modified = lockfile_metadata
-        .modified()
-        .context("Failed to get lockfile modification time")?;
-    let gemfile_modified = gemfile_metadata
-        .modified()
-        .context("Failed to get Gemfile modification time")?;
+/// Quiet period after the last change before reinstalling
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
 
-    // If Gemfile is newer than lockfile, error in frozen mode
-    if gemfile_modified > lockfile_modified {
-        anyhow::bail!(
-            "Your Gemfile has been modified since the lockfile was generated.\n\
-             In frozen mode, Bundler will not check for changes.\n\
-             To update the lockfile, run `bundle lock` or `bundle install` without frozen mode."
-        );
-    }
+/// Source file extensions that trigger a rebuild of path gem extensions
+const WATCHED_EXTENSION_SOURCES: &[&str] = &["c", "cc", "cpp", "h", "hpp", "m", "rb", "rs"];
 
-    if verbose {
-        println!("Frozen mode: Gemfile matches lockfile");
+/// Modification times of everything `install --watch` reacts to
+#[derive(Debug, Default, PartialEq, Eq)]
+struct WatchSnapshot {
+    /// Gemfile and path gem gemspecs (changes require re-resolving)
+    manifests: BTreeMap<PathBuf, SystemTime>,
+    /// Extension sources of path gems (changes require a rebuild only)
+    extensions: BTreeMap<PathBuf, SystemTime>,
+}
+
+/// Install, then keep re-resolving and installing as the Gemfile changes
+///
+/// Polls the Gemfile, path gem gemspecs and path gem extension sources.
+/// Gemfile edits re-lock only the gems whose declarations changed; extension
+/// edits just reinstall, which rebuilds the linked path gem in place.
+pub(crate) async fn watch(mut options: InstallOptions<'_>, gemfile_path: &Path) -> Result<()> {
+    // Linked path gems make source edits visible without recopying
+    options.link_path_gems = true;
+    let lockfile_path = PathBuf::from(options.lockfile_path);
+
+    if let Err(e) = run(options.clone()).await {
+        eprintln!("Install failed: {e:#}");
     }
 
-    Ok(())
-}
+    let mut gemfile = Gemfile::parse_file(gemfile_path).ok();
+    let mut snapshot = watch_snapshot(gemfile_path, &lockfile_path);
+    println!(
+        "\nWatching {} for changes (Ctrl-C to stop)...",
+        gemfile_path.display()
+    );
 
-/// Filter gems by group membership based on without/with group lists
-fn filter_gems_by_groups(
-    lockfile_gems: &[lode::GemSpec],
-    gemfile: &lode::Gemfile,
-    without_groups: &[String],
-    with_groups: &[String],
-    verbose: bool,
-) -> Vec<lode::GemSpec> {
-    use std::collections::HashMap;
+    loop {
+        tokio::time::sleep(WATCH_POLL_INTERVAL).await;
+        let mut current = watch_snapshot(gemfile_path, &lockfile_path);
+        if current == snapshot {
+            continue;
+        }
 
-    // Build a map of gem names to their groups from the Gemfile
-    let gem_groups: HashMap<String, Vec<String>> = gemfile
-        .gems
-        .iter()
-        .map(|gem_dep| (gem_dep.name.clone(), gem_dep.groups.clone()))
-        .collect();
+        // Debounce: wait for editors and formatters to finish writing
+        loop {
+            tokio::time::sleep(WATCH_DEBOUNCE).await;
+            let next = watch_snapshot(gemfile_path, &lockfile_path);
+            if next == current {
+                break;
+            }
+            current = next;
+        }
 
-    // Default group is :default - gems without explicit group are in default group
-    let default_group = "default".to_string();
+        let before = fs::read_to_string(&lockfile_path)
+            .ok()
+            .and_then(|content| Lockfile::parse(&content).ok());
 
-    let filtered: Vec<_> = lockfile_gems
-        .iter()
-        .filter(|gem| {
-            let groups = gem_groups
-                .get(&gem.name)
-                .cloned()
-                .unwrap_or_else(|| vec![default_group.clone()]);
+        if current.manifests == snapshot.manifests {
+            println!("\nPath gem extension sources changed: rebuilding");
+        } else {
+            gemfile = relock_changed(gemfile, gemfile_path, &options).await;
+        }
 
-            // If with_groups is specified, only include gems in those groups
-            if !with_groups.is_empty() {
-                let in_with_groups = groups.iter().any(|g| with_groups.contains(g));
-                if !in_with_groups {
-                    if verbose {
-                        println!(
+        let started = Instant::now();
+        let install_options = InstallOptions {
+            quiet: true,
+            ..options.clone()
+        };
+        match run(install_options).await {
+            Ok(()) => {
+                let after = fs::read_to_string(&lockfile_path)
+                    .ok()
+                    .and_then(|content| Lockfile::parse(&content).ok());
+                let changes = match (&before, &after) {
+                    (Some(before), Some(after)) => lockfile_changes(before, after),
+                    _ => Vec::new(),
+                };
+                for change in &changes {
+                    println!("  {change}");
+                }
+                println!(
+                    "Bundle updated in {:.1}s ({} gem change{})",
+                    started.elapsed().as_secs_f64(),
+                    changes.len(),
+                    if changes.len() == 1 { "" } else { "s" }
+                );
+            }
+            Err(e) => eprintln!("Install failed: {e:#}"),
+        }
+
+        // Re-snapshot so files written during the install are not seen as edits
+        snapshot = watch_snapshot(gemfile_path, &lockfile_path);
+    }
+}
+
+/// Re-resolve the gems whose Gemfile declarations changed
+///
+/// Returns the Gemfile to diff against next time; a Gemfile that fails to
+/// parse keeps the previous one so the next valid edit is compared correctly.
+async fn relock_changed(
+    previous: Option<Gemfile>,
+    gemfile_path: &Path,
+    options: &InstallOptions<'_>,
+) -> Option<Gemfile> {
+    let updated = match Gemfile::parse_file(gemfile_path) {
+        Ok(updated) => updated,
+        Err(e) => {
+            eprintln!("\nGemfile has errors, waiting for a fix: {e}");
+            return previous;
+        }
+    };
+
+    let changed = changed_declarations(previous.as_ref(), &updated);
+    if !changed.is_empty() {
+        println!("\nGemfile changed: re-resolving {}", changed.join(", "));
+        if let Err(e) = super::lock::run(
+            &gemfile_path.display().to_string(),
+            Some(options.lockfile_path),
+            &[],
+            &[],
+            &changed,
+            false,
+            &std::collections::BTreeMap::new(),
+            lode::ResolutionStrategy::Latest,
+            false,
+            options.verbose,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            options.local,
+            false,
+            None,
+            false,
+            false,
+            options.full_index,
+            true,
+            &options.profiler,
+        )
+        .await
+        {
+            eprintln!("Resolution failed: {e:#}");
+        }
+    }
+
+    Some(updated)
+}
+
+/// Collect modification times for the Gemfile and path gems in the lockfile
+fn watch_snapshot(gemfile_path: &Path, lockfile_path: &Path) -> WatchSnapshot {
+    let mut snapshot = WatchSnapshot::default();
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    if let Some(mtime) = modified(gemfile_path) {
+        snapshot.manifests.insert(gemfile_path.to_path_buf(), mtime);
+    }
+
+    let path_gems = fs::read_to_string(lockfile_path)
+        .ok()
+        .and_then(|content| Lockfile::parse(&content).ok())
+        .map(|lockfile| lockfile.path_gems)
+        .unwrap_or_default();
+
+    for path_gem in path_gems {
+        let root = PathBuf::from(&path_gem.path);
+        if let Ok(entries) = fs::read_dir(&root) {
+            for path in entries.flatten().map(|e| e.path()) {
+                if path.extension().is_some_and(|ext| ext == "gemspec")
+                    && let Some(mtime) = modified(&path)
+                {
+                    snapshot.manifests.insert(path, mtime);
+                }
+            }
+        }
+
+        for entry in walkdir::WalkDir::new(root.join("ext"))
+            .into_iter()
+            .filter_entry(|e| e.file_name() != "target")
+            .filter_map(Result::ok)
+            .filter(|e| e.file_type().is_file())
+        {
+            let is_source = entry
+                .path()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| WATCHED_EXTENSION_SOURCES.contains(&ext));
+            if is_source && let Some(mtime) = modified(entry.path()) {
+                snapshot.extensions.insert(entry.into_path(), mtime);
+            }
+        }
+    }
+
+    snapshot
+}
+
+/// Gems whose Gemfile declaration was added, removed or changed
+///
+/// Everything else stays locked at its current version when re-resolving.
+/// With no previous Gemfile every declared gem counts as changed.
+fn changed_declarations(previous: Option<&Gemfile>, current: &Gemfile) -> Vec<String> {
+    let Some(previous) = previous else {
+        return current.gems.iter().map(|g| g.name.clone()).collect();
+    };
+
+    let mut changed: Vec<String> = current
+        .gems
+        .iter()
+        .filter(|gem| {
+            previous
+                .gems
+                .iter()
+                .find(|old| old.name == gem.name)
+                .is_none_or(|old| {
+                    old.version_requirement != gem.version_requirement
+                        || old.source != gem.source
+                        || old.git != gem.git
+                        || old.path != gem.path
+                        || old.platforms != gem.platforms
+                })
+        })
+        .map(|gem| gem.name.clone())
+        .collect();
+
+    changed.extend(
+        previous
+            .gems
+            .iter()
+            .filter(|old| !current.gems.iter().any(|gem| gem.name == old.name))
+            .map(|old| old.name.clone()),
+    );
+
+    changed
+}
+
+/// Human-readable gem additions, removals and version changes
+fn lockfile_changes(before: &Lockfile, after: &Lockfile) -> Vec<String> {
+    let versions = |lockfile: &Lockfile| -> BTreeMap<String, String> {
+        lockfile
+            .gems
+            .iter()
+            .map(|g| (g.name.clone(), g.version.clone()))
+            .chain(
+                lockfile
+                    .git_gems
+                    .iter()
+                    .map(|g| (g.name.clone(), g.version.clone())),
+            )
+            .chain(
+                lockfile
+                    .path_gems
+                    .iter()
+                    .map(|g| (g.name.clone(), g.version.clone())),
+            )
+            .collect()
+    };
+    let old = versions(before);
+    let new = versions(after);
+
+    let mut changes = Vec::new();
+    for (name, version) in &new {
+        match old.get(name) {
+            None => changes.push(format!("+ {name} {version}")),
+            Some(previous) if previous != version => {
+                changes.push(format!("~ {name} {previous} -> {version}"));
+            }
+            Some(_) => {}
+        }
+    }
+    for (name, version) in &old {
+        if !new.contains_key(name) {
+            changes.push(format!("- {name} {version}"));
+        }
+    }
+    changes
+}
+
+/// Concurrent downloads when `--jobs` is not given
+const DEFAULT_DOWNLOAD_JOBS: usize = 16;
+
+/// How [`InstallProgress`] reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProgressMode {
+    /// Overall bars plus a live line per busy worker
+    Bars,
+    /// One printed line as each step starts, for logs and CI
+    Lines,
+    /// Verbose and quiet installs print their own output, or none
+    Off,
+}
+
+/// Live install status: overall bars plus one line per busy worker
+///
+/// On a terminal each worker line shows its gem, what it is doing and for
+/// how long, so a long extension compile does not look like a stall. When
+/// stderr is not a terminal the same steps are printed as plain lines.
+#[derive(Clone)]
+struct InstallProgress {
+    multi: MultiProgress,
+    mode: ProgressMode,
+}
+
+impl InstallProgress {
+    fn new(verbose: bool, quiet: bool) -> Self {
+        let mode = if verbose || quiet {
+            ProgressMode::Off
+        } else if std::io::stderr().is_terminal() {
+            ProgressMode::Bars
+        } else {
+            ProgressMode::Lines
+        };
+        Self {
+            multi: MultiProgress::new(),
+            mode,
+        }
+    }
+
+    fn shows_bars(&self) -> bool {
+        self.mode == ProgressMode::Bars
+    }
+
+    /// Draw `bar` above the worker lines that follow it
+    fn overall(&self, bar: ProgressBar) -> ProgressBar {
+        self.multi.add(bar)
+    }
+
+    /// Show `subject` as busy with `action` until the line is dropped
+    fn worker(&self, action: &str, subject: &str) -> WorkerLine {
+        match self.mode {
+            ProgressMode::Bars => {
+                let line = self.multi.add(ProgressBar::new_spinner());
+                line.set_style(
+                    ProgressStyle::with_template("  {spinner:.green} {prefix} {msg} [{elapsed}]")
+                        .unwrap_or_else(|_| ProgressStyle::default_spinner()),
+                );
+                line.set_prefix(action.to_string());
+                line.set_message(subject.to_string());
+                line.enable_steady_tick(Duration::from_millis(100));
+                WorkerLine(Some((self.multi.clone(), line)))
+            }
+            ProgressMode::Lines => {
+                println!("{action} {subject}");
+                WorkerLine(None)
+            }
+            ProgressMode::Off => WorkerLine(None),
+        }
+    }
+}
+
+/// A worker's status line, cleared when dropped
+struct WorkerLine(Option<(MultiProgress, ProgressBar)>);
+
+impl Drop for WorkerLine {
+    fn drop(&mut self) {
+        if let Some((multi, line)) = self.0.take() {
+            line.finish_and_clear();
+            multi.remove(&line);
+        }
+    }
+}
+
+/// Average speed of a transfer, e.g. `1.50 MB/s`
+fn transfer_rate(bytes: u64, elapsed: Duration) -> String {
+    let per_second = u128::from(bytes) * 1000 / elapsed.as_millis().max(1);
+    format!(
+        "{}/s",
+        super::clean::format_bytes(u64::try_from(per_second).unwrap_or(u64::MAX))
+    )
+}
+
+/// Download the full index from `source`, resuming into `cache_dir`, with a
+/// byte progress bar unless `quiet`
+pub(crate) async fn download_full_index(
+    source: &str,
+    cache_dir: &Path,
+    quiet: bool,
+) -> Result<lode::FullIndex> {
+    let bar = if quiet || !std::io::stderr().is_terminal() {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(0).with_style(
+            ProgressStyle::default_bar()
+                .template(
+                    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] \
+                     {bytes}/{total_bytes} ({bytes_per_sec}, ETA {eta}) {msg}",
+                )
+                .unwrap_or_else(|_| ProgressStyle::default_bar())
+                .progress_chars("#>-"),
+        )
+    };
+    bar.set_message("Downloading full index...");
+
+    let index = lode::FullIndex::download_and_parse(source, cache_dir, |received, total| {
+        if let Some(total) = total {
+            bar.set_length(total);
+        }
+        bar.set_position(received);
+    })
+    .await;
+    bar.finish_and_clear();
+    index
+}
+
+/// A gem `lode install` would fetch
+#[derive(Debug, PartialEq, Eq)]
+struct PlannedDownload {
+    full_name: String,
+    /// Already in the download cache, so nothing is fetched
+    cached: bool,
+    /// Bytes on disk when cached, else `Content-Length` from the source
+    size: Option<u64>,
+}
+
+/// What `lode install` would do, worked out without side effects
+#[derive(Debug, Default)]
+struct InstallPlan {
+    /// Gems already installed in the bundle
+    satisfied: Vec<String>,
+    downloads: Vec<PlannedDownload>,
+    /// Gems whose cached metadata declares extensions, with their kind
+    extensions: Vec<(String, String)>,
+    /// Gems that must be downloaded before their extensions are known
+    unchecked: usize,
+}
+
+impl InstallPlan {
+    /// Bytes to fetch, and how many downloads report no size
+    fn download_size(&self) -> (u64, usize) {
+        self.downloads
+            .iter()
+            .filter(|download| !download.cached)
+            .fold((0, 0), |(total, unknown), download| {
+                download
+                    .size
+                    .map_or((total, unknown + 1), |size| (total + size, unknown))
+            })
+    }
+}
+
+/// Plan the install of `gems` into `gems_root` for `--dry-run`
+///
+/// Reads the bundle and download cache and sends `HEAD` requests for sizes;
+/// nothing is written.
+async fn plan_install(
+    gems: &[lode::GemSpec],
+    gems_root: &Path,
+    redownload: bool,
+    dm: &DownloadManager,
+) -> InstallPlan {
+    let mut plan = InstallPlan::default();
+    let mut pending = Vec::new();
+    for gem in gems {
+        if !redownload && gems_root.join(gem.full_name()).exists() {
+            plan.satisfied.push(gem.full_name().to_string());
+        } else {
+            pending.push(gem);
+        }
+    }
+
+    let sizes = futures_util::future::join_all(pending.iter().map(|gem| async move {
+        match dm.cached_gem(gem) {
+            Some(path) => (Some(path.clone()), fs::metadata(path).ok().map(|m| m.len())),
+            None => (None, dm.gem_size(gem).await),
+        }
+    }))
+    .await;
+
+    for (gem, (cached, size)) in pending.into_iter().zip(sizes) {
+        let platform = gem.platform.as_deref();
+        let precompiled = platform.is_some_and(|p| p != "ruby" && !p.is_empty());
+        match cached.as_deref().map(lode::Gemspec::from_gem_file) {
+            _ if precompiled => {}
+            Some(Ok(spec)) => {
+                let extension = lode::extensions::detect_extension_from_spec(
+                    Path::new(""),
+                    &spec.extensions,
+                    platform,
+                );
+                if extension.needs_building() {
+                    plan.extensions.push((
+                        gem.full_name().to_string(),
+                        extension.description().to_string(),
+                    ));
+                }
+            }
+            Some(Err(_)) | None => plan.unchecked += 1,
+        }
+        plan.downloads.push(PlannedDownload {
+            full_name: gem.full_name_with_platform().to_string(),
+            cached: cached.is_some(),
+            size,
+        });
+    }
+    plan
+}
+
+/// Print an [`InstallPlan`] for `lode install --dry-run`
+fn print_install_plan(plan: &InstallPlan, lockfile: &Lockfile) {
+    use super::clean::format_bytes;
+
+    println!("Dry run: nothing will be downloaded, built or written.");
+
+    println!("\nAlready installed ({}):", plan.satisfied.len());
+    for name in &plan.satisfied {
+        println!("  {name}");
+    }
+
+    println!("\nTo install ({}):", plan.downloads.len());
+    for download in &plan.downloads {
+        let size = match (download.cached, download.size) {
+            (true, _) => "cached".to_string(),
+            (false, Some(size)) => format_bytes(size),
+            (false, None) => "size unknown".to_string(),
+        };
+        println!("  {} ({size})", download.full_name);
+    }
+
+    if !plan.extensions.is_empty() || plan.unchecked > 0 {
+        println!("\nExtensions to compile ({}):", plan.extensions.len());
+        for (name, kind) in &plan.extensions {
+            println!("  {name} ({kind})");
+        }
+        if plan.unchecked > 0 {
+            println!(
+                "  {} more gem{} will be checked for extensions after download",
+                plan.unchecked,
+                if plan.unchecked == 1 { "" } else { "s" }
+            );
+        }
+    }
+
+    if !lockfile.git_gems.is_empty() || !lockfile.path_gems.is_empty() {
+        println!(
+            "\nAlso installs {} git gem{} and {} path gem{} from their sources",
+            lockfile.git_gems.len(),
+            if lockfile.git_gems.len() == 1 {
+                ""
+            } else {
+                "s"
+            },
+            lockfile.path_gems.len(),
+            if lockfile.path_gems.len() == 1 {
+                ""
+            } else {
+                "s"
+            }
+        );
+    }
+
+    let (total, unknown) = plan.download_size();
+    let unknown = if unknown == 0 {
+        String::new()
+    } else {
+        format!(" ({unknown} without a reported size)")
+    };
+    println!("\nEstimated download: {}{unknown}", format_bytes(total));
+}
+
+/// Refuse commands that edit the Gemfile or lockfile in deployment mode
+///
+/// # Errors
+///
+/// Returns an error naming `command` when deployment mode is enabled.
+pub(crate) fn refuse_in_deployment(command: &str) -> Result<()> {
+    if config::deployment_enabled() {
+        anyhow::bail!(
+            "{}\n\n{}",
+            lode::tr!(Message::DeploymentRefused, command = command),
+            messages::text(Message::DeploymentGuidance)
+        );
+    }
+    Ok(())
+}
+
+/// Check deployment mode - the lockfile must exist and lock every Gemfile gem
+///
+/// Unlike [`check_frozen_mode`], this compares contents rather than
+/// modification times, which a fresh checkout does not preserve.
+fn check_deployment_mode(lockfile_path: &str, verbose: bool) -> Result<()> {
+    if !Path::new(lockfile_path).exists() {
+        return Err(ExitCode::Lockfile.error(lode::tr!(
+            Message::DeploymentNeedsLockfile,
+            lockfile = lockfile_path
+        )));
+    }
+
+    let gemfile_path = lockfile_path
+        .strip_suffix(".lock")
+        .filter(|path| !path.is_empty())
+        .unwrap_or("Gemfile");
+    if !Path::new(gemfile_path).exists() {
+        return Ok(());
+    }
+
+    let gemfile = Gemfile::parse_file(gemfile_path)
+        .with_context(|| format!("Failed to parse {gemfile_path}"))?;
+    let lockfile =
+        Lockfile::parse(&fs::read_to_string(lockfile_path).context("Failed to read lockfile")?)
+            .context("Failed to parse lockfile")?;
+
+    let unlocked = lockfile.unlocked_dependencies(&gemfile);
+    if !unlocked.is_empty() {
+        return Err(ExitCode::Lockfile.error(lode::tr!(
+            Message::DeploymentGemfileChanged,
+            guidance = messages::text(Message::DeploymentGuidance),
+            gems = unlocked.join(", "),
+        )));
+    }
+
+    if verbose {
+        println!("Deployment mode: lockfile matches Gemfile");
+    }
+    Ok(())
+}
+
+/// Check frozen mode - ensure Gemfile hasn't changed without updating lockfile
+fn check_frozen_mode(lockfile_path: &str, verbose: bool) -> Result<()> {
+    // Determine Gemfile path from lockfile path
+    let gemfile_path = if std::path::Path::new(lockfile_path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("lock"))
+    {
+        lockfile_path.trim_end_matches(".lock")
+    } else {
+        "Gemfile"
+    };
+
+    // Check if Gemfile exists
+    let gemfile_pathbuf = std::path::Path::new(gemfile_path);
+    if !gemfile_pathbuf.exists() {
+        // No Gemfile, nothing to check
+        return Ok(());
+    }
+
+    // Get modification times
+    let lockfile_metadata = std::fs::metadata(lockfile_path).map_err(|_| {
+        ExitCode::Lockfile.error("Lockfile not found - frozen mode requires an existing lockfile")
+    })?;
+    let gemfile_metadata =
+        std::fs::metadata(gemfile_path).context("Failed to read Gemfile metadata")?;
+
+    let lockfile_modified = lockfile_metadata
+        .modified()
+        .context("Failed to get lockfile modification time")?;
+    let gemfile_modified = gemfile_metadata
+        .modified()
+        .context("Failed to get Gemfile modification time")?;
+
+    // If Gemfile is newer than lockfile, error in frozen mode
+    if gemfile_modified > lockfile_modified {
+        return Err(ExitCode::Lockfile.error(
+            "Your Gemfile has been modified since the lockfile was generated.\n\
+             In frozen mode, Bundler will not check for changes.\n\
+             To update the lockfile, run `bundle lock` or `bundle install` without frozen mode.",
+        ));
+    }
+
+    if verbose {
+        println!("Frozen mode: Gemfile matches lockfile");
+    }
+
+    Ok(())
+}
+
+/// Record `path` as the project's isolated gem directory in `.lode.toml`
+///
+/// Like `bundle install --path`: later installs and `lode exec` use it
+/// without user or system gems. A project-local path is added to
+/// `.gitignore`, and gems left behind in a previous `--path` are reported.
+fn remember_install_path(path: &str, quiet: bool) -> Result<()> {
+    let config_path = Path::new(".lode.toml");
+    // Only the two keys `--path` owns change
+    let mut document = project_config_document(config_path)?;
+
+    let (previous, changed) = set_install_path(&mut document, path);
+    if changed {
+        fs::write(config_path, document.to_string())
+            .with_context(|| format!("Failed to write {}", config_path.display()))?;
+    }
+
+    if let Some(entry) = gitignore_entry(path)
+        && ignore_path(Path::new(".gitignore"), &entry)?
+        && !quiet
+    {
+        println!("Added {entry} to .gitignore");
+    }
+
+    if let Some(previous) = previous.filter(|previous| Path::new(previous) != Path::new(path))
+        && has_installed_gems(Path::new(&previous))
+    {
+        lode::warn!(
+            "Gems installed in {previous} are no longer used now that this project \
+             installs into {path}; remove {previous} if nothing else needs it"
+        );
+    }
+
+    Ok(())
+}
+
+/// A project config file for editing in place, empty when it does not exist
+///
+/// Commands that record a setting change only their own keys through this,
+/// so comments, order and keys lode does not know survive.
+pub(crate) fn project_config_document(config_path: &Path) -> Result<toml_edit::DocumentMut> {
+    let content = match fs::read_to_string(config_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {}", config_path.display()));
+        }
+    };
+    content
+        .parse()
+        .with_context(|| format!("Failed to parse {}", config_path.display()))
+}
+
+/// Point `vendor_dir` at `path` with shared gems disabled, returning the
+/// previous `vendor_dir` and whether anything changed
+fn set_install_path(document: &mut toml_edit::DocumentMut, path: &str) -> (Option<String>, bool) {
+    let previous = document
+        .get("vendor_dir")
+        .and_then(toml_edit::Item::as_str)
+        .map(str::to_string);
+    let isolated = document
+        .get("disable_shared_gems")
+        .and_then(toml_edit::Item::as_bool)
+        == Some(true);
+    let changed = previous.as_deref() != Some(path) || !isolated;
+    if changed {
+        document.insert("vendor_dir", toml_edit::value(path));
+        document.insert("disable_shared_gems", toml_edit::value(true));
+    }
+    (previous, changed)
+}
+
+/// The `.gitignore` line for an install path inside the project, like
+/// `/vendor/bundle/`, or `None` for a path outside it
+fn gitignore_entry(path: &str) -> Option<String> {
+    let relative = Path::new(path);
+    if relative.is_absolute()
+        || relative
+            .components()
+            .any(|c| matches!(c, std::path::Component::ParentDir))
+    {
+        return None;
+    }
+    let dir = path.trim_start_matches("./").trim_end_matches('/');
+    (!dir.is_empty() && dir != ".").then(|| format!("/{dir}/"))
+}
+
+/// Add `entry` to `gitignore` unless a spelling of it is already there;
+/// `true` when the file changed
+fn ignore_path(gitignore: &Path, entry: &str) -> Result<bool> {
+    let content = match fs::read_to_string(gitignore) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", gitignore.display())),
+    };
+
+    let dir = entry.trim_matches('/');
+    if content
+        .lines()
+        .any(|line| line.trim().trim_matches('/') == dir)
+    {
+        return Ok(false);
+    }
+
+    let mut updated = content;
+    if !updated.is_empty() && !updated.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated.push_str(entry);
+    updated.push('\n');
+    fs::write(gitignore, updated)
+        .with_context(|| format!("Failed to write {}", gitignore.display()))?;
+    Ok(true)
+}
+
+/// Whether any Ruby version under install path `dir` has gems in it
+fn has_installed_gems(dir: &Path) -> bool {
+    fs::read_dir(dir.join("ruby")).is_ok_and(|versions| {
+        versions.flatten().any(|version| {
+            fs::read_dir(version.path().join("gems")).is_ok_and(|mut gems| gems.next().is_some())
+        })
+    })
+}
+
+/// Warn when native extensions would build for another arch than the machine's
+///
+/// An `x86_64` Ruby under Rosetta builds `x86_64` extensions, which then fail
+/// to load in a native arm64 Ruby sharing the bundle. `--arch` settles it.
+fn warn_arch_mismatch(arch: Option<&str>) {
+    let Some((ruby_arch, machine_arch)) = lode::platform::arch_mismatch() else {
+        return;
+    };
+    let translated = if lode::platform::is_rosetta() {
+        " (running under Rosetta)"
+    } else {
+        ""
+    };
+    match arch {
+        Some(arch) => lode::warn!(
+            "Ruby is {ruby_arch} but this machine is {machine_arch}{translated}; \
+             building native extensions for {arch}"
+        ),
+        None => lode::warn!(
+            "Ruby is {ruby_arch} but this machine is {machine_arch}{translated}; \
+             native extensions will build for {ruby_arch}. Pass --arch {machine_arch} \
+             (or --arch universal) to build for this machine"
+        ),
+    }
+}
+
+/// Replace precompiled variants of `forced` gems with their `ruby` variant
+///
+/// A locked `ruby` variant is kept and the precompiled ones dropped. When
+/// only precompiled variants are locked, the `ruby` gem of the same version
+/// is installed in their place.
+fn force_ruby_platform(
+    gems: Vec<lode::lockfile::GemSpec>,
+    forced: &BTreeSet<String>,
+    quiet: bool,
+) -> Vec<lode::lockfile::GemSpec> {
+    let is_ruby = |gem: &lode::lockfile::GemSpec| {
+        gem.platform
+            .as_deref()
+            .is_none_or(|platform| platform == "ruby")
+    };
+    let mut ruby_variants: BTreeSet<(String, String)> = gems
+        .iter()
+        .filter(|gem| forced.contains(&gem.name) && is_ruby(gem))
+        .map(|gem| (gem.name.clone(), gem.version.clone()))
+        .collect();
+
+    gems.into_iter()
+        .filter_map(|gem| {
+            if !forced.contains(&gem.name) || is_ruby(&gem) {
+                return Some(gem);
+            }
+            if !ruby_variants.insert((gem.name.clone(), gem.version.clone())) {
+                return None;
+            }
+            if !quiet {
+                println!(
+                    "Installing {} for the ruby platform instead of {} (force_ruby_platform); \
+                     run `lode lock` to lock its dependencies",
+                    gem.full_name(),
+                    gem.platform.as_deref().unwrap_or_default()
+                );
+            }
+            Some(lode::lockfile::GemSpec::new(
+                gem.name,
+                gem.version,
+                None,
+                gem.dependencies,
+                gem.groups,
+            ))
+        })
+        .collect()
+}
+
+/// Write `.lode/groups/<group>.paths` next to the Gemfile
+///
+/// Manifests are a convenience for boot scripts, so failures only warn.
+fn write_group_manifests(
+    lockfile: &Lockfile,
+    gemfile: Option<&Gemfile>,
+    gemfile_path: &Path,
+    gems_dir: &Path,
+    verbose: bool,
+) {
+    let Some(gemfile) = gemfile else {
+        return;
+    };
+    let gems_dir = gems_dir
+        .canonicalize()
+        .unwrap_or_else(|_| gems_dir.to_path_buf());
+    let manifest_dir = gemfile_path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(lode::group_manifest::MANIFEST_DIR);
+    let groups = lode::group_manifest::group_load_paths(lockfile, gemfile, &gems_dir);
+    match lode::group_manifest::write(&manifest_dir, &groups) {
+        Ok(()) if verbose => println!(
+            "Wrote load path manifests for {} groups to {}",
+            groups.len(),
+            manifest_dir.display()
+        ),
+        Ok(()) => {}
+        Err(e) => lode::warn!(
+            "could not write group manifests to {}: {e}",
+            manifest_dir.display()
+        ),
+    }
+}
+
+/// Write `.lode/activation.paths` next to the Gemfile for
+/// `lode exec --cached-setup`
+///
+/// Like the group manifests, failures only warn.
+fn write_activation_manifest(
+    lockfile: &Lockfile,
+    lockfile_content: &str,
+    gemfile_path: &Path,
+    gems_dir: &Path,
+    ruby_version: &str,
+    verbose: bool,
+) {
+    let gems_dir = gems_dir
+        .canonicalize()
+        .unwrap_or_else(|_| gems_dir.to_path_buf());
+    let extensions_dir = gems_dir
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join("extensions")
+        .join(lode::detect_current_platform())
+        .join(ruby_version);
+    let manifest = gemfile_path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(lode::activation::MANIFEST_FILE);
+    let paths = lode::activation::load_paths(lockfile, &gems_dir, &extensions_dir);
+    let digest = lode::activation::lockfile_digest(lockfile_content);
+    match lode::activation::write(&manifest, &digest, &paths) {
+        Ok(()) if verbose => println!(
+            "Wrote activation manifest with {} load paths to {}",
+            paths.len(),
+            manifest.display()
+        ),
+        Ok(()) => {}
+        Err(e) => lode::warn!(
+            "could not write activation manifest to {}: {e}",
+            manifest.display()
+        ),
+    }
+}
+
+/// Filter gems by group membership based on without/with group lists
+fn filter_gems_by_groups(
+    lockfile_gems: &[lode::GemSpec],
+    gemfile: &lode::Gemfile,
+    without_groups: &[String],
+    with_groups: &[String],
+    verbose: bool,
+) -> Vec<lode::GemSpec> {
+    use std::collections::HashMap;
+
+    // Build a map of gem names to their groups from the Gemfile
+    let gem_groups: HashMap<String, Vec<String>> = gemfile
+        .gems
+        .iter()
+        .map(|gem_dep| (gem_dep.name.clone(), gem_dep.groups.clone()))
+        .collect();
+
+    // Default group is :default - gems without explicit group are in default group
+    let default_group = "default".to_string();
+
+    let filtered: Vec<_> = lockfile_gems
+        .iter()
+        .filter(|gem| {
+            let groups = gem_groups
+                .get(&gem.name)
+                .cloned()
+                .unwrap_or_else(|| vec![default_group.clone()]);
+
+            // If with_groups is specified, only include gems in those groups
+            if !with_groups.is_empty() {
+                let in_with_groups = groups.iter().any(|g| with_groups.contains(g));
+                if !in_with_groups {
+                    if verbose {
+                        println!(
                             "  Excluding {} (not in with groups: {:?})",
                             gem.name, with_groups
                         );
@@ -1078,6 +2520,128 @@ fn filter_gems_by_groups(
     filtered
 }
 
+/// Keep only gems in `only_groups` and the gems they depend on
+///
+/// Unlike `with_groups`, dependencies of the selected gems are installed
+/// even when they also belong to other groups, since the selected gems
+/// cannot load without them.
+fn filter_gems_by_only_groups(
+    lockfile: &Lockfile,
+    gemfile: &lode::Gemfile,
+    only_groups: &[String],
+    verbose: bool,
+) -> Vec<lode::GemSpec> {
+    let roots: Vec<String> = gemfile
+        .gems
+        .iter()
+        .filter(|gem| {
+            if gem.groups.is_empty() {
+                only_groups.iter().any(|g| g == "default")
+            } else {
+                gem.groups.iter().any(|g| only_groups.contains(g))
+            }
+        })
+        .map(|gem| gem.name.clone())
+        .collect();
+    let included = lockfile.dependency_closure(&roots);
+
+    let filtered: Vec<_> = lockfile
+        .gems
+        .iter()
+        .filter(|gem| {
+            let keep = included.contains(&gem.name);
+            if !keep && verbose {
+                println!(
+                    "  Excluding {} (not needed by only groups: {only_groups:?})",
+                    gem.name
+                );
+            }
+            keep
+        })
+        .cloned()
+        .collect();
+
+    if verbose && filtered.len() != lockfile.gems.len() {
+        println!(
+            "Group filtering: {} -> {} gems",
+            lockfile.gems.len(),
+            filtered.len()
+        );
+    }
+
+    filtered
+}
+
+/// Print funding requests and deprecated or unmaintained gems
+///
+/// Funding comes from installed gemspecs; the maintenance check needs
+/// RubyGems.org metadata and is skipped for `--local` installs.
+async fn report_gem_notices(
+    gems: &[lode::lockfile::GemSpec],
+    ruby_dir: &Path,
+    cfg: &Config,
+    source: &str,
+    offline: bool,
+) {
+    use lode::gem_notices::{self, GemNotice};
+
+    let mut notices = Vec::new();
+    if !config::funding_requests_ignored(Some(cfg)) {
+        let specs: Vec<lode::Gemspec> = gems
+            .iter()
+            .filter_map(|gem| {
+                lode::Gemspec::load_for_gem_dir(&ruby_dir.join("gems").join(gem.full_name()))
+            })
+            .collect();
+        notices.extend(gem_notices::funding_notices(&specs));
+    }
+
+    if !offline
+        && !config::deprecations_silenced(Some(cfg))
+        && let Ok(client) = lode::RubyGemsClient::new(source)
+        && let Ok(cache_dir) = config::cache_dir(Some(cfg))
+    {
+        let mut names: Vec<String> = gems.iter().map(|gem| gem.name.clone()).collect();
+        names.sort();
+        names.dedup();
+        notices.extend(
+            gem_notices::maintenance_notices(&client, &names, &cache_dir, chrono::Utc::now()).await,
+        );
+    }
+
+    let funding: Vec<_> = notices
+        .iter()
+        .filter_map(|notice| match notice {
+            GemNotice::Funding { gem, uri } => Some(format!("  {gem}: {uri}")),
+            _ => None,
+        })
+        .collect();
+    if !funding.is_empty() {
+        println!(
+            "\n{} installed gems are looking for funding:",
+            funding.len()
+        );
+        println!("{}", funding.join("\n"));
+    }
+
+    let warnings: Vec<String> = notices
+        .iter()
+        .filter_map(|notice| match notice {
+            GemNotice::Deprecated { gem, summary } => {
+                Some(format!("Warning: {gem} is deprecated: {summary}"))
+            }
+            GemNotice::Unmaintained { gem, last_release } => Some(format!(
+                "Warning: {gem} has had no release since {last_release} (over {} years)",
+                gem_notices::STALE_AFTER_YEARS
+            )),
+            GemNotice::Funding { .. } => None,
+        })
+        .collect();
+    if !warnings.is_empty() {
+        println!("\n{}", warnings.join("\n"));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1087,6 +2651,43 @@ mod tests {
     use std::time::Duration;
     use tempfile::TempDir;
 
+    #[test]
+    fn changed_declarations_detects_edits_and_removals() {
+        let mut previous = Gemfile::default();
+        previous.gems.push(GemDependency::new("rack"));
+        previous.gems.push(GemDependency::new("rails"));
+        previous.gems.push(GemDependency::new("puma"));
+
+        let mut current = Gemfile::default();
+        current.gems.push(GemDependency::new("rack"));
+        let mut rails = GemDependency::new("rails");
+        rails.version_requirement = "~> 8.0".to_string();
+        current.gems.push(rails);
+        current.gems.push(GemDependency::new("sidekiq"));
+
+        assert_eq!(
+            changed_declarations(Some(&previous), &current),
+            vec!["rails", "sidekiq", "puma"]
+        );
+        assert_eq!(changed_declarations(Some(&current), &current).len(), 0);
+    }
+
+    #[test]
+    fn lockfile_changes_summarizes_versions() {
+        let gem = |name: &str, version: &str| {
+            GemSpec::new(name.to_string(), version.to_string(), None, vec![], vec![])
+        };
+        let mut before = Lockfile::new();
+        before.gems = vec![gem("rack", "3.0.0"), gem("puma", "6.4.0")];
+        let mut after = Lockfile::new();
+        after.gems = vec![gem("rack", "3.1.0"), gem("sidekiq", "7.3.0")];
+
+        assert_eq!(
+            lockfile_changes(&before, &after),
+            vec!["~ rack 3.0.0 -> 3.1.0", "+ sidekiq 7.3.0", "- puma 6.4.0"]
+        );
+    }
+
     #[test]
     fn test_check_frozen_mode_no_gemfile() {
         let temp_dir = TempDir::new().unwrap();
@@ -1115,6 +2716,194 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn install_progress_is_off_for_verbose_and_quiet_installs() {
+        for (verbose, quiet) in [(true, false), (false, true)] {
+            let progress = InstallProgress::new(verbose, quiet);
+            assert_eq!(progress.mode, ProgressMode::Off);
+            assert!(progress.worker("Fetching", "rack-3.0.8").0.is_none());
+        }
+    }
+
+    #[test]
+    fn install_path_is_gitignored_once() {
+        let temp = TempDir::new().unwrap();
+        let gitignore = temp.path().join(".gitignore");
+        fs::write(&gitignore, "/log/\nvendor/bundle").unwrap();
+
+        assert_eq!(
+            gitignore_entry("./vendor/bundle/").as_deref(),
+            Some("/vendor/bundle/")
+        );
+        assert_eq!(gitignore_entry("/opt/gems"), None);
+        assert_eq!(gitignore_entry("../shared"), None);
+
+        assert!(!ignore_path(&gitignore, "/vendor/bundle/").unwrap());
+        assert!(ignore_path(&gitignore, "/.gems/").unwrap());
+        assert!(!ignore_path(&gitignore, "/.gems/").unwrap());
+        assert_eq!(
+            fs::read_to_string(&gitignore).unwrap(),
+            "/log/\nvendor/bundle\n/.gems/\n"
+        );
+    }
+
+    #[test]
+    fn install_path_keeps_the_rest_of_the_config() {
+        let mut document: toml_edit::DocumentMut =
+            "# team settings\ncache_dir = \"/tmp/gems\"\nvendor_dir = \"old\"\nfuture_key = 1\n"
+                .parse()
+                .unwrap();
+
+        let (previous, changed) = set_install_path(&mut document, "vendor/bundle");
+        assert_eq!(previous.as_deref(), Some("old"));
+        assert!(changed);
+        assert_eq!(
+            document.to_string(),
+            "# team settings\ncache_dir = \"/tmp/gems\"\nvendor_dir = \"vendor/bundle\"\nfuture_key = 1\ndisable_shared_gems = true\n"
+        );
+
+        let (_, changed) = set_install_path(&mut document, "vendor/bundle");
+        assert!(!changed);
+    }
+
+    #[test]
+    fn stale_install_path_needs_installed_gems() {
+        let temp = TempDir::new().unwrap();
+        assert!(!has_installed_gems(temp.path()));
+        fs::create_dir_all(temp.path().join("ruby/3.4.0/gems")).unwrap();
+        assert!(!has_installed_gems(temp.path()));
+        fs::create_dir_all(temp.path().join("ruby/3.4.0/gems/rack-3.1.0")).unwrap();
+        assert!(has_installed_gems(temp.path()));
+    }
+
+    #[test]
+    fn transfer_rate_is_bytes_per_second() {
+        assert_eq!(
+            transfer_rate(3 * 1024 * 1024, Duration::from_secs(2)),
+            "1.50 MB/s"
+        );
+        assert_eq!(transfer_rate(512, Duration::ZERO), "500.00 KB/s");
+    }
+
+    #[test]
+    fn force_ruby_platform_installs_ruby_variants_of_forced_gems() {
+        let gem = |name: &str, platform: Option<&str>| {
+            GemSpec::new(
+                name.to_string(),
+                "1.16.0".to_string(),
+                platform.map(String::from),
+                vec![],
+                vec![],
+            )
+        };
+        let forced = BTreeSet::from(["nokogiri".to_string(), "grpc".to_string()]);
+        let gems = force_ruby_platform(
+            vec![
+                gem("nokogiri", Some("x86_64-linux")),
+                gem("nokogiri", None),
+                gem("grpc", Some("x86_64-linux")),
+                gem("grpc", Some("x86_64-linux-gnu")),
+                gem("ffi", Some("x86_64-linux")),
+            ],
+            &forced,
+            true,
+        );
+
+        let selected: Vec<_> = gems.iter().map(GemSpec::full_name_with_platform).collect();
+        assert_eq!(
+            selected,
+            vec!["nokogiri-1.16.0", "grpc-1.16.0", "ffi-1.16.0-x86_64-linux"]
+        );
+    }
+
+    #[tokio::test]
+    async fn plan_install_splits_installed_cached_and_remote_gems() {
+        let temp_dir = TempDir::new().unwrap();
+        let gems_root = temp_dir.path().join("gems");
+        let cache_dir = temp_dir.path().join("cache");
+        fs::create_dir_all(gems_root.join("rack-3.0.8")).unwrap();
+        fs::create_dir_all(&cache_dir).unwrap();
+        fs::write(cache_dir.join("puma-6.4.0.gem"), "not a real gem").unwrap();
+
+        // Nothing listens here, so remote sizes are unknown
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let source = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let dm = DownloadManager::for_planning(cache_dir.clone(), vec![source], 0).unwrap();
+
+        let gem = |name: &str, version: &str, platform: Option<&str>| {
+            GemSpec::new(
+                name.to_string(),
+                version.to_string(),
+                platform.map(String::from),
+                vec![],
+                vec![],
+            )
+        };
+        let gems = vec![
+            gem("rack", "3.0.8", None),
+            gem("puma", "6.4.0", None),
+            gem("nokogiri", "1.16.0", Some("x86_64-linux")),
+        ];
+
+        let plan = plan_install(&gems, &gems_root, false, &dm).await;
+        assert_eq!(plan.satisfied, vec!["rack-3.0.8".to_string()]);
+        assert_eq!(
+            plan.downloads,
+            vec![
+                PlannedDownload {
+                    full_name: "puma-6.4.0".to_string(),
+                    cached: true,
+                    size: Some(14),
+                },
+                PlannedDownload {
+                    full_name: "nokogiri-1.16.0-x86_64-linux".to_string(),
+                    cached: false,
+                    size: None,
+                },
+            ]
+        );
+        // Unreadable cached metadata stays unchecked; precompiled gems never build
+        assert!(plan.extensions.is_empty());
+        assert_eq!(plan.unchecked, 1);
+        assert_eq!(plan.download_size(), (0, 1));
+        assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_check_deployment_mode_compares_contents() {
+        let temp_dir = TempDir::new().unwrap();
+        let gemfile = temp_dir.path().join("Gemfile");
+        let lockfile = temp_dir.path().join("Gemfile.lock");
+        let lockfile_path = lockfile.to_str().unwrap();
+
+        fs::write(&gemfile, "source 'https://rubygems.org'\ngem 'rack'\n").unwrap();
+        let missing = check_deployment_mode(lockfile_path, false).unwrap_err();
+        assert!(missing.to_string().contains("requires a lockfile"));
+
+        // An older Gemfile is fine as long as the lockfile satisfies it
+        fs::write(
+            &lockfile,
+            "GEM\n  remote: https://rubygems.org/\n  specs:\n    rack (3.0.8)\n\nPLATFORMS\n  ruby\n",
+        )
+        .unwrap();
+        thread::sleep(Duration::from_millis(10));
+        fs::write(
+            &gemfile,
+            "source 'https://rubygems.org'\ngem 'rack', '~> 3.0'\n",
+        )
+        .unwrap();
+        assert!(check_deployment_mode(lockfile_path, false).is_ok());
+
+        fs::write(
+            &gemfile,
+            "source 'https://rubygems.org'\ngem 'rack'\ngem 'puma'\n",
+        )
+        .unwrap();
+        let stale = check_deployment_mode(lockfile_path, false).unwrap_err();
+        assert!(stale.to_string().contains("puma"));
+    }
+
     #[test]
     fn test_check_frozen_mode_gemfile_newer_than_lockfile() {
         let temp_dir = TempDir::new().unwrap();
@@ -1190,6 +2979,8 @@ mod tests {
             ],
             sources: vec![],
             gemspecs: vec![],
+            plugin_sources: vec![],
+            plugins: vec![],
         };
 
         let without = vec!["test".to_string()];
@@ -1255,6 +3046,8 @@ mod tests {
             ],
             sources: vec![],
             gemspecs: vec![],
+            plugin_sources: vec![],
+            plugins: vec![],
         };
 
         let without = vec![];
@@ -1305,6 +3098,8 @@ mod tests {
             }],
             sources: vec![],
             gemspecs: vec![],
+            plugin_sources: vec![],
+            plugins: vec![],
         };
 
         let without = vec!["test".to_string()];
@@ -1314,4 +3109,38 @@ mod tests {
         // Both gems should pass - rake is default, unknown-dep treated as default
         assert_eq!(filtered.len(), 2);
     }
+
+    #[test]
+    fn test_filter_gems_by_only_groups_keeps_dependencies() {
+        let lockfile = Lockfile::parse(
+            "GEM
+  remote: https://rubygems.org/
+  specs:
+    diff-lcs (1.5.1)
+    rake (13.0.0)
+    rspec (3.13.0)
+      rspec-core (~> 3.13.0)
+    rspec-core (3.13.0)
+      diff-lcs (>= 1.2)
+    rubocop (1.60.0)
+
+PLATFORMS
+  ruby
+",
+        )
+        .unwrap();
+        let gemfile = Gemfile::parse(
+            "source \"https://rubygems.org\"
+gem \"rake\"
+gem \"rspec\", group: :test
+gem \"rubocop\", group: :development
+",
+        )
+        .unwrap();
+
+        let filtered =
+            filter_gems_by_only_groups(&lockfile, &gemfile, &["test".to_string()], false);
+        let names: Vec<_> = filtered.iter().map(|gem| gem.name.as_str()).collect();
+        assert_eq!(names, vec!["diff-lcs", "rspec", "rspec-core"]);
+    }
 }
@@ -151,7 +151,10 @@ pub(crate) fn run(
                 "path" => "(path) ",
                 _ => "",
             };
-            println!("  * {type_label}{name} ({version})");
+            println!(
+                "  * {type_label}{name} ({})",
+                lode::reporter::version(version)
+            );
         }
 
         println!("\nTotal: {} gems", all_gems.len());
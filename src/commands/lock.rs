@@ -6,9 +6,12 @@ use anyhow::{Context, Result};
 use futures_util::stream::{self, StreamExt};
 use lode::lockfile::{Dependency, GemSpec};
 use lode::platform::detect_current_platform;
-use lode::resolver::ResolvedGem;
-use lode::{Config, Gemfile, Lockfile, Resolver, RubyGemsClient};
-use std::collections::HashSet;
+use lode::resolver::{ResolvedDependency, ResolvedGem};
+use lode::{
+    Config, Gemfile, Lockfile, Phase, Profiler, ResolutionCache, ResolutionStrategy, Resolver,
+    RubyGemsClient,
+};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fs;
 use std::sync::Arc;
 
@@ -30,6 +33,9 @@ pub(crate) async fn run(
     add_platforms: &[String],
     remove_platforms: &[String],
     update_gems: &[String],
+    release_hold: bool,
+    constraints: &BTreeMap<String, String>,
+    strategy: ResolutionStrategy,
     print: bool,
     verbose: bool,
     patch: bool,
@@ -37,6 +43,7 @@ pub(crate) async fn run(
     _major: bool, // Major updates are the default behavior (no constraint)
     strict: bool,
     conservative: bool,
+    incremental: bool,
     local: bool,
     pre: bool,
     bundler: Option<&str>,
@@ -44,6 +51,7 @@ pub(crate) async fn run(
     add_checksums: bool,
     full_index: bool,
     quiet: bool,
+    profiler: &Profiler,
 ) -> Result<()> {
     // Determine lockfile path based on provided path or derive from gemfile
     let lockfile_pathbuf = lockfile_path.map_or_else(
@@ -78,6 +86,10 @@ pub(crate) async fn run(
         println!("Including prerelease versions (alpha, beta, rc)");
     }
 
+    if strategy != ResolutionStrategy::Latest && verbose {
+        println!("Resolution strategy: {}", strategy.as_str());
+    }
+
     // Local mode: only use cached gems, no network requests
     if local && verbose {
         println!("Local mode: using only cached gems");
@@ -85,6 +97,7 @@ pub(crate) async fn run(
 
     // Download and cache full index if requested
     let _full_index_data = if full_index {
+        let _span = profiler.phase(Phase::Metadata);
         if verbose {
             println!("Downloading and parsing full RubyGems index...");
         }
@@ -109,13 +122,17 @@ pub(crate) async fn run(
                 if verbose {
                     println!("Cached index invalid, downloading fresh index...");
                 }
-                let idx = lode::FullIndex::download_and_parse(lode::RUBYGEMS_ORG_URL).await?;
+                let idx =
+                    super::install::download_full_index(lode::RUBYGEMS_ORG_URL, &cache_dir, quiet)
+                        .await?;
                 idx.save_to_cache(&index_cache_path)?;
                 idx
             }
         } else {
             // Download fresh index
-            let idx = lode::FullIndex::download_and_parse(lode::RUBYGEMS_ORG_URL).await?;
+            let idx =
+                super::install::download_full_index(lode::RUBYGEMS_ORG_URL, &cache_dir, quiet)
+                    .await?;
             if verbose {
                 println!(
                     "Downloaded {} gems with {} versions",
@@ -139,11 +156,16 @@ pub(crate) async fn run(
     };
 
     // Load config
-    let _config = Config::load().context("Failed to load configuration")?;
+    let config = Config::load().context("Failed to load configuration")?;
 
     // Parse Gemfile
     let mut gemfile = Gemfile::parse_file(gemfile_path)
         .with_context(|| format!("Failed to parse Gemfile at {gemfile_path}"))?;
+    check_sources(
+        &gemfile,
+        lode::config::multisource_disabled(Some(&config)),
+        quiet,
+    )?;
 
     if verbose {
         println!("Found {} gems in Gemfile", gemfile.gems.len());
@@ -152,6 +174,14 @@ pub(crate) async fn run(
         }
     }
 
+    // Held gems (`lode hold`) keep their locked versions
+    let hold_pins = hold_pins(&config, &lockfile_pathbuf, update_gems, release_hold)?;
+    if !quiet {
+        for (gem, requirement) in &hold_pins {
+            println!("Holding {gem} at {}", requirement.trim_start_matches("= "));
+        }
+    }
+
     // Implement selective gem updates with version level control
     // --update with gems: Lock non-updated gems to their current versions from lockfile
     // --update without gems: Update all gems (full resolution)
@@ -289,16 +319,180 @@ pub(crate) async fn run(
         .with_cache_only(local)
         .with_prerelease(pre);
 
-    // Create resolver
-    let resolver = Resolver::new(client);
+    // Gems in typed `source` blocks come from their source providers
+    let source_routes = Arc::new(
+        lode::SourceRoutes::from_gemfile(
+            &gemfile,
+            &lode::SourceProviders::builtin(&lode::config::cache_dir(Some(&config))?),
+        )
+        .context("Failed to set up gem sources")?,
+    );
 
-    // Resolve dependencies
-    if verbose {
-        println!("\nResolving dependencies with PubGrub...");
+    // Candidates must support the Gemfile's exact `ruby` version, or else
+    // the interpreter on PATH
+    let interpreter = lode::detect_interpreter_versions();
+    let target_ruby = gemfile
+        .ruby_version
+        .as_deref()
+        .filter(|v| v.starts_with(|c: char| c.is_ascii_digit()))
+        .map(lode::ruby::parse_ruby_version_string)
+        .or_else(|| interpreter.as_ref().map(|(ruby, _)| ruby.clone()));
+    let target_rubygems = interpreter.map(|(_, rubygems)| rubygems);
+    let runtime = target_ruby
+        .as_deref()
+        .map(|ruby| format!("{ruby}/{}", target_rubygems.as_deref().unwrap_or("")));
+
+    // The minimal strategy keeps gems at their locked versions where it can
+    let locked_versions = if strategy == ResolutionStrategy::Minimal {
+        locked_versions(&lockfile_pathbuf, update_gems)
+    } else {
+        BTreeMap::new()
+    };
+
+    // Gems whose precompiled variants are skipped (`force_ruby_platform.GEM`)
+    let ruby_platform_gems = lode::config::ruby_platform_gems(Some(&config));
+
+    // Incremental mode holds every gem outside the changed requirements'
+    // subgraph at its locked version, and skips resolution entirely when
+    // nothing the lockfile holds has changed
+    let previous_lock =
+        if incremental && update_gems.is_empty() && constraints.is_empty() && !patch && !minor {
+            fs::read_to_string(&lockfile_pathbuf)
+                .ok()
+                .and_then(|content| Lockfile::parse(&content).ok())
+        } else {
+            None
+        };
+    let incremental_pins = previous_lock
+        .as_ref()
+        .map(|lockfile| incremental_pins(lockfile, &gemfile));
+    let unchanged_gems = previous_lock
+        .as_ref()
+        .filter(|lockfile| lockfile.platforms == platforms)
+        .and_then(|lockfile| lockfile.satisfied_gems(&gemfile))
+        .map(|specs| specs.into_iter().map(locked_resolution).collect::<Vec<_>>());
+    if verbose && let Some(pins) = &incremental_pins {
+        println!("Incremental mode: keeping {} gems locked", pins.len());
     }
 
-    let platforms_refs: Vec<&str> = platforms.iter().map(String::as_str).collect();
-    let resolved_gems = resolver.resolve(&gemfile, &platforms_refs, pre).await?;
+    // A cached resolution is only valid when the Gemfile was not rewritten
+    // with locked or level-constrained versions above, no gems are held for
+    // incremental locking, newest versions were preferred and no gem is
+    // forced to the ruby platform
+    let resolution_cache = lode::config::cache_dir(Some(&config))
+        .ok()
+        .map(|dir| ResolutionCache::new(&dir));
+    let cache_key = if update_gems.is_empty()
+        && constraints.is_empty()
+        && hold_pins.is_empty()
+        && incremental_pins.is_none()
+        && !patch
+        && !minor
+        && strategy == ResolutionStrategy::Latest
+        && ruby_platform_gems.is_empty()
+    {
+        fs::read_to_string(gemfile_path).ok().map(|content| {
+            ResolutionCache::key(
+                &content,
+                std::slice::from_ref(&gem_source),
+                &platforms,
+                runtime.as_deref(),
+                pre,
+            )
+        })
+    } else {
+        None
+    };
+    let index_stamp = if cache_key.is_some() {
+        client.index_stamp().await
+    } else {
+        None
+    };
+    let cached = resolution_cache
+        .as_ref()
+        .zip(cache_key.as_deref())
+        .and_then(|(cache, key)| cache.load(key, index_stamp.as_deref()));
+
+    if cache_key.is_some()
+        && let Ok(cache_dir) = lode::config::cache_dir(Some(&config))
+    {
+        let mut usage = lode::CacheUsage::load(&cache_dir);
+        usage.record_resolution(cached.is_some());
+        if let Err(e) = usage.save() {
+            lode::debug!("could not save cache usage counters: {e}");
+        }
+    }
+
+    let resolved_gems = if let Some(gems) = unchanged_gems {
+        if verbose {
+            println!("\nLockfile already satisfies the Gemfile, skipping resolution");
+        }
+        gems
+    } else if let Some(gems) = cached {
+        if verbose {
+            println!("\nUsing cached resolution (Gemfile and index unchanged)");
+        }
+        gems
+    } else {
+        let resolver_with = |constraints: BTreeMap<String, String>| {
+            Resolver::new(client.clone())
+                .with_ruby_version(target_ruby.clone())
+                .with_rubygems_version(target_rubygems.clone())
+                .with_source_routes(Arc::clone(&source_routes))
+                .with_profiler(profiler.clone())
+                .with_constraints(constraints)
+                .with_strategy(strategy, locked_versions.clone())
+                .with_ruby_platform(ruby_platform_gems.clone())
+        };
+
+        // Resolve dependencies
+        if verbose {
+            println!("\nResolving dependencies with PubGrub...");
+        }
+
+        let platforms_refs: Vec<&str> = platforms.iter().map(String::as_str).collect();
+        let resolution = profiler.phase(Phase::Resolution);
+
+        // Held gems can conflict with the changed requirements, in which
+        // case the whole graph is re-resolved
+        let mut incremental_result = None;
+        if let Some(mut pins) = incremental_pins {
+            pins.extend(hold_pins.clone());
+            let resolver = resolver_with(pins);
+            match resolver.resolve(&gemfile, &platforms_refs, pre).await {
+                Ok(gems) => incremental_result = Some((resolver, gems)),
+                Err(e) => {
+                    if verbose {
+                        println!("Incremental resolution failed, re-resolving all gems: {e}");
+                    }
+                }
+            }
+        }
+        let (resolver, gems) = if let Some(result) = incremental_result {
+            result
+        } else {
+            let mut constraints = constraints.clone();
+            constraints.extend(hold_pins);
+            let resolver = resolver_with(constraints);
+            let gems = resolver.resolve(&gemfile, &platforms_refs, pre).await?;
+            (resolver, gems)
+        };
+        drop(resolution);
+
+        if !quiet {
+            for skipped in resolver.incompatible_versions() {
+                println!("Skipped {skipped}");
+            }
+        }
+
+        if let (Some(cache), Some(key)) = (&resolution_cache, &cache_key)
+            && let Err(e) = cache.store(key, index_stamp.as_deref(), &gems)
+            && verbose
+        {
+            println!("Could not cache resolution: {e}");
+        }
+        gems
+    };
 
     if verbose {
         println!("Resolved {} gems", resolved_gems.len());
@@ -311,42 +505,14 @@ pub(crate) async fn run(
         lockfile.gems.push(convert_to_gem_spec(resolved));
     }
 
-    // Set platforms (normalize if requested)
-    lockfile.platforms = if normalize_platforms {
-        platforms
-            .into_iter()
-            .map(|p| {
-                // Normalize platform names (e.g., arm64-darwin25.0.0 -> arm64-darwin)
-                // Strip version numbers from the end of platform segments
-                // Handle both "darwin-25" and "darwin25" patterns
-
-                // First, try to find a dash followed by a digit (e.g., "linux-gnu-5")
-                if let Some(idx) = p.rfind('-') {
-                    let suffix = &p[idx + 1..];
-                    if suffix.chars().next().is_some_and(|c| c.is_ascii_digit()) {
-                        return p[..idx].to_string();
-                    }
-                }
-
-                // If no dash+digit, look for embedded version (e.g., "darwin25.0.0" -> "darwin")
-                // Find last segment after final dash
-                if let Some(last_dash_idx) = p.rfind('-') {
-                    let last_segment = &p[last_dash_idx + 1..];
-                    // Find where digits start in this segment
-                    if let Some(digit_pos) = last_segment.find(|c: char| c.is_ascii_digit())
-                        && digit_pos > 0
-                    {
-                        // There's text before the digits, keep prefix
-                        return format!("{}-{}", &p[..last_dash_idx], &last_segment[..digit_pos]);
-                    }
-                }
+    lockfile.plugin_sources =
+        source_routes.plugin_sources(lockfile.gems.iter().map(|gem| gem.name.as_str()));
+    lockfile.platforms = platforms;
 
-                p
-            })
-            .collect()
-    } else {
-        platforms
-    };
+    // Canonicalize platform spellings and merge equivalent specs
+    if normalize_platforms {
+        lockfile.normalize_platforms();
+    }
 
     // Set Ruby version
     lockfile.ruby_version.clone_from(&gemfile.ruby_version);
@@ -374,52 +540,109 @@ pub(crate) async fn run(
             .context("Failed to create download manager")?,
         );
 
-        // Download all gems in parallel and compute checksums
-        let checksum_results: Vec<_> = stream::iter(&lockfile.gems)
-            .map(|gem| {
-                let dm = Arc::clone(&dm);
-                let gem_name = gem.name.clone();
-                let gem_version = gem.version.clone();
-                let gem_platform = gem.platform.clone();
+        // Cached gems are hashed locally. The rest take the SHA-256 the
+        // compact index publishes, and only gems it has none for (plugin
+        // sources, indexes without checksums) are downloaded and hashed.
+        let mut local_paths = Vec::new();
+        let mut unhashed = Vec::new();
+        for gem in &lockfile.gems {
+            let gem_spec = GemSpec::new(
+                gem.name.clone(),
+                gem.version.clone(),
+                gem.platform.clone(),
+                vec![],
+                vec![],
+            );
+            match dm.cached_gem(&gem_spec) {
+                Some(path) => local_paths.push((gem_spec, path)),
+                None => unhashed.push(gem_spec),
+            }
+        }
 
-                async move {
-                    // Download gem to cache
-                    let gem_spec = lode::lockfile::GemSpec::new(
-                        gem_name.clone(),
-                        gem_version.clone(),
-                        gem_platform,
-                        vec![],
-                        vec![],
+        let published =
+            published_checksums(&gemfile, &gem_source, &unhashed, &source_routes, local).await;
+        let mut checksums: BTreeMap<String, String> = BTreeMap::new();
+        let mut to_download = Vec::new();
+        for gem_spec in unhashed {
+            match published.get(gem_spec.full_name_with_platform()) {
+                Some(checksum) => {
+                    checksums.insert(
+                        gem_spec.full_name_with_platform().to_string(),
+                        checksum.clone(),
                     );
-                    let cache_path = dm.download_gem(&gem_spec).await?;
+                }
+                None => to_download.push(gem_spec),
+            }
+        }
+        if verbose {
+            println!(
+                "{} checksums from the compact index, {} cached gems to hash, {} gems to download",
+                checksums.len(),
+                local_paths.len(),
+                to_download.len()
+            );
+        }
 
-                    // Compute checksum
-                    let checksum = lode::DownloadManager::compute_checksum(&cache_path)?;
+        // Download the remaining gems in parallel, then hash them
+        let downloads = profiler.phase(Phase::Download);
+        let download_results: Vec<_> = stream::iter(to_download)
+            .map(|gem_spec| {
+                let dm = Arc::clone(&dm);
+                let source_routes = Arc::clone(&source_routes);
+                let profiler = profiler.clone();
 
-                    Ok::<(String, String, String), anyhow::Error>((gem_name, gem_version, checksum))
+                async move {
+                    let _span = profiler.gem(Phase::Download, &gem_spec.name);
+                    let cache_path = match source_routes
+                        .fetch_into_cache(&gem_spec, dm.cache_dir())
+                        .await
+                    {
+                        Some(fetched) => fetched?,
+                        None => dm.download_gem(&gem_spec).await?,
+                    };
+                    Ok::<_, anyhow::Error>((gem_spec, cache_path))
                 }
             })
             .buffer_unordered(10) // Process 10 gems in parallel
             .collect()
             .await;
+        drop(downloads);
 
-        // Apply checksums to lockfile gems
-        for result in checksum_results {
+        for result in download_results {
             match result {
-                Ok((name, version, checksum)) => {
-                    for gem in &mut lockfile.gems {
-                        if gem.name == name && gem.version == version {
-                            gem.checksum = Some(checksum);
-                            break;
-                        }
-                    }
+                Ok(gem) => local_paths.push(gem),
+                Err(e) => lode::warn!("Failed to download gem for checksum: {e}"),
+            }
+        }
+
+        // Unchanged cache entries reuse their recorded digests
+        let mut digests = lode::DigestCache::load(dm.cache_dir());
+        let paths: Vec<_> = local_paths.iter().map(|(_, path)| path.clone()).collect();
+        let hashed = digests.sha256_all(&paths);
+        if let Err(e) = digests.save()
+            && verbose
+        {
+            println!("Could not save checksum cache: {e}");
+        }
+
+        for ((gem_spec, path), checksum) in local_paths.into_iter().zip(hashed) {
+            match checksum {
+                Ok(checksum) => {
+                    checksums.insert(gem_spec.full_name_with_platform().to_string(), checksum);
                 }
                 Err(e) => {
-                    eprintln!("Warning: Failed to compute checksum: {e}");
+                    lode::warn!("Failed to compute checksum for {}: {e}", path.display());
                 }
             }
         }
 
+        // Apply checksums to lockfile gems
+        for gem in &mut lockfile.gems {
+            if let Some(checksum) = checksums.remove(gem.full_name_with_platform()) {
+                gem.checksum = Some(checksum);
+            }
+        }
+
         if verbose {
             let checksummed = lockfile
                 .gems
@@ -430,6 +653,20 @@ pub(crate) async fn run(
         }
     }
 
+    // Gems under a pinned prefix must not resolve from any other source
+    let violations = lode::source_audit::pin_violations(&lockfile, &gemfile, &config.source_pins);
+    if !violations.is_empty() {
+        let lines: Vec<_> = violations
+            .iter()
+            .map(|finding| format!("  {finding}"))
+            .collect();
+        anyhow::bail!(
+            "Refusing to lock gems outside their pinned source:\n{}\n\
+             Declare them in a `source ... do` block for the pinned source.",
+            lines.join("\n")
+        );
+    }
+
     // Write lockfile or print to stdout
     let lockfile_content = lockfile.to_string();
 
@@ -451,6 +688,124 @@ pub(crate) async fn run(
     Ok(())
 }
 
+/// Warn about gems that several global sources could serve, or refuse them
+/// when `disable_multisource` is set
+///
+/// # Errors
+///
+/// Returns an error when `strict` and the Gemfile has ambiguous gems.
+pub(crate) fn check_sources(gemfile: &Gemfile, strict: bool, quiet: bool) -> Result<()> {
+    let ambiguous = gemfile.ambiguous_gems();
+    if ambiguous.is_empty() {
+        return Ok(());
+    }
+
+    let mut sources = vec![gemfile.source.as_str()];
+    sources.extend(gemfile.sources.iter().map(String::as_str));
+    let message = format!(
+        "The Gemfile declares multiple global sources ({}), so {} could be \
+         installed from any of them. Move each private gem into a `source ... do` \
+         block to pin it.",
+        sources.join(", "),
+        ambiguous.join(", ")
+    );
+    if strict {
+        anyhow::bail!("{message}\n(disable_multisource is set)");
+    }
+    if !quiet {
+        lode::warn!("{message}");
+    }
+    Ok(())
+}
+
+/// Versions locked in `lockfile_path` by gem name, except `update_gems`
+fn locked_versions(
+    lockfile_path: &std::path::Path,
+    update_gems: &[String],
+) -> BTreeMap<String, String> {
+    fs::read_to_string(lockfile_path)
+        .ok()
+        .and_then(|content| Lockfile::parse(&content).ok())
+        .map(|lockfile| {
+            lockfile
+                .gems
+                .into_iter()
+                .filter(|gem| !update_gems.contains(&gem.name))
+                .map(|gem| (gem.name, gem.version))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Exact requirements keeping held gems at their locked versions
+///
+/// Naming a held gem in `--update` is refused unless `release_hold`, which
+/// lets every held gem move. Without a parseable lockfile nothing is locked
+/// yet, so nothing is pinned.
+fn hold_pins(
+    config: &Config,
+    lockfile_path: &std::path::Path,
+    update_gems: &[String],
+    release_hold: bool,
+) -> Result<BTreeMap<String, String>> {
+    let holds = lode::config::held_gems(Some(config));
+    if holds.is_empty() || release_hold {
+        return Ok(BTreeMap::new());
+    }
+    if let Some((gem, reason)) = update_gems.iter().find_map(|gem| holds.get_key_value(gem)) {
+        anyhow::bail!(
+            "{gem} is held{}; pass --release-hold to update it, or release it with `lode hold --release {gem}`",
+            super::hold::describe(reason)
+        );
+    }
+
+    let Some(lockfile) = fs::read_to_string(lockfile_path)
+        .ok()
+        .and_then(|content| Lockfile::parse(&content).ok())
+    else {
+        return Ok(BTreeMap::new());
+    };
+    Ok(lockfile
+        .gems
+        .iter()
+        .filter(|gem| holds.contains_key(&gem.name))
+        .map(|gem| (gem.name.clone(), format!("= {}", gem.version)))
+        .collect())
+}
+
+/// Exact requirements holding each locked gem outside the subgraph reachable
+/// from Gemfile requirements the lockfile no longer satisfies
+///
+/// Gems no longer depended on are not pinned into the resolution, because
+/// constraints only apply to gems that are resolved anyway.
+fn incremental_pins(lockfile: &Lockfile, gemfile: &Gemfile) -> BTreeMap<String, String> {
+    let changed = lockfile.dependency_closure(&lockfile.unlocked_dependencies(gemfile));
+    lockfile
+        .gems
+        .iter()
+        .filter(|gem| !changed.contains(&gem.name))
+        .map(|gem| (gem.name.clone(), format!("= {}", gem.version)))
+        .collect()
+}
+
+/// A locked gem as the resolver would have returned it
+fn locked_resolution(spec: &GemSpec) -> ResolvedGem {
+    ResolvedGem {
+        name: spec.name.clone(),
+        version: spec.version.clone(),
+        platform: spec.platform.clone().unwrap_or_else(|| "ruby".to_string()),
+        dependencies: spec
+            .dependencies
+            .iter()
+            .map(|dep| ResolvedDependency {
+                name: dep.name.clone(),
+                requirement: dep.requirement.clone(),
+            })
+            .collect(),
+        ruby_version: None,
+    }
+}
+
 /// Convert a `ResolvedGem` to a `GemSpec` for the lockfile
 fn convert_to_gem_spec(resolved: ResolvedGem) -> GemSpec {
     let platform = if resolved.platform == "ruby" || resolved.platform.is_empty() {
@@ -476,3 +831,47 @@ fn convert_to_gem_spec(resolved: ResolvedGem) -> GemSpec {
         vec![], // Groups are handled by Gemfile, not resolver
     )
 }
+
+/// Compact index checksums for `gems`, keyed by full name with platform
+///
+/// Gems without a `source` of their own are looked up on `default_source`.
+/// Gems served by a plugin source, or that their source publishes no
+/// checksum for, are missing from the result.
+async fn published_checksums(
+    gemfile: &Gemfile,
+    default_source: &str,
+    gems: &[GemSpec],
+    source_routes: &lode::SourceRoutes,
+    local: bool,
+) -> BTreeMap<String, String> {
+    let names: BTreeSet<&str> = gems
+        .iter()
+        .map(|gem| gem.name.as_str())
+        .filter(|name| !source_routes.contains(name))
+        .collect();
+
+    stream::iter(names)
+        .map(|name| async move {
+            let source = gemfile
+                .gems
+                .iter()
+                .find(|dependency| dependency.name == name)
+                .and_then(|dependency| dependency.source.as_deref())
+                .unwrap_or(default_source);
+            let Ok(client) = RubyGemsClient::new(source) else {
+                return Vec::new();
+            };
+            client
+                .with_cache_only(local)
+                .fetch_checksums(name)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(version, checksum)| (format!("{name}-{version}"), checksum))
+                .collect::<Vec<_>>()
+        })
+        .buffer_unordered(10)
+        .flat_map(stream::iter)
+        .collect()
+        .await
+}
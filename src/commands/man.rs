@@ -0,0 +1,101 @@
+//! Man command
+//!
+//! Render roff man pages from the CLI definition: `lode(1)` plus one
+//! `lode-COMMAND(1)` page per subcommand, so `man lode-install` works once
+//! they are installed. Pages include the examples, config keys and
+//! environment variables from `lode help COMMAND --examples`.
+
+use anyhow::{Context, Result};
+use clap::CommandFactory;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Print a man page, or write every page to `dir`
+///
+/// # Examples
+///
+/// ```bash
+/// # Read a page without installing it
+/// lode man install | man -l -
+///
+/// # Install all pages
+/// lode man --dir /usr/local/share/man/man1
+/// ```
+pub(crate) fn run(command: Option<&str>, dir: Option<&str>) -> Result<()> {
+    let mut cli = crate::Cli::command().disable_help_subcommand(true);
+    cli.build();
+
+    if let Some(dir) = dir {
+        return write_all(&cli, Path::new(dir));
+    }
+
+    let page = match command {
+        None => render(&cli)?,
+        Some(name) => {
+            let Some(subcommand) = cli.find_subcommand(name) else {
+                anyhow::bail!("Unknown command `{name}`. Run `lode help` to list commands.");
+            };
+            render(subcommand)?
+        }
+    };
+    io::stdout().write_all(&page)?;
+    Ok(())
+}
+
+/// Write `lode.1` and a page for each visible subcommand into `dir`
+fn write_all(cli: &clap::Command, dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let pages = std::iter::once(cli).chain(cli.get_subcommands().filter(|sub| !sub.is_hide_set()));
+    let mut written = 0;
+    for command in pages {
+        let path = dir.join(format!("{}.1", page_name(command)));
+        fs::write(&path, render(command)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        written += 1;
+    }
+
+    println!("Wrote {written} man pages to {}", dir.display());
+    Ok(())
+}
+
+/// `lode` or `lode-COMMAND`
+fn page_name(command: &clap::Command) -> &str {
+    command
+        .get_display_name()
+        .unwrap_or_else(|| command.get_name())
+}
+
+/// Roff for `command`, with its extended documentation appended
+fn render(command: &clap::Command) -> Result<Vec<u8>> {
+    let mut page = Vec::new();
+    clap_mangen::Man::new(command.clone())
+        .title(page_name(command).to_uppercase())
+        .manual("Lode Manual")
+        .render(&mut page)?;
+    if let Some(sections) = super::help::render_roff(command.get_name()) {
+        page.extend_from_slice(sections.as_bytes());
+    }
+    Ok(page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Building the full CLI needs a large stack, as for `lode completion`
+
+    #[test]
+    #[ignore = "requires large stack (run with RUST_MIN_STACK=8388608)"]
+    fn writes_a_page_per_command() {
+        let temp = tempfile::tempdir().unwrap();
+        run(None, Some(temp.path().to_str().unwrap())).unwrap();
+
+        assert!(temp.path().join("lode.1").exists());
+        let install = fs::read_to_string(temp.path().join("lode-install.1")).unwrap();
+        assert!(install.contains(".TH LODE-INSTALL 1"));
+        assert!(install.contains(".SH EXAMPLES"));
+        assert!(!temp.path().join("lode-complete-candidates.1").exists());
+    }
+}
@@ -0,0 +1,319 @@
+//! Matrix command
+//!
+//! Generate Appraisal-style gemfiles from a base Gemfile and a matrix of
+//! dependency variations, then lock each one. A matrix maps variant names to
+//! the gems they change:
+//!
+//! ```toml
+//! [rails_7_1]
+//! rails = "~> 7.1.0"
+//!
+//! [rails_8_0]
+//! rails = "~> 8.0.0"
+//! sqlite3 = ">= 2.1"
+//! byebug = false
+//! ```
+//!
+//! A requirement pins the gem (adding it when the base Gemfile lacks it),
+//! `false` removes it, and `true` adds it unconstrained if missing. The same
+//! shape works as YAML. Variants are written to `gemfiles/<name>.gemfile`.
+
+use anyhow::{Context, Result};
+use lode::gemfile_writer::GemfileWriter;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Matrix definitions looked for when `--file` is not given
+const MATRIX_FILES: [&str; 3] = ["matrix.toml", "matrix.yml", "matrix.yaml"];
+
+/// How a variant changes one gem
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+enum Variation {
+    /// Pin the gem to this requirement
+    Requirement(String),
+    /// `false` removes the gem, `true` adds it unconstrained if missing
+    Present(bool),
+}
+
+/// Variant name to the gems it changes
+type Matrix = BTreeMap<String, BTreeMap<String, Variation>>;
+
+/// Generate a gemfile per matrix variant and lock each one
+pub(crate) async fn run(
+    matrix_path: Option<&str>,
+    gemfile: Option<&str>,
+    output: &str,
+    lock: bool,
+    quiet: bool,
+) -> Result<()> {
+    let matrix_path = match matrix_path {
+        Some(path) => PathBuf::from(path),
+        None => MATRIX_FILES
+            .iter()
+            .map(PathBuf::from)
+            .find(|path| path.exists())
+            .with_context(|| {
+                format!(
+                    "No matrix definition found (looked for {})",
+                    MATRIX_FILES.join(", ")
+                )
+            })?,
+    };
+    let matrix = load_matrix(&matrix_path)?;
+    let base_path = gemfile.map_or_else(lode::paths::find_gemfile, PathBuf::from);
+
+    let generated = generate(&matrix, &matrix_path, &base_path, Path::new(output))?;
+    for gemfile in &generated {
+        if !quiet {
+            println!("Generated {}", gemfile.display());
+        }
+        if lock {
+            super::lock::run(
+                &gemfile.to_string_lossy(),
+                None,                               // lockfile_path
+                &[],                                // add_platforms
+                &[],                                // remove_platforms
+                &[],                                // update_gems
+                false,                              // release_hold
+                &std::collections::BTreeMap::new(), // constraints
+                lode::ResolutionStrategy::Latest,   // strategy
+                false,                              // print
+                false,                              // verbose
+                false,                              // patch
+                false,                              // minor
+                false,                              // major
+                false,                              // strict
+                false,                              // conservative
+                false,                              // incremental
+                false,                              // local
+                false,                              // pre
+                None,                               // bundler
+                false,                              // normalize_platforms
+                false,                              // add_checksums
+                false,                              // full_index
+                true,                               // quiet
+                &lode::Profiler::default(),
+            )
+            .await
+            .with_context(|| format!("Failed to lock {}", gemfile.display()))?;
+            if !quiet {
+                println!(
+                    "Locked {}",
+                    lode::paths::lockfile_for_gemfile(gemfile).display()
+                );
+            }
+        }
+    }
+
+    if !quiet {
+        println!(
+            "\n{} variants written to {output}. Run `lode install --gemfile '{output}/*.gemfile'` to install them all.",
+            generated.len()
+        );
+    }
+    Ok(())
+}
+
+/// Parse a TOML or YAML matrix, chosen by file extension
+fn load_matrix(path: &Path) -> Result<Matrix> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read matrix: {}", path.display()))?;
+    let matrix: Matrix = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("yml" | "yaml") => serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse matrix: {}", path.display()))?,
+        _ => toml::from_str(&content)
+            .with_context(|| format!("Failed to parse matrix: {}", path.display()))?,
+    };
+
+    if matrix.is_empty() {
+        anyhow::bail!("Matrix {} defines no variants", path.display());
+    }
+    if let Some(name) = matrix.keys().find(|name| {
+        !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    }) {
+        anyhow::bail!("Invalid variant name {name:?}: use letters, digits, '_', '-' and '.'");
+    }
+    Ok(matrix)
+}
+
+/// Write `<output>/<variant>.gemfile` for every variant, returning their paths
+fn generate(
+    matrix: &Matrix,
+    matrix_path: &Path,
+    base_path: &Path,
+    output: &Path,
+) -> Result<Vec<PathBuf>> {
+    let base = fs::read_to_string(base_path)
+        .with_context(|| format!("Failed to read Gemfile: {}", base_path.display()))?;
+    fs::create_dir_all(output).with_context(|| format!("Failed to create {}", output.display()))?;
+
+    let base_dir = base_path.parent().unwrap_or_else(|| Path::new(""));
+    let header = format!(
+        "# This file was generated by `lode matrix` from {}. Do not edit it by hand.\n\n",
+        matrix_path.display()
+    );
+    let content = header + &relocate_gemspec(&base, &relative_base(output, base_dir));
+
+    let mut generated = Vec::with_capacity(matrix.len());
+    for (name, variations) in matrix {
+        let path = output.join(format!("{name}.gemfile"));
+        fs::write(&path, &content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        let mut writer = GemfileWriter::load(&path)?;
+        for (gem, variation) in variations {
+            match variation {
+                Variation::Requirement(requirement) => {
+                    writer.add_gem(gem, Some(requirement), None, None)?;
+                }
+                Variation::Present(true) => writer.add_gem(gem, None, None, None)?,
+                Variation::Present(false) => {
+                    writer.remove_gem(gem)?;
+                }
+            }
+        }
+        writer.write()?;
+        generated.push(path);
+    }
+    Ok(generated)
+}
+
+/// The base Gemfile's directory as seen from `output`
+fn relative_base(output: &Path, base_dir: &Path) -> PathBuf {
+    if output.is_absolute() || output.components().any(|c| c == Component::ParentDir) {
+        let dir = if base_dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            base_dir
+        };
+        return fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    }
+
+    let depth = output
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .count();
+    let mut path: PathBuf = std::iter::repeat_n("..", depth).collect();
+    if !base_dir.as_os_str().is_empty() {
+        path.push(base_dir);
+    }
+    path
+}
+
+/// Point bare `gemspec` lines at the base Gemfile's directory
+///
+/// Gemfiles resolve `gemspec` relative to themselves, so a copy in
+/// `gemfiles/` would otherwise look for the gemspec in the wrong place.
+fn relocate_gemspec(content: &str, base: &Path) -> String {
+    let option = format!("path: \"{}\"", base.display());
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            let code = line.trim_start();
+            let indent = &line[..line.len() - code.len()];
+            let (statement, comment) = code.find(" #").map_or((code, ""), |at| code.split_at(at));
+            let statement = statement.trim_end();
+            let ending = &code[statement.len() + comment.len()..];
+            let is_gemspec = statement
+                .strip_prefix("gemspec")
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']));
+            if !is_gemspec || statement.contains("path:") {
+                line.to_string()
+            } else if statement == "gemspec" {
+                format!("{indent}gemspec {option}{comment}{ending}")
+            } else {
+                format!("{indent}{statement}, {option}{comment}{ending}")
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn loads_toml_and_yaml_matrices() {
+        let temp = TempDir::new().unwrap();
+        let toml_path = temp.path().join("matrix.toml");
+        fs::write(
+            &toml_path,
+            "[rails_7_1]\nrails = \"~> 7.1.0\"\n\n[rails_8_0]\nrails = \"~> 8.0.0\"\nbyebug = false\n",
+        )
+        .unwrap();
+        let yaml_path = temp.path().join("matrix.yml");
+        fs::write(
+            &yaml_path,
+            "rails_7_1:\n  rails: \"~> 7.1.0\"\nrails_8_0:\n  rails: \"~> 8.0.0\"\n  byebug: false\n",
+        )
+        .unwrap();
+
+        let matrix = load_matrix(&toml_path).unwrap();
+        assert_eq!(matrix, load_matrix(&yaml_path).unwrap());
+        assert_eq!(
+            matrix.get("rails_8_0").and_then(|gems| gems.get("byebug")),
+            Some(&Variation::Present(false))
+        );
+
+        fs::write(&toml_path, "[\"rails 8\"]\nrails = \"8.0\"\n").unwrap();
+        assert!(load_matrix(&toml_path).is_err());
+    }
+
+    #[test]
+    fn generates_a_gemfile_per_variant() {
+        let temp = TempDir::new().unwrap();
+        let base = temp.path().join("Gemfile");
+        fs::write(
+            &base,
+            "source \"https://rubygems.org\"\n\ngemspec\n\ngem \"rails\", \"~> 7.0\"\ngem \"byebug\"\n",
+        )
+        .unwrap();
+        let matrix: Matrix = toml::from_str(
+            "[rails_8_0]\nrails = \"~> 8.0.0\"\nsqlite3 = \">= 2.1\"\nbyebug = false\n",
+        )
+        .unwrap();
+
+        let output = temp.path().join("gemfiles");
+        let generated = generate(&matrix, Path::new("matrix.toml"), &base, &output).unwrap();
+        assert_eq!(generated, vec![output.join("rails_8_0.gemfile")]);
+
+        let content = fs::read_to_string(output.join("rails_8_0.gemfile")).unwrap();
+        assert!(content.starts_with("# This file was generated by `lode matrix`"));
+        assert!(content.contains("gem \"rails\", \"~> 8.0.0\""));
+        assert!(content.contains("gem \"sqlite3\", \">= 2.1\""));
+        assert!(!content.contains("byebug"));
+        assert!(content.contains("gemspec path: \""));
+    }
+
+    #[test]
+    fn gemspec_points_back_at_the_base_gemfile() {
+        assert_eq!(
+            relative_base(Path::new("gemfiles"), Path::new("")),
+            Path::new("..")
+        );
+        assert_eq!(
+            relative_base(Path::new("./ci/gemfiles"), Path::new("app")),
+            Path::new("../../app")
+        );
+
+        let base = Path::new("..");
+        assert_eq!(
+            relocate_gemspec("source 'x'\ngemspec\n", base),
+            "source 'x'\ngemspec path: \"..\"\n"
+        );
+        assert_eq!(
+            relocate_gemspec("  gemspec name: \"foo\" # main\n", base),
+            "  gemspec name: \"foo\", path: \"..\" # main\n"
+        );
+        assert_eq!(
+            relocate_gemspec("gemspec path: \"lib\"\ngem \"gemspec-tools\"\n", base),
+            "gemspec path: \"lib\"\ngem \"gemspec-tools\"\n"
+        );
+    }
+}
@@ -3,10 +3,12 @@
 //! This module contains all Bundler and `RubyGems` command handlers.
 
 pub(crate) mod add;
+pub(crate) mod audit;
 pub(crate) mod binstubs;
 pub(crate) mod cache;
 pub(crate) mod check;
 pub(crate) mod clean;
+pub(crate) mod complete;
 pub(crate) mod completion;
 pub(crate) mod config;
 pub(crate) mod contents;
@@ -39,20 +41,35 @@ pub(crate) mod gem_uninstall;
 pub(crate) mod gem_update;
 pub(crate) mod gem_which;
 pub(crate) mod gem_yank;
+pub(crate) mod help;
+pub(crate) mod hold;
+pub(crate) mod index;
 pub(crate) mod info;
 pub(crate) mod init;
 pub(crate) mod install;
+pub(crate) mod issue;
 pub(crate) mod list;
 pub(crate) mod lock;
+pub(crate) mod man;
+pub(crate) mod matrix;
 pub(crate) mod open;
 pub(crate) mod outdated;
 pub(crate) mod platform;
 pub(crate) mod plugin;
 pub(crate) mod pristine;
+pub(crate) mod profile;
 pub(crate) mod remove;
+pub(crate) mod resolve;
 pub(crate) mod search;
+pub(crate) mod self_update;
+pub(crate) mod shell;
 pub(crate) mod show;
+pub(crate) mod size;
 pub(crate) mod specification;
+pub(crate) mod stats;
+pub(crate) mod test_install;
 pub(crate) mod unpack;
 pub(crate) mod update;
+pub(crate) mod verify_env;
 pub(crate) mod which;
+pub(crate) mod with_unbundled_env;
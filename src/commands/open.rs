@@ -3,9 +3,10 @@
 //! Open a gem in your editor
 
 use anyhow::{Context, Result};
+use lode::gem_store::{GemPath, GemQuery};
 use lode::{Config, config, lockfile::Lockfile};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 /// Open a gem's source code in your editor
@@ -20,6 +21,9 @@ use std::process::Command;
 pub(crate) fn run(gem_name: &str, relative_path: Option<&str>) -> Result<()> {
     // Find the gem's installation directory
     let gem_dir = find_gem_path(gem_name)?;
+    if let Some(gem_home) = gem_dir.parent().and_then(Path::parent) {
+        println!("Found {gem_name} in {}", gem_home.display());
+    }
 
     // Determine the path to open (gem dir or specific file within it)
     let path_to_open = if let Some(rel_path) = relative_path {
@@ -81,10 +85,25 @@ where
 
 /// Find the installation path of a gem
 ///
-/// This duplicates logic from show.rs but keeps the command self-contained.
+/// Locked gems are looked up in the vendor directory, then every
+/// `GEM_PATH` directory. Without a lockfile the newest installed copy on
+/// `GEM_PATH` is used.
 fn find_gem_path(gem_name: &str) -> Result<PathBuf> {
-    // Read and parse lockfile
+    // Outside a bundle, open the newest copy anywhere on GEM_PATH
     let lockfile_path = "Gemfile.lock";
+    if !Path::new(lockfile_path).exists() {
+        let gem_path = GemPath::from_env(&config::ruby_version(None));
+        let installed = gem_path.query(&GemQuery::new().with_name(gem_name).latest_only())?;
+        return installed
+            .into_iter()
+            .next()
+            .map(|gem| gem.path)
+            .with_context(|| {
+                format!("Gem '{gem_name}' is not installed in any GEM_PATH directory")
+            });
+    }
+
+    // Read and parse lockfile
     let content = fs::read_to_string(lockfile_path)
         .with_context(|| format!("Failed to read lockfile: {lockfile_path}"))?;
 
@@ -102,14 +121,15 @@ fn find_gem_path(gem_name: &str) -> Result<PathBuf> {
         .map_or_else(|| "3.4.0".to_string(), |v| normalize_version(v));
 
     let gems_dir = vendor_dir.join("ruby").join(&ruby_version).join("gems");
+    let gem_path = GemPath::for_bundle(gems_dir.clone(), &ruby_version);
 
     // Find the gem in the lockfile
     // Check regular gems
     if let Some(gem) = lockfile.gems.iter().find(|gem| gem.name == gem_name) {
-        let gem_dir = gems_dir.join(gem.full_name());
-        if gem_dir.exists() {
+        if let Some(gem_dir) = find_installed(&gem_path, gem.full_name()) {
             return Ok(gem_dir);
         }
+        let gem_dir = gems_dir.join(gem.full_name());
         anyhow::bail!(
             "Gem {} ({}) is in the lockfile but not installed at {}",
             gem.name,
@@ -120,10 +140,11 @@ fn find_gem_path(gem_name: &str) -> Result<PathBuf> {
 
     // Check git gems
     if let Some(gem) = lockfile.git_gems.iter().find(|gem| gem.name == gem_name) {
-        let gem_dir = gems_dir.join(format!("{}-{}", gem.name, gem.version));
-        if gem_dir.exists() {
+        let full_name = format!("{}-{}", gem.name, gem.version);
+        if let Some(gem_dir) = find_installed(&gem_path, &full_name) {
             return Ok(gem_dir);
         }
+        let gem_dir = gems_dir.join(full_name);
         anyhow::bail!(
             "Gem {} ({}) [git] is in the lockfile but not installed at {}",
             gem.name,
@@ -134,10 +155,11 @@ fn find_gem_path(gem_name: &str) -> Result<PathBuf> {
 
     // Check path gems
     if let Some(gem) = lockfile.path_gems.iter().find(|gem| gem.name == gem_name) {
-        let gem_dir = gems_dir.join(format!("{}-{}", gem.name, gem.version));
-        if gem_dir.exists() {
+        let full_name = format!("{}-{}", gem.name, gem.version);
+        if let Some(gem_dir) = find_installed(&gem_path, &full_name) {
             return Ok(gem_dir);
         }
+        let gem_dir = gems_dir.join(full_name);
         anyhow::bail!(
             "Gem {} ({}) [path] is in the lockfile but not installed at {}",
             gem.name,
@@ -159,6 +181,15 @@ fn find_gem_path(gem_name: &str) -> Result<PathBuf> {
     );
 }
 
+/// First directory on `gem_path` holding `full_name`
+fn find_installed(gem_path: &GemPath, full_name: &str) -> Option<PathBuf> {
+    gem_path
+        .stores()
+        .iter()
+        .map(|store| store.gem_dir().join(full_name))
+        .find(|dir| dir.is_dir())
+}
+
 /// Normalize Ruby version from lockfile format
 ///
 /// Converts "ruby 3.3.0p0" to "3.3.0"
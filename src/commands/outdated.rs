@@ -4,14 +4,160 @@
 
 use anyhow::{Context, Result};
 use indicatif::{ProgressBar, ProgressStyle};
-use lode::{Gemfile, lockfile::Lockfile, rubygems_client::RubyGemsClient};
+use lode::exit_code::ExitCode;
+use lode::messages::{self, Message};
+use lode::reporter::version;
+use lode::{AdvisoryDatabase, Gemfile, lockfile::Lockfile, rubygems_client::RubyGemsClient};
 use semver::Version;
+use serde::Serialize;
 use std::collections::HashSet;
 use std::fs;
 
+/// How `outdated` prints its results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// Human-readable list
+    Text,
+    /// JSON array, one object per outdated gem
+    Json,
+}
+
+/// Which outdated gems make `outdated` exit nonzero
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum FailLevel {
+    /// A new major release
+    Major,
+    /// A new major or minor release
+    Minor,
+    /// Any newer release
+    Patch,
+    /// A newer release while the locked version has a security advisory
+    Security,
+}
+
+impl FailLevel {
+    /// Whether `gem` breaks this policy
+    fn fails(self, gem: &OutdatedGem, advisories: Option<&AdvisoryDatabase>) -> bool {
+        match self {
+            Self::Major => gem.severity == Severity::Major,
+            Self::Minor => matches!(gem.severity, Severity::Major | Severity::Minor),
+            Self::Patch => true,
+            Self::Security => advisories.is_some_and(|database| {
+                !database.vulnerabilities(&gem.name, &gem.current).is_empty()
+            }),
+        }
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Major => "major",
+            Self::Minor => "minor",
+            Self::Patch => "patch",
+            Self::Security => "security",
+        }
+    }
+}
+
+/// Size of the version bump to the newest release
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Major,
+    Minor,
+    Patch,
+    /// Versions that don't parse, or differ past the patch level
+    Unknown,
+}
+
+impl Severity {
+    fn of(current: &str, latest: &str) -> Self {
+        match (
+            parse_lenient_version(current),
+            parse_lenient_version(latest),
+        ) {
+            (Ok(current), Ok(latest)) if latest.major > current.major => Self::Major,
+            (Ok(current), Ok(latest)) if latest.minor > current.minor => Self::Minor,
+            (Ok(current), Ok(latest)) if latest.patch > current.patch => Self::Patch,
+            _ => Self::Unknown,
+        }
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Major => "major",
+            Self::Minor => "minor",
+            Self::Patch => "patch",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// An outdated gem with the Gemfile context an update bot needs
+#[derive(Debug, Clone, Serialize)]
+struct OutdatedGem {
+    name: String,
+    current: String,
+    latest: String,
+    /// Gemfile groups; empty for gems only pulled in as dependencies
+    groups: Vec<String>,
+    /// Gemfile requirement; empty for gems only pulled in as dependencies
+    requirement: String,
+    source: String,
+    severity: Severity,
+}
+
+impl OutdatedGem {
+    fn new(name: &str, current: &str, latest: &str, gemfile: Option<&Gemfile>) -> Self {
+        let declared = gemfile.and_then(|gemfile| gemfile.gems.iter().find(|gem| gem.name == name));
+        let groups = declared.map_or_else(Vec::new, |gem| {
+            if gem.groups.is_empty() {
+                vec!["default".to_string()]
+            } else {
+                gem.groups.clone()
+            }
+        });
+        let requirement = declared.map_or_else(String::new, |gem| {
+            if gem.version_requirement.is_empty() {
+                ">= 0".to_string()
+            } else {
+                gem.version_requirement.clone()
+            }
+        });
+        let source = gemfile.map_or(lode::DEFAULT_GEM_SOURCE, |gemfile| {
+            lode::source_audit::resolved_source(gemfile, name)
+        });
+
+        Self {
+            name: name.to_string(),
+            current: current.to_string(),
+            latest: latest.to_string(),
+            groups,
+            requirement,
+            source: source.to_string(),
+            severity: Severity::of(current, latest),
+        }
+    }
+
+    /// Tab-separated: name, current, latest, groups, requirement, source,
+    /// severity
+    fn parseable_line(&self) -> String {
+        [
+            self.name.as_str(),
+            &self.current,
+            &self.latest,
+            &self.groups.join(","),
+            &self.requirement,
+            &self.source,
+            self.severity.name(),
+        ]
+        .join("\t")
+    }
+}
+
 /// Compare installed gem versions with latest available versions on RubyGems.org
 #[allow(
     clippy::fn_params_excessive_bools,
+    clippy::too_many_arguments,
     reason = "Parameters come from CLI structure"
 )]
 #[allow(
@@ -21,12 +167,18 @@ use std::fs;
 pub(crate) async fn run(
     lockfile_path: &str,
     parseable: bool,
+    format: OutputFormat,
     filter_major: bool,
     filter_minor: bool,
     filter_patch: bool,
     include_prerelease: bool,
     group_filter: Option<&str>,
+    fail_level: Option<FailLevel>,
 ) -> Result<()> {
+    let json = format == OutputFormat::Json;
+    // Machine-readable output skips progress and chatter
+    let parseable = parseable || json;
+
     // Read and parse lockfile
     let content = fs::read_to_string(lockfile_path)
         .with_context(|| format!("Failed to read lockfile: {lockfile_path}"))?;
@@ -35,15 +187,20 @@ pub(crate) async fn run(
         .with_context(|| format!("Failed to parse lockfile: {lockfile_path}"))?;
 
     if lockfile.gems.is_empty() {
-        if !parseable {
+        if json {
+            println!("[]");
+        } else if !parseable {
             println!("No gems found in lockfile");
         }
         return Ok(());
     }
 
+    // Groups, requirements and sources come from the Gemfile when there is one
+    let gemfile_path = lode::paths::find_gemfile();
+    let gemfile = Gemfile::parse_file(&gemfile_path).ok();
+
     // Filter by group if requested
     let gems_in_group: Option<HashSet<String>> = if let Some(group_name) = group_filter {
-        let gemfile_path = lode::paths::find_gemfile();
         let gemfile = Gemfile::parse_file(&gemfile_path).with_context(|| {
             format!(
                 "Failed to parse {} for group filtering",
@@ -59,7 +216,9 @@ pub(crate) async fn run(
             .collect();
 
         if filtered.is_empty() {
-            if !parseable {
+            if json {
+                println!("[]");
+            } else if !parseable {
                 println!("No gems found in group '{group_name}'");
             }
             return Ok(());
@@ -96,6 +255,8 @@ pub(crate) async fn run(
     let mut outdated_gems = Vec::new();
     let mut up_to_date_count = 0;
     let mut error_count = 0;
+    let holds = lode::config::held_gems(lode::Config::load().ok().as_ref());
+    let mut held = Vec::new();
 
     for gem in &lockfile.gems {
         // Skip gems not in requested group
@@ -105,6 +266,20 @@ pub(crate) async fn run(
             continue;
         }
 
+        // Held gems (`lode hold`) are not checked
+        if let Some(reason) = holds.get(&gem.name) {
+            held.push(format!(
+                "{} ({}){}",
+                gem.name,
+                gem.version,
+                super::hold::describe(reason)
+            ));
+            if let Some(ref pb) = pb {
+                pb.inc(1);
+            }
+            continue;
+        }
+
         if let Some(ref pb) = pb {
             pb.set_message(format!("Checking {}", gem.name));
         }
@@ -152,7 +327,12 @@ pub(crate) async fn run(
 
         // Compare versions
         if is_newer(&latest.number, &gem.version) {
-            outdated_gems.push((gem.name.clone(), gem.version.clone(), latest.number.clone()));
+            outdated_gems.push(OutdatedGem::new(
+                &gem.name,
+                &gem.version,
+                &latest.number,
+                gemfile.as_ref(),
+            ));
         } else {
             up_to_date_count += 1;
         }
@@ -167,43 +347,39 @@ pub(crate) async fn run(
     }
 
     // Filter outdated gems by version change type if requested
-    let outdated_gems = if filter_major || filter_minor || filter_patch {
-        outdated_gems
-            .into_iter()
-            .filter(|(_, current, latest)| {
-                match (
-                    parse_lenient_version(current),
-                    parse_lenient_version(latest),
-                ) {
-                    (Ok(curr_ver), Ok(latest_ver)) => {
-                        if filter_major {
-                            latest_ver.major > curr_ver.major
-                        } else if filter_minor {
-                            latest_ver.major == curr_ver.major && latest_ver.minor > curr_ver.minor
-                        } else if filter_patch {
-                            latest_ver.major == curr_ver.major
-                                && latest_ver.minor == curr_ver.minor
-                                && latest_ver.patch > curr_ver.patch
-                        } else {
-                            true
-                        }
-                    }
-                    _ => true, // Include gems with non-parseable versions
-                }
-            })
-            .collect()
+    let wanted = if filter_major {
+        Some(Severity::Major)
+    } else if filter_minor {
+        Some(Severity::Minor)
+    } else if filter_patch {
+        Some(Severity::Patch)
+    } else {
+        None
+    };
+    let outdated_gems: Vec<OutdatedGem> = outdated_gems
+        .into_iter()
+        .filter(|gem| {
+            // Include gems with non-parseable versions
+            wanted.is_none_or(|wanted| gem.severity == wanted || gem.severity == Severity::Unknown)
+        })
+        .collect();
+
+    let advisories = if fail_level == Some(FailLevel::Security) {
+        let cache_dir = lode::config::cache_dir(None)?;
+        Some(AdvisoryDatabase::open(&cache_dir).context("Failed to load the advisory database")?)
     } else {
-        outdated_gems
+        None
     };
 
     // Display results
-    if parseable {
-        // Machine-readable format: gem_name current_version latest_version
-        for (name, current, latest) in &outdated_gems {
-            println!("{name} {current} {latest}");
+    if json {
+        println!("{}", serde_json::to_string_pretty(&outdated_gems)?);
+    } else if parseable {
+        for gem in &outdated_gems {
+            println!("{}", gem.parseable_line());
         }
     } else if outdated_gems.is_empty() {
-        println!("All gems are up to date!");
+        println!("{}", messages::text(Message::AllGemsUpToDate));
         println!(
             "   {} gems checked, {} errors",
             lockfile.gems.len(),
@@ -215,12 +391,17 @@ pub(crate) async fn run(
         // Find the longest gem name for alignment
         let max_name_len = outdated_gems
             .iter()
-            .map(|(name, _, _): &(String, String, String)| name.len())
+            .map(|gem| gem.name.len())
             .max()
             .unwrap_or(0);
 
-        for (name, current, latest) in &outdated_gems {
-            println!("  • {name:<max_name_len$}  {current} -> {latest}");
+        for gem in &outdated_gems {
+            println!(
+                "  • {:<max_name_len$}  {} -> {}",
+                gem.name,
+                version(&gem.current),
+                version(&gem.latest)
+            );
         }
 
         println!(
@@ -229,12 +410,44 @@ pub(crate) async fn run(
             outdated_gems.len(),
             error_count
         );
-        println!("\nRun `lode update` to update gems to their latest versions.");
+        println!("\n{}", messages::text(Message::RunUpdateHint));
+    }
+
+    if !parseable && !held.is_empty() {
+        println!("\nHeld gems, not checked (release with `lode hold --release GEM`):");
+        for gem in &held {
+            println!("  • {gem}");
+        }
+    }
+
+    if let Some(level) = fail_level {
+        let failing = policy_failures(&outdated_gems, level, advisories.as_ref());
+        if !failing.is_empty() {
+            return Err(ExitCode::Failure.error(format!(
+                "{} outdated gems at the {} level: {}",
+                failing.len(),
+                level.name(),
+                failing.join(", ")
+            )));
+        }
     }
 
     Ok(())
 }
 
+/// Names of the outdated gems that break the `level` policy
+fn policy_failures(
+    outdated_gems: &[OutdatedGem],
+    level: FailLevel,
+    advisories: Option<&AdvisoryDatabase>,
+) -> Vec<String> {
+    outdated_gems
+        .iter()
+        .filter(|gem| level.fails(gem, advisories))
+        .map(|gem| gem.name.clone())
+        .collect()
+}
+
 /// Check if a version string indicates a prerelease version
 ///
 /// Prerelease versions typically contain: alpha, beta, rc, pre, dev
@@ -356,6 +569,48 @@ mod tests {
         assert!(!is_newer("1.0.0", "1.0.0.1"));
     }
 
+    #[test]
+    fn severity_of_bump() {
+        assert_eq!(Severity::of("1.2.3", "2.0.0"), Severity::Major);
+        assert_eq!(Severity::of("1.2.3", "1.3.0"), Severity::Minor);
+        assert_eq!(Severity::of("1.2.3", "1.2.4"), Severity::Patch);
+        assert_eq!(Severity::of("1.2.3.1", "1.2.3.2"), Severity::Unknown);
+    }
+
+    #[test]
+    fn parseable_line_includes_gemfile_context() {
+        let gemfile = Gemfile::parse(
+            "source \"https://rubygems.org\"\n\
+             gem \"rails\", \"~> 7.0\"\n\
+             gem \"rspec\", group: :test\n",
+        )
+        .unwrap();
+
+        let rails = OutdatedGem::new("rails", "7.0.8", "7.1.0", Some(&gemfile));
+        assert_eq!(
+            rails.parseable_line(),
+            "rails\t7.0.8\t7.1.0\tdefault\t~> 7.0\thttps://rubygems.org\tminor"
+        );
+
+        let rspec = OutdatedGem::new("rspec", "3.12.0", "3.13.0", Some(&gemfile));
+        assert_eq!(rspec.groups, vec!["test"]);
+        assert_eq!(rspec.requirement, ">= 0");
+
+        let rack = OutdatedGem::new("rack", "2.2.8", "3.0.0", Some(&gemfile));
+        assert_eq!(
+            serde_json::to_value(&rack).unwrap(),
+            serde_json::json!({
+                "name": "rack",
+                "current": "2.2.8",
+                "latest": "3.0.0",
+                "groups": [],
+                "requirement": "",
+                "source": "https://rubygems.org",
+                "severity": "major",
+            })
+        );
+    }
+
     #[test]
     fn version_comparison_edge_cases() {
         assert!(is_newer("10.0.0", "9.0.0"));
@@ -366,4 +621,32 @@ mod tests {
         assert!(!is_newer("1.9.0", "1.10.0"));
         assert!(!is_newer("1.0.9", "1.0.10"));
     }
+
+    #[test]
+    fn fail_levels_select_policy_violations() {
+        let gems = vec![
+            OutdatedGem::new("rails", "7.0.8", "8.0.1", None),
+            OutdatedGem::new("rack", "3.0.0", "3.1.7", None),
+            OutdatedGem::new("puma", "6.4.2", "6.4.3", None),
+        ];
+        let database = AdvisoryDatabase::from_advisories([lode::Advisory {
+            gem: "puma".to_string(),
+            patched_versions: vec![">= 6.4.3".to_string()],
+            ..lode::Advisory::default()
+        }]);
+
+        assert_eq!(
+            policy_failures(&gems, FailLevel::Major, None),
+            vec!["rails"]
+        );
+        assert_eq!(
+            policy_failures(&gems, FailLevel::Minor, None),
+            vec!["rails", "rack"]
+        );
+        assert_eq!(policy_failures(&gems, FailLevel::Patch, None).len(), 3);
+        assert_eq!(
+            policy_failures(&gems, FailLevel::Security, Some(&database)),
+            vec!["puma"]
+        );
+    }
 }
@@ -181,8 +181,10 @@ pub(crate) async fn install(
             remote: false,
             both: true, // Prefer cache but use remote if needed
             bulk_threshold: None,
-            clear_sources: false,
-            source: source.map(String::from),
+            sources: source.map_or_else(
+                || vec![lode::remote_sources::default_source()],
+                |url| vec![url.to_string()],
+            ),
             http_proxy: None,
             verbose: true,
             quiet: false,
@@ -191,6 +193,7 @@ pub(crate) async fn install(
             backtrace: false,
             debug: false,
             norc: false,
+            yes: false,
         };
 
         // Actually install the gem
@@ -246,6 +249,30 @@ pub(crate) async fn install(
     Ok(())
 }
 
+/// Install the Gemfile's `plugin` declarations that aren't installed yet
+pub(crate) async fn install_declared(plugins: &[lode::PluginDeclaration]) -> Result<()> {
+    let index = PluginIndex::load()?;
+
+    for plugin in plugins {
+        if index.plugins.contains_key(&plugin.name) {
+            continue;
+        }
+        install(
+            &plugin.name,
+            plugin.source.as_deref(),
+            plugin.version.as_deref(),
+            plugin.git.as_deref(),
+            plugin.branch.as_deref(),
+            plugin.ref_.as_deref(),
+            plugin.path.as_deref(),
+        )
+        .await
+        .with_context(|| format!("Failed to install plugin {} from Gemfile", plugin.name))?;
+    }
+
+    Ok(())
+}
+
 /// Uninstall a plugin
 pub(crate) fn uninstall(plugin: Option<&str>, all: bool) -> Result<()> {
     let mut index = PluginIndex::load()?;
@@ -275,12 +302,15 @@ pub(crate) fn uninstall(plugin: Option<&str>, all: bool) -> Result<()> {
     Ok(())
 }
 
-/// List installed plugins
+/// List installed plugins and the available `source` types
 pub(crate) fn list() -> Result<()> {
     let index = PluginIndex::load()?;
+    let providers = lode::SourceProviders::builtin(&lode::config::cache_dir(None)?);
+    let source_types = providers.names().join(", ");
 
     if index.plugins.is_empty() {
         println!("No plugins installed");
+        println!("Source types: {source_types}");
         return Ok(());
     }
 
@@ -299,6 +329,7 @@ pub(crate) fn list() -> Result<()> {
             println!("  {} ({})", plugin.name, plugin.version);
         }
     }
+    println!("Source types: {source_types}");
 
     Ok(())
 }
@@ -67,6 +67,7 @@ pub(crate) fn run(
     );
 
     println!("Restoring {} gems...", gems_to_restore.len());
+    let collisions = lode::install::CaseCollisions::from_config(cfg.as_ref());
 
     // Create progress bar
     let pb = ProgressBar::new(gems_to_restore.len() as u64);
@@ -81,7 +82,7 @@ pub(crate) fn run(
     let results: Vec<_> = gems_to_restore
         .par_iter()
         .map(|gem_spec| {
-            let result = restore_gem(gem_spec, &cache_dir, &vendor_dir, &ruby_version);
+            let result = restore_gem(gem_spec, &cache_dir, &vendor_dir, &ruby_version, collisions);
             pb.inc(1);
             (gem_spec, result)
         })
@@ -129,6 +130,7 @@ fn restore_gem(
     cache_dir: &std::path::Path,
     vendor_dir: &std::path::Path,
     ruby_version: &str,
+    collisions: lode::install::CaseCollisions,
 ) -> Result<()> {
     // Build paths
     let cache_path = cache_dir.join(format!("{}.gem", gem_spec.full_name()));
@@ -162,7 +164,7 @@ fn restore_gem(
     }
 
     // Reinstall from cache
-    lode::install::install_gem(gem_spec, &cache_path, vendor_dir, ruby_version)?;
+    lode::install::install_gem(gem_spec, &cache_path, vendor_dir, ruby_version, collisions)?;
 
     Ok(())
 }
@@ -0,0 +1,64 @@
+//! Profile command
+//!
+//! Show the group profiles `lode install --group-profile` can apply
+
+use anyhow::{Context, Result};
+use lode::Config;
+use lode::config::GroupProfile;
+
+/// List group profiles, or show the one called `name`
+pub(crate) fn run(name: Option<&str>) -> Result<()> {
+    let config = Config::load().context("Failed to load configuration")?;
+
+    if let Some(name) = name {
+        let profile = lode::config::group_profile(Some(&config), name)?;
+        println!("{}", describe(name, &profile));
+        return Ok(());
+    }
+
+    if config.profiles.is_empty() {
+        println!("No group profiles defined");
+        println!("Define one with: lode config profile.ci.without development,test");
+        return Ok(());
+    }
+    println!("Group profiles:");
+    for (name, profile) in &config.profiles {
+        println!("  {}", describe(name, profile));
+    }
+    Ok(())
+}
+
+/// One-line summary, like `ci: without development, test`
+fn describe(name: &str, profile: &GroupProfile) -> String {
+    let parts: Vec<String> = ["with", "without"]
+        .into_iter()
+        .filter(|field| !profile.groups(field).is_empty())
+        .map(|field| format!("{field} {}", profile.groups(field).join(", ")))
+        .collect();
+    if parts.is_empty() {
+        format!("{name}: all default groups")
+    } else {
+        format!("{name}: {}", parts.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_lists_with_and_without_groups() {
+        let profile = GroupProfile {
+            with: vec!["development".to_string(), "test".to_string()],
+            without: vec!["docs".to_string()],
+        };
+        assert_eq!(
+            describe("full", &profile),
+            "full: with development, test; without docs"
+        );
+        assert_eq!(
+            describe("plain", &GroupProfile::default()),
+            "plain: all default groups"
+        );
+    }
+}
@@ -16,13 +16,14 @@ use lode::GemfileWriter;
 /// lode remove minitest
 /// lode remove rspec webmock
 /// ```
-pub(crate) async fn run(gem_names: &[String], quiet: bool) -> Result<()> {
+pub(crate) async fn run(gem_names: &[String], incremental: bool, quiet: bool) -> Result<()> {
+    super::install::refuse_in_deployment("remove")?;
     if gem_names.is_empty() {
         anyhow::bail!("No gems specified. Usage: lode remove GEM [GEM ...]");
     }
 
     // Default behavior: always run lock and clean, never run install
-    run_with_gemfile(gem_names, None, false, true, true, quiet).await
+    run_with_gemfile(gem_names, None, false, true, true, incremental, quiet).await
 }
 
 #[allow(clippy::fn_params_excessive_bools)]
@@ -32,6 +33,7 @@ async fn run_with_gemfile(
     run_install: bool,
     run_lock: bool,
     run_clean: bool,
+    incremental: bool,
     quiet: bool,
 ) -> Result<()> {
     let gemfile_path = gemfile_path.map_or_else(lode::find_gemfile, std::path::PathBuf::from);
@@ -111,17 +113,21 @@ async fn run_with_gemfile(
         }
         crate::commands::lock::run(
             gemfile_path.to_str().unwrap_or("Gemfile"),
-            None,  // lockfile_path
-            &[],   // add_platforms
-            &[],   // remove_platforms
-            &[],   // update_gems
-            false, // print
-            false, // verbose
-            false, // patch
-            false, // minor
-            false, // major
-            false, // strict
-            false, // conservative
+            None,                               // lockfile_path
+            &[],                                // add_platforms
+            &[],                                // remove_platforms
+            &[],                                // update_gems
+            false,                              // release_hold
+            &std::collections::BTreeMap::new(), // constraints
+            lode::ResolutionStrategy::Latest,   // strategy
+            false,                              // print
+            false,                              // verbose
+            false,                              // patch
+            false,                              // minor
+            false,                              // major
+            false,                              // strict
+            false,                              // conservative
+            incremental,
             false, // local
             false, // pre
             None,  // bundler
@@ -129,6 +135,7 @@ async fn run_with_gemfile(
             false, // add_checksums
             false, // full_index
             quiet, // quiet
+            &lode::Profiler::default(),
         )
         .await?;
         if !quiet {
@@ -159,6 +166,7 @@ async fn run_with_gemfile(
         let lockfile_str = lockfile_path.to_str().unwrap_or("Gemfile.lock");
         crate::commands::install::run(crate::commands::install::InstallOptions {
             lockfile_path: lockfile_str,
+            gemfile: None,
             redownload: false,
             verbose: false,
             quiet,
@@ -171,10 +179,19 @@ async fn run_with_gemfile(
             trust_policy: None,
             full_index: false,
             target_rbconfig: None,
+            arch: None,
+            path: None,
             frozen: false,
+            deployment: false,
             without_groups: vec![],
             with_groups: vec![],
+            only_groups: vec![],
+            group_profile: None,
             auto_clean: false,
+            link_path_gems: false,
+            profiler: lode::Profiler::default(),
+            dry_run: false,
+            resume: false,
         })
         .await?;
         if !quiet {
@@ -204,6 +221,7 @@ mod tests {
             false,
             false,
             false,
+            true,
             false,
         )
         .await;
@@ -223,6 +241,7 @@ mod tests {
             false,
             false,
             false,
+            true,
             false,
         )
         .await;
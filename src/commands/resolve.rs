@@ -0,0 +1,176 @@
+//! Resolve command
+//!
+//! Resolve a Gemfile without writing a lockfile and print the resolved set,
+//! optionally with extra requirements layered on top. Tooling can ask "what
+//! would lock if rails were `>= 7.1`?" without touching the project:
+//!
+//! ```text
+//! lode resolve --add 'rails >= 7.1' --json
+//! cat Gemfile | lode resolve -
+//! ```
+
+use anyhow::{Context, Result};
+use lode::platform::detect_current_platform;
+use lode::{GemDependency, Gemfile, ResolvedGem, Resolver, RubyGemsClient};
+use std::io::Read;
+use std::sync::Arc;
+
+/// Resolve `gemfile_path` (`-` for stdin) with `additions` and print the result
+pub(crate) async fn run(
+    gemfile_path: Option<&str>,
+    additions: &[String],
+    platforms: &[String],
+    pre: bool,
+    local: bool,
+    json: bool,
+) -> Result<()> {
+    let mut gemfile = match gemfile_path {
+        Some("-") => {
+            let mut content = String::new();
+            std::io::stdin()
+                .read_to_string(&mut content)
+                .context("Failed to read Gemfile from stdin")?;
+            Gemfile::parse(&content).context("Failed to parse Gemfile from stdin")?
+        }
+        Some(path) => Gemfile::parse_file(path)
+            .with_context(|| format!("Failed to parse Gemfile at {path}"))?,
+        None => {
+            let path = lode::paths::find_gemfile();
+            Gemfile::parse_file(&path)
+                .with_context(|| format!("Failed to parse Gemfile at {}", path.display()))?
+        }
+    };
+
+    for addition in additions {
+        let (name, requirement) = parse_addition(addition)?;
+        add_requirement(&mut gemfile, name, requirement);
+    }
+
+    let mut platforms = if platforms.is_empty() {
+        vec![detect_current_platform()]
+    } else {
+        platforms.to_vec()
+    };
+    platforms.sort();
+    platforms.dedup();
+
+    let config = lode::Config::load().unwrap_or_default();
+    let gem_source = lode::env_vars::gem_source().unwrap_or_else(|| gemfile.source.clone());
+    let client = RubyGemsClient::new(&gem_source)
+        .context("Failed to create RubyGems API client")?
+        .with_cache_only(local)
+        .with_prerelease(pre);
+    let source_routes = Arc::new(
+        lode::SourceRoutes::from_gemfile(
+            &gemfile,
+            &lode::SourceProviders::builtin(&lode::config::cache_dir(Some(&config))?),
+        )
+        .context("Failed to set up gem sources")?,
+    );
+
+    let interpreter = lode::detect_interpreter_versions();
+    let target_ruby = gemfile
+        .ruby_version
+        .as_deref()
+        .filter(|v| v.starts_with(|c: char| c.is_ascii_digit()))
+        .map(lode::ruby::parse_ruby_version_string)
+        .or_else(|| interpreter.as_ref().map(|(ruby, _)| ruby.clone()));
+
+    let resolver = Resolver::new(client)
+        .with_ruby_version(target_ruby)
+        .with_rubygems_version(interpreter.map(|(_, rubygems)| rubygems))
+        .with_source_routes(source_routes)
+        .with_ruby_platform(lode::config::ruby_platform_gems(Some(&config)));
+    let platform_refs: Vec<&str> = platforms.iter().map(String::as_str).collect();
+    let resolved = resolver.resolve(&gemfile, &platform_refs, pre).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&resolved)?);
+    } else {
+        print!("{}", format_text(&resolved));
+    }
+    Ok(())
+}
+
+/// Split `--add` input like `rails >= 7.1` into name and requirement
+fn parse_addition(addition: &str) -> Result<(&str, &str)> {
+    let addition = addition.trim();
+    let (name, requirement) = addition
+        .split_once(char::is_whitespace)
+        .map_or((addition, ""), |(name, rest)| (name, rest.trim()));
+    let name = name.trim_end_matches(',');
+    if name.is_empty() {
+        anyhow::bail!("Expected `GEM [REQUIREMENT]` for --add, got {addition:?}");
+    }
+    Ok((name, requirement))
+}
+
+/// Require `requirement` of `name`, on top of any Gemfile requirement
+fn add_requirement(gemfile: &mut Gemfile, name: &str, requirement: &str) {
+    if let Some(gem) = gemfile.gems.iter_mut().find(|gem| gem.name == name) {
+        if gem.version_requirement.is_empty() {
+            gem.version_requirement = requirement.to_string();
+        } else if !requirement.is_empty() {
+            gem.version_requirement = format!("{}, {requirement}", gem.version_requirement);
+        }
+    } else {
+        let mut gem = GemDependency::new(name);
+        gem.version_requirement = requirement.to_string();
+        gemfile.gems.push(gem);
+    }
+}
+
+/// One `name (version)` line per gem, with the platform when not `ruby`
+fn format_text(resolved: &[ResolvedGem]) -> String {
+    resolved
+        .iter()
+        .map(|gem| {
+            if gem.platform.is_empty() || gem.platform == "ruby" {
+                format!("{} ({})\n", gem.name, gem.version)
+            } else {
+                format!("{} ({}-{})\n", gem.name, gem.version, gem.platform)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_addition_splits_name_and_requirement() {
+        assert_eq!(parse_addition("rails >= 7.1").unwrap(), ("rails", ">= 7.1"));
+        assert_eq!(
+            parse_addition("  rack, ~> 3.0 ").unwrap(),
+            ("rack", "~> 3.0")
+        );
+        assert_eq!(parse_addition("puma").unwrap(), ("puma", ""));
+        assert!(parse_addition("  ").is_err());
+    }
+
+    #[test]
+    fn add_requirement_combines_with_gemfile_requirements() {
+        let mut gemfile = Gemfile::parse(
+            "source \"https://rubygems.org\"
+gem \"rails\", \"~> 7.0\"
+gem \"rack\"
+",
+        )
+        .unwrap();
+
+        add_requirement(&mut gemfile, "rails", ">= 7.1");
+        add_requirement(&mut gemfile, "rack", "< 3");
+        add_requirement(&mut gemfile, "puma", "");
+
+        let requirements: Vec<_> = gemfile
+            .gems
+            .iter()
+            .map(|gem| (gem.name.as_str(), gem.version_requirement.as_str()))
+            .collect();
+        assert_eq!(
+            requirements,
+            vec![("rails", "~> 7.0, >= 7.1"), ("rack", "< 3"), ("puma", "")]
+        );
+    }
+}
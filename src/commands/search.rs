@@ -3,6 +3,7 @@
 //! Search for gems
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::Deserialize;
 
 /// Search result from RubyGems.org API
@@ -13,6 +14,10 @@ struct SearchResult {
     downloads: u64,
     version: String,
     #[serde(default)]
+    version_downloads: u64,
+    #[serde(default)]
+    version_created_at: Option<String>,
+    #[serde(default)]
     info: String,
 }
 
@@ -51,7 +56,7 @@ pub(crate) async fn run(query: &str) -> Result<()> {
     }
 
     // Sort by downloads (descending) to show most popular first
-    results.sort_by(|a, b| b.downloads.cmp(&a.downloads));
+    results.sort_by_key(|r| std::cmp::Reverse(r.downloads));
 
     // Limit results
     let display_count = results.len().min(limit);
@@ -59,13 +64,15 @@ pub(crate) async fn run(query: &str) -> Result<()> {
 
     println!("Gems matching '{query}' ({display_count} results):\n");
 
+    let now = Utc::now();
+
     for result in &results {
         println!("{} ({})", result.name, result.version);
 
         if !result.info.is_empty() {
             // Truncate long descriptions
-            let info = if result.info.len() > 100 {
-                format!("{}...", &result.info[..97])
+            let info = if result.info.chars().count() > 100 {
+                format!("{}...", result.info.chars().take(97).collect::<String>())
             } else {
                 result.info.clone()
             };
@@ -73,7 +80,20 @@ pub(crate) async fn run(query: &str) -> Result<()> {
         }
 
         if result.downloads > 0 {
-            println!("   {} downloads", format_downloads(result.downloads));
+            println!(
+                "   {} downloads ({} for {})",
+                format_downloads(result.downloads),
+                format_downloads(result.version_downloads),
+                result.version
+            );
+        }
+
+        if let Some(released) = result
+            .version_created_at
+            .as_deref()
+            .and_then(|created_at| format_release(created_at, now))
+        {
+            println!("   Released {released}");
         }
 
         println!();
@@ -92,7 +112,7 @@ pub(crate) async fn run(query: &str) -> Result<()> {
 }
 
 /// Format download count with commas for readability
-fn format_downloads(count: u64) -> String {
+pub(crate) fn format_downloads(count: u64) -> String {
     let s = count.to_string();
     let mut result = String::new();
     let chars: Vec<char> = s.chars().rev().collect();
@@ -107,11 +127,41 @@ fn format_downloads(count: u64) -> String {
     result.chars().rev().collect()
 }
 
+/// Release date and how long ago it was, e.g. `2024-05-01 (120 days ago)`
+pub(crate) fn format_release(created_at: &str, now: DateTime<Utc>) -> Option<String> {
+    let date = created_at.get(..10)?;
+    let released = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    let age = match (now.date_naive() - released).num_days() {
+        ..=0 => "today".to_string(),
+        1 => "1 day ago".to_string(),
+        days => format!("{days} days ago"),
+    };
+    Some(format!("{date} ({age})"))
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, reason = "Tests can panic")]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_format_release() {
+        let now = "2026-10-17T12:00:00Z".parse().unwrap();
+        assert_eq!(
+            format_release("2026-06-19T08:30:00.000Z", now).as_deref(),
+            Some("2026-06-19 (120 days ago)")
+        );
+        assert_eq!(
+            format_release("2026-10-16T23:00:00Z", now).as_deref(),
+            Some("2026-10-16 (1 day ago)")
+        );
+        assert_eq!(
+            format_release("2026-10-17", now).as_deref(),
+            Some("2026-10-17 (today)")
+        );
+        assert_eq!(format_release("yesterday", now), None);
+    }
+
     #[test]
     fn test_format_downloads() {
         assert_eq!(format_downloads(0), "0");
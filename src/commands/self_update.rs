@@ -0,0 +1,311 @@
+//! Self-update command
+//!
+//! Replace the running lode binary with the newest release
+//!
+//! Downloads are checked against the SHA-256 checksum published with the
+//! same release. That catches a corrupt or truncated download, but not a
+//! tampered release: whoever can publish the binary (or serve the feed at
+//! `LODE_UPDATE_URL`) can publish a matching checksum too. No signature is
+//! verified, so the release feed and its host are trusted as a whole.
+
+use anyhow::{Context, Result};
+use lode::gem_utils::compare_versions;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Release feed checked unless `LODE_UPDATE_URL` points elsewhere
+const DEFAULT_UPDATE_URL: &str = "https://api.github.com/repos/havenwood/lode/releases";
+
+/// Which releases to follow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum Channel {
+    /// Tagged releases
+    Stable,
+    /// Prerelease builds
+    Nightly,
+}
+
+/// A release in the feed (GitHub releases JSON)
+#[derive(Debug, Clone, Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Check for a newer lode and install it over the current executable
+///
+/// The download must match a published SHA-256 checksum (`<asset>.sha256` or
+/// a `SHA256SUMS` file in the same release) before it replaces anything.
+/// This guards against broken downloads, not a compromised release; see the
+/// module documentation.
+pub(crate) async fn run(channel: Channel, check: bool) -> Result<()> {
+    let url = lode::env_vars::lode_update_url().unwrap_or_else(|| DEFAULT_UPDATE_URL.to_string());
+    let client = reqwest::Client::builder()
+        .user_agent(format!("lode/{}", env!("CARGO_PKG_VERSION")))
+        .build()
+        .context("Failed to create HTTP client")?;
+
+    let releases: Vec<Release> = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("Failed to fetch releases from {url}"))?
+        .json()
+        .await
+        .context("Failed to parse release feed")?;
+
+    let current = env!("CARGO_PKG_VERSION");
+    let Some(release) = select_release(&releases, channel, current) else {
+        println!("lode {current} is up to date");
+        return Ok(());
+    };
+
+    let asset_name = asset_name();
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == asset_name)
+        .with_context(|| format!("Release {} has no {asset_name} binary", release.tag_name))?;
+    let expected = expected_checksum(&client, release, &asset_name).await?;
+
+    if check {
+        println!(
+            "lode {} is available (current: {current})",
+            release.tag_name
+        );
+        return Ok(());
+    }
+
+    let exe = std::env::current_exe().context("Failed to locate the running lode binary")?;
+    let exe = fs::canonicalize(&exe).unwrap_or(exe);
+    if file_checksum(&exe).is_ok_and(|checksum| checksum == expected) {
+        println!("lode is already at {}", release.tag_name);
+        return Ok(());
+    }
+
+    println!("Downloading lode {}...", release.tag_name);
+    let bytes = client
+        .get(&asset.browser_download_url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("Failed to download {}", asset.browser_download_url))?
+        .bytes()
+        .await
+        .context("Failed to read downloaded binary")?;
+
+    let actual = format!("{:x}", Sha256::digest(&bytes));
+    if actual != expected {
+        return Err(lode::exit_code::ExitCode::Verification.error(format!(
+            "Checksum mismatch for {asset_name}: expected {expected}, got {actual}"
+        )));
+    }
+
+    replace_executable(&exe, &bytes)?;
+    println!("Updated lode {current} -> {}", release.tag_name);
+    Ok(())
+}
+
+/// The release to install, if it is newer than `current`
+///
+/// Stable picks the highest non-prerelease version. Nightly picks the most
+/// recent prerelease, which the feed lists first; whether it differs from
+/// the running build is decided later by checksum.
+fn select_release<'a>(
+    releases: &'a [Release],
+    channel: Channel,
+    current: &str,
+) -> Option<&'a Release> {
+    let published = releases.iter().filter(|release| !release.draft);
+    match channel {
+        Channel::Stable => published
+            .filter(|release| !release.prerelease)
+            .max_by(|a, b| compare_versions(version_of(a), version_of(b)))
+            .filter(|release| compare_versions(version_of(release), current).is_gt()),
+        Channel::Nightly => published.into_iter().find(|release| release.prerelease),
+    }
+}
+
+fn version_of(release: &Release) -> &str {
+    release.tag_name.trim_start_matches('v')
+}
+
+/// Binary name published for this platform (e.g. `lode-x86_64-linux`)
+fn asset_name() -> String {
+    let suffix = if cfg!(windows) { ".exe" } else { "" };
+    format!(
+        "lode-{}-{}{suffix}",
+        std::env::consts::ARCH,
+        std::env::consts::OS
+    )
+}
+
+/// Published SHA-256 of `asset_name` in `release`
+async fn expected_checksum(
+    client: &reqwest::Client,
+    release: &Release,
+    asset_name: &str,
+) -> Result<String> {
+    let sidecar = format!("{asset_name}.sha256");
+    let checksums = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == sidecar)
+        .or_else(|| {
+            release
+                .assets
+                .iter()
+                .find(|asset| asset.name == "SHA256SUMS")
+        })
+        .with_context(|| {
+            format!(
+                "Release {} publishes no checksum for {asset_name}; refusing to install it",
+                release.tag_name
+            )
+        })?;
+
+    let text = client
+        .get(&checksums.browser_download_url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .with_context(|| format!("Failed to download {}", checksums.name))?
+        .text()
+        .await?;
+    parse_checksum(&text, asset_name)
+        .with_context(|| format!("No checksum for {asset_name} in {}", checksums.name))
+}
+
+/// Checksum for `asset_name` from a `sha256sum`-style listing
+///
+/// Accepts both a bare digest and `<digest>  <file>` lines.
+fn parse_checksum(text: &str, asset_name: &str) -> Option<String> {
+    text.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let digest = fields.next()?;
+        let file = fields.next().map(|file| file.trim_start_matches('*'));
+        let valid = digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit());
+        (valid && file.is_none_or(|file| file == asset_name)).then(|| digest.to_ascii_lowercase())
+    })
+}
+
+fn file_checksum(path: &Path) -> Result<String> {
+    let bytes = fs::read(path)?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// Swap in the new binary with a rename, so the executable is never
+/// half-written
+fn replace_executable(exe: &Path, bytes: &[u8]) -> Result<()> {
+    let dir = exe
+        .parent()
+        .context("The lode binary has no parent directory")?;
+    let mut staged = tempfile::NamedTempFile::new_in(dir)
+        .with_context(|| format!("Cannot write to {}", dir.display()))?;
+    staged.write_all(bytes)?;
+    staged.as_file().sync_all()?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(staged.path(), fs::Permissions::from_mode(0o755))?;
+    }
+
+    // Windows cannot replace a running executable, but can rename it away
+    #[cfg(windows)]
+    let old = {
+        let old = exe.with_extension("old.exe");
+        fs::remove_file(&old).ok();
+        fs::rename(exe, &old).context("Failed to move the running lode binary aside")?;
+        old
+    };
+
+    let persisted = staged.persist(exe);
+    // Put the old binary back rather than leave no lode at all
+    #[cfg(windows)]
+    if persisted.is_err()
+        && let Err(e) = fs::rename(&old, exe)
+    {
+        lode::warn!(
+            "could not restore {} from {} ({e})",
+            exe.display(),
+            old.display()
+        );
+    }
+    persisted.with_context(|| format!("Failed to replace {}", exe.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str, prerelease: bool) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            prerelease,
+            draft: false,
+            assets: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn stable_channel_picks_newest_release() {
+        let releases = [
+            release("nightly-2026-10-01", true),
+            release("v0.3.0", false),
+            release("v0.10.0", false),
+            release("v0.2.0", false),
+        ];
+
+        let newest = select_release(&releases, Channel::Stable, "0.3.0").expect("update");
+        assert_eq!(newest.tag_name, "v0.10.0");
+        assert!(select_release(&releases, Channel::Stable, "0.10.0").is_none());
+
+        let nightly = select_release(&releases, Channel::Nightly, "0.3.0").expect("nightly");
+        assert_eq!(nightly.tag_name, "nightly-2026-10-01");
+    }
+
+    #[test]
+    fn checksum_from_sidecar_or_listing() {
+        let digest = "a".repeat(64);
+        assert_eq!(
+            parse_checksum(&format!("{digest}\n"), "lode-x86_64-linux"),
+            Some(digest.clone())
+        );
+
+        let listing = format!(
+            "{}  lode-aarch64-macos\n{digest} *lode-x86_64-linux\n",
+            "b".repeat(64)
+        );
+        assert_eq!(parse_checksum(&listing, "lode-x86_64-linux"), Some(digest));
+        assert_eq!(parse_checksum(&listing, "lode-x86_64-windows.exe"), None);
+        assert_eq!(parse_checksum("not a checksum", "lode-x86_64-linux"), None);
+    }
+
+    #[test]
+    fn replace_executable_swaps_file() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let exe = temp.path().join("lode");
+        fs::write(&exe, "old").expect("write exe");
+
+        replace_executable(&exe, b"new").expect("replace");
+
+        assert_eq!(fs::read(&exe).expect("read exe"), b"new");
+    }
+}
@@ -0,0 +1,88 @@
+//! Shell command
+//!
+//! Spawn a subshell with the bundle environment activated
+
+use anyhow::{Context, Result};
+use std::env;
+use std::process::Command;
+
+/// Set inside `lode shell` to the project directory that was activated
+const LODE_SHELL_VAR: &str = "LODE_SHELL";
+
+/// Spawn `$SHELL` with the bundle environment, or print it for `eval`
+///
+/// The subshell gets the same `GEM_HOME`, `GEM_PATH`, `PATH` and `RUBYLIB`
+/// as `lode exec`, with the `BUNDLER_ORIG_*` record of their previous values,
+/// plus `LODE_SHELL` and a `(lode)` prompt prefix. Shell
+/// rc files that assign `PS1` replace the prefix; prompts can check
+/// `$LODE_SHELL` instead.
+pub(crate) fn run(lockfile_path: &str, print_env: bool) -> Result<()> {
+    let mut vars = super::exec::bundle_env(lockfile_path, &[], false, false)?;
+    let originals = lode::env_preserver::originals(&vars, |name| env::var(name).ok());
+    vars.extend(originals);
+    let project = env::current_dir()
+        .context("Failed to get current directory")?
+        .display()
+        .to_string();
+    vars.push((LODE_SHELL_VAR, project));
+
+    if print_env {
+        print!("{}", export_script(&vars));
+        return Ok(());
+    }
+
+    if env::var_os(LODE_SHELL_VAR).is_some() {
+        anyhow::bail!("Already inside a lode shell; type `exit` to leave it first");
+    }
+
+    let prompt = format!(
+        "(lode) {}",
+        env::var("PS1").unwrap_or_else(|_| String::from("$ "))
+    );
+    vars.push(("PS1", prompt));
+
+    let shell = env::var("SHELL").unwrap_or_else(|_| String::from("/bin/sh"));
+    println!("Spawning {shell} with the bundle environment (type `exit` to leave)");
+
+    let status = Command::new(&shell)
+        .envs(vars)
+        .status()
+        .with_context(|| format!("Failed to spawn shell: {shell}"))?;
+
+    if !status.success() {
+        std::process::exit(lode::exit_code::child_exit_code(status));
+    }
+
+    Ok(())
+}
+
+/// POSIX `export` statements suitable for `eval "$(lode shell --print-env)"`
+fn export_script(vars: &[(&str, String)]) -> String {
+    let mut script = String::new();
+    for (name, value) in vars {
+        script.push_str("export ");
+        script.push_str(name);
+        script.push_str("='");
+        script.push_str(&value.replace('\'', r"'\''"));
+        script.push_str("'\n");
+    }
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_script_quotes_values() {
+        let vars = [
+            ("GEM_HOME", String::from("/srv/app/vendor/ruby/3.3.0")),
+            ("LODE_SHELL", String::from("/home/me/it's")),
+        ];
+
+        assert_eq!(
+            export_script(&vars),
+            "export GEM_HOME='/srv/app/vendor/ruby/3.3.0'\nexport LODE_SHELL='/home/me/it'\\''s'\n"
+        );
+    }
+}
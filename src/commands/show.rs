@@ -3,11 +3,22 @@
 //! Show installed gems and their locations
 
 use anyhow::{Context, Result};
-use lode::{Config, config, lockfile::Lockfile};
+use lode::lockfile::{GemSpec, GitGemSpec, Lockfile};
+use lode::trust_policy::{GemVerifier, TrustPolicy, VerificationError};
+use lode::{Config, Gemfile, config};
 use std::fs;
+use std::path::Path;
 
 /// Show the source location of a gem
-pub(crate) fn run(gem_name: Option<&str>, paths: bool, lockfile_path: &str) -> Result<()> {
+///
+/// With `verify`, print where the gem came from and whether what is
+/// installed still matches the lockfile.
+pub(crate) fn run(
+    gem_name: Option<&str>,
+    paths: bool,
+    verify: bool,
+    lockfile_path: &str,
+) -> Result<()> {
     // Read and parse lockfile
     let content = fs::read_to_string(lockfile_path)
         .with_context(|| format!("Failed to read lockfile: {lockfile_path}"))?;
@@ -24,6 +35,11 @@ pub(crate) fn run(gem_name: Option<&str>, paths: bool, lockfile_path: &str) -> R
 
     let gems_dir = vendor_dir.join("ruby").join(&ruby_version).join("gems");
 
+    if verify {
+        let gem_name = gem_name.context("--verify needs a gem name")?;
+        return verify_gem(&lockfile, gem_name, &gems_dir, &cfg);
+    }
+
     // If --paths flag is set, list all gem paths (sorted by name)
     if paths {
         let mut all_gems = Vec::new();
@@ -125,7 +141,10 @@ pub(crate) fn run(gem_name: Option<&str>, paths: bool, lockfile_path: &str) -> R
         );
     }
 
-    // Gem not found in any collection
+    not_found(&lockfile, gem_name)
+}
+
+fn not_found(lockfile: &Lockfile, gem_name: &str) -> Result<()> {
     anyhow::bail!(
         "Gem '{}' not found in lockfile. Available gems:\n{}",
         gem_name,
@@ -137,3 +156,166 @@ pub(crate) fn run(gem_name: Option<&str>, paths: bool, lockfile_path: &str) -> R
             .join("\n")
     );
 }
+
+/// Print the provenance of `gem_name`: its source, the lockfile checksum
+/// against the cached `.gem`, a digest of the installed files, the
+/// signature status and, for git gems, the locked and checked out revisions
+///
+/// Fails when the `.gem` or git checkout doesn't match the lockfile.
+fn verify_gem(lockfile: &Lockfile, gem_name: &str, gems_dir: &Path, cfg: &Config) -> Result<()> {
+    let mismatches = if let Some(gem) = lockfile.gems.iter().find(|gem| gem.name == gem_name) {
+        verify_registry_gem(lockfile, gem, gems_dir, cfg)?
+    } else if let Some(gem) = lockfile.git_gems.iter().find(|gem| gem.name == gem_name) {
+        verify_git_gem(gem, gems_dir, cfg)?
+    } else if let Some(gem) = lockfile.path_gems.iter().find(|gem| gem.name == gem_name) {
+        let gem_dir = gems_dir.join(format!("{}-{}", gem.name, gem.version));
+        println!("{} ({})", gem.name, gem.version);
+        print_field("Path", &installed_path(&gem_dir));
+        print_field("Source", &format!("path {}", gem.path));
+        print_field("Signature", "n/a (path source)");
+        print_field("Installed", &tree_digest(&gem_dir));
+        Vec::new()
+    } else {
+        return not_found(lockfile, gem_name);
+    };
+
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+    anyhow::bail!(
+        "Provenance check failed for {gem_name}:\n  {}",
+        mismatches.join("\n  ")
+    )
+}
+
+/// Provenance of a gem from a gem server, returning any mismatches
+fn verify_registry_gem(
+    lockfile: &Lockfile,
+    gem: &GemSpec,
+    gems_dir: &Path,
+    cfg: &Config,
+) -> Result<Vec<String>> {
+    let gem_dir = gems_dir.join(gem.full_name());
+    println!("{} ({})", gem.name, gem.version);
+    print_field("Path", &installed_path(&gem_dir));
+
+    let source = lockfile
+        .plugin_sources
+        .iter()
+        .find(|source| source.gems.contains(&gem.name))
+        .map(|source| format!("{} ({})", source.remote, source.kind))
+        .or_else(|| {
+            Gemfile::parse_file(lode::paths::find_gemfile())
+                .ok()
+                .map(|gemfile| lode::source_audit::resolved_source(&gemfile, &gem.name).to_string())
+        })
+        .unwrap_or_else(|| lode::DEFAULT_GEM_SOURCE.to_string());
+    print_field("Source", &source);
+    print_field(
+        "Lockfile",
+        &gem.checksum
+            .as_ref()
+            .map_or_else(|| "no checksum".to_string(), |sum| format!("sha256={sum}")),
+    );
+
+    let mut mismatches = Vec::new();
+    let package =
+        config::cache_dir(Some(cfg))?.join(format!("{}.gem", gem.full_name_with_platform()));
+    if package.is_file() {
+        let digest = lode::digest_cache::sha256_file(&package)
+            .with_context(|| format!("Failed to hash {}", package.display()))?;
+        let verdict = match &gem.checksum {
+            Some(locked) if locked.eq_ignore_ascii_case(&digest) => " (matches lockfile)",
+            Some(_) => {
+                mismatches.push(format!(
+                    "{} does not match the lockfile checksum",
+                    package.display()
+                ));
+                " (DOES NOT MATCH lockfile)"
+            }
+            None => "",
+        };
+        print_field("Package", &format!("sha256={digest}{verdict}"));
+        print_field("Signature", &signature_status(&package));
+    } else {
+        print_field("Package", "not in the download cache");
+        print_field("Signature", "unknown (no cached .gem)");
+    }
+    print_field("Installed", &tree_digest(&gem_dir));
+    Ok(mismatches)
+}
+
+/// Provenance of a gem from a git repository, returning any mismatches
+fn verify_git_gem(gem: &GitGemSpec, gems_dir: &Path, cfg: &Config) -> Result<Vec<String>> {
+    let gem_dir = gems_dir.join(format!("{}-{}", gem.name, gem.version));
+    println!("{} ({})", gem.name, gem.version);
+    print_field("Path", &installed_path(&gem_dir));
+    let reference = gem
+        .branch
+        .as_ref()
+        .map(|branch| format!(" (branch {branch})"))
+        .or_else(|| gem.tag.as_ref().map(|tag| format!(" (tag {tag})")))
+        .unwrap_or_default();
+    print_field("Source", &format!("git {}{reference}", gem.repository));
+    print_field("Revision", &gem.revision);
+
+    let mut mismatches = Vec::new();
+    let checkout = lode::GitManager::new(config::cache_dir(Some(cfg))?.join("git"))
+        .ok()
+        .and_then(|git| git.checked_out_revision(&gem.repository));
+    match checkout {
+        Some(head) if head == gem.revision => {
+            print_field("Checkout", &format!("{head} (matches lockfile)"));
+        }
+        Some(head) => {
+            mismatches.push(format!(
+                "git checkout is at {head}, not the locked {}",
+                gem.revision
+            ));
+            print_field("Checkout", &format!("{head} (DOES NOT MATCH lockfile)"));
+        }
+        None => print_field("Checkout", "not in the git cache"),
+    }
+    print_field("Signature", "n/a (git source)");
+    print_field("Installed", &tree_digest(&gem_dir));
+    Ok(mismatches)
+}
+
+fn print_field(label: &str, value: &str) {
+    println!("  {:<11}{value}", format!("{label}:"));
+}
+
+fn installed_path(gem_dir: &Path) -> String {
+    if gem_dir.exists() {
+        gem_dir.display().to_string()
+    } else {
+        format!("{} (not installed)", gem_dir.display())
+    }
+}
+
+fn tree_digest(gem_dir: &Path) -> String {
+    if !gem_dir.exists() {
+        return "not installed".to_string();
+    }
+    match lode::digest_cache::sha256_tree(gem_dir) {
+        Ok((digest, 1)) => format!("sha256={digest} (1 file)"),
+        Ok((digest, files)) => format!("sha256={digest} ({files} files)"),
+        Err(e) => format!("unreadable: {e}"),
+    }
+}
+
+/// Signature status of a `.gem` against the certificates in `~/.gem/trust`
+fn signature_status(package: &Path) -> String {
+    let verifier = match GemVerifier::new(TrustPolicy::HighSecurity) {
+        Ok(verifier) => verifier,
+        Err(e) => return format!("unknown ({e})"),
+    };
+    match verifier.verify_gem(package) {
+        Ok(()) => "signed by a trusted certificate".to_string(),
+        Err(VerificationError::UnsignedGem { .. }) => "unsigned".to_string(),
+        Err(VerificationError::NoTrustedCertificate { .. }) => {
+            "signed, but not by a trusted certificate".to_string()
+        }
+        Err(e) => format!("invalid: {e}"),
+    }
+}
@@ -0,0 +1,368 @@
+//! Size command
+//!
+//! Report the disk space each installed gem takes, split into library code,
+//! native extensions, documentation and everything else, along with the
+//! Gemfile dependencies whose transitive trees weigh the most.
+
+use anyhow::{Context, Result};
+use lode::docs::DocLayout;
+use lode::{Config, Gemfile, Lockfile, config, human_bytes};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Bytes a gem occupies, by kind of content
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+struct Breakdown {
+    /// The gem's `lib` directory
+    lib: u64,
+    /// The gem's `ext` sources plus its built extensions
+    ext: u64,
+    /// Bundled `doc` directories plus generated `RDoc` and ri
+    docs: u64,
+    /// Everything else: tests, executables, READMEs
+    other: u64,
+}
+
+impl Breakdown {
+    const fn total(self) -> u64 {
+        self.lib + self.ext + self.docs + self.other
+    }
+}
+
+/// One installed gem
+#[derive(Debug, Serialize)]
+struct GemSize {
+    name: String,
+    version: String,
+    total: u64,
+    #[serde(flatten)]
+    breakdown: Breakdown,
+}
+
+/// A Gemfile dependency with everything it pulls in
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct Subtree {
+    name: String,
+    /// Installed bytes of the gem and its transitive dependencies
+    total: u64,
+    /// Number of installed gems in the tree
+    gems: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct SizeReport {
+    path: PathBuf,
+    total: u64,
+    gems: Vec<GemSize>,
+    subtrees: Vec<Subtree>,
+    not_installed: Vec<String>,
+}
+
+/// Report installed size per gem and per dependency tree
+///
+/// Text output shows the `top` heaviest gems and trees; `--json` includes
+/// all of them.
+pub(crate) fn run(lockfile_path: &str, json: bool, top: usize) -> Result<()> {
+    let content = fs::read_to_string(lockfile_path)
+        .with_context(|| format!("Failed to read lockfile: {lockfile_path}"))?;
+    let lockfile = Lockfile::parse(&content)
+        .with_context(|| format!("Failed to parse lockfile: {lockfile_path}"))?;
+
+    let cfg = Config::load().unwrap_or_default();
+    let ruby_version = config::ruby_version(lockfile.ruby_version.as_deref());
+    let ruby_dir = config::vendor_dir(Some(&cfg))?
+        .join("ruby")
+        .join(&ruby_version);
+    let gems_dir = ruby_dir.join("gems");
+    let extensions_dir = ruby_dir
+        .join("extensions")
+        .join(lode::detect_current_platform())
+        .join(&ruby_version);
+    let docs = DocLayout::for_gems_dir(&gems_dir);
+
+    let current_platform = lode::detect_current_platform();
+    let locked = lockfile
+        .gems
+        .iter()
+        .filter(|gem| lode::platform_matches(&gem.platform, &current_platform))
+        .map(|gem| (gem.name.clone(), gem.version.clone()))
+        .chain(
+            lockfile
+                .git_gems
+                .iter()
+                .map(|gem| (gem.name.clone(), gem.version.clone())),
+        )
+        .chain(
+            lockfile
+                .path_gems
+                .iter()
+                .map(|gem| (gem.name.clone(), gem.version.clone())),
+        );
+
+    let mut gems = Vec::new();
+    let mut not_installed = Vec::new();
+    for (name, version) in locked {
+        let full_name = format!("{name}-{version}");
+        let gem_dir = gems_dir.join(&full_name);
+        if !gem_dir.is_dir() {
+            not_installed.push(name);
+            continue;
+        }
+        let breakdown = measure(
+            &gem_dir,
+            &extensions_dir.join(&full_name),
+            &docs.dir(&full_name),
+        );
+        gems.push(GemSize {
+            name,
+            version,
+            total: breakdown.total(),
+            breakdown,
+        });
+    }
+    gems.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+
+    // Trees are rooted at the Gemfile's gems, or every gem without one
+    let roots: Vec<String> = Gemfile::parse_file(lode::paths::find_gemfile()).map_or_else(
+        |_| gems.iter().map(|gem| gem.name.clone()).collect(),
+        |gemfile| gemfile.gems.into_iter().map(|gem| gem.name).collect(),
+    );
+    let sizes: BTreeMap<&str, u64> = gems
+        .iter()
+        .map(|gem| (gem.name.as_str(), gem.total))
+        .collect();
+    let subtrees = heaviest_subtrees(&lockfile, &roots, &sizes);
+
+    let report = SizeReport {
+        path: gems_dir,
+        total: gems.iter().map(|gem| gem.total).sum(),
+        gems,
+        subtrees,
+        not_installed,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    print_report(&report, top);
+    Ok(())
+}
+
+/// Split an installed gem's footprint into lib, ext, docs and other
+fn measure(gem_dir: &Path, built_extensions: &Path, generated_docs: &Path) -> Breakdown {
+    let mut breakdown = Breakdown {
+        ext: dir_size(built_extensions),
+        docs: dir_size(generated_docs),
+        ..Breakdown::default()
+    };
+    for entry in fs::read_dir(gem_dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        let size = if path.is_dir() {
+            dir_size(&path)
+        } else {
+            entry.metadata().map_or(0, |metadata| metadata.len())
+        };
+        let bucket = match entry.file_name().to_str() {
+            Some("lib") => &mut breakdown.lib,
+            Some("ext") => &mut breakdown.ext,
+            Some("doc" | "docs" | "rdoc") => &mut breakdown.docs,
+            _ => &mut breakdown.other,
+        };
+        *bucket += size;
+    }
+    breakdown
+}
+
+/// Total bytes under `path`, zero when it does not exist
+fn dir_size(path: &Path) -> u64 {
+    lode::collect_stats(path).map_or(0, |stats| u64::try_from(stats.total_size).unwrap_or(0))
+}
+
+/// Installed size of each root gem plus its transitive dependencies, heaviest first
+///
+/// A dependency shared by two roots counts toward both.
+fn heaviest_subtrees(
+    lockfile: &Lockfile,
+    roots: &[String],
+    sizes: &BTreeMap<&str, u64>,
+) -> Vec<Subtree> {
+    let dependencies: BTreeMap<&str, Vec<&str>> = lockfile
+        .gems
+        .iter()
+        .map(|gem| {
+            (
+                gem.name.as_str(),
+                gem.dependencies
+                    .iter()
+                    .map(|dependency| dependency.name.as_str())
+                    .collect(),
+            )
+        })
+        .collect();
+
+    let mut subtrees: Vec<Subtree> = roots
+        .iter()
+        .filter(|root| sizes.contains_key(root.as_str()))
+        .map(|root| {
+            let mut members = BTreeSet::new();
+            let mut pending = vec![root.as_str()];
+            while let Some(name) = pending.pop() {
+                if members.insert(name) {
+                    pending.extend(dependencies.get(name).into_iter().flatten());
+                }
+            }
+            let installed: Vec<u64> = members
+                .iter()
+                .filter_map(|name| sizes.get(name).copied())
+                .collect();
+            Subtree {
+                name: root.clone(),
+                total: installed.iter().sum(),
+                gems: installed.len(),
+            }
+        })
+        .collect();
+    subtrees.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.name.cmp(&b.name)));
+    subtrees.dedup_by(|a, b| a.name == b.name);
+    subtrees
+}
+
+fn print_report(report: &SizeReport, top: usize) {
+    println!(
+        "Bundle size: {} in {} gems ({})",
+        bytes(report.total),
+        report.gems.len(),
+        report.path.display()
+    );
+    if report.gems.is_empty() {
+        return;
+    }
+
+    println!();
+    println!(
+        "{:<36} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "Gem", "Total", "lib", "ext", "docs", "other"
+    );
+    for gem in report.gems.iter().take(top) {
+        println!(
+            "{:<36} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            format!("{}-{}", gem.name, gem.version),
+            bytes(gem.total),
+            bytes(gem.breakdown.lib),
+            bytes(gem.breakdown.ext),
+            bytes(gem.breakdown.docs),
+            bytes(gem.breakdown.other)
+        );
+    }
+    if report.gems.len() > top {
+        println!("... and {} smaller gems", report.gems.len() - top);
+    }
+
+    if !report.subtrees.is_empty() {
+        println!();
+        println!("Heaviest dependency trees:");
+        for subtree in report.subtrees.iter().take(top) {
+            println!(
+                "  {:<34} {:>10} ({} gems)",
+                subtree.name,
+                bytes(subtree.total),
+                subtree.gems
+            );
+        }
+    }
+
+    if !report.not_installed.is_empty() {
+        println!();
+        println!(
+            "Not installed ({}): {}",
+            report.not_installed.len(),
+            report.not_installed.join(", ")
+        );
+    }
+}
+
+fn bytes(size: u64) -> String {
+    human_bytes(i64::try_from(size).unwrap_or(i64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measure_splits_gem_contents() {
+        let temp = tempfile::tempdir().unwrap();
+        let gem_dir = temp.path().join("gems/nokogiri-1.16.0");
+        for (file, size) in [
+            ("lib/nokogiri.rb", 100),
+            ("ext/nokogiri/xml.c", 40),
+            ("doc/guide.md", 7),
+            ("test/test_xml.rb", 3),
+            ("README.md", 2),
+        ] {
+            let path = gem_dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, vec![b'x'; size]).unwrap();
+        }
+        let built = temp.path().join("extensions/nokogiri-1.16.0");
+        fs::create_dir_all(&built).unwrap();
+        fs::write(built.join("nokogiri.so"), [0; 50]).unwrap();
+
+        let breakdown = measure(&gem_dir, &built, &temp.path().join("doc/missing"));
+        assert_eq!(
+            breakdown,
+            Breakdown {
+                lib: 100,
+                ext: 90,
+                docs: 7,
+                other: 5,
+            }
+        );
+        assert_eq!(breakdown.total(), 202);
+    }
+
+    #[test]
+    fn subtrees_sum_transitive_dependencies() {
+        let lockfile = Lockfile::parse(
+            "GEM
+  remote: https://rubygems.org/
+  specs:
+    actionpack (7.1.0)
+      rack (>= 2.2)
+    rack (3.1.7)
+    rails (7.1.0)
+      actionpack (= 7.1.0)
+    rake (13.0.6)
+
+PLATFORMS
+  ruby
+
+DEPENDENCIES
+  rails
+  rake
+",
+        )
+        .unwrap();
+        let sizes = BTreeMap::from([("actionpack", 30), ("rack", 10), ("rails", 5), ("rake", 20)]);
+        let roots = ["rake", "rails", "missing"].map(String::from);
+
+        assert_eq!(
+            heaviest_subtrees(&lockfile, &roots, &sizes),
+            vec![
+                Subtree {
+                    name: "rails".to_string(),
+                    total: 45,
+                    gems: 3,
+                },
+                Subtree {
+                    name: "rake".to_string(),
+                    total: 20,
+                    gems: 1,
+                },
+            ]
+        );
+    }
+}
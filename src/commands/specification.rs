@@ -3,7 +3,7 @@
 //! Display full gemspec details
 
 use anyhow::{Context, Result};
-use lode::{Lockfile, RubyGemsClient, gem_store::GemStore};
+use lode::{DependencyKind, Gemspec, Lockfile, RubyGemsClient, gem_store::GemStore};
 use std::fs;
 
 /// Display full gemspec details for a gem.
@@ -63,7 +63,10 @@ async fn run_with_lockfile(
         if let Ok(gems) = gem_store.list_gems() {
             for gem_info in gems {
                 if gem_info.name == gem_name && gem_info.version == gem_version {
-                    display_local_spec(&gem_info.name, &gem_info.version);
+                    match Gemspec::load_for_gem_dir(&gem_info.path) {
+                        Some(spec) => display_gemspec(&spec),
+                        None => display_local_spec(&gem_info.name, &gem_info.version),
+                    }
                     return Ok(());
                 }
             }
@@ -185,6 +188,81 @@ async fn run_with_lockfile(
     Ok(())
 }
 
+/// Display the full specification parsed from an installed gemspec
+fn display_gemspec(spec: &Gemspec) {
+    println!("--- !ruby/object:Gem::Specification");
+    println!("name: {}", spec.name);
+    println!("version: !ruby/object:Gem::Version");
+    println!("  version: {}", spec.version);
+    println!("platform: {}", spec.platform);
+
+    print_list("authors", &spec.authors);
+    println!("bindir: {}", spec.bindir);
+
+    for (label, kind) in [
+        ("dependencies", DependencyKind::Runtime),
+        ("development_dependencies", DependencyKind::Development),
+    ] {
+        let deps: Vec<_> = spec
+            .dependencies
+            .iter()
+            .filter(|d| d.kind == kind)
+            .collect();
+        if deps.is_empty() {
+            continue;
+        }
+        println!("{label}:");
+        for dep in deps {
+            println!("- !ruby/object:Gem::Dependency");
+            println!("  name: {}", dep.name);
+            println!("  requirement: \"{}\"", dep.requirement_string());
+        }
+    }
+
+    if let Some(description) = &spec.description {
+        println!("description: |");
+        for line in description.lines() {
+            println!("  {line}");
+        }
+    }
+
+    print_list("executables", &spec.executables);
+    print_list("extensions", &spec.extensions);
+    if let Some(homepage) = &spec.homepage {
+        println!("homepage: {homepage}");
+    }
+    print_list("licenses", &spec.licenses);
+
+    if !spec.metadata.is_empty() {
+        println!("metadata:");
+        for (key, value) in &spec.metadata {
+            println!("  {key}: {value}");
+        }
+    }
+
+    print_list("require_paths", &spec.require_paths);
+    if !spec.required_ruby_version.is_empty() {
+        println!(
+            "required_ruby_version: \"{}\"",
+            spec.required_ruby_version.join(", ")
+        );
+    }
+    if let Some(summary) = &spec.summary {
+        println!("summary: {summary}");
+    }
+}
+
+/// Print a YAML-style list field, skipping empty lists
+fn print_list(label: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    println!("{label}:");
+    for item in items {
+        println!("- {item}");
+    }
+}
+
 /// Display minimal specification for locally installed gem
 fn display_local_spec(gem_name: &str, version: &str) {
     println!("--- !ruby/object:Gem::Specification");
@@ -274,6 +352,14 @@ mod tests {
         display_local_spec("rake", "13.0.0");
     }
 
+    #[test]
+    fn test_display_gemspec() {
+        let spec =
+            Gemspec::parse("name: rake\nversion: 13.0.0\nexecutables:\n- rake\nlicenses:\n- MIT\n")
+                .unwrap();
+        display_gemspec(&spec);
+    }
+
     #[tokio::test]
     async fn test_specification_lockfile_with_multiple_gems() {
         let temp = TempDir::new().unwrap();
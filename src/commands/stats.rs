@@ -0,0 +1,227 @@
+//! Stats command
+//!
+//! Report what Lode keeps in its cache directory and what it has measured
+//! over time: cache hit rates, the downloads the cache saved, and how long
+//! each gem's native extension takes to build on each machine.
+
+use anyhow::{Context, Result};
+use lode::build_history::{BuildHistory, BuildRecord};
+use lode::{CacheUsage, UsageCounters, collect_stats, config, human_bytes};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Files and bytes in one part of the cache
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+struct Usage {
+    files: u64,
+    bytes: u64,
+}
+
+/// Everything `lode stats` reports
+#[derive(Debug, Serialize)]
+struct Overview {
+    cache_dir: String,
+    /// The whole cache directory
+    total: Usage,
+    /// Downloaded `.gem` archives
+    gems: Usage,
+    /// Bare clones of git sources
+    git: Usage,
+    /// Cached dependency resolutions, one file per Gemfile and index state
+    resolutions: Usage,
+    /// The full gem index used for offline searches
+    full_index: Usage,
+    #[serde(flatten)]
+    counters: UsageCounters,
+    gem_hit_rate: Option<f64>,
+    resolution_hit_rate: Option<f64>,
+}
+
+/// Show cache sizes, hit rates and download savings
+pub(crate) fn overview(json: bool) -> Result<()> {
+    let cache_dir = config::cache_dir(None)?;
+    let counters = *CacheUsage::load(&cache_dir).counters();
+    let overview = Overview {
+        cache_dir: cache_dir.display().to_string(),
+        total: dir_usage(&cache_dir)?,
+        gems: gem_usage(&cache_dir)?,
+        git: dir_usage(&cache_dir.join("git"))?,
+        resolutions: dir_usage(&cache_dir.join("resolutions"))?,
+        full_index: file_usage(&lode::FullIndex::cache_path(&cache_dir)),
+        counters,
+        gem_hit_rate: counters.gem_hit_rate(),
+        resolution_hit_rate: counters.resolution_hit_rate(),
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&overview)?);
+        return Ok(());
+    }
+
+    println!("Cache: {}", overview.cache_dir);
+    println!("  {:<13}{}", "Total", sized(overview.total, "files"));
+    println!("  {:<13}{}", "Gems", sized(overview.gems, ".gem files"));
+    println!("  {:<13}{}", "Git sources", human(overview.git.bytes));
+    println!(
+        "  {:<13}{}",
+        "Resolutions",
+        sized(overview.resolutions, "cached")
+    );
+    println!("  {:<13}{}", "Full index", human(overview.full_index.bytes));
+
+    println!("\nHit rates:");
+    println!(
+        "  {:<13}{}",
+        "Gems",
+        rate(
+            overview.gem_hit_rate,
+            counters.gem_hits,
+            counters.gem_misses,
+            "installs"
+        )
+    );
+    println!(
+        "  {:<13}{}",
+        "Resolutions",
+        rate(
+            overview.resolution_hit_rate,
+            counters.resolution_hits,
+            counters.resolution_misses,
+            "lock runs"
+        )
+    );
+
+    println!(
+        "\nSaved by the cache: {} not downloaded again ({} downloaded)",
+        human(counters.bytes_reused),
+        human(counters.bytes_downloaded)
+    );
+
+    Ok(())
+}
+
+/// Every file under `dir`
+fn dir_usage(dir: &Path) -> Result<Usage> {
+    let stats = collect_stats(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    Ok(Usage {
+        files: u64::try_from(stats.files).unwrap_or(u64::MAX),
+        bytes: u64::try_from(stats.total_size).unwrap_or(0),
+    })
+}
+
+/// The `.gem` archives directly in the cache directory
+fn gem_usage(cache_dir: &Path) -> Result<Usage> {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return Ok(Usage::default());
+    };
+    let mut usage = Usage::default();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "gem") {
+            let file = file_usage(&path);
+            usage.files += file.files;
+            usage.bytes += file.bytes;
+        }
+    }
+    Ok(usage)
+}
+
+/// A single file, if it exists
+fn file_usage(path: &Path) -> Usage {
+    fs::metadata(path)
+        .ok()
+        .filter(fs::Metadata::is_file)
+        .map_or_else(Usage::default, |metadata| Usage {
+            files: 1,
+            bytes: metadata.len(),
+        })
+}
+
+fn human(bytes: u64) -> String {
+    human_bytes(i64::try_from(bytes).unwrap_or(i64::MAX))
+}
+
+fn sized(usage: Usage, noun: &str) -> String {
+    format!("{} in {} {noun}", human(usage.bytes), usage.files)
+}
+
+fn rate(rate: Option<f64>, hits: u64, misses: u64, noun: &str) -> String {
+    rate.map_or_else(
+        || format!("no {noun} recorded yet"),
+        |rate| {
+            format!(
+                "{:.1}% from cache ({hits} of {} {noun})",
+                rate * 100.0,
+                hits + misses
+            )
+        },
+    )
+}
+
+/// One row of `lode stats builds --json`
+#[derive(Debug, Serialize)]
+struct BuildStat<'a> {
+    #[serde(flatten)]
+    record: &'a BuildRecord,
+    average_ms: u64,
+}
+
+/// Show historical extension build durations, slowest first
+pub(crate) fn builds(json: bool) -> Result<()> {
+    let history = BuildHistory::load(&config::cache_dir(None)?);
+    let mut records: Vec<&BuildRecord> = history.records().iter().collect();
+    records.sort_by(|a, b| {
+        b.average()
+            .cmp(&a.average())
+            .then_with(|| a.gem.cmp(&b.gem))
+    });
+
+    if json {
+        let stats: Vec<_> = records
+            .iter()
+            .map(|record| BuildStat {
+                record,
+                average_ms: u64::try_from(record.average().as_millis()).unwrap_or(u64::MAX),
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+
+    if records.is_empty() {
+        println!("No extension builds recorded yet");
+        return Ok(());
+    }
+
+    let width = |column: fn(&BuildRecord) -> &str, header: &str| {
+        records
+            .iter()
+            .map(|record| column(record).len())
+            .max()
+            .unwrap_or(0)
+            .max(header.len())
+    };
+    let gem_width = width(|r| &r.gem, "GEM");
+    let version_width = width(|r| &r.version, "VERSION");
+    let machine_width = width(|r| &r.machine, "MACHINE");
+
+    println!(
+        "{:gem_width$}  {:version_width$}  {:machine_width$}  {:>9}  {:>6}  LAST BUILT",
+        "GEM", "VERSION", "MACHINE", "AVERAGE", "BUILDS"
+    );
+    for record in records {
+        let last_built = chrono::DateTime::from_timestamp(record.last_built, 0)
+            .map_or_else(String::new, |time| time.format("%Y-%m-%d").to_string());
+        println!(
+            "{:gem_width$}  {:version_width$}  {:machine_width$}  {:>8.1}s  {:>6}  {last_built}",
+            record.gem,
+            record.version,
+            record.machine,
+            record.average().as_secs_f64(),
+            record.samples_ms.len()
+        );
+    }
+
+    Ok(())
+}
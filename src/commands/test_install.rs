@@ -0,0 +1,418 @@
+//! Test-install command
+//!
+//! Smoke test a freshly built `.gem` before `lode gem-push`: install it with
+//! its runtime dependencies into a throwaway `GEM_HOME`, then use the
+//! detected Ruby to require each file at the top of its require paths and to
+//! run each executable with `--version` (or `--help`). Problems are listed
+//! together and fail the command.
+
+use anyhow::{Context, Result};
+use lode::install::CaseCollisions;
+use lode::platform::detect_current_platform;
+use lode::{
+    DownloadManager, ExtensionBuilder, GemSpec, Gemfile, Gemspec, Resolver, RubyGemsClient,
+};
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How long an executable may run before it counts as hanging
+const EXECUTABLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A check that did not pass
+#[derive(Debug)]
+struct Problem {
+    check: String,
+    detail: String,
+}
+
+/// Install `gem_path` into a temporary prefix and exercise it
+///
+/// # Examples
+///
+/// ```bash
+/// lode gem-build
+/// lode test-install mygem-1.0.0.gem
+///
+/// # Keep the prefix around to poke at a failure
+/// lode test-install mygem-1.0.0.gem --keep
+/// ```
+pub(crate) async fn run(gem_path: &str, keep: bool, verbose: bool) -> Result<()> {
+    let gem_file = Path::new(gem_path);
+    if !gem_file.is_file() {
+        anyhow::bail!("Gem file not found: {gem_path}");
+    }
+    let spec = Gemspec::from_gem_file(gem_file)
+        .with_context(|| format!("Failed to read gem metadata from {gem_path}"))?;
+    let interpreter = lode::detect_interpreter_versions()
+        .context("No working `ruby` found on PATH; test-install needs one to load the gem")?;
+
+    let prefix = tempfile::Builder::new()
+        .prefix("lode-test-install-")
+        .tempdir()
+        .context("Failed to create temporary install prefix")?;
+    let ruby_version = lode::config::ruby_version(None);
+    let gem_home = prefix.path().join("ruby").join(&ruby_version);
+
+    println!(
+        "Installing {}-{} into {}...",
+        spec.name,
+        spec.version,
+        prefix.path().display()
+    );
+    let dependencies =
+        install_dependencies(&spec, &interpreter, prefix.path(), &ruby_version, verbose).await?;
+    let platform = (spec.platform != "ruby").then(|| spec.platform.clone());
+    let gem = GemSpec::new(
+        spec.name.clone(),
+        spec.version.clone(),
+        platform,
+        vec![],
+        vec![],
+    );
+    install_one(&gem, gem_file, prefix.path(), &ruby_version, verbose)?;
+    println!(
+        "Installed {} with {} dependencies",
+        gem.full_name(),
+        dependencies
+    );
+    println!();
+
+    let gem_dir = gem_home.join("gems").join(gem.full_name());
+    let mut problems = Vec::new();
+    for feature in require_features(&spec, &gem_dir) {
+        let check = format!("require {feature:?}");
+        let mut command = ruby_command(&gem_home);
+        command
+            .args(["-e", "gem ARGV[0], \"= #{ARGV[1]}\"; require ARGV[2]"])
+            .args([&spec.name, &spec.version, &feature]);
+        report(&check, run_check(command), verbose, &mut problems);
+    }
+    for executable in &spec.executables {
+        let script = gem_dir.join(&spec.bindir).join(executable);
+        let run_with = |flag: &str| {
+            let mut command = ruby_command(&gem_home);
+            command.arg(&script).arg(flag);
+            run_check(command)
+        };
+        // Not every executable knows --version
+        let outcome = run_with("--version").or_else(|_| run_with("--help"));
+        report(
+            &format!("{executable} --version/--help"),
+            outcome,
+            verbose,
+            &mut problems,
+        );
+    }
+
+    if keep {
+        let kept = prefix.keep();
+        println!();
+        println!("Kept the install prefix at {}", kept.display());
+        println!(
+            "  GEM_HOME={}",
+            kept.join("ruby").join(&ruby_version).display()
+        );
+    }
+
+    println!();
+    if problems.is_empty() {
+        println!("{} is ready to push", gem.full_name());
+        return Ok(());
+    }
+    eprintln!("{} problem(s) found:", problems.len());
+    for problem in &problems {
+        eprintln!("  - {}", problem.check);
+        for line in problem.detail.lines().take(10) {
+            eprintln!("      {line}");
+        }
+    }
+    anyhow::bail!("{} failed its test install", gem.full_name())
+}
+
+/// Resolve, download and install the runtime dependencies of `spec`
+///
+/// Returns how many gems were installed.
+async fn install_dependencies(
+    spec: &Gemspec,
+    interpreter: &(String, String),
+    prefix: &Path,
+    ruby_version: &str,
+    verbose: bool,
+) -> Result<usize> {
+    let gemfile = Gemfile::parse(&dependency_gemfile(spec, &lode::gem_source_url()))
+        .context("Failed to read the gem's runtime dependencies")?;
+    if gemfile.gems.is_empty() {
+        return Ok(0);
+    }
+
+    let client = RubyGemsClient::new(lode::gem_source_url())
+        .context("Failed to create RubyGems API client")?;
+    let (ruby, rubygems) = interpreter;
+    let platforms = [detect_current_platform(), "ruby".to_string()];
+    let platform_refs: Vec<&str> = platforms.iter().map(String::as_str).collect();
+    let resolved = Resolver::new(client)
+        .with_ruby_version(Some(ruby.clone()))
+        .with_rubygems_version(Some(rubygems.clone()))
+        .resolve(&gemfile, &platform_refs, false)
+        .await
+        .context("Failed to resolve the gem's runtime dependencies")?;
+
+    let cache_dir = lode::config::cache_dir(None)?;
+    let downloader = DownloadManager::new(cache_dir)?;
+    for dependency in &resolved {
+        let platform = (dependency.platform != "ruby").then(|| dependency.platform.clone());
+        let gem = GemSpec::new(
+            dependency.name.clone(),
+            dependency.version.clone(),
+            platform,
+            vec![],
+            vec![],
+        );
+        let path = downloader
+            .download_gem(&gem)
+            .await
+            .with_context(|| format!("Failed to download {}", gem.full_name()))?;
+        install_one(&gem, &path, prefix, ruby_version, verbose)?;
+    }
+    Ok(resolved.len())
+}
+
+/// Unpack one `.gem` into the prefix and build its extensions
+fn install_one(
+    gem: &GemSpec,
+    gem_path: &Path,
+    prefix: &Path,
+    ruby_version: &str,
+    verbose: bool,
+) -> Result<()> {
+    if verbose {
+        println!("  Installing {}", gem.full_name());
+    }
+    lode::install::install_gem(gem, gem_path, prefix, ruby_version, CaseCollisions::Fail)
+        .with_context(|| format!("Failed to install {}", gem.full_name()))?;
+
+    let gem_dir = prefix
+        .join("ruby")
+        .join(ruby_version)
+        .join("gems")
+        .join(gem.full_name());
+    let mut builder = ExtensionBuilder::new(false, verbose, None);
+    if let Some(result) = builder.build_if_needed(&gem.name, &gem_dir, gem.platform.as_deref())
+        && !result.success
+    {
+        return Err(lode::exit_code::ExitCode::ExtensionBuild.error(format!(
+            "Failed to build native extensions for {}: {}",
+            gem.full_name(),
+            result.error.as_deref().unwrap_or(&result.output)
+        )));
+    }
+    Ok(())
+}
+
+/// A Gemfile requiring the runtime dependencies of `spec`
+fn dependency_gemfile(spec: &Gemspec, source: &str) -> String {
+    let mut gemfile = format!("source {source:?}\n");
+    for dependency in spec.runtime_dependencies() {
+        let _ = write!(gemfile, "gem {:?}", dependency.name);
+        for requirement in &dependency.requirements {
+            let _ = write!(gemfile, ", {requirement:?}");
+        }
+        gemfile.push('\n');
+    }
+    gemfile
+}
+
+/// Features to require: the Ruby files at the top of each require path
+///
+/// A gem with none (only nested files or a native extension) is required by
+/// its name, the way `Bundler.require` would.
+fn require_features(spec: &Gemspec, gem_dir: &Path) -> Vec<String> {
+    let require_paths = if spec.require_paths.is_empty() {
+        vec!["lib".to_string()]
+    } else {
+        spec.require_paths.clone()
+    };
+
+    let mut features: Vec<String> = require_paths
+        .iter()
+        .filter_map(|path| fs::read_dir(gem_dir.join(path)).ok())
+        .flat_map(|entries| entries.filter_map(Result::ok))
+        .map(|entry| PathBuf::from(entry.file_name()))
+        .filter(|name| name.extension().is_some_and(|ext| ext == "rb"))
+        .filter_map(|name| Some(name.file_stem()?.to_str()?.to_string()))
+        .collect();
+    features.sort();
+    features.dedup();
+    if features.is_empty() {
+        features.push(spec.name.replace('-', "/"));
+    }
+    features
+}
+
+/// `ruby` seeing only the gems of `gem_home`, outside any bundle
+fn ruby_command(gem_home: &Path) -> Command {
+    let mut env = lode::env_preserver::unbundled_env(std::env::vars_os().collect());
+    env.insert(OsString::from("GEM_HOME"), gem_home.into());
+    env.insert(OsString::from("GEM_PATH"), gem_home.into());
+
+    let mut command = Command::new("ruby");
+    command
+        .env_clear()
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    command
+}
+
+/// Run one check, returning its output on failure
+fn run_check(mut command: Command) -> Result<(), String> {
+    let mut child = command.spawn().map_err(|e| e.to_string())?;
+    // Drain both pipes while waiting so a chatty child never blocks on a
+    // full pipe buffer and gets mistaken for a hang
+    let stdout = child.stdout.take().map(drain);
+    let stderr = child.stderr.take().map(drain);
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() > EXECUTABLE_TIMEOUT => {
+                drop(child.kill());
+                drop(child.wait());
+                return Err(format!(
+                    "still running after {}s",
+                    EXECUTABLE_TIMEOUT.as_secs()
+                ));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+
+    if status.success() {
+        return Ok(());
+    }
+    let stderr = collect(stderr);
+    let detail = if stderr.trim().is_empty() {
+        collect(stdout)
+    } else {
+        stderr
+    };
+    Err(format!("{status}\n{}", detail.trim()))
+}
+
+/// Read a child's pipe to the end on its own thread
+fn drain(mut pipe: impl Read + Send + 'static) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        drop(pipe.read_to_end(&mut buffer));
+        buffer
+    })
+}
+
+/// Output read by [`drain`], lossily decoded
+fn collect(reader: Option<JoinHandle<Vec<u8>>>) -> String {
+    let bytes = reader
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Print the outcome of a check and collect its failure
+fn report(check: &str, outcome: Result<(), String>, verbose: bool, problems: &mut Vec<Problem>) {
+    match outcome {
+        Ok(()) => println!("  ok      {check}"),
+        Err(detail) => {
+            println!("  FAILED  {check}");
+            if verbose {
+                for line in detail.lines() {
+                    println!("          {line}");
+                }
+            }
+            problems.push(Problem {
+                check: check.to_string(),
+                detail,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lode::{DependencyKind, GemspecDependency};
+
+    fn spec_with_dependencies() -> Gemspec {
+        Gemspec {
+            name: "my-gem".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: vec![
+                GemspecDependency {
+                    name: "rack".to_string(),
+                    requirements: vec![">= 2.0".to_string(), "< 4".to_string()],
+                    kind: DependencyKind::Runtime,
+                },
+                GemspecDependency {
+                    name: "rspec".to_string(),
+                    requirements: vec!["~> 3.0".to_string()],
+                    kind: DependencyKind::Development,
+                },
+            ],
+            ..Gemspec::default()
+        }
+    }
+
+    #[test]
+    fn gemfile_lists_runtime_dependencies_only() {
+        let gemfile = dependency_gemfile(&spec_with_dependencies(), "https://rubygems.org");
+        assert_eq!(
+            gemfile,
+            "source \"https://rubygems.org\"\ngem \"rack\", \">= 2.0\", \"< 4\"\n"
+        );
+        let parsed = Gemfile::parse(&gemfile).unwrap();
+        assert_eq!(parsed.gems.len(), 1);
+    }
+
+    #[test]
+    fn features_come_from_top_of_require_paths() {
+        let temp = tempfile::tempdir().unwrap();
+        let lib = temp.path().join("lib");
+        fs::create_dir_all(lib.join("my/gem")).unwrap();
+        fs::write(lib.join("my_gem.rb"), "").unwrap();
+        fs::write(lib.join("my-gem.rb"), "").unwrap();
+        fs::write(lib.join("my/gem/version.rb"), "").unwrap();
+
+        let spec = spec_with_dependencies();
+        assert_eq!(
+            require_features(&spec, temp.path()),
+            vec!["my-gem", "my_gem"]
+        );
+
+        fs::remove_file(lib.join("my_gem.rb")).unwrap();
+        fs::remove_file(lib.join("my-gem.rb")).unwrap();
+        assert_eq!(require_features(&spec, temp.path()), vec!["my/gem"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_larger_than_a_pipe_buffer_does_not_hang() {
+        let mut command = Command::new("sh");
+        command
+            .args([
+                "-c",
+                "head -c 1000000 /dev/zero; head -c 1000000 /dev/zero >&2; exit 3",
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let started = Instant::now();
+        let detail = run_check(command).unwrap_err();
+        assert!(started.elapsed() < EXECUTABLE_TIMEOUT);
+        assert!(detail.starts_with("exit status: 3"), "{detail}");
+    }
+}
@@ -4,19 +4,28 @@
 
 use anyhow::{Context, Result};
 use flate2::read::GzDecoder;
-use lode::{Config, DownloadManager, GemSpec, Lockfile, config};
+use lode::{Config, DownloadManager, GemSpec, GemVerifier, Lockfile, TrustPolicy, config};
 use std::fs;
 use std::path::{Path, PathBuf};
 use tar::Archive;
 
 /// Unpack a gem to the current directory.
 ///
-/// Downloads the gem if needed, then extracts it to `./<gem-name>-<version>/`
+/// Downloads the gem if needed, verifies it against `trust_policy`, then
+/// extracts it to `./<gem-name>-<version>/`
 pub(crate) async fn run(
     gem_name: &str,
     version: Option<&str>,
     target_dir: Option<&str>,
+    trust_policy: Option<&str>,
 ) -> Result<()> {
+    let trust_policy = trust_policy
+        .map(|policy| {
+            TrustPolicy::parse(policy).with_context(|| format!("Invalid trust policy: {policy}"))
+        })
+        .transpose()?
+        .unwrap_or(TrustPolicy::NoSecurity);
+
     // Load configuration
     let config = Config::load().context("Failed to load configuration")?;
     let cache_dir = config::cache_dir(Some(&config))?;
@@ -80,6 +89,10 @@ pub(crate) async fn run(
 
     println!("Fetched gem to {}", gem_path.display());
 
+    if trust_policy != TrustPolicy::NoSecurity {
+        GemVerifier::new(trust_policy)?.verify_gem(&gem_path)?;
+    }
+
     // Determine target directory
     let target = target_dir.map_or_else(|| PathBuf::from("."), PathBuf::from);
 
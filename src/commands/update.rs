@@ -5,9 +5,14 @@
 use anyhow::{Context, Result};
 use futures_util::stream::{self, StreamExt};
 use indicatif::{ProgressBar, ProgressStyle};
-use lode::{lockfile::Lockfile, rubygems_client::RubyGemsClient};
+use lode::gem_utils::{compare_versions, requirement_satisfied};
+use lode::messages::{self, Message};
+use lode::reporter::version;
+use lode::{AdvisoryDatabase, lockfile::Lockfile, rubygems_client::RubyGemsClient};
 use semver::Version;
-use std::collections::HashSet;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::{self, Write as _};
 use std::fs;
 use std::sync::Arc;
 use std::time::Duration;
@@ -46,6 +51,7 @@ pub(crate) async fn run(
     // Note: --redownload and --full-index accepted for Bundler compatibility
     // --redownload: Use `lode fetch --force` to re-download gems
     // --full-index: Update uses dependency API (full index not needed)
+    super::install::refuse_in_deployment("update")?;
 
     let lockfile_path = gemfile.as_ref().map_or_else(
         || "Gemfile.lock".to_string(),
@@ -105,6 +111,23 @@ pub(crate) async fn run(
     let gemfile_path_buf = gemfile.map_or_else(lode::paths::find_gemfile, std::path::PathBuf::from);
     let parsed_gemfile = lode::Gemfile::parse_file(&gemfile_path_buf).ok();
 
+    // A git source named by --source is re-locked against its remote directly
+    if let Some(source_name) = source
+        && lockfile
+            .git_gems
+            .iter()
+            .any(|g| git_source_matches(g, source_name))
+    {
+        return relock_git_source(
+            &lockfile_path,
+            &content,
+            &lockfile,
+            parsed_gemfile.as_ref(),
+            source_name,
+            quiet,
+        );
+    }
+
     // Determine which gems to check
     let mut gems_to_check: HashSet<String> = if gems_to_update.is_empty() {
         // Update all gems
@@ -154,6 +177,18 @@ pub(crate) async fn run(
         }
     }
 
+    // Held gems (`lode hold`) keep their locked versions
+    let holds = lode::config::held_gems(lode::Config::load().ok().as_ref());
+    gems_to_check.retain(|gem_name| {
+        let Some(reason) = holds.get(gem_name) else {
+            return true;
+        };
+        if !quiet {
+            println!("Skipping {gem_name}: held{}", super::hold::describe(reason));
+        }
+        false
+    });
+
     if !quiet {
         println!("Checking for updates...\n");
     }
@@ -346,7 +381,7 @@ pub(crate) async fn run(
 
     // Display results
     if updatable_gems.is_empty() {
-        println!("All gems are up to date!");
+        println!("{}", messages::text(Message::AllGemsUpToDate));
         if gems_to_update.is_empty() {
             println!("   {} gems checked, {} errors", lockfile.gems.len(), errors);
         } else {
@@ -365,7 +400,11 @@ pub(crate) async fn run(
         .unwrap_or(0);
 
     for (name, current, latest) in &updatable_gems {
-        println!("  • {name:<max_name_len$}  {current} -> {latest}");
+        println!(
+            "  • {name:<max_name_len$}  {} -> {}",
+            version(current),
+            version(latest)
+        );
     }
 
     println!(
@@ -387,17 +426,21 @@ pub(crate) async fn run(
 
     crate::commands::lock::run(
         gemfile_str,
-        None,   // lockfile_path
-        &[],    // add_platforms
-        &[],    // remove_platforms
-        &[],    // update_gems
-        false,  // print
-        !quiet, // verbose
+        None,                             // lockfile_path
+        &[],                              // add_platforms
+        &[],                              // remove_platforms
+        &[],                              // update_gems
+        false,                            // release_hold
+        &BTreeMap::new(),                 // constraints
+        lode::ResolutionStrategy::Latest, // strategy
+        false,                            // print
+        !quiet,                           // verbose
         patch,
         minor,
         major,
         strict,
         conservative,
+        false, // incremental
         local,
         pre,
         None,  // bundler
@@ -405,6 +448,7 @@ pub(crate) async fn run(
         false, // add_checksums
         false, // full_index
         quiet, // quiet
+        &lode::Profiler::default(),
     )
     .await?;
 
@@ -414,6 +458,942 @@ pub(crate) async fn run(
     Ok(())
 }
 
+/// How `update --bot` splits updates into branches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum BotGrouping {
+    /// One branch per outdated gem
+    Gem,
+    /// One branch per Gemfile group; transitive dependencies share one
+    Group,
+    /// One branch bumping every gem with a known advisory
+    Security,
+}
+
+/// Where `update --bot` writes its branches and report
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BotOptions<'a> {
+    pub grouping: BotGrouping,
+    /// Prepended to the group name to form each branch name
+    pub branch_prefix: &'a str,
+    /// Report file; stdout when `None`
+    pub output: Option<&'a str>,
+}
+
+/// JSON report printed by `update --bot`
+#[derive(Debug, Serialize)]
+struct BotReport {
+    grouping: BotGrouping,
+    updates: Vec<BotUpdate>,
+}
+
+/// One branch of updates, ready to open as a pull request
+#[derive(Debug, Serialize)]
+struct BotUpdate {
+    group: String,
+    branch: String,
+    commit: String,
+    title: String,
+    /// Markdown pull request body
+    body: String,
+    changes: Vec<BotChange>,
+}
+
+/// A gem whose locked version changed; `from` is `None` for new gems and
+/// `to` is `None` for removed ones
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct BotChange {
+    name: String,
+    from: Option<String>,
+    to: Option<String>,
+    changelog_url: Option<String>,
+    /// Advisories the update fixes
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    advisories: Vec<String>,
+}
+
+/// Update in grouped branches for pull request automation
+///
+/// Each group is re-locked from the current Gemfile.lock on its own, the
+/// resulting lockfile is committed to `<prefix><group>` on top of `HEAD`
+/// (the working tree is not touched), and a JSON report describing every
+/// branch is written for the bot to open pull requests from.
+#[allow(clippy::too_many_lines)]
+pub(crate) async fn run_bot(
+    gems_to_update: &[String],
+    gemfile: Option<&str>,
+    options: BotOptions<'_>,
+    jobs: Option<usize>,
+    local: bool,
+    pre: bool,
+) -> Result<()> {
+    super::install::refuse_in_deployment("update")?;
+
+    let gemfile_path = gemfile.map_or_else(lode::paths::find_gemfile, std::path::PathBuf::from);
+    let lockfile_path = lode::lockfile_for_gemfile(&gemfile_path);
+    let holds = lode::config::held_gems(lode::Config::load().ok().as_ref());
+    let content = fs::read_to_string(&lockfile_path)
+        .with_context(|| format!("Failed to read lockfile: {}", lockfile_path.display()))?;
+    let lockfile = Lockfile::parse(&content)
+        .with_context(|| format!("Failed to parse lockfile: {}", lockfile_path.display()))?;
+    let parsed_gemfile = lode::Gemfile::parse_file(&gemfile_path)
+        .with_context(|| format!("Failed to parse {}", gemfile_path.display()))?;
+
+    let mut seen = HashSet::new();
+    let candidates: Vec<_> = lockfile
+        .gems
+        .iter()
+        .filter(|gem| seen.insert(gem.name.as_str()))
+        .filter(|gem| gems_to_update.is_empty() || gems_to_update.contains(&gem.name))
+        .filter(|gem| !holds.contains_key(&gem.name))
+        .collect();
+
+    let client = RubyGemsClient::new(lode::gem_source_url())
+        .context("Failed to create RubyGems client")?
+        .with_cache_only(local)
+        .with_prerelease(pre);
+
+    let advisories = if options.grouping == BotGrouping::Security {
+        let cache_dir = lode::config::cache_dir(None)?;
+        Some(AdvisoryDatabase::open(&cache_dir).context("Failed to load the advisory database")?)
+    } else {
+        None
+    };
+
+    // Security branches move each vulnerable gem to its smallest fix
+    let mut constraints = BTreeMap::new();
+    let outdated: Vec<String> = if let Some(database) = &advisories {
+        let plan =
+            plan_security_fixes(&client, database, &candidates, &parsed_gemfile, jobs, pre).await?;
+        for gem in &plan.unfixable {
+            lode::warn!("cannot update {gem}");
+        }
+        constraints = plan
+            .targets
+            .iter()
+            .map(|(name, version)| (name.clone(), format!("= {version}")))
+            .collect();
+        plan.targets.into_keys().collect()
+    } else {
+        let client = &client;
+        let mut outdated: Vec<String> = stream::iter(candidates)
+            .map(|gem| async move {
+                let versions = client.fetch_versions(&gem.name).await.ok()?;
+                let latest = versions
+                    .iter()
+                    .find(|version| pre || !is_prerelease(&version.number))?;
+                is_newer(&latest.number, &gem.version).then(|| gem.name.clone())
+            })
+            .buffer_unordered(jobs.unwrap_or(10))
+            .filter_map(std::future::ready)
+            .collect()
+            .await;
+        outdated.sort();
+        outdated
+    };
+
+    let gemfile_str = gemfile_path.to_str().unwrap_or("Gemfile");
+    let scratch = tempfile::tempdir().context("Failed to create a scratch directory")?;
+    let mut updates = Vec::new();
+    for (group, gems) in group_updates(options.grouping, &outdated, &parsed_gemfile) {
+        // Re-lock a copy so each group starts from the committed lockfile
+        let scratch_lockfile = scratch.path().join("Gemfile.lock");
+        fs::write(&scratch_lockfile, &content).context("Failed to write scratch lockfile")?;
+        let relocked = crate::commands::lock::run(
+            gemfile_str,
+            scratch_lockfile.to_str(),
+            &[],   // add_platforms
+            &[],   // remove_platforms
+            &gems, // update_gems
+            false, // release_hold
+            &constraints,
+            lode::ResolutionStrategy::Latest,
+            false, // print
+            false, // verbose
+            false, // patch
+            false, // minor
+            false, // major
+            false, // strict
+            false, // conservative
+            false, // incremental
+            local,
+            pre,
+            None,  // bundler
+            false, // normalize_platforms
+            false, // add_checksums
+            false, // full_index
+            true,  // quiet
+            &lode::Profiler::default(),
+        )
+        .await;
+        if let Err(e) = relocked {
+            lode::warn!("could not update {group}: {e:#}");
+            continue;
+        }
+
+        let new_content =
+            fs::read_to_string(&scratch_lockfile).context("Failed to read scratch lockfile")?;
+        let new_lockfile = Lockfile::parse(&new_content).context("Failed to parse new lockfile")?;
+        let mut changes = lockfile_changes(&lockfile, &new_lockfile);
+        if changes.is_empty() {
+            continue;
+        }
+        for change in &mut changes {
+            if let Some(database) = &advisories {
+                change.advisories = fixed_advisories(database, change);
+            }
+            if let Some(to) = &change.to {
+                let summary = if local {
+                    None
+                } else {
+                    client.fetch_gem_summary(&change.name).await.ok()
+                };
+                change.changelog_url = Some(changelog_url(&change.name, to, summary.as_ref()));
+            }
+        }
+
+        let title = update_title(options.grouping, &group, &changes);
+        let body = update_body(&changes);
+        let branch = format!("{}{group}", options.branch_prefix);
+        let commit = lode::git::commit_file_to_branch(
+            &lockfile_path,
+            new_content.as_bytes(),
+            &branch,
+            &format!("{title}\n\n{body}"),
+        )
+        .with_context(|| format!("Failed to write branch {branch}"))?;
+
+        updates.push(BotUpdate {
+            group,
+            branch,
+            commit,
+            title,
+            body,
+            changes,
+        });
+    }
+
+    let report = BotReport {
+        grouping: options.grouping,
+        updates,
+    };
+    let json = serde_json::to_string_pretty(&report)?;
+    match options.output {
+        Some(path) => {
+            fs::write(path, format!("{json}\n"))
+                .with_context(|| format!("Failed to write {path}"))?;
+        }
+        None => println!("{json}"),
+    }
+    Ok(())
+}
+
+/// Why `update --security` left a vulnerable gem alone
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Unfixable {
+    /// A patched release exists, but the Gemfile requirement excludes it
+    Constrained {
+        requirement: String,
+        patched: String,
+    },
+    /// No release is free of the gem's advisories
+    Unpatched,
+}
+
+/// A vulnerable gem that needs a Gemfile change or an upstream fix
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UnfixableGem {
+    name: String,
+    version: String,
+    advisories: Vec<String>,
+    reason: Unfixable,
+}
+
+impl fmt::Display for UnfixableGem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} ({}): ",
+            self.name,
+            self.version,
+            self.advisories.join(", ")
+        )?;
+        match &self.reason {
+            Unfixable::Constrained {
+                requirement,
+                patched,
+            } => write!(
+                f,
+                "patched in {patched}, but the Gemfile requires {requirement}"
+            ),
+            Unfixable::Unpatched => write!(f, "no patched release"),
+        }
+    }
+}
+
+/// Versions `update --security` moves vulnerable gems to
+#[derive(Debug, Default)]
+struct SecurityPlan {
+    /// Smallest non-vulnerable version for each fixable gem
+    targets: BTreeMap<String, String>,
+    unfixable: Vec<UnfixableGem>,
+}
+
+/// Pick the smallest release of each vulnerable locked gem that no
+/// advisory affects and the Gemfile allows
+async fn plan_security_fixes(
+    client: &RubyGemsClient,
+    database: &AdvisoryDatabase,
+    candidates: &[&lode::GemSpec],
+    gemfile: &lode::Gemfile,
+    jobs: Option<usize>,
+    pre: bool,
+) -> Result<SecurityPlan> {
+    let vulnerable: Vec<_> = candidates
+        .iter()
+        .filter(|gem| !database.vulnerabilities(&gem.name, &gem.version).is_empty())
+        .collect();
+    let fetched: Vec<_> = stream::iter(vulnerable)
+        .map(|gem| async move {
+            let versions = client
+                .fetch_versions(&gem.name)
+                .await
+                .with_context(|| format!("Failed to fetch versions of {}", gem.name));
+            (gem, versions)
+        })
+        .buffer_unordered(jobs.unwrap_or(10))
+        .collect()
+        .await;
+
+    let mut plan = SecurityPlan::default();
+    for (gem, versions) in fetched {
+        let numbers: Vec<String> = versions?
+            .into_iter()
+            .map(|version| version.number)
+            .collect();
+        let requirement = gemfile
+            .gems
+            .iter()
+            .find(|dependency| dependency.name == gem.name)
+            .map(|dependency| dependency.version_requirement.as_str());
+        match smallest_fix(
+            database,
+            &gem.name,
+            &gem.version,
+            &numbers,
+            requirement,
+            pre,
+        ) {
+            Ok(version) => {
+                plan.targets.insert(gem.name.clone(), version);
+            }
+            Err(reason) => plan.unfixable.push(UnfixableGem {
+                name: gem.name.clone(),
+                version: gem.version.clone(),
+                advisories: database
+                    .vulnerabilities(&gem.name, &gem.version)
+                    .into_iter()
+                    .map(|advisory| advisory.id.clone())
+                    .collect(),
+                reason,
+            }),
+        }
+    }
+    plan.unfixable.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(plan)
+}
+
+/// Smallest release newer than `current` that no advisory affects and
+/// `requirement` allows
+fn smallest_fix(
+    database: &AdvisoryDatabase,
+    gem: &str,
+    current: &str,
+    versions: &[String],
+    requirement: Option<&str>,
+    pre: bool,
+) -> Result<String, Unfixable> {
+    let mut fixed: Vec<&String> = versions
+        .iter()
+        .filter(|version| pre || !is_prerelease(version))
+        .filter(|version| is_newer(version, current))
+        .filter(|version| database.vulnerabilities(gem, version).is_empty())
+        .collect();
+    fixed.sort_by(|a, b| compare_versions(a, b));
+    fixed.dedup();
+
+    let allowed = |version: &&&String| {
+        requirement.is_none_or(|requirement| requirement_satisfied(version, requirement))
+    };
+    match (fixed.iter().find(allowed), fixed.first()) {
+        (Some(version), _) => Ok((*version).clone()),
+        (None, Some(patched)) => Err(Unfixable::Constrained {
+            requirement: requirement.unwrap_or_default().to_string(),
+            patched: (*patched).clone(),
+        }),
+        (None, None) => Err(Unfixable::Unpatched),
+    }
+}
+
+/// Move only gems with known advisories, each to its smallest fixed
+/// version, holding every other gem where it is when possible
+///
+/// Fails after writing the lockfile when vulnerable gems remain, so a
+/// scheduled CI job notices them.
+#[allow(clippy::too_many_lines)]
+pub(crate) async fn run_security(
+    gems_to_update: &[String],
+    gemfile: Option<&str>,
+    jobs: Option<usize>,
+    local: bool,
+    pre: bool,
+    quiet: bool,
+) -> Result<()> {
+    super::install::refuse_in_deployment("update")?;
+
+    let gemfile_path = gemfile.map_or_else(lode::paths::find_gemfile, std::path::PathBuf::from);
+    let lockfile_path = lode::lockfile_for_gemfile(&gemfile_path);
+    let holds = lode::config::held_gems(lode::Config::load().ok().as_ref());
+    let content = fs::read_to_string(&lockfile_path)
+        .with_context(|| format!("Failed to read lockfile: {}", lockfile_path.display()))?;
+    let lockfile = Lockfile::parse(&content)
+        .with_context(|| format!("Failed to parse lockfile: {}", lockfile_path.display()))?;
+    let parsed_gemfile = lode::Gemfile::parse_file(&gemfile_path)
+        .with_context(|| format!("Failed to parse {}", gemfile_path.display()))?;
+
+    let cache_dir = lode::config::cache_dir(None)?;
+    let database =
+        AdvisoryDatabase::open(&cache_dir).context("Failed to load the advisory database")?;
+
+    let mut seen = HashSet::new();
+    let candidates: Vec<_> = lockfile
+        .gems
+        .iter()
+        .filter(|gem| seen.insert(gem.name.as_str()))
+        .filter(|gem| gems_to_update.is_empty() || gems_to_update.contains(&gem.name))
+        .filter(|gem| !holds.contains_key(&gem.name))
+        .collect();
+
+    let client = RubyGemsClient::new(lode::gem_source_url())
+        .context("Failed to create RubyGems client")?
+        .with_cache_only(local)
+        .with_prerelease(pre);
+    let plan =
+        plan_security_fixes(&client, &database, &candidates, &parsed_gemfile, jobs, pre).await?;
+
+    if plan.targets.is_empty() && plan.unfixable.is_empty() {
+        if !quiet {
+            println!(
+                "No vulnerable gems found ({} gems, {} advisories)",
+                candidates.len(),
+                database.len()
+            );
+        }
+        return Ok(());
+    }
+
+    if !plan.targets.is_empty() {
+        let update_gems: Vec<String> = plan.targets.keys().cloned().collect();
+        let gemfile_str = gemfile_path.to_str().unwrap_or("Gemfile");
+
+        // First hold every other gem at its locked version; if a fix needs
+        // newer dependencies, let those move within the Gemfile
+        let held: BTreeMap<String, String> = candidates
+            .iter()
+            .map(|gem| (gem.name.clone(), format!("= {}", gem.version)))
+            .chain(
+                plan.targets
+                    .iter()
+                    .map(|(name, version)| (name.clone(), format!("= {version}"))),
+            )
+            .collect();
+        let targets: BTreeMap<String, String> = plan
+            .targets
+            .iter()
+            .map(|(name, version)| (name.clone(), format!("= {version}")))
+            .collect();
+
+        let relock = async |constraints| {
+            crate::commands::lock::run(
+                gemfile_str,
+                None,         // lockfile_path
+                &[],          // add_platforms
+                &[],          // remove_platforms
+                &update_gems, // update_gems
+                false,        // release_hold
+                constraints,
+                lode::ResolutionStrategy::Latest,
+                false, // print
+                false, // verbose
+                false, // patch
+                false, // minor
+                false, // major
+                false, // strict
+                false, // conservative
+                false, // incremental
+                local,
+                pre,
+                None,  // bundler
+                false, // normalize_platforms
+                false, // add_checksums
+                false, // full_index
+                true,  // quiet
+                &lode::Profiler::default(),
+            )
+            .await
+        };
+        if relock(&held).await.is_err() {
+            relock(&targets)
+                .await
+                .context("The security fixes conflict with other locked gems")?;
+        }
+
+        if !quiet {
+            let new_content = fs::read_to_string(&lockfile_path)
+                .with_context(|| format!("Failed to read lockfile: {}", lockfile_path.display()))?;
+            let new_lockfile = Lockfile::parse(&new_content).with_context(|| {
+                format!("Failed to parse lockfile: {}", lockfile_path.display())
+            })?;
+            println!("Security updates:");
+            for change in lockfile_changes(&lockfile, &new_lockfile) {
+                let fixes = fixed_advisories(&database, &change);
+                let fixes = if fixes.is_empty() {
+                    String::new()
+                } else {
+                    format!(" (fixes {})", fixes.join(", "))
+                };
+                println!(
+                    "  {} {} -> {}{fixes}",
+                    change.name,
+                    version(change.from.as_deref().unwrap_or("(new)")),
+                    version(change.to.as_deref().unwrap_or("(removed)"))
+                );
+            }
+        }
+    }
+
+    if plan.unfixable.is_empty() {
+        return Ok(());
+    }
+    eprintln!("Vulnerable gems that cannot be updated:");
+    for gem in &plan.unfixable {
+        eprintln!("  {gem}");
+    }
+    anyhow::bail!(
+        "{} vulnerable gems need a Gemfile change or an upstream fix",
+        plan.unfixable.len()
+    )
+}
+
+/// A Gemfile requirement that holds gems back
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Relaxation {
+    gem: String,
+    requirement: String,
+    /// Gems that would move forward without it, as (name, from, to)
+    unblocked: Vec<(String, String, String)>,
+}
+
+/// Suggest which Gemfile requirements to relax so gems can update
+///
+/// Resolves the Gemfile once as written, then once more with each pinned
+/// requirement removed, and reports the requirements whose removal lets
+/// `gems_to_update` (or any gem, if empty) resolve to newer versions.
+/// Neither the Gemfile nor the lockfile is changed.
+pub(crate) async fn run_suggest_relaxations(
+    gems_to_update: &[String],
+    gemfile: Option<&str>,
+    local: bool,
+    pre: bool,
+    quiet: bool,
+) -> Result<()> {
+    let gemfile_path = gemfile.map_or_else(lode::paths::find_gemfile, std::path::PathBuf::from);
+    let lockfile_path = lode::lockfile_for_gemfile(&gemfile_path);
+    let parsed_gemfile = lode::Gemfile::parse_file(&gemfile_path)
+        .with_context(|| format!("Failed to parse {}", gemfile_path.display()))?;
+    let lockfile = fs::read_to_string(&lockfile_path)
+        .ok()
+        .and_then(|content| Lockfile::parse(&content).ok());
+
+    let mut platforms = vec![lode::platform::detect_current_platform()];
+    if let Some(lockfile) = &lockfile {
+        platforms.extend(lockfile.platforms.iter().cloned());
+    }
+    platforms.sort();
+    platforms.dedup();
+    let platforms: Vec<&str> = platforms.iter().map(String::as_str).collect();
+
+    let source_routes = Arc::new(
+        lode::SourceRoutes::from_gemfile(
+            &parsed_gemfile,
+            &lode::SourceProviders::builtin(&lode::config::cache_dir(None)?),
+        )
+        .context("Failed to set up gem sources")?,
+    );
+    let interpreter = lode::detect_interpreter_versions();
+    let client = RubyGemsClient::new(&parsed_gemfile.source)
+        .context("Failed to create RubyGems client")?
+        .with_cache_only(local)
+        .with_prerelease(pre);
+    let resolver = lode::Resolver::new(client)
+        .with_ruby_version(interpreter.as_ref().map(|(ruby, _)| ruby.clone()))
+        .with_rubygems_version(interpreter.map(|(_, rubygems)| rubygems))
+        .with_source_routes(source_routes)
+        .with_ruby_platform(lode::config::ruby_platform_gems(
+            lode::Config::load().ok().as_ref(),
+        ));
+
+    let resolve = async |gemfile: &lode::Gemfile| {
+        resolver
+            .resolve(gemfile, &platforms, pre)
+            .await
+            .map(|gems| -> BTreeMap<String, String> {
+                gems.into_iter()
+                    .map(|gem| (gem.name, gem.version))
+                    .collect()
+            })
+    };
+
+    if !quiet {
+        println!("Resolving the Gemfile as written...");
+    }
+    let baseline = resolve(&parsed_gemfile)
+        .await
+        .context("The Gemfile does not resolve as written")?;
+    let targets: Vec<&str> = if gems_to_update.is_empty() {
+        baseline.keys().map(String::as_str).collect()
+    } else {
+        gems_to_update.iter().map(String::as_str).collect()
+    };
+
+    let mut relaxations = Vec::new();
+    for (index, dependency) in parsed_gemfile.gems.iter().enumerate() {
+        if dependency.version_requirement.is_empty()
+            || dependency.git.is_some()
+            || dependency.path.is_some()
+        {
+            continue;
+        }
+        if !quiet {
+            println!(
+                "Trying without {} \"{}\"...",
+                dependency.name, dependency.version_requirement
+            );
+        }
+        let mut relaxed = parsed_gemfile.clone();
+        if let Some(gem) = relaxed.gems.get_mut(index) {
+            gem.version_requirement.clear();
+        }
+        match resolve(&relaxed).await {
+            Ok(versions) => {
+                let unblocked = unblocked_gems(&targets, &baseline, &versions);
+                if !unblocked.is_empty() {
+                    relaxations.push(Relaxation {
+                        gem: dependency.name.clone(),
+                        requirement: dependency.version_requirement.clone(),
+                        unblocked,
+                    });
+                }
+            }
+            Err(e) => lode::debug!("resolution without {} failed: {e}", dependency.name),
+        }
+    }
+
+    if relaxations.is_empty() {
+        println!("No single Gemfile requirement is holding back these gems");
+        return Ok(());
+    }
+    println!("\nRelaxing these Gemfile requirements would allow newer versions:");
+    for relaxation in &relaxations {
+        println!(
+            "  gem \"{}\", \"{}\"",
+            relaxation.gem, relaxation.requirement
+        );
+        for (name, from, to) in &relaxation.unblocked {
+            println!("    {name} {} -> {}", version(from), version(to));
+        }
+    }
+    Ok(())
+}
+
+/// Target gems resolved newer in `relaxed` than in `baseline`
+fn unblocked_gems(
+    targets: &[&str],
+    baseline: &BTreeMap<String, String>,
+    relaxed: &BTreeMap<String, String>,
+) -> Vec<(String, String, String)> {
+    targets
+        .iter()
+        .filter_map(|name| {
+            let from = baseline.get(*name)?;
+            let to = relaxed.get(*name)?;
+            is_newer(to, from).then(|| ((*name).to_string(), from.clone(), to.clone()))
+        })
+        .collect()
+}
+
+/// Split outdated gems into named groups, each becoming one branch
+fn group_updates(
+    grouping: BotGrouping,
+    outdated: &[String],
+    gemfile: &lode::Gemfile,
+) -> BTreeMap<String, Vec<String>> {
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for name in outdated {
+        let group = match grouping {
+            BotGrouping::Gem => name.clone(),
+            BotGrouping::Security => "security".to_string(),
+            BotGrouping::Group => gemfile
+                .gems
+                .iter()
+                .find(|dependency| &dependency.name == name)
+                .map_or_else(
+                    || "dependencies".to_string(),
+                    |dependency| {
+                        if dependency.groups.is_empty() {
+                            "default".to_string()
+                        } else {
+                            dependency.groups.join("-")
+                        }
+                    },
+                ),
+        };
+        groups.entry(group).or_default().push(name.clone());
+    }
+    groups
+}
+
+/// Gems whose locked version differs between two lockfiles, by name
+fn lockfile_changes(old: &Lockfile, new: &Lockfile) -> Vec<BotChange> {
+    let versions = |lockfile: &Lockfile| -> BTreeMap<String, String> {
+        lockfile
+            .gems
+            .iter()
+            .rev()
+            .map(|gem| (gem.name.clone(), gem.version.clone()))
+            .collect()
+    };
+    let old = versions(old);
+    let new = versions(new);
+    old.keys()
+        .chain(new.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|name| old.get(*name) != new.get(*name))
+        .map(|name| BotChange {
+            name: name.clone(),
+            from: old.get(name).cloned(),
+            to: new.get(name).cloned(),
+            changelog_url: None,
+            advisories: Vec::new(),
+        })
+        .collect()
+}
+
+/// Advisories affecting the old version but not the new one
+fn fixed_advisories(database: &AdvisoryDatabase, change: &BotChange) -> Vec<String> {
+    let Some(from) = &change.from else {
+        return Vec::new();
+    };
+    database
+        .vulnerabilities(&change.name, from)
+        .into_iter()
+        .filter(|advisory| {
+            change
+                .to
+                .as_ref()
+                .is_none_or(|to| !advisory.is_vulnerable(to))
+        })
+        .map(|advisory| advisory.id.clone())
+        .collect()
+}
+
+/// The gem's changelog, else its source, else its rubygems.org page
+fn changelog_url(name: &str, version: &str, summary: Option<&lode::GemSummary>) -> String {
+    summary
+        .and_then(|summary| {
+            summary
+                .changelog_uri
+                .clone()
+                .or_else(|| summary.source_code_uri.clone())
+        })
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| format!("https://rubygems.org/gems/{name}/versions/{version}"))
+}
+
+fn update_title(grouping: BotGrouping, group: &str, changes: &[BotChange]) -> String {
+    let describe = |change: &BotChange| {
+        format!(
+            "{} from {} to {}",
+            change.name,
+            change.from.as_deref().unwrap_or("none"),
+            change.to.as_deref().unwrap_or("none")
+        )
+    };
+    if let [change] = changes {
+        return format!("Bump {}", describe(change));
+    }
+    match grouping {
+        BotGrouping::Gem => changes
+            .iter()
+            .find(|change| change.name == group)
+            .map_or_else(
+                || format!("Bump {} gems for {group}", changes.len()),
+                |change| {
+                    format!(
+                        "Bump {} and {} dependencies",
+                        describe(change),
+                        changes.len() - 1
+                    )
+                },
+            ),
+        BotGrouping::Group => format!("Bump {} gems in the {group} group", changes.len()),
+        BotGrouping::Security => format!("Bump {} gems with security advisories", changes.len()),
+    }
+}
+
+fn update_body(changes: &[BotChange]) -> String {
+    let mut body = String::from("| Gem | From | To | Changelog |\n|---|---|---|---|\n");
+    for change in changes {
+        let mut to = change.to.clone().unwrap_or_else(|| "removed".to_string());
+        if !change.advisories.is_empty() {
+            to = format!("{to} (fixes {})", change.advisories.join(", "));
+        }
+        let _ = writeln!(
+            body,
+            "| {} | {} | {to} | {} |",
+            change.name,
+            change.from.as_deref().unwrap_or("new"),
+            change.changelog_url.as_deref().unwrap_or("")
+        );
+    }
+    body
+}
+
+/// Whether `--source NAME` refers to this git gem
+///
+/// Matches the gem name or the repository name (`rails` for
+/// `https://github.com/rails/rails.git`).
+fn git_source_matches(gem: &lode::GitGemSpec, source_name: &str) -> bool {
+    gem.name == source_name
+        || gem.repository == source_name
+        || gem
+            .repository
+            .trim_end_matches('/')
+            .trim_end_matches(".git")
+            .rsplit(['/', ':'])
+            .next()
+            .is_some_and(|repo| repo == source_name)
+}
+
+/// Re-lock a git source to the latest commit of its branch or tag
+///
+/// Fetches the remote, resolves the branch/tag from the Gemfile (falling back
+/// to what the lockfile recorded) and rewrites only that GIT section,
+/// refreshing gem versions from the gemspecs at the new revision.
+fn relock_git_source(
+    lockfile_path: &str,
+    content: &str,
+    lockfile: &Lockfile,
+    gemfile: Option<&lode::Gemfile>,
+    source_name: &str,
+    quiet: bool,
+) -> Result<()> {
+    let config = lode::Config::load().context("Failed to load configuration")?;
+    let git_manager =
+        lode::git::GitManager::new(lode::config::cache_dir(Some(&config))?.join("git"))
+            .context("Failed to create git manager")?;
+
+    let repositories: Vec<&str> = lockfile
+        .git_gems
+        .iter()
+        .filter(|g| git_source_matches(g, source_name))
+        .map(|g| g.repository.as_str())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let mut updated = content.to_string();
+    for repository in repositories {
+        let gems: Vec<&lode::GitGemSpec> = lockfile
+            .git_gems
+            .iter()
+            .filter(|g| g.repository == repository)
+            .collect();
+        let Some(first) = gems.first() else {
+            continue;
+        };
+
+        // Gemfile declarations win so a changed branch/tag is picked up
+        let declared = gemfile.and_then(|gf| {
+            gf.gems
+                .iter()
+                .find(|d| gems.iter().any(|g| g.name == d.name) && d.git.is_some())
+        });
+        let branch = declared.map_or(first.branch.as_deref(), |d| d.branch.as_deref());
+        let tag = declared.map_or(first.tag.as_deref(), |d| d.tag.as_deref());
+
+        let revision = match declared.and_then(|d| d.ref_.as_deref()) {
+            Some(pinned) => pinned.to_string(),
+            None => git_manager
+                .resolve_revision(repository, branch, tag)
+                .with_context(|| format!("Failed to fetch {repository}"))?,
+        };
+
+        if revision == first.revision {
+            if !quiet {
+                println!("{repository} is already at {}", short_sha(&revision));
+            }
+            continue;
+        }
+
+        let checkout = git_manager
+            .clone_and_checkout(repository, &revision)
+            .with_context(|| format!("Failed to check out {repository} @ {revision}"))?;
+
+        let mut versions = std::collections::BTreeMap::new();
+        for gem in &gems {
+            if let Some(version) = lode::git::GitManager::gemspec_version(&checkout, &gem.name) {
+                if !quiet && version != gem.version {
+                    println!(
+                        "  • {}  {} -> {}",
+                        gem.name,
+                        lode::reporter::version(&gem.version),
+                        lode::reporter::version(&version)
+                    );
+                }
+                versions.insert(gem.name.clone(), version);
+            }
+        }
+
+        if !quiet {
+            println!(
+                "Re-locked {repository}: {} -> {}",
+                short_sha(&first.revision),
+                short_sha(&revision)
+            );
+        }
+
+        updated = lode::lockfile::update_git_source(&updated, repository, &revision, &versions);
+    }
+
+    if updated != content {
+        fs::write(lockfile_path, updated)
+            .with_context(|| format!("Failed to write lockfile: {lockfile_path}"))?;
+        if !quiet {
+            println!("\nUpdate complete!");
+            println!("   Run `lode install` to install the updated gems");
+        }
+    }
+
+    Ok(())
+}
+
+/// First 8 characters of a commit SHA for display
+fn short_sha(revision: &str) -> &str {
+    revision.get(..8).unwrap_or(revision)
+}
+
 /// Find a conservative update (prefers minimal version changes)
 ///
 /// NOTE: This does NOT match Bundler's --conservative behavior exactly.
@@ -706,6 +1686,170 @@ fn parse_version_parts(version: &str) -> Vec<u32> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn git_source_matches_gem_or_repository_name() {
+        let gem = lode::GitGemSpec {
+            name: "activerecord".to_string(),
+            version: "8.0.0".to_string(),
+            repository: "https://github.com/rails/rails.git".to_string(),
+            revision: "abc".to_string(),
+            branch: Some("main".to_string()),
+            tag: None,
+            groups: vec![],
+        };
+        assert!(git_source_matches(&gem, "activerecord"));
+        assert!(git_source_matches(&gem, "rails"));
+        assert!(git_source_matches(
+            &gem,
+            "https://github.com/rails/rails.git"
+        ));
+        assert!(!git_source_matches(&gem, "rack"));
+    }
+
+    #[test]
+    fn unblocked_gems_are_targets_that_move_forward() {
+        let versions = |pairs: &[(&str, &str)]| -> BTreeMap<String, String> {
+            pairs
+                .iter()
+                .map(|(name, version)| ((*name).to_string(), (*version).to_string()))
+                .collect()
+        };
+        let baseline = versions(&[("rails", "7.0.8"), ("rack", "2.2.9"), ("puma", "6.4.2")]);
+        let relaxed = versions(&[("rails", "7.1.3"), ("rack", "3.1.7"), ("puma", "6.4.2")]);
+
+        assert_eq!(
+            unblocked_gems(&["rails", "puma"], &baseline, &relaxed),
+            [(
+                "rails".to_string(),
+                "7.0.8".to_string(),
+                "7.1.3".to_string()
+            )]
+        );
+        assert!(unblocked_gems(&["puma", "missing"], &baseline, &relaxed).is_empty());
+    }
+
+    fn locked(gems: &[(&str, &str)]) -> Lockfile {
+        let mut lockfile = Lockfile::new();
+        lockfile.gems = gems
+            .iter()
+            .map(|(name, version)| {
+                lode::GemSpec::new(
+                    (*name).to_string(),
+                    (*version).to_string(),
+                    None,
+                    Vec::new(),
+                    Vec::new(),
+                )
+            })
+            .collect();
+        lockfile
+    }
+
+    #[test]
+    fn bot_groups_by_gem_group_or_security() {
+        let gemfile = lode::Gemfile::parse(
+            "source \"https://rubygems.org\"\n\
+             gem \"rails\"\n\
+             gem \"rspec\", group: :test\n",
+        )
+        .unwrap();
+        let outdated = ["rack".to_string(), "rails".to_string(), "rspec".to_string()];
+
+        let by_gem = group_updates(BotGrouping::Gem, &outdated, &gemfile);
+        assert_eq!(
+            by_gem.keys().collect::<Vec<_>>(),
+            ["rack", "rails", "rspec"]
+        );
+
+        let by_group = group_updates(BotGrouping::Group, &outdated, &gemfile);
+        assert_eq!(by_group.get("default").unwrap(), &["rails"]);
+        assert_eq!(by_group.get("test").unwrap(), &["rspec"]);
+        assert_eq!(by_group.get("dependencies").unwrap(), &["rack"]);
+
+        let security = group_updates(BotGrouping::Security, &outdated, &gemfile);
+        assert_eq!(security.get("security").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn bot_reports_changed_versions() {
+        let old = locked(&[("rack", "3.0.0"), ("rails", "7.1.0"), ("thor", "1.2.0")]);
+        let new = locked(&[("rack", "3.0.9"), ("rails", "7.1.0"), ("zeitwerk", "2.6.0")]);
+        let changes = lockfile_changes(&old, &new);
+
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|change| {
+                (
+                    change.name.as_str(),
+                    change.from.as_deref(),
+                    change.to.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("rack", Some("3.0.0"), Some("3.0.9")),
+                ("thor", Some("1.2.0"), None),
+                ("zeitwerk", None, Some("2.6.0")),
+            ]
+        );
+        assert_eq!(
+            update_title(BotGrouping::Gem, "rack", &changes),
+            "Bump rack from 3.0.0 to 3.0.9 and 2 dependencies"
+        );
+        assert_eq!(
+            update_title(BotGrouping::Gem, "rack", changes.get(..1).unwrap()),
+            "Bump rack from 3.0.0 to 3.0.9"
+        );
+        assert_eq!(
+            update_title(BotGrouping::Group, "test", &changes),
+            "Bump 3 gems in the test group"
+        );
+        assert_eq!(
+            changelog_url("rack", "3.0.9", None),
+            "https://rubygems.org/gems/rack/versions/3.0.9"
+        );
+    }
+
+    #[test]
+    fn security_fix_is_smallest_patched_version_allowed() {
+        let database = AdvisoryDatabase::from_advisories([lode::Advisory {
+            id: "CVE-2024-26146".to_string(),
+            gem: "rack".to_string(),
+            patched_versions: vec!["~> 2.2.9".to_string(), ">= 3.0.9".to_string()],
+            ..lode::Advisory::default()
+        }]);
+        let versions: Vec<String> = ["3.1.0", "3.0.10", "3.0.9", "3.0.8", "2.2.9", "2.2.8"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let fix = |current, requirement| {
+            smallest_fix(&database, "rack", current, &versions, requirement, false)
+        };
+
+        assert_eq!(fix("3.0.0", None), Ok("3.0.9".to_string()));
+        assert_eq!(fix("2.2.8", Some("~> 2.2")), Ok("2.2.9".to_string()));
+        assert_eq!(fix("3.0.0", Some(">= 3.1")), Ok("3.1.0".to_string()));
+        assert_eq!(
+            fix("3.0.0", Some("~> 3.0.0, < 3.0.9")),
+            Err(Unfixable::Constrained {
+                requirement: "~> 3.0.0, < 3.0.9".to_string(),
+                patched: "3.0.9".to_string(),
+            })
+        );
+
+        let unpatched = smallest_fix(
+            &database,
+            "rack",
+            "3.0.0",
+            versions.get(3..).unwrap(),
+            None,
+            false,
+        );
+        assert_eq!(unpatched, Err(Unfixable::Unpatched));
+    }
+
     #[test]
     fn test_is_prerelease() {
         assert!(is_prerelease("1.0.0.alpha"));
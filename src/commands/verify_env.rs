@@ -0,0 +1,76 @@
+//! Verify-env command
+//!
+//! Compare this machine's toolchain against the one recorded at lock time
+
+use anyhow::{Context, Result};
+use lode::{BuildInfo, Lockfile};
+use std::fs;
+use std::path::Path;
+
+/// Record the current toolchain next to the lockfile (`lode lock --emit-metadata`)
+pub(crate) fn emit(lockfile_path: &Path, quiet: bool) -> Result<()> {
+    let lockfile = fs::read_to_string(lockfile_path)
+        .ok()
+        .and_then(|content| Lockfile::parse(&content).ok());
+    let info = BuildInfo::capture(lockfile.as_ref());
+
+    let path = BuildInfo::path_for_lockfile(lockfile_path);
+    info.save(&path)
+        .with_context(|| format!("Failed to write build metadata: {}", path.display()))?;
+
+    if !quiet {
+        println!("Recorded build environment in {}", path.display());
+    }
+    Ok(())
+}
+
+/// Fail when the current toolchain differs from the recorded one
+pub(crate) fn run(lockfile_path: &Path) -> Result<()> {
+    let path = BuildInfo::path_for_lockfile(lockfile_path);
+    let recorded = BuildInfo::load(&path).with_context(|| {
+        format!(
+            "No build metadata at {}. Run `lode lock --emit-metadata` first",
+            path.display()
+        )
+    })?;
+
+    let lockfile = fs::read_to_string(lockfile_path)
+        .ok()
+        .and_then(|content| Lockfile::parse(&content).ok());
+    let differences = recorded.differences(&BuildInfo::capture(lockfile.as_ref()));
+
+    if differences.is_empty() {
+        println!("Build environment matches {}", path.display());
+        return Ok(());
+    }
+
+    eprintln!("Build environment differs from {}:", path.display());
+    for difference in &differences {
+        eprintln!("  {}:", difference.component);
+        eprintln!(
+            "    recorded: {}",
+            difference.recorded.as_deref().unwrap_or("(none)")
+        );
+        eprintln!(
+            "    current:  {}",
+            difference.current.as_deref().unwrap_or("(none)")
+        );
+    }
+    anyhow::bail!(
+        "{} toolchain component(s) differ from the locked build environment",
+        differences.len()
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "Tests can panic")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_without_metadata_fails() {
+        let temp = tempfile::tempdir().unwrap();
+        let err = run(&temp.path().join("Gemfile.lock")).unwrap_err();
+        assert!(err.to_string().contains("--emit-metadata"));
+    }
+}
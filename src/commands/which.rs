@@ -3,16 +3,21 @@
 //! Find the location of a required library file
 
 use anyhow::{Context, Result};
-use lode::{Config, config, get_system_gem_dir};
+use lode::gem_store::{GemPath, GemQuery};
+use lode::{Config, config};
 use std::path::Path;
 
 /// Find the location of a library file.
 ///
 /// Searches in order:
 /// 1. Vendor gems (from lockfile)
-/// 2. System gems
+/// 2. Every `GEM_PATH` directory
 /// 3. Ruby standard library
-pub(crate) fn run(file_name: &str) -> Result<()> {
+///
+/// Within each gem directory only the newest version of each gem counts,
+/// unless `all_paths` is set: then every copy in every directory is
+/// listed, shadowed ones included.
+pub(crate) fn run(file_name: &str, all_paths: bool) -> Result<()> {
     // Normalize file name - add .rb extension if not present
     let search_name = if Path::new(file_name)
         .extension()
@@ -26,78 +31,68 @@ pub(crate) fn run(file_name: &str) -> Result<()> {
     // Load configuration
     let config = Config::load().context("Failed to load configuration")?;
 
-    // Build search paths in priority order
-    let mut search_paths = Vec::new();
-
-    // 1. Vendor directory (project gems)
-    if let Ok(vendor_dir) = config::vendor_dir(Some(&config)) {
-        // Detect Ruby version from lockfile if available
-        let ruby_version = if Path::new("Gemfile.lock").exists() {
-            let lockfile_content =
-                std::fs::read_to_string("Gemfile.lock").context("Failed to read Gemfile.lock")?;
-            let lockfile =
-                lode::Lockfile::parse(&lockfile_content).context("Failed to parse Gemfile.lock")?;
-            lockfile.ruby_version
-        } else {
-            None
-        };
-
-        let ruby_ver = config::ruby_version(ruby_version.as_deref());
-        let vendor_lib_dir = vendor_dir.join("ruby").join(&ruby_ver).join("gems");
-
-        if vendor_lib_dir.exists() {
-            // Add lib directories for all installed gems
-            if let Ok(entries) = std::fs::read_dir(&vendor_lib_dir) {
-                for entry in entries.flatten() {
-                    let gem_dir = entry.path();
-                    let lib_dir = gem_dir.join("lib");
-                    if lib_dir.is_dir() {
-                        search_paths.push(lib_dir);
-                    }
-                }
-            }
-        }
-    }
+    // Detect Ruby version from lockfile if available
+    let lockfile_ruby = if Path::new("Gemfile.lock").exists() {
+        let lockfile_content =
+            std::fs::read_to_string("Gemfile.lock").context("Failed to read Gemfile.lock")?;
+        let lockfile =
+            lode::Lockfile::parse(&lockfile_content).context("Failed to parse Gemfile.lock")?;
+        lockfile.ruby_version
+    } else {
+        None
+    };
 
     // Get ruby version for system and standard library paths
     let ruby_ver = config::ruby_version(None);
 
-    // 2. System gem directory
-    let system_gem_dir = get_system_gem_dir(&ruby_ver);
-    if system_gem_dir.exists()
-        && let Ok(entries) = std::fs::read_dir(&system_gem_dir)
-    {
-        for entry in entries.flatten() {
-            let gem_dir = entry.path();
-            let lib_dir = gem_dir.join("lib");
-            if lib_dir.is_dir() {
-                search_paths.push(lib_dir);
-            }
-        }
-    }
+    // Gem directories in priority order: the project's vendor directory,
+    // then GEM_PATH
+    let gem_path = config::vendor_dir(Some(&config)).map_or_else(
+        |_| GemPath::from_env(&ruby_ver),
+        |vendor_dir| {
+            let bundle_ruby = config::ruby_version(lockfile_ruby.as_deref());
+            GemPath::for_bundle(
+                vendor_dir.join("ruby").join(&bundle_ruby).join("gems"),
+                &ruby_ver,
+            )
+        },
+    );
+
+    // `require` activates the newest version of each gem
+    let query = if all_paths {
+        GemQuery::new()
+    } else {
+        GemQuery::new().latest_only()
+    };
+    let mut search_paths: Vec<_> = gem_path
+        .stores()
+        .iter()
+        .flat_map(|store| store.query(&query).unwrap_or_default())
+        .map(|gem| gem.path.join("lib"))
+        .filter(|lib_dir| lib_dir.is_dir())
+        .collect();
 
     // 3. Ruby standard library paths
     let std_lib_paths = lode::get_standard_gem_paths(&ruby_ver);
     search_paths.extend(std_lib_paths);
 
     // Search for the file
+    let mut found = false;
     for lib_path in &search_paths {
         let candidate = lib_path.join(&search_name);
         if candidate.exists() {
             println!("{}", candidate.display());
-            return Ok(());
-        }
-
-        // Also check for nested paths (e.g., "rake/file_list" -> "lib/rake/file_list.rb")
-        if search_name.contains('/') {
-            let nested = lib_path.join(&search_name);
-            if nested.exists() {
-                println!("{}", nested.display());
+            if !all_paths {
                 return Ok(());
             }
+            found = true;
         }
     }
 
+    if found {
+        return Ok(());
+    }
+
     // Not found
     anyhow::bail!("Can't find file '{search_name}' in gem paths");
 }
@@ -0,0 +1,42 @@
+//! With-unbundled-env command
+//!
+//! Run a command outside the bundle from inside `lode exec` or `lode shell`
+
+use anyhow::{Context, Result};
+use std::env;
+use std::process::Command;
+
+/// Run `command` with the environment from before the bundle was activated
+///
+/// Variables the session changed get their `BUNDLER_ORIG_*` values back
+/// and Bundler and Lode variables are removed; see
+/// [`lode::env_preserver::unbundled_env`].
+pub(crate) fn run(command: &[String]) -> Result<()> {
+    let (program, args) = command
+        .split_first()
+        .context("No command specified. Usage: lode with-unbundled-env -- <command> [args...]")?;
+
+    let status = Command::new(program)
+        .args(args)
+        .env_clear()
+        .envs(lode::env_preserver::unbundled_env(env::vars_os().collect()))
+        .status()
+        .with_context(|| format!("Failed to execute command: {program}"))?;
+
+    if !status.success() {
+        std::process::exit(lode::exit_code::child_exit_code(status));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requires_a_command() {
+        let error = run(&[]).unwrap_err();
+        assert!(error.to_string().contains("No command specified"));
+    }
+}
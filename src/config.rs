@@ -2,15 +2,32 @@
 //!
 //! Handles reading and writing lode's TOML configuration files from project
 //! and global locations.
+//!
+//! Like the `operating_system.rb` hook distros use to patch `RubyGems`
+//! defaults, two site-wide files are layered under those: the vendor
+//! defaults a package ships ([`VENDOR_CONFIG`], or `LODE_VENDOR_CONFIG` at
+//! build time) and the administrator's `/etc/lode/config.toml`. Keys set in
+//! a later layer replace the same keys of an earlier one, tables merge key
+//! by key, and the project or user config comes last.
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Distro defaults, read first; packagers can set `LODE_VENDOR_CONFIG`
+/// when building to move it
+pub const VENDOR_CONFIG: &str = match option_env!("LODE_VENDOR_CONFIG") {
+    Some(path) => path,
+    None => "/usr/share/lode/config.toml",
+};
+
+/// Site-wide config, read after the vendor defaults
+pub const SYSTEM_CONFIG: &str = "/etc/lode/config.toml";
+
 /// Application configuration loaded from TOML files
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct Config {
@@ -26,9 +43,155 @@ pub struct Config {
     #[serde(default)]
     pub gemfile: Option<String>,
 
+    /// Directory for generated binstubs (`lode binstubs --path`)
+    #[serde(default)]
+    pub bin_dir: Option<String>,
+
     /// Gem sources with optional fallbacks
     #[serde(default)]
     pub gem_sources: Vec<GemSource>,
+
+    /// Local git checkouts overriding git gems (`local.GEM_NAME`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub local: BTreeMap<String, String>,
+
+    /// Allow local overrides whose branch differs from the Gemfile
+    #[serde(default)]
+    pub disable_local_branch_check: Option<bool>,
+
+    /// Hide the post-install list of gems requesting funding
+    #[serde(default)]
+    pub ignore_funding_requests: Option<bool>,
+
+    /// Skip the post-install check for deprecated and unmaintained gems
+    #[serde(default)]
+    pub silence_deprecations: Option<bool>,
+
+    /// Refuse Gemfiles with several global sources instead of warning
+    #[serde(default)]
+    pub disable_multisource: Option<bool>,
+
+    /// Keep the bundle isolated from user and system gems, as after
+    /// `lode install --path`
+    #[serde(default)]
+    pub disable_shared_gems: Option<bool>,
+
+    /// Gem name prefixes that must resolve from a given source
+    /// (`source_pin.PREFIX`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub source_pins: BTreeMap<String, String>,
+
+    /// Hosts or URL prefixes allowed to serve gems and git repositories
+    /// over plain `http://`, for air-gapped mirrors
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub insecure_sources: Vec<String>,
+
+    /// Warn about other plain `http://` sources instead of refusing them
+    #[serde(default)]
+    pub allow_insecure_sources: Option<bool>,
+
+    /// Refuse gems with files whose names differ only in case instead of
+    /// warning
+    #[serde(default)]
+    pub fail_on_case_collisions: Option<bool>,
+
+    /// Ceiling on requests per second to each gem server, for proxies that
+    /// throttle harder than the server does
+    #[serde(default)]
+    pub max_requests_per_second: Option<f64>,
+
+    /// Extra arguments for a gem's `extconf.rb` (`build.GEM_NAME`), like
+    /// `bundle config build.GEM_NAME`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub build: BTreeMap<String, String>,
+
+    /// Gems `update` and `outdated` skip and `lock --update` keeps at their
+    /// locked version (`hold.GEM_NAME`, set by `lode hold`), with the reason
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hold: BTreeMap<String, String>,
+
+    /// Extra environment variables `lode exec --isolate` removes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exec_unset_env: Vec<String>,
+
+    /// Message language (`en` or `ja`), overriding `LANG`
+    #[serde(default)]
+    pub locale: Option<String>,
+
+    /// Gems to resolve and install from their `ruby` platform variant
+    /// instead of a precompiled one (`force_ruby_platform.GEM_NAME`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub force_ruby_platform: BTreeMap<String, bool>,
+
+    /// Named group selections for `lode install --group-profile`
+    /// (`profile.NAME.with` and `profile.NAME.without`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, GroupProfile>,
+
+    /// Settings for the `lode gem-*` commands (`gem.KEY`)
+    #[serde(default, skip_serializing_if = "GemSettings::is_empty")]
+    pub gem: GemSettings,
+
+    /// `.gemrc` settings, loaded by [`Config::load_with_options`]
+    #[serde(skip)]
+    pub gemrc: crate::gemrc::Gemrc,
+}
+
+/// `[gem]` table: defaults for the `lode gem-*` commands
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default)]
+pub struct GemSettings {
+    /// Skip rdoc and ri generation, like `gem: --no-document` in `.gemrc`
+    #[serde(default)]
+    pub no_document: Option<bool>,
+}
+
+impl GemSettings {
+    /// Whether no setting is present
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.no_document.is_none()
+    }
+}
+
+/// `[profiles.NAME]` table: which Gemfile groups an install includes
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq, Eq)]
+pub struct GroupProfile {
+    /// Optional groups to install, like `BUNDLE_WITH`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub with: Vec<String>,
+
+    /// Groups to skip, like `BUNDLE_WITHOUT`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub without: Vec<String>,
+}
+
+impl GroupProfile {
+    /// Split a `profile.NAME.with` or `profile.NAME.without` config key
+    /// into the profile name and `with` or `without`
+    #[must_use]
+    pub fn parse_key(key: &str) -> Option<(&str, &str)> {
+        let (name, field) = key.strip_prefix("profile.")?.rsplit_once('.')?;
+        (!name.is_empty() && matches!(field, "with" | "without")).then_some((name, field))
+    }
+
+    /// Groups in `field` (`with` or `without`)
+    #[must_use]
+    pub fn groups(&self, field: &str) -> &[String] {
+        if field == "with" {
+            &self.with
+        } else {
+            &self.without
+        }
+    }
+
+    /// Mutable groups in `field` (`with` or `without`)
+    pub fn groups_mut(&mut self, field: &str) -> &mut Vec<String> {
+        if field == "with" {
+            &mut self.with
+        } else {
+            &mut self.without
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -59,6 +222,8 @@ pub struct BundleConfig {
     pub without: Option<Vec<String>>,
     /// Groups to include (`BUNDLE_WITH`)
     pub with: Option<Vec<String>>,
+    /// The only groups to install (`BUNDLE_ONLY`)
+    pub only: Option<Vec<String>>,
     /// Cache all gems including path/git (`BUNDLE_CACHE_ALL`)
     pub cache_all: Option<bool>,
     /// Cache gems for all platforms (`BUNDLE_CACHE_ALL_PLATFORMS`)
@@ -123,60 +288,87 @@ pub struct BundleConfig {
     pub ssl_client_cert: Option<String>,
     /// SSL verify mode (`BUNDLE_SSL_VERIFY_MODE`)
     pub ssl_verify_mode: Option<String>,
+    /// Local git overrides by gem name (`BUNDLE_LOCAL__<GEM>`)
+    pub local_overrides: BTreeMap<String, String>,
+    /// Skip branch verification for local overrides (`BUNDLE_DISABLE_LOCAL_BRANCH_CHECK`)
+    pub disable_local_branch_check: Option<bool>,
+    /// Reject multiple global sources (`BUNDLE_DISABLE_MULTISOURCE`)
+    pub disable_multisource: Option<bool>,
 }
 
 impl Config {
     /// Load configuration from TOML files.
-    /// Priority: ./.lode.toml -> ~/.config/lode/config.toml
+    /// Priority: ./.lode.toml -> ~/.config/lode/config.toml, over the
+    /// site-wide files from [`Config::site_config_paths`]
     ///
     /// # Errors
     ///
     /// Returns an error if config file parsing fails.
     pub fn load() -> Result<Self> {
-        Self::load_with_options(None, false)
+        let mut table = toml::Table::new();
+        for path in Self::site_config_paths() {
+            if !path.exists() {
+                continue;
+            }
+            match Self::load_table(&path)
+                .and_then(|site| Self::from_table(site.clone()).map(|_| site))
+            {
+                Ok(site) => merge_tables(&mut table, site),
+                Err(e) => crate::warn!("Ignoring {}: {e}", path.display()),
+            }
+        }
+
+        // Project config first, else user config
+        let user_paths = std::iter::once(PathBuf::from(".lode.toml"))
+            .chain(Self::user_config_dir().map(|dir| dir.join("config.toml")));
+        if let Some(user) = user_paths
+            .filter_map(|path| Self::load_table(path).ok())
+            .find(|user| Self::from_table(user.clone()).is_ok())
+        {
+            merge_tables(&mut table, user);
+        }
+
+        Self::from_table(table)
+    }
+
+    /// Site-wide config files in load order: vendor defaults, then
+    /// `/etc/lode/config.toml` (or `LODE_SYSTEM_CONFIG`)
+    #[must_use]
+    pub fn site_config_paths() -> Vec<PathBuf> {
+        vec![
+            PathBuf::from(VENDOR_CONFIG),
+            crate::env_vars::lode_system_config()
+                .map_or_else(|| PathBuf::from(SYSTEM_CONFIG), PathBuf::from),
+        ]
     }
 
-    /// Load configuration with custom options.
+    /// Load configuration for a `gem-*` command.
+    ///
+    /// lode's own config is loaded as by [`Config::load`], together with the
+    /// `.gemrc` in [`Config::gemrc`].
     ///
     /// # Arguments
-    /// * `custom_path` - Optional custom path to config file (overrides defaults)
-    /// * `skip_rc` - If true, skip loading config files (return default config)
+    /// * `gemrc_path` - `--config-file`, replacing `~/.gemrc`
+    /// * `skip_rc` - `--norc`, skip loading `.gemrc`
     ///
     /// # Errors
     ///
-    /// Returns an error if config file reading or parsing fails.
-    pub fn load_with_options(custom_path: Option<&str>, skip_rc: bool) -> Result<Self> {
-        // If skip_rc is set, return default config
-        if skip_rc {
-            return Ok(Self::default());
-        }
-
-        // If custom path provided, load from that
-        if let Some(path) = custom_path {
-            return Self::load_from(path);
-        }
-
-        // Try local config first
-        if let Ok(config) = Self::load_from(".lode.toml") {
-            return Ok(config);
-        }
-
-        // Try user config
-        if let Some(config_dir) = Self::user_config_dir() {
-            let config_path = config_dir.join("config.toml");
-            if let Ok(config) = Self::load_from(&config_path) {
-                return Ok(config);
-            }
-        }
+    /// Returns an error if a config file cannot be read or parsed.
+    pub fn load_with_options(gemrc_path: Option<&str>, skip_rc: bool) -> Result<Self> {
+        let mut config = Self::load()?;
+        config.gemrc = crate::gemrc::Gemrc::load(gemrc_path, skip_rc)?;
+        Ok(config)
+    }
 
-        // Return default config
-        Ok(Self::default())
+    fn load_table<P: AsRef<Path>>(path: P) -> Result<toml::Table> {
+        let contents = fs::read_to_string(&path)?;
+        let table: toml::Table = toml::from_str(&contents)?;
+        crate::debug!("loaded config from {}", path.as_ref().display());
+        Ok(table)
     }
 
-    fn load_from<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let contents = fs::read_to_string(path)?;
-        let config: Self = toml::from_str(&contents)?;
-        Ok(config)
+    fn from_table(table: toml::Table) -> Result<Self> {
+        Ok(toml::Value::Table(table).try_into()?)
     }
 
     fn user_config_dir() -> Option<PathBuf> {
@@ -190,6 +382,21 @@ impl Config {
     }
 }
 
+/// Merge `overlay` into `base`: tables merge key by key, anything else in
+/// `overlay` replaces what `base` had
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => {
+                merge_tables(base, overlay);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
 impl BundleConfig {
     /// Load Bundler configuration from config files
     ///
@@ -278,6 +485,7 @@ impl BundleConfig {
                 "BUNDLE_DEPLOYMENT" => config.deployment = parse_bool_value(&value),
                 "BUNDLE_WITHOUT" => config.without = parse_list_value(&value),
                 "BUNDLE_WITH" => config.with = parse_list_value(&value),
+                "BUNDLE_ONLY" => config.only = parse_list_value(&value),
                 "BUNDLE_CACHE_ALL" => config.cache_all = parse_bool_value(&value),
                 "BUNDLE_CACHE_ALL_PLATFORMS" => {
                     config.cache_all_platforms = parse_bool_value(&value);
@@ -328,8 +536,22 @@ impl BundleConfig {
                 "BUNDLE_SSL_CA_CERT" => config.ssl_ca_cert = parse_string_value(&value),
                 "BUNDLE_SSL_CLIENT_CERT" => config.ssl_client_cert = parse_string_value(&value),
                 "BUNDLE_SSL_VERIFY_MODE" => config.ssl_verify_mode = parse_string_value(&value),
-                // Ignore unknown keys for forward compatibility
-                _ => {}
+                "BUNDLE_DISABLE_LOCAL_BRANCH_CHECK" => {
+                    config.disable_local_branch_check = parse_bool_value(&value);
+                }
+                "BUNDLE_DISABLE_MULTISOURCE" => {
+                    config.disable_multisource = parse_bool_value(&value);
+                }
+                // `bundle config local.rack PATH` is stored as BUNDLE_LOCAL__RACK
+                other => {
+                    if let Some(gem) = other.strip_prefix("BUNDLE_LOCAL__")
+                        && let Some(path) = parse_string_value(&value)
+                    {
+                        config
+                            .local_overrides
+                            .insert(gem.to_lowercase().replace("___", "-"), path);
+                    }
+                }
             }
         }
 
@@ -364,6 +586,9 @@ impl BundleConfig {
         if other.with.is_some() {
             self.with = other.with;
         }
+        if other.only.is_some() {
+            self.only = other.only;
+        }
         if other.cache_all.is_some() {
             self.cache_all = other.cache_all;
         }
@@ -460,6 +685,13 @@ impl BundleConfig {
         if other.ssl_verify_mode.is_some() {
             self.ssl_verify_mode = other.ssl_verify_mode;
         }
+        self.local_overrides.extend(other.local_overrides);
+        if other.disable_local_branch_check.is_some() {
+            self.disable_local_branch_check = other.disable_local_branch_check;
+        }
+        if other.disable_multisource.is_some() {
+            self.disable_multisource = other.disable_multisource;
+        }
         self
     }
 }
@@ -521,7 +753,7 @@ fn parse_list_value(value: &serde_yaml::Value) -> Option<Vec<String>> {
     )
 }
 
-/// Resolve vendor directory with Bundler 4 priority: Config -> env -> .bundle/config -> system gem dir.
+/// Resolve vendor directory with Bundler 4 priority: Config -> env -> .bundle/config -> deployment -> system gem dir.
 ///
 /// # Errors
 ///
@@ -546,10 +778,246 @@ pub fn vendor_dir(config: Option<&Config>) -> Result<PathBuf> {
         return Ok(PathBuf::from(path));
     }
 
-    // 4. Fall back to system gem directory
+    // 4. Deployment installs into the project, like `bundle install --deployment`
+    if deployment_enabled() {
+        return Ok(PathBuf::from(DEPLOYMENT_PATH));
+    }
+
+    // 5. Fall back to system gem directory
     system_gem_dir()
 }
 
+/// Install path used in deployment mode when no path is configured
+pub const DEPLOYMENT_PATH: &str = "vendor/bundle";
+
+/// Whether deployment mode is on, from `BUNDLE_DEPLOYMENT` or `.bundle/config`
+///
+/// Deployment installs into [`DEPLOYMENT_PATH`], requires an up-to-date
+/// lockfile and refuses commands that would edit the Gemfile or lockfile.
+#[must_use]
+pub fn deployment_enabled() -> bool {
+    crate::env_vars::bundle_deployment()
+        || BundleConfig::load()
+            .ok()
+            .and_then(|bundle| bundle.deployment)
+            .unwrap_or(false)
+}
+
+/// Whether the bundle is frozen, decided the way Bundler does
+///
+/// `--frozen`/`--no-frozen` wins, then `frozen` from `.bundle/config`, then
+/// `BUNDLE_FROZEN`, then `~/.bundle/config`. The first level that sets it
+/// decides, even to `false`, so `BUNDLE_FROZEN=false` lifts a global
+/// `frozen: true` for one run. Only when nothing sets it does deployment
+/// mode freeze the bundle.
+#[must_use]
+pub fn frozen_enabled(cli: Option<bool>) -> bool {
+    let (local, global) = if crate::env_vars::bundle_ignore_config() {
+        (None, None)
+    } else {
+        let frozen = |config: Result<Option<BundleConfig>>| config.ok().flatten()?.frozen;
+        (
+            frozen(BundleConfig::load_local()),
+            frozen(BundleConfig::load_global()),
+        )
+    };
+    first_setting(&[cli, local, crate::env_vars::bundle_frozen(), global])
+        .unwrap_or_else(deployment_enabled)
+}
+
+/// The highest-priority level that has a value
+fn first_setting(levels: &[Option<bool>]) -> Option<bool> {
+    levels.iter().find_map(|level| *level)
+}
+
+/// Local git overrides from lode config and `.bundle/config`
+///
+/// Maps gem names to checkout directories, with `~` expanded. Lode config
+/// entries take precedence over Bundler's `BUNDLE_LOCAL__<GEM>` keys.
+#[must_use]
+pub fn local_overrides(config: Option<&Config>) -> BTreeMap<String, PathBuf> {
+    let mut overrides: BTreeMap<String, String> = BundleConfig::load()
+        .map(|bundle| bundle.local_overrides)
+        .unwrap_or_default();
+    if let Some(config) = config {
+        overrides.extend(config.local.clone());
+    }
+
+    overrides
+        .into_iter()
+        .map(|(gem, path)| (gem, expand_home(&path)))
+        .collect()
+}
+
+/// Whether local overrides may use a branch other than the Gemfile's
+#[must_use]
+pub fn local_branch_check_disabled(config: Option<&Config>) -> bool {
+    config
+        .and_then(|c| c.disable_local_branch_check)
+        .or_else(|| {
+            BundleConfig::load()
+                .ok()
+                .and_then(|bundle| bundle.disable_local_branch_check)
+        })
+        .unwrap_or(false)
+}
+
+/// Whether install should skip listing gems that request funding
+///
+/// Lode config wins, then `BUNDLE_IGNORE_FUNDING_REQUESTS` and `.bundle/config`.
+#[must_use]
+pub fn funding_requests_ignored(config: Option<&Config>) -> bool {
+    config
+        .and_then(|c| c.ignore_funding_requests)
+        .unwrap_or_else(|| {
+            crate::env_vars::bundle_ignore_funding_requests()
+                || BundleConfig::load()
+                    .ok()
+                    .and_then(|bundle| bundle.ignore_funding_requests)
+                    .unwrap_or(false)
+        })
+}
+
+/// Whether a Gemfile with several global sources is an error rather than a
+/// warning
+///
+/// Lode config wins, then `BUNDLE_DISABLE_MULTISOURCE` and `.bundle/config`.
+#[must_use]
+pub fn multisource_disabled(config: Option<&Config>) -> bool {
+    config
+        .and_then(|c| c.disable_multisource)
+        .unwrap_or_else(|| {
+            crate::env_vars::bundle_disable_multisource()
+                || BundleConfig::load()
+                    .ok()
+                    .and_then(|bundle| bundle.disable_multisource)
+                    .unwrap_or(false)
+        })
+}
+
+/// Whether the bundle runs without user and system gems on `GEM_PATH`
+///
+/// Lode config wins, then `BUNDLE_DISABLE_SHARED_GEMS` and `.bundle/config`.
+#[must_use]
+pub fn shared_gems_disabled(config: Option<&Config>) -> bool {
+    config
+        .and_then(|c| c.disable_shared_gems)
+        .unwrap_or_else(|| {
+            crate::env_vars::bundle_disable_shared_gems()
+                || BundleConfig::load()
+                    .ok()
+                    .and_then(|bundle| bundle.disable_shared_gems)
+                    .unwrap_or(false)
+        })
+}
+
+/// Whether a gem with files differing only in case fails to install
+/// rather than installing with a warning
+#[must_use]
+pub fn case_collisions_fatal(config: Option<&Config>) -> bool {
+    config
+        .and_then(|c| c.fail_on_case_collisions)
+        .unwrap_or(false)
+}
+
+/// Ceiling on requests per second to each gem server
+///
+/// `LODE_MAX_REQUESTS_PER_SECOND` wins over lode config.
+#[must_use]
+pub fn max_requests_per_second(config: Option<&Config>) -> Option<f64> {
+    crate::env_vars::lode_max_requests_per_second()
+        .or_else(|| config.and_then(|c| c.max_requests_per_second))
+}
+
+/// Extra `extconf.rb` arguments for `gem`, split on whitespace
+#[must_use]
+pub fn build_flags(config: Option<&Config>, gem: &str) -> Vec<String> {
+    config
+        .and_then(|c| c.build.get(gem))
+        .map(|flags| flags.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Whether install should skip reporting deprecated and unmaintained gems
+///
+/// Lode config wins, then `BUNDLE_SILENCE_DEPRECATIONS` and `.bundle/config`.
+#[must_use]
+pub fn deprecations_silenced(config: Option<&Config>) -> bool {
+    config
+        .and_then(|c| c.silence_deprecations)
+        .unwrap_or_else(|| {
+            crate::env_vars::bundle_silence_deprecations()
+                || BundleConfig::load()
+                    .ok()
+                    .and_then(|bundle| bundle.silence_deprecations)
+                    .unwrap_or(false)
+        })
+}
+
+/// Whether gem installs should skip documentation by default
+///
+/// Only lode's `gem.no_document` is consulted here; `.gemrc` defaults are
+/// handled by [`crate::docs::DocPolicy::resolve`].
+#[must_use]
+pub fn no_document(config: Option<&Config>) -> bool {
+    config.and_then(|c| c.gem.no_document).unwrap_or(false)
+}
+
+/// Gems forced to their `ruby` platform variant
+///
+/// Set per gem with `force_ruby_platform.GEM_NAME`, for precompiled gems
+/// that fail to load on this system (for example against an older glibc).
+#[must_use]
+pub fn ruby_platform_gems(config: Option<&Config>) -> BTreeSet<String> {
+    config
+        .into_iter()
+        .flat_map(|c| &c.force_ruby_platform)
+        .filter(|(_, forced)| **forced)
+        .map(|(gem, _)| gem.clone())
+        .collect()
+}
+
+/// Held gems and why they are held (empty when no reason was given)
+#[must_use]
+pub fn held_gems(config: Option<&Config>) -> BTreeMap<String, String> {
+    config.map(|c| c.hold.clone()).unwrap_or_default()
+}
+
+/// The group profile called `name`
+///
+/// # Errors
+///
+/// Returns an error naming the defined profiles if `name` is not one.
+pub fn group_profile(config: Option<&Config>, name: &str) -> Result<GroupProfile> {
+    let profiles = config.map(|c| &c.profiles);
+    if let Some(profile) = profiles.and_then(|profiles| profiles.get(name)) {
+        return Ok(profile.clone());
+    }
+    let defined: Vec<&str> = profiles
+        .into_iter()
+        .flat_map(BTreeMap::keys)
+        .map(String::as_str)
+        .collect();
+    if defined.is_empty() {
+        anyhow::bail!(
+            "Unknown group profile '{name}'; define one with `lode config profile.{name}.without GROUPS`"
+        );
+    }
+    anyhow::bail!(
+        "Unknown group profile '{name}' (defined: {})",
+        defined.join(", ")
+    )
+}
+
+/// Expand a leading `~` to the home directory
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ if path == "~" => dirs::home_dir().unwrap_or_else(|| PathBuf::from(path)),
+        _ => PathBuf::from(path),
+    }
+}
+
 /// Resolve cache directory: `BUNDLE_USER_CACHE` env -> Config -> platform cache dir.
 ///
 /// # Errors
@@ -747,6 +1215,51 @@ mod tests {
             assert!(config.vendor_dir.is_none());
         }
 
+        #[test]
+        fn group_profiles_from_toml() -> Result<()> {
+            let config: Config = toml::from_str(
+                r#"
+[profiles.ci]
+without = ["development", "test"]
+
+[profiles.full]
+with = ["docs"]
+"#,
+            )?;
+            assert_eq!(
+                group_profile(Some(&config), "ci")?.without,
+                ["development", "test"]
+            );
+            assert_eq!(group_profile(Some(&config), "full")?.with, ["docs"]);
+            let unknown = group_profile(Some(&config), "nightly").unwrap_err();
+            assert!(unknown.to_string().contains("defined: ci, full"));
+
+            assert_eq!(
+                GroupProfile::parse_key("profile.ci.without"),
+                Some(("ci", "without"))
+            );
+            assert_eq!(GroupProfile::parse_key("profile.ci.groups"), None);
+            assert_eq!(GroupProfile::parse_key("profile.with"), None);
+            Ok(())
+        }
+
+        #[test]
+        fn ruby_platform_gems_from_toml() -> Result<()> {
+            let config: Config = toml::from_str(
+                r"
+[force_ruby_platform]
+nokogiri = true
+grpc = false
+",
+            )?;
+            assert_eq!(
+                ruby_platform_gems(Some(&config)),
+                BTreeSet::from(["nokogiri".to_string()])
+            );
+            assert!(ruby_platform_gems(None).is_empty());
+            Ok(())
+        }
+
         #[test]
         fn load_from_toml() -> Result<()> {
             let temp_dir = tempfile::tempdir()?;
@@ -764,7 +1277,7 @@ fallback = "https://mirror.example.com"
 "#,
             )?;
 
-            let config = Config::load_from(&config_path)?;
+            let config = Config::from_table(Config::load_table(&config_path)?)?;
             assert_eq!(config.vendor_dir, Some("/custom/vendor".to_string()));
             assert_eq!(config.cache_dir, Some("/custom/cache".to_string()));
             assert_eq!(config.gem_sources.len(), 1);
@@ -778,6 +1291,45 @@ fallback = "https://mirror.example.com"
 
             Ok(())
         }
+
+        #[test]
+        fn later_layers_override_site_defaults() -> Result<()> {
+            let mut table: toml::Table = toml::from_str(
+                r#"
+vendor_dir = "/usr/lib/ruby/vendor"
+max_requests_per_second = 2.0
+
+[build]
+nokogiri = "--use-system-libraries"
+pg = "--with-pg-config=/usr/bin/pg_config"
+"#,
+            )?;
+            merge_tables(
+                &mut table,
+                toml::from_str(
+                    r#"
+vendor_dir = "vendor/bundle"
+
+[build]
+pg = "--with-pg-config=/opt/pg/bin/pg_config"
+"#,
+                )?,
+            );
+
+            let config = Config::from_table(table)?;
+            assert_eq!(config.vendor_dir.as_deref(), Some("vendor/bundle"));
+            assert!(config.max_requests_per_second.is_some());
+            assert_eq!(
+                build_flags(Some(&config), "nokogiri"),
+                ["--use-system-libraries"]
+            );
+            assert_eq!(
+                build_flags(Some(&config), "pg"),
+                ["--with-pg-config=/opt/pg/bin/pg_config"]
+            );
+            assert!(build_flags(Some(&config), "json").is_empty());
+            Ok(())
+        }
     }
 
     mod directories {
@@ -790,6 +1342,7 @@ fallback = "https://mirror.example.com"
                 cache_dir: None,
                 gemfile: None,
                 gem_sources: vec![],
+                ..Default::default()
             };
 
             let result = vendor_dir(Some(&config)).unwrap();
@@ -803,6 +1356,7 @@ fallback = "https://mirror.example.com"
                 cache_dir: Some("/config/cache".to_string()),
                 gemfile: None,
                 gem_sources: vec![],
+                ..Default::default()
             };
 
             let result = cache_dir(Some(&config)).unwrap();
@@ -908,6 +1462,29 @@ BUNDLE_FROZEN: "true"
             Ok(())
         }
 
+        #[test]
+        fn parses_local_overrides() -> Result<()> {
+            let config = BundleConfig::parse_yaml(
+                r#"---
+BUNDLE_LOCAL__RACK: "/src/rack"
+BUNDLE_LOCAL__RACK___TEST: "/src/rack-test"
+BUNDLE_DISABLE_LOCAL_BRANCH_CHECK: "true"
+BUNDLE_DISABLE_MULTISOURCE: "true"
+"#,
+            )?;
+            assert_eq!(
+                config.local_overrides.get("rack").map(String::as_str),
+                Some("/src/rack")
+            );
+            assert_eq!(
+                config.local_overrides.get("rack-test").map(String::as_str),
+                Some("/src/rack-test")
+            );
+            assert_eq!(config.disable_local_branch_check, Some(true));
+            assert_eq!(config.disable_multisource, Some(true));
+            Ok(())
+        }
+
         #[test]
         fn parses_boolean_variants() -> Result<()> {
             let temp_dir = tempfile::tempdir()?;
@@ -960,6 +1537,24 @@ BUNDLE_WITHOUT: "development:test"
             env::set_current_dir(original_dir)?;
             Ok(())
         }
+
+        #[test]
+        fn first_frozen_setting_wins_even_when_false() {
+            // CLI, local config, BUNDLE_FROZEN, global config
+            assert_eq!(
+                first_setting(&[None, None, Some(false), Some(true)]),
+                Some(false)
+            );
+            assert_eq!(
+                first_setting(&[Some(false), Some(true), None, None]),
+                Some(false)
+            );
+            assert_eq!(
+                first_setting(&[None, Some(true), Some(false), None]),
+                Some(true)
+            );
+            assert_eq!(first_setting(&[None, None, None, None]), None);
+        }
     }
 
     mod gem_source {
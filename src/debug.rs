@@ -1,34 +1,278 @@
 //! Debug logging utilities
 //!
-//! Provides debug logging that respects the global --debug flag.
-//! When debug mode is disabled, all debug logging has zero cost.
+//! Leveled, module-targeted logging. `LODE_LOG` picks what to show, either
+//! a level for everything (`LODE_LOG=debug`) or per-module levels
+//! (`LODE_LOG=resolver=debug,download=trace`). `--debug` turns on every
+//! module at trace level. Lines go to stderr, or to `--log-file`, as text or
+//! JSON. When logging is disabled, a log call costs a single check.
+//! `--debug` runs are also mirrored to a log file for `lode issue`.
 
-use std::sync::OnceLock;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
 
-static DEBUG_ENABLED: OnceLock<bool> = OnceLock::new();
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Severity of a log line, from most to least important
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    /// Level named `name` (case-insensitive)
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "error" => Some(Self::Error),
+            "warn" | "warning" => Some(Self::Warn),
+            "info" => Some(Self::Info),
+            "debug" => Some(Self::Debug),
+            "trace" => Some(Self::Trace),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name, as used in `LODE_LOG` and JSON lines
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Debug => "debug",
+            Self::Trace => "trace",
+        }
+    }
+}
+
+/// Which modules log at which level, parsed from a `LODE_LOG` spec
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogFilter {
+    /// Level for modules without their own directive
+    default: Option<Level>,
+    /// Per-module levels (`None` for `off`), most specific first
+    targets: Vec<(String, Option<Level>)>,
+}
+
+impl LogFilter {
+    /// Filter logging every module at `level`
+    #[must_use]
+    pub const fn all(level: Level) -> Self {
+        Self {
+            default: Some(level),
+            targets: Vec::new(),
+        }
+    }
+
+    /// Parse `level` or `module=level` directives separated by commas
+    ///
+    /// Unknown levels are ignored. `off` silences a module.
+    #[must_use]
+    pub fn parse(spec: &str) -> Self {
+        let mut filter = Self::default();
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, "off")) => filter.targets.push((target.trim().to_string(), None)),
+                Some((target, level)) => {
+                    if let Some(level) = Level::parse(level) {
+                        filter
+                            .targets
+                            .push((target.trim().to_string(), Some(level)));
+                    }
+                }
+                None if directive == "off" => filter.default = None,
+                None => filter.default = Level::parse(directive).or(filter.default),
+            }
+        }
+        filter
+            .targets
+            .sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+        filter
+    }
+
+    /// Whether a line at `level` from `target` passes the filter
+    #[must_use]
+    pub fn enabled(&self, level: Level, target: &str) -> bool {
+        self.targets
+            .iter()
+            .find(|(name, _)| {
+                target == name
+                    || target
+                        .strip_prefix(name.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .map_or(self.default, |(_, level)| *level)
+            .is_some_and(|max| level <= max)
+    }
+
+    /// Whether nothing can pass the filter
+    #[must_use]
+    pub fn is_off(&self) -> bool {
+        self.default.is_none() && self.targets.iter().all(|(_, level)| level.is_none())
+    }
+}
+
+/// How log lines are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `[DEBUG resolver] message`
+    #[default]
+    Text,
+    /// One JSON object per line with time, level, target and message
+    Json,
+}
+
+/// Logging setup chosen on the command line and in the environment
+#[derive(Debug, Default)]
+pub struct LogOptions {
+    /// `--debug`: every module at trace level
+    pub debug: bool,
+    /// A `LODE_LOG` spec
+    pub filter: Option<String>,
+    /// Write lines here instead of stderr
+    pub file: Option<PathBuf>,
+    pub format: LogFormat,
+}
+
+struct Logger {
+    filter: LogFilter,
+    format: LogFormat,
+    /// `--log-file`, replacing stderr
+    file: Option<Mutex<File>>,
+    /// Copy of `--debug` runs kept for `lode issue`
+    mirror: Option<Mutex<File>>,
+}
+
+/// Initialize logging
+///
+/// `--debug` overrides any `LODE_LOG` filter and starts a fresh
+/// [`DEBUG_LOG`](crate::diagnostics::DEBUG_LOG).
+///
+/// # Errors
+///
+/// Returns an error if the log file cannot be created.
+pub fn init_logging(options: LogOptions) -> std::io::Result<()> {
+    let filter = if options.debug {
+        LogFilter::all(Level::Trace)
+    } else {
+        options
+            .filter
+            .as_deref()
+            .map(LogFilter::parse)
+            .unwrap_or_default()
+    };
+    let file = options.file.map(File::create).transpose()?.map(Mutex::new);
+    let mirror = options
+        .debug
+        .then(crate::diagnostics::log_dir)
+        .flatten()
+        .filter(|dir| std::fs::create_dir_all(dir).is_ok())
+        .and_then(|dir| File::create(dir.join(crate::diagnostics::DEBUG_LOG)).ok())
+        .map(Mutex::new);
+    LOGGER
+        .set(Logger {
+            filter,
+            format: options.format,
+            file,
+            mirror,
+        })
+        .ok();
+    Ok(())
+}
 
 /// Initialize debug mode from command-line flag
 pub fn init_debug(enabled: bool) {
-    let _ = DEBUG_ENABLED.set(enabled);
+    init_logging(LogOptions {
+        debug: enabled,
+        ..LogOptions::default()
+    })
+    .ok();
 }
 
 /// Check if debug mode is enabled
 pub fn is_debug_enabled() -> bool {
-    DEBUG_ENABLED.get().copied().unwrap_or(false)
+    LOGGER.get().is_some_and(|logger| !logger.filter.is_off())
+}
+
+/// Whether a line at `level` from `target` would be written
+#[must_use]
+pub fn enabled(level: Level, target: &str) -> bool {
+    LOGGER
+        .get()
+        .is_some_and(|logger| logger.filter.enabled(level, target))
+}
+
+/// Log target for a `module_path!()`, without the crate name
+#[must_use]
+pub fn target(module_path: &str) -> &str {
+    module_path.strip_prefix("lode::").unwrap_or(module_path)
+}
+
+/// Write a log line if `level` is enabled for `target`
+pub fn log(level: Level, target: &str, args: fmt::Arguments<'_>) {
+    let Some(logger) = LOGGER.get() else {
+        return;
+    };
+    if !logger.filter.enabled(level, target) {
+        return;
+    }
+
+    let text = format!("[{} {target}] {args}", level.as_str().to_ascii_uppercase());
+    let line = match logger.format {
+        LogFormat::Text => text.clone(),
+        LogFormat::Json => serde_json::json!({
+            "time": chrono::Utc::now().to_rfc3339(),
+            "level": level.as_str(),
+            "target": target,
+            "message": args.to_string(),
+        })
+        .to_string(),
+    };
+
+    match &logger.file {
+        Some(file) => {
+            if let Ok(mut file) = file.lock() {
+                writeln!(file, "{line}").ok();
+            }
+        }
+        None => eprintln!("{line}"),
+    }
+    if let Some(mirror) = &logger.mirror
+        && let Ok(mut mirror) = mirror.lock()
+    {
+        writeln!(mirror, "{text}").ok();
+    }
 }
 
 /// Print a debug message if debug mode is enabled
 pub fn debug_log(message: &str) {
-    if is_debug_enabled() {
-        eprintln!("[DEBUG] {message}");
-    }
+    log(Level::Debug, "lode", format_args!("{message}"));
 }
 
 /// Print formatted debug message if debug mode is enabled
-pub fn debug_logf(args: std::fmt::Arguments<'_>) {
-    if is_debug_enabled() {
-        eprintln!("[DEBUG] {args}");
-    }
+pub fn debug_logf(args: fmt::Arguments<'_>) {
+    log(Level::Debug, "lode", args);
+}
+
+/// Macro for leveled logging targeted at the calling module
+///
+/// Usage: `log!(Level::Info, "message with {}", variable)`
+#[macro_export]
+macro_rules! log {
+    ($level:expr, $($arg:tt)*) => {{
+        let level = $level;
+        let target = $crate::debug::target(module_path!());
+        if $crate::debug::enabled(level, target) {
+            $crate::debug::log(level, target, format_args!($($arg)*));
+        }
+    }};
 }
 
 /// Macro for convenient debug logging
@@ -37,8 +281,53 @@ pub fn debug_logf(args: std::fmt::Arguments<'_>) {
 #[macro_export]
 macro_rules! debug {
     ($($arg:tt)*) => {
-        if $crate::debug::is_debug_enabled() {
-            eprintln!("[DEBUG] {}", format_args!($($arg)*));
-        }
+        $crate::log!($crate::debug::Level::Debug, $($arg)*)
     };
 }
+
+/// Macro for trace logging, the most verbose level
+///
+/// Usage: `trace!("message with {}", variable)`
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        $crate::log!($crate::debug::Level::Trace, $($arg)*)
+    };
+}
+
+/// Macro for info logging
+///
+/// Usage: `info!("message with {}", variable)`
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::log!($crate::debug::Level::Info, $($arg)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_matches_modules_and_levels() {
+        let filter = LogFilter::parse("warn,resolver=debug,download=trace,download::retry=off");
+        assert!(filter.enabled(Level::Debug, "resolver"));
+        assert!(!filter.enabled(Level::Trace, "resolver"));
+        assert!(filter.enabled(Level::Trace, "download"));
+        assert!(!filter.enabled(Level::Error, "download::retry"));
+        assert!(filter.enabled(Level::Warn, "lockfile"));
+        assert!(!filter.enabled(Level::Info, "lockfile"));
+        assert!(!filter.enabled(Level::Debug, "resolver_cache"));
+
+        assert!(LogFilter::parse("").is_off());
+        assert!(LogFilter::parse("off").is_off());
+        assert!(!LogFilter::parse("git=info").is_off());
+    }
+
+    #[test]
+    fn target_strips_crate_name() {
+        assert_eq!(target("lode::commands::install"), "commands::install");
+        assert_eq!(target("lode"), "lode");
+    }
+}
@@ -0,0 +1,259 @@
+//! Cached SHA-256 digests of gem files
+//!
+//! Hashing every cached `.gem` for `lock --add-checksums` or a cache check is
+//! dominated by large native gems. Digests are remembered in
+//! `<cache>/digests.json` keyed by path, size and modification time, so only
+//! new or changed files are read again, and misses are hashed in parallel.
+//! The `sha2` crate picks SHA-NI on x86 or the SHA extensions on `aarch64`
+//! at runtime when the CPU has them.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Version of `digests.json`; on a mismatch every entry is discarded and
+/// each file is rehashed the next time its digest is needed
+const FORMAT_VERSION: u32 = 1;
+
+/// Read size while hashing; large enough to keep the hasher busy
+const BUFFER_SIZE: usize = 256 * 1024;
+
+/// What a digest was computed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Stamp {
+    size: u64,
+    mtime_nanos: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    #[serde(flatten)]
+    stamp: Stamp,
+    sha256: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DigestFile {
+    format: u32,
+    entries: BTreeMap<PathBuf, Entry>,
+}
+
+/// SHA-256 digests of files, reused while a file's size and mtime are unchanged
+#[derive(Debug, Clone)]
+pub struct DigestCache {
+    path: PathBuf,
+    entries: BTreeMap<PathBuf, Entry>,
+    dirty: bool,
+}
+
+impl DigestCache {
+    /// Load `<cache_dir>/digests.json`, starting empty if it is missing or
+    /// unreadable
+    #[must_use]
+    pub fn load(cache_dir: &Path) -> Self {
+        let path = cache_dir.join("digests.json");
+        let entries = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<DigestFile>(&bytes).ok())
+            .filter(|file| file.format == FORMAT_VERSION)
+            .map(|file| file.entries)
+            .unwrap_or_default();
+        Self {
+            path,
+            entries,
+            dirty: false,
+        }
+    }
+
+    /// Hex SHA-256 of each of `paths`, in order
+    ///
+    /// Files whose size and mtime match a remembered digest are not read.
+    /// The rest are hashed in parallel.
+    pub fn sha256_all(&mut self, paths: &[PathBuf]) -> Vec<io::Result<String>> {
+        let results: Vec<_> = paths
+            .par_iter()
+            .map(|path| {
+                let stamp = stamp(path)?;
+                if let Some(entry) = self.entries.get(path)
+                    && entry.stamp == stamp
+                {
+                    return Ok((entry.sha256.clone(), None));
+                }
+                let sha256 = sha256_file(path)?;
+                Ok((sha256.clone(), Some(Entry { stamp, sha256 })))
+            })
+            .collect();
+
+        paths
+            .iter()
+            .zip(results)
+            .map(|(path, result)| {
+                result.map(|(sha256, fresh)| {
+                    if let Some(entry) = fresh {
+                        self.entries.insert(path.clone(), entry);
+                        self.dirty = true;
+                    }
+                    sha256
+                })
+            })
+            .collect()
+    }
+
+    /// Persist new digests, dropping entries for files that no longer exist
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the digest file cannot be written.
+    pub fn save(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.entries.retain(|path, _| path.exists());
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = DigestFile {
+            format: FORMAT_VERSION,
+            entries: self.entries.clone(),
+        };
+        let json = serde_json::to_vec(&file).map_err(io::Error::other)?;
+        let temp = self.path.with_extension("json.tmp");
+        fs::write(&temp, json)?;
+        fs::rename(&temp, &self.path)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// Hex SHA-256 of a file
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read.
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        let count = file.read(&mut buffer)?;
+        if count == 0 {
+            break;
+        }
+        hasher.update(buffer.get(..count).unwrap_or_default());
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Hex SHA-256 over every file under `dir`, with the number of files
+///
+/// Files are hashed in path order, each as its relative path, a NUL and its
+/// contents, so renames and edits both change the digest.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be walked or a file read.
+pub fn sha256_tree(dir: &Path) -> io::Result<(String, usize)> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.map_err(io::Error::from)?;
+        if entry.file_type().is_file() {
+            files.push(entry.into_path());
+        }
+    }
+
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    for path in &files {
+        let relative = path.strip_prefix(dir).unwrap_or(path);
+        hasher.update(relative.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        let mut file = File::open(path)?;
+        loop {
+            let count = file.read(&mut buffer)?;
+            if count == 0 {
+                break;
+            }
+            hasher.update(buffer.get(..count).unwrap_or_default());
+        }
+    }
+    Ok((format!("{:x}", hasher.finalize()), files.len()))
+}
+
+fn stamp(path: &Path) -> io::Result<Stamp> {
+    let metadata = fs::metadata(path)?;
+    let mtime_nanos = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |age| u64::try_from(age.as_nanos()).unwrap_or(u64::MAX));
+    Ok(Stamp {
+        size: metadata.len(),
+        mtime_nanos,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SHA-256 of "hello"
+    const HELLO: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    #[test]
+    fn digests_are_remembered_until_the_file_changes() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let gem = temp.path().join("rack-3.0.0.gem");
+        fs::write(&gem, "hello").expect("write gem");
+
+        let mut cache = DigestCache::load(temp.path());
+        let digests = cache.sha256_all(std::slice::from_ref(&gem));
+        assert_eq!(
+            digests.into_iter().next().and_then(Result::ok).as_deref(),
+            Some(HELLO)
+        );
+        cache.save().expect("save");
+
+        // A remembered digest is returned without reading the file
+        let mut cache = DigestCache::load(temp.path());
+        cache
+            .entries
+            .get_mut(&gem)
+            .expect("entry")
+            .sha256
+            .replace_range(.., "remembered");
+        let digest = cache.sha256_all(std::slice::from_ref(&gem)).pop();
+        assert_eq!(digest.and_then(Result::ok).as_deref(), Some("remembered"));
+
+        // A size change invalidates it
+        fs::write(&gem, "hello, world").expect("rewrite gem");
+        let digest = cache.sha256_all(std::slice::from_ref(&gem)).pop();
+        assert_ne!(digest.and_then(Result::ok).as_deref(), Some("remembered"));
+    }
+
+    #[test]
+    fn missing_files_are_errors() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let mut cache = DigestCache::load(temp.path());
+        let results = cache.sha256_all(&[temp.path().join("missing.gem")]);
+        assert!(results.first().is_some_and(Result::is_err));
+    }
+
+    #[test]
+    fn tree_digest_covers_names_and_contents() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        fs::create_dir(temp.path().join("lib")).expect("mkdir");
+        fs::write(temp.path().join("lib/rack.rb"), "hello").expect("write");
+        fs::write(temp.path().join("README"), "").expect("write");
+
+        let (digest, files) = sha256_tree(temp.path()).expect("digest");
+        assert_eq!(files, 2);
+        assert_eq!(sha256_tree(temp.path()).expect("digest").0, digest);
+
+        fs::rename(temp.path().join("README"), temp.path().join("README.md")).expect("rename");
+        assert_ne!(sha256_tree(temp.path()).expect("digest").0, digest);
+    }
+}
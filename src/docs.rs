@@ -0,0 +1,356 @@
+//! Gem documentation (`RDoc` HTML and ri data)
+//!
+//! Follows the `RubyGems` layout: documentation for `rack-3.1.7` lives in
+//! `<GEM_HOME>/doc/rack-3.1.7/rdoc` and `.../ri`, next to
+//! `<GEM_HOME>/gems/rack-3.1.7`. [`DocPolicy`] decides what to generate:
+//! command-line flags win, then lode's `gem.no_document` config, then the
+//! defaults in [`crate::gemrc`] (like `gem: --no-document`), then both kinds.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::config::Config;
+
+/// Which kinds of documentation to generate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DocPolicy {
+    /// `RDoc` HTML
+    pub rdoc: bool,
+    /// ri data for the `ri` command
+    pub ri: bool,
+}
+
+impl DocPolicy {
+    /// Generate both kinds, the `RubyGems` default
+    pub const ALL: Self = Self {
+        rdoc: true,
+        ri: true,
+    };
+
+    /// Generate nothing
+    pub const NONE: Self = Self {
+        rdoc: false,
+        ri: false,
+    };
+
+    /// Parse a `--document` list like `rdoc,ri`
+    ///
+    /// Returns the policy and any formats that were not recognized.
+    #[must_use]
+    pub fn parse(formats: &str) -> (Self, Vec<String>) {
+        let mut policy = Self::NONE;
+        let mut unknown = Vec::new();
+        for format in formats.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            match format {
+                "rdoc" => policy.rdoc = true,
+                "ri" => policy.ri = true,
+                other => unknown.push(other.to_string()),
+            }
+        }
+        (policy, unknown)
+    }
+
+    /// Policy from `gem` command arguments, like a `.gemrc` `gem:` line
+    ///
+    /// Understands `--no-document`, `--document=LIST`, `--document LIST`
+    /// and the legacy `--no-rdoc`/`--no-ri`. `None` if none are present.
+    #[must_use]
+    pub fn from_gem_args(args: &str) -> Option<Self> {
+        let mut policy = None;
+        let mut words = args.split_whitespace();
+        while let Some(word) = words.next() {
+            match word {
+                "--no-document" | "-N" => policy = Some(Self::NONE),
+                "--document" => policy = words.next().map(|list| Self::parse(list).0),
+                "--no-rdoc" => {
+                    policy = Some(Self {
+                        rdoc: false,
+                        ..policy.unwrap_or(Self::ALL)
+                    });
+                }
+                "--no-ri" => {
+                    policy = Some(Self {
+                        ri: false,
+                        ..policy.unwrap_or(Self::ALL)
+                    });
+                }
+                _ => {
+                    if let Some(list) = word.strip_prefix("--document=") {
+                        policy = Some(Self::parse(list).0);
+                    }
+                }
+            }
+        }
+        policy
+    }
+
+    /// Policy for a `gem-*` command such as `install` or `update`
+    ///
+    /// `--no-document` and `--document` win, then `gem.no_document` in lode
+    /// config, then the command's `.gemrc` defaults, then [`Self::ALL`].
+    #[must_use]
+    pub fn resolve(
+        document: Option<&str>,
+        no_document: bool,
+        config: Option<&Config>,
+        command: &str,
+    ) -> Self {
+        if no_document {
+            return Self::NONE;
+        }
+        if let Some(formats) = document {
+            return Self::parse(formats).0;
+        }
+        if crate::config::no_document(config) {
+            return Self::NONE;
+        }
+        config
+            .and_then(|c| Self::from_gem_args(&c.gemrc.command_args(command).join(" ")))
+            .unwrap_or(Self::ALL)
+    }
+
+    /// Whether nothing would be generated
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        !self.rdoc && !self.ri
+    }
+
+    /// Human description, like `rdoc and ri`
+    #[must_use]
+    pub const fn describe(self) -> &'static str {
+        match (self.rdoc, self.ri) {
+            (true, true) => "rdoc and ri",
+            (true, false) => "rdoc",
+            (false, true) => "ri",
+            (false, false) => "no",
+        }
+    }
+}
+
+/// Where documentation lives for a gem directory
+#[derive(Debug, Clone)]
+pub struct DocLayout {
+    /// `<GEM_HOME>/doc`
+    root: PathBuf,
+}
+
+impl DocLayout {
+    /// Layout beside a `<GEM_HOME>/gems` directory
+    #[must_use]
+    pub fn for_gems_dir(gems_dir: &Path) -> Self {
+        Self {
+            root: gems_dir.parent().unwrap_or(gems_dir).join("doc"),
+        }
+    }
+
+    /// Layout for an installed gem's directory, like `<GEM_HOME>/gems/rack-3.1.7`
+    #[must_use]
+    pub fn for_gem_dir(gem_dir: &Path) -> Self {
+        Self::for_gems_dir(gem_dir.parent().unwrap_or(gem_dir))
+    }
+
+    /// `<GEM_HOME>/doc`
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Documentation directory for a gem's full name
+    #[must_use]
+    pub fn dir(&self, full_name: &str) -> PathBuf {
+        self.root.join(full_name)
+    }
+
+    /// `RDoc` HTML directory for a gem's full name
+    #[must_use]
+    pub fn rdoc_dir(&self, full_name: &str) -> PathBuf {
+        self.dir(full_name).join("rdoc")
+    }
+
+    /// ri data directory for a gem's full name
+    #[must_use]
+    pub fn ri_dir(&self, full_name: &str) -> PathBuf {
+        self.dir(full_name).join("ri")
+    }
+
+    /// Documentation directories whose gem is no longer in `gems_dir`
+    #[must_use]
+    pub fn orphans(&self, gems_dir: &Path) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(&self.root) else {
+            return Vec::new();
+        };
+        let mut orphans: Vec<PathBuf> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter(|entry| !gems_dir.join(entry.file_name()).exists())
+            .map(|entry| entry.path())
+            .collect();
+        orphans.sort();
+        orphans
+    }
+}
+
+/// What [`generate`] did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocOutcome {
+    /// Documentation was written
+    Generated,
+    /// Nothing to do, with the reason
+    Skipped(&'static str),
+    /// rdoc failed or is missing; installs carry on regardless
+    Failed(String),
+}
+
+/// Generate documentation for an installed gem
+///
+/// Kinds that already exist are kept unless `overwrite` is set.
+///
+/// # Errors
+///
+/// Returns an error if the documentation directory cannot be prepared.
+pub fn generate(
+    gem_dir: &Path,
+    policy: DocPolicy,
+    overwrite: bool,
+    quiet: bool,
+) -> Result<DocOutcome> {
+    if policy.is_empty() {
+        return Ok(DocOutcome::Skipped("documentation disabled"));
+    }
+    if !gem_dir.join("lib").is_dir() {
+        return Ok(DocOutcome::Skipped("no lib directory"));
+    }
+    let full_name = gem_dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .context("Invalid gem directory")?;
+    let layout = DocLayout::for_gem_dir(gem_dir);
+
+    let targets = [
+        (policy.rdoc, layout.rdoc_dir(full_name), false),
+        (policy.ri, layout.ri_dir(full_name), true),
+    ];
+    let mut generated = false;
+    for (wanted, dir, ri) in targets {
+        if !wanted {
+            continue;
+        }
+        if dir.exists() {
+            if !overwrite {
+                continue;
+            }
+            fs::remove_dir_all(&dir)
+                .with_context(|| format!("Failed to remove {}", dir.display()))?;
+        }
+        if let Some(parent) = dir.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let mut cmd = Command::new("rdoc");
+        if ri {
+            cmd.arg("--ri");
+        }
+        cmd.arg("--op").arg(&dir).arg("lib").current_dir(gem_dir);
+        if quiet {
+            cmd.stdout(Stdio::null()).stderr(Stdio::null());
+        }
+        crate::debug!("running rdoc for {full_name} into {}", dir.display());
+
+        match cmd.output() {
+            Ok(output) if output.status.success() => generated = true,
+            Ok(output) => {
+                return Ok(DocOutcome::Failed(format!(
+                    "rdoc exited with {}: {}",
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+            Err(e) => {
+                return Ok(DocOutcome::Failed(format!(
+                    "could not run rdoc ({e}); install it with: gem install rdoc"
+                )));
+            }
+        }
+    }
+
+    Ok(if generated {
+        DocOutcome::Generated
+    } else {
+        DocOutcome::Skipped("already documented")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy_follows_gem_arguments_and_gemrc() {
+        assert_eq!(
+            DocPolicy::parse("ri, bogus"),
+            (
+                DocPolicy {
+                    rdoc: false,
+                    ri: true
+                },
+                vec!["bogus".to_string()]
+            )
+        );
+        assert_eq!(
+            DocPolicy::from_gem_args("--no-document --env-shebang"),
+            Some(DocPolicy::NONE)
+        );
+        assert_eq!(
+            DocPolicy::from_gem_args("--document=rdoc"),
+            Some(DocPolicy {
+                rdoc: true,
+                ri: false
+            })
+        );
+        assert_eq!(
+            DocPolicy::from_gem_args("--no-ri"),
+            Some(DocPolicy {
+                rdoc: true,
+                ri: false
+            })
+        );
+        assert_eq!(DocPolicy::from_gem_args("--env-shebang"), None);
+
+        let config = Config {
+            gemrc: crate::gemrc::Gemrc::parse("gem: --no-document\n").unwrap(),
+            ..Config::default()
+        };
+        assert_eq!(
+            DocPolicy::resolve(None, false, Some(&config), "install"),
+            DocPolicy::NONE
+        );
+
+        assert_eq!(
+            DocPolicy::resolve(Some("ri"), false, None, "install"),
+            DocPolicy {
+                rdoc: false,
+                ri: true
+            }
+        );
+        assert_eq!(
+            DocPolicy::resolve(None, true, None, "install"),
+            DocPolicy::NONE
+        );
+    }
+
+    #[test]
+    fn orphans_are_docs_without_a_gem() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let gems = temp.path().join("gems");
+        fs::create_dir_all(gems.join("rack-3.1.7")).unwrap();
+        let layout = DocLayout::for_gems_dir(&gems);
+        fs::create_dir_all(layout.rdoc_dir("rack-3.1.7")).unwrap();
+        fs::create_dir_all(layout.ri_dir("rack-2.2.9")).unwrap();
+
+        assert_eq!(layout.root(), temp.path().join("doc"));
+        assert_eq!(layout.orphans(&gems), vec![layout.dir("rack-2.2.9")]);
+    }
+}
@@ -1,15 +1,33 @@
 //! Gem download and caching
 //!
 //! Manages parallel gem downloads from RubyGems.org with retry logic and caching.
+//!
+//! With several sources configured, each download tries them in priority
+//! order. Sources that keep failing (network errors, 5xx responses) are
+//! demoted behind healthy ones for a while, so a dead mirror costs a few
+//! failed connections instead of a timeout per gem.
 
+use crate::insecure_source::InsecureSourcePolicy;
 use crate::lockfile::GemSpec;
+use crate::static_source;
 use anyhow::{Context, Result};
 use futures_util::StreamExt;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tokio::io::AsyncWriteExt;
 
+/// Consecutive failed requests before a source is demoted
+const FAILURE_BUDGET: u32 = 3;
+
+/// How long a demoted source is tried only after healthy ones
+const DEMOTION_PERIOD: Duration = Duration::from_mins(1);
+
+/// Unreachable sources fail within this, rather than the request timeout
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[derive(Debug, Error)]
 pub enum DownloadError {
     #[error("Gem not found: {gem} (searched {location})")]
@@ -73,6 +91,58 @@ impl DownloadError {
     }
 }
 
+/// Download health of one gem source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceHealth {
+    /// Source URL
+    pub source: String,
+    /// Gems downloaded from this source
+    pub served: usize,
+    /// Requests that failed with a network or server error
+    pub failures: usize,
+    /// Total time spent on downloads this source served
+    pub latency: Duration,
+    consecutive_failures: u32,
+    demoted_until: Option<Instant>,
+}
+
+impl SourceHealth {
+    fn new(source: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            served: 0,
+            failures: 0,
+            latency: Duration::ZERO,
+            consecutive_failures: 0,
+            demoted_until: None,
+        }
+    }
+
+    /// Mean download time, once the source has served a gem
+    #[must_use]
+    pub fn average_latency(&self) -> Option<Duration> {
+        u32::try_from(self.served)
+            .ok()
+            .filter(|served| *served > 0)
+            .map(|served| self.latency / served)
+    }
+
+    /// Whether the source is currently tried after healthy ones
+    #[must_use]
+    pub fn is_demoted(&self) -> bool {
+        self.demoted_until
+            .is_some_and(|until| Instant::now() < until)
+    }
+}
+
+/// Health of every source, shared by clones of a download manager
+#[derive(Debug)]
+struct Health {
+    sources: Vec<SourceHealth>,
+    /// Gem file name to the index of the source that served it
+    served_by: BTreeMap<String, usize>,
+}
+
 /// Manages gem downloads with caching
 #[derive(Clone)]
 pub struct DownloadManager {
@@ -82,6 +152,9 @@ pub struct DownloadManager {
     max_retries: usize,
     skip_cache: bool,
     local_only: bool,
+    health: Arc<Mutex<Health>>,
+    /// Called with the size of each chunk written to the cache
+    progress: Option<Arc<dyn Fn(u64) + Send + Sync>>,
 }
 
 impl std::fmt::Debug for DownloadManager {
@@ -137,9 +210,27 @@ impl DownloadManager {
         max_retries: usize,
     ) -> Result<Self> {
         std::fs::create_dir_all(&cache_dir).context("Failed to create cache directory")?;
+        Self::for_planning(cache_dir, sources, max_retries)
+    }
 
+    /// Create a download manager that only inspects the cache and sources
+    ///
+    /// Unlike [`Self::with_sources_and_retry`], the cache directory is not
+    /// created, so [`Self::cached_gem`] and [`Self::gem_size`] leave the
+    /// filesystem untouched. Used by `lode install --dry-run`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be built or a source uses
+    /// plain `http://` without being allowed by the `insecure_sources` config.
+    pub fn for_planning(
+        cache_dir: PathBuf,
+        sources: Vec<String>,
+        max_retries: usize,
+    ) -> Result<Self> {
         let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(60))
+            .timeout(Duration::from_mins(1))
+            .connect_timeout(CONNECT_TIMEOUT)
             .user_agent(format!("lode/{}", env!("CARGO_PKG_VERSION")))
             .build()?;
 
@@ -148,6 +239,15 @@ impl DownloadManager {
         } else {
             sources
         };
+        InsecureSourcePolicy::load().check_all(sources.iter().map(String::as_str))?;
+
+        let health = Health {
+            sources: sources
+                .iter()
+                .map(|source| SourceHealth::new(source))
+                .collect(),
+            served_by: BTreeMap::new(),
+        };
 
         Ok(Self {
             cache_dir,
@@ -156,6 +256,8 @@ impl DownloadManager {
             max_retries,
             skip_cache: false,
             local_only: false,
+            health: Arc::new(Mutex::new(health)),
+            progress: None,
         })
     }
 
@@ -173,11 +275,22 @@ impl DownloadManager {
         self
     }
 
+    /// Report downloaded bytes as they arrive, for byte-level progress
+    #[must_use]
+    pub fn with_progress(mut self, progress: impl Fn(u64) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
     /// Download a gem to the cache.
     ///
     /// Returns the cached gem path. Reuses existing cached files.
     ///
-    /// Tries all configured sources with retry logic on network errors.
+    /// Tries sources in priority order, demoted sources last. A 404 moves on
+    /// to the next source; network errors and server errors are retried
+    /// with backoff, then count against the source and move on. `file://`
+    /// sources are copied from their `gems/` directory, and servers without
+    /// `/downloads/` are asked for the classic `/gems/<file>` path.
     ///
     /// # Errors
     ///
@@ -188,8 +301,8 @@ impl DownloadManager {
         let cache_path = self.cache_dir.join(&filename);
 
         // Check if already cached (unless skip_cache is enabled)
-        if !self.skip_cache && cache_path.exists() {
-            return Ok(cache_path);
+        if let Some(cached) = self.cached_gem(spec) {
+            return Ok(cached);
         }
 
         // If local_only is set and gem not in cache, return error
@@ -200,58 +313,94 @@ impl DownloadManager {
             });
         }
 
-        // Try each source in order
         let mut last_error = None;
-        for source in &self.sources {
-            let url = format!("{source}/downloads/{filename}");
+        for index in self.source_order() {
+            let Some(source) = self.sources.get(index) else {
+                continue;
+            };
+            if let Some(dir) = static_source::file_url_path(source) {
+                let started = Instant::now();
+                match self.copy_from_dir(&dir, spec, &filename, &cache_path) {
+                    Ok(path) => {
+                        self.record_success(index, &filename, started.elapsed());
+                        crate::debug!("{filename} served by {source}");
+                        return Ok(path);
+                    }
+                    Err(error @ DownloadError::GemNotFound { .. }) => {
+                        last_error = Some(error);
+                        continue;
+                    }
+                    Err(error) => return Err(error),
+                }
+            }
 
-            // Attempt download with retry
-            let mut network_error = None;
-            for attempt in 0..=self.max_retries {
-                match self.client.get(&url).send().await {
-                    Ok(response) => {
-                        let status = response.status();
-
-                        // Check for 404 - try next source
-                        if status.as_u16() == 404 {
-                            last_error = Some(DownloadError::GemNotFound {
-                                gem: spec.full_name_with_platform().to_string(),
-                                location: source.clone(),
-                            });
-                            break; // Break retry loop, try next source
+            // Gem servers answer on /downloads/; static file trees only
+            // have /gems/
+            let urls = [
+                format!("{source}/downloads/{filename}"),
+                format!("{source}/gems/{filename}"),
+            ];
+            for url in urls {
+                for attempt in 0..=self.max_retries {
+                    crate::trace!("GET {url} (attempt {})", attempt + 1);
+                    let started = Instant::now();
+                    let error = match crate::rate_limit::send(self.client.get(&url)).await {
+                        Ok(response) if response.status().is_success() => {
+                            match self
+                                .download_from_response(response, spec, cache_path.clone())
+                                .await
+                            {
+                                Ok(path) => {
+                                    self.record_success(index, &filename, started.elapsed());
+                                    crate::debug!("{filename} served by {source}");
+                                    return Ok(path);
+                                }
+                                Err(error @ DownloadError::NetworkError { .. }) => error,
+                                Err(error) => return Err(error),
+                            }
                         }
-
-                        // Other HTTP errors fail immediately
-                        if !status.is_success() {
-                            return Err(DownloadError::HttpError {
+                        Ok(response) if response.status().is_server_error() => {
+                            DownloadError::HttpError {
                                 gem: spec.name.clone(),
-                                status: status.as_u16(),
-                                url,
-                            });
+                                status: response.status().as_u16(),
+                                url: url.clone(),
+                            }
                         }
-
-                        // Success! Download the gem
-                        return self
-                            .download_from_response(response, spec, cache_path.clone())
-                            .await;
-                    }
-                    Err(e) => {
-                        network_error = Some(e);
-                        if attempt < self.max_retries {
-                            // Wait before retrying (exponential backoff)
-                            let delay = Duration::from_millis(100 * 2_u64.pow(attempt as u32));
-                            tokio::time::sleep(delay).await;
+                        Ok(response) => {
+                            // Not found (or refused) here; the next source may have it
+                            let status = response.status().as_u16();
+                            last_error = Some(if status == 404 {
+                                DownloadError::GemNotFound {
+                                    gem: spec.full_name_with_platform().to_string(),
+                                    location: source.clone(),
+                                }
+                            } else {
+                                DownloadError::HttpError {
+                                    gem: spec.name.clone(),
+                                    status,
+                                    url: url.clone(),
+                                }
+                            });
+                            break;
                         }
+                        Err(e) => DownloadError::NetworkError {
+                            gem: spec.name.clone(),
+                            source: e,
+                        },
+                    };
+
+                    self.record_failure(index);
+                    crate::debug!("{filename} failed from {source}: {error}");
+                    last_error = Some(error);
+                    if attempt < self.max_retries {
+                        // Wait before retrying (exponential backoff)
+                        let delay = Duration::from_millis(100 * 2_u64.pow(attempt as u32));
+                        tokio::time::sleep(delay).await;
                     }
                 }
-            }
-
-            // If we had a network error after all retries, return it
-            if let Some(e) = network_error {
-                return Err(DownloadError::NetworkError {
-                    gem: spec.name.clone(),
-                    source: e,
-                });
+                if !matches!(last_error, Some(DownloadError::GemNotFound { .. })) {
+                    break;
+                }
             }
         }
 
@@ -262,6 +411,147 @@ impl DownloadManager {
         }))
     }
 
+    /// Path of the cached `.gem` that [`Self::download_gem`] would reuse
+    ///
+    /// Always `None` when the cache is skipped.
+    #[must_use]
+    pub fn cached_gem(&self, spec: &GemSpec) -> Option<PathBuf> {
+        let cache_path = self
+            .cache_dir
+            .join(format!("{}.gem", spec.full_name_with_platform()));
+        (!self.skip_cache && cache_path.exists()).then_some(cache_path)
+    }
+
+    /// Size in bytes of the `.gem` a download would fetch
+    ///
+    /// Issues a `HEAD` request to each source in priority order and reads
+    /// `Content-Length` from the first that has the gem. Returns `None` in
+    /// local-only mode or when no source reports a size.
+    pub async fn gem_size(&self, spec: &GemSpec) -> Option<u64> {
+        if self.local_only {
+            return None;
+        }
+
+        let filename = format!("{}.gem", spec.full_name_with_platform());
+        for index in self.source_order() {
+            let Some(source) = self.sources.get(index) else {
+                continue;
+            };
+            let url = format!("{source}/downloads/{filename}");
+            let Ok(response) = self.client.head(&url).send().await else {
+                continue;
+            };
+            if !response.status().is_success() {
+                continue;
+            }
+            // `Response::content_length` reflects the (empty) HEAD body, so
+            // read the header instead
+            if let Some(size) = response
+                .headers()
+                .get(reqwest::header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+            {
+                return Some(size);
+            }
+        }
+        None
+    }
+
+    /// Health of each source so far, in configured order
+    #[must_use]
+    pub fn source_health(&self) -> Vec<SourceHealth> {
+        self.health
+            .lock()
+            .map(|health| health.sources.clone())
+            .unwrap_or_default()
+    }
+
+    /// Source that served `spec` during this run, if it was downloaded
+    #[must_use]
+    pub fn served_by(&self, spec: &GemSpec) -> Option<String> {
+        let filename = format!("{}.gem", spec.full_name_with_platform());
+        let index = *self.health.lock().ok()?.served_by.get(&filename)?;
+        self.sources.get(index).cloned()
+    }
+
+    /// Source indices in the order to try them: healthy sources by
+    /// priority, then demoted ones
+    fn source_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.sources.len()).collect();
+        if let Ok(health) = self.health.lock() {
+            order.sort_by_key(|index| {
+                health
+                    .sources
+                    .get(*index)
+                    .is_some_and(SourceHealth::is_demoted)
+            });
+        }
+        order
+    }
+
+    fn record_success(&self, index: usize, filename: &str, latency: Duration) {
+        let Ok(mut health) = self.health.lock() else {
+            return;
+        };
+        health.served_by.insert(filename.to_string(), index);
+        if let Some(source) = health.sources.get_mut(index) {
+            source.served += 1;
+            source.latency += latency;
+            source.consecutive_failures = 0;
+            source.demoted_until = None;
+        }
+    }
+
+    fn record_failure(&self, index: usize) {
+        let Ok(mut health) = self.health.lock() else {
+            return;
+        };
+        let Some(source) = health.sources.get_mut(index) else {
+            return;
+        };
+        source.failures += 1;
+        source.consecutive_failures += 1;
+        if source.consecutive_failures >= FAILURE_BUDGET && !source.is_demoted() {
+            source.demoted_until = Some(Instant::now() + DEMOTION_PERIOD);
+            crate::debug!(
+                "Demoting {} after {} consecutive failures",
+                source.source,
+                source.consecutive_failures
+            );
+        }
+    }
+
+    /// Copy a gem from a `file://` source's `gems/` directory
+    fn copy_from_dir(
+        &self,
+        dir: &Path,
+        spec: &GemSpec,
+        filename: &str,
+        cache_path: &Path,
+    ) -> Result<PathBuf, DownloadError> {
+        let source_path = dir.join("gems").join(filename);
+        if !source_path.is_file() {
+            return Err(DownloadError::GemNotFound {
+                gem: spec.full_name_with_platform().to_string(),
+                location: dir.display().to_string(),
+            });
+        }
+
+        let temp_file = tempfile::NamedTempFile::new_in(&self.cache_dir)
+            .map_err(DownloadError::wrap_io(&spec.name))?;
+        let copied = std::fs::copy(&source_path, temp_file.path())
+            .map_err(DownloadError::wrap_io(&spec.name))?;
+        if let Some(progress) = &self.progress {
+            progress(copied);
+        }
+        temp_file
+            .persist(cache_path)
+            .map_err(DownloadError::wrap_tempfile(&spec.name))?;
+
+        Ok(cache_path.to_path_buf())
+    }
+
     /// Download gem from a successful HTTP response
     async fn download_from_response(
         &self,
@@ -286,6 +576,9 @@ impl DownloadManager {
                 file.write_all(&chunk)
                     .await
                     .map_err(DownloadError::wrap_io(&spec.name))?;
+                if let Some(progress) = &self.progress {
+                    progress(chunk.len() as u64);
+                }
             }
 
             file.flush()
@@ -313,34 +606,12 @@ impl DownloadManager {
     ///
     /// Returns an error if the file cannot be read or hashed
     pub fn compute_checksum(gem_path: &Path) -> Result<String> {
-        use sha2::{Digest, Sha256};
-        use std::io::Read;
-
-        let mut file = std::fs::File::open(gem_path).with_context(|| {
+        crate::digest_cache::sha256_file(gem_path).with_context(|| {
             format!(
-                "Failed to open gem file for checksum: {}",
+                "Failed to read gem file for checksum: {}",
                 gem_path.display()
             )
-        })?;
-
-        let mut hasher = Sha256::new();
-        let mut buffer = [0; 8192];
-
-        loop {
-            let count = file.read(&mut buffer).with_context(|| {
-                format!(
-                    "Failed to read gem file for checksum: {}",
-                    gem_path.display()
-                )
-            })?;
-            if count == 0 {
-                break;
-            }
-            hasher.update(buffer.get(..count).unwrap_or(&[]));
-        }
-
-        let result = hasher.finalize();
-        Ok(format!("{result:x}"))
+        })
     }
 }
 
@@ -348,6 +619,127 @@ impl DownloadManager {
 mod tests {
     use super::*;
 
+    /// Serve `body` to every request on a local port
+    async fn serve(body: &'static [u8]) -> String {
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while let Ok(count @ 1..) = stream.read(&mut buffer).await {
+                    request.extend_from_slice(buffer.get(..count).unwrap_or_default());
+                    if request.windows(4).any(|window| window == b"\r\n\r\n") {
+                        break;
+                    }
+                }
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                stream.write_all(head.as_bytes()).await.ok();
+                stream.write_all(body).await.ok();
+            }
+        });
+        format!("http://{address}")
+    }
+
+    /// A URL nothing listens on
+    fn dead_source() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+        format!("http://{address}")
+    }
+
+    fn gem(name: &str) -> GemSpec {
+        GemSpec::new(name.to_string(), "1.0.0".to_string(), None, vec![], vec![])
+    }
+
+    #[tokio::test]
+    async fn falls_back_past_unreachable_source() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dead = dead_source();
+        let live = serve(b"gem data").await;
+        let dm = DownloadManager::with_sources(
+            temp_dir.path().to_path_buf(),
+            vec![dead.clone(), live.clone()],
+        )?;
+
+        let path = dm.download_gem(&gem("rack")).await?;
+
+        assert_eq!(std::fs::read(path)?, b"gem data");
+        assert_eq!(dm.served_by(&gem("rack")), Some(live));
+        let health = dm.source_health();
+        assert_eq!(health.first().map(|h| (h.served, h.failures)), Some((0, 1)));
+        assert_eq!(health.get(1).map(|h| (h.served, h.failures)), Some((1, 0)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn progress_reports_downloaded_bytes() -> Result<()> {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        let temp_dir = tempfile::tempdir()?;
+        let bytes = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&bytes);
+        let dm = DownloadManager::with_sources(
+            temp_dir.path().to_path_buf(),
+            vec![serve(b"gem data").await],
+        )?
+        .with_progress(move |chunk| {
+            counter.fetch_add(chunk, Ordering::Relaxed);
+        });
+
+        dm.download_gem(&gem("rack")).await?;
+        // Cached gems are not downloaded again
+        dm.download_gem(&gem("rack")).await?;
+        assert_eq!(bytes.load(Ordering::Relaxed), 8);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn failing_source_is_demoted() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let dm = DownloadManager::with_sources(
+            temp_dir.path().to_path_buf(),
+            vec![dead_source(), serve(b"gem data").await],
+        )?;
+
+        for name in ["a", "b", "c"] {
+            dm.download_gem(&gem(name)).await?;
+        }
+        assert_eq!(dm.source_order(), vec![1, 0]);
+        assert!(
+            dm.source_health()
+                .first()
+                .is_some_and(SourceHealth::is_demoted)
+        );
+
+        // Demoted sources are skipped while a healthy one serves
+        dm.download_gem(&gem("d")).await?;
+        assert_eq!(dm.source_health().first().map(|h| h.failures), Some(3));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn planning_reports_sizes_without_writing() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let cache_dir = temp_dir.path().join("cache");
+        let dm = DownloadManager::for_planning(
+            cache_dir.clone(),
+            vec![dead_source(), serve(b"gem data").await],
+            0,
+        )?;
+
+        assert_eq!(dm.cached_gem(&gem("rack")), None);
+        assert_eq!(dm.gem_size(&gem("rack")).await, Some(8));
+        assert!(!cache_dir.exists());
+        Ok(())
+    }
+
     #[test]
     fn download_manager_creation() -> Result<()> {
         let temp_dir = tempfile::tempdir().context("Failed to create temp dir")?;
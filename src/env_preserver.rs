@@ -0,0 +1,143 @@
+//! Pre-bundle environment of `lode exec` sessions
+//!
+//! Like Bundler's `EnvironmentPreserver`, `lode exec` and `lode shell`
+//! record the value each variable they change had before the bundle was
+//! activated, as `BUNDLER_ORIG_<NAME>`. `lode with-unbundled-env` (and
+//! `Bundler.with_unbundled_env` in Ruby code) puts those values back, so
+//! scripts inside a session can run system Ruby tools as if no bundle were
+//! active. The names are Bundler's, so either tool can undo the other's
+//! session.
+
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+
+/// Recorded in place of a variable that was not set at all
+pub const INTENTIONALLY_NIL: &str = "BUNDLER_ENVIRONMENT_PRESERVER_INTENTIONALLY_NIL";
+
+/// Variables a session may change, with the name their original is kept in
+const PRESERVED: [(&str, &str); 7] = [
+    ("PATH", "BUNDLER_ORIG_PATH"),
+    ("GEM_HOME", "BUNDLER_ORIG_GEM_HOME"),
+    ("GEM_PATH", "BUNDLER_ORIG_GEM_PATH"),
+    ("RUBYOPT", "BUNDLER_ORIG_RUBYOPT"),
+    ("RUBYLIB", "BUNDLER_ORIG_RUBYLIB"),
+    ("MANPATH", "BUNDLER_ORIG_MANPATH"),
+    ("BUNDLE_GEMFILE", "BUNDLER_ORIG_BUNDLE_GEMFILE"),
+];
+
+/// `BUNDLER_ORIG_*` variables recording the current value of every
+/// preserved variable in `changed`
+///
+/// `current` looks up the parent environment. Originals already recorded
+/// by an outer session are left alone, so nested sessions still restore the
+/// environment from before the first one.
+pub fn originals(
+    changed: &[(&str, String)],
+    current: impl Fn(&str) -> Option<String>,
+) -> Vec<(&'static str, String)> {
+    PRESERVED
+        .iter()
+        .filter(|(name, _)| changed.iter().any(|(changed, _)| changed == name))
+        .filter(|(_, original)| current(original).is_none())
+        .map(|(name, original)| {
+            (
+                *original,
+                current(name).unwrap_or_else(|| INTENTIONALLY_NIL.to_string()),
+            )
+        })
+        .collect()
+}
+
+/// `env` with every session change undone
+///
+/// Preserved variables get their recorded values back (or are removed if
+/// they were unset), and the `BUNDLER_ORIG_*`, `BUNDLE_*` and `LODE_SHELL`
+/// variables are dropped. As in Bundler, `-rbundler/setup` is also taken out
+/// of a `RUBYOPT` that had no recorded original.
+#[must_use]
+pub fn unbundled_env(mut env: BTreeMap<OsString, OsString>) -> BTreeMap<OsString, OsString> {
+    for (name, original) in PRESERVED {
+        let Some(value) = env.get(OsStr::new(original)).cloned() else {
+            continue;
+        };
+        if value == INTENTIONALLY_NIL {
+            env.remove(OsStr::new(name));
+        } else {
+            env.insert(name.into(), value);
+        }
+    }
+
+    env.retain(|name, _| {
+        !name.to_str().is_some_and(|name| {
+            name.starts_with("BUNDLER_ORIG_") || name.starts_with("BUNDLE_") || name == "LODE_SHELL"
+        })
+    });
+
+    if let Some(rubyopt) = env.get(OsStr::new("RUBYOPT")).and_then(|v| v.to_str()) {
+        let kept = rubyopt
+            .split_whitespace()
+            .filter(|flag| *flag != "-rbundler/setup")
+            .collect::<Vec<_>>()
+            .join(" ");
+        env.insert("RUBYOPT".into(), kept.into());
+    }
+
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> BTreeMap<OsString, OsString> {
+        vars.iter()
+            .map(|(name, value)| ((*name).into(), (*value).into()))
+            .collect()
+    }
+
+    #[test]
+    fn originals_record_unset_and_keep_outer_sessions() {
+        let parent = env(&[("PATH", "/usr/bin"), ("BUNDLER_ORIG_GEM_HOME", "/gems")]);
+        let current = |name: &str| {
+            parent
+                .get(OsStr::new(name))
+                .map(|v| v.to_string_lossy().into_owned())
+        };
+        let changed = [
+            ("PATH", "/app/bin:/usr/bin".to_string()),
+            ("GEM_HOME", "/app/vendor".to_string()),
+            ("RUBYLIB", "/app/lib".to_string()),
+        ];
+
+        assert_eq!(
+            originals(&changed, current),
+            vec![
+                ("BUNDLER_ORIG_PATH", "/usr/bin".to_string()),
+                ("BUNDLER_ORIG_RUBYLIB", INTENTIONALLY_NIL.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unbundled_env_restores_originals() {
+        let session = env(&[
+            ("PATH", "/app/bin:/usr/bin"),
+            ("BUNDLER_ORIG_PATH", "/usr/bin"),
+            ("RUBYLIB", "/app/lib"),
+            ("BUNDLER_ORIG_RUBYLIB", INTENTIONALLY_NIL),
+            ("RUBYOPT", "-W0 -rbundler/setup"),
+            ("BUNDLE_GEMFILE", "/app/Gemfile"),
+            ("LODE_SHELL", "/app"),
+            ("HOME", "/home/me"),
+        ]);
+
+        assert_eq!(
+            unbundled_env(session),
+            env(&[
+                ("HOME", "/home/me"),
+                ("PATH", "/usr/bin"),
+                ("RUBYOPT", "-W0"),
+            ])
+        );
+    }
+}
@@ -143,13 +143,18 @@ pub fn bundle_with() -> Option<Vec<String>> {
     })
 }
 
-/// Check if frozen mode is enabled.
+/// Frozen mode from `BUNDLE_FROZEN`, or `None` when unset.
+///
+/// `BUNDLE_FROZEN=false` is `Some(false)`, which overrides a global config.
 #[must_use]
-pub fn bundle_frozen() -> bool {
-    env::var("BUNDLE_FROZEN").ok().is_some_and(|s| {
-        let s = s.to_lowercase();
-        s == "1" || s == "true" || s == "yes"
-    })
+pub fn bundle_frozen() -> Option<bool> {
+    env::var("BUNDLE_FROZEN")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let s = s.to_lowercase();
+            s == "1" || s == "true" || s == "yes"
+        })
 }
 
 /// Check if deployment mode is enabled.
@@ -289,6 +294,32 @@ pub fn bundle_verbose() -> bool {
     is_enabled("BUNDLE_VERBOSE")
 }
 
+/// Message locale from `LC_ALL`, `LC_MESSAGES` or `LANG`, in POSIX priority order.
+#[must_use]
+pub fn locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .find_map(|var| env::var(var).ok().filter(|s| !s.is_empty()))
+}
+
+/// Path to the `RubyGems` config file (`GEMRC`), overriding `~/.gemrc`.
+#[must_use]
+pub fn gemrc() -> Option<String> {
+    env::var("GEMRC").ok().filter(|s| !s.is_empty())
+}
+
+/// Check if colored output is disabled (`NO_COLOR` set to any non-empty value).
+#[must_use]
+pub fn no_color() -> bool {
+    env::var("NO_COLOR").is_ok_and(|s| !s.is_empty())
+}
+
+/// Check if colored output is disabled for Bundler commands.
+#[must_use]
+pub fn bundle_no_color() -> bool {
+    is_enabled("BUNDLE_NO_COLOR")
+}
+
 // Advanced features
 
 /// Get exclusive groups to install (colon/space-separated list).
@@ -491,6 +522,12 @@ pub fn bundle_silence_deprecations() -> bool {
     is_enabled("BUNDLE_SILENCE_DEPRECATIONS")
 }
 
+/// Check if multiple global Gemfile sources should be rejected.
+#[must_use]
+pub fn bundle_disable_multisource() -> bool {
+    is_enabled("BUNDLE_DISABLE_MULTISOURCE")
+}
+
 /// Check if funding requests should be ignored.
 #[must_use]
 pub fn bundle_ignore_funding_requests() -> bool {
@@ -521,6 +558,68 @@ pub fn bundle_system() -> bool {
     is_enabled("BUNDLE_SYSTEM")
 }
 
+// Lode-specific configuration
+
+/// Get the release feed `lode self-update` checks (GitHub releases JSON).
+#[must_use]
+pub fn lode_update_url() -> Option<String> {
+    env::var("LODE_UPDATE_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+}
+
+/// Get a local ruby-advisory-db checkout to use instead of fetching one.
+#[must_use]
+pub fn lode_advisory_db() -> Option<String> {
+    env::var("LODE_ADVISORY_DB")
+        .ok()
+        .filter(|path| !path.is_empty())
+}
+
+/// Get the `LODE_LOG` filter, like `debug` or `resolver=debug,download=trace`.
+#[must_use]
+pub fn lode_log() -> Option<String> {
+    env::var("LODE_LOG").ok().filter(|spec| !spec.is_empty())
+}
+
+/// Get the file log lines are written to instead of stderr.
+#[must_use]
+pub fn lode_log_file() -> Option<String> {
+    env::var("LODE_LOG_FILE")
+        .ok()
+        .filter(|path| !path.is_empty())
+}
+
+/// Check if log lines should be JSON (`LODE_LOG_FORMAT=json`).
+#[must_use]
+pub fn lode_log_json() -> bool {
+    env::var("LODE_LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json"))
+}
+
+/// Get the ceiling on requests per second to each gem server.
+#[must_use]
+pub fn lode_max_requests_per_second() -> Option<f64> {
+    env::var("LODE_MAX_REQUESTS_PER_SECOND")
+        .ok()
+        .and_then(|rate| rate.trim().parse().ok())
+}
+
+/// Get the site-wide config file read instead of `/etc/lode/config.toml`.
+#[must_use]
+pub fn lode_system_config() -> Option<String> {
+    env::var("LODE_SYSTEM_CONFIG")
+        .ok()
+        .filter(|path| !path.is_empty())
+}
+
+/// Get the default output level (`LODE_VERBOSITY`: silent, quiet, normal or verbose).
+#[must_use]
+pub fn lode_verbosity() -> Option<String> {
+    env::var("LODE_VERBOSITY")
+        .ok()
+        .filter(|level| !level.is_empty())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -786,6 +885,11 @@ mod tests {
         test_bool_flag(&["1", "true", "yes"], &["0", "false", "no"]);
     }
 
+    #[test]
+    fn bundle_disable_multisource_parsing() {
+        test_bool_flag(&["1", "true", "yes"], &["0", "false", "no"]);
+    }
+
     #[test]
     fn bundle_ignore_funding_requests_parsing() {
         test_bool_flag(&["1", "true", "yes"], &["0", "false", "no"]);
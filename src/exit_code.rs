@@ -0,0 +1,199 @@
+//! Process exit codes
+//!
+//! lode exits 0 on success and 1 on an unclassified failure. The failures
+//! scripts most often need to tell apart get their own codes, matching
+//! Bundler's where it has one so `bundle` scripts keep working:
+//!
+//! | Code | Meaning |
+//! |------|---------|
+//! | 5    | A native extension failed to build |
+//! | 6    | Dependencies could not be resolved |
+//! | 16   | The lockfile is missing, or frozen/deployment mode rejected it |
+//! | 17   | A network request failed |
+//! | 19   | A checksum or signature did not verify |
+//!
+//! `lode exec` exits with the child's own code, or 128 plus the signal
+//! number when the child was killed by a signal.
+
+use std::fmt;
+
+use crate::download::DownloadError;
+use crate::resolver::ResolverError;
+use crate::rubygems_client::RubyGemsError;
+use crate::trust_policy::VerificationError;
+
+/// Why a command failed, as reported to the shell
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    /// Any failure without a more specific code
+    Failure,
+    /// A native extension failed to build
+    ExtensionBuild,
+    /// Dependencies could not be resolved
+    ResolutionConflict,
+    /// The lockfile is missing, or frozen/deployment mode rejected it
+    Lockfile,
+    /// A network request failed
+    Network,
+    /// A checksum or signature did not verify
+    Verification,
+}
+
+impl ExitCode {
+    /// Numeric process exit code
+    #[must_use]
+    pub const fn code(self) -> i32 {
+        match self {
+            Self::Failure => 1,
+            Self::ExtensionBuild => 5,
+            Self::ResolutionConflict => 6,
+            Self::Lockfile => 16,
+            Self::Network => 17,
+            Self::Verification => 19,
+        }
+    }
+
+    /// Error with `message` that exits with this code
+    pub fn error(self, message: impl Into<String>) -> anyhow::Error {
+        anyhow::Error::new(Failure {
+            code: self,
+            message: message.into(),
+        })
+    }
+
+    /// Exit code for `err`, from the first classified error in its chain
+    #[must_use]
+    pub fn classify(err: &anyhow::Error) -> Self {
+        err.chain()
+            .find_map(|cause| {
+                if let Some(failure) = cause.downcast_ref::<Failure>() {
+                    return Some(failure.code);
+                }
+                if let Some(err) = cause.downcast_ref::<ResolverError>() {
+                    return match err {
+                        ResolverError::ResolutionFailed { .. }
+                        | ResolverError::CircularDependency { .. } => {
+                            Some(Self::ResolutionConflict)
+                        }
+                        err if err.is_network() => Some(Self::Network),
+                        _ => None,
+                    };
+                }
+                if let Some(err) = cause.downcast_ref::<RubyGemsError>() {
+                    return is_network(err).then_some(Self::Network);
+                }
+                if let Some(err) = cause.downcast_ref::<DownloadError>() {
+                    return matches!(
+                        err,
+                        DownloadError::NetworkError { .. } | DownloadError::HttpError { .. }
+                    )
+                    .then_some(Self::Network);
+                }
+                if cause.is::<reqwest::Error>() {
+                    return Some(Self::Network);
+                }
+                cause
+                    .is::<VerificationError>()
+                    .then_some(Self::Verification)
+            })
+            .unwrap_or(Self::Failure)
+    }
+}
+
+/// Whether a metadata error means the source could not be reached
+const fn is_network(err: &RubyGemsError) -> bool {
+    matches!(
+        err,
+        RubyGemsError::NetworkError { .. } | RubyGemsError::HttpError { .. }
+    )
+}
+
+/// An error that ends the process with a specific [`ExitCode`]
+///
+/// For failures with no typed error of their own, like a frozen lockfile;
+/// made with [`ExitCode::error`].
+#[derive(Debug)]
+pub struct Failure {
+    code: ExitCode,
+    message: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+/// Exit code to pass on for a finished child process
+///
+/// The child's own code, else 128 plus the signal that killed it, as
+/// shells report it.
+#[must_use]
+pub fn child_exit_code(status: std::process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn classify_finds_typed_errors_in_the_chain() {
+        let conflict = anyhow::Error::new(ResolverError::ResolutionFailed {
+            message: "rails requires rack < 3".to_string(),
+        })
+        .context("Failed to lock");
+        assert_eq!(ExitCode::classify(&conflict), ExitCode::ResolutionConflict);
+
+        let unreachable = anyhow::Error::new(RubyGemsError::HttpError {
+            gem: "rack".to_string(),
+            status: 503,
+            url: "https://rubygems.org".to_string(),
+        });
+        assert_eq!(ExitCode::classify(&unreachable), ExitCode::Network);
+
+        let missing = anyhow::Error::new(ResolverError::NetworkError {
+            gem: "nope".to_string(),
+            source: RubyGemsError::GemNotFound {
+                gem: "nope".to_string(),
+            },
+        });
+        assert_eq!(ExitCode::classify(&missing), ExitCode::Failure);
+
+        let unsigned = Err::<(), _>(VerificationError::UnsignedGem {
+            gem_path: "rack-3.1.7.gem".to_string(),
+        })
+        .context("Gem verification failed")
+        .unwrap_err();
+        assert_eq!(ExitCode::classify(&unsigned), ExitCode::Verification);
+
+        let frozen = ExitCode::Lockfile
+            .error("Gemfile changed")
+            .context("install");
+        assert_eq!(ExitCode::classify(&frozen), ExitCode::Lockfile);
+        assert_eq!(
+            ExitCode::classify(&anyhow::anyhow!("boom")),
+            ExitCode::Failure
+        );
+    }
+
+    #[test]
+    fn codes_match_bundler() {
+        assert_eq!(ExitCode::Failure.code(), 1);
+        assert_eq!(ExitCode::ExtensionBuild.code(), 5);
+        assert_eq!(ExitCode::ResolutionConflict.code(), 6);
+        assert_eq!(ExitCode::Lockfile.code(), 16);
+        assert_eq!(ExitCode::Network.code(), 17);
+        assert_eq!(ExitCode::Verification.code(), 19);
+    }
+}
@@ -1,5 +1,6 @@
 //! Generate wrapper scripts for gem executables.
 
+use crate::gemspec::Gemspec;
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -17,6 +18,8 @@ pub struct BinstubGenerator {
     shebang: Option<String>,
     /// Overwrite existing binstubs
     force: bool,
+    /// Standalone bundle root; stubs load its `bundler/setup.rb` instead of Bundler
+    standalone: Option<PathBuf>,
 }
 
 impl BinstubGenerator {
@@ -33,9 +36,21 @@ impl BinstubGenerator {
             gemfile_path,
             shebang,
             force,
+            standalone: None,
         }
     }
 
+    /// Generate stubs for a standalone bundle (`lode install --standalone`)
+    ///
+    /// The stubs require `<bundle_path>/bundler/setup.rb` and load the
+    /// executable from the gem directory passed to [`Self::generate`], so
+    /// neither Bundler nor Lode is needed at runtime.
+    #[must_use]
+    pub fn with_standalone(mut self, bundle_path: PathBuf) -> Self {
+        self.standalone = Some(bundle_path);
+        self
+    }
+
     /// Generate binstubs for a gem
     ///
     /// # Arguments
@@ -50,7 +65,7 @@ impl BinstubGenerator {
     /// Returns an error if binstub generation fails.
     pub fn generate(&self, gem_name: &str, gem_dir: &Path) -> Result<usize> {
         // Find executables in gem
-        let executables = Self::find_executables(gem_dir)?;
+        let executables = Self::find_executable_paths(gem_dir)?;
 
         if executables.is_empty() {
             return Ok(0);
@@ -63,8 +78,12 @@ impl BinstubGenerator {
 
         // Generate binstub for each executable
         let mut count = 0;
-        for exe_name in &executables {
-            self.create_binstub(exe_name, gem_name)?;
+        for (exe_name, exe_path) in &executables {
+            if let Some(bundle_path) = &self.standalone {
+                self.create_standalone_binstub(exe_name, exe_path, bundle_path)?;
+            } else {
+                self.create_binstub(exe_name, gem_name)?;
+            }
             count += 1;
         }
 
@@ -72,21 +91,34 @@ impl BinstubGenerator {
     }
 
     /// Find executables in a gem directory
-    ///
-    /// Looks in exe/ (modern) and bin/ (legacy) directories.
+    #[cfg(test)]
     fn find_executables(gem_dir: &Path) -> Result<Vec<String>> {
-        let mut executables = Vec::new();
+        Ok(Self::find_executable_paths(gem_dir)?
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect())
+    }
 
-        // Check exe/ directory (modern convention)
-        let exe_dir = gem_dir.join("exe");
-        if exe_dir.exists() && exe_dir.is_dir() {
-            executables.extend(Self::scan_dir(&exe_dir)?);
+    /// Find executables and their paths in a gem directory
+    ///
+    /// Uses the installed gemspec's declared `executables` when available.
+    /// Otherwise looks in exe/ (modern) and bin/ (legacy) directories.
+    fn find_executable_paths(gem_dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+        if let Some(spec) = Gemspec::load_for_gem_dir(gem_dir) {
+            return Ok(spec.executable_paths(gem_dir));
         }
 
-        // Check bin/ directory (legacy convention)
-        let bin_dir = gem_dir.join("bin");
-        if bin_dir.exists() && bin_dir.is_dir() {
-            executables.extend(Self::scan_dir(&bin_dir)?);
+        let mut executables = Vec::new();
+
+        // Check exe/ (modern convention) then bin/ (legacy convention)
+        for dir in [gem_dir.join("exe"), gem_dir.join("bin")] {
+            if dir.is_dir() {
+                executables.extend(
+                    Self::scan_dir(&dir)?
+                        .into_iter()
+                        .map(|name| (name.clone(), dir.join(name))),
+                );
+            }
         }
 
         Ok(executables)
@@ -167,6 +199,56 @@ load Gem.bin_path('{gem_name}', '{exe_name}')
         Ok(())
     }
 
+    /// Create a binstub that runs against a standalone bundle
+    ///
+    /// Mirrors `bundle binstubs --standalone`: the stub requires the bundle's
+    /// generated `setup.rb` and loads the executable by relative path.
+    fn create_standalone_binstub(
+        &self,
+        exe_name: &str,
+        exe_path: &Path,
+        bundle_path: &Path,
+    ) -> Result<()> {
+        let binstub_path = self.bin_dir.join(exe_name);
+
+        // Skip if file exists and not force mode
+        if binstub_path.exists() && !self.force {
+            return Ok(());
+        }
+
+        let setup_rb = bundle_path.join("bundler").join("setup.rb");
+        let relative_setup = Self::calculate_relative_path(&self.bin_dir, &setup_rb);
+        let relative_setup = relative_setup.trim_end_matches(".rb");
+        let relative_exe = Self::calculate_relative_path(&self.bin_dir, exe_path);
+
+        let shebang = self.shebang.as_deref().unwrap_or("#!/usr/bin/env ruby");
+
+        let content = format!(
+            r"{shebang}
+# Generated by Lode
+# This file runs this gem's executable from the standalone bundle,
+# without requiring Bundler or Lode at runtime.
+
+require_relative '{relative_setup}'
+
+load File.expand_path('{relative_exe}', __dir__)
+"
+        );
+
+        fs::write(&binstub_path, content)
+            .with_context(|| format!("Failed to write binstub: {}", binstub_path.display()))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(&binstub_path)?.permissions();
+            perms.set_mode(0o755); // rwxr-xr-x
+            fs::set_permissions(&binstub_path, perms)?;
+        }
+
+        Ok(())
+    }
+
     /// Calculate relative path from one directory to a file
     ///
     /// Similar to Ruby's `File.expand_path('../Gemfile', __dir__)`
@@ -331,6 +413,28 @@ mod tests {
         assert_eq!(executables.len(), 0);
     }
 
+    #[test]
+    fn find_executables_from_gemspec() {
+        let root = TempDir::new().unwrap();
+        let gem_dir = root.path().join("gems").join("rake-13.2.1");
+        fs::create_dir_all(gem_dir.join("exe")).unwrap();
+        fs::create_dir_all(gem_dir.join("bin")).unwrap();
+        fs::write(gem_dir.join("exe").join("rake"), "").unwrap();
+        fs::write(gem_dir.join("bin").join("console"), "").unwrap();
+        fs::create_dir_all(root.path().join("specifications")).unwrap();
+        fs::write(
+            root.path()
+                .join("specifications")
+                .join("rake-13.2.1.gemspec"),
+            "name: rake\nversion: 13.2.1\nbindir: exe\nexecutables:\n- rake\n",
+        )
+        .unwrap();
+
+        let executables = BinstubGenerator::find_executables(&gem_dir).unwrap();
+
+        assert_eq!(executables, vec!["rake".to_string()]);
+    }
+
     #[test]
     fn test_generate_binstubs() {
         let gem_dir = create_gem_with_executables(&["rails", "rake"]);
@@ -390,6 +494,31 @@ mod tests {
         assert!(perms.mode() & 0o111 != 0, "Binstub should be executable");
     }
 
+    #[test]
+    fn standalone_binstub_loads_setup_rb() {
+        let temp = TempDir::new().unwrap();
+        let bundle = temp.path().join("bundle");
+        let gem_dir = bundle.join("ruby/3.3.0/gems/rake-13.2.1");
+        fs::create_dir_all(gem_dir.join("exe")).unwrap();
+        fs::write(gem_dir.join("exe").join("rake"), "").unwrap();
+        fs::create_dir_all(bundle.join("bundler")).unwrap();
+        fs::write(bundle.join("bundler").join("setup.rb"), "").unwrap();
+        let bin_dir = temp.path().join("exec");
+
+        let generator =
+            BinstubGenerator::new(bin_dir.clone(), temp.path().join("Gemfile"), None, false)
+                .with_standalone(bundle);
+        assert_eq!(generator.generate("rake", &gem_dir).unwrap(), 1);
+
+        let content = fs::read_to_string(bin_dir.join("rake")).unwrap();
+        assert!(content.contains("require_relative '../bundle/bundler/setup'"));
+        assert!(content.contains(
+            "load File.expand_path('../bundle/ruby/3.3.0/gems/rake-13.2.1/exe/rake', __dir__)"
+        ));
+        assert!(!content.contains("require 'bundler/setup'"));
+        assert!(!content.contains("BUNDLE_GEMFILE"));
+    }
+
     #[test]
     fn test_calculate_relative_path() {
         let temp = TempDir::new().unwrap();
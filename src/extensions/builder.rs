@@ -6,9 +6,10 @@
 
 use super::c_extension::CExtensionBuilder;
 use super::cmake_extension::CMakeExtensionBuilder;
-use super::detector::detect_extension;
+use super::detector::{detect_extension, detect_extension_from_spec};
 use super::rust_extension::RustExtensionBuilder;
 use super::types::{BuildResult, ExtensionType};
+use crate::gemspec::Gemspec;
 use std::path::Path;
 
 /// Extension builder coordinator
@@ -23,6 +24,8 @@ pub struct ExtensionBuilder {
     verbose: bool,
     /// Path to alternative `RbConfig` for cross-compilation
     rbconfig_path: Option<String>,
+    /// CPU architecture(s) to build C extensions for (`--arch`)
+    arch: Option<String>,
     /// C extension builder (lazy-initialized)
     c_builder: Option<CExtensionBuilder>,
     /// Rust extension builder (lazy-initialized)
@@ -39,15 +42,28 @@ impl ExtensionBuilder {
             skip_extensions,
             verbose,
             rbconfig_path,
+            arch: None,
             c_builder: None,
             rust_builder: None,
             cmake_builder: None,
         }
     }
 
+    /// Build C extensions for `arch` instead of the arch of the running Ruby
+    ///
+    /// Takes `arm64`, `x86_64`, a comma-separated list, or `universal` for a
+    /// fat binary; see [`crate::platform::archflags`].
+    #[must_use]
+    pub fn with_arch(mut self, arch: Option<String>) -> Self {
+        self.arch = arch;
+        self
+    }
+
     /// Build extension if needed
     ///
     /// Detects extension type and builds if necessary. Skips precompiled and pure Ruby gems.
+    /// Uses the installed gemspec's `extensions` list when available, falling back to
+    /// scanning the gem directory.
     ///
     /// # Arguments
     /// * `gem_name` - Name of the gem
@@ -72,9 +88,13 @@ impl ExtensionBuilder {
             return None;
         }
 
-        // Detect extension type
-        let ext_type = detect_extension(gem_dir, gem_name, platform);
+        // Detect extension type (prefer what the gemspec declares)
+        let ext_type = Gemspec::load_for_gem_dir(gem_dir).map_or_else(
+            || detect_extension(gem_dir, gem_name, platform),
+            |spec| detect_extension_from_spec(gem_dir, &spec.extensions, platform),
+        );
 
+        crate::debug!("{gem_name}: {}", ext_type.description());
         if self.verbose {
             println!("Extension type for {gem_name}: {}", ext_type.description());
         }
@@ -92,7 +112,9 @@ impl ExtensionBuilder {
                 // Lazy-initialize C builder
                 if self.c_builder.is_none() {
                     match CExtensionBuilder::new(self.verbose) {
-                        Ok(builder) => self.c_builder = Some(builder),
+                        Ok(builder) => {
+                            self.c_builder = Some(builder.with_arch(self.arch.as_deref()));
+                        }
                         Err(e) => {
                             return Some(BuildResult::failure(
                                 gem_name.to_string(),
@@ -28,6 +28,8 @@ pub struct CExtensionBuilder {
     ruby_path: PathBuf,
     /// Enable verbose output
     verbose: bool,
+    /// `ARCHFLAGS` for an `--arch` override
+    archflags: Option<String>,
 }
 
 impl CExtensionBuilder {
@@ -46,7 +48,21 @@ impl CExtensionBuilder {
         let ruby_path = Self::find_ruby_executable()
             .context("Ruby executable not found. C extensions require Ruby to be installed.")?;
 
-        Ok(Self { ruby_path, verbose })
+        Ok(Self {
+            ruby_path,
+            verbose,
+            archflags: None,
+        })
+    }
+
+    /// Build for `arch` rather than the arch of the running Ruby
+    ///
+    /// `mkmf` reads `ARCHFLAGS`, so this is how an `x86_64` Ruby under
+    /// Rosetta builds an arm64 (or fat) extension.
+    #[must_use]
+    pub fn with_arch(mut self, arch: Option<&str>) -> Self {
+        self.archflags = arch.map(crate::platform::archflags);
+        self
     }
 
     /// Find Ruby executable on the system
@@ -119,7 +135,18 @@ impl CExtensionBuilder {
         }
 
         cmd.arg("extconf.rb");
+        // `build.GEM_NAME` flags, like `--with-xml2-config=...`
+        let build_flags = crate::config::build_flags(crate::Config::load().ok().as_ref(), gem_name);
+        if self.verbose && !build_flags.is_empty() {
+            println!("  Build flags: {}", build_flags.join(" "));
+        }
+        cmd.args(build_flags);
         cmd.current_dir(ext_dir);
+        if self.verbose
+            && let Some(archflags) = &self.archflags
+        {
+            println!("  ARCHFLAGS: {archflags}");
+        }
 
         // Pass build tool environment variables to extconf.rb
         // These affect how mkmf generates the Makefile
@@ -138,6 +165,9 @@ impl CExtensionBuilder {
         if let Some(ldflags) = crate::env_vars::ldflags() {
             cmd.env("LDFLAGS", ldflags);
         }
+        if let Some(archflags) = &self.archflags {
+            cmd.env("ARCHFLAGS", archflags);
+        }
 
         let extconf_result = cmd.output();
 
@@ -157,16 +187,17 @@ impl CExtensionBuilder {
         output.push_str(&String::from_utf8_lossy(&extconf_output.stderr));
 
         if !extconf_output.status.success() {
+            let error = format!(
+                "extconf.rb failed with exit code: {}",
+                extconf_output
+                    .status
+                    .code()
+                    .map_or_else(|| "unknown".to_string(), |c| c.to_string())
+            );
             return BuildResult::failure(
                 gem_name.to_string(),
                 start_time.elapsed(),
-                format!(
-                    "extconf.rb failed with exit code: {}",
-                    extconf_output
-                        .status
-                        .code()
-                        .map_or_else(|| "unknown".to_string(), |c| c.to_string())
-                ),
+                with_diagnosis(error, ext_dir, &output),
                 output,
             );
         }
@@ -198,6 +229,9 @@ impl CExtensionBuilder {
         if let Some(ldflags) = crate::env_vars::ldflags() {
             cmd.env("LDFLAGS", ldflags);
         }
+        if let Some(archflags) = &self.archflags {
+            cmd.env("ARCHFLAGS", archflags);
+        }
 
         let make_result = cmd.output();
 
@@ -217,16 +251,17 @@ impl CExtensionBuilder {
         output.push_str(&String::from_utf8_lossy(&make_output.stderr));
 
         if !make_output.status.success() {
+            let error = format!(
+                "make failed with exit code: {}",
+                make_output
+                    .status
+                    .code()
+                    .map_or_else(|| "unknown".to_string(), |c| c.to_string())
+            );
             return BuildResult::failure(
                 gem_name.to_string(),
                 start_time.elapsed(),
-                format!(
-                    "make failed with exit code: {}",
-                    make_output
-                        .status
-                        .code()
-                        .map_or_else(|| "unknown".to_string(), |c| c.to_string())
-                ),
+                with_diagnosis(error, ext_dir, &output),
                 output,
             );
         }
@@ -331,6 +366,14 @@ impl CExtensionBuilder {
     }
 }
 
+/// `error`, followed by what `mkmf.log` or the output says was missing
+fn with_diagnosis(error: String, ext_dir: &Path, output: &str) -> String {
+    let Some(diagnosis) = super::mkmf_log::diagnose(ext_dir, output) else {
+        return error;
+    };
+    format!("{error}\n{diagnosis}")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -86,6 +86,44 @@ pub fn detect_extension(gem_dir: &Path, _gem_name: &str, platform: Option<&str>)
     ExtensionType::None
 }
 
+/// Detect extension type from a gemspec's declared `extensions` list
+///
+/// Each entry is a path relative to the gem directory (e.g.
+/// `ext/nokogiri/extconf.rb`). The first buildable entry wins. An empty
+/// list means the gem declares no extensions, so nothing is built even if
+/// an `ext/` directory ships with the gem.
+#[must_use]
+pub fn detect_extension_from_spec(
+    gem_dir: &Path,
+    extensions: &[String],
+    platform: Option<&str>,
+) -> ExtensionType {
+    if let Some(plat) = platform
+        && plat != "ruby"
+        && !plat.is_empty()
+    {
+        return ExtensionType::Precompiled;
+    }
+
+    for extension in extensions {
+        let path = gem_dir.join(extension);
+        match path.file_name().and_then(|n| n.to_str()) {
+            Some("extconf.rb") => {
+                let ext_dir = path.parent().unwrap_or(gem_dir).to_path_buf();
+                return ExtensionType::CExtension {
+                    ext_dir,
+                    extconf_path: path,
+                };
+            }
+            Some("Cargo.toml") => return ExtensionType::RustExtension { cargo_toml: path },
+            Some("CMakeLists.txt") => return ExtensionType::CMakeExtension { cmake_lists: path },
+            _ => {}
+        }
+    }
+
+    ExtensionType::None
+}
+
 /// Check if a gem name indicates it's precompiled (has platform suffix)
 ///
 /// Examples:
@@ -177,6 +215,42 @@ mod tests {
         assert!(!ext_type.needs_building());
     }
 
+    #[test]
+    fn detect_from_spec_extconf() {
+        let gem_dir = create_test_gem("nokogiri", &["ext/nokogiri/extconf.rb"]);
+        let extensions = vec!["ext/nokogiri/extconf.rb".to_string()];
+
+        let ext_type = detect_extension_from_spec(gem_dir.path(), &extensions, None);
+
+        assert_eq!(
+            ext_type,
+            ExtensionType::CExtension {
+                ext_dir: gem_dir.path().join("ext/nokogiri"),
+                extconf_path: gem_dir.path().join("ext/nokogiri/extconf.rb"),
+            }
+        );
+    }
+
+    #[test]
+    fn detect_from_spec_ignores_undeclared_ext_dir() {
+        let gem_dir = create_test_gem("ffi_helpers", &["ext/helpers/extconf.rb"]);
+
+        let ext_type = detect_extension_from_spec(gem_dir.path(), &[], None);
+
+        assert_eq!(ext_type, ExtensionType::None);
+    }
+
+    #[test]
+    fn detect_from_spec_precompiled() {
+        let gem_dir = create_test_gem("nokogiri", &["ext/nokogiri/extconf.rb"]);
+        let extensions = vec!["ext/nokogiri/extconf.rb".to_string()];
+
+        let ext_type =
+            detect_extension_from_spec(gem_dir.path(), &extensions, Some("x86_64-linux"));
+
+        assert_eq!(ext_type, ExtensionType::Precompiled);
+    }
+
     #[test]
     fn test_has_platform_suffix() {
         assert!(has_platform_suffix("nokogiri-1.14.0-arm64-darwin"));
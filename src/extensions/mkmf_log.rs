@@ -0,0 +1,450 @@
+//! Failed `extconf.rb` diagnosis
+//!
+//! When a C extension fails to configure or compile, the real cause is
+//! usually a missing system header or library buried in `mkmf.log` or the
+//! compiler output. This module finds it and, for the libraries gems most
+//! often need (libxml2, `PostgreSQL`, `OpenSSL`, `MySQL`, ...), suggests the
+//! package to install with the local package manager.
+
+use std::env;
+use std::fmt::{self, Write};
+use std::fs;
+use std::path::Path;
+
+/// Something a failed build could not find
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Missing {
+    /// A header, like `libpq-fe.h` or `libxml/parser.h`
+    Header(String),
+    /// A library to link, like `pq` for `-lpq`
+    Library(String),
+    /// A `pkg-config` package, like `libxml-2.0`
+    Package(String),
+    /// No working C compiler
+    Compiler,
+}
+
+impl fmt::Display for Missing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Header(header) => write!(f, "header {header}"),
+            Self::Library(library) => write!(f, "library -l{library}"),
+            Self::Package(package) => write!(f, "pkg-config package {package}"),
+            Self::Compiler => f.write_str("C compiler"),
+        }
+    }
+}
+
+/// Package managers install suggestions are given for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PackageManager {
+    Brew,
+    Apt,
+    Dnf,
+    Apk,
+}
+
+impl PackageManager {
+    const fn install_command(self) -> &'static str {
+        match self {
+            Self::Brew => "brew install",
+            Self::Apt => "sudo apt-get install",
+            Self::Dnf => "sudo dnf install",
+            Self::Apk => "sudo apk add",
+        }
+    }
+}
+
+/// A system library with the files that give it away and its packages
+struct SystemLibrary {
+    name: &'static str,
+    /// Header paths or directory prefixes (`openssl/`)
+    headers: &'static [&'static str],
+    /// Names passed to `-l`
+    libraries: &'static [&'static str],
+    /// `pkg-config` package names
+    packages: &'static [&'static str],
+    brew: &'static str,
+    apt: &'static str,
+    dnf: &'static str,
+    apk: &'static str,
+}
+
+impl SystemLibrary {
+    fn provides(&self, missing: &Missing) -> bool {
+        match missing {
+            Missing::Header(header) => self.headers.iter().any(|known| {
+                if known.ends_with('/') {
+                    header.starts_with(known)
+                } else {
+                    header == known
+                }
+            }),
+            Missing::Library(library) => self.libraries.contains(&library.as_str()),
+            Missing::Package(package) => self.packages.contains(&package.as_str()),
+            Missing::Compiler => false,
+        }
+    }
+
+    const fn package(&self, manager: PackageManager) -> &'static str {
+        match manager {
+            PackageManager::Brew => self.brew,
+            PackageManager::Apt => self.apt,
+            PackageManager::Dnf => self.dnf,
+            PackageManager::Apk => self.apk,
+        }
+    }
+}
+
+/// Libraries native gems commonly build against
+const SYSTEM_LIBRARIES: &[SystemLibrary] = &[
+    SystemLibrary {
+        name: "libxml2",
+        headers: &["libxml/", "libxml2/"],
+        libraries: &["xml2"],
+        packages: &["libxml-2.0"],
+        brew: "libxml2",
+        apt: "libxml2-dev",
+        dnf: "libxml2-devel",
+        apk: "libxml2-dev",
+    },
+    SystemLibrary {
+        name: "libxslt",
+        headers: &["libxslt/", "libexslt/"],
+        libraries: &["xslt", "exslt"],
+        packages: &["libxslt", "libexslt"],
+        brew: "libxslt",
+        apt: "libxslt1-dev",
+        dnf: "libxslt-devel",
+        apk: "libxslt-dev",
+    },
+    SystemLibrary {
+        name: "PostgreSQL client library",
+        headers: &["libpq-fe.h", "pg_config.h", "postgres_ext.h"],
+        libraries: &["pq"],
+        packages: &["libpq"],
+        brew: "libpq",
+        apt: "libpq-dev",
+        dnf: "libpq-devel",
+        apk: "postgresql-dev",
+    },
+    SystemLibrary {
+        name: "OpenSSL",
+        headers: &["openssl/"],
+        libraries: &["ssl", "crypto"],
+        packages: &["openssl", "libssl", "libcrypto"],
+        brew: "openssl@3",
+        apt: "libssl-dev",
+        dnf: "openssl-devel",
+        apk: "openssl-dev",
+    },
+    SystemLibrary {
+        name: "MySQL client library",
+        headers: &["mysql.h", "mysql/"],
+        libraries: &["mysqlclient", "mariadb"],
+        packages: &["mysqlclient", "libmariadb"],
+        brew: "mysql-client",
+        apt: "default-libmysqlclient-dev",
+        dnf: "mysql-devel",
+        apk: "mariadb-dev",
+    },
+    SystemLibrary {
+        name: "SQLite",
+        headers: &["sqlite3.h"],
+        libraries: &["sqlite3"],
+        packages: &["sqlite3"],
+        brew: "sqlite",
+        apt: "libsqlite3-dev",
+        dnf: "sqlite-devel",
+        apk: "sqlite-dev",
+    },
+    SystemLibrary {
+        name: "libyaml",
+        headers: &["yaml.h"],
+        libraries: &["yaml"],
+        packages: &["yaml-0.1"],
+        brew: "libyaml",
+        apt: "libyaml-dev",
+        dnf: "libyaml-devel",
+        apk: "yaml-dev",
+    },
+    SystemLibrary {
+        name: "zlib",
+        headers: &["zlib.h"],
+        libraries: &["z"],
+        packages: &["zlib"],
+        brew: "zlib",
+        apt: "zlib1g-dev",
+        dnf: "zlib-devel",
+        apk: "zlib-dev",
+    },
+    SystemLibrary {
+        name: "libffi",
+        headers: &["ffi.h"],
+        libraries: &["ffi"],
+        packages: &["libffi"],
+        brew: "libffi",
+        apt: "libffi-dev",
+        dnf: "libffi-devel",
+        apk: "libffi-dev",
+    },
+];
+
+/// Explain a failed build: what was missing and how to install it
+///
+/// Looks at the build output first, where the last failed `checking for
+/// ...` line names what aborted `extconf.rb`, then at `mkmf.log` in
+/// `ext_dir`. Returns `None` when neither names a missing dependency.
+#[must_use]
+pub fn diagnose(ext_dir: &Path, output: &str) -> Option<String> {
+    let log_path = ext_dir.join("mkmf.log");
+    let log = fs::read_to_string(&log_path).unwrap_or_default();
+    let missing = find_missing(output).or_else(|| find_missing(&log))?;
+
+    let mut message = format!("Missing {missing}");
+    if let Some(library) = SYSTEM_LIBRARIES
+        .iter()
+        .find(|library| library.provides(&missing))
+    {
+        let _ = write!(message, " ({})", library.name);
+        for manager in package_managers() {
+            let _ = write!(
+                message,
+                "\n  Install it with: {} {}",
+                manager.install_command(),
+                library.package(manager)
+            );
+        }
+    } else if missing == Missing::Compiler {
+        let _ = write!(message, "\n  {}", compiler_hint());
+    } else {
+        message.push_str("\n  Install the development package that provides it");
+    }
+    if log_path.is_file() {
+        let _ = write!(message, "\n  See {}", log_path.display());
+    }
+    Some(message)
+}
+
+/// The dependency whose absence failed the build, from `extconf.rb`
+/// output, `mkmf.log` or compiler output
+///
+/// The last mention wins: earlier failed checks are often optional.
+#[must_use]
+pub fn find_missing(text: &str) -> Option<Missing> {
+    text.lines().rev().find_map(missing_in_line)
+}
+
+fn missing_in_line(line: &str) -> Option<Missing> {
+    let line = line.trim();
+
+    if line.contains("You have to install development tools first")
+        || line.contains("C compiler cannot create executables")
+    {
+        return Some(Missing::Compiler);
+    }
+
+    // extconf.rb progress: "checking for xmlParseDoc() in -lxml2... no"
+    if let Some(check) = line
+        .strip_prefix("checking for ")
+        .and_then(|rest| rest.strip_suffix("... no"))
+    {
+        if Path::new(check)
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("h"))
+        {
+            return Some(Missing::Header(check.to_string()));
+        }
+        return check
+            .rsplit_once(" in -l")
+            .map(|(_, library)| library)
+            .or_else(|| check.strip_prefix("-l"))
+            .map(|library| Missing::Library(library.trim().to_string()));
+    }
+
+    // clang: "fatal error: 'libpq-fe.h' file not found"
+    if let Some(header) = line
+        .split_once("fatal error: '")
+        .and_then(|(_, rest)| rest.split_once("' file not found"))
+        .map(|(header, _)| header)
+    {
+        return Some(Missing::Header(header.to_string()));
+    }
+    // gcc: "fatal error: libpq-fe.h: No such file or directory"
+    if let Some(header) = line
+        .split_once("fatal error: ")
+        .and_then(|(_, rest)| rest.split_once(": No such file or directory"))
+        .map(|(header, _)| header)
+    {
+        return Some(Missing::Header(header.to_string()));
+    }
+
+    // ld64: "library not found for -lpq"; GNU ld: "cannot find -lpq"
+    if let Some(library) = ["library not found for -l", "cannot find -l"]
+        .iter()
+        .find_map(|marker| line.split_once(marker).map(|(_, rest)| rest))
+    {
+        let library = library
+            .split(|c: char| c.is_whitespace() || c == ':')
+            .next()?;
+        return Some(Missing::Library(library.to_string()));
+    }
+
+    // pkg_config: "package configuration for libxml-2.0 is not found"
+    line.split_once("package configuration for ")
+        .and_then(|(_, rest)| rest.split_once(" is not found"))
+        .map(|(package, _)| Missing::Package(package.to_string()))
+}
+
+/// Package managers to suggest on this machine
+fn package_managers() -> Vec<PackageManager> {
+    match env::consts::OS {
+        "macos" => vec![PackageManager::Brew],
+        "linux" => os_release_managers(&fs::read_to_string("/etc/os-release").unwrap_or_default()),
+        _ => Vec::new(),
+    }
+}
+
+/// Package managers for a Linux distribution, from `/etc/os-release`
+///
+/// Every common one when the distribution is not recognized.
+fn os_release_managers(os_release: &str) -> Vec<PackageManager> {
+    let ids: Vec<&str> = os_release
+        .lines()
+        .filter_map(|line| {
+            line.strip_prefix("ID=")
+                .or_else(|| line.strip_prefix("ID_LIKE="))
+        })
+        .flat_map(|value| value.trim_matches('"').split_whitespace())
+        .collect();
+
+    let known = [
+        (&["debian", "ubuntu"][..], PackageManager::Apt),
+        (&["fedora", "rhel", "centos"][..], PackageManager::Dnf),
+        (&["alpine"][..], PackageManager::Apk),
+    ];
+    known
+        .iter()
+        .find(|(names, _)| ids.iter().any(|id| names.contains(id)))
+        .map_or_else(
+            || {
+                vec![
+                    PackageManager::Apt,
+                    PackageManager::Dnf,
+                    PackageManager::Apk,
+                ]
+            },
+            |(_, manager)| vec![*manager],
+        )
+}
+
+/// How to get a C compiler on this machine
+fn compiler_hint() -> &'static str {
+    if env::consts::OS == "macos" {
+        "Install the command line tools with: xcode-select --install"
+    } else {
+        "Install a C compiler and make (build-essential, gcc and make, or build-base)"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn finds_the_check_that_aborted_extconf() {
+        let output = "checking for xmlParseDoc() in -lxml2... yes\n\
+                      checking for ruby/st.h... no\n\
+                      checking for libpq-fe.h... no\n\
+                      Can't find the 'libpq-fe.h header\n";
+        assert_eq!(
+            find_missing(output),
+            Some(Missing::Header("libpq-fe.h".to_string()))
+        );
+        assert_eq!(
+            find_missing("checking for PQconnectdb() in -lpq... no"),
+            Some(Missing::Library("pq".to_string()))
+        );
+    }
+
+    #[test]
+    fn finds_compiler_and_linker_errors() {
+        assert_eq!(
+            find_missing("conftest.c:3:10: fatal error: 'openssl/ssl.h' file not found"),
+            Some(Missing::Header("openssl/ssl.h".to_string()))
+        );
+        assert_eq!(
+            find_missing("conftest.c:1:10: fatal error: mysql.h: No such file or directory"),
+            Some(Missing::Header("mysql.h".to_string()))
+        );
+        assert_eq!(
+            find_missing("ld: library not found for -lssl"),
+            Some(Missing::Library("ssl".to_string()))
+        );
+        assert_eq!(
+            find_missing("/usr/bin/ld: cannot find -lxml2: No such file or directory"),
+            Some(Missing::Library("xml2".to_string()))
+        );
+        assert_eq!(
+            find_missing("package configuration for libxml-2.0 is not found"),
+            Some(Missing::Package("libxml-2.0".to_string()))
+        );
+        assert_eq!(find_missing("make: *** [all] Error 2"), None);
+    }
+
+    #[test]
+    fn known_libraries_map_to_packages() {
+        let provider = |missing: Missing| {
+            SYSTEM_LIBRARIES
+                .iter()
+                .find(|library| library.provides(&missing))
+                .map(|library| library.package(PackageManager::Apt))
+        };
+        assert_eq!(
+            provider(Missing::Header("libxml/parser.h".to_string())),
+            Some("libxml2-dev")
+        );
+        assert_eq!(
+            provider(Missing::Library("pq".to_string())),
+            Some("libpq-dev")
+        );
+        assert_eq!(
+            provider(Missing::Header("openssl/ssl.h".to_string())),
+            Some("libssl-dev")
+        );
+        assert_eq!(provider(Missing::Header("foo.h".to_string())), None);
+    }
+
+    #[test]
+    fn distribution_picks_package_manager() {
+        assert_eq!(
+            os_release_managers("ID=ubuntu\nID_LIKE=debian\n"),
+            vec![PackageManager::Apt]
+        );
+        assert_eq!(
+            os_release_managers("ID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\n"),
+            vec![PackageManager::Dnf]
+        );
+        assert_eq!(
+            os_release_managers("ID=alpine\n"),
+            vec![PackageManager::Apk]
+        );
+        assert_eq!(os_release_managers("").len(), 3);
+    }
+
+    #[test]
+    fn diagnose_points_at_mkmf_log() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join("mkmf.log"),
+            "conftest.c:3:10: fatal error: 'sqlite3.h' file not found\n",
+        )
+        .unwrap();
+
+        let message = diagnose(temp.path(), "*** extconf.rb failed ***").unwrap();
+        assert!(message.starts_with("Missing header sqlite3.h (SQLite)"));
+        assert!(message.contains("mkmf.log"));
+        assert_eq!(diagnose(temp.path().join("none").as_path(), ""), None);
+    }
+}
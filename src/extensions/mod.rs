@@ -9,12 +9,15 @@
 //! - Rust extensions (`Cargo.toml`)
 //! - `CMake` extensions (`CMakeLists.txt`)
 //! - Precompiled (no build needed)
+//!
+//! Failed C extension builds are explained from `mkmf.log`.
 
 pub mod binstubs;
 pub mod builder;
 pub mod c_extension;
 pub mod cmake_extension;
 pub mod detector;
+pub mod mkmf_log;
 pub mod rust_extension;
 pub mod types;
 
@@ -22,6 +25,6 @@ pub use binstubs::{BinstubGenerator, generate_binstubs};
 pub use builder::{ExtensionBuilder, build_extensions};
 pub use c_extension::CExtensionBuilder;
 pub use cmake_extension::CMakeExtensionBuilder;
-pub use detector::{detect_extension, has_platform_suffix};
+pub use detector::{detect_extension, detect_extension_from_spec, has_platform_suffix};
 pub use rust_extension::RustExtensionBuilder;
 pub use types::{BuildResult, ExtensionType};
@@ -1,11 +1,22 @@
 //! Download and parse the complete `RubyGems` index (specs.4.8.gz).
+//!
+//! A parsed index is cached on disk in a binary format that is read one gem
+//! at a time, so a lookup never loads or checksums the whole file.
 
-use alox_48::{Value, from_bytes};
+use crate::marshal::{self, Value};
 use anyhow::{Context, Result};
-use flate2::read::GzDecoder;
+use flate2::write::GzDecoder;
+use futures_util::StreamExt;
+use reqwest::StatusCode;
+use reqwest::header::{ETAG, HeaderMap, IF_RANGE, LAST_MODIFIED, RANGE};
 use std::collections::HashMap;
-use std::io::Read;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
 
 /// A gem specification from the full index
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -45,49 +56,85 @@ impl IndexGemSpec {
 /// Full `RubyGems` index
 #[derive(Debug)]
 pub struct FullIndex {
-    /// Map of gem name to list of available versions
-    specs: HashMap<String, Vec<IndexGemSpec>>,
+    /// Gem name to its available versions
+    specs: Specs,
 
     /// Total number of gem specs in the index
     total_count: usize,
 }
 
+/// Where an index's specs live
+#[derive(Debug)]
+enum Specs {
+    /// Parsed from Marshal
+    Parsed(HashMap<String, Vec<IndexGemSpec>>),
+
+    /// Read from the binary cache, decoded one gem at a time
+    Cached(CachedSpecs),
+}
+
 impl FullIndex {
     /// Download and parse the full `RubyGems` index
     ///
-    /// Downloads from `https://rubygems.org/specs.4.8.gz` by default.
+    /// Downloads `specs.4.8.gz` from `base_url` (`https://rubygems.org/` by
+    /// default) into `cache_dir`, decompressing as it streams. An
+    /// interrupted download is resumed with a `Range` request as long as the
+    /// server's `ETag` (or `Last-Modified`) still matches. `progress` gets the
+    /// bytes received so far and the total size, when known.
+    ///
+    /// The index is verified (length, gzip checksum, Marshal structure)
+    /// before it is returned, so a bad download never replaces a good cache.
     ///
     /// # Errors
     ///
     /// Returns an error if:
     /// - Network request fails
-    /// - Decompression fails
+    /// - Decompression or gzip verification fails
     /// - Marshal parsing fails
-    pub async fn download_and_parse(base_url: &str) -> Result<Self> {
+    pub async fn download_and_parse(
+        base_url: &str,
+        cache_dir: &Path,
+        progress: impl Fn(u64, Option<u64>) + Sync,
+    ) -> Result<Self> {
         let url = if base_url.ends_with('/') {
             format!("{base_url}specs.4.8.gz")
         } else {
             format!("{base_url}/specs.4.8.gz")
         };
+        std::fs::create_dir_all(cache_dir)
+            .with_context(|| format!("Failed to create {}", cache_dir.display()))?;
+        let partial = PartialDownload::in_dir(cache_dir);
+
+        let marshal_data = match partial.fetch(&url, &progress).await {
+            Ok(data) => data,
+            Err(e @ IndexFetchError::Corrupt(_)) => {
+                partial.discard();
+                return Err(e.into());
+            }
+            Err(e) => return Err(e.into()),
+        };
 
-        // Download compressed index
-        let response = reqwest::get(&url)
-            .await
-            .with_context(|| format!("Failed to download full index from {url}"))?;
-
-        let compressed_data = response
-            .bytes()
-            .await
-            .context("Failed to read response body")?;
+        let index = Self::parse(&marshal_data);
+        partial.discard();
+        let index = index.context("Downloaded full index failed validation")?;
+        if index.total_count == 0 {
+            anyhow::bail!("Downloaded full index from {url} has no gems");
+        }
+        Ok(index)
+    }
 
-        // Decompress gzip data
-        let mut decoder = GzDecoder::new(&compressed_data[..]);
-        let mut marshal_data = Vec::new();
+    /// Parse a gzipped Marshal index as served (`specs.4.8.gz`)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if decompression or Marshal parsing fails
+    pub fn parse_gzipped(compressed_data: &[u8]) -> Result<Self> {
+        let mut decoder = GzDecoder::new(Vec::new());
         decoder
-            .read_to_end(&mut marshal_data)
+            .write_all(compressed_data)
             .context("Failed to decompress gzip data")?;
+        let marshal_data = decoder.finish().context("Failed to decompress gzip data")?;
 
-        // Parse Marshal format
         Self::parse(&marshal_data)
     }
 
@@ -97,8 +144,7 @@ impl FullIndex {
     ///
     /// Returns an error if Marshal parsing fails or data format is invalid
     pub fn parse(marshal_data: &[u8]) -> Result<Self> {
-        // Parse Marshal format using alox-48
-        let value: Value = from_bytes(marshal_data).context("Failed to parse Marshal data")?;
+        let value = marshal::load(marshal_data).context("Failed to parse Marshal data")?;
 
         // Extract array of specs
         let array = value
@@ -115,7 +161,10 @@ impl FullIndex {
             total_count += 1;
         }
 
-        Ok(Self { specs, total_count })
+        Ok(Self {
+            specs: Specs::Parsed(specs),
+            total_count,
+        })
     }
 
     /// Parse a single spec entry from Marshal data
@@ -146,43 +195,25 @@ impl FullIndex {
     }
 
     /// Extract string from Marshal Value
+    ///
+    /// Versions are usually `Gem::Version` objects rather than strings.
     fn extract_string(value: &Value, field_name: &str) -> Result<String> {
-        // Try direct string first
-        if let Some(rb_string) = value.as_string() {
-            return String::from_utf8(rb_string.data.clone())
-                .with_context(|| format!("Invalid UTF-8 in {field_name}"));
-        }
-
-        // Try as array (for Gem::Version objects which contain [version_string])
-        if let Some(arr) = value.as_array()
-            && let Some(first) = arr.first()
-            && let Some(rb_string) = first.as_string()
-        {
-            return String::from_utf8(rb_string.data.clone())
-                .with_context(|| format!("Invalid UTF-8 in {field_name}"));
-        }
-
-        // Try as object (for other wrapped values)
-        if let Some(obj) = value.as_object() {
-            // Try common field names
-            for key in &["__value", "version", "@version", "v", "@v"] {
-                let symbol = alox_48::Symbol::from((*key).to_string());
-                if let Some(field) = obj.fields.get(&symbol)
-                    && let Some(rb_string) = field.as_string()
-                {
-                    return String::from_utf8(rb_string.data.clone())
-                        .with_context(|| format!("Invalid UTF-8 in {field_name}"));
-                }
-            }
-        }
-
-        anyhow::bail!("Unable to extract string from {field_name}: unexpected format")
+        let bytes = value
+            .as_bytes()
+            .or_else(|| value.gem_version().map(str::as_bytes))
+            .with_context(|| {
+                format!("Unable to extract string from {field_name}: unexpected format")
+            })?;
+        String::from_utf8(bytes.to_vec()).with_context(|| format!("Invalid UTF-8 in {field_name}"))
     }
 
     /// Find all versions of a gem
     #[must_use]
-    pub fn find_gem(&self, name: &str) -> Option<&Vec<IndexGemSpec>> {
-        self.specs.get(name)
+    pub fn find_gem(&self, name: &str) -> Option<Vec<IndexGemSpec>> {
+        match &self.specs {
+            Specs::Parsed(specs) => specs.get(name).cloned(),
+            Specs::Cached(cached) => cached.find(name),
+        }
     }
 
     /// Get total number of gem specs in the index
@@ -194,45 +225,430 @@ impl FullIndex {
     /// Get number of unique gems
     #[must_use]
     pub fn gem_count(&self) -> usize {
-        self.specs.len()
+        match &self.specs {
+            Specs::Parsed(specs) => specs.len(),
+            Specs::Cached(cached) => cached.gem_count(),
+        }
     }
 
-    /// Save parsed index to cache file
+    /// Save the index to a binary cache file
     ///
     /// # Errors
     ///
     /// Returns an error if file operations fail
     pub fn save_to_cache(&self, cache_path: &Path) -> Result<()> {
-        let serialized =
-            serde_json::to_vec(&self.specs).context("Failed to serialize index to JSON")?;
+        let serialized = match &self.specs {
+            Specs::Parsed(specs) => CachedSpecs::encode(specs, self.total_count)?,
+            Specs::Cached(cached) if cached.path == cache_path => return Ok(()),
+            Specs::Cached(cached) => std::fs::read(&cached.path)
+                .with_context(|| format!("Failed to read cache from {}", cached.path.display()))?,
+        };
 
-        std::fs::write(cache_path, serialized)
+        // Replace the old cache only once the new one is fully written
+        let dir = cache_path.parent().unwrap_or_else(|| Path::new("."));
+        let mut temp = tempfile::NamedTempFile::new_in(dir)
+            .with_context(|| format!("Failed to write cache to {}", cache_path.display()))?;
+        temp.write_all(&serialized)
             .with_context(|| format!("Failed to write cache to {}", cache_path.display()))?;
+        temp.persist(cache_path)
+            .with_context(|| format!("Failed to write cache to {}", cache_path.display()))?;
+
+        // Caches from before the binary format are never read again
+        if let Err(e) = remove_if_exists(&dir.join(LEGACY_CACHE_FILE)) {
+            crate::debug!("could not remove legacy full index cache: {e}");
+        }
 
         Ok(())
     }
 
     /// Load index from cache file
     ///
+    /// Only the header and directory are read up front; each gem's versions
+    /// are read from disk and checked when it is looked up.
+    ///
     /// # Errors
     ///
-    /// Returns an error if file operations fail or JSON is invalid
+    /// Returns an error if the file cannot be read, was written by another
+    /// format version, or its directory fails its checksum
     pub fn load_from_cache(cache_path: &Path) -> Result<Self> {
-        let data = std::fs::read(cache_path)
-            .with_context(|| format!("Failed to read cache from {}", cache_path.display()))?;
+        let cached = CachedSpecs::open(cache_path)
+            .with_context(|| format!("Invalid full index cache {}", cache_path.display()))?;
 
-        let specs: HashMap<String, Vec<IndexGemSpec>> =
-            serde_json::from_slice(&data).context("Failed to deserialize cache JSON")?;
-
-        let total_count = specs.values().map(Vec::len).sum();
-
-        Ok(Self { specs, total_count })
+        Ok(Self {
+            total_count: cached.total_count,
+            specs: Specs::Cached(cached),
+        })
     }
 
     /// Get cache file path for full index
     #[must_use]
     pub fn cache_path(cache_dir: &Path) -> PathBuf {
-        cache_dir.join("full_index.json")
+        cache_dir.join("full_index.bin")
+    }
+}
+
+/// Cache file written before the binary format
+const LEGACY_CACHE_FILE: &str = "full_index.json";
+
+/// Identifies a binary full index cache
+const CACHE_MAGIC: &[u8; 8] = b"LODEIDX\0";
+
+/// Bumped whenever the layout below changes
+const CACHE_VERSION: u32 = 2;
+
+/// Magic, version, CRC-32 of the directory, gem count, spec count
+const CACHE_HEADER_LEN: usize = 24;
+
+/// Offset and CRC-32 of one record
+const DIRECTORY_ENTRY_LEN: usize = 8;
+
+/// A binary full index cache, read lazily from disk
+///
+/// Layout (little-endian):
+///
+/// ```text
+/// header:    magic[8] version:u32 crc32:u32 gems:u32 specs:u32
+/// directory: gems x (offset:u32 crc32:u32), sorted by gem name
+/// records:   name_len:u16 name spec_count:u32
+///            spec_count x (version_len:u16 version platform_len:u16 platform)
+/// ```
+///
+/// Offsets are relative to the start of the body (after the header), and a
+/// record ends where the next one starts. Loading reads the header and the
+/// directory, checked against the header's CRC; a lookup is a binary search
+/// that reads each probed record and checks it against its own CRC.
+///
+/// The file is read with positioned reads rather than memory-mapped: lode
+/// has no mapping dependency, and a mapping would fault if another lode
+/// process truncated the cache while it was in use. Reads only touch the
+/// records a lookup needs, so the cost stays proportional to the gems
+/// resolved rather than to the whole index.
+#[derive(Debug)]
+struct CachedSpecs {
+    file: Mutex<File>,
+    path: PathBuf,
+    directory: Vec<u8>,
+    body_len: u64,
+    gem_count: usize,
+    total_count: usize,
+}
+
+impl CachedSpecs {
+    /// Open a cache file, validating its header and directory
+    fn open(path: &Path) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+
+        let mut header = [0; CACHE_HEADER_LEN];
+        file.read_exact(&mut header).context("truncated header")?;
+        let mut header = Cursor::new(&header);
+        if header.take(CACHE_MAGIC.len()) != Some(CACHE_MAGIC.as_slice()) {
+            anyhow::bail!("not a lode full index cache");
+        }
+        let version = header.u32();
+        if version != Some(CACHE_VERSION) {
+            anyhow::bail!("unsupported cache format {version:?}");
+        }
+        let (Some(crc), Some(gem_count), Some(total_count)) =
+            (header.u32(), header.u32(), header.u32())
+        else {
+            anyhow::bail!("truncated header");
+        };
+
+        let mut directory = vec![0; gem_count as usize * DIRECTORY_ENTRY_LEN];
+        file.read_exact(&mut directory)
+            .context("truncated directory")?;
+        if crc32fast::hash(&directory) != crc {
+            anyhow::bail!("directory checksum mismatch");
+        }
+
+        Ok(Self {
+            file: Mutex::new(file),
+            path: path.to_path_buf(),
+            directory,
+            body_len: file_len.saturating_sub(CACHE_HEADER_LEN as u64),
+            gem_count: gem_count as usize,
+            total_count: total_count as usize,
+        })
+    }
+
+    /// Serialize parsed specs, directory sorted by name
+    fn encode(specs: &HashMap<String, Vec<IndexGemSpec>>, total_count: usize) -> Result<Vec<u8>> {
+        let mut names: Vec<&String> = specs.keys().collect();
+        names.sort_unstable();
+
+        let mut directory = Vec::with_capacity(names.len() * DIRECTORY_ENTRY_LEN);
+        let mut records = Vec::new();
+        let directory_len = names.len() * DIRECTORY_ENTRY_LEN;
+        for name in names {
+            let offset = u32::try_from(directory_len + records.len())
+                .context("Full index too large to cache")?;
+
+            let mut record = Vec::new();
+            let versions = specs.get(name).map_or(&[][..], Vec::as_slice);
+            put_str(&mut record, name)?;
+            record.extend_from_slice(&u32::try_from(versions.len())?.to_le_bytes());
+            for spec in versions {
+                put_str(&mut record, &spec.version)?;
+                put_str(&mut record, &spec.platform)?;
+            }
+
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(&crc32fast::hash(&record).to_le_bytes());
+            records.append(&mut record);
+        }
+
+        let mut data = Vec::with_capacity(CACHE_HEADER_LEN + directory.len() + records.len());
+        data.extend_from_slice(CACHE_MAGIC);
+        data.extend_from_slice(&CACHE_VERSION.to_le_bytes());
+        data.extend_from_slice(&crc32fast::hash(&directory).to_le_bytes());
+        data.extend_from_slice(&u32::try_from(specs.len())?.to_le_bytes());
+        data.extend_from_slice(&u32::try_from(total_count)?.to_le_bytes());
+        data.append(&mut directory);
+        data.append(&mut records);
+        Ok(data)
+    }
+
+    const fn gem_count(&self) -> usize {
+        self.gem_count
+    }
+
+    /// Decode the versions of gem `name`, if it is indexed
+    fn find(&self, name: &str) -> Option<Vec<IndexGemSpec>> {
+        let (mut low, mut high) = (0, self.gem_count);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let bytes = self.record(middle)?;
+            let mut record = Cursor::new(&bytes);
+            let found = record.str()?;
+            match found.cmp(name) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => {
+                    let count = record.u32()?;
+                    return (0..count)
+                        .map(|_| {
+                            Some(IndexGemSpec::new(
+                                name.to_string(),
+                                record.str()?.to_string(),
+                                record.str()?.to_string(),
+                            ))
+                        })
+                        .collect();
+                }
+            }
+        }
+        None
+    }
+
+    /// Read the `index`th record, checked against its CRC-32
+    fn record(&self, index: usize) -> Option<Vec<u8>> {
+        let entry = |index: usize| {
+            let mut entry = Cursor::new(self.directory.get(index * DIRECTORY_ENTRY_LEN..)?);
+            Some((u64::from(entry.u32()?), entry.u32()?))
+        };
+        let (start, crc) = entry(index)?;
+        let end = entry(index + 1).map_or(self.body_len, |(offset, _)| offset);
+
+        let mut bytes = vec![0; usize::try_from(end.checked_sub(start)?).ok()?];
+        let read = self.file.lock().ok().and_then(|mut file| {
+            file.seek(SeekFrom::Start(CACHE_HEADER_LEN as u64 + start))
+                .and_then(|_| file.read_exact(&mut bytes))
+                .ok()
+        });
+        if read.is_none() || crc32fast::hash(&bytes) != crc {
+            crate::warn!(
+                "Full index cache {} is corrupt; remove it to rebuild",
+                self.path.display()
+            );
+            return None;
+        }
+        Some(bytes)
+    }
+}
+
+/// Reads little-endian fields from a cache body
+struct Cursor<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    const fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let (taken, rest) = self.bytes.split_at_checked(len)?;
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn str(&mut self) -> Option<&'a str> {
+        let len = u16::from_le_bytes(self.take(2)?.try_into().ok()?);
+        std::str::from_utf8(self.take(usize::from(len))?).ok()
+    }
+}
+
+fn put_str(out: &mut Vec<u8>, value: &str) -> Result<()> {
+    let len = u16::try_from(value.len())
+        .with_context(|| format!("{value:?} is too long for the full index cache"))?;
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+/// Why fetching `specs.4.8.gz` failed
+#[derive(Debug, Error)]
+enum IndexFetchError {
+    /// The request or stream failed; the partial download is kept
+    #[error("Failed to download full index from {url} (rerun to resume): {source}")]
+    Network { url: String, source: reqwest::Error },
+
+    /// The partial download could not be read or written
+    #[error("Failed to store full index download: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The bytes received are not the index; the partial download is dropped
+    #[error("Downloaded full index is corrupt: {0}")]
+    Corrupt(String),
+}
+
+/// `specs.4.8.gz` as far as it has been downloaded, beside the validator
+/// (`ETag` or `Last-Modified`) of the response it came from
+struct PartialDownload {
+    path: PathBuf,
+    validator_path: PathBuf,
+}
+
+impl PartialDownload {
+    fn in_dir(cache_dir: &Path) -> Self {
+        Self {
+            path: cache_dir.join("specs.4.8.gz.partial"),
+            validator_path: cache_dir.join("specs.4.8.gz.partial.validator"),
+        }
+    }
+
+    /// Download the rest of the index and return it decompressed
+    async fn fetch(
+        &self,
+        url: &str,
+        progress: &(impl Fn(u64, Option<u64>) + Sync),
+    ) -> Result<Vec<u8>, IndexFetchError> {
+        let network = |source| IndexFetchError::Network {
+            url: url.to_string(),
+            source,
+        };
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(30))
+            .user_agent(format!("lode/{}", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(network)?;
+
+        let mut resume_from = self.resumable_length();
+        let response = loop {
+            let mut request = client.get(url);
+            if let Some((offset, validator)) = &resume_from {
+                crate::debug!("resuming full index download at byte {offset}");
+                request = request
+                    .header(RANGE, format!("bytes={offset}-"))
+                    .header(IF_RANGE, validator);
+            }
+            let response = crate::rate_limit::send(request).await.map_err(network)?;
+
+            // The partial file is already complete or longer than the index
+            if response.status() == StatusCode::RANGE_NOT_SATISFIABLE && resume_from.is_some() {
+                self.discard();
+                resume_from = None;
+                continue;
+            }
+            break response.error_for_status().map_err(network)?;
+        };
+
+        let offset = match resume_from {
+            Some((offset, _)) if response.status() == StatusCode::PARTIAL_CONTENT => offset,
+            _ => {
+                self.start_over(response.headers())?;
+                0
+            }
+        };
+        let total = response.content_length().map(|length| length + offset);
+
+        // Decompress what was already downloaded, then each chunk as it arrives
+        let mut decoder = GzDecoder::new(Vec::new());
+        if offset > 0 {
+            std::io::copy(&mut File::open(&self.path)?, &mut decoder).map_err(|e| corrupt(&e))?;
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .await?;
+
+        let mut received = offset;
+        progress(received, total);
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(network)?;
+            file.write_all(&chunk).await?;
+            decoder.write_all(&chunk).map_err(|e| corrupt(&e))?;
+            received += chunk.len() as u64;
+            progress(received, total);
+        }
+        file.flush().await?;
+
+        if let Some(total) = total
+            && received != total
+        {
+            return Err(IndexFetchError::Corrupt(format!(
+                "received {received} of {total} bytes"
+            )));
+        }
+        // Checks the gzip trailer's CRC-32 and length
+        decoder.finish().map_err(|e| corrupt(&e))
+    }
+
+    /// Bytes already downloaded, with the validator to resume them against
+    fn resumable_length(&self) -> Option<(u64, String)> {
+        let length = std::fs::metadata(&self.path).ok()?.len();
+        let validator = std::fs::read_to_string(&self.validator_path).ok()?;
+        (length > 0).then(|| (length, validator.trim().to_string()))
+    }
+
+    /// Truncate the partial file for a full response and remember its
+    /// validator, if it has one
+    fn start_over(&self, headers: &HeaderMap) -> std::io::Result<()> {
+        drop(File::create(&self.path)?);
+        headers
+            .get(ETAG)
+            .or_else(|| headers.get(LAST_MODIFIED))
+            .and_then(|value| value.to_str().ok())
+            .map_or_else(
+                || remove_if_exists(&self.validator_path),
+                |validator| std::fs::write(&self.validator_path, validator),
+            )
+    }
+
+    /// Drop the partial download so the next attempt starts from scratch
+    fn discard(&self) {
+        for path in [&self.path, &self.validator_path] {
+            if let Err(e) = remove_if_exists(path) {
+                crate::debug!("could not remove {}: {e}", path.display());
+            }
+        }
+    }
+}
+
+fn corrupt(e: &std::io::Error) -> IndexFetchError {
+    IndexFetchError::Corrupt(e.to_string())
+}
+
+fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
     }
 }
 
@@ -273,7 +689,7 @@ mod tests {
         );
 
         let index = FullIndex {
-            specs,
+            specs: Specs::Parsed(specs),
             total_count: 2,
         };
 
@@ -305,7 +721,7 @@ mod tests {
         );
 
         let index = FullIndex {
-            specs,
+            specs: Specs::Parsed(specs),
             total_count: 3,
         };
 
@@ -313,11 +729,143 @@ mod tests {
         assert_eq!(index.total_count(), 3); // 3 total specs
     }
 
-    // NOTE: Regression tests for extract_string() are difficult to write because
-    // alox_48::Value requires proper Marshal serialization. The function is tested
-    // indirectly through the integration with real Marshal data from RubyGems.org.
-    // Key behavior tested in production:
-    // - Direct strings (gem names)
-    // - Arrays with string first element (Gem::Version objects like ["1.0.0"])
-    // - Objects with field access (older marshal format)
+    /// `[["rack", Gem::Version "3.0.0", "ruby"], ["rake", "13.2.1", "java"]]`
+    fn marshal_specs() -> Vec<u8> {
+        [
+            &b"\x04\x08[\x07[\x08\"\x09rackU:\x11Gem::Version[\x06\"\x0a3.0.0\"\x09ruby"[..],
+            b"[\x08\"\x09rake\"\x0b13.2.1\"\x09java",
+        ]
+        .concat()
+    }
+
+    /// Serve `body` with `ETag: "v1"`, honoring `Range: bytes=N-`
+    async fn serve_ranges(body: Vec<u8>) -> String {
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while let Ok(count @ 1..) = stream.read(&mut buffer).await {
+                    request.extend_from_slice(buffer.get(..count).unwrap_or_default());
+                    if request.windows(4).any(|window| window == b"\r\n\r\n") {
+                        break;
+                    }
+                }
+                let request = String::from_utf8_lossy(&request).to_lowercase();
+                let offset = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("range: bytes="))
+                    .and_then(|range| range.trim_end_matches('-').parse::<usize>().ok())
+                    .filter(|_| request.contains("if-range: \"v1\""));
+                let (status, rest) = offset.map_or(("200 OK", &body[..]), |offset| {
+                    (
+                        "206 Partial Content",
+                        body.get(offset..).unwrap_or_default(),
+                    )
+                });
+                let head = format!(
+                    "HTTP/1.1 {status}\r\nETag: \"v1\"\r\nContent-Length: {}\r\n\
+                     Connection: close\r\n\r\n",
+                    rest.len()
+                );
+                stream.write_all(head.as_bytes()).await.ok();
+                stream.write_all(rest).await.ok();
+            }
+        });
+        format!("http://{address}/")
+    }
+
+    #[tokio::test]
+    async fn download_resumes_partial_index() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&marshal_specs()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+        let half = gzipped.len() / 2;
+
+        let temp = tempfile::tempdir().unwrap();
+        let partial = PartialDownload::in_dir(temp.path());
+        std::fs::write(&partial.path, gzipped.get(..half).unwrap()).unwrap();
+        std::fs::write(&partial.validator_path, "\"v1\"").unwrap();
+
+        let seen = std::sync::Mutex::new(Vec::new());
+        let url = serve_ranges(gzipped.clone()).await;
+        let index = FullIndex::download_and_parse(&url, temp.path(), |received, total| {
+            seen.lock().unwrap().push((received, total));
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(index.total_count(), 2);
+        let seen = seen.into_inner().unwrap();
+        let total = Some(gzipped.len() as u64);
+        assert_eq!(seen.first(), Some(&(half as u64, total)));
+        assert_eq!(seen.last(), Some(&(gzipped.len() as u64, total)));
+        assert!(!partial.path.exists());
+        assert!(!partial.validator_path.exists());
+    }
+
+    #[tokio::test]
+    async fn corrupt_download_is_discarded() {
+        let temp = tempfile::tempdir().unwrap();
+        let url = serve_ranges(b"not gzip at all".to_vec()).await;
+
+        let result = FullIndex::download_and_parse(&url, temp.path(), |_, _| {}).await;
+
+        assert!(result.is_err());
+        assert!(!PartialDownload::in_dir(temp.path()).path.exists());
+    }
+
+    #[test]
+    fn binary_cache_round_trips() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache_path = FullIndex::cache_path(temp.path());
+        std::fs::write(temp.path().join(LEGACY_CACHE_FILE), "{}").unwrap();
+        let parsed = FullIndex::parse(&marshal_specs()).unwrap();
+
+        parsed.save_to_cache(&cache_path).unwrap();
+        let cached = FullIndex::load_from_cache(&cache_path).unwrap();
+
+        assert!(matches!(cached.specs, Specs::Cached(_)));
+        assert_eq!((cached.gem_count(), cached.total_count()), (2, 2));
+        assert_eq!(cached.find_gem("rack"), parsed.find_gem("rack"));
+        assert_eq!(cached.find_gem("rake"), parsed.find_gem("rake"));
+        assert_eq!(cached.find_gem("rails"), None);
+        assert!(!temp.path().join(LEGACY_CACHE_FILE).exists());
+
+        // A damaged record fails the lookups that read it, not the load
+        let original = std::fs::read(&cache_path).unwrap();
+        let mut corrupted = original.clone();
+        if let Some(byte) = corrupted.last_mut() {
+            *byte ^= 0xff;
+        }
+        std::fs::write(&cache_path, corrupted).unwrap();
+        let cached = FullIndex::load_from_cache(&cache_path).unwrap();
+        assert_eq!(cached.find_gem("rake"), None);
+
+        // A damaged directory fails the load
+        let mut corrupted = original;
+        if let Some(byte) = corrupted.get_mut(CACHE_HEADER_LEN) {
+            *byte ^= 0xff;
+        }
+        std::fs::write(&cache_path, corrupted).unwrap();
+        assert!(FullIndex::load_from_cache(&cache_path).is_err());
+    }
+
+    #[test]
+    fn parse_reads_strings_and_gem_versions() {
+        let index = FullIndex::parse(&marshal_specs()).unwrap();
+
+        assert_eq!(index.total_count(), 2);
+        let first = |name| {
+            index
+                .find_gem(name)
+                .and_then(|specs| specs.into_iter().next())
+        };
+        assert_eq!(first("rack").unwrap().version, "3.0.0");
+        assert_eq!(first("rake").unwrap().platform, "java");
+        assert!(FullIndex::parse(b"\x04\x08[\x06i\x06").is_err());
+    }
 }
@@ -0,0 +1,260 @@
+//! Post-install notices about the installed bundle
+//!
+//! Funding requests come from the `funding_uri` metadata of installed
+//! gemspecs. Deprecated and unmaintained gems are detected from RubyGems.org
+//! version metadata, fetched concurrently and cached on disk for a day so
+//! repeated installs do not hit the network.
+
+use crate::gem_utils::compare_versions;
+use crate::gemspec::Gemspec;
+use crate::rubygems_client::{GemVersion, RubyGemsClient};
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Gems without a release for this many years are reported as unmaintained
+pub const STALE_AFTER_YEARS: i64 = 3;
+
+/// How long fetched release metadata stays fresh
+const CACHE_TTL_SECS: i64 = 24 * 60 * 60;
+
+/// Concurrent metadata requests
+const FETCH_CONCURRENCY: usize = 8;
+
+/// Something worth telling the user about an installed gem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GemNotice {
+    /// The gem asks for funding at `uri`
+    Funding { gem: String, uri: String },
+    /// The latest release describes itself as deprecated
+    Deprecated { gem: String, summary: String },
+    /// The latest release is older than [`STALE_AFTER_YEARS`]
+    Unmaintained { gem: String, last_release: String },
+}
+
+/// Release metadata cached per gem
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ReleaseInfo {
+    /// Unix time the metadata was fetched
+    fetched_at: i64,
+    /// Date of the newest stable release (`YYYY-MM-DD`)
+    latest_release: Option<String>,
+    /// Summary of the newest release when it announces a deprecation
+    deprecation: Option<String>,
+}
+
+/// Funding requests from installed gemspecs, one per gem, sorted by name
+#[must_use]
+pub fn funding_notices(specs: &[Gemspec]) -> Vec<GemNotice> {
+    let funding: BTreeMap<&str, &str> = specs
+        .iter()
+        .filter_map(|spec| {
+            let uri = spec.metadata.get("funding_uri")?;
+            (!uri.trim().is_empty()).then_some((spec.name.as_str(), uri.as_str()))
+        })
+        .collect();
+
+    funding
+        .into_iter()
+        .map(|(gem, uri)| GemNotice::Funding {
+            gem: gem.to_string(),
+            uri: uri.to_string(),
+        })
+        .collect()
+}
+
+/// Deprecated and unmaintained gems among `gem_names`
+///
+/// Metadata older than a day is refetched; gems whose metadata cannot be
+/// fetched are silently skipped. The cache lives at
+/// `<cache_dir>/gem_notices.json`.
+pub async fn maintenance_notices(
+    client: &RubyGemsClient,
+    gem_names: &[String],
+    cache_dir: &Path,
+    now: DateTime<Utc>,
+) -> Vec<GemNotice> {
+    let cache_path = cache_path(cache_dir);
+    let mut cache = load_cache(&cache_path);
+
+    let stale: Vec<&String> = gem_names
+        .iter()
+        .filter(|name| {
+            cache
+                .get(name.as_str())
+                .is_none_or(|info| now.timestamp() - info.fetched_at > CACHE_TTL_SECS)
+        })
+        .collect();
+
+    if !stale.is_empty() {
+        let fetched: Vec<(String, ReleaseInfo)> = stream::iter(stale)
+            .map(|name| async move {
+                let versions = client.fetch_versions(name).await.ok()?;
+                Some((name.clone(), release_info(&versions, now)))
+            })
+            .buffer_unordered(FETCH_CONCURRENCY)
+            .filter_map(|entry| async move { entry })
+            .collect()
+            .await;
+        cache.extend(fetched);
+        save_cache(&cache_path, &cache);
+    }
+
+    gem_names
+        .iter()
+        .filter_map(|name| notice_for(name, cache.get(name)?, now))
+        .collect()
+}
+
+/// Summarize the newest stable release in `versions`
+fn release_info(versions: &[GemVersion], now: DateTime<Utc>) -> ReleaseInfo {
+    let latest = versions
+        .iter()
+        .filter(|v| !v.number.chars().any(|c| c.is_ascii_alphabetic()))
+        .max_by(|a, b| compare_versions(&a.number, &b.number));
+
+    ReleaseInfo {
+        fetched_at: now.timestamp(),
+        latest_release: latest
+            .and_then(|v| v.created_at.as_deref())
+            .and_then(|created| created.get(..10))
+            .map(str::to_string),
+        deprecation: latest
+            .and_then(|v| v.summary.as_deref())
+            .filter(|summary| summary.to_lowercase().contains("deprecated"))
+            .map(|summary| summary.trim().to_string()),
+    }
+}
+
+/// Deprecation wins over staleness; fresh, active gems get no notice
+fn notice_for(gem: &str, info: &ReleaseInfo, now: DateTime<Utc>) -> Option<GemNotice> {
+    if let Some(summary) = &info.deprecation {
+        return Some(GemNotice::Deprecated {
+            gem: gem.to_string(),
+            summary: summary.clone(),
+        });
+    }
+
+    let last_release = info.latest_release.as_deref()?;
+    let released = chrono::NaiveDate::parse_from_str(last_release, "%Y-%m-%d").ok()?;
+    let age_days = (now.date_naive() - released).num_days();
+    (age_days > STALE_AFTER_YEARS * 365).then(|| GemNotice::Unmaintained {
+        gem: gem.to_string(),
+        last_release: last_release.to_string(),
+    })
+}
+
+fn cache_path(cache_dir: &Path) -> PathBuf {
+    cache_dir.join("gem_notices.json")
+}
+
+fn load_cache(path: &Path) -> BTreeMap<String, ReleaseInfo> {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+/// Best effort: notices are advisory, so a read-only cache is not an error
+fn save_cache(path: &Path, cache: &BTreeMap<String, ReleaseInfo>) {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).ok();
+    }
+    if let Ok(json) = serde_json::to_vec(cache) {
+        fs::write(path, json).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(number: &str, created_at: &str, summary: &str) -> GemVersion {
+        GemVersion {
+            number: number.to_string(),
+            platform: "ruby".to_string(),
+            ruby_version: None,
+            rubygems_version: None,
+            created_at: Some(created_at.to_string()),
+            downloads_count: None,
+            summary: Some(summary.to_string()),
+            dependencies: crate::rubygems_client::Dependencies::default(),
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        "2026-06-01T00:00:00Z".parse().expect("timestamp")
+    }
+
+    #[test]
+    fn funding_from_gemspec_metadata() {
+        let mut rack = Gemspec {
+            name: "rack".to_string(),
+            ..Gemspec::default()
+        };
+        rack.metadata.insert(
+            "funding_uri".to_string(),
+            "https://github.com/sponsors/rack".to_string(),
+        );
+        let rake = Gemspec {
+            name: "rake".to_string(),
+            ..Gemspec::default()
+        };
+
+        assert_eq!(
+            funding_notices(&[rake, rack]),
+            vec![GemNotice::Funding {
+                gem: "rack".to_string(),
+                uri: "https://github.com/sponsors/rack".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn deprecated_and_unmaintained_gems() {
+        let deprecated = release_info(
+            &[
+                version("1.0.0", "2025-01-01T00:00:00.000Z", "A client"),
+                version(
+                    "1.1.0",
+                    "2025-06-01T00:00:00.000Z",
+                    "DEPRECATED: use faraday",
+                ),
+                version("2.0.0.beta1", "2026-01-01T00:00:00.000Z", "A client"),
+            ],
+            now(),
+        );
+        assert_eq!(
+            notice_for("old_client", &deprecated, now()),
+            Some(GemNotice::Deprecated {
+                gem: "old_client".to_string(),
+                summary: "DEPRECATED: use faraday".to_string(),
+            })
+        );
+
+        let stale = release_info(
+            &[version("0.9.1", "2019-03-04T10:00:00.000Z", "Parser")],
+            now(),
+        );
+        assert_eq!(
+            notice_for("parser", &stale, now()),
+            Some(GemNotice::Unmaintained {
+                gem: "parser".to_string(),
+                last_release: "2019-03-04".to_string(),
+            })
+        );
+
+        let active = release_info(
+            &[version(
+                "3.1.0",
+                "2026-05-01T00:00:00.000Z",
+                "Web server interface",
+            )],
+            now(),
+        );
+        assert_eq!(notice_for("rack", &active, now()), None);
+    }
+}
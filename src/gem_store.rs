@@ -11,10 +11,14 @@
 #![allow(clippy::flat_map_option)]
 #![allow(clippy::needless_continue)]
 
+use crate::gemspec::{DependencyKind, Gemspec, GemspecDependency};
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::UNIX_EPOCH;
 
 #[derive(Debug, Clone)]
 pub struct InstalledGem {
@@ -28,11 +32,272 @@ pub struct InstalledGem {
     pub path: PathBuf,
 }
 
+impl InstalledGem {
+    /// Gem directory this gem was found in (the `GEM_PATH` entry), the
+    /// parent of its `gems/` directory
+    #[must_use]
+    pub fn gem_home(&self) -> Option<&Path> {
+        self.path.parent()?.parent()
+    }
+
+    /// Directory name: `name-version` plus `-platform` when not `ruby`
+    #[must_use]
+    pub fn full_name(&self) -> String {
+        if self.platform == "ruby" {
+            format!("{}-{}", self.name, self.version)
+        } else {
+            format!("{}-{}-{}", self.name, self.version, self.platform)
+        }
+    }
+}
+
+/// Criteria for [`GemStore::query`] and [`GemPath::query`]
+///
+/// Every criterion left unset matches all gems.
+///
+/// ```
+/// use lode::gem_store::GemQuery;
+///
+/// let query = GemQuery::new()
+///     .with_name("nokogiri")
+///     .with_platform("x86_64-linux")
+///     .latest_only();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct GemQuery {
+    name: Option<String>,
+    pattern: Option<String>,
+    platform: Option<String>,
+    ruby_abi: Option<String>,
+    latest: bool,
+    shadowed: bool,
+}
+
+impl GemQuery {
+    /// Query matching every installed gem
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only gems with exactly this name (case-insensitive)
+    #[must_use]
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into().to_lowercase());
+        self
+    }
+
+    /// Only gems whose name contains `pattern` (case-insensitive)
+    #[must_use]
+    pub fn with_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.pattern = Some(pattern.into().to_lowercase());
+        self
+    }
+
+    /// Only gems that run on `platform`: pure Ruby gems and builds for it
+    #[must_use]
+    pub fn with_platform(mut self, platform: impl Into<String>) -> Self {
+        self.platform = Some(platform.into());
+        self
+    }
+
+    /// Only gems from directories for this Ruby ABI (`3.3.0`)
+    ///
+    /// Directories whose ABI can't be told from their path, like a
+    /// `vendor/gems` checkout, always match.
+    #[must_use]
+    pub fn with_ruby_abi(mut self, abi: impl Into<String>) -> Self {
+        self.ruby_abi = Some(abi.into());
+        self
+    }
+
+    /// Only the newest installed version of each gem
+    #[must_use]
+    pub const fn latest_only(mut self) -> Self {
+        self.latest = true;
+        self
+    }
+
+    /// Also return copies hidden by the same version in an earlier
+    /// [`GemPath`] directory
+    #[must_use]
+    pub const fn with_shadowed(mut self) -> Self {
+        self.shadowed = true;
+        self
+    }
+
+    /// Whether gems from `store` can match
+    fn accepts_store(&self, store: &GemStore) -> bool {
+        match (&self.ruby_abi, store.ruby_abi()) {
+            (Some(wanted), Some(abi)) => wanted == abi,
+            _ => true,
+        }
+    }
+
+    /// Whether `gem` matches the name, pattern and platform criteria
+    fn matches(&self, gem: &InstalledGem) -> bool {
+        let name = gem.name.to_lowercase();
+        self.name.as_ref().is_none_or(|wanted| &name == wanted)
+            && self
+                .pattern
+                .as_ref()
+                .is_none_or(|pattern| name.contains(pattern.as_str()))
+            && self.platform.as_ref().is_none_or(|platform| {
+                crate::platform::platform_matches(&Some(gem.platform.clone()), platform)
+            })
+    }
+
+    /// Filter, sort and (with [`Self::latest_only`]) reduce `gems`
+    fn select(&self, mut gems: Vec<InstalledGem>) -> Vec<InstalledGem> {
+        gems.retain(|gem| self.matches(gem));
+        sort_gems(&mut gems);
+        if self.latest {
+            latest_versions(gems)
+        } else {
+            gems
+        }
+    }
+}
+
+/// Several gem directories searched in precedence order, like `GEM_PATH`
+///
+/// When the same gem version is installed in more than one directory, the
+/// copy in the earliest directory wins.
+#[derive(Debug, Default)]
+pub struct GemPath {
+    stores: Vec<GemStore>,
+}
+
+impl GemPath {
+    /// Search `stores`, earliest first
+    #[must_use]
+    pub const fn new(stores: Vec<GemStore>) -> Self {
+        Self { stores }
+    }
+
+    /// Every `Gem.path` directory (`GEM_PATH`, then the defaults and
+    /// `GEM_HOME`) for `ruby_version`
+    #[must_use]
+    pub fn from_env(ruby_version: &str) -> Self {
+        Self::new(
+            crate::ruby::gem_path(ruby_version)
+                .into_iter()
+                .map(|dir| GemStore::with_path(dir.join("gems")))
+                .collect(),
+        )
+    }
+
+    /// The bundle's `gems_dir` ahead of every `Gem.path` directory, for
+    /// commands that work inside a project
+    #[must_use]
+    pub fn for_bundle(gems_dir: PathBuf, ruby_version: &str) -> Self {
+        let mut path = Self::from_env(ruby_version);
+        path.stores.insert(0, GemStore::with_path(gems_dir));
+        path
+    }
+
+    /// Directories searched, in precedence order
+    #[must_use]
+    pub fn stores(&self) -> &[GemStore] {
+        &self.stores
+    }
+
+    /// Installed gems matching `query` across every directory
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a gem directory cannot be read.
+    pub fn query(&self, query: &GemQuery) -> Result<Vec<InstalledGem>> {
+        let mut seen = std::collections::HashSet::new();
+        let mut gems = Vec::new();
+        for store in self
+            .stores
+            .iter()
+            .filter(|store| query.accepts_store(store))
+        {
+            for gem in store.list_gems()? {
+                if seen.insert(gem.full_name()) || query.shadowed {
+                    gems.push(gem);
+                }
+            }
+        }
+        Ok(query.select(gems))
+    }
+}
+
+/// Sort by name, then real `Gem::Version` order, then platform
+fn sort_gems(gems: &mut [InstalledGem]) {
+    gems.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then_with(|| GemStore::compare_versions(&a.version, &b.version))
+            .then_with(|| a.platform.cmp(&b.platform))
+    });
+}
+
+/// Newest version of each gem in a list sorted by [`GemStore::list_gems`]
+///
+/// Every platform build of the newest version is kept.
+#[must_use]
+pub fn latest_versions(gems: Vec<InstalledGem>) -> Vec<InstalledGem> {
+    let mut latest: Vec<InstalledGem> = Vec::new();
+    for gem in gems {
+        if let Some(last) = latest.last()
+            && last.name == gem.name
+            && GemStore::compare_versions(&last.version, &gem.version).is_lt()
+        {
+            latest.retain(|kept| kept.name != gem.name);
+        }
+        latest.push(gem);
+    }
+    latest
+}
+
+/// An installed gem that depends on another gem
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependent {
+    /// Name of the depending gem
+    pub name: String,
+    /// Version of the depending gem
+    pub version: String,
+    /// Requirement placed on the dependency (e.g. `~> 2.0`)
+    pub requirement: String,
+    /// Whether this is a development dependency
+    pub development: bool,
+}
+
+/// Reverse dependency index: dependency name -> installed gems that need it
+///
+/// Cached on disk and keyed by the gem directory's modification time, which
+/// changes whenever a gem is installed or removed.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ReverseDependencyIndex {
+    /// Gem directory mtime (nanoseconds since epoch) when the index was built
+    fingerprint: u128,
+    dependents: BTreeMap<String, Vec<Dependent>>,
+}
+
+impl ReverseDependencyIndex {
+    /// Installed gems that declare a dependency on `name`
+    #[must_use]
+    pub fn dependents_of(&self, name: &str) -> &[Dependent] {
+        self.dependents.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// Installed gems with a runtime dependency on `name`
+    pub fn runtime_dependents_of(&self, name: &str) -> impl Iterator<Item = &Dependent> {
+        self.dependents_of(name).iter().filter(|d| !d.development)
+    }
+}
+
 /// Manages system gem directory operations
 #[derive(Debug)]
 pub struct GemStore {
     /// Path to system gems directory
     gem_dir: PathBuf,
+    /// Directory for the cached reverse dependency index (defaults to the
+    /// user cache directory)
+    index_dir: Option<PathBuf>,
 }
 
 impl GemStore {
@@ -43,13 +308,26 @@ impl GemStore {
     /// Returns an error if system gem directory cannot be detected.
     pub fn new() -> Result<Self> {
         let gem_dir = Self::find_gem_dir()?;
-        Ok(Self { gem_dir })
+        Ok(Self {
+            gem_dir,
+            index_dir: None,
+        })
     }
 
     /// Create a `GemStore` with explicit gem directory
     #[must_use]
     pub const fn with_path(path: PathBuf) -> Self {
-        Self { gem_dir: path }
+        Self {
+            gem_dir: path,
+            index_dir: None,
+        }
+    }
+
+    /// Store the reverse dependency index in a specific directory
+    #[must_use]
+    pub fn with_index_dir(mut self, dir: PathBuf) -> Self {
+        self.index_dir = Some(dir);
+        self
     }
 
     /// Get the system gem directory path
@@ -135,34 +413,32 @@ impl GemStore {
             }
         }
 
-        // Sort by name, then version
-        gems.sort_by(|a, b| {
-            match a.name.cmp(&b.name) {
-                std::cmp::Ordering::Equal => {
-                    // Simple semver-like comparison: try to parse as X.Y.Z
-                    Self::compare_versions(&a.version, &b.version)
-                }
-                other => other,
-            }
-        });
-
+        sort_gems(&mut gems);
         Ok(gems)
     }
 
+    /// Installed gems matching `query`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gem directory cannot be read.
+    pub fn query(&self, query: &GemQuery) -> Result<Vec<InstalledGem>> {
+        if !query.accepts_store(self) {
+            return Ok(Vec::new());
+        }
+        Ok(query.select(self.list_gems()?))
+    }
+
     /// Find gems matching a pattern
     ///
     /// # Errors
     ///
     /// Returns an error if gem listing fails.
     pub fn find_gems(&self, pattern: Option<&str>) -> Result<Vec<InstalledGem>> {
-        let mut gems = self.list_gems()?;
-
-        if let Some(pattern) = pattern {
-            let pattern = pattern.to_lowercase();
-            gems.retain(|gem| gem.name.to_lowercase().contains(&pattern));
-        }
-
-        Ok(gems)
+        let query = pattern.map_or_else(GemQuery::new, |pattern| {
+            GemQuery::new().with_pattern(pattern)
+        });
+        self.query(&query)
     }
 
     /// Find a specific gem by name (returns all versions)
@@ -171,14 +447,7 @@ impl GemStore {
     ///
     /// Returns an error if gem listing fails.
     pub fn find_gem_by_name(&self, name: &str) -> Result<Vec<InstalledGem>> {
-        let name_lower = name.to_lowercase();
-        let all_gems = self.list_gems()?;
-        let matching: Vec<_> = all_gems
-            .into_iter()
-            .filter(|g| g.name.to_lowercase() == name_lower)
-            .collect();
-
-        Ok(matching)
+        self.query(&GemQuery::new().with_name(name))
     }
 
     /// Get the latest version of a gem
@@ -191,6 +460,123 @@ impl GemStore {
         Ok(versions.pop()) // Already sorted, last is latest
     }
 
+    /// Ruby ABI of this directory, when its path names one
+    ///
+    /// `RubyGems` lays gems out as `<prefix>/<abi>/gems`, for example
+    /// `~/.gem/ruby/3.3.0/gems`.
+    #[must_use]
+    pub fn ruby_abi(&self) -> Option<&str> {
+        let abi = self.gem_dir.parent()?.file_name()?.to_str()?;
+        let is_abi = abi.split('.').count() == 3
+            && abi
+                .split('.')
+                .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()));
+        is_abi.then_some(abi)
+    }
+
+    /// Declared dependencies of an installed gem
+    ///
+    /// Reads the YAML spec Lode writes on install, falling back to the
+    /// Ruby-format gemspec that `RubyGems` writes to `specifications/`.
+    #[must_use]
+    pub fn gem_dependencies(gem: &InstalledGem) -> Vec<GemspecDependency> {
+        if let Some(spec) = Gemspec::load_for_gem_dir(&gem.path) {
+            return spec.dependencies;
+        }
+
+        Gemspec::spec_path_for_gem_dir(&gem.path)
+            .and_then(|path| fs::read(path).ok())
+            .map(|content| {
+                String::from_utf8_lossy(&content)
+                    .lines()
+                    .filter_map(parse_ruby_dependency)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Reverse dependency index for this store
+    ///
+    /// Loads the cached index when it is still current, otherwise rebuilds
+    /// it from the installed gemspecs and saves it for next time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gem directory cannot be listed.
+    pub fn reverse_dependencies(&self) -> Result<ReverseDependencyIndex> {
+        let fingerprint = self.fingerprint();
+        let index_path = self.index_path();
+
+        if let Some(path) = &index_path
+            && let Ok(content) = fs::read_to_string(path)
+            && let Ok(index) = serde_json::from_str::<ReverseDependencyIndex>(&content)
+            && index.fingerprint == fingerprint
+        {
+            return Ok(index);
+        }
+
+        let mut index = ReverseDependencyIndex {
+            fingerprint,
+            dependents: BTreeMap::new(),
+        };
+        for gem in self.list_gems()? {
+            for dep in Self::gem_dependencies(&gem) {
+                index
+                    .dependents
+                    .entry(dep.name.clone())
+                    .or_default()
+                    .push(Dependent {
+                        name: gem.name.clone(),
+                        version: gem.version.clone(),
+                        requirement: dep.requirement_string(),
+                        development: dep.kind == DependencyKind::Development,
+                    });
+            }
+        }
+
+        // Caching is best-effort; an unwritable cache just means a rebuild next time
+        if let Some(path) = &index_path
+            && let Some(parent) = path.parent()
+            && fs::create_dir_all(parent).is_ok()
+            && let Ok(json) = serde_json::to_string(&index)
+        {
+            fs::write(path, json).ok();
+        }
+
+        Ok(index)
+    }
+
+    /// Drop the cached reverse dependency index
+    ///
+    /// Call after installing or removing gems so the next lookup rebuilds.
+    pub fn invalidate_reverse_dependencies(&self) {
+        if let Some(path) = self.index_path() {
+            fs::remove_file(path).ok();
+        }
+    }
+
+    /// Cache file for this store's reverse dependency index
+    fn index_path(&self) -> Option<PathBuf> {
+        use sha2::{Digest, Sha256};
+
+        let dir = self
+            .index_dir
+            .clone()
+            .or_else(|| dirs::cache_dir().map(|d| d.join("lode").join("reverse_deps")))?;
+        let digest = Sha256::digest(self.gem_dir.to_string_lossy().as_bytes());
+        let key: String = format!("{digest:x}").chars().take(16).collect();
+        Some(dir.join(format!("{key}.json")))
+    }
+
+    /// Gem directory modification time, used to detect installs/uninstalls
+    fn fingerprint(&self) -> u128 {
+        fs::metadata(&self.gem_dir)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos())
+    }
+
     /// Parse gem directory name into components
     /// Examples: "rake-13.0.6", "nokogiri-1.16.0-x86_64-linux"
     fn parse_gem_dir(dir_name: &str, path: PathBuf) -> Option<InstalledGem> {
@@ -243,28 +629,46 @@ impl GemStore {
         }
     }
 
-    /// Simple version comparison (not full semver, just for sorting)
+    /// Version comparison following `Gem::Version` ordering
     fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
-        let a_parts: Vec<u32> = a
-            .split('.')
-            .take(3)
-            .flat_map(|s| s.parse::<u32>().ok())
-            .collect();
-        let b_parts: Vec<u32> = b
-            .split('.')
-            .take(3)
-            .flat_map(|s| s.parse::<u32>().ok())
-            .collect();
-
-        for (a_part, b_part) in a_parts.iter().zip(b_parts.iter()) {
-            match a_part.cmp(b_part) {
-                std::cmp::Ordering::Equal => continue,
-                other => return other,
-            }
-        }
+        crate::gem_utils::compare_versions(a, b)
+    }
+}
 
-        a_parts.len().cmp(&b_parts.len())
+/// Parse a dependency line from a Ruby-format gemspec
+///
+/// Handles both `s.add_runtime_dependency(%q<rack>.freeze, [">= 2"])` (as
+/// written by `RubyGems`) and `spec.add_dependency "rack", "~> 2.0"`.
+fn parse_ruby_dependency(line: &str) -> Option<GemspecDependency> {
+    let trimmed = line.trim();
+    let (_, call) = trimmed.split_once(".add_")?;
+    let (method, args) = call.split_once(['(', ' '])?;
+    let kind = match method {
+        "dependency" | "runtime_dependency" => DependencyKind::Runtime,
+        "development_dependency" => DependencyKind::Development,
+        _ => return None,
+    };
+
+    let mut strings = Vec::new();
+    let mut rest = args;
+    while let Some(start) = rest.find(['"', '\'', '<']) {
+        let close = match rest[start..].chars().next()? {
+            '<' => '>',
+            quote => quote,
+        };
+        let after = &rest[start + 1..];
+        let end = after.find(close)?;
+        strings.push(after[..end].to_string());
+        rest = &after[end + 1..];
     }
+
+    let mut strings = strings.into_iter();
+    let name = strings.next().filter(|n| !n.is_empty())?;
+    Some(GemspecDependency {
+        name,
+        requirements: strings.collect(),
+        kind,
+    })
 }
 
 #[cfg(test)]
@@ -323,4 +727,123 @@ mod tests {
         assert_eq!(version, "1.16.0");
         assert_eq!(platform, "x86_64-linux");
     }
+
+    #[test]
+    fn parse_ruby_dependency_lines() {
+        let dep = parse_ruby_dependency(
+            "    s.add_runtime_dependency(%q<rack>.freeze, [\">= 2.2\", \"< 4\"])",
+        )
+        .unwrap();
+        assert_eq!(dep.name, "rack");
+        assert_eq!(dep.requirements, vec![">= 2.2", "< 4"]);
+        assert_eq!(dep.kind, DependencyKind::Runtime);
+
+        let dep =
+            parse_ruby_dependency("  spec.add_development_dependency 'rspec', '~> 3.0'").unwrap();
+        assert_eq!(dep.name, "rspec");
+        assert_eq!(dep.kind, DependencyKind::Development);
+
+        assert!(parse_ruby_dependency("  s.name = \"rack\"").is_none());
+    }
+
+    #[test]
+    fn reverse_dependencies_index() {
+        let root = tempfile::TempDir::new().unwrap();
+        let gems = root.path().join("gems");
+        let specs = root.path().join("specifications");
+        fs::create_dir_all(&specs).unwrap();
+        for full_name in ["rack-3.0.8", "rack-test-2.1.0", "sinatra-4.0.0"] {
+            fs::create_dir_all(gems.join(full_name)).unwrap();
+        }
+        fs::write(
+            specs.join("sinatra-4.0.0.gemspec"),
+            "s.add_runtime_dependency(%q<rack>.freeze, [\">= 3.0\"])\n",
+        )
+        .unwrap();
+        fs::write(
+            specs.join("rack-test-2.1.0.gemspec"),
+            "name: rack-test\nversion: 2.1.0\ndependencies:\n- name: rack\n  requirement:\n    requirements:\n    - - \">=\"\n      - version: '1.3'\n  type: :runtime\n",
+        )
+        .unwrap();
+
+        let index_dir = root.path().join("index");
+        let store = GemStore::with_path(gems).with_index_dir(index_dir.clone());
+        let index = store.reverse_dependencies().unwrap();
+
+        let names: Vec<_> = index
+            .dependents_of("rack")
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["rack-test", "sinatra"]);
+        assert!(index.dependents_of("sinatra").is_empty());
+        assert_eq!(fs::read_dir(&index_dir).unwrap().count(), 1);
+
+        store.invalidate_reverse_dependencies();
+        assert_eq!(fs::read_dir(&index_dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn query_orders_versions_and_merges_paths() {
+        let root = tempfile::TempDir::new().unwrap();
+        let user = root.path().join("user/3.3.0/gems");
+        let system = root.path().join("system/3.3.0/gems");
+        let old_abi = root.path().join("old/3.2.0/gems");
+        for full_name in ["rack-3.0.9", "rack-3.0.10", "nokogiri-1.16.0-x86_64-linux"] {
+            fs::create_dir_all(user.join(full_name)).unwrap();
+        }
+        for full_name in ["rack-3.0.10", "nokogiri-1.16.0-arm64-darwin", "json-2.7.1"] {
+            fs::create_dir_all(system.join(full_name)).unwrap();
+        }
+        fs::create_dir_all(old_abi.join("json-2.9.0")).unwrap();
+
+        let path = GemPath::new(vec![
+            GemStore::with_path(user.clone()),
+            GemStore::with_path(system),
+            GemStore::with_path(old_abi),
+        ]);
+        let names = |gems: Vec<InstalledGem>| -> Vec<String> {
+            gems.iter().map(InstalledGem::full_name).collect()
+        };
+
+        let rack = path.query(&GemQuery::new().with_name("Rack")).unwrap();
+        assert_eq!(names(rack.clone()), vec!["rack-3.0.9", "rack-3.0.10"]);
+        assert_eq!(rack[1].path, user.join("rack-3.0.10"));
+
+        let copies = path
+            .query(&GemQuery::new().with_name("rack").with_shadowed())
+            .unwrap();
+        assert_eq!(copies.len(), 3);
+        assert_eq!(
+            copies[2].gem_home(),
+            Some(root.path().join("system/3.3.0").as_path())
+        );
+
+        let linux = path
+            .query(
+                &GemQuery::new()
+                    .with_name("nokogiri")
+                    .with_platform("x86_64-linux"),
+            )
+            .unwrap();
+        assert_eq!(names(linux), vec!["nokogiri-1.16.0-x86_64-linux"]);
+
+        let latest = path
+            .query(&GemQuery::new().with_ruby_abi("3.3.0").latest_only())
+            .unwrap();
+        assert_eq!(
+            names(latest),
+            vec![
+                "json-2.7.1",
+                "nokogiri-1.16.0-arm64-darwin",
+                "nokogiri-1.16.0-x86_64-linux",
+                "rack-3.0.10",
+            ]
+        );
+        assert_eq!(GemStore::with_path(user).ruby_abi(), Some("3.3.0"));
+        assert_eq!(
+            GemStore::with_path(root.path().join("vendor/gems")).ruby_abi(),
+            None
+        );
+    }
 }
@@ -38,6 +38,190 @@ pub fn parse_gem_name(dir_name: &str) -> Option<(&str, &str)> {
     })
 }
 
+/// A single version segment: numeric (`13`) or textual (`rc1`, `pre`)
+#[derive(Debug, PartialEq, Eq)]
+enum Segment<'a> {
+    Number(u64),
+    Text(&'a str),
+}
+
+/// Split a version into `RubyGems` segments (`1.0.0.rc1` -> 1, 0, 0, "rc", 1)
+fn segments(version: &str) -> Vec<Segment<'_>> {
+    let mut result = Vec::new();
+    for part in version.split(['.', '-']) {
+        let mut rest = part;
+        while !rest.is_empty() {
+            let is_digit = rest.starts_with(|c: char| c.is_ascii_digit());
+            let end = rest
+                .find(|c: char| c.is_ascii_digit() != is_digit)
+                .unwrap_or(rest.len());
+            let (chunk, tail) = rest.split_at(end);
+            result.push(if is_digit {
+                Segment::Number(chunk.parse().unwrap_or(u64::MAX))
+            } else {
+                Segment::Text(chunk)
+            });
+            rest = tail;
+        }
+    }
+    result
+}
+
+/// Compare two gem versions using `RubyGems` ordering
+///
+/// Numeric segments compare numerically, trailing zeros are ignored, and
+/// textual (prerelease) segments sort before numeric ones, so
+/// `1.0.0.rc1 < 1.0.0 < 1.0.1`.
+///
+/// # Examples
+///
+/// ```
+/// use lode::gem_utils::compare_versions;
+/// use std::cmp::Ordering;
+///
+/// assert_eq!(compare_versions("1.10.0", "1.9.9"), Ordering::Greater);
+/// assert_eq!(compare_versions("2.0", "2.0.0"), Ordering::Equal);
+/// assert_eq!(compare_versions("1.0.0.rc1", "1.0.0"), Ordering::Less);
+/// ```
+#[must_use]
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+
+    let a_segments = segments(a);
+    let b_segments = segments(b);
+    let len = a_segments.len().max(b_segments.len());
+    let zero = Segment::Number(0);
+
+    for i in 0..len {
+        let left = a_segments.get(i).unwrap_or(&zero);
+        let right = b_segments.get(i).unwrap_or(&zero);
+        let ordering = match (left, right) {
+            (Segment::Number(l), Segment::Number(r)) => l.cmp(r),
+            (Segment::Text(l), Segment::Text(r)) => l.cmp(r),
+            (Segment::Text(_), Segment::Number(_)) => Ordering::Less,
+            (Segment::Number(_), Segment::Text(_)) => Ordering::Greater,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    Ordering::Equal
+}
+
+/// Check whether a version satisfies a `RubyGems` requirement string
+///
+/// Supports `=`, `!=`, `>`, `<`, `>=`, `<=`, and `~>` operators, bare
+/// versions (treated as `=`), and comma-separated compound requirements.
+/// An empty requirement matches everything.
+///
+/// # Examples
+///
+/// ```
+/// use lode::gem_utils::requirement_satisfied;
+///
+/// assert!(requirement_satisfied("1.2.5", "~> 1.2"));
+/// assert!(!requirement_satisfied("2.0.0", "~> 1.2"));
+/// assert!(requirement_satisfied("1.5", ">= 1.0, < 2"));
+/// ```
+#[must_use]
+pub fn requirement_satisfied(version: &str, requirement: &str) -> bool {
+    use std::cmp::Ordering;
+
+    requirement
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .all(|part| {
+            let (op, target) = split_requirement(part);
+            let ordering = compare_versions(version, target);
+            match op {
+                "!=" => ordering != Ordering::Equal,
+                ">" => ordering == Ordering::Greater,
+                "<" => ordering == Ordering::Less,
+                ">=" => ordering != Ordering::Less,
+                "<=" => ordering != Ordering::Greater,
+                "~>" => {
+                    ordering != Ordering::Less
+                        && compare_versions(version, &pessimistic_upper_bound(target))
+                            == Ordering::Less
+                }
+                _ => ordering == Ordering::Equal,
+            }
+        })
+}
+
+/// Check whether a string is a well-formed `RubyGems` requirement
+///
+/// Accepts the same syntax as [`requirement_satisfied`]: bare versions and
+/// operator-prefixed versions, optionally comma-separated.
+///
+/// # Examples
+///
+/// ```
+/// use lode::gem_utils::is_valid_requirement;
+///
+/// assert!(is_valid_requirement("~> 1.2"));
+/// assert!(is_valid_requirement(">= 1.0, < 2"));
+/// assert!(!is_valid_requirement("~>"));
+/// assert!(!is_valid_requirement("latest"));
+/// ```
+#[must_use]
+pub fn is_valid_requirement(requirement: &str) -> bool {
+    let mut parts = requirement.split(',').map(str::trim).peekable();
+    parts.peek().is_some()
+        && parts.all(|part| {
+            let (_, target) = split_requirement(part);
+            target.starts_with(|c: char| c.is_ascii_digit())
+                && target
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+        })
+}
+
+/// Whether a requirement explicitly names a prerelease version
+///
+/// `RubyGems` only matches prereleases when the requirement itself does,
+/// so `-v '>= 2.0.0.rc1'` considers release candidates while `-v '~> 2.0'`
+/// does not.
+#[must_use]
+pub fn requirement_allows_prerelease(requirement: &str) -> bool {
+    requirement
+        .split(',')
+        .map(|part| split_requirement(part.trim()).1)
+        .any(|target| target.chars().any(|c| c.is_ascii_alphabetic()))
+}
+
+/// Split `">= 1.0"` into (`">="`, `"1.0"`); bare versions get `"="`
+fn split_requirement(requirement: &str) -> (&str, &str) {
+    for op in ["~>", ">=", "<=", "!=", "=", ">", "<"] {
+        if let Some(rest) = requirement.strip_prefix(op) {
+            return (op, rest.trim());
+        }
+    }
+    ("=", requirement.trim())
+}
+
+/// Upper bound for `~> target`: drop the last release segment and bump the
+/// one before it (`~> 1.2.3` -> `1.3`, `~> 1.2` -> `2`)
+fn pessimistic_upper_bound(target: &str) -> String {
+    let mut release: Vec<u64> = target
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect();
+    if release.len() > 1 {
+        release.pop();
+    }
+    if let Some(last) = release.last_mut() {
+        *last += 1;
+    }
+    release
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -76,4 +260,54 @@ mod tests {
         assert_eq!(parse_gem_name("-1.0.0"), None);
         assert_eq!(parse_gem_name("just-a-name"), None);
     }
+
+    #[test]
+    fn test_compare_versions() {
+        use std::cmp::Ordering;
+
+        assert_eq!(compare_versions("1.0.0", "1.0.1"), Ordering::Less);
+        assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("3.0", "3.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("2.0.0.beta1", "2.0.0"), Ordering::Less);
+        assert_eq!(
+            compare_versions("2.0.0.beta2", "2.0.0.beta10"),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_versions("2.0.0.rc1", "2.0.0.beta1"),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_requirement_satisfied() {
+        assert!(requirement_satisfied("1.0.0", ""));
+        assert!(requirement_satisfied("1.0.0", "1.0.0"));
+        assert!(requirement_satisfied("1.0.0", "= 1.0"));
+        assert!(!requirement_satisfied("1.0.1", "= 1.0.0"));
+        assert!(requirement_satisfied("1.2.9", "~> 1.2.3"));
+        assert!(!requirement_satisfied("1.3.0", "~> 1.2.3"));
+        assert!(requirement_satisfied("1.9", "~> 1.2"));
+        assert!(!requirement_satisfied("2.0", "~> 1.2"));
+        assert!(requirement_satisfied("1.5.0", ">= 1.0, < 2.0"));
+        assert!(!requirement_satisfied("2.0.0", ">= 1.0, < 2.0"));
+        assert!(requirement_satisfied("1.1", "!= 1.0"));
+        assert!(requirement_satisfied("1.0", "<= 1.0"));
+        assert!(!requirement_satisfied("0.9", "> 0.9"));
+    }
+
+    #[test]
+    fn test_is_valid_requirement() {
+        assert!(is_valid_requirement("1.0.0"));
+        assert!(is_valid_requirement("= 1.0"));
+        assert!(is_valid_requirement(">=1.0,<2"));
+        assert!(is_valid_requirement("2.0.0.rc1"));
+        assert!(!is_valid_requirement(""));
+        assert!(!is_valid_requirement(">= 1.0,"));
+        assert!(!is_valid_requirement("=> 1.0"));
+        assert!(!is_valid_requirement("1.0 beta"));
+
+        assert!(requirement_allows_prerelease(">= 2.0.0.rc1"));
+        assert!(!requirement_allows_prerelease("~> 2.0"));
+    }
 }
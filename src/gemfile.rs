@@ -1,5 +1,6 @@
 //! Gemfile parsing using tree-sitter.
 
+use crate::insecure_source::{InsecureSourceError, InsecureSourcePolicy};
 use anyhow::Result;
 use std::path::Path;
 use thiserror::Error;
@@ -19,6 +20,9 @@ pub enum GemfileError {
 
     #[error("Invalid version constraint: {0}")]
     InvalidVersion(String),
+
+    #[error(transparent)]
+    InsecureSource(#[from] InsecureSourceError),
 }
 
 /// Represents a gem dependency from a Gemfile
@@ -60,8 +64,10 @@ pub struct GemDependency {
     /// Platform constraints (e.g., `["ruby", "x86_64-linux"]`)
     pub platforms: Vec<String>,
 
-    /// Require statement (e.g., `require: false`)
-    pub require: Option<bool>,
+    /// Files `Bundler.require` loads for this gem (the `require:` option)
+    ///
+    /// `None` requires the gem's own name; `require: false` is an empty list.
+    pub require: Option<Vec<String>>,
 }
 
 impl GemDependency {
@@ -100,10 +106,46 @@ impl GemDependency {
     #[must_use]
     #[inline]
     pub fn should_require(&self) -> bool {
-        self.require.unwrap_or(true)
+        self.require.as_ref().is_none_or(|paths| !paths.is_empty())
+    }
+
+    /// Files `Bundler.require` loads for this gem, in order
+    #[must_use]
+    pub fn require_paths(&self) -> Vec<String> {
+        self.require
+            .clone()
+            .unwrap_or_else(|| vec![self.name.clone()])
     }
 }
 
+/// A `source` block served by a source provider
+///
+/// Declared as `source "https://github.com/acme/widgets", type: "github" do`;
+/// the gems inside the block have their `source` set to `remote`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginSource {
+    /// Source URI as written in the Gemfile
+    pub remote: String,
+
+    /// Provider type (the `type:` option)
+    pub kind: String,
+}
+
+/// A Bundler plugin declared with `plugin "name"`
+///
+/// Takes an optional version requirement and the `source:`, `git:`
+/// (with `branch:` or `ref:`) and `path:` options, like `lode plugin install`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PluginDeclaration {
+    pub name: String,
+    pub version: Option<String>,
+    pub source: Option<String>,
+    pub git: Option<String>,
+    pub branch: Option<String>,
+    pub ref_: Option<String>,
+    pub path: Option<String>,
+}
+
 /// Represents a parsed Gemfile
 ///
 /// Parses Gemfile syntax without evaluation. Uses tree-sitter to extract
@@ -125,6 +167,12 @@ pub struct Gemfile {
 
     /// Gemspec directives (for gem development)
     pub gemspecs: Vec<String>,
+
+    /// Typed `source` blocks handled by source providers
+    pub plugin_sources: Vec<PluginSource>,
+
+    /// `plugin` declarations, installed before the gems
+    pub plugins: Vec<PluginDeclaration>,
 }
 
 impl Default for Gemfile {
@@ -143,14 +191,57 @@ impl Gemfile {
             source: crate::DEFAULT_GEM_SOURCE.to_string(),
             sources: Vec::new(),
             gemspecs: Vec::new(),
+            plugin_sources: Vec::new(),
+            plugins: Vec::new(),
+        }
+    }
+
+    /// Gems that any of several global sources could serve
+    ///
+    /// With more than one global `source`, a gem outside a `source` block
+    /// and without `source:`, `git:` or `path:` comes from whichever source
+    /// publishes that name, which is how dependency confusion attacks work.
+    /// Empty when there is a single global source.
+    #[must_use]
+    pub fn ambiguous_gems(&self) -> Vec<&str> {
+        if self.sources.is_empty() {
+            return Vec::new();
         }
+        self.gems
+            .iter()
+            .filter(|gem| gem.source.is_none() && gem.git.is_none() && gem.path.is_none())
+            .map(|gem| gem.name.as_str())
+            .collect()
+    }
+
+    /// Every remote the Gemfile fetches from: global sources, `source`
+    /// blocks and options, and `git:` repositories
+    pub fn remote_urls(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.source.as_str())
+            .chain(self.sources.iter().map(String::as_str))
+            .chain(
+                self.plugin_sources
+                    .iter()
+                    .map(|source| source.remote.as_str()),
+            )
+            .chain(
+                self.gems
+                    .iter()
+                    .flat_map(|gem| gem.source.iter().chain(&gem.git))
+                    .map(String::as_str),
+            )
     }
 
     /// Parse a Gemfile from a file path
     ///
+    /// Sources are checked against the `insecure_sources` policy, so a
+    /// Gemfile with a plain `http://` source is refused here rather than
+    /// partway through an install.
+    ///
     /// # Errors
     ///
-    /// Returns an error if the file cannot be read or parsed.
+    /// Returns an error if the file cannot be read or parsed, or uses a
+    /// refused insecure source.
     ///
     /// # Example
     ///
@@ -167,7 +258,9 @@ impl Gemfile {
             source: e,
         })?;
 
-        Self::parse(&content)
+        let gemfile = Self::parse(&content)?;
+        InsecureSourcePolicy::load().check_all(gemfile.remote_urls())?;
+        Ok(gemfile)
     }
 
     /// Parse a Gemfile from string content
@@ -185,6 +278,11 @@ impl Gemfile {
 
         let mut gemfile = Self::new();
 
+        // Open blocks, innermost last; `source` blocks carry their URI so
+        // the gems inside can be attributed to it
+        let mut blocks: Vec<Option<String>> = Vec::new();
+        let mut declared_source = false;
+
         // Line-by-line parsing with regex for gem directives
         // Handles: source, ruby, gem, group, platforms
         for line in content.lines() {
@@ -195,10 +293,42 @@ impl Gemfile {
                 continue;
             }
 
+            if line == "end" {
+                blocks.pop();
+                continue;
+            }
+
             // Parse source directive
             if line.starts_with("source ") {
-                if let Some(url) = extract_string_literal(line) {
-                    gemfile.source = url;
+                let url = extract_string_literal(line);
+                if opens_block(line) {
+                    if let (Some(remote), Some(kind)) = (&url, extract_option(line, "type:")) {
+                        gemfile.plugin_sources.push(PluginSource {
+                            remote: remote.clone(),
+                            kind,
+                        });
+                    }
+                    blocks.push(url);
+                } else if let Some(url) = url {
+                    // Later global sources take precedence; earlier ones
+                    // are still searched
+                    let previous = std::mem::replace(&mut gemfile.source, url);
+                    if declared_source && previous != gemfile.source {
+                        gemfile.sources.push(previous);
+                    }
+                    declared_source = true;
+                }
+                continue;
+            }
+
+            if opens_block(line) {
+                blocks.push(None);
+                continue;
+            }
+
+            if line.starts_with("plugin ") {
+                if let Some(plugin) = parse_plugin_line(line) {
+                    gemfile.plugins.push(plugin);
                 }
                 continue;
             }
@@ -213,8 +343,11 @@ impl Gemfile {
 
             // Parse gem directive (simplified)
             if line.starts_with("gem ")
-                && let Some(gem) = parse_gem_line(line)
+                && let Some(mut gem) = parse_gem_line(line)
             {
+                if gem.source.is_none() {
+                    gem.source = blocks.iter().rev().find_map(Clone::clone);
+                }
                 gemfile.gems.push(gem);
             }
         }
@@ -241,6 +374,22 @@ impl Gemfile {
     }
 }
 
+/// Whether a line opens a `do ... end` block or a conditional closed by `end`
+fn opens_block(line: &str) -> bool {
+    let code = line.split(" #").next().unwrap_or(line).trim_end();
+    code.ends_with(" do")
+        || code.contains(" do |")
+        || ["if ", "unless ", "case ", "begin"]
+            .iter()
+            .any(|keyword| code.starts_with(keyword))
+}
+
+/// Extract the string value of a keyword option (e.g. `type: "github"`)
+fn extract_option(line: &str, key: &str) -> Option<String> {
+    line.split_once(key)
+        .and_then(|(_, rest)| extract_string_literal(rest))
+}
+
 /// Extract a string literal from a line (handles both single and double quotes)
 fn extract_string_literal(line: &str) -> Option<String> {
     // Find first quote (single or double)
@@ -253,6 +402,33 @@ fn extract_string_literal(line: &str) -> Option<String> {
     Some(line[start + 1..start + 1 + end].to_string())
 }
 
+/// Parse a `plugin "name", "~> 1.0", source: "..."` line
+fn parse_plugin_line(line: &str) -> Option<PluginDeclaration> {
+    let name = extract_string_literal(line)?;
+    let after_name = line
+        .split_once(&format!("\"{name}\""))
+        .or_else(|| line.split_once(&format!("'{name}'")))?
+        .1;
+
+    // Only a positional string is a version; option values come after a key
+    let version = after_name
+        .trim_start()
+        .strip_prefix(',')
+        .map(str::trim_start)
+        .filter(|rest| rest.starts_with(['"', '\'']))
+        .and_then(extract_string_literal);
+
+    Some(PluginDeclaration {
+        version,
+        source: extract_option(after_name, "source:"),
+        git: extract_option(after_name, "git:"),
+        branch: extract_option(after_name, "branch:"),
+        ref_: extract_option(after_name, "ref:"),
+        path: extract_option(after_name, "path:"),
+        name,
+    })
+}
+
 /// Parse a simple gem line (placeholder for tree-sitter implementation)
 ///
 /// Simplified parser that handles basic gem declarations. The full tree-sitter
@@ -278,13 +454,15 @@ fn parse_gem_line(line: &str) -> Option<GemDependency> {
     }
 
     // Check for git option
-    if line.contains("git:")
-        && let Some(git_url) = after_name.split("git:").nth(1)
-        && let Some(url) = extract_string_literal(git_url)
-    {
+    if let Some(url) = extract_option(after_name, "git:") {
         gem.git = Some(url);
     }
 
+    // Check for source option
+    if let Some(source) = extract_option(after_name, "source:") {
+        gem.source = Some(source);
+    }
+
     // Check for path option
     if line.contains("path:")
         && let Some(path_part) = after_name.split("path:").nth(1)
@@ -308,9 +486,38 @@ fn parse_gem_line(line: &str) -> Option<GemDependency> {
         gem.groups.extend(extract_groups_array(groups_part));
     }
 
+    // Check for require option: false, a path, or an array of paths
+    if let Some((_, require_part)) = after_name.split_once("require:") {
+        gem.require = extract_require(require_part);
+    }
+
     Some(gem)
 }
 
+/// Extract the value of a `require:` option
+///
+/// `false` yields an empty list and `true` the default (`None`).
+fn extract_require(s: &str) -> Option<Vec<String>> {
+    let trimmed = s.trim_start();
+    if trimmed.starts_with("false") {
+        return Some(Vec::new());
+    }
+    if trimmed.starts_with("true") {
+        return None;
+    }
+    if let Some(array) = trimmed.strip_prefix('[') {
+        let content = array.split(']').next().unwrap_or(array);
+        return Some(
+            content
+                .split(',')
+                .filter_map(extract_string_literal)
+                .collect(),
+        );
+    }
+    let value = trimmed.split(',').next().unwrap_or(trimmed);
+    extract_string_literal(value).map(|path| vec![path])
+}
+
 /// Extract a group symbol from Ruby code (e.g., ":development" -> "development")
 fn extract_group_symbol(s: &str) -> Option<String> {
     let trimmed = s.trim();
@@ -356,6 +563,107 @@ mod tests {
     mod parsing {
         use super::*;
 
+        #[test]
+        fn remote_urls_cover_every_source() {
+            let content = r#"
+source "https://rubygems.org"
+source "http://gems.corp"
+
+source "http://private.corp" do
+  gem "internal"
+end
+
+gem "rails", git: "git://github.com/rails/rails.git"
+gem "local", path: "../local"
+"#;
+            let gemfile = Gemfile::parse(content).unwrap();
+            let urls: Vec<_> = gemfile.remote_urls().collect();
+            assert_eq!(
+                urls,
+                vec![
+                    "http://gems.corp",
+                    "https://rubygems.org",
+                    "http://private.corp",
+                    "git://github.com/rails/rails.git",
+                ]
+            );
+        }
+
+        #[test]
+        fn typed_source_blocks() {
+            let content = r#"
+source "https://rubygems.org"
+
+source "https://github.com/acme/widgets", type: "github" do
+  gem "widgets"
+end
+
+group :test do
+  gem "rspec"
+end
+
+gem "rack"
+"#;
+            let gemfile = Gemfile::parse(content).unwrap();
+
+            assert_eq!(gemfile.source, "https://rubygems.org");
+            assert_eq!(
+                gemfile.plugin_sources,
+                vec![PluginSource {
+                    remote: "https://github.com/acme/widgets".to_string(),
+                    kind: "github".to_string(),
+                }]
+            );
+            let sources: Vec<_> = gemfile
+                .gems
+                .iter()
+                .map(|gem| (gem.name.as_str(), gem.source.as_deref()))
+                .collect();
+            assert_eq!(
+                sources,
+                vec![
+                    ("widgets", Some("https://github.com/acme/widgets")),
+                    ("rspec", None),
+                    ("rack", None),
+                ]
+            );
+        }
+
+        #[test]
+        fn plugin_declarations() {
+            let content = r#"
+source "https://rubygems.org"
+plugin "bundler-graph"
+plugin 'bundler-audit', "~> 0.9", source: "https://gems.example.com"
+plugin "acme-hooks", git: "https://github.com/acme/hooks", branch: "main"
+gem "rack"
+"#;
+            let gemfile = Gemfile::parse(content).unwrap();
+
+            assert_eq!(gemfile.gems.len(), 1);
+            assert_eq!(
+                gemfile.plugins,
+                vec![
+                    PluginDeclaration {
+                        name: "bundler-graph".to_string(),
+                        ..PluginDeclaration::default()
+                    },
+                    PluginDeclaration {
+                        name: "bundler-audit".to_string(),
+                        version: Some("~> 0.9".to_string()),
+                        source: Some("https://gems.example.com".to_string()),
+                        ..PluginDeclaration::default()
+                    },
+                    PluginDeclaration {
+                        name: "acme-hooks".to_string(),
+                        git: Some("https://github.com/acme/hooks".to_string()),
+                        branch: Some("main".to_string()),
+                        ..PluginDeclaration::default()
+                    },
+                ]
+            );
+        }
+
         #[test]
         fn empty_gemfile() {
             let gemfile = Gemfile::parse("").unwrap();
@@ -369,6 +677,25 @@ mod tests {
             assert_eq!(gemfile.source, "https://rubygems.org");
         }
 
+        #[test]
+        fn multiple_global_sources_are_ambiguous() {
+            let content = r#"source "https://rubygems.org"
+source "https://gems.example.com"
+gem "rails"
+gem "internal", source: "https://gems.example.com"
+source "https://gems.example.com" do
+  gem "billing"
+end
+"#;
+            let gemfile = Gemfile::parse(content).unwrap();
+            assert_eq!(gemfile.source, "https://gems.example.com");
+            assert_eq!(gemfile.sources, vec!["https://rubygems.org".to_string()]);
+            assert_eq!(gemfile.ambiguous_gems(), vec!["rails"]);
+
+            let single = Gemfile::parse("source \"https://rubygems.org\"\ngem \"rails\"").unwrap();
+            assert!(single.ambiguous_gems().is_empty());
+        }
+
         #[test]
         fn ruby_version() {
             let content = r#"ruby "3.2.0""#;
@@ -439,6 +766,33 @@ mod tests {
             assert_eq!(gem.name, "pry");
             assert_eq!(gem.groups, vec!["development", "test"]);
         }
+
+        #[test]
+        fn gem_require_option() {
+            let content = r#"gem "rake"
+gem "bootsnap", require: false
+gem "sprockets-rails", require: "sprockets/railtie"
+gem "aws-sdk", "~> 3", require: ["aws-sdk-s3", 'aws-sdk-sqs'], group: :production
+"#;
+            let gemfile = Gemfile::parse(content).unwrap();
+            let requires: Vec<_> = gemfile
+                .gems
+                .iter()
+                .map(|gem| (gem.should_require(), gem.require_paths()))
+                .collect();
+            assert_eq!(
+                requires,
+                vec![
+                    (true, vec!["rake".to_string()]),
+                    (false, vec![]),
+                    (true, vec!["sprockets/railtie".to_string()]),
+                    (
+                        true,
+                        vec!["aws-sdk-s3".to_string(), "aws-sdk-sqs".to_string()]
+                    ),
+                ]
+            );
+        }
     }
 
     mod gem_dependency {
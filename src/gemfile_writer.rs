@@ -8,9 +8,12 @@
 //!
 //! # Approach
 //!
-//! Rather than parsing to an AST and regenerating, we read the file line-by-line,
-//! identify gem declarations using regex, and perform targeted modifications.
-//! This preserves user formatting and comments.
+//! Rather than parsing to an AST and regenerating, we keep the file as raw
+//! lines (each with its own line ending) and group them into statements: a
+//! statement continues across lines while brackets are open or a line ends in
+//! a comma or backslash, and `do`/`if`/`end` track block nesting. Edits touch
+//! only the lines of the gem being added or removed; every other byte, down
+//! to CRLF endings and a missing final newline, is written back unchanged.
 //!
 //! Similar to `bundle add` / `bundle remove` - modifies your Gemfile programmatically
 //! while keeping your formatting intact.
@@ -19,12 +22,65 @@ use anyhow::{Context, Result};
 use regex::Regex;
 use std::fs;
 use std::path::Path;
+use std::sync::LazyLock;
+
+/// A statement ending in a `do` block, optionally with block arguments
+static DO_BLOCK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bdo\s*(\|[^|]*\|)?$").expect("should build valid regex"));
+
+/// Keywords that open a block closed by `end`
+const BLOCK_KEYWORDS: [&str; 10] = [
+    "if", "unless", "case", "while", "until", "begin", "def", "class", "module", "for",
+];
+
+/// Top-level settings that new top-level gems are placed after
+const HEADER_METHODS: [&str; 6] = [
+    "source",
+    "ruby",
+    "gemspec",
+    "git_source",
+    "plugin",
+    "eval_gemfile",
+];
 
 /// A Gemfile writer that preserves structure and formatting
 #[derive(Debug)]
 pub struct GemfileWriter {
     path: String,
+    /// Raw lines, each including its line ending (the last may have none)
     lines: Vec<String>,
+    /// Line ending used for inserted lines, matching the file
+    newline: &'static str,
+}
+
+/// What a statement does
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum StatementKind {
+    /// `gem "name", ...`
+    Gem {
+        name: String,
+    },
+    /// `group :a, :b do`
+    Group(Vec<String>),
+    /// Any other statement opening a block closed by `end`
+    Block,
+    /// `end`
+    End,
+    Other,
+}
+
+/// A logical statement, possibly spanning several lines
+#[derive(Debug)]
+struct Statement {
+    /// Index of the first line
+    first: usize,
+    /// Index of the last line (inclusive)
+    last: usize,
+    /// Block nesting depth; an `end` has the depth of the line it closes
+    depth: usize,
+    /// Code of every line, comments stripped, joined with spaces
+    code: String,
+    kind: StatementKind,
 }
 
 impl GemfileWriter {
@@ -38,12 +94,22 @@ impl GemfileWriter {
         let content = fs::read_to_string(&path)
             .with_context(|| format!("Failed to read Gemfile at {path_str}"))?;
 
-        let lines = content.lines().map(String::from).collect();
+        Ok(Self::parse(path_str, &content))
+    }
 
-        Ok(Self {
-            path: path_str,
+    fn parse(path: String, content: &str) -> Self {
+        let lines: Vec<String> = content.split_inclusive('\n').map(String::from).collect();
+        let newline = if lines.first().is_some_and(|line| line.ends_with("\r\n")) {
+            "\r\n"
+        } else {
+            "\n"
+        };
+
+        Self {
+            path,
             lines,
-        })
+            newline,
+        }
     }
 
     /// Add a gem to the Gemfile.
@@ -72,13 +138,26 @@ impl GemfileWriter {
         group: Option<&str>,
         options: Option<&str>,
     ) -> Result<()> {
+        let statements = self.statements();
+
         // Check if gem already exists
-        if let Some(line_idx) = self.find_gem(name) {
+        if let Some(existing) = statements
+            .iter()
+            .find(|s| matches!(&s.kind, StatementKind::Gem { name: n } if n == name))
+        {
             // Update existing gem
-            self.update_gem_at(line_idx, name, version, options);
+            self.update_gem(existing, name, version, options);
+        } else if let Some(group_names) = group {
+            // Insert into group block
+            let groups: Vec<String> = group_names
+                .split(',')
+                .map(|g| g.trim().trim_start_matches(':').to_string())
+                .filter(|g| !g.is_empty())
+                .collect();
+            self.insert_into_group(&statements, name, version, &groups, options);
         } else {
-            // Insert new gem
-            self.insert_gem(name, version, group, options)?;
+            // Insert into default gems section (after source, before groups)
+            self.insert_into_default_section(&statements, name, version, options);
         }
 
         Ok(())
@@ -86,7 +165,8 @@ impl GemfileWriter {
 
     /// Remove a gem from the Gemfile
     ///
-    /// Removes all declarations of the gem, including those in group blocks.
+    /// Removes all declarations of the gem, including those in group blocks
+    /// and those spanning several lines.
     ///
     /// # Arguments
     ///
@@ -112,20 +192,19 @@ impl GemfileWriter {
     ///
     /// Returns an error if gem removal validation fails.
     pub fn remove_gem(&mut self, name: &str) -> Result<bool> {
-        let mut removed = false;
-        let gem_pattern = Self::gem_pattern(name);
+        let declarations: Vec<(usize, usize)> = self
+            .statements()
+            .iter()
+            .filter(|s| matches!(&s.kind, StatementKind::Gem { name: n } if n == name))
+            .map(|s| (s.first, s.last))
+            .collect();
 
         // Remove in reverse order to avoid index shifting
-        for idx in (0..self.lines.len()).rev() {
-            if let Some(line) = self.lines.get(idx)
-                && gem_pattern.is_match(line)
-            {
-                self.lines.remove(idx);
-                removed = true;
-            }
+        for &(first, last) in declarations.iter().rev() {
+            self.lines.drain(first..=last);
         }
 
-        Ok(removed)
+        Ok(!declarations.is_empty())
     }
 
     /// Write the modified Gemfile back to disk
@@ -134,175 +213,296 @@ impl GemfileWriter {
     ///
     /// Returns an error if the file cannot be written.
     pub fn write(&self) -> Result<()> {
-        let content = self.lines.join("\n");
-        // Add trailing newline if original had one
-        let content = if content.is_empty() || content.ends_with('\n') {
-            content
-        } else {
-            format!("{content}\n")
-        };
-
-        fs::write(&self.path, content)
+        fs::write(&self.path, self.lines.concat())
             .with_context(|| format!("Failed to write Gemfile to {}", self.path))
     }
 
-    /// Find the line index of a gem declaration
-    fn find_gem(&self, name: &str) -> Option<usize> {
-        let pattern = Self::gem_pattern(name);
-        self.lines.iter().position(|line| pattern.is_match(line))
+    /// Line `idx` without its line ending
+    fn text(&self, idx: usize) -> &str {
+        self.lines
+            .get(idx)
+            .map_or("", |line| line.trim_end_matches(['\n', '\r']))
     }
 
-    /// Update a gem declaration at a specific line
-    fn update_gem_at(
-        &mut self,
-        line_idx: usize,
-        name: &str,
-        version: Option<&str>,
-        options: Option<&str>,
-    ) {
-        if let Some(existing_line) = self.lines.get(line_idx) {
-            // Extract indentation from existing line
-            let indent = existing_line
-                .chars()
-                .take_while(|c| c.is_whitespace())
-                .collect::<String>();
-
-            // Build new gem line
-            let new_line = Self::format_gem_line(&indent, name, version, options);
-            if let Some(line) = self.lines.get_mut(line_idx) {
-                *line = new_line;
+    /// Leading whitespace of line `idx`
+    fn indent(&self, idx: usize) -> String {
+        self.text(idx)
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect()
+    }
+
+    /// Group the lines into statements, tracking block depth
+    fn statements(&self) -> Vec<Statement> {
+        let mut statements = Vec::new();
+        let mut depth = 0_usize;
+        let mut in_doc_comment = false;
+        let mut idx = 0;
+
+        while idx < self.lines.len() {
+            let text = self.text(idx);
+
+            // =begin/=end embedded documentation
+            if in_doc_comment || text.starts_with("=begin") {
+                in_doc_comment = !text.starts_with("=end");
+                idx += 1;
+                continue;
             }
+
+            let mut code = strip_comment(text).trim().to_string();
+            if code.is_empty() {
+                idx += 1;
+                continue;
+            }
+
+            let first = idx;
+            while continues(&code) && idx + 1 < self.lines.len() {
+                idx += 1;
+                let next = strip_comment(self.text(idx)).trim();
+                if !next.is_empty() {
+                    code.push(' ');
+                    code.push_str(next);
+                }
+            }
+
+            let kind = classify(&code);
+            if kind == StatementKind::End {
+                depth = depth.saturating_sub(1);
+            }
+            let statement_depth = depth;
+            if matches!(kind, StatementKind::Group(_) | StatementKind::Block) {
+                depth += 1;
+            }
+
+            statements.push(Statement {
+                first,
+                last: idx,
+                depth: statement_depth,
+                code,
+                kind,
+            });
+            idx += 1;
         }
+
+        statements
+    }
+
+    /// Quote character the Gemfile uses for gem names
+    fn quote_style(statements: &[Statement]) -> char {
+        statements
+            .iter()
+            .find(|s| matches!(s.kind, StatementKind::Gem { .. }))
+            .and_then(|s| gem_arguments(&s.code).first()?.chars().next())
+            .filter(|c| *c == '\'')
+            .unwrap_or('"')
     }
 
-    /// Insert a new gem declaration
-    fn insert_gem(
+    /// Rewrite an existing declaration, keeping what the caller leaves unset
+    ///
+    /// The indentation, quote style and trailing comments stay as they were;
+    /// existing version requirements and options are kept unless replaced.
+    fn update_gem(
         &mut self,
+        statement: &Statement,
         name: &str,
         version: Option<&str>,
-        group: Option<&str>,
         options: Option<&str>,
-    ) -> Result<()> {
-        if let Some(group_name) = group {
-            // Insert into group block
-            self.insert_into_group(name, version, group_name, options)?;
-        } else {
-            // Insert into default gems section (after source, before groups)
-            self.insert_into_default_section(name, version, options);
+    ) {
+        let arguments = gem_arguments(&statement.code);
+        let quote = arguments
+            .first()
+            .and_then(|name| name.chars().next())
+            .filter(|c| *c == '\'')
+            .unwrap_or('"');
+        let version_count = arguments
+            .iter()
+            .skip(1)
+            .take_while(|arg| string_literal(arg).is_some())
+            .count();
+        let existing_versions = arguments.iter().skip(1).take(version_count);
+        let existing_options = arguments.iter().skip(1 + version_count);
+
+        let mut parts = vec![format!("{quote}{name}{quote}")];
+        match version {
+            Some(version) => parts.push(format!("{quote}{version}{quote}")),
+            None => parts.extend(existing_versions.map(ToString::to_string)),
+        }
+        match options {
+            Some(options) => parts.push(options.to_string()),
+            None => parts.extend(existing_options.map(ToString::to_string)),
+        }
+        let mut line = format!("{}gem {}", self.indent(statement.first), parts.join(", "));
+
+        let comments: Vec<&str> = (statement.first..=statement.last)
+            .filter_map(|idx| {
+                let text = self.text(idx);
+                text.get(strip_comment(text).len()..)
+                    .filter(|comment| !comment.is_empty())
+            })
+            .collect();
+        if !comments.is_empty() {
+            line.push(' ');
+            line.push_str(&comments.join(" "));
         }
 
-        Ok(())
+        let ending = self
+            .lines
+            .get(statement.last)
+            .map_or("", |last| {
+                &last[last.trim_end_matches(['\n', '\r']).len()..]
+            })
+            .to_string();
+        self.lines.splice(
+            statement.first..=statement.last,
+            std::iter::once(format!("{line}{ending}")),
+        );
     }
 
-    /// Insert gem into a group block
+    /// Insert gem into the block for exactly `groups`, creating it if needed
     fn insert_into_group(
         &mut self,
+        statements: &[Statement],
         name: &str,
         version: Option<&str>,
-        group_name: &str,
+        groups: &[String],
         options: Option<&str>,
-    ) -> Result<()> {
-        // Find group block
-        let group_pattern = Regex::new(&format!(r"^\s*group\s+:?{group_name}"))?;
-
-        if let Some(group_start) = self
-            .lines
+    ) {
+        let quote = Self::quote_style(statements);
+        let group = statements
             .iter()
-            .position(|line| group_pattern.is_match(line))
-        {
-            // Find end of group block
-            let mut insert_idx = group_start + 1;
-            let mut group_depth = 1;
-
-            for idx in (group_start + 1)..self.lines.len() {
-                let Some(line) = self.lines.get(idx) else {
-                    continue;
-                };
-
-                if line.trim().starts_with("group") {
-                    group_depth += 1;
-                } else if line.trim() == "end" {
-                    group_depth -= 1;
-                    if group_depth == 0 {
-                        insert_idx = idx;
-                        break;
-                    }
-                }
-
-                // Check for gem declarations to maintain alphabetical order
-                if let Some(existing_name) = Self::extract_gem_name(line) {
-                    if existing_name.as_str() > name {
-                        insert_idx = idx;
-                        break;
-                    }
-                    insert_idx = idx + 1;
-                }
-            }
-
-            // Build gem line with proper indentation
-            let gem_line = Self::format_gem_line("  ", name, version, options);
-            self.lines.insert(insert_idx, gem_line);
-        } else {
+            .position(|s| matches!(&s.kind, StatementKind::Group(names) if names == groups));
+        let block = group.and_then(|start| {
+            let depth = statements.get(start)?.depth;
+            let body = statements.get(start + 1..)?;
+            let end = body
+                .iter()
+                .position(|s| s.depth == depth && s.kind == StatementKind::End)?;
+            Some((start, body.get(..end)?, body.get(end)?))
+        });
+
+        let Some((start, body, end)) = block else {
             // Group doesn't exist, create it
-            self.create_group_block(name, version, group_name, options);
-        }
+            self.create_group_block(name, version, groups, options, quote);
+            return;
+        };
 
-        Ok(())
+        let depth = statements.get(start).map_or(0, |s| s.depth);
+        let gems: Vec<&Statement> = body
+            .iter()
+            .filter(|s| s.depth == depth + 1 && matches!(s.kind, StatementKind::Gem { .. }))
+            .collect();
+        let indent = gems.first().map_or_else(
+            || format!("{}  ", self.indent(end.first)),
+            |gem| self.indent(gem.first),
+        );
+        let idx = self.sorted_position(&gems, name).unwrap_or(end.first);
+
+        let line = Self::format_gem_line(&indent, name, version, options, quote);
+        self.insert_lines(idx, &[line]);
     }
 
     /// Insert gem into default section (not in any group)
     fn insert_into_default_section(
         &mut self,
+        statements: &[Statement],
         name: &str,
         version: Option<&str>,
         options: Option<&str>,
     ) {
-        // Find insertion point: after source/ruby declarations, before first group
-        let mut insert_idx = 0;
-
-        // Skip source/ruby/gemspec lines
-        for (idx, line) in self.lines.iter().enumerate() {
-            let trimmed = line.trim();
-            if trimmed.starts_with("source")
-                || trimmed.starts_with("ruby")
-                || trimmed.starts_with("gemspec")
-                || trimmed.is_empty()
-                || trimmed.starts_with('#')
-            {
-                insert_idx = idx + 1;
-            } else if trimmed.starts_with("group") {
-                // Stop before first group
-                break;
-            } else if trimmed.starts_with("gem") {
-                // Found existing gem, maintain alphabetical order
-                if let Some(existing_name) = Self::extract_gem_name(line) {
-                    if existing_name.as_str() > name {
-                        insert_idx = idx;
-                        break;
-                    }
-                    insert_idx = idx + 1;
-                }
-            }
-        }
+        let quote = Self::quote_style(statements);
+        let gems: Vec<&Statement> = statements
+            .iter()
+            .filter(|s| s.depth == 0 && matches!(s.kind, StatementKind::Gem { .. }))
+            .collect();
+
+        // Without top-level gems, go after source/ruby declarations, before the first block
+        let idx = self.sorted_position(&gems, name).unwrap_or_else(|| {
+            let top_level: Vec<&Statement> = statements
+                .iter()
+                .filter(|s| s.depth == 0)
+                .take_while(|s| !matches!(s.kind, StatementKind::Group(_) | StatementKind::Block))
+                .collect();
+            top_level
+                .iter()
+                .rev()
+                .find(|s| HEADER_METHODS.contains(&method_name(&s.code)))
+                .map(|s| s.last + 1)
+                .or_else(|| statements.first().map(|s| s.first))
+                .unwrap_or(self.lines.len())
+        });
+
+        let line = Self::format_gem_line("", name, version, options, quote);
+        self.insert_lines(idx, &[line]);
+    }
 
-        let gem_line = Self::format_gem_line("", name, version, options);
-        self.lines.insert(insert_idx, gem_line);
+    /// Line to insert `name` at among sibling `gems`, keeping alphabetical order
+    ///
+    /// Goes above the first gem sorting after `name`, along with the comments
+    /// directly above it, else below the last gem. `None` without siblings.
+    fn sorted_position(&self, gems: &[&Statement], name: &str) -> Option<usize> {
+        let after = gems.iter().find(|gem| {
+            matches!(&gem.kind, StatementKind::Gem { name: existing } if existing.as_str() > name)
+        });
+        after.map_or_else(
+            || gems.last().map(|gem| gem.last + 1),
+            |gem| {
+                let mut idx = gem.first;
+                while idx > 0 && self.text(idx - 1).trim_start().starts_with('#') {
+                    idx -= 1;
+                }
+                Some(idx)
+            },
+        )
     }
 
-    /// Create a new group block and add gem to it
+    /// Create a new group block at the end of the file and add gem to it
     fn create_group_block(
         &mut self,
         name: &str,
         version: Option<&str>,
-        group_name: &str,
+        groups: &[String],
         options: Option<&str>,
+        quote: char,
     ) {
-        // Insert group block at end of file
-        self.lines.push(String::new()); // Empty line before group
-        self.lines.push(format!("group :{group_name} do"));
-        self.lines
-            .push(Self::format_gem_line("  ", name, version, options));
-        self.lines.push("end".to_string());
+        let mut block = Vec::with_capacity(4);
+        let last_is_blank = self
+            .lines
+            .len()
+            .checked_sub(1)
+            .is_none_or(|last| self.text(last).trim().is_empty());
+        if !last_is_blank {
+            block.push(String::new()); // Empty line before group
+        }
+        let names: Vec<String> = groups.iter().map(|g| format!(":{g}")).collect();
+        block.push(format!("group {} do", names.join(", ")));
+        block.push(Self::format_gem_line("  ", name, version, options, quote));
+        block.push("end".to_string());
+
+        let end = self.lines.len();
+        self.insert_lines(end, &block);
+    }
+
+    /// Insert whole lines before line `idx`, using the file's line endings
+    ///
+    /// Appending to a file without a final newline keeps it that way.
+    fn insert_lines(&mut self, idx: usize, texts: &[String]) {
+        let unterminated_end =
+            idx >= self.lines.len() && self.lines.last().is_some_and(|last| !last.ends_with('\n'));
+        if unterminated_end && let Some(last) = self.lines.last_mut() {
+            last.push_str(self.newline);
+        }
+
+        let idx = idx.min(self.lines.len());
+        let newline = self.newline;
+        let new_lines = texts.iter().enumerate().map(|(offset, text)| {
+            let ending = if unterminated_end && offset + 1 == texts.len() {
+                ""
+            } else {
+                newline
+            };
+            format!("{text}{ending}")
+        });
+        self.lines.splice(idx..idx, new_lines);
     }
 
     /// Format a gem declaration line
@@ -311,13 +511,14 @@ impl GemfileWriter {
         name: &str,
         version: Option<&str>,
         options: Option<&str>,
+        quote: char,
     ) -> String {
         use std::fmt::Write;
 
-        let mut line = format!("{indent}gem \"{name}\"");
+        let mut line = format!("{indent}gem {quote}{name}{quote}");
 
         if let Some(ver) = version {
-            let _ = write!(line, ", \"{ver}\"");
+            let _ = write!(line, ", {quote}{ver}{quote}");
         }
 
         if let Some(opts) = options {
@@ -327,18 +528,147 @@ impl GemfileWriter {
         line
     }
 
-    /// Create a regex pattern to match a gem declaration
-    fn gem_pattern(name: &str) -> Regex {
-        // Matches: gem "name" or gem 'name' at start of line (with optional whitespace)
-        Regex::new(&format!(r#"^\s*gem\s+["']{name}["']"#)).expect("should build valid regex")
-    }
-
     /// Extract gem name from a gem declaration line
+    #[cfg(test)]
     fn extract_gem_name(line: &str) -> Option<String> {
-        let pattern = Regex::new(r#"^\s*gem\s+["']([^"']+)["']"#).ok()?;
-        pattern
-            .captures(line)
-            .and_then(|cap| cap.get(1).map(|m| m.as_str().to_string()))
+        match classify(strip_comment(line).trim()) {
+            StatementKind::Gem { name } => Some(name),
+            _ => None,
+        }
+    }
+}
+
+/// Byte offsets and characters of `text` outside string literals
+fn unquoted(text: &str) -> impl Iterator<Item = (usize, char)> + '_ {
+    let mut quote = None;
+    let mut escaped = false;
+    text.char_indices().filter(move |&(_, c)| {
+        if let Some(open) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == open {
+                quote = None;
+            }
+            false
+        } else if c == '"' || c == '\'' {
+            quote = Some(c);
+            false
+        } else {
+            true
+        }
+    })
+}
+
+/// `text` up to any `#` comment
+fn strip_comment(text: &str) -> &str {
+    unquoted(text)
+        .find(|&(_, c)| c == '#')
+        .and_then(|(idx, _)| text.get(..idx))
+        .unwrap_or(text)
+}
+
+/// Whether a statement's code carries on to the next line
+fn continues(code: &str) -> bool {
+    let open_brackets = unquoted(code).fold(0_i32, |depth, (_, c)| match c {
+        '(' | '[' | '{' => depth + 1,
+        ')' | ']' | '}' => depth - 1,
+        _ => depth,
+    });
+    open_brackets > 0
+        || [",", "\\", "||", "&&", "=>"]
+            .iter()
+            .any(|suffix| code.ends_with(suffix))
+}
+
+/// Method or keyword a statement starts with
+fn method_name(code: &str) -> &str {
+    code.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .next()
+        .unwrap_or_default()
+}
+
+/// Split `text` on commas outside strings and brackets
+fn split_arguments(text: &str) -> Vec<&str> {
+    let mut arguments = Vec::new();
+    let mut depth = 0_i32;
+    let mut start = 0;
+    for (idx, c) in unquoted(text) {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                arguments.push(text.get(start..idx).unwrap_or_default().trim());
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    arguments.push(text.get(start..).unwrap_or_default().trim());
+    arguments.retain(|arg| !arg.is_empty());
+    arguments
+}
+
+/// Arguments of a `gem` call, with or without parentheses
+fn gem_arguments(code: &str) -> Vec<&str> {
+    let rest = code
+        .get(method_name(code).len()..)
+        .unwrap_or_default()
+        .trim();
+    let rest = rest
+        .strip_prefix('(')
+        .and_then(|inner| inner.strip_suffix(')'))
+        .unwrap_or(rest);
+    split_arguments(rest)
+}
+
+/// Contents of a quoted string literal
+fn string_literal(arg: &str) -> Option<&str> {
+    ['"', '\''].iter().find_map(|&quote| {
+        arg.strip_prefix(quote)
+            .and_then(|inner| inner.strip_suffix(quote))
+    })
+}
+
+/// Classify a statement by its code
+fn classify(code: &str) -> StatementKind {
+    let method = method_name(code);
+    match method {
+        "end" => return StatementKind::End,
+        "gem" => {
+            if let Some(name) = gem_arguments(code)
+                .first()
+                .and_then(|arg| string_literal(arg))
+            {
+                return StatementKind::Gem {
+                    name: name.to_string(),
+                };
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(found) = DO_BLOCK.find(code) {
+        if method == "group" {
+            let arguments = code.get(..found.start()).unwrap_or_default();
+            let groups = gem_arguments(arguments.trim_end())
+                .into_iter()
+                .filter_map(|arg| {
+                    arg.strip_prefix(':')
+                        .or_else(|| string_literal(arg))
+                        .map(|name| name.trim_matches(['"', '\'']).to_string())
+                })
+                .collect();
+            return StatementKind::Group(groups);
+        }
+        return StatementKind::Block;
+    }
+
+    if BLOCK_KEYWORDS.contains(&method) {
+        StatementKind::Block
+    } else {
+        StatementKind::Other
     }
 }
 
@@ -395,19 +725,159 @@ mod tests {
     #[test]
     fn test_format_gem_line() {
         assert_eq!(
-            GemfileWriter::format_gem_line("", "rails", None, None),
+            GemfileWriter::format_gem_line("", "rails", None, None, '"'),
             "gem \"rails\""
         );
         assert_eq!(
-            GemfileWriter::format_gem_line("  ", "rails", Some("~> 7.0"), None),
+            GemfileWriter::format_gem_line("  ", "rails", Some("~> 7.0"), None, '"'),
             "  gem \"rails\", \"~> 7.0\""
         );
         assert_eq!(
-            GemfileWriter::format_gem_line("", "rails", Some("~> 7.0"), Some("require: false")),
+            GemfileWriter::format_gem_line(
+                "",
+                "rails",
+                Some("~> 7.0"),
+                Some("require: false"),
+                '"'
+            ),
             "gem \"rails\", \"~> 7.0\", require: false"
         );
     }
 
+    /// Apply `edit` to `content` and return the resulting Gemfile
+    fn edited(content: &str, edit: impl FnOnce(&mut GemfileWriter)) -> String {
+        let mut writer = GemfileWriter::parse("Gemfile".to_string(), content);
+        edit(&mut writer);
+        writer.lines.concat()
+    }
+
+    const RAILS_GEMFILE: &str = "\
+# frozen_string_literal: true
+
+source 'https://rubygems.org'
+git_source(:github) { |repo| \"https://github.com/#{repo}.git\" }
+
+ruby '3.3.0'
+
+# Bundle edge Rails instead: gem 'rails', github: 'rails/rails'
+gem 'rails', '~> 7.1'   # the framework
+# Use Puma as the app server
+gem 'puma', '>= 5.0'
+gem 'sprockets-rails',
+    require: 'sprockets/railtie'
+
+group :development, :test do
+  # See https://guides.rubyonrails.org/debugging_rails_applications.html
+  gem 'debug', platforms: %i[mri windows]
+
+  platforms :jruby do
+    gem 'jdbc-sqlite3'
+  end
+end
+
+group :test_tools do
+  gem 'capybara'
+end
+
+if ENV['CI']
+  gem 'simplecov'
+end
+
+group :test do
+  gem 'selenium-webdriver'
+end
+";
+
+    #[test]
+    fn add_and_remove_leave_other_lines_untouched() {
+        let added = edited(RAILS_GEMFILE, |w| {
+            w.add_gem("pg", None, None, None).unwrap();
+        });
+        assert_eq!(
+            added,
+            RAILS_GEMFILE.replace("# Bundle edge Rails", "gem 'pg'\n# Bundle edge Rails")
+        );
+        let removed = edited(&added, |w| {
+            assert!(w.remove_gem("pg").unwrap());
+        });
+        assert_eq!(removed, RAILS_GEMFILE);
+
+        // Multi-line declarations go as a whole
+        let removed = edited(RAILS_GEMFILE, |w| {
+            assert!(w.remove_gem("sprockets-rails").unwrap());
+        });
+        assert_eq!(
+            removed,
+            RAILS_GEMFILE.replace(
+                "gem 'sprockets-rails',\n    require: 'sprockets/railtie'\n",
+                ""
+            )
+        );
+    }
+
+    #[test]
+    fn add_to_group_matches_exact_group_past_nested_blocks() {
+        let added = edited(RAILS_GEMFILE, |w| {
+            w.add_gem("rspec-rails", None, Some("development, test"), None)
+                .unwrap();
+            w.add_gem("minitest", None, Some("test"), None).unwrap();
+        });
+        assert_eq!(
+            added,
+            RAILS_GEMFILE
+                .replace(
+                    "  gem 'debug', platforms: %i[mri windows]\n",
+                    "  gem 'debug', platforms: %i[mri windows]\n  gem 'rspec-rails'\n"
+                )
+                .replace(
+                    "group :test do\n  gem 'selenium-webdriver'",
+                    "group :test do\n  gem 'minitest'\n  gem 'selenium-webdriver'"
+                )
+        );
+
+        let created = edited(RAILS_GEMFILE, |w| {
+            w.add_gem("bootsnap", None, Some("production"), None)
+                .unwrap();
+        });
+        assert_eq!(
+            created,
+            format!("{RAILS_GEMFILE}\ngroup :production do\n  gem 'bootsnap'\nend\n")
+        );
+    }
+
+    #[test]
+    fn preserves_line_endings_and_missing_final_newline() {
+        let crlf = "source \"https://rubygems.org\"\r\n\r\ngem \"rack\"";
+        let added = edited(crlf, |w| {
+            w.add_gem("sinatra", None, None, None).unwrap();
+        });
+        assert_eq!(
+            added,
+            "source \"https://rubygems.org\"\r\n\r\ngem \"rack\"\r\ngem \"sinatra\""
+        );
+    }
+
+    #[test]
+    fn update_keeps_options_and_comments() {
+        let updated = edited(RAILS_GEMFILE, |w| {
+            w.add_gem("rails", Some("~> 7.2"), None, None).unwrap();
+            w.add_gem("debug", Some(">= 1.9"), Some("test"), None)
+                .unwrap();
+        });
+        assert_eq!(
+            updated,
+            RAILS_GEMFILE
+                .replace(
+                    "gem 'rails', '~> 7.1'   # the framework",
+                    "gem 'rails', '~> 7.2' # the framework"
+                )
+                .replace(
+                    "gem 'debug', platforms: %i[mri windows]",
+                    "gem 'debug', '>= 1.9', platforms: %i[mri windows]"
+                )
+        );
+    }
+
     #[test]
     fn update_existing_gem() {
         let temp = NamedTempFile::new().unwrap();
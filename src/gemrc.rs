@@ -0,0 +1,237 @@
+//! `RubyGems` configuration (`.gemrc`)
+//!
+//! Reads the YAML file `gem` itself uses: `~/.gemrc`, `$GEMRC`, or the file
+//! given with `--config-file`. Keys may be written with or without a
+//! leading colon (`:sources:` or `sources:`). The `gem:` entry holds default
+//! arguments for every command, and entries named after a command (like
+//! `install:`) hold defaults for that command only.
+//!
+//! Settings become extra command-line arguments, as in `RubyGems`:
+//! [`Gemrc::extra_args`] lists them and the CLI keeps the ones each
+//! `gem-*` command accepts, placed before the user's own arguments.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Parsed `.gemrc`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Gemrc {
+    /// File the settings came from, if any
+    pub path: Option<PathBuf>,
+    /// `:sources:`, replacing rubygems.org as the remote source
+    pub sources: Vec<String>,
+    /// `:backtrace:`, show backtraces on errors
+    pub backtrace: Option<bool>,
+    /// `:verbose:`, verbose output
+    pub verbose: Option<bool>,
+    /// `http_proxy:`, proxy for remote operations
+    pub http_proxy: Option<String>,
+    /// Default arguments by command (`gem` applies to every command)
+    pub args: BTreeMap<String, Vec<String>>,
+}
+
+impl Gemrc {
+    /// Load the `.gemrc` for a command
+    ///
+    /// `config_file` replaces the default location. `norc` skips loading
+    /// entirely. A missing default file is not an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `config_file` cannot be read, or if the file is
+    /// not valid YAML.
+    pub fn load(config_file: Option<&str>, norc: bool) -> Result<Self> {
+        if norc {
+            return Ok(Self::default());
+        }
+        if let Some(path) = config_file {
+            return Self::load_from(Path::new(path));
+        }
+        match Self::default_path() {
+            Some(path) if path.is_file() => Self::load_from(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// `$GEMRC`, else `~/.gemrc`
+    #[must_use]
+    pub fn default_path() -> Option<PathBuf> {
+        crate::env_vars::gemrc()
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(".gemrc")))
+    }
+
+    fn load_from(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read gem config file {}", path.display()))?;
+        let mut gemrc = Self::parse(&content)
+            .with_context(|| format!("Failed to parse gem config file {}", path.display()))?;
+        crate::debug!("loaded gemrc from {}", path.display());
+        gemrc.path = Some(path.to_path_buf());
+        Ok(gemrc)
+    }
+
+    /// Parse `.gemrc` YAML
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `content` is not valid YAML.
+    pub fn parse(content: &str) -> Result<Self> {
+        let yaml: serde_yaml::Value = serde_yaml::from_str(content)?;
+        let mut gemrc = Self::default();
+        let Some(mapping) = yaml.as_mapping() else {
+            return Ok(gemrc);
+        };
+
+        for (key, value) in mapping {
+            let Some(key) = key.as_str() else {
+                continue;
+            };
+            match key.trim_start_matches(':') {
+                "sources" => {
+                    gemrc.sources = value
+                        .as_sequence()
+                        .map(|sources| {
+                            sources
+                                .iter()
+                                .filter_map(|source| source.as_str().map(str::to_string))
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                }
+                "backtrace" => gemrc.backtrace = value.as_bool(),
+                "verbose" => gemrc.verbose = value.as_bool(),
+                "http_proxy" => {
+                    gemrc.http_proxy = value
+                        .as_str()
+                        .filter(|proxy| !proxy.is_empty() && *proxy != ":no_proxy")
+                        .map(str::to_string);
+                }
+                command => {
+                    if let Some(args) = value.as_str() {
+                        gemrc.args.insert(
+                            command.to_string(),
+                            args.split_whitespace().map(str::to_string).collect(),
+                        );
+                    }
+                }
+            }
+        }
+        Ok(gemrc)
+    }
+
+    /// Default arguments for `command` (like `install`), `gem:` ones first
+    #[must_use]
+    pub fn command_args(&self, command: &str) -> Vec<String> {
+        ["gem", command]
+            .iter()
+            .filter_map(|key| self.args.get(*key))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// Every setting for `command` as argument groups, like `["--source", URL]`
+    ///
+    /// A flag followed by a value that does not start with `-` forms one
+    /// group, so callers can test and keep each group on its own.
+    #[must_use]
+    pub fn extra_args(&self, command: &str) -> Vec<Vec<String>> {
+        let mut groups = Vec::new();
+        if let Some(source) = self.sources.first() {
+            groups.push(vec!["--source".to_string(), source.clone()]);
+        }
+        if let Some(proxy) = &self.http_proxy {
+            groups.push(vec!["--http-proxy".to_string(), proxy.clone()]);
+        }
+        if self.backtrace == Some(true) {
+            groups.push(vec!["--backtrace".to_string()]);
+        }
+        if self.verbose == Some(true) {
+            groups.push(vec!["--verbose".to_string()]);
+        }
+
+        let mut args = self.command_args(command).into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let mut group = vec![arg];
+            if let Some(value) = args.next_if(|next| !next.starts_with('-')) {
+                group.push(value);
+            }
+            groups.push(group);
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GEMRC: &str = "\
+---
+:backtrace: true
+:sources:
+- https://gems.example.com/
+- https://rubygems.org/
+gem: --no-document --env-shebang
+install: --user-install -n /usr/local/bin
+http_proxy: http://proxy.example.com:8080
+";
+
+    #[test]
+    fn parse_reads_symbol_and_string_keys() {
+        let gemrc = Gemrc::parse(GEMRC).unwrap();
+        assert_eq!(gemrc.backtrace, Some(true));
+        assert_eq!(
+            gemrc.sources,
+            ["https://gems.example.com/", "https://rubygems.org/"]
+        );
+        assert_eq!(
+            gemrc.http_proxy.as_deref(),
+            Some("http://proxy.example.com:8080")
+        );
+        assert_eq!(
+            gemrc.command_args("install"),
+            [
+                "--no-document",
+                "--env-shebang",
+                "--user-install",
+                "-n",
+                "/usr/local/bin"
+            ]
+        );
+        assert_eq!(
+            gemrc.command_args("list"),
+            ["--no-document", "--env-shebang"]
+        );
+    }
+
+    #[test]
+    fn extra_args_groups_flags_with_values() {
+        let gemrc = Gemrc::parse(GEMRC).unwrap();
+        assert_eq!(
+            gemrc.extra_args("install"),
+            [
+                vec!["--source", "https://gems.example.com/"],
+                vec!["--http-proxy", "http://proxy.example.com:8080"],
+                vec!["--backtrace"],
+                vec!["--no-document"],
+                vec!["--env-shebang"],
+                vec!["--user-install"],
+                vec!["-n", "/usr/local/bin"],
+            ]
+        );
+    }
+
+    #[test]
+    fn load_skips_with_norc_and_rejects_missing_config_file() {
+        assert_eq!(
+            Gemrc::load(Some("/nonexistent/gemrc"), true).unwrap(),
+            Gemrc::default()
+        );
+        assert!(Gemrc::load(Some("/nonexistent/gemrc"), false).is_err());
+        assert!(Gemrc::parse("gem: [unclosed").is_err());
+    }
+}
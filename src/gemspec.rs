@@ -0,0 +1,1184 @@
+//! Gemspec metadata parsing
+//!
+//! Parses the YAML `Gem::Specification` that `RubyGems` stores in a gem's
+//! `metadata.gz` (and that Lode writes to `specifications/*.gemspec` on
+//! install). Psych tags such as `!ruby/object:Gem::Version` are unwrapped,
+//! so only the field values matter.
+//!
+//! Ruby-format specs (`specifications/default/*.gemspec` and `RubyGems`
+//! installs) are read for their literal fields only.
+//!
+//! Old gems were often built with Latin-1 author names or descriptions.
+//! Text that is not valid UTF-8 is read with replacement characters instead
+//! of failing the whole spec.
+
+use crate::marshal::{self, MarshalError};
+use crate::rubygems_client::GemMetadata;
+use flate2::Compression;
+use flate2::read::{GzDecoder, ZlibDecoder, ZlibEncoder};
+use serde::Serialize;
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tar::Archive;
+use thiserror::Error;
+
+/// `RubyGems` version recorded in generated quick specs
+const QUICK_SPEC_RUBYGEMS_VERSION: &str = "3.5.0";
+
+#[derive(Debug, Error)]
+pub enum GemspecError {
+    #[error("Failed to parse gemspec YAML: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("Gemspec is missing required field: {0}")]
+    MissingField(&'static str),
+
+    #[error("Gemspec is not a YAML mapping")]
+    NotAMapping,
+
+    #[error("metadata.gz not found in {0}")]
+    MetadataNotFound(PathBuf),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to read marshaled gemspec: {0}")]
+    Marshal(#[from] MarshalError),
+
+    #[error("Marshal data is not a Gem::Specification")]
+    NotASpecification,
+}
+
+/// Dependency type declared in a gemspec
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    Runtime,
+    Development,
+}
+
+/// A dependency declared in a gemspec
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GemspecDependency {
+    pub name: String,
+    /// Requirement strings such as `~> 1.2` or `>= 0`
+    pub requirements: Vec<String>,
+    pub kind: DependencyKind,
+}
+
+impl GemspecDependency {
+    /// Requirements joined the way `RubyGems` displays them (`>= 1.0, < 2`)
+    #[must_use]
+    pub fn requirement_string(&self) -> String {
+        if self.requirements.is_empty() {
+            ">= 0".to_string()
+        } else {
+            self.requirements.join(", ")
+        }
+    }
+}
+
+/// Parsed `Gem::Specification`
+///
+/// Serializes to a normalized, tag-free form for tooling that wants gemspec
+/// data without Ruby (`lode info --spec-json`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Gemspec {
+    pub name: String,
+    pub version: String,
+    /// Platform (`ruby` for pure Ruby gems)
+    pub platform: String,
+    pub authors: Vec<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub licenses: Vec<String>,
+    pub require_paths: Vec<String>,
+    /// Directory holding executables (`bin` unless the gem says otherwise)
+    pub bindir: String,
+    /// Executable names, relative to `bindir`
+    pub executables: Vec<String>,
+    /// Extension build files such as `ext/foo/extconf.rb`
+    pub extensions: Vec<String>,
+    pub files: Vec<String>,
+    pub dependencies: Vec<GemspecDependency>,
+    /// Free-form `metadata` hash (`source_code_uri`, `funding_uri`, ...)
+    pub metadata: BTreeMap<String, String>,
+    pub required_ruby_version: Vec<String>,
+    pub required_rubygems_version: Vec<String>,
+    pub post_install_message: Option<String>,
+}
+
+impl Gemspec {
+    /// Parse a YAML gemspec as produced by `Gem::Specification#to_yaml`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the YAML is malformed or lacks a name or version.
+    pub fn parse(yaml: &str) -> Result<Self, GemspecError> {
+        let doc: Value = serde_yaml::from_str(yaml)?;
+        let map = untag(&doc).as_mapping().ok_or(GemspecError::NotAMapping)?;
+        let field = |key: &str| map.get(key).map(untag);
+
+        let name = field("name")
+            .and_then(scalar_string)
+            .ok_or(GemspecError::MissingField("name"))?;
+        let version = field("version")
+            .and_then(version_string)
+            .ok_or(GemspecError::MissingField("version"))?;
+
+        let dependencies = field("dependencies")
+            .and_then(Value::as_sequence)
+            .map(|deps| deps.iter().filter_map(parse_dependency).collect())
+            .unwrap_or_default();
+
+        let metadata = field("metadata")
+            .and_then(Value::as_mapping)
+            .map(|m| {
+                m.iter()
+                    .filter_map(|(k, v)| Some((scalar_string(k)?, scalar_string(untag(v))?)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            name,
+            version,
+            platform: field("platform")
+                .and_then(scalar_string)
+                .unwrap_or_else(|| "ruby".to_string()),
+            authors: field("authors").map(string_list).unwrap_or_default(),
+            summary: field("summary").and_then(scalar_string),
+            description: field("description").and_then(scalar_string),
+            homepage: field("homepage").and_then(scalar_string),
+            licenses: field("licenses").map(string_list).unwrap_or_default(),
+            require_paths: field("require_paths")
+                .map(string_list)
+                .filter(|paths| !paths.is_empty())
+                .unwrap_or_else(|| vec!["lib".to_string()]),
+            bindir: field("bindir")
+                .and_then(scalar_string)
+                .unwrap_or_else(|| "bin".to_string()),
+            executables: field("executables").map(string_list).unwrap_or_default(),
+            extensions: field("extensions").map(string_list).unwrap_or_default(),
+            files: field("files").map(string_list).unwrap_or_default(),
+            dependencies,
+            metadata,
+            required_ruby_version: field("required_ruby_version")
+                .map(requirement_list)
+                .unwrap_or_default(),
+            required_rubygems_version: field("required_rubygems_version")
+                .map(requirement_list)
+                .unwrap_or_default(),
+            post_install_message: field("post_install_message").and_then(scalar_string),
+        })
+    }
+
+    /// Parse a quick index gemspec (`quick/Marshal.4.8/<gem>.gemspec.rz`)
+    ///
+    /// The file is a deflated Marshal dump of a `Gem::Specification`, whose
+    /// `_dump` writes a nested dump of its fields in a fixed order. It carries
+    /// everything resolution needs, but no file lists or executables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data does not inflate, is not Marshal, or
+    /// does not hold a specification with a name and version.
+    pub fn from_quick_spec(deflated: &[u8]) -> Result<Self, GemspecError> {
+        let mut dump = Vec::new();
+        ZlibDecoder::new(deflated).read_to_end(&mut dump)?;
+        let marshal::Value::UserDefined { class, data } = marshal::load(&dump)? else {
+            return Err(GemspecError::NotASpecification);
+        };
+        if class != "Gem::Specification" {
+            return Err(GemspecError::NotASpecification);
+        }
+        let fields = marshal::load(&data)?;
+        let fields = fields.as_array().ok_or(GemspecError::NotASpecification)?;
+        let field = |index: usize| {
+            fields
+                .get(index)
+                .filter(|v| !matches!(v, marshal::Value::Nil))
+        };
+        let string = |index: usize| {
+            field(index)
+                .and_then(marshal::Value::as_bytes)
+                .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+        };
+        let strings = |index: usize| {
+            field(index)
+                .and_then(marshal::Value::as_array)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(marshal::Value::as_bytes)
+                .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let name = field(2)
+            .and_then(marshal::Value::as_str)
+            .ok_or(GemspecError::MissingField("name"))?
+            .to_string();
+        let version = field(3)
+            .and_then(marshal::Value::gem_version)
+            .ok_or(GemspecError::MissingField("version"))?
+            .to_string();
+
+        let dependencies = field(9)
+            .and_then(marshal::Value::as_array)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|dep| {
+                let name = dep.ivar("@name")?.as_str()?.to_string();
+                let requirements = dep
+                    .ivar("@requirement")
+                    .or_else(|| dep.ivar("@version_requirements"))
+                    .map(marshal::Value::gem_requirements)
+                    .unwrap_or_default();
+                let kind = match dep.ivar("@type").and_then(marshal::Value::as_str) {
+                    Some("development") => DependencyKind::Development,
+                    _ => DependencyKind::Runtime,
+                };
+                Some(GemspecDependency {
+                    name,
+                    requirements,
+                    kind,
+                })
+            })
+            .collect();
+
+        let metadata = match field(18) {
+            Some(marshal::Value::Hash { entries, .. }) => entries
+                .iter()
+                .filter_map(|(k, v)| Some((k.as_str()?.to_string(), v.as_str()?.to_string())))
+                .collect(),
+            _ => BTreeMap::new(),
+        };
+
+        // `@new_platform` is the normalized platform; the original may be a
+        // legacy string
+        let platform = field(16)
+            .or_else(|| field(8))
+            .and_then(marshal::Value::gem_platform)
+            .unwrap_or_else(|| "ruby".to_string());
+
+        Ok(Self {
+            name,
+            version,
+            platform,
+            authors: strings(12),
+            summary: string(5),
+            description: string(13),
+            homepage: string(14),
+            licenses: strings(17),
+            require_paths: vec!["lib".to_string()],
+            bindir: "bin".to_string(),
+            dependencies,
+            metadata,
+            required_ruby_version: field(6)
+                .map(marshal::Value::gem_requirements)
+                .unwrap_or_default(),
+            required_rubygems_version: field(7)
+                .map(marshal::Value::gem_requirements)
+                .unwrap_or_default(),
+            ..Self::default()
+        })
+    }
+
+    /// Deflated Marshal dump for `quick/Marshal.4.8/<full_name>.gemspec.rz`
+    ///
+    /// Writes the field array `Gem::Specification._load` expects, so
+    /// `RubyGems`, Bundler and [`Gemspec::from_quick_spec`] can all read it.
+    /// The spec carries no build date, so the reproducible-build default of
+    /// 1980-01-02 stands in.
+    ///
+    /// # Panics
+    ///
+    /// Never in practice: deflating into an in-memory buffer cannot fail.
+    #[must_use]
+    pub fn to_quick_spec(&self) -> Vec<u8> {
+        use marshal::Value as M;
+        let string = |s: &str| M::String(s.as_bytes().to_vec());
+        let optional = |s: &Option<String>| s.as_deref().map_or(M::Nil, string);
+        let strings = |list: &[String]| M::Array(list.iter().map(|s| string(s)).collect());
+
+        let dependencies = self
+            .dependencies
+            .iter()
+            .map(|dep| {
+                let kind = match dep.kind {
+                    DependencyKind::Runtime => "runtime",
+                    DependencyKind::Development => "development",
+                };
+                let ivars = BTreeMap::from([
+                    ("@name".to_string(), string(&dep.name)),
+                    ("@prerelease".to_string(), M::Bool(false)),
+                    (
+                        "@requirement".to_string(),
+                        marshal_requirement(&dep.requirements),
+                    ),
+                    ("@type".to_string(), M::Symbol(kind.to_string())),
+                ]);
+                M::Object {
+                    class: "Gem::Dependency".to_string(),
+                    ivars,
+                }
+            })
+            .collect();
+
+        let fields = M::Array(vec![
+            string(QUICK_SPEC_RUBYGEMS_VERSION),
+            M::Integer(4),
+            string(&self.name),
+            marshal_version(&self.version),
+            string("1980-01-02"),
+            optional(&self.summary),
+            marshal_requirement(&self.required_ruby_version),
+            marshal_requirement(&self.required_rubygems_version),
+            string(&self.platform),
+            M::Array(dependencies),
+            // rubyforge_project, long gone
+            string(""),
+            M::Nil,
+            strings(&self.authors),
+            optional(&self.description),
+            optional(&self.homepage),
+            M::Bool(true),
+            string(&self.platform),
+            strings(&self.licenses),
+            M::Hash {
+                entries: self
+                    .metadata
+                    .iter()
+                    .map(|(key, value)| (string(key), string(value)))
+                    .collect(),
+                default: None,
+            },
+        ]);
+        let spec = M::UserDefined {
+            class: "Gem::Specification".to_string(),
+            data: marshal::dump(&fields),
+        };
+
+        let mut deflated = Vec::new();
+        ZlibEncoder::new(marshal::dump(&spec).as_slice(), Compression::default())
+            .read_to_end(&mut deflated)
+            .expect("deflating into memory cannot fail");
+        deflated
+    }
+
+    /// Parse the literal assignments of a Ruby gemspec as written by
+    /// `Gem::Specification#to_ruby`, the format of installed and default
+    /// gem specs
+    ///
+    /// Only string and string-array values are read; fields computed by Ruby
+    /// code (requirements, dependencies, `metadata`) stay empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the spec assigns no name or version.
+    pub fn parse_ruby(source: &str) -> Result<Self, GemspecError> {
+        let mut spec = Self {
+            platform: "ruby".to_string(),
+            bindir: "bin".to_string(),
+            ..Self::default()
+        };
+
+        for line in source.lines() {
+            let Some((target, value)) = line.trim().split_once(" = ") else {
+                continue;
+            };
+            let Some((_, field)) = target.split_once('.') else {
+                continue;
+            };
+            let mut strings = ruby_string_literals(value);
+            let first = (!strings.is_empty()).then(|| strings.swap_remove(0));
+            match field {
+                "name" => spec.name = first.unwrap_or_default(),
+                "version" => spec.version = first.unwrap_or_default(),
+                "platform" => spec.platform = first.unwrap_or(spec.platform),
+                "bindir" => spec.bindir = first.unwrap_or(spec.bindir),
+                "summary" => spec.summary = first,
+                "description" => spec.description = first,
+                "homepage" => spec.homepage = first,
+                "post_install_message" => spec.post_install_message = first,
+                _ => {
+                    let list = first.into_iter().chain(strings).collect();
+                    match field {
+                        "authors" => spec.authors = list,
+                        "licenses" => spec.licenses = list,
+                        "require_paths" => spec.require_paths = list,
+                        "executables" => spec.executables = list,
+                        "extensions" => spec.extensions = list,
+                        "files" => spec.files = list,
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        if spec.name.is_empty() {
+            return Err(GemspecError::MissingField("name"));
+        }
+        if spec.version.is_empty() {
+            return Err(GemspecError::MissingField("version"));
+        }
+        if spec.require_paths.is_empty() {
+            spec.require_paths = vec!["lib".to_string()];
+        }
+        Ok(spec)
+    }
+
+    /// Load a YAML gemspec from disk
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Self, GemspecError> {
+        Self::parse(&spec_text(&fs::read(path)?))
+    }
+
+    /// Read the gemspec embedded in a `.gem` archive's `metadata.gz`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive cannot be read, has no metadata, or
+    /// the metadata cannot be parsed.
+    pub fn from_gem_file(gem_path: &Path) -> Result<Self, GemspecError> {
+        Self::from_gem_archive(fs::File::open(gem_path)?)?
+            .ok_or_else(|| GemspecError::MetadataNotFound(gem_path.to_path_buf()))
+    }
+
+    /// Read the gemspec embedded in a `.gem` archive held in memory
+    ///
+    /// `name` identifies the archive in errors, usually its file name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is malformed, has no metadata, or the
+    /// metadata cannot be parsed.
+    pub fn from_gem_bytes(bytes: &[u8], name: &str) -> Result<Self, GemspecError> {
+        Self::from_gem_archive(bytes)?.ok_or_else(|| GemspecError::MetadataNotFound(name.into()))
+    }
+
+    /// The gemspec in a `.gem` tar stream, or `None` if it has no metadata
+    fn from_gem_archive(reader: impl Read) -> Result<Option<Self>, GemspecError> {
+        let mut archive = Archive::new(reader);
+
+        for entry in archive.entries()? {
+            let entry = entry?;
+            if entry.path()?.to_str() == Some("metadata.gz") {
+                let mut yaml = Vec::new();
+                GzDecoder::new(entry).read_to_end(&mut yaml)?;
+                return Self::parse(&spec_text(&yaml)).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Location of the installed gemspec for a gem directory
+    ///
+    /// Gems live in `<root>/gems/<full_name>` with their spec at
+    /// `<root>/specifications/<full_name>.gemspec`.
+    #[must_use]
+    pub fn spec_path_for_gem_dir(gem_dir: &Path) -> Option<PathBuf> {
+        let mut spec_name = gem_dir.file_name()?.to_os_string();
+        spec_name.push(".gemspec");
+        let root = gem_dir.parent()?.parent()?;
+        Some(root.join("specifications").join(spec_name))
+    }
+
+    /// Load the installed gemspec for a gem directory, if it is YAML
+    ///
+    /// Returns `None` when there is no spec or it is a Ruby-format gemspec,
+    /// so callers can fall back to directory heuristics.
+    #[must_use]
+    pub fn load_for_gem_dir(gem_dir: &Path) -> Option<Self> {
+        let spec_path = Self::spec_path_for_gem_dir(gem_dir)?;
+        Self::load(&spec_path).ok()
+    }
+
+    /// Load the installed gemspec for a gem directory in either format
+    ///
+    /// `RubyGems` writes Ruby-format specs and lode writes YAML; a Ruby-format
+    /// spec only carries its literal fields (see [`Self::parse_ruby`]).
+    #[must_use]
+    pub fn load_installed(gem_dir: &Path) -> Option<Self> {
+        let source = fs::read_to_string(Self::spec_path_for_gem_dir(gem_dir)?).ok()?;
+        Self::parse(&source)
+            .or_else(|_| Self::parse_ruby(&source))
+            .ok()
+    }
+
+    /// Full gem name including platform (`nokogiri-1.16.0-x86_64-linux`)
+    #[must_use]
+    pub fn full_name(&self) -> String {
+        if self.platform.is_empty() || self.platform == "ruby" {
+            format!("{}-{}", self.name, self.version)
+        } else {
+            format!("{}-{}-{}", self.name, self.version, self.platform)
+        }
+    }
+
+    /// Declared executables as `(name, path)` pairs under `bindir`
+    ///
+    /// Only executables that actually exist in `gem_dir` are returned.
+    #[must_use]
+    pub fn executable_paths(&self, gem_dir: &Path) -> Vec<(String, PathBuf)> {
+        let bin_dir = gem_dir.join(&self.bindir);
+        self.executables
+            .iter()
+            .map(|exe| (exe.clone(), bin_dir.join(exe)))
+            .filter(|(_, path)| path.is_file())
+            .collect()
+    }
+
+    /// Runtime dependencies only
+    pub fn runtime_dependencies(&self) -> impl Iterator<Item = &GemspecDependency> {
+        self.dependencies
+            .iter()
+            .filter(|dep| dep.kind == DependencyKind::Runtime)
+    }
+}
+
+/// Build a spec from the `RubyGems` version API
+///
+/// The API omits file lists, executables, and the `metadata` hash, so those
+/// stay empty; prefer [`Gemspec::from_gem_file`] when the `.gem` is at hand.
+impl From<&GemMetadata> for Gemspec {
+    fn from(metadata: &GemMetadata) -> Self {
+        let dependencies = [
+            (&metadata.dependencies.runtime, DependencyKind::Runtime),
+            (
+                &metadata.dependencies.development,
+                DependencyKind::Development,
+            ),
+        ]
+        .into_iter()
+        .flat_map(|(deps, kind)| {
+            deps.iter().map(move |dep| GemspecDependency {
+                name: dep.name.clone(),
+                requirements: split_list(&dep.requirements),
+                kind,
+            })
+        })
+        .collect();
+
+        Self {
+            name: metadata.name.clone(),
+            version: metadata.version.clone(),
+            platform: if metadata.platform.is_empty() {
+                "ruby".to_string()
+            } else {
+                metadata.platform.clone()
+            },
+            authors: split_list(&metadata.authors),
+            summary: metadata.summary.clone().filter(|s| !s.is_empty()),
+            description: metadata.description.clone().filter(|s| !s.is_empty()),
+            homepage: metadata.homepage.clone().filter(|s| !s.is_empty()),
+            licenses: metadata.licenses.clone(),
+            require_paths: vec!["lib".to_string()],
+            bindir: "bin".to_string(),
+            dependencies,
+            post_install_message: metadata
+                .post_install_message
+                .clone()
+                .filter(|s| !s.is_empty()),
+            ..Self::default()
+        }
+    }
+}
+
+/// A `Gem::Version`, which dumps as `[version]`
+fn marshal_version(version: &str) -> marshal::Value {
+    marshal::Value::UserMarshal {
+        class: "Gem::Version".to_string(),
+        data: Box::new(marshal::Value::Array(vec![marshal::Value::String(
+            version.as_bytes().to_vec(),
+        )])),
+    }
+}
+
+/// A `Gem::Requirement` from strings like `~> 1.2`, which dumps as
+/// `[[[op, Gem::Version], ...]]`; no requirements means `>= 0`
+fn marshal_requirement(requirements: &[String]) -> marshal::Value {
+    let pairs = if requirements.is_empty() {
+        vec![(">=", "0")]
+    } else {
+        requirements
+            .iter()
+            .map(|requirement| {
+                let requirement = requirement.trim();
+                requirement
+                    .split_once(' ')
+                    .map_or(("=", requirement), |(op, version)| (op, version.trim()))
+            })
+            .collect()
+    };
+    let pairs = pairs
+        .into_iter()
+        .map(|(op, version)| {
+            marshal::Value::Array(vec![
+                marshal::Value::String(op.as_bytes().to_vec()),
+                marshal_version(version),
+            ])
+        })
+        .collect();
+    marshal::Value::UserMarshal {
+        class: "Gem::Requirement".to_string(),
+        data: Box::new(marshal::Value::Array(vec![marshal::Value::Array(pairs)])),
+    }
+}
+
+/// Split a comma-separated API field (`"a, b"`) into its items
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// String literals in a line of Ruby, in order (`["a".freeze, 'b']`)
+fn ruby_string_literals(code: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut chars = code.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' && c != '\'' {
+            continue;
+        }
+        let quote = c;
+        let mut string = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('n') if quote == '"' => string.push('\n'),
+                    Some('t') if quote == '"' => string.push('\t'),
+                    Some(escaped) => string.push(escaped),
+                    None => break,
+                },
+                c if c == quote => break,
+                c => string.push(c),
+            }
+        }
+        strings.push(string);
+    }
+    strings
+}
+
+/// Strip any Psych tags (`!ruby/object:...`) from a value
+fn untag(value: &Value) -> &Value {
+    match value {
+        Value::Tagged(tagged) => untag(&tagged.value),
+        other => other,
+    }
+}
+
+/// Render a YAML scalar as a string, treating `null` and `""` as absent
+fn scalar_string(value: &Value) -> Option<String> {
+    match untag(value) {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Version values are `Gem::Version` objects with a nested `version` key
+fn version_string(value: &Value) -> Option<String> {
+    match untag(value) {
+        Value::Mapping(map) => map.get("version").and_then(scalar_string),
+        other => scalar_string(other),
+    }
+}
+
+fn string_list(value: &Value) -> Vec<String> {
+    match untag(value) {
+        Value::Sequence(items) => items.iter().filter_map(scalar_string).collect(),
+        other => scalar_string(other).into_iter().collect(),
+    }
+}
+
+/// Flatten a `Gem::Requirement` into strings like `~> 1.2`
+fn requirement_list(value: &Value) -> Vec<String> {
+    let Some(requirements) = untag(value)
+        .as_mapping()
+        .and_then(|m| m.get("requirements"))
+        .and_then(|r| untag(r).as_sequence())
+    else {
+        return Vec::new();
+    };
+
+    requirements
+        .iter()
+        .filter_map(|pair| {
+            let pair = untag(pair).as_sequence()?;
+            let op = scalar_string(pair.first()?)?;
+            let version = version_string(pair.get(1)?)?;
+            Some(format!("{op} {version}"))
+        })
+        .collect()
+}
+
+fn parse_dependency(value: &Value) -> Option<GemspecDependency> {
+    let map = untag(value).as_mapping()?;
+    let name = map.get("name").and_then(scalar_string)?;
+
+    // Older specs store the requirement under `version_requirements`
+    let requirements = map
+        .get("requirement")
+        .or_else(|| map.get("version_requirements"))
+        .map(requirement_list)
+        .unwrap_or_default();
+
+    let kind = match map.get("type").and_then(scalar_string).as_deref() {
+        Some(":development" | "development") => DependencyKind::Development,
+        _ => DependencyKind::Runtime,
+    };
+
+    Some(GemspecDependency {
+        name,
+        requirements,
+        kind,
+    })
+}
+
+/// List files in a gem's `bin/` directory as `(name, path)` pairs
+///
+/// Used for gems installed without a readable gemspec, where the declared
+/// executables are unknown. A missing directory yields an empty list.
+///
+/// # Errors
+///
+/// Returns an error if the directory exists but cannot be read.
+pub fn scan_bin_dir(bin_dir: &Path) -> std::io::Result<Vec<(String, PathBuf)>> {
+    if !bin_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut executables = Vec::new();
+    for entry in fs::read_dir(bin_dir)? {
+        let entry = entry?;
+        executables.push((
+            entry.file_name().to_string_lossy().to_string(),
+            entry.path(),
+        ));
+    }
+    Ok(executables)
+}
+
+/// Spec text read leniently: invalid UTF-8 becomes U+FFFD, as do characters
+/// YAML refuses or reads as line breaks (C1 controls, noncharacters and
+/// line separators), which Latin-1 bytes can happen to decode to
+fn spec_text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .chars()
+        .map(|c| {
+            if (c.is_control() && !matches!(c, '\t' | '\n' | '\r'))
+                || matches!(c, '\u{2028}' | '\u{2029}' | '\u{fffe}' | '\u{ffff}')
+            {
+                char::REPLACEMENT_CHARACTER
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "Tests can panic")]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const RAKE_SPEC: &str = r#"--- !ruby/object:Gem::Specification
+name: rake
+version: !ruby/object:Gem::Version
+  version: 13.2.1
+platform: ruby
+authors:
+- Hiroshi SHIBATA
+- Eric Hodel
+bindir: exe
+cert_chain: []
+date: 2024-04-05 00:00:00.000000000 Z
+dependencies: []
+description: |
+  Rake is a Make-like program implemented in Ruby.
+executables:
+- rake
+extensions: []
+files:
+- exe/rake
+- lib/rake.rb
+homepage: https://github.com/ruby/rake
+licenses:
+- MIT
+metadata:
+  bug_tracker_uri: https://github.com/ruby/rake/issues
+  source_code_uri: https://github.com/ruby/rake/tree/v13.2.1
+require_paths:
+- lib
+required_ruby_version: !ruby/object:Gem::Requirement
+  requirements:
+  - - ">="
+    - !ruby/object:Gem::Version
+      version: '2.3'
+required_rubygems_version: !ruby/object:Gem::Requirement
+  requirements:
+  - - ">="
+    - !ruby/object:Gem::Version
+      version: '1.3.2'
+summary: Rake is a Make-like program implemented in Ruby
+"#;
+
+    const NOKOGIRI_SPEC: &str = r#"--- !ruby/object:Gem::Specification
+name: nokogiri
+version: !ruby/object:Gem::Version
+  version: 1.16.0
+platform: ruby
+dependencies:
+- !ruby/object:Gem::Dependency
+  name: racc
+  requirement: !ruby/object:Gem::Requirement
+    requirements:
+    - - "~>"
+      - !ruby/object:Gem::Version
+        version: '1.4'
+  type: :runtime
+  prerelease: false
+  version_requirements: !ruby/object:Gem::Requirement
+    requirements:
+    - - "~>"
+      - !ruby/object:Gem::Version
+        version: '1.4'
+- !ruby/object:Gem::Dependency
+  name: mini_portile2
+  requirement: !ruby/object:Gem::Requirement
+    requirements:
+    - - ">="
+      - !ruby/object:Gem::Version
+        version: 2.8.2
+    - - "<"
+      - !ruby/object:Gem::Version
+        version: '3'
+  type: :development
+  prerelease: false
+executables:
+- nokogiri
+extensions:
+- ext/nokogiri/extconf.rb
+require_paths:
+- lib
+"#;
+
+    #[test]
+    fn parse_basic_fields() {
+        let spec = Gemspec::parse(RAKE_SPEC).unwrap();
+        assert_eq!(spec.name, "rake");
+        assert_eq!(spec.version, "13.2.1");
+        assert_eq!(spec.platform, "ruby");
+        assert_eq!(spec.authors, vec!["Hiroshi SHIBATA", "Eric Hodel"]);
+        assert_eq!(spec.bindir, "exe");
+        assert_eq!(spec.executables, vec!["rake"]);
+        assert!(spec.extensions.is_empty());
+        assert_eq!(spec.licenses, vec!["MIT"]);
+        assert_eq!(spec.require_paths, vec!["lib"]);
+        assert_eq!(
+            spec.homepage.as_deref(),
+            Some("https://github.com/ruby/rake")
+        );
+        assert_eq!(
+            spec.metadata.get("source_code_uri").map(String::as_str),
+            Some("https://github.com/ruby/rake/tree/v13.2.1")
+        );
+        assert_eq!(spec.required_ruby_version, vec![">= 2.3"]);
+        assert_eq!(spec.full_name(), "rake-13.2.1");
+    }
+
+    #[test]
+    fn parse_dependencies_and_extensions() {
+        let spec = Gemspec::parse(NOKOGIRI_SPEC).unwrap();
+        assert_eq!(spec.extensions, vec!["ext/nokogiri/extconf.rb"]);
+        assert_eq!(spec.dependencies.len(), 2);
+
+        let runtime: Vec<_> = spec.runtime_dependencies().collect();
+        assert_eq!(runtime.len(), 1);
+        let racc = runtime.first().unwrap();
+        assert_eq!(racc.name, "racc");
+        assert_eq!(racc.requirement_string(), "~> 1.4");
+
+        let dev = spec.dependencies.get(1).unwrap();
+        assert_eq!(dev.kind, DependencyKind::Development);
+        assert_eq!(dev.requirement_string(), ">= 2.8.2, < 3");
+    }
+
+    #[test]
+    fn parse_defaults() {
+        let spec = Gemspec::parse("name: tiny\nversion: '1.0'\n").unwrap();
+        assert_eq!(spec.bindir, "bin");
+        assert_eq!(spec.require_paths, vec!["lib"]);
+        assert_eq!(spec.platform, "ruby");
+    }
+
+    #[test]
+    fn parse_rejects_missing_version() {
+        assert!(matches!(
+            Gemspec::parse("name: tiny\n"),
+            Err(GemspecError::MissingField("version"))
+        ));
+    }
+
+    #[test]
+    fn parse_rejects_ruby_gemspec() {
+        let ruby = "Gem::Specification.new do |s|\n  s.name = \"tiny\"\nend\n";
+        assert!(Gemspec::parse(ruby).is_err());
+    }
+
+    #[test]
+    fn parse_ruby_reads_literal_fields() {
+        let source = r#"# -*- encoding: utf-8 -*-
+# stub: json 2.7.1 ruby lib
+
+Gem::Specification.new do |s|
+  s.name = "json".freeze
+  s.version = "2.7.1".freeze
+
+  s.required_rubygems_version = Gem::Requirement.new(">= 0".freeze) if s.respond_to? :required_rubygems_version=
+  s.require_paths = ["lib".freeze]
+  s.authors = ["Florian Frank".freeze, "David \"Dave\" Smith".freeze]
+  s.files = ["json.rb".freeze, "json/add/core.rb".freeze, "json/ext/parser.so".freeze]
+  s.summary = "JSON Implementation for Ruby".freeze
+end
+"#;
+        let spec = Gemspec::parse_ruby(source).unwrap();
+        assert_eq!(spec.full_name(), "json-2.7.1");
+        assert_eq!(spec.require_paths, vec!["lib"]);
+        assert_eq!(
+            spec.authors.get(1).map(String::as_str),
+            Some("David \"Dave\" Smith")
+        );
+        assert_eq!(spec.files.len(), 3);
+        assert_eq!(
+            spec.summary.as_deref(),
+            Some("JSON Implementation for Ruby")
+        );
+        assert!(spec.dependencies.is_empty());
+
+        assert!(matches!(
+            Gemspec::parse_ruby("Gem::Specification.new do |s|\nend\n"),
+            Err(GemspecError::MissingField("name"))
+        ));
+    }
+
+    #[test]
+    fn from_quick_spec_reads_marshaled_specs() {
+        let quick = crate::marshal::encode::quick_spec("widget", "1.2.0", &[("rack", "~> 3.0")]);
+        let spec = Gemspec::from_quick_spec(&quick).unwrap();
+
+        assert_eq!(spec.full_name(), "widget-1.2.0");
+        assert_eq!(spec.summary.as_deref(), Some("A widget"));
+        assert_eq!(spec.authors, vec!["Ann Author"]);
+        assert_eq!(spec.licenses, vec!["MIT"]);
+        assert_eq!(spec.required_ruby_version, vec![">= 3.1"]);
+        assert_eq!(
+            spec.dependencies,
+            vec![GemspecDependency {
+                name: "rack".to_string(),
+                requirements: vec!["~> 3.0".to_string()],
+                kind: DependencyKind::Runtime,
+            }]
+        );
+
+        assert!(matches!(
+            Gemspec::from_quick_spec(b"not deflated"),
+            Err(GemspecError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn quick_specs_round_trip() {
+        let mut spec = Gemspec::parse(RAKE_SPEC).unwrap();
+        spec.platform = "x86_64-linux".to_string();
+        spec.dependencies = vec![GemspecDependency {
+            name: "rack".to_string(),
+            requirements: vec![">= 2.0".to_string(), "< 4".to_string()],
+            kind: DependencyKind::Development,
+        }];
+        spec.metadata.insert(
+            "source_code_uri".to_string(),
+            "https://example.com".to_string(),
+        );
+
+        let read = Gemspec::from_quick_spec(&spec.to_quick_spec()).unwrap();
+        assert_eq!(read.full_name(), "rake-13.2.1-x86_64-linux");
+        assert_eq!(read.dependencies, spec.dependencies);
+        assert_eq!(read.metadata, spec.metadata);
+        assert_eq!(read.authors, spec.authors);
+        assert_eq!(read.summary, spec.summary);
+        assert_eq!(read.required_ruby_version, spec.required_ruby_version);
+        assert_eq!(read.required_rubygems_version, vec![">= 1.3.2"]);
+    }
+
+    #[test]
+    fn executable_paths_use_bindir() {
+        let temp = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("exe")).unwrap();
+        fs::create_dir_all(temp.path().join("bin")).unwrap();
+        fs::write(temp.path().join("exe/rake"), "").unwrap();
+        fs::write(temp.path().join("bin/setup"), "").unwrap();
+
+        let spec = Gemspec::parse(RAKE_SPEC).unwrap();
+        let exes = spec.executable_paths(temp.path());
+
+        assert_eq!(
+            exes,
+            vec![("rake".to_string(), temp.path().join("exe/rake"))]
+        );
+    }
+
+    #[test]
+    fn scan_bin_dir_lists_files_or_nothing() {
+        let temp = tempfile::TempDir::new().unwrap();
+        assert!(scan_bin_dir(&temp.path().join("bin")).unwrap().is_empty());
+
+        fs::create_dir_all(temp.path().join("bin")).unwrap();
+        fs::write(temp.path().join("bin/setup"), "").unwrap();
+        assert_eq!(
+            scan_bin_dir(&temp.path().join("bin")).unwrap(),
+            vec![("setup".to_string(), temp.path().join("bin/setup"))]
+        );
+    }
+
+    #[test]
+    fn full_name_with_platform() {
+        let spec = Gemspec {
+            name: "nokogiri".to_string(),
+            version: "1.16.0".to_string(),
+            platform: "x86_64-linux".to_string(),
+            ..Gemspec::default()
+        };
+        assert_eq!(spec.full_name(), "nokogiri-1.16.0-x86_64-linux");
+    }
+
+    #[test]
+    fn load_for_gem_dir() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let gem_dir = temp.path().join("gems").join("rake-13.2.1");
+        fs::create_dir_all(&gem_dir).unwrap();
+        fs::create_dir_all(temp.path().join("specifications")).unwrap();
+        fs::write(
+            temp.path()
+                .join("specifications")
+                .join("rake-13.2.1.gemspec"),
+            RAKE_SPEC,
+        )
+        .unwrap();
+
+        let spec = Gemspec::load_for_gem_dir(&gem_dir).unwrap();
+        assert_eq!(spec.name, "rake");
+        assert!(Gemspec::load_for_gem_dir(&temp.path().join("gems").join("missing-1.0")).is_none());
+    }
+
+    #[test]
+    fn from_gem_file_reads_metadata() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let gem_path = temp.path().join("rake-13.2.1.gem");
+
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(RAKE_SPEC.as_bytes()).unwrap();
+        let metadata = gz.finish().unwrap();
+
+        let mut builder = tar::Builder::new(fs::File::create(&gem_path).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(metadata.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "metadata.gz", metadata.as_slice())
+            .unwrap();
+        builder.finish().unwrap();
+
+        let spec = Gemspec::from_gem_file(&gem_path).unwrap();
+        assert_eq!(spec.version, "13.2.1");
+    }
+
+    /// `.gem` archive bytes holding only `metadata.gz` with `yaml`
+    fn gem_with_metadata(yaml: &[u8]) -> Vec<u8> {
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(yaml).unwrap();
+        let metadata = gz.finish().unwrap();
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(metadata.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "metadata.gz", metadata.as_slice())
+            .unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn metadata_in_legacy_encodings_still_parses(
+            author in proptest::collection::vec(
+                proptest::prop_oneof![b'a'..=b'z', 0x80u8..=0xff],
+                1..40,
+            )
+        ) {
+            let mut yaml = b"--- !ruby/object:Gem::Specification\nname: demo\nversion: !ruby/object:Gem::Version\n  version: 1.0.0\nauthors:\n- '".to_vec();
+            yaml.extend_from_slice(&author);
+            yaml.extend_from_slice(b"'\n");
+
+            let spec = Gemspec::from_gem_bytes(&gem_with_metadata(&yaml), "demo-1.0.0.gem")
+                .unwrap();
+            proptest::prop_assert_eq!(spec.name, "demo");
+            proptest::prop_assert_eq!(&spec.authors, &vec![spec_text(&author)]);
+            let letters: Vec<u8> = spec.authors.concat().bytes().filter(u8::is_ascii_lowercase).collect();
+            let expected: Vec<u8> = author.iter().copied().filter(u8::is_ascii_lowercase).collect();
+            proptest::prop_assert_eq!(letters, expected);
+        }
+    }
+
+    #[test]
+    fn from_api_metadata_normalizes_lists() {
+        let metadata: GemMetadata = serde_json::from_str(
+            r#"{
+                "name": "rack-test",
+                "number": "2.1.0",
+                "platform": "ruby",
+                "authors": "Josh Peek, Bryan Helmkamp",
+                "description": null,
+                "summary": "Simple testing API",
+                "homepage_uri": "https://github.com/rack/rack-test",
+                "licenses": ["MIT"],
+                "dependencies": {
+                    "runtime": [{"name": "rack", "requirements": ">= 1.3"}],
+                    "development": [{"name": "rake", "requirements": ">= 0, < 14"}]
+                },
+                "post_install_message": null
+            }"#,
+        )
+        .unwrap();
+
+        let spec = Gemspec::from(&metadata);
+        assert_eq!(spec.version, "2.1.0");
+        assert_eq!(spec.authors, vec!["Josh Peek", "Bryan Helmkamp"]);
+        assert_eq!(spec.require_paths, vec!["lib"]);
+        assert_eq!(
+            spec.dependencies.get(1).map(|dep| dep.requirements.clone()),
+            Some(vec![">= 0".to_string(), "< 14".to_string()])
+        );
+
+        let json = serde_json::to_value(&spec).unwrap();
+        let kind = |pointer| json.pointer(pointer).and_then(serde_json::Value::as_str);
+        assert_eq!(kind("/dependencies/0/kind"), Some("runtime"));
+        assert_eq!(kind("/dependencies/1/kind"), Some("development"));
+    }
+}
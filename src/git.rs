@@ -3,6 +3,7 @@
 //! Handles cloning and checking out git repositories for gems. Git gems are
 //! sourced from git repositories instead of RubyGems.org.
 
+use crate::insecure_source::{InsecureSourceError, InsecureSourcePolicy};
 use anyhow::{Context, Result};
 use git2::{Repository, build::CheckoutBuilder};
 use std::path::{Path, PathBuf};
@@ -25,8 +26,30 @@ pub enum GitError {
         source: git2::Error,
     },
 
+    #[error("Failed to resolve {reference} in {repo}: {source}")]
+    ResolveError {
+        repo: String,
+        reference: String,
+        #[source]
+        source: git2::Error,
+    },
+
+    #[error(transparent)]
+    InsecureSource(#[from] InsecureSourceError),
+
     #[error("Repository not found at {path}")]
     RepositoryNotFound { path: String },
+
+    #[error("Failed to commit {path} to {branch}: {source}")]
+    CommitError {
+        path: String,
+        branch: String,
+        #[source]
+        source: git2::Error,
+    },
+
+    #[error("Cannot use local override for {gem}: {reason}")]
+    LocalOverride { gem: String, reason: String },
 }
 
 /// Manages git operations for git gem sources
@@ -34,6 +57,8 @@ pub enum GitError {
 pub struct GitManager {
     /// Cache directory for git repositories
     cache_dir: PathBuf,
+    /// Which `http://` and `git://` remotes may be fetched
+    source_policy: InsecureSourcePolicy,
 }
 
 impl GitManager {
@@ -44,7 +69,10 @@ impl GitManager {
     /// Returns an error if the cache directory cannot be created.
     pub fn new(cache_dir: PathBuf) -> Result<Self> {
         std::fs::create_dir_all(&cache_dir).context("Failed to create git cache directory")?;
-        Ok(Self { cache_dir })
+        Ok(Self {
+            cache_dir,
+            source_policy: InsecureSourcePolicy::load(),
+        })
     }
 
     /// Clone or update a git repository and checkout a specific revision
@@ -63,35 +91,8 @@ impl GitManager {
         repository_url: &str,
         revision: &str,
     ) -> Result<PathBuf, GitError> {
-        let repo_name = Self::repo_name_from_url(repository_url);
-        let repo_path = self.cache_dir.join(&repo_name);
-
-        let repo = if repo_path.exists() {
-            Repository::open(&repo_path).map_err(|e| GitError::CloneError {
-                repo: repository_url.to_string(),
-                source: e,
-            })?
-        } else {
-            Repository::clone(repository_url, &repo_path).map_err(|e| GitError::CloneError {
-                repo: repository_url.to_string(),
-                source: e,
-            })?
-        };
-
-        let mut remote = repo
-            .find_remote("origin")
-            .or_else(|_| repo.remote_anonymous(repository_url))
-            .map_err(|e| GitError::CloneError {
-                repo: repository_url.to_string(),
-                source: e,
-            })?;
-
-        remote
-            .fetch(&["refs/heads/*:refs/heads/*"], None, None)
-            .map_err(|e| GitError::CloneError {
-                repo: repository_url.to_string(),
-                source: e,
-            })?;
+        crate::debug!("checking out {repository_url} at {revision}");
+        let (repo, repo_path) = self.open_and_fetch(repository_url)?;
 
         let oid = git2::Oid::from_str(revision).map_err(|e| GitError::CheckoutError {
             repo: repository_url.to_string(),
@@ -122,6 +123,137 @@ impl GitManager {
         Ok(repo_path)
     }
 
+    /// Fetch a repository and resolve a branch or tag to a commit SHA
+    ///
+    /// With neither `branch` nor `tag`, the remote's default branch (`HEAD`)
+    /// is used. Used when re-locking a git source to pick up new commits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if fetching fails or the reference does not exist.
+    pub fn resolve_revision(
+        &self,
+        repository_url: &str,
+        branch: Option<&str>,
+        tag: Option<&str>,
+    ) -> Result<String, GitError> {
+        let (repo, _) = self.open_and_fetch(repository_url)?;
+
+        let reference = match (tag, branch) {
+            (Some(tag), _) => format!("refs/tags/{tag}"),
+            (None, Some(branch)) => format!("refs/heads/{branch}"),
+            (None, None) => Self::default_branch_ref(&repo),
+        };
+
+        let commit = repo
+            .revparse_single(&reference)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|e| GitError::ResolveError {
+                repo: repository_url.to_string(),
+                reference: reference.clone(),
+                source: e,
+            })?;
+
+        Ok(commit.id().to_string())
+    }
+
+    /// Reference name of the remote's default branch
+    ///
+    /// `HEAD` in the cache may be detached at a previously locked revision,
+    /// so follow `origin/HEAD` instead and fall back to `main`/`master`.
+    fn default_branch_ref(repo: &Repository) -> String {
+        repo.find_reference("refs/remotes/origin/HEAD")
+            .ok()
+            .and_then(|r| r.symbolic_target().map(str::to_string))
+            .and_then(|target| {
+                target
+                    .strip_prefix("refs/remotes/origin/")
+                    .map(|branch| format!("refs/heads/{branch}"))
+            })
+            .or_else(|| {
+                ["refs/heads/main", "refs/heads/master"]
+                    .into_iter()
+                    .find(|name| repo.find_reference(name).is_ok())
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| "HEAD".to_string())
+    }
+
+    /// Open (or clone) the cached repository and fetch branches and tags
+    fn open_and_fetch(&self, repository_url: &str) -> Result<(Repository, PathBuf), GitError> {
+        self.source_policy.check(repository_url)?;
+        let repo_name = Self::repo_name_from_url(repository_url);
+        let repo_path = self.cache_dir.join(&repo_name);
+
+        let repo = if repo_path.exists() {
+            Repository::open(&repo_path).map_err(|e| GitError::CloneError {
+                repo: repository_url.to_string(),
+                source: e,
+            })?
+        } else {
+            Repository::clone(repository_url, &repo_path).map_err(|e| GitError::CloneError {
+                repo: repository_url.to_string(),
+                source: e,
+            })?
+        };
+
+        {
+            let mut remote = repo
+                .find_remote("origin")
+                .or_else(|_| repo.remote_anonymous(repository_url))
+                .map_err(|e| GitError::CloneError {
+                    repo: repository_url.to_string(),
+                    source: e,
+                })?;
+
+            remote
+                .fetch(
+                    &["refs/heads/*:refs/heads/*", "refs/tags/*:refs/tags/*"],
+                    None,
+                    None,
+                )
+                .map_err(|e| GitError::CloneError {
+                    repo: repository_url.to_string(),
+                    source: e,
+                })?;
+        }
+
+        Ok((repo, repo_path))
+    }
+
+    /// Read a gem's version from the gemspec in a checked-out repository
+    ///
+    /// Handles a literal `spec.version = "1.2.3"` and the common
+    /// `Name::VERSION` pattern backed by `lib/<name>/version.rb`.
+    #[must_use]
+    pub fn gemspec_version(repo_path: &Path, gem_name: &str) -> Option<String> {
+        let gemspec =
+            std::fs::read_to_string(repo_path.join(format!("{gem_name}.gemspec"))).ok()?;
+
+        let assignment = gemspec.lines().find_map(|line| {
+            let (lhs, rhs) = line.split_once('=')?;
+            lhs.trim()
+                .ends_with(".version")
+                .then(|| rhs.trim().to_string())
+        })?;
+
+        if let Some(version) = quoted_string(&assignment) {
+            return Some(version);
+        }
+
+        let version_rb = repo_path
+            .join("lib")
+            .join(gem_name.replace('-', "/"))
+            .join("version.rb");
+        std::fs::read_to_string(version_rb)
+            .ok()?
+            .lines()
+            .find_map(|line| {
+                let (lhs, rhs) = line.split_once('=')?;
+                (lhs.trim() == "VERSION").then(|| quoted_string(rhs.trim()))?
+            })
+    }
+
     /// Converts repository URL to safe directory name
     ///
     /// Example: `https://github.com/rails/rails` -> `github.com-rails-rails`
@@ -138,6 +270,185 @@ impl GitManager {
     pub fn cache_dir(&self) -> &Path {
         &self.cache_dir
     }
+
+    /// Commit checked out in the cached clone of `repository_url`, if it has
+    /// been cloned
+    #[must_use]
+    pub fn checked_out_revision(&self, repository_url: &str) -> Option<String> {
+        let repo_path = self
+            .cache_dir
+            .join(Self::repo_name_from_url(repository_url));
+        let repo = Repository::open(repo_path).ok()?;
+        let head = repo.head().ok()?.peel_to_commit().ok()?;
+        Some(head.id().to_string())
+    }
+
+    /// Whether `revision` is reachable from a branch or tag of the remote
+    ///
+    /// Fetches first, so a commit that only survives in the cache (say, after
+    /// a force-push) is not counted as reachable.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the repository cannot be fetched.
+    pub fn revision_reachable(
+        &self,
+        repository_url: &str,
+        revision: &str,
+    ) -> Result<bool, GitError> {
+        let (repo, _) = self.open_and_fetch(repository_url)?;
+        let Ok(commit) = repo
+            .revparse_single(revision)
+            .and_then(|object| object.peel_to_commit())
+        else {
+            return Ok(false);
+        };
+
+        let references = repo.references().map_err(|e| GitError::ResolveError {
+            repo: repository_url.to_string(),
+            reference: revision.to_string(),
+            source: e,
+        })?;
+        Ok(references
+            .flatten()
+            .filter(|reference| {
+                reference.name().is_some_and(|name| {
+                    name.starts_with("refs/heads/") || name.starts_with("refs/tags/")
+                })
+            })
+            .filter_map(|reference| reference.peel_to_commit().ok())
+            .any(|tip| {
+                tip.id() == commit.id()
+                    || repo
+                        .graph_descendant_of(tip.id(), commit.id())
+                        .unwrap_or(false)
+            }))
+    }
+}
+
+/// Verify a local checkout can stand in for a locked git gem
+///
+/// Mirrors Bundler's `local.GEM_NAME` rules: the path must be a git
+/// repository, and unless `check_branch` is false the Gemfile must declare a
+/// `branch` that matches the checkout's current branch. Returns the commit SHA
+/// currently checked out.
+///
+/// # Errors
+///
+/// Returns an error if the path is not a repository or the branch check fails.
+pub fn verify_local_override(
+    gem_name: &str,
+    path: &Path,
+    expected_branch: Option<&str>,
+    check_branch: bool,
+) -> Result<String, GitError> {
+    let override_error = |reason: String| GitError::LocalOverride {
+        gem: gem_name.to_string(),
+        reason,
+    };
+
+    let repo = Repository::open(path).map_err(|_| GitError::RepositoryNotFound {
+        path: path.display().to_string(),
+    })?;
+    let head = repo
+        .head()
+        .map_err(|e| override_error(format!("cannot read HEAD ({e})")))?;
+
+    if check_branch {
+        let Some(expected) = expected_branch else {
+            return Err(override_error(
+                ":branch is not specified in Gemfile".to_string(),
+            ));
+        };
+        let current = head.shorthand().filter(|_| head.is_branch());
+        if current != Some(expected) {
+            return Err(override_error(format!(
+                "checkout at {} is on {} but Gemfile specifies {expected}",
+                path.display(),
+                current.unwrap_or("a detached HEAD")
+            )));
+        }
+    }
+
+    head.peel_to_commit()
+        .map(|commit| commit.id().to_string())
+        .map_err(|e| override_error(format!("cannot resolve HEAD ({e})")))
+}
+
+/// Commit new contents for one file onto `branch`, on top of `HEAD`
+///
+/// The commit is built from `HEAD`'s tree with only `path` replaced, so the
+/// working tree, index and current branch are left alone. An existing
+/// `branch` is reset to the new commit. Returns the commit SHA.
+///
+/// # Errors
+///
+/// Returns an error if `path` is not inside a repository with a `HEAD`
+/// commit, or the commit cannot be written.
+pub fn commit_file_to_branch(
+    path: &Path,
+    contents: &[u8],
+    branch: &str,
+    message: &str,
+) -> Result<String, GitError> {
+    let not_found = || GitError::RepositoryNotFound {
+        path: path.display().to_string(),
+    };
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let repo = Repository::discover(dir).map_err(|_| not_found())?;
+    let commit_error = |source| GitError::CommitError {
+        path: path.display().to_string(),
+        branch: branch.to_string(),
+        source,
+    };
+
+    // Tree paths are relative to the work tree; canonicalize both sides so
+    // symlinked temp dirs and `..` segments compare equal
+    let workdir = repo
+        .workdir()
+        .and_then(|dir| dir.canonicalize().ok())
+        .ok_or_else(not_found)?;
+    let absolute = dir
+        .canonicalize()
+        .ok()
+        .zip(path.file_name())
+        .map(|(dir, name)| dir.join(name))
+        .ok_or_else(not_found)?;
+    let relative = absolute.strip_prefix(&workdir).map_err(|_| not_found())?;
+
+    let parent = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(commit_error)?;
+    let blob = repo.blob(contents).map_err(commit_error)?;
+    let base_tree = parent.tree().map_err(commit_error)?;
+    let tree_id = git2::build::TreeUpdateBuilder::new()
+        .upsert(relative, blob, git2::FileMode::Blob)
+        .create_updated(&repo, &base_tree)
+        .map_err(commit_error)?;
+    let tree = repo.find_tree(tree_id).map_err(commit_error)?;
+
+    let signature = repo
+        .signature()
+        .or_else(|_| git2::Signature::now("lode", "lode@localhost"))
+        .map_err(commit_error)?;
+    let commit = repo
+        .commit(None, &signature, &signature, message, &tree, &[&parent])
+        .map_err(commit_error)?;
+    let commit_object = repo.find_commit(commit).map_err(commit_error)?;
+    repo.branch(branch, &commit_object, true)
+        .map_err(commit_error)?;
+    Ok(commit.to_string())
+}
+
+/// Extract the contents of a leading single- or double-quoted string
+fn quoted_string(value: &str) -> Option<String> {
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let rest = value.get(1..)?;
+    rest.split(quote).next().map(str::to_string)
 }
 
 #[cfg(test)]
@@ -169,4 +480,80 @@ mod tests {
         assert!(manager.cache_dir().exists());
         Ok(())
     }
+
+    #[test]
+    fn gemspec_version_literal() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        std::fs::write(
+            temp_dir.path().join("mygem.gemspec"),
+            "Gem::Specification.new do |spec|\n  spec.name = \"mygem\"\n  spec.version = \"1.2.3\"\nend\n",
+        )?;
+        assert_eq!(
+            GitManager::gemspec_version(temp_dir.path(), "mygem").as_deref(),
+            Some("1.2.3")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn gemspec_version_constant() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        std::fs::write(
+            temp_dir.path().join("my-gem.gemspec"),
+            "Gem::Specification.new do |s|\n  s.version = My::Gem::VERSION\nend\n",
+        )?;
+        let version_dir = temp_dir.path().join("lib/my/gem");
+        std::fs::create_dir_all(&version_dir)?;
+        std::fs::write(
+            version_dir.join("version.rb"),
+            "module My\n  module Gem\n    VERSION = '0.4.0'\n  end\nend\n",
+        )?;
+        assert_eq!(
+            GitManager::gemspec_version(temp_dir.path(), "my-gem").as_deref(),
+            Some("0.4.0")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn verify_local_override_checks_branch() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let repo = Repository::init(temp_dir.path())?;
+        let signature = git2::Signature::now("Test", "test@example.com")?;
+        let tree_id = repo.index()?.write_tree()?;
+        let tree = repo.find_tree(tree_id)?;
+        let oid = repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])?;
+        let branch = repo.head()?.shorthand().unwrap_or_default().to_string();
+
+        let revision = verify_local_override("mygem", temp_dir.path(), Some(&branch), true)?;
+        assert_eq!(revision, oid.to_string());
+
+        assert!(verify_local_override("mygem", temp_dir.path(), Some("other"), true).is_err());
+        assert!(verify_local_override("mygem", temp_dir.path(), None, true).is_err());
+        assert!(verify_local_override("mygem", temp_dir.path(), Some("other"), false).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn commit_file_to_branch_leaves_worktree_alone() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let repo = Repository::init(temp_dir.path())?;
+        let lockfile = temp_dir.path().join("Gemfile.lock");
+        std::fs::write(&lockfile, "old\n")?;
+        let mut index = repo.index()?;
+        index.add_path(Path::new("Gemfile.lock"))?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let signature = git2::Signature::now("Test", "test@example.com")?;
+        let base = repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])?;
+
+        let sha = commit_file_to_branch(&lockfile, b"new\n", "lode/update/rack", "Bump rack")?;
+
+        let commit = repo.find_commit(git2::Oid::from_str(&sha)?)?;
+        assert_eq!(commit.parent_id(0)?, base);
+        let entry = commit.tree()?.get_path(Path::new("Gemfile.lock"))?;
+        assert_eq!(repo.find_blob(entry.id())?.content(), b"new\n");
+        assert_eq!(std::fs::read_to_string(&lockfile)?, "old\n");
+        assert_eq!(repo.head()?.peel_to_commit()?.id(), base);
+        Ok(())
+    }
 }
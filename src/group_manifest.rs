@@ -0,0 +1,276 @@
+//! Per-group load path manifests
+//!
+//! `lode install` writes `.lode/groups/<group>.paths` next to the Gemfile:
+//! the require paths of every gem a Gemfile group needs, its transitive
+//! dependencies included, one absolute path per line. A boot script can
+//! build `$LOAD_PATH` for `default` and `production` from two files
+//! without parsing the Gemfile or activating `RubyGems`, and
+//! `lode exec --only-group` reads the same files.
+
+use crate::gemfile::Gemfile;
+use crate::gemspec::Gemspec;
+use crate::lockfile::Lockfile;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Manifest directory, relative to the Gemfile's directory
+pub const MANIFEST_DIR: &str = ".lode/groups";
+
+/// Group of Gemfile gems declared outside any group
+pub const DEFAULT_GROUP: &str = "default";
+
+/// Gem names each Gemfile group needs, transitive dependencies included
+///
+/// Locked gems no Gemfile entry reaches (for example a lockfile left over
+/// from a removed gem) belong to no group.
+#[must_use]
+pub fn group_members(lockfile: &Lockfile, gemfile: &Gemfile) -> BTreeMap<String, BTreeSet<String>> {
+    let dependencies: BTreeMap<&str, Vec<&str>> = lockfile
+        .gems
+        .iter()
+        .map(|spec| {
+            (
+                spec.name.as_str(),
+                spec.dependencies
+                    .iter()
+                    .map(|dependency| dependency.name.as_str())
+                    .collect(),
+            )
+        })
+        .collect();
+
+    let mut members: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for gem in &gemfile.gems {
+        let groups = if gem.groups.is_empty() {
+            vec![DEFAULT_GROUP.to_string()]
+        } else {
+            gem.groups.clone()
+        };
+        for group in groups {
+            let names = members.entry(group).or_default();
+            let mut pending = vec![gem.name.as_str()];
+            while let Some(name) = pending.pop() {
+                if names.insert(name.to_string()) {
+                    pending.extend(dependencies.get(name).into_iter().flatten());
+                }
+            }
+        }
+    }
+    members
+}
+
+/// Load path entries for each group, from gems installed in `gems_dir`
+///
+/// Each gem contributes its gemspec's `require_paths` (`lib` when the
+/// gemspec is missing). Gems that are not installed are skipped.
+#[must_use]
+pub fn group_load_paths(
+    lockfile: &Lockfile,
+    gemfile: &Gemfile,
+    gems_dir: &Path,
+) -> BTreeMap<String, Vec<PathBuf>> {
+    let mut versions: BTreeMap<&str, &str> = BTreeMap::new();
+    for (name, version) in lockfile
+        .gems
+        .iter()
+        .map(|gem| (gem.name.as_str(), gem.version.as_str()))
+        .chain(
+            lockfile
+                .git_gems
+                .iter()
+                .map(|gem| (gem.name.as_str(), gem.version.as_str())),
+        )
+        .chain(
+            lockfile
+                .path_gems
+                .iter()
+                .map(|gem| (gem.name.as_str(), gem.version.as_str())),
+        )
+    {
+        versions.entry(name).or_insert(version);
+    }
+
+    group_members(lockfile, gemfile)
+        .into_iter()
+        .map(|(group, names)| {
+            let paths = names
+                .iter()
+                .filter_map(|name| {
+                    let version = versions.get(name.as_str())?;
+                    let gem_dir = gems_dir.join(format!("{name}-{version}"));
+                    gem_dir.is_dir().then_some(gem_dir)
+                })
+                .flat_map(|gem_dir| require_paths(&gem_dir))
+                .collect();
+            (group, paths)
+        })
+        .collect()
+}
+
+/// Load path entries of the gem installed in `gem_dir`
+///
+/// The gemspec's `require_paths`, or `lib` when the gemspec is missing.
+pub(crate) fn require_paths(gem_dir: &Path) -> Vec<PathBuf> {
+    Gemspec::load_for_gem_dir(gem_dir)
+        .map(|spec| spec.require_paths)
+        .filter(|paths| !paths.is_empty())
+        .unwrap_or_else(|| vec!["lib".to_string()])
+        .into_iter()
+        .map(|path| gem_dir.join(path))
+        .collect()
+}
+
+/// Write one `<group>.paths` file per group into `dir`, removing manifests
+/// for groups that no longer exist
+///
+/// # Errors
+///
+/// Returns an error if the directory or a manifest cannot be written.
+pub fn write(dir: &Path, groups: &BTreeMap<String, Vec<PathBuf>>) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        let stale = path.extension().is_some_and(|ext| ext == "paths")
+            && path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_none_or(|group| !groups.contains_key(group));
+        if stale {
+            fs::remove_file(&path)?;
+        }
+    }
+    for (group, paths) in groups {
+        let mut contents = String::new();
+        for path in paths {
+            contents.push_str(&path.to_string_lossy());
+            contents.push('\n');
+        }
+        fs::write(dir.join(format!("{group}.paths")), contents)?;
+    }
+    Ok(())
+}
+
+/// Load path entries for `groups`, in order and without duplicates
+///
+/// # Errors
+///
+/// Returns an error naming the group if its manifest cannot be read.
+pub fn read(dir: &Path, groups: &[String]) -> io::Result<Vec<PathBuf>> {
+    let mut seen = BTreeSet::new();
+    let mut paths = Vec::new();
+    for group in groups {
+        let manifest = dir.join(format!("{group}.paths"));
+        let contents = fs::read_to_string(&manifest).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("no manifest for group '{group}' ({})", manifest.display()),
+            )
+        })?;
+        for line in contents.lines().filter(|line| !line.is_empty()) {
+            if seen.insert(line.to_string()) {
+                paths.push(PathBuf::from(line));
+            }
+        }
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::{Dependency, GemSpec};
+
+    fn spec(name: &str, dependencies: &[&str]) -> GemSpec {
+        GemSpec::new(
+            name.to_string(),
+            "1.0.0".to_string(),
+            None,
+            dependencies
+                .iter()
+                .map(|dependency| Dependency {
+                    name: (*dependency).to_string(),
+                    requirement: ">= 0".to_string(),
+                })
+                .collect(),
+            Vec::new(),
+        )
+    }
+
+    fn fixture() -> (Lockfile, Gemfile) {
+        let mut lockfile = Lockfile::new();
+        lockfile.gems = vec![
+            spec("rails", &["rack"]),
+            spec("rack", &[]),
+            spec("rspec", &["diff-lcs"]),
+            spec("diff-lcs", &[]),
+            spec("pg", &[]),
+        ];
+        let gemfile = Gemfile::parse(
+            "source \"https://rubygems.org\"\n\
+             gem \"rails\"\n\
+             gem \"rspec\", group: :test\n\
+             gem \"pg\", groups: [:production, :staging]\n",
+        )
+        .expect("gemfile");
+        (lockfile, gemfile)
+    }
+
+    #[test]
+    fn groups_include_transitive_dependencies() {
+        let (lockfile, gemfile) = fixture();
+        let members = group_members(&lockfile, &gemfile);
+        let names = |group: &str| -> Vec<&str> {
+            members
+                .get(group)
+                .into_iter()
+                .flatten()
+                .map(String::as_str)
+                .collect()
+        };
+        assert_eq!(names("default"), vec!["rack", "rails"]);
+        assert_eq!(names("test"), vec!["diff-lcs", "rspec"]);
+        assert_eq!(names("production"), vec!["pg"]);
+        assert_eq!(names("staging"), vec!["pg"]);
+    }
+
+    #[test]
+    fn manifests_round_trip_per_group() -> io::Result<()> {
+        let (lockfile, gemfile) = fixture();
+        let temp = tempfile::tempdir()?;
+        let gems_dir = temp.path().join("gems");
+        for name in ["rails", "rack", "rspec", "pg"] {
+            fs::create_dir_all(gems_dir.join(format!("{name}-1.0.0/lib")))?;
+        }
+
+        let manifests = temp.path().join(MANIFEST_DIR);
+        fs::create_dir_all(&manifests)?;
+        fs::write(manifests.join("removed.paths"), "/old\n")?;
+        write(
+            &manifests,
+            &group_load_paths(&lockfile, &gemfile, &gems_dir),
+        )?;
+        assert!(!manifests.join("removed.paths").exists());
+
+        let paths = read(
+            &manifests,
+            &["default".to_string(), "production".to_string()],
+        )?;
+        assert_eq!(
+            paths,
+            vec![
+                gems_dir.join("rack-1.0.0/lib"),
+                gems_dir.join("rails-1.0.0/lib"),
+                gems_dir.join("pg-1.0.0/lib"),
+            ]
+        );
+        // diff-lcs is locked but not installed
+        assert_eq!(
+            read(&manifests, &["test".to_string()])?,
+            vec![gems_dir.join("rspec-1.0.0/lib")]
+        );
+        assert!(read(&manifests, &["missing".to_string()]).is_err());
+        Ok(())
+    }
+}
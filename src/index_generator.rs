@@ -0,0 +1,376 @@
+//! Gem server indexes for a directory of gems
+//!
+//! The counterpart of `gem generate_index`: from the `.gem` files in
+//! `DIR/gems/` it writes every index a gem server publishes, so any static
+//! file server (or `source "file://DIR"`) can serve them to `RubyGems`,
+//! Bundler and Lode:
+//!
+//! - `specs.4.8`, `latest_specs.4.8` and `prerelease_specs.4.8`, the Marshal
+//!   indexes, each also gzipped
+//! - `quick/Marshal.4.8/<gem>.gemspec.rz`, one deflated spec per gem
+//! - the compact index: `versions`, `names` and `info/<name>`
+//!
+//! Files are replaced atomically and indexes of gems that were removed from
+//! `gems/` are deleted, so regenerating a live mirror is safe.
+
+use crate::gem_utils::compare_versions;
+use crate::gemspec::{DependencyKind, Gemspec};
+use crate::marshal::{self, Value};
+use anyhow::{Context, Result};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Directory of quick gemspecs, relative to the index root
+const QUICK_DIR: &str = "quick/Marshal.4.8";
+
+/// What [`generate`] indexed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexSummary {
+    /// Distinct gem names
+    pub gems: usize,
+    /// Indexed archives, one per version and platform
+    pub versions: usize,
+    /// Archives skipped because their metadata could not be read
+    pub skipped: usize,
+}
+
+/// An archive and what the indexes record about it
+#[derive(Debug)]
+struct Entry {
+    spec: Gemspec,
+    sha256: String,
+}
+
+impl Entry {
+    fn prerelease(&self) -> bool {
+        self.spec.version.chars().any(|c| c.is_ascii_alphabetic())
+    }
+
+    /// `VERSION` or `VERSION-PLATFORM`, as the compact index lists it
+    fn compact_version(&self) -> String {
+        if self.spec.platform == "ruby" {
+            self.spec.version.clone()
+        } else {
+            format!("{}-{}", self.spec.version, self.spec.platform)
+        }
+    }
+}
+
+/// Write all indexes for the gems in `dir/gems/`
+///
+/// Archives whose metadata cannot be read are skipped with a warning.
+///
+/// # Errors
+///
+/// Returns an error if `dir/gems` cannot be read or an index file cannot be
+/// written.
+pub fn generate(dir: &Path) -> Result<IndexSummary> {
+    let gems_dir = dir.join("gems");
+    let mut archives: Vec<PathBuf> = fs::read_dir(&gems_dir)
+        .with_context(|| format!("Failed to read {}", gems_dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "gem"))
+        .collect();
+    archives.sort();
+
+    let mut summary = IndexSummary::default();
+    let mut entries = Vec::with_capacity(archives.len());
+    for path in &archives {
+        match read_entry(path) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => {
+                crate::warn!("Skipping {}: {e:#}", path.display());
+                summary.skipped += 1;
+            }
+        }
+    }
+    entries.sort_by(|a, b| {
+        a.spec
+            .name
+            .cmp(&b.spec.name)
+            .then_with(|| compare_versions(&a.spec.version, &b.spec.version))
+            .then_with(|| a.spec.platform.cmp(&b.spec.platform))
+    });
+
+    write_marshal_indexes(dir, &entries)?;
+    write_quick_specs(dir, &entries)?;
+    write_compact_index(dir, &entries)?;
+
+    summary.versions = entries.len();
+    summary.gems = entries
+        .iter()
+        .map(|entry| entry.spec.name.as_str())
+        .collect::<BTreeSet<_>>()
+        .len();
+    Ok(summary)
+}
+
+fn read_entry(path: &Path) -> Result<Entry> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let name = path
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    let spec = Gemspec::from_gem_bytes(&bytes, &name)?;
+    Ok(Entry {
+        spec,
+        sha256: format!("{:x}", Sha256::digest(&bytes)),
+    })
+}
+
+/// `specs`, `latest_specs` and `prerelease_specs`, each a Marshal array of
+/// `[name, Gem::Version, platform]`
+fn write_marshal_indexes(dir: &Path, entries: &[Entry]) -> Result<()> {
+    let (prerelease, released): (Vec<&Entry>, Vec<&Entry>) =
+        entries.iter().partition(|entry| entry.prerelease());
+
+    // Entries are sorted, so the last release per name and platform wins
+    let mut latest: BTreeMap<(&str, &str), &Entry> = BTreeMap::new();
+    for entry in &released {
+        latest.insert(
+            (entry.spec.name.as_str(), entry.spec.platform.as_str()),
+            *entry,
+        );
+    }
+    let latest: Vec<&Entry> = latest.into_values().collect();
+
+    for (name, list) in [
+        ("specs.4.8", &released),
+        ("latest_specs.4.8", &latest),
+        ("prerelease_specs.4.8", &prerelease),
+    ] {
+        let index = Value::Array(list.iter().map(|entry| spec_tuple(&entry.spec)).collect());
+        let data = marshal::dump(&index);
+        write_file(&dir.join(format!("{name}.gz")), &gzip(&data)?)?;
+        write_file(&dir.join(name), &data)?;
+    }
+    Ok(())
+}
+
+fn spec_tuple(spec: &Gemspec) -> Value {
+    Value::Array(vec![
+        Value::String(spec.name.as_bytes().to_vec()),
+        Value::UserMarshal {
+            class: "Gem::Version".to_string(),
+            data: Box::new(Value::Array(vec![Value::String(
+                spec.version.as_bytes().to_vec(),
+            )])),
+        },
+        Value::String(spec.platform.as_bytes().to_vec()),
+    ])
+}
+
+fn write_quick_specs(dir: &Path, entries: &[Entry]) -> Result<()> {
+    let quick = dir.join(QUICK_DIR);
+    let mut written = BTreeSet::new();
+    for entry in entries {
+        let filename = format!("{}.gemspec.rz", entry.spec.full_name());
+        write_file(&quick.join(&filename), &entry.spec.to_quick_spec())?;
+        written.insert(filename);
+    }
+    remove_stale(&quick, &written)
+}
+
+/// `names`, `versions` and one `info/<name>` per gem
+///
+/// Each `versions` line ends with the MD5 of the gem's `info` file, which
+/// Bundler uses to decide whether its cached copy is current.
+fn write_compact_index(dir: &Path, entries: &[Entry]) -> Result<()> {
+    let mut by_name: BTreeMap<&str, Vec<&Entry>> = BTreeMap::new();
+    for entry in entries {
+        by_name.entry(&entry.spec.name).or_default().push(entry);
+    }
+
+    let info_dir = dir.join("info");
+    let mut versions = format!(
+        "created_at: {}\n---\n",
+        chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    );
+    let mut names = String::from("---\n");
+    let mut written = BTreeSet::new();
+    for (name, entries) in &by_name {
+        let info = info_file(entries);
+        write_file(&info_dir.join(name), info.as_bytes())?;
+        written.insert((*name).to_string());
+
+        let listed: Vec<String> = entries
+            .iter()
+            .map(|entry| entry.compact_version())
+            .collect();
+        let _ = writeln!(
+            versions,
+            "{name} {} {:x}",
+            listed.join(","),
+            Md5::digest(info.as_bytes())
+        );
+        names.push_str(name);
+        names.push('\n');
+    }
+    write_file(&dir.join("versions"), versions.as_bytes())?;
+    write_file(&dir.join("names"), names.as_bytes())?;
+    remove_stale(&info_dir, &written)
+}
+
+/// Compact index `info` file: `VERSION[-PLATFORM] DEPS|REQUIREMENTS` lines
+/// with runtime dependencies as `name:req&req` and the archive checksum
+fn info_file(entries: &[&Entry]) -> String {
+    let mut info = String::from("---\n");
+    for entry in entries {
+        let spec = &entry.spec;
+        let dependencies: Vec<String> = spec
+            .dependencies
+            .iter()
+            .filter(|dep| dep.kind == DependencyKind::Runtime)
+            .map(|dep| format!("{}:{}", dep.name, joined_requirement(&dep.requirements)))
+            .collect();
+        let mut requirements = vec![format!("checksum:{}", entry.sha256)];
+        for (label, list) in [
+            ("ruby", &spec.required_ruby_version),
+            ("rubygems", &spec.required_rubygems_version),
+        ] {
+            if list.iter().any(|requirement| requirement != ">= 0") {
+                requirements.push(format!("{label}:{}", joined_requirement(list)));
+            }
+        }
+        let _ = writeln!(
+            info,
+            "{} {}|{}",
+            entry.compact_version(),
+            dependencies.join(","),
+            requirements.join(",")
+        );
+    }
+    info
+}
+
+fn joined_requirement(requirements: &[String]) -> String {
+    if requirements.is_empty() {
+        ">= 0".to_string()
+    } else {
+        requirements.join("&")
+    }
+}
+
+fn gzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// Replace `path` atomically so servers never hand out a partial file
+fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    let mut temp = tempfile::NamedTempFile::new_in(parent)
+        .with_context(|| format!("Failed to create a temporary file in {}", parent.display()))?;
+    temp.write_all(contents)?;
+    temp.persist(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Delete files in `dir` that were not just written, left by removed gems
+fn remove_stale(dir: &Path, written: &BTreeSet<String>) -> Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", dir.display())),
+    };
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type()?.is_file() && !written.contains(&name) {
+            crate::debug!("Removing stale index file {}", entry.path().display());
+            fs::remove_file(entry.path())
+                .with_context(|| format!("Failed to remove {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::full_index::FullIndex;
+    use crate::static_source::StaticSource;
+    use crate::test_gems::write_gem;
+
+    #[tokio::test]
+    async fn generated_indexes_serve_a_static_source() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        write_gem(&temp.path().join("gems"), "rack", "3.0.0", &[]);
+        write_gem(&temp.path().join("gems"), "rack", "3.1.0.beta1", &[]);
+        write_gem(
+            &temp.path().join("gems"),
+            "widget",
+            "1.0.0",
+            &[("rack", "2")],
+        );
+        fs::write(temp.path().join("gems/broken.gem"), "not a gem").expect("write");
+
+        let summary = generate(temp.path()).expect("generate");
+        assert_eq!(
+            summary,
+            IndexSummary {
+                gems: 2,
+                versions: 3,
+                skipped: 1
+            }
+        );
+
+        let specs = FullIndex::parse_gzipped(&fs::read(temp.path().join("specs.4.8.gz")).unwrap())
+            .expect("specs");
+        assert_eq!(specs.total_count(), 2);
+        let prerelease =
+            FullIndex::parse(&fs::read(temp.path().join("prerelease_specs.4.8")).unwrap())
+                .expect("prerelease");
+        assert_eq!(
+            prerelease
+                .find_gem("rack")
+                .unwrap()
+                .first()
+                .unwrap()
+                .version,
+            "3.1.0.beta1"
+        );
+
+        let info = fs::read_to_string(temp.path().join("info/widget")).unwrap();
+        let line = info.lines().nth(1).unwrap();
+        assert!(line.starts_with("1.0.0 rack:>= 2|checksum:"), "{line}");
+        let versions = fs::read_to_string(temp.path().join("versions")).unwrap();
+        assert!(versions.contains("\nrack 3.0.0,3.1.0.beta1 "), "{versions}");
+        let names = fs::read_to_string(temp.path().join("names")).unwrap();
+        assert!(names.ends_with("---\nrack\nwidget\n"));
+
+        let url = format!("file://{}", temp.path().display());
+        let source = StaticSource::new(&url, reqwest::Client::new());
+        let widget = source.versions("widget").await.expect("versions");
+        let widget = widget.first().expect("widget");
+        assert_eq!(
+            widget
+                .dependencies
+                .runtime
+                .first()
+                .map(|dep| dep.name.as_str()),
+            Some("rack")
+        );
+
+        // Removing a gem drops its indexes on the next run
+        fs::remove_file(temp.path().join("gems/widget-1.0.0.gem")).unwrap();
+        generate(temp.path()).expect("generate");
+        assert!(!temp.path().join("info/widget").exists());
+        assert!(
+            !temp
+                .path()
+                .join(QUICK_DIR)
+                .join("widget-1.0.0.gemspec.rz")
+                .exists()
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Plain `http://` source policy
+//!
+//! Gems and git repositories fetched over plain HTTP (or the unauthenticated
+//! `git://` protocol) can be swapped in transit, so Lode refuses such
+//! sources. Hosts listed in the `insecure_sources` config are allowed for
+//! air-gapped mirrors, and `allow_insecure_sources` downgrades the refusal
+//! to a warning. Loopback hosts never leave the machine and are always
+//! allowed.
+
+use crate::config::Config;
+use thiserror::Error;
+
+/// A source refused for using an insecure scheme
+#[derive(Debug, Error)]
+#[error(
+    "Refusing insecure source {url}: plain {scheme}:// can be tampered with in transit. \
+     Use https://, or allow the host with `lode config insecure_sources HOST`"
+)]
+pub struct InsecureSourceError {
+    pub url: String,
+    pub scheme: String,
+}
+
+/// Which insecure sources may be used, and whether the rest only warn
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InsecureSourcePolicy {
+    /// Hosts (`mirror.internal`) or URL prefixes (`http://mirror.internal/gems`)
+    allowlist: Vec<String>,
+    /// Warn instead of refusing sources outside the allowlist
+    warn_only: bool,
+}
+
+impl InsecureSourcePolicy {
+    /// Policy allowing the hosts or URL prefixes in `allowlist`
+    #[must_use]
+    pub const fn new(allowlist: Vec<String>, warn_only: bool) -> Self {
+        Self {
+            allowlist,
+            warn_only,
+        }
+    }
+
+    /// Policy from the `insecure_sources` and `allow_insecure_sources` config
+    #[must_use]
+    pub fn from_config(config: Option<&Config>) -> Self {
+        config.map_or_else(Self::default, |config| {
+            Self::new(
+                config.insecure_sources.clone(),
+                config.allow_insecure_sources.unwrap_or(false),
+            )
+        })
+    }
+
+    /// Policy from the user's config files
+    #[must_use]
+    pub fn load() -> Self {
+        Self::from_config(Config::load().ok().as_ref())
+    }
+
+    /// Check one gem source or git URL
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for an insecure URL that is neither allowlisted nor
+    /// on a loopback host, unless the policy only warns.
+    pub fn check(&self, url: &str) -> Result<(), InsecureSourceError> {
+        let Some(scheme) = insecure_scheme(url) else {
+            return Ok(());
+        };
+        if is_loopback(url) || self.allows(url) {
+            return Ok(());
+        }
+
+        if self.warn_only {
+            crate::warn!(
+                "{url} uses plain {scheme}://; gems from it can be tampered with in transit"
+            );
+            return Ok(());
+        }
+        Err(InsecureSourceError {
+            url: url.to_string(),
+            scheme: scheme.to_string(),
+        })
+    }
+
+    /// Check every URL in `urls`, stopping at the first refused one
+    ///
+    /// # Errors
+    ///
+    /// Returns the first refused URL, as [`Self::check`].
+    pub fn check_all(
+        &self,
+        urls: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<(), InsecureSourceError> {
+        urls.into_iter()
+            .try_for_each(|url| self.check(url.as_ref()))
+    }
+
+    /// Whether `url` matches an allowlist entry by host or prefix
+    fn allows(&self, url: &str) -> bool {
+        let host = host(url);
+        self.allowlist.iter().any(|entry| {
+            let entry = entry.trim_end_matches('/');
+            !entry.is_empty() && (entry.eq_ignore_ascii_case(host) || url.starts_with(entry))
+        })
+    }
+}
+
+/// The insecure scheme `url` uses, if any
+#[must_use]
+pub fn insecure_scheme(url: &str) -> Option<&'static str> {
+    let (scheme, _) = url.split_once("://")?;
+    if scheme.eq_ignore_ascii_case("http") {
+        Some("http")
+    } else if scheme.eq_ignore_ascii_case("git") {
+        Some("git")
+    } else {
+        None
+    }
+}
+
+/// Host part of a URL, without credentials or port
+fn host(url: &str) -> &str {
+    let authority = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    let authority = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    if let Some(bracketed) = authority.strip_prefix('[') {
+        return bracketed.split(']').next().unwrap_or_default();
+    }
+    authority.split(':').next().unwrap_or_default()
+}
+
+/// Whether `url` points at this machine
+fn is_loopback(url: &str) -> bool {
+    let host = host(url);
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_plain_http_outside_the_allowlist() {
+        let policy = InsecureSourcePolicy::new(
+            vec![
+                "mirror.internal".to_string(),
+                "http://gems.corp/private/".to_string(),
+            ],
+            false,
+        );
+
+        assert!(policy.check("https://rubygems.org").is_ok());
+        assert!(policy.check("git@github.com:rails/rails.git").is_ok());
+        assert!(policy.check("http://127.0.0.1:9292").is_ok());
+        assert!(policy.check("http://localhost/gems").is_ok());
+        assert!(policy.check("http://[::1]:8080").is_ok());
+        assert!(policy.check("http://user:pw@mirror.internal:8808/").is_ok());
+        assert!(policy.check("http://gems.corp/private/rack").is_ok());
+
+        let err = policy.check("http://rubygems.org").unwrap_err();
+        assert_eq!(err.url, "http://rubygems.org");
+        assert!(policy.check("http://gems.corp/public").is_err());
+        assert!(policy.check("git://github.com/rack/rack.git").is_err());
+        assert!(
+            policy
+                .check_all(["https://rubygems.org", "HTTP://evil.example"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn warn_only_policy_accepts_insecure_sources() {
+        let policy = InsecureSourcePolicy::new(Vec::new(), true);
+        assert!(policy.check("http://rubygems.org").is_ok());
+    }
+
+    #[test]
+    fn policy_from_config() {
+        let config = Config {
+            insecure_sources: vec!["mirror.internal".to_string()],
+            ..Config::default()
+        };
+        let policy = InsecureSourcePolicy::from_config(Some(&config));
+        assert!(policy.check("http://mirror.internal").is_ok());
+        assert!(policy.check("http://other.internal").is_err());
+        assert_eq!(
+            InsecureSourcePolicy::from_config(None),
+            InsecureSourcePolicy::default()
+        );
+    }
+}
@@ -6,7 +6,10 @@
 use crate::lockfile::{GemSpec, GitGemSpec, PathGemSpec};
 use anyhow::Result;
 use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use tar::Archive;
@@ -24,23 +27,74 @@ pub enum InstallError {
     #[error("Invalid gem archive for {gem}: {reason}")]
     InvalidArchive { gem: String, reason: String },
 
+    #[error(
+        "{gem} has files whose names differ only in case, which overwrite each other on case-insensitive filesystems: {}",
+        describe_collisions(.paths)
+    )]
+    CaseCollision {
+        gem: String,
+        paths: Vec<(PathBuf, PathBuf)>,
+    },
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
 
+/// What to do with a gem whose files differ only in case
+///
+/// Such files overwrite each other when extracted on a case-insensitive
+/// filesystem (the macOS and Windows defaults), so one of them silently
+/// goes missing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaseCollisions {
+    /// Warn with the affected paths and install anyway
+    #[default]
+    Warn,
+    /// Refuse to install the gem
+    Fail,
+}
+
+impl CaseCollisions {
+    /// Policy from the `fail_on_case_collisions` setting
+    #[must_use]
+    pub fn from_config(config: Option<&crate::Config>) -> Self {
+        if crate::config::case_collisions_fatal(config) {
+            Self::Fail
+        } else {
+            Self::Warn
+        }
+    }
+}
+
+fn describe_collisions(paths: &[(PathBuf, PathBuf)]) -> String {
+    paths
+        .iter()
+        .map(|(first, second)| format!("{} and {}", first.display(), second.display()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Extract a .gem file to a destination directory
 ///
-/// Extracts gem contents and metadata to appropriate directories.
+/// Extracts gem contents and metadata to appropriate directories. Files
+/// whose paths differ only in case are reported according to `collisions`.
 ///
 /// # Errors
 ///
-/// Returns an error if the gem file cannot be read, is corrupted, or extraction fails.
+/// Returns an error if the gem file cannot be read, is corrupted, or extraction fails,
+/// or if it has colliding files and `collisions` is [`CaseCollisions::Fail`].
 pub fn extract_gem(
     gem_path: &Path,
     dest_dir: &Path,
     gem_name: &str,
     spec_path: &Path,
+    collisions: CaseCollisions,
 ) -> Result<(), InstallError> {
+    crate::trace!(
+        "extracting {} to {}",
+        gem_path.display(),
+        dest_dir.display()
+    );
     let file = fs::File::open(gem_path).map_err(|e| InstallError::ExtractionError {
         gem: gem_name.to_string(),
         source: e,
@@ -75,12 +129,7 @@ pub fn extract_gem(
                 let gz = GzDecoder::new(entry);
                 let mut data_archive = Archive::new(gz);
 
-                data_archive
-                    .unpack(dest_dir)
-                    .map_err(|e| InstallError::ExtractionError {
-                        gem: gem_name.to_string(),
-                        source: e,
-                    })?;
+                unpack_data(&mut data_archive, dest_dir, gem_name, collisions)?;
             }
             Some("metadata.gz") => {
                 found_metadata = true;
@@ -122,6 +171,99 @@ pub fn extract_gem(
     Ok(())
 }
 
+/// Unpack the files of `data.tar.gz` into `dest_dir`
+///
+/// Like [`Archive::unpack`], but every path is checked against the ones
+/// before it for names that differ only in case. Directories are created
+/// last so their permissions cannot block the files inside them.
+fn unpack_data<R: Read>(
+    archive: &mut Archive<R>,
+    dest_dir: &Path,
+    gem_name: &str,
+    collisions: CaseCollisions,
+) -> Result<(), InstallError> {
+    let extraction_error = |source| InstallError::ExtractionError {
+        gem: gem_name.to_string(),
+        source,
+    };
+    fs::create_dir_all(dest_dir).map_err(extraction_error)?;
+    let dest_dir = long_path(dest_dir);
+    let mut seen: BTreeMap<Vec<u8>, PathBuf> = BTreeMap::new();
+    let mut found = Vec::new();
+    let mut directories = Vec::new();
+
+    for entry in archive.entries().map_err(extraction_error)? {
+        let mut entry = entry.map_err(extraction_error)?;
+        let path = entry.path().map_err(extraction_error)?.into_owned();
+        let folded = fold_case(&path);
+        match seen.get(&folded) {
+            Some(first) if first.as_path() != path.as_path() => {
+                found.push((first.clone(), path.clone()));
+                if collisions == CaseCollisions::Fail {
+                    return Err(InstallError::CaseCollision {
+                        gem: gem_name.to_string(),
+                        paths: found,
+                    });
+                }
+            }
+            Some(_) => {}
+            None => {
+                seen.insert(folded, path);
+            }
+        }
+
+        if entry.header().entry_type().is_dir() {
+            directories.push(entry);
+        } else {
+            entry.unpack_in(&dest_dir).map_err(extraction_error)?;
+        }
+    }
+    for mut directory in directories {
+        directory.unpack_in(&dest_dir).map_err(extraction_error)?;
+    }
+
+    if !found.is_empty() {
+        crate::warn!(
+            "{gem_name} has files whose names differ only in case; on a case-insensitive \
+             filesystem only one of each survives: {}",
+            describe_collisions(&found)
+        );
+    }
+    Ok(())
+}
+
+/// `path` as a case-insensitive filesystem compares it
+///
+/// Names that are not valid UTF-8 fold only their ASCII letters, so distinct
+/// byte sequences never collapse into the same replacement characters.
+fn fold_case(path: &Path) -> Vec<u8> {
+    path.to_str().map_or_else(
+        || {
+            let bytes = path.as_os_str().as_encoded_bytes();
+            bytes
+                .strip_suffix(b"/")
+                .unwrap_or(bytes)
+                .to_ascii_lowercase()
+        },
+        |path| path.trim_end_matches('/').to_lowercase().into_bytes(),
+    )
+}
+
+/// `dir` in a form whose children may exceed 260 characters
+///
+/// Windows refuses longer paths unless they are absolute extended-length
+/// (`\\?\`) paths, which `canonicalize` returns there. Deeply nested gems
+/// under a long vendor directory hit this limit.
+#[cfg(windows)]
+fn long_path(dir: &Path) -> PathBuf {
+    fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
+
+#[cfg(not(windows))]
+fn long_path(dir: &Path) -> PathBuf {
+    dir.to_path_buf()
+}
+
 /// Install a gem from cache to vendor directory
 ///
 /// Creates standard `RubyGems` directory structure.
@@ -134,6 +276,7 @@ pub fn install_gem(
     cache_path: &Path,
     vendor_dir: &Path,
     ruby_version: &str,
+    collisions: CaseCollisions,
 ) -> Result<(), InstallError> {
     // Build installation paths
     let ruby_dir = vendor_dir.join("ruby").join(ruby_version);
@@ -155,8 +298,24 @@ pub fn install_gem(
     // Create gem directory
     fs::create_dir_all(&gem_install_dir)?;
 
-    // Extract gem files and gemspec
-    extract_gem(cache_path, &gem_install_dir, &gem_spec.name, &spec_path)?;
+    // Extract gem files and gemspec, leaving no partial install behind
+    if let Err(e) = extract_gem(
+        cache_path,
+        &gem_install_dir,
+        &gem_spec.name,
+        &spec_path,
+        collisions,
+    ) {
+        drop(fs::remove_dir_all(&gem_install_dir));
+        return Err(e);
+    }
+
+    if let Err(e) = crate::rubygems_plugins::regenerate(&ruby_dir, &gem_spec.name) {
+        crate::warn!(
+            "Could not register RubyGems plugin of {}: {e}",
+            gem_spec.name
+        );
+    }
 
     Ok(())
 }
@@ -178,12 +337,72 @@ pub fn install_path_gem(
     let gem_full_name = format!("{}-{}", path_spec.name, path_spec.version);
     let gem_install_dir = ruby_dir.join("gems").join(&gem_full_name);
 
+    // A previous linked install is replaced by a real copy
+    if gem_install_dir.is_symlink() {
+        remove_symlink(&gem_install_dir)?;
+    }
+
     // Skip if already installed
     if gem_install_dir.exists() {
         return Ok(());
     }
 
-    // Resolve path (relative to current directory)
+    let source_path = path_gem_source(path_spec)?;
+
+    // Create parent directories
+    if let Some(parent) = gem_install_dir.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Copy gem directory
+    copy_dir_recursive(&source_path, &gem_install_dir)?;
+
+    write_path_gem_spec(path_spec, &source_path, &ruby_dir)
+}
+
+/// Install a gem from a local path by symlinking its source directory
+///
+/// Edits in the source tree are visible immediately, so only binstubs and
+/// extensions need regenerating after a change. An existing copied install
+/// is replaced with the link.
+///
+/// # Errors
+///
+/// Returns an error if the path doesn't exist or the link cannot be created.
+pub fn link_path_gem(
+    path_spec: &PathGemSpec,
+    vendor_dir: &Path,
+    ruby_version: &str,
+) -> Result<(), InstallError> {
+    let ruby_dir = vendor_dir.join("ruby").join(ruby_version);
+    let gem_full_name = format!("{}-{}", path_spec.name, path_spec.version);
+    let gem_install_dir = ruby_dir.join("gems").join(&gem_full_name);
+
+    let source_path = path_gem_source(path_spec)?;
+
+    if gem_install_dir.is_symlink() {
+        if fs::read_link(&gem_install_dir)? == source_path {
+            return write_path_gem_spec(path_spec, &source_path, &ruby_dir);
+        }
+        remove_symlink(&gem_install_dir)?;
+    } else if gem_install_dir.exists() {
+        fs::remove_dir_all(&gem_install_dir)?;
+    }
+
+    if let Some(parent) = gem_install_dir.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&source_path, &gem_install_dir)?;
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_dir(&source_path, &gem_install_dir)?;
+
+    write_path_gem_spec(path_spec, &source_path, &ruby_dir)
+}
+
+/// Resolve a path gem's source directory (relative to the current directory)
+fn path_gem_source(path_spec: &PathGemSpec) -> Result<PathBuf, InstallError> {
     let source_path = PathBuf::from(&path_spec.path);
     let source_path = if source_path.is_absolute() {
         source_path
@@ -199,13 +418,25 @@ pub fn install_path_gem(
         });
     }
 
-    // Create parent directories
-    if let Some(parent) = gem_install_dir.parent() {
-        fs::create_dir_all(parent)?;
-    }
+    Ok(source_path)
+}
 
-    // Copy gem directory
-    copy_dir_recursive(&source_path, &gem_install_dir)?;
+/// Remove a directory symlink without touching its target
+fn remove_symlink(path: &Path) -> Result<(), InstallError> {
+    #[cfg(windows)]
+    fs::remove_dir(path)?;
+    #[cfg(not(windows))]
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+/// Create the specification entry for a path gem if it is missing
+fn write_path_gem_spec(
+    path_spec: &PathGemSpec,
+    source_path: &Path,
+    ruby_dir: &Path,
+) -> Result<(), InstallError> {
+    let gem_full_name = format!("{}-{}", path_spec.name, path_spec.version);
 
     // Create gemspec stub if needed (for Bundler compatibility)
     let spec_path = ruby_dir
@@ -218,7 +449,7 @@ pub fn install_path_gem(
         }
 
         // Look for .gemspec file in source directory
-        if let Some(gemspec_file) = find_gemspec(&source_path) {
+        if let Some(gemspec_file) = find_gemspec(source_path) {
             fs::copy(gemspec_file, &spec_path)?;
         } else {
             // Create minimal gemspec stub
@@ -344,6 +575,7 @@ pub fn install_git_gem(
     source_dir: &Path,
     vendor_dir: &Path,
     ruby_version: &str,
+    collisions: CaseCollisions,
 ) -> Result<(), InstallError> {
     // Build gem from source
     let build_dir = source_dir.join("pkg");
@@ -359,11 +591,145 @@ pub fn install_git_gem(
     );
 
     // Install the built gem
-    install_gem(&gem_spec, &gem_path, vendor_dir, ruby_version)?;
+    install_gem(&gem_spec, &gem_path, vendor_dir, ruby_version, collisions)?;
 
     Ok(())
 }
 
+/// Differences between an installed gem directory and its packaged `.gem`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ContentsDiff {
+    /// Files whose contents differ from the package
+    pub modified: Vec<PathBuf>,
+    /// Packaged files that are absent from the install
+    pub missing: Vec<PathBuf>,
+    /// Installed files that are not in the package (e.g. build artifacts)
+    pub extra: Vec<PathBuf>,
+}
+
+impl ContentsDiff {
+    /// True when the installed files match the package exactly
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.modified.is_empty() && self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
+/// Compare an installed gem directory against the packaged `.gem` file
+///
+/// Hashes every file in the gem's `data.tar.gz` and the corresponding
+/// installed file. Paths in the result are relative to the gem directory.
+///
+/// # Errors
+///
+/// Returns an error if the gem archive cannot be read or has no data.
+pub fn diff_installed_gem(
+    gem_path: &Path,
+    gem_dir: &Path,
+    gem_name: &str,
+) -> Result<ContentsDiff, InstallError> {
+    let packaged = packaged_digests(gem_path, gem_name)?;
+
+    let mut installed = BTreeMap::new();
+    for entry in walkdir::WalkDir::new(gem_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+    {
+        if let Ok(relative) = entry.path().strip_prefix(gem_dir) {
+            installed.insert(relative.to_path_buf(), entry.path().to_path_buf());
+        }
+    }
+
+    let mut diff = ContentsDiff::default();
+    for (relative, digest) in &packaged {
+        match installed.get(relative) {
+            Some(path) => {
+                if file_digest(fs::File::open(path)?)? != *digest {
+                    diff.modified.push(relative.clone());
+                }
+            }
+            None => diff.missing.push(relative.clone()),
+        }
+    }
+    diff.extra = installed
+        .into_keys()
+        .filter(|relative| !packaged.contains_key(relative))
+        .collect();
+
+    Ok(diff)
+}
+
+/// SHA256 digests of every regular file in a gem's `data.tar.gz`
+fn packaged_digests(
+    gem_path: &Path,
+    gem_name: &str,
+) -> Result<BTreeMap<PathBuf, String>, InstallError> {
+    let extraction_error = |e| InstallError::ExtractionError {
+        gem: gem_name.to_string(),
+        source: e,
+    };
+
+    let mut archive = Archive::new(fs::File::open(gem_path).map_err(extraction_error)?);
+    for entry in archive.entries().map_err(extraction_error)? {
+        let entry = entry.map_err(extraction_error)?;
+        if entry.path().map_err(extraction_error)?.to_str() != Some("data.tar.gz") {
+            continue;
+        }
+
+        let mut digests = BTreeMap::new();
+        let mut data_archive = Archive::new(GzDecoder::new(entry));
+        for file in data_archive.entries().map_err(extraction_error)? {
+            let file = file.map_err(extraction_error)?;
+            if !file.header().entry_type().is_file() {
+                continue;
+            }
+            let path = file.path().map_err(extraction_error)?.to_path_buf();
+            digests.insert(path, file_digest(file).map_err(extraction_error)?);
+        }
+        return Ok(digests);
+    }
+
+    Err(InstallError::InvalidArchive {
+        gem: gem_name.to_string(),
+        reason: "data.tar.gz not found in gem archive".to_string(),
+    })
+}
+
+/// Hex SHA256 digest of a reader's contents
+fn file_digest(mut reader: impl Read) -> Result<String, std::io::Error> {
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 8192];
+    loop {
+        let count = reader.read(&mut buffer)?;
+        if count == 0 {
+            break;
+        }
+        hasher.update(buffer.get(..count).unwrap_or(&[]));
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Locate the cached `.gem` file for an installed gem directory
+///
+/// Checks the `cache/` directory that sits beside `gems/` (the `RubyGems`
+/// layout) and then each of `cache_dirs`.
+#[must_use]
+pub fn find_cached_gem(gem_dir: &Path, cache_dirs: &[PathBuf]) -> Option<PathBuf> {
+    let mut file_name = gem_dir.file_name()?.to_os_string();
+    file_name.push(".gem");
+    let sibling_cache = gem_dir
+        .parent()
+        .and_then(Path::parent)
+        .map(|root| root.join("cache"));
+
+    sibling_cache
+        .iter()
+        .chain(cache_dirs)
+        .map(|dir| dir.join(&file_name))
+        .find(|path| path.is_file())
+}
+
 /// Install report statistics
 #[derive(Debug, Default, Copy, Clone)]
 pub struct InstallReport {
@@ -395,6 +761,201 @@ impl InstallReport {
 mod tests {
     use super::*;
 
+    fn build_test_gem(dir: &Path, files: &[(&str, &str)]) -> PathBuf {
+        let mut data = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            data.append_data(&mut header, name, content.as_bytes())
+                .expect("append file");
+        }
+        let data = data
+            .into_inner()
+            .and_then(flate2::write::GzEncoder::finish)
+            .expect("finish data.tar.gz");
+        write_gem(dir, &data)
+    }
+
+    /// `demo-1.0.0.gem` in `dir` with `data` as its `data.tar.gz`
+    fn write_gem(dir: &Path, data: &[u8]) -> PathBuf {
+        let gem_path = dir.join("demo-1.0.0.gem");
+        let mut gem = tar::Builder::new(fs::File::create(&gem_path).expect("create gem"));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        gem.append_data(&mut header, "data.tar.gz", data)
+            .expect("append data.tar.gz");
+        gem.finish().expect("finish gem");
+        gem_path
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn link_path_gem_replaces_copy_with_symlink() {
+        let temp = tempfile::TempDir::new().expect("tempdir");
+        let source = temp.path().join("src/mygem");
+        fs::create_dir_all(source.join("lib")).expect("create source");
+        fs::write(source.join("lib/mygem.rb"), "module Mygem; end\n").expect("write lib");
+
+        let path_spec = PathGemSpec {
+            name: "mygem".to_string(),
+            version: "0.1.0".to_string(),
+            path: source.display().to_string(),
+            groups: vec![],
+        };
+        let vendor = temp.path().join("vendor");
+        let gem_dir = vendor.join("ruby/3.4.0/gems/mygem-0.1.0");
+
+        install_path_gem(&path_spec, &vendor, "3.4.0").expect("copy install");
+        assert!(gem_dir.is_dir() && !gem_dir.is_symlink());
+
+        link_path_gem(&path_spec, &vendor, "3.4.0").expect("link install");
+        assert!(gem_dir.is_symlink());
+        assert_eq!(fs::read_link(&gem_dir).expect("read link"), source);
+        assert!(
+            vendor
+                .join("ruby/3.4.0/specifications/mygem-0.1.0.gemspec")
+                .exists()
+        );
+
+        // Edits in the source are visible without reinstalling
+        fs::write(source.join("lib/extra.rb"), "").expect("write extra");
+        assert!(gem_dir.join("lib/extra.rb").exists());
+
+        install_path_gem(&path_spec, &vendor, "3.4.0").expect("copy again");
+        assert!(!gem_dir.is_symlink());
+        assert!(source.join("lib/mygem.rb").exists());
+    }
+
+    #[cfg(unix)]
+    proptest::proptest! {
+        #[test]
+        fn extract_gem_keeps_file_names_byte_for_byte(
+            name in proptest::collection::vec(1u8..=255, 1..40)
+        ) {
+            use std::os::unix::ffi::OsStrExt;
+            proptest::prop_assume!(!name.contains(&b'/') && name != b"." && name != b"..");
+
+            // Raw header bytes, which need not be UTF-8
+            let mut header = tar::Header::new_gnu();
+            for (slot, byte) in header.as_old_mut().name.iter_mut().zip(&name) {
+                *slot = *byte;
+            }
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_size(5);
+            header.set_mode(0o644);
+            header.set_cksum();
+            let mut data = tar::Builder::new(flate2::write::GzEncoder::new(
+                Vec::new(),
+                flate2::Compression::default(),
+            ));
+            data.append(&header, &b"hello"[..]).expect("append file");
+            let data = data
+                .into_inner()
+                .and_then(flate2::write::GzEncoder::finish)
+                .expect("finish data.tar.gz");
+
+            let temp = tempfile::TempDir::new().expect("tempdir");
+            let gem_path = write_gem(temp.path(), &data);
+            let dest = temp.path().join("demo-1.0.0");
+            extract_gem(
+                &gem_path,
+                &dest,
+                "demo",
+                &temp.path().join("demo-1.0.0.gemspec"),
+                CaseCollisions::Warn,
+            )
+            .expect("extract");
+
+            let extracted = fs::read(dest.join(std::ffi::OsStr::from_bytes(&name)))
+                .expect("read extracted file");
+            proptest::prop_assert_eq!(extracted, b"hello");
+        }
+    }
+
+    #[test]
+    fn case_collisions_warn_or_fail() {
+        let temp = tempfile::TempDir::new().expect("tempdir");
+        let gem_path = build_test_gem(
+            temp.path(),
+            &[
+                ("lib/Demo.rb", "module Demo; end\n"),
+                ("lib/demo.rb", "require 'Demo'\n"),
+            ],
+        );
+        let spec_path = temp.path().join("demo-1.0.0.gemspec");
+
+        let warned = temp.path().join("warned");
+        extract_gem(&gem_path, &warned, "demo", &spec_path, CaseCollisions::Warn)
+            .expect("extract with warning");
+        assert!(warned.join("lib/demo.rb").exists());
+
+        let failed = temp.path().join("failed");
+        let err = extract_gem(&gem_path, &failed, "demo", &spec_path, CaseCollisions::Fail)
+            .expect_err("collision is fatal");
+        assert!(matches!(
+            &err,
+            InstallError::CaseCollision { gem, paths }
+                if gem == "demo"
+                    && paths == &[(PathBuf::from("lib/Demo.rb"), PathBuf::from("lib/demo.rb"))]
+        ));
+        assert!(err.to_string().contains("lib/Demo.rb and lib/demo.rb"));
+    }
+
+    #[test]
+    fn diff_installed_gem_reports_changes() {
+        let temp = tempfile::TempDir::new().expect("tempdir");
+        let gem_path = build_test_gem(
+            temp.path(),
+            &[
+                ("lib/demo.rb", "module Demo; end\n"),
+                ("lib/demo/version.rb", "VERSION = '1.0.0'\n"),
+                ("README.md", "# Demo\n"),
+            ],
+        );
+
+        let gem_dir = temp.path().join("gems").join("demo-1.0.0");
+        fs::create_dir_all(gem_dir.join("lib/demo")).expect("mkdir");
+        fs::write(
+            gem_dir.join("lib/demo.rb"),
+            "module Demo; PATCHED = 1; end\n",
+        )
+        .expect("write");
+        fs::write(gem_dir.join("lib/demo/version.rb"), "VERSION = '1.0.0'\n").expect("write");
+        fs::write(gem_dir.join("lib/demo.so"), "binary").expect("write");
+
+        let diff = diff_installed_gem(&gem_path, &gem_dir, "demo").expect("diff");
+
+        assert_eq!(diff.modified, vec![PathBuf::from("lib/demo.rb")]);
+        assert_eq!(diff.missing, vec![PathBuf::from("README.md")]);
+        assert_eq!(diff.extra, vec![PathBuf::from("lib/demo.so")]);
+        assert!(!diff.is_clean());
+    }
+
+    #[test]
+    fn find_cached_gem_checks_sibling_cache() {
+        let temp = tempfile::TempDir::new().expect("tempdir");
+        let gem_dir = temp.path().join("gems").join("demo-1.0.0");
+        fs::create_dir_all(&gem_dir).expect("mkdir");
+        fs::create_dir_all(temp.path().join("cache")).expect("mkdir");
+        fs::write(temp.path().join("cache/demo-1.0.0.gem"), "").expect("write");
+
+        assert_eq!(
+            find_cached_gem(&gem_dir, &[]),
+            Some(temp.path().join("cache/demo-1.0.0.gem"))
+        );
+        assert_eq!(
+            find_cached_gem(&temp.path().join("gems/other-1.0"), &[]),
+            None
+        );
+    }
+
     #[test]
     fn install_report() {
         let mut report = InstallReport::new();
@@ -0,0 +1,246 @@
+//! Resumable install journal
+//!
+//! `lode install` keeps `.lode/state/install.json` next to the Gemfile while
+//! it works: the gems it set out to install, and each one it finished
+//! (extracted, extension built, binstubs written) along with the size, mtime
+//! and SHA-256 of the cached `.gem` it came from. The file is replaced
+//! atomically after every gem, so a Ctrl-C or OOM kill leaves the last
+//! finished state behind.
+//!
+//! `lode install --resume` then skips the finished gems whose `.gem` still
+//! matches, checking size and mtime first and the digest only when those
+//! differ, and reinstalls the rest, including gem directories an
+//! interrupted extraction left half written. A completed install removes
+//! the journal.
+
+use crate::digest_cache::sha256_file;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// State directory, relative to the Gemfile's directory
+pub const STATE_DIR: &str = ".lode/state";
+
+/// Journal file name inside [`STATE_DIR`]
+const JOURNAL_FILE: &str = "install.json";
+
+/// Version of the install journal; a journal from another version is
+/// ignored, so an interrupted install starts over instead of resuming
+const FORMAT_VERSION: u32 = 1;
+
+/// The cached `.gem` a finished gem was installed from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Completed {
+    gem_path: PathBuf,
+    size: u64,
+    mtime_nanos: u64,
+    sha256: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalFile {
+    format: u32,
+    lockfile_sha256: String,
+    pending: BTreeSet<String>,
+    completed: BTreeMap<String, Completed>,
+}
+
+/// Progress of one `lode install`, persisted after every finished gem
+#[derive(Debug)]
+pub struct InstallJournal {
+    path: PathBuf,
+    lockfile_sha256: String,
+    pending: BTreeSet<String>,
+    completed: BTreeMap<String, Completed>,
+}
+
+impl InstallJournal {
+    /// Journal path for the project whose Gemfile lives in `project_dir`
+    #[must_use]
+    pub fn path(project_dir: &Path) -> PathBuf {
+        project_dir.join(STATE_DIR).join(JOURNAL_FILE)
+    }
+
+    /// Start a journal for installing the gems named by `full_names`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the journal cannot be written.
+    pub fn begin(
+        project_dir: &Path,
+        lockfile_content: &str,
+        full_names: impl IntoIterator<Item = String>,
+    ) -> io::Result<Self> {
+        let journal = Self {
+            path: Self::path(project_dir),
+            lockfile_sha256: sha256_str(lockfile_content),
+            pending: full_names.into_iter().collect(),
+            completed: BTreeMap::new(),
+        };
+        journal.save()?;
+        Ok(journal)
+    }
+
+    /// The journal an interrupted install left in `project_dir`, if any
+    ///
+    /// Unreadable journals and ones from another format version are ignored.
+    #[must_use]
+    pub fn load(project_dir: &Path) -> Option<Self> {
+        let path = Self::path(project_dir);
+        let file = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<JournalFile>(&bytes).ok())
+            .filter(|file| file.format == FORMAT_VERSION)?;
+        Some(Self {
+            path,
+            lockfile_sha256: file.lockfile_sha256,
+            pending: file.pending,
+            completed: file.completed,
+        })
+    }
+
+    /// Whether the journal was written for this lockfile
+    #[must_use]
+    pub fn matches_lockfile(&self, lockfile_content: &str) -> bool {
+        self.lockfile_sha256 == sha256_str(lockfile_content)
+    }
+
+    /// Whether the interrupted install set out to install `full_name`
+    #[must_use]
+    pub fn is_pending(&self, full_name: &str) -> bool {
+        self.pending.contains(full_name)
+    }
+
+    /// Number of gems recorded as finished
+    #[must_use]
+    pub fn completed_count(&self) -> usize {
+        self.completed.len()
+    }
+
+    /// Whether `full_name` finished and its cached `.gem` is unchanged
+    ///
+    /// Matching size and mtime are trusted; otherwise the file is hashed and
+    /// compared with the recorded digest.
+    #[must_use]
+    pub fn is_complete(&self, full_name: &str) -> bool {
+        self.completed
+            .get(full_name)
+            .is_some_and(|entry| match stamp(&entry.gem_path) {
+                Ok((size, mtime_nanos))
+                    if size == entry.size && mtime_nanos == entry.mtime_nanos =>
+                {
+                    true
+                }
+                Ok(_) => sha256_file(&entry.gem_path).is_ok_and(|sha256| sha256 == entry.sha256),
+                Err(_) => false,
+            })
+    }
+
+    /// Record `full_name` as finished, installed from `gem_path`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `gem_path` cannot be read or the journal cannot be
+    /// written.
+    pub fn record(&mut self, full_name: &str, gem_path: &Path) -> io::Result<()> {
+        let (size, mtime_nanos) = stamp(gem_path)?;
+        let entry = Completed {
+            gem_path: gem_path.to_path_buf(),
+            size,
+            mtime_nanos,
+            sha256: sha256_file(gem_path)?,
+        };
+        self.completed.insert(full_name.to_string(), entry);
+        self.save()
+    }
+
+    /// Remove the journal once the install has finished
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the journal exists but cannot be removed.
+    pub fn finish(self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Write the journal to a temporary file, sync it and rename it into place
+    fn save(&self) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = JournalFile {
+            format: FORMAT_VERSION,
+            lockfile_sha256: self.lockfile_sha256.clone(),
+            pending: self.pending.clone(),
+            completed: self.completed.clone(),
+        };
+        let json = serde_json::to_vec(&file).map_err(io::Error::other)?;
+        let temp = self.path.with_extension("json.tmp");
+        let mut out = File::create(&temp)?;
+        out.write_all(&json)?;
+        out.sync_all()?;
+        fs::rename(&temp, &self.path)
+    }
+}
+
+fn sha256_str(content: &str) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+fn stamp(path: &Path) -> io::Result<(u64, u64)> {
+    let metadata = fs::metadata(path)?;
+    let mtime_nanos = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |age| u64::try_from(age.as_nanos()).unwrap_or(u64::MAX));
+    Ok((metadata.len(), mtime_nanos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn journal_survives_a_restart_and_verifies_finished_gems() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let gem = temp.path().join("rack-3.1.7.gem");
+        fs::write(&gem, "rack").expect("write gem");
+
+        let names = ["rack-3.1.7", "rake-13.0.6"].map(String::from);
+        let mut journal =
+            InstallJournal::begin(temp.path(), "GEM\n", names).expect("begin journal");
+        journal.record("rack-3.1.7", &gem).expect("record");
+        drop(journal);
+
+        let journal = InstallJournal::load(temp.path()).expect("journal on disk");
+        assert!(journal.matches_lockfile("GEM\n"));
+        assert!(!journal.matches_lockfile("GEM\n  rack\n"));
+        assert!(journal.is_pending("rake-13.0.6"));
+        assert!(journal.is_complete("rack-3.1.7"));
+        assert!(!journal.is_complete("rake-13.0.6"));
+        assert_eq!(journal.completed_count(), 1);
+
+        // A touched but identical gem still verifies by digest
+        File::options()
+            .write(true)
+            .open(&gem)
+            .and_then(|file| file.set_modified(SystemTime::now() + Duration::from_mins(1)))
+            .expect("touch gem");
+        assert!(journal.is_complete("rack-3.1.7"));
+
+        // Changed contents do not
+        fs::write(&gem, "tampered").expect("rewrite gem");
+        assert!(!journal.is_complete("rack-3.1.7"));
+
+        journal.finish().expect("finish");
+        assert!(InstallJournal::load(temp.path()).is_none());
+    }
+}
@@ -13,33 +13,69 @@ pub fn gem_source_url() -> String {
     env_vars::gem_source().unwrap_or_else(|| DEFAULT_GEM_SOURCE.to_string())
 }
 
+pub mod activation;
+pub mod advisory;
+pub mod build_history;
+pub mod build_info;
 pub mod cache;
+pub mod cache_usage;
 pub mod config;
 pub mod debug;
+pub mod diagnostics;
+pub mod digest_cache;
+pub mod docs;
 pub mod download;
+pub mod env_preserver;
 pub mod env_vars;
+pub mod exit_code;
 pub mod extensions;
 pub mod full_index;
+pub mod gem_notices;
 pub mod gem_store;
 pub mod gem_utils;
 pub mod gemfile;
 pub mod gemfile_writer;
+pub mod gemrc;
+pub mod gemspec;
 pub mod git;
+pub mod group_manifest;
+pub mod index_generator;
+pub mod insecure_source;
 pub mod install;
+pub mod install_state;
 pub mod lockfile;
+pub mod marshal;
+pub mod messages;
 pub mod paths;
 pub mod platform;
+pub mod profile;
+pub mod rate_limit;
+pub mod remote_sources;
+pub mod reporter;
+pub mod resolution_cache;
 pub mod resolver;
 pub mod ruby;
 pub mod rubygems_client;
+pub mod rubygems_plugins;
+pub mod source_audit;
+pub mod source_provider;
 pub mod standalone;
+pub mod static_source;
+#[cfg(test)]
+mod test_gems;
 pub mod trust_policy;
+pub mod typosquat;
 pub mod user;
 
 // Re-export common types for convenience
+pub use advisory::{Advisory, AdvisoryDatabase};
+pub use build_history::BuildHistory;
+pub use build_info::{BuildInfo, BuildInfoDifference};
 pub use cache::{Stats as CacheDirStats, collect_stats, human_bytes};
+pub use cache_usage::{CacheUsage, UsageCounters};
 pub use config::{BundleConfig, Config};
-pub use debug::{debug_log, debug_logf, init_debug, is_debug_enabled};
+pub use debug::{debug_log, debug_logf, init_debug, init_logging, is_debug_enabled};
+pub use digest_cache::DigestCache;
 pub use download::DownloadManager;
 pub use extensions::{
     BinstubGenerator, BuildResult, CExtensionBuilder, ExtensionBuilder, ExtensionType,
@@ -47,25 +83,39 @@ pub use extensions::{
 };
 pub use full_index::{FullIndex, IndexGemSpec};
 pub use gem_utils::parse_gem_name;
-pub use gemfile::{GemDependency, Gemfile, GemfileError};
+pub use gemfile::{GemDependency, Gemfile, GemfileError, PluginDeclaration, PluginSource};
 pub use gemfile_writer::GemfileWriter;
+pub use gemspec::{DependencyKind, Gemspec, GemspecDependency, GemspecError};
 pub use git::{GitError, GitManager};
 pub use install::InstallReport;
-pub use lockfile::{Dependency, GemSpec, GitGemSpec, Lockfile, LockfileError, PathGemSpec};
+pub use lockfile::{
+    Dependency, GemSpec, GitGemSpec, Lockfile, LockfileError, PathGemSpec, PluginSourceSpec,
+};
 pub use paths::{
     find_gemfile, find_gemfile_in, find_lockfile, find_lockfile_in, gemfile_for_lockfile,
     lockfile_for_gemfile,
 };
 pub use platform::{detect_current_platform, platform_matches};
-pub use resolver::{ResolvedDependency, ResolvedGem, Resolver, ResolverError};
+pub use profile::{Phase, Profiler, SpanRecord};
+pub use resolution_cache::ResolutionCache;
+pub use resolver::{
+    IncompatibleVersion, ResolutionStrategy, ResolvedDependency, ResolvedGem, Resolver,
+    ResolverError,
+};
 pub use ruby::{
-    RubyEngine, detect_engine, detect_engine_from_platform, detect_ruby_version,
-    detect_ruby_version_from_lockfile, get_standard_gem_paths, get_system_gem_dir,
-    normalize_ruby_version, to_major_minor,
+    RubyEngine, detect_engine, detect_engine_from_platform, detect_interpreter_versions,
+    detect_ruby_version, detect_ruby_version_from_lockfile, get_standard_gem_paths,
+    get_system_gem_dir, normalize_ruby_version, to_major_minor,
 };
 pub use rubygems_client::{
-    CacheStats, Dependencies, DependencySpec, GemMetadata, GemVersion, RubyGemsClient,
+    CacheStats, Dependencies, DependencySpec, GemMetadata, GemSummary, GemVersion, RubyGemsClient,
     RubyGemsError,
 };
+pub use source_audit::{SourceConcern, SourceFinding};
+pub use source_provider::{
+    DirectoryProvider, GithubReleasesProvider, SourceProvider, SourceProviderError,
+    SourceProviders, SourceRoutes,
+};
 pub use standalone::{StandaloneBundle, StandaloneGem, StandaloneOptions};
+pub use static_source::StaticSource;
 pub use trust_policy::{GemVerifier, TrustPolicy, VerificationError};
@@ -3,6 +3,7 @@
 //! Parses and generates Bundler-compatible Gemfile.lock files with support
 //! for GEM, GIT, PATH sections, platforms, and dependency specifications.
 
+use std::collections::BTreeSet;
 use std::fmt;
 use thiserror::Error;
 
@@ -100,6 +101,20 @@ pub struct PathGemSpec {
     pub groups: Vec<String>,
 }
 
+/// A `PLUGIN SOURCE` section: gems served by a source provider
+///
+/// The specs themselves live in [`Lockfile::gems`]; this records which of
+/// them come from `remote` rather than the gem server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginSourceSpec {
+    /// Source URI from the Gemfile
+    pub remote: String,
+    /// Provider type (`type:` in the Gemfile)
+    pub kind: String,
+    /// Names of the gems locked from this source
+    pub gems: Vec<String>,
+}
+
 /// Complete representation of a Gemfile.lock
 #[derive(Debug, Clone)]
 pub struct Lockfile {
@@ -109,6 +124,8 @@ pub struct Lockfile {
     pub git_gems: Vec<GitGemSpec>,
     /// Gems from local paths
     pub path_gems: Vec<PathGemSpec>,
+    /// Sources served by source providers
+    pub plugin_sources: Vec<PluginSourceSpec>,
     /// Supported platforms
     pub platforms: Vec<String>,
     /// Ruby version constraint
@@ -125,6 +142,7 @@ impl Lockfile {
             gems: Vec::new(),
             git_gems: Vec::new(),
             path_gems: Vec::new(),
+            plugin_sources: Vec::new(),
             platforms: Vec::new(),
             ruby_version: None,
             bundled_with: None,
@@ -139,6 +157,164 @@ impl Lockfile {
     pub fn parse(content: &str) -> Result<Self, LockfileError> {
         Parser::new(content).parse()
     }
+
+    /// Canonicalize, deduplicate and sort platforms
+    ///
+    /// Rewrites the PLATFORMS list and every gem platform through
+    /// [`crate::platform::normalize_platform`], then merges gem specs that
+    /// became identical (same name, version and platform), combining their
+    /// dependencies, groups and checksum.
+    pub fn normalize_platforms(&mut self) {
+        let mut platforms: Vec<String> = self
+            .platforms
+            .iter()
+            .map(|p| crate::platform::normalize_platform(p))
+            .collect();
+        platforms.sort();
+        platforms.dedup();
+        self.platforms = platforms;
+
+        let mut merged: Vec<GemSpec> = Vec::with_capacity(self.gems.len());
+        for gem in std::mem::take(&mut self.gems) {
+            let platform = gem
+                .platform
+                .as_deref()
+                .map(crate::platform::normalize_platform);
+
+            if let Some(existing) = merged
+                .iter_mut()
+                .find(|g| g.name == gem.name && g.version == gem.version && g.platform == platform)
+            {
+                for dep in gem.dependencies {
+                    if !existing.dependencies.iter().any(|d| d.name == dep.name) {
+                        existing.dependencies.push(dep);
+                    }
+                }
+                for group in gem.groups {
+                    if !existing.groups.contains(&group) {
+                        existing.groups.push(group);
+                    }
+                }
+                if existing.checksum.is_none() {
+                    existing.checksum = gem.checksum;
+                }
+                continue;
+            }
+
+            let mut spec = GemSpec::new(
+                gem.name,
+                gem.version,
+                platform,
+                gem.dependencies,
+                gem.groups,
+            );
+            spec.checksum = gem.checksum;
+            merged.push(spec);
+        }
+
+        merged.sort_by(|a, b| {
+            a.name
+                .cmp(&b.name)
+                .then_with(|| crate::gem_utils::compare_versions(&a.version, &b.version))
+                .then_with(|| a.platform.cmp(&b.platform))
+        });
+        for gem in &mut merged {
+            gem.dependencies.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        self.gems = merged;
+    }
+
+    /// Gemfile gems this lockfile does not satisfy
+    ///
+    /// A gem is unlocked when no GEM, GIT or PATH spec has its name, or when
+    /// no locked version meets its Gemfile requirement. Platform-specific
+    /// Gemfile entries are skipped, since a lockfile may legitimately omit
+    /// them for its platforms.
+    #[must_use]
+    pub fn unlocked_dependencies(&self, gemfile: &crate::gemfile::Gemfile) -> Vec<String> {
+        gemfile
+            .gems
+            .iter()
+            .filter(|gem| gem.platforms.is_empty())
+            .filter(|gem| {
+                let mut versions = self
+                    .gems
+                    .iter()
+                    .filter(|spec| spec.name == gem.name)
+                    .map(|spec| spec.version.as_str())
+                    .chain(
+                        self.git_gems
+                            .iter()
+                            .filter(|spec| spec.name == gem.name)
+                            .map(|spec| spec.version.as_str()),
+                    )
+                    .chain(
+                        self.path_gems
+                            .iter()
+                            .filter(|spec| spec.name == gem.name)
+                            .map(|spec| spec.version.as_str()),
+                    );
+                !versions.any(|version| {
+                    crate::gem_utils::requirement_satisfied(version, &gem.version_requirement)
+                })
+            })
+            .map(|gem| gem.name.clone())
+            .collect()
+    }
+
+    /// Names of `roots` and every locked gem they depend on, transitively
+    ///
+    /// Follows the dependencies recorded on GEM specs across all platform
+    /// variants; roots that are not locked are still included.
+    #[must_use]
+    pub fn dependency_closure(&self, roots: &[String]) -> BTreeSet<String> {
+        let mut reached = BTreeSet::new();
+        let mut pending: Vec<&str> = roots.iter().map(String::as_str).collect();
+        while let Some(name) = pending.pop() {
+            if !reached.insert(name.to_string()) {
+                continue;
+            }
+            for spec in self.gems.iter().filter(|spec| spec.name == name) {
+                pending.extend(
+                    spec.dependencies
+                        .iter()
+                        .map(|dep| dep.name.as_str())
+                        .filter(|dep| !reached.contains(*dep)),
+                );
+            }
+        }
+        reached
+    }
+
+    /// Locked gems the Gemfile still depends on, when no requirement changed
+    ///
+    /// Returns `None` when a Gemfile gem is unlocked or missing from the
+    /// lockfile, or when the lockfile has GIT or PATH gems, since only a
+    /// resolution can answer for those. Gems no Gemfile entry reaches any
+    /// more, such as the dependencies of a removed gem, are left out.
+    #[must_use]
+    pub fn satisfied_gems(&self, gemfile: &crate::gemfile::Gemfile) -> Option<Vec<&GemSpec>> {
+        if !self.git_gems.is_empty()
+            || !self.path_gems.is_empty()
+            || !self.unlocked_dependencies(gemfile).is_empty()
+        {
+            return None;
+        }
+        let roots: Vec<String> = gemfile.gems.iter().map(|gem| gem.name.clone()).collect();
+        if roots
+            .iter()
+            .any(|root| !self.gems.iter().any(|spec| &spec.name == root))
+        {
+            return None;
+        }
+        let reached = self.dependency_closure(&roots);
+        Some(
+            self.gems
+                .iter()
+                .filter(|spec| reached.contains(&spec.name))
+                .collect(),
+        )
+    }
 }
 
 impl Default for Lockfile {
@@ -147,6 +323,58 @@ impl Default for Lockfile {
     }
 }
 
+/// Rewrite a single GIT section of lockfile content in place
+///
+/// Only the block whose `remote:` matches `repository` is touched: its
+/// `revision:` line is replaced and any spec listed in `versions` gets the new
+/// version. Every other line, including other sections, is kept verbatim so
+/// re-locking one git source produces a minimal diff.
+#[must_use]
+pub fn update_git_source(
+    content: &str,
+    repository: &str,
+    revision: &str,
+    versions: &std::collections::BTreeMap<String, String>,
+) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut in_git = false;
+    let mut matching = false;
+
+    for line in content.lines() {
+        if line == "GIT" {
+            in_git = true;
+            matching = false;
+        } else if line.trim().is_empty() || !line.starts_with(' ') {
+            in_git = false;
+            matching = false;
+        }
+
+        let mut rewritten = None;
+        if in_git {
+            if let Some(remote) = line.strip_prefix("  remote: ") {
+                matching = remote.trim() == repository;
+            } else if matching && line.starts_with("  revision: ") {
+                rewritten = Some(format!("  revision: {revision}"));
+            } else if matching
+                && line.starts_with("    ")
+                && !line.starts_with("     ")
+                && let Some((name, _)) = line.trim().split_once(" (")
+                && let Some(version) = versions.get(name)
+            {
+                rewritten = Some(format!("    {name} ({version})"));
+            }
+        }
+
+        output.push_str(rewritten.as_deref().unwrap_or(line));
+        output.push('\n');
+    }
+
+    if !content.ends_with('\n') {
+        output.pop();
+    }
+    output
+}
+
 #[derive(Debug, Error)]
 pub enum LockfileError {
     #[error("failed to parse lockfile at line {line}: {message}")]
@@ -200,6 +428,10 @@ impl<'a> Parser<'a> {
                     self.advance();
                     self.parse_path_section(&mut lockfile);
                 }
+                "PLUGIN SOURCE" => {
+                    self.advance();
+                    self.parse_plugin_source_section(&mut lockfile)?;
+                }
                 "PLATFORMS" => {
                     self.advance();
                     self.parse_platforms(&mut lockfile);
@@ -559,6 +791,57 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn parse_plugin_source_section(
+        &mut self,
+        lockfile: &mut Lockfile,
+    ) -> Result<(), LockfileError> {
+        // PLUGIN SOURCE
+        //   remote: https://github.com/acme/widgets
+        //   type: github
+        //   specs:
+        //     widgets (1.1.0)
+        //       rack (>= 2.0)
+        let mut source = PluginSourceSpec {
+            remote: String::new(),
+            kind: String::new(),
+            gems: Vec::new(),
+        };
+        while !self.is_eof() && self.current().starts_with("  ") {
+            let line = self.current().trim();
+            if line == "specs:" {
+                break;
+            }
+            if let Some(remote) = line.strip_prefix("remote:") {
+                source.remote = remote.trim().to_string();
+            } else if let Some(kind) = line.strip_prefix("type:") {
+                source.kind = kind.trim().to_string();
+            }
+            self.advance();
+        }
+
+        if !self.is_eof() && self.current().trim() == "specs:" {
+            self.advance();
+
+            while !self.is_eof() {
+                let line = self.current();
+                if !line.starts_with("    ") && !line.is_empty() {
+                    break;
+                }
+
+                if line.starts_with("    ") && !line.starts_with("      ") {
+                    let gem = self.parse_gem_spec()?;
+                    source.gems.push(gem.name.clone());
+                    lockfile.gems.push(gem);
+                } else {
+                    self.advance();
+                }
+            }
+        }
+
+        lockfile.plugin_sources.push(source);
+        Ok(())
+    }
+
     fn parse_platforms(&mut self, lockfile: &mut Lockfile) {
         while !self.is_eof() {
             let line = self.current();
@@ -685,35 +968,25 @@ impl fmt::Display for Lockfile {
     /// Generates the exact format that Bundler expects. The order matters:
     /// GEM, GIT, PATH, PLATFORMS, DEPENDENCIES, RUBY VERSION, BUNDLED WITH
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let from_plugin_source = |gem: &GemSpec| {
+            self.plugin_sources
+                .iter()
+                .any(|source| source.gems.contains(&gem.name))
+        };
+
         // GEM section
-        if !self.gems.is_empty() {
+        let server_gems: Vec<&GemSpec> = self
+            .gems
+            .iter()
+            .filter(|gem| !from_plugin_source(gem))
+            .collect();
+        if !server_gems.is_empty() {
             writeln!(f, "GEM")?;
 
             // Group gems by source (for now, assume all from gems.coop)
             writeln!(f, "  remote: {}/", crate::DEFAULT_GEM_SOURCE)?;
             writeln!(f, "  specs:")?;
-
-            // Sort gems alphabetically
-            let mut sorted_gems = self.gems.clone();
-            sorted_gems.sort_by(|a, b| a.name.cmp(&b.name));
-
-            for gem in &sorted_gems {
-                // Write gem line with platform if present
-                if let Some(ref platform) = gem.platform {
-                    writeln!(f, "    {} ({}-{})", gem.name, gem.version, platform)?;
-                } else {
-                    writeln!(f, "    {} ({})", gem.name, gem.version)?;
-                }
-
-                // Write dependencies (indented with 6 spaces)
-                for dep in &gem.dependencies {
-                    if dep.requirement.is_empty() || dep.requirement == ">= 0" {
-                        writeln!(f, "      {}", dep.name)?;
-                    } else {
-                        writeln!(f, "      {} ({})", dep.name, dep.requirement)?;
-                    }
-                }
-            }
+            write_specs(f, server_gems)?;
             writeln!(f)?;
         }
 
@@ -761,6 +1034,22 @@ impl fmt::Display for Lockfile {
             }
         }
 
+        // PLUGIN SOURCE sections
+        for source in &self.plugin_sources {
+            writeln!(f, "PLUGIN SOURCE")?;
+            writeln!(f, "  remote: {}", source.remote)?;
+            writeln!(f, "  type: {}", source.kind)?;
+            writeln!(f, "  specs:")?;
+            write_specs(
+                f,
+                self.gems
+                    .iter()
+                    .filter(|gem| source.gems.contains(&gem.name))
+                    .collect(),
+            )?;
+            writeln!(f)?;
+        }
+
         // PLATFORMS section
         if !self.platforms.is_empty() {
             writeln!(f, "PLATFORMS")?;
@@ -815,6 +1104,30 @@ impl fmt::Display for Lockfile {
     }
 }
 
+/// Spec lines with their indented dependencies, sorted by gem name
+fn write_specs(f: &mut fmt::Formatter<'_>, mut gems: Vec<&GemSpec>) -> fmt::Result {
+    gems.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for gem in gems {
+        // Write gem line with platform if present
+        if let Some(ref platform) = gem.platform {
+            writeln!(f, "    {} ({}-{})", gem.name, gem.version, platform)?;
+        } else {
+            writeln!(f, "    {} ({})", gem.name, gem.version)?;
+        }
+
+        // Write dependencies (indented with 6 spaces)
+        for dep in &gem.dependencies {
+            if dep.requirement.is_empty() || dep.requirement == ">= 0" {
+                writeln!(f, "      {}", dep.name)?;
+            } else {
+                writeln!(f, "      {} ({})", dep.name, dep.requirement)?;
+            }
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1048,6 +1361,45 @@ PLATFORMS
         }
     }
 
+    mod git_source {
+        use super::*;
+
+        #[test]
+        fn update_git_source_only_touches_matching_block() {
+            let content = "\
+GIT
+  remote: https://github.com/rack/rack
+  revision: aaaa
+  branch: main
+  specs:
+    rack (3.0.0)
+
+GIT
+  remote: https://github.com/other/other
+  revision: bbbb
+  specs:
+    other (1.0.0)
+
+GEM
+  remote: https://rubygems.org/
+  specs:
+    rack-test (2.1.0)
+      rack (>= 1.3)
+";
+            let versions =
+                std::collections::BTreeMap::from([("rack".to_string(), "3.1.0".to_string())]);
+            let updated =
+                update_git_source(content, "https://github.com/rack/rack", "cccc", &versions);
+
+            assert!(updated.contains("  revision: cccc\n  branch: main"));
+            assert!(updated.contains("    rack (3.1.0)"));
+            assert!(updated.contains("  revision: bbbb"));
+            assert!(updated.contains("    other (1.0.0)"));
+            assert!(updated.contains("      rack (>= 1.3)"));
+            assert_eq!(updated.lines().count(), content.lines().count());
+        }
+    }
+
     mod gem_spec {
         use super::*;
 
@@ -1094,6 +1446,193 @@ PLATFORMS
     mod lockfile {
         use super::*;
 
+        #[test]
+        fn plugin_source_round_trip() {
+            let content = "GEM
+  remote: https://rubygems.org/
+  specs:
+    rack (3.0.8)
+
+PLUGIN SOURCE
+  remote: https://github.com/acme/widgets
+  type: github
+  specs:
+    widgets (1.1.0)
+      rack (>= 2.0)
+
+PLATFORMS
+  ruby
+
+";
+            let lockfile = Lockfile::parse(content).expect("parse");
+
+            assert_eq!(lockfile.gems.len(), 2);
+            assert_eq!(
+                lockfile.plugin_sources,
+                vec![PluginSourceSpec {
+                    remote: "https://github.com/acme/widgets".to_string(),
+                    kind: "github".to_string(),
+                    gems: vec!["widgets".to_string()],
+                }]
+            );
+            assert_eq!(lockfile.to_string(), content);
+        }
+
+        #[test]
+        fn unlocked_dependencies_reports_missing_and_unsatisfied_gems() {
+            let lockfile = Lockfile::parse(
+                "GEM
+  remote: https://rubygems.org/
+  specs:
+    rack (2.2.8)
+    rake (13.1.0)
+
+PLATFORMS
+  ruby
+",
+            )
+            .expect("parse");
+            let mut gemfile = crate::gemfile::Gemfile::parse(
+                "source \"https://rubygems.org\"
+gem \"rake\"
+gem \"rack\", \"~> 3.0\"
+gem \"puma\"
+",
+            )
+            .expect("gemfile");
+            let mut wdm = crate::gemfile::GemDependency::new("wdm");
+            wdm.platforms = vec!["windows".to_string()];
+            gemfile.gems.push(wdm);
+
+            assert_eq!(
+                lockfile.unlocked_dependencies(&gemfile),
+                vec!["rack".to_string(), "puma".to_string()]
+            );
+        }
+
+        #[test]
+        fn dependency_closure_follows_locked_dependencies() {
+            let lockfile = Lockfile::parse(
+                "GEM
+  remote: https://rubygems.org/
+  specs:
+    actionpack (7.1.0)
+      rack (>= 2.2.4)
+      rack-test (>= 0.6.3)
+    rack (3.0.8)
+    rack-test (2.1.0)
+      rack (>= 1.3)
+    rake (13.1.0)
+
+PLATFORMS
+  ruby
+",
+            )
+            .expect("parse");
+
+            let closure =
+                lockfile.dependency_closure(&["rack-test".to_string(), "puma".to_string()]);
+            assert_eq!(
+                closure.into_iter().collect::<Vec<_>>(),
+                vec!["puma", "rack", "rack-test"]
+            );
+        }
+
+        #[test]
+        fn satisfied_gems_drops_unreachable_specs() {
+            let lockfile = Lockfile::parse(
+                "GEM
+  remote: https://rubygems.org/
+  specs:
+    rack (3.0.8)
+    rack-test (2.1.0)
+      rack (>= 1.3)
+    rake (13.1.0)
+
+PLATFORMS
+  ruby
+",
+            )
+            .expect("parse");
+            let gemfile = crate::gemfile::Gemfile::parse(
+                "source \"https://rubygems.org\"
+gem \"rack-test\"
+",
+            )
+            .expect("gemfile");
+            let names: Vec<&str> = lockfile
+                .satisfied_gems(&gemfile)
+                .expect("satisfied")
+                .iter()
+                .map(|spec| spec.name.as_str())
+                .collect();
+            assert_eq!(names, vec!["rack", "rack-test"]);
+
+            let changed = crate::gemfile::Gemfile::parse(
+                "source \"https://rubygems.org\"
+gem \"rack-test\"
+gem \"rack\", \"~> 2.0\"
+",
+            )
+            .expect("gemfile");
+            assert!(lockfile.satisfied_gems(&changed).is_none());
+        }
+
+        #[test]
+        fn normalize_platforms_merges_equivalent_specs() {
+            let mut lockfile = Lockfile::new();
+            lockfile.platforms = vec![
+                "x86_64-linux-gnu".to_string(),
+                "universal-darwin-23".to_string(),
+                "x86_64-linux".to_string(),
+                "ruby".to_string(),
+            ];
+
+            let mut gnu = GemSpec::new(
+                "nokogiri".to_string(),
+                "1.16.0".to_string(),
+                Some("x86_64-linux-gnu".to_string()),
+                vec![Dependency {
+                    name: "racc".to_string(),
+                    requirement: "~> 1.4".to_string(),
+                }],
+                vec!["default".to_string()],
+            );
+            gnu.checksum = Some("abc".to_string());
+            lockfile.gems.push(gnu);
+            lockfile.gems.push(GemSpec::new(
+                "nokogiri".to_string(),
+                "1.16.0".to_string(),
+                Some("x86_64-linux".to_string()),
+                vec![],
+                vec!["test".to_string()],
+            ));
+            lockfile.gems.push(GemSpec::new(
+                "ffi".to_string(),
+                "1.17.0".to_string(),
+                Some("arm64-darwin23".to_string()),
+                vec![],
+                vec![],
+            ));
+
+            lockfile.normalize_platforms();
+
+            assert_eq!(
+                lockfile.platforms,
+                vec!["ruby", "universal-darwin", "x86_64-linux"]
+            );
+            assert_eq!(lockfile.gems.len(), 2);
+
+            let ffi = lockfile.gems.first().expect("ffi");
+            assert_eq!(ffi.full_name_with_platform(), "ffi-1.17.0-arm64-darwin");
+
+            let nokogiri = lockfile.gems.get(1).expect("nokogiri");
+            assert_eq!(nokogiri.platform.as_deref(), Some("x86_64-linux"));
+            assert_eq!(nokogiri.dependencies.len(), 1);
+            assert_eq!(nokogiri.groups, vec!["default", "test"]);
+            assert_eq!(nokogiri.checksum.as_deref(), Some("abc"));
+        }
+
         #[test]
         fn new_creates_empty() {
             let lockfile = Lockfile::new();
@@ -2,7 +2,8 @@
 //!
 //! Bundler and `RubyGems` compatible package manager for Ruby
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::ffi::OsString;
 use std::process;
 
 /// Note: backtrace display is controlled by the `--backtrace` flag
@@ -11,14 +12,35 @@ fn setup_backtrace(_enabled: bool) {
     // Backtrace display is handled in display_error() function
 }
 
+/// Print the `--profile` summary and write the trace, if profiling
+fn report_profile(profiler: &lode::Profiler, trace: Option<&str>) -> anyhow::Result<()> {
+    if !profiler.is_enabled() {
+        return Ok(());
+    }
+    eprint!("\n{}", profiler.summary());
+    if let Some(path) = trace {
+        profiler
+            .write_chrome_trace(std::path::Path::new(path))
+            .map_err(|e| anyhow::anyhow!("Failed to write profile trace to {path}: {e}"))?;
+        eprintln!("Wrote Chrome trace to {path}");
+    }
+    Ok(())
+}
+
 /// Display an error with optional backtrace information
 fn display_error(err: &anyhow::Error, backtrace_enabled: bool) {
-    eprintln!("error: {err}");
+    eprintln!(
+        "{} {err}",
+        lode::reporter::error(lode::messages::text(lode::messages::Message::ErrorLabel))
+    );
 
     // Show error chain
     let mut source = err.source();
     while let Some(err) = source {
-        eprintln!("caused by: {err}");
+        eprintln!(
+            "{} {err}",
+            lode::messages::text(lode::messages::Message::CausedBy)
+        );
         source = err.source();
     }
 
@@ -37,21 +59,108 @@ fn display_error(err: &anyhow::Error, backtrace_enabled: bool) {
 #[command(version = env!("CARGO_PKG_VERSION"))]
 #[command(about = "A Ruby package manager", long_about = None)]
 #[command(disable_version_flag = true)]
+#[command(disable_help_subcommand = true)]
 pub(crate) struct Cli {
     /// Print version
     #[arg(short = 'v', long = "version", action = clap::ArgAction::Version)]
     _version: Option<bool>,
 
+    /// When to color output (`NO_COLOR` and `BUNDLE_NO_COLOR` turn off `auto`)
+    #[arg(long, global = true, value_enum, default_value_t = ColorWhen::Auto)]
+    color: ColorWhen,
+
+    /// Write log lines to this file instead of stderr (also `LODE_LOG_FILE`)
+    #[arg(long, global = true)]
+    log_file: Option<String>,
+
+    /// Format of log lines (also `LODE_LOG_FORMAT`)
+    #[arg(long, global = true, value_enum)]
+    log_format: Option<LogFormat>,
+
+    /// Ceiling on requests per second to each gem server (also
+    /// `LODE_MAX_REQUESTS_PER_SECOND` and the `max_requests_per_second` config key)
+    #[arg(long, global = true, value_name = "N")]
+    max_requests_per_second: Option<f64>,
+
+    /// Print extra detail (also `LODE_VERBOSITY=verbose` and `BUNDLE_VERBOSE`)
+    #[arg(short = 'V', long, global = true)]
+    verbose: bool,
+
+    /// Print only warnings and errors (also `LODE_VERBOSITY=quiet`)
+    #[arg(short = 'q', long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Print only errors (also `LODE_VERBOSITY=silent`)
+    #[arg(long, global = true, conflicts_with_all = ["verbose", "quiet"])]
+    silent: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// Values for the global `--color` flag
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ColorWhen {
+    Auto,
+    Always,
+    Never,
+}
+
+impl From<ColorWhen> for lode::reporter::ColorChoice {
+    fn from(when: ColorWhen) -> Self {
+        match when {
+            ColorWhen::Auto => Self::Auto,
+            ColorWhen::Always => Self::Always,
+            ColorWhen::Never => Self::Never,
+        }
+    }
+}
+
+/// Values for `lode lock --strategy`
+#[derive(Clone, Copy, Default, clap::ValueEnum)]
+enum ResolutionStrategy {
+    /// Prefer the newest allowed versions
+    #[default]
+    Latest,
+    /// Prefer the currently locked versions
+    Minimal,
+    /// Prefer the oldest allowed versions, to test lower bounds
+    OldestCompatible,
+}
+
+impl From<ResolutionStrategy> for lode::ResolutionStrategy {
+    fn from(strategy: ResolutionStrategy) -> Self {
+        match strategy {
+            ResolutionStrategy::Latest => Self::Latest,
+            ResolutionStrategy::Minimal => Self::Minimal,
+            ResolutionStrategy::OldestCompatible => Self::OldestCompatible,
+        }
+    }
+}
+
+/// Values for the global `--log-format` flag
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+impl From<LogFormat> for lode::debug::LogFormat {
+    fn from(format: LogFormat) -> Self {
+        match format {
+            LogFormat::Text => Self::Text,
+            LogFormat::Json => Self::Json,
+        }
+    }
+}
+
 #[derive(Subcommand)]
 #[allow(clippy::large_enum_variant)]
 enum Commands {
     /// Install gems from Gemfile.lock
     Install {
-        /// Path to Gemfile (lockfile will be derived as Gemfile.lock)
+        /// Path to Gemfile (lockfile will be derived as Gemfile.lock), or a glob
+        /// such as 'gemfiles/*.gemfile' to install each one in turn
         #[arg(long)]
         gemfile: Option<String>,
 
@@ -59,14 +168,6 @@ enum Commands {
         #[arg(long, visible_alias = "force")]
         redownload: bool,
 
-        /// Enable verbose output including extension build logs
-        #[arg(long)]
-        verbose: bool,
-
-        /// Suppress all output except errors
-        #[arg(long, short, conflicts_with = "verbose")]
-        quiet: bool,
-
         /// Number of concurrent downloads (Bundler: --jobs/-j)
         #[arg(long, short = 'j', alias = "workers")]
         jobs: Option<usize>,
@@ -99,9 +200,67 @@ enum Commands {
         #[arg(long)]
         full_index: bool,
 
+        /// Refuse to change Gemfile.lock, overriding config (`BUNDLE_FROZEN`)
+        #[arg(long, overrides_with = "no_frozen")]
+        frozen: bool,
+
+        /// Allow Gemfile.lock changes even if config or deployment mode freezes
+        /// the bundle (negation of --frozen)
+        #[arg(long, overrides_with = "frozen")]
+        no_frozen: bool,
+
         /// Use alternative rbconfig for native extensions (for cross-compilation)
         #[arg(long)]
         target_rbconfig: Option<String>,
+
+        /// Build native extensions for this CPU arch instead of Ruby's:
+        /// `arm64`, `x86_64`, or `universal` for a fat binary (sets `ARCHFLAGS`)
+        #[arg(long, value_name = "ARCH")]
+        arch: Option<String>,
+
+        /// Install gems and executables into DIR, isolated from user and
+        /// system gems, and remember it for this project (`vendor_dir` and
+        /// `disable_shared_gems` in .lode.toml)
+        #[arg(long, value_name = "DIR")]
+        path: Option<String>,
+
+        /// Symlink path gems into the bundle instead of copying (live edits)
+        #[arg(long)]
+        link_path_gems: bool,
+
+        /// Install the groups chosen by a named profile from config
+        /// (`lode config profile.NAME.without GROUPS`)
+        #[arg(long, value_name = "NAME")]
+        group_profile: Option<String>,
+
+        /// Install only these groups and their dependencies (`BUNDLE_ONLY`)
+        #[arg(
+            long,
+            value_name = "GROUP[,GROUP]",
+            value_delimiter = ',',
+            conflicts_with = "group_profile"
+        )]
+        only: Vec<String>,
+
+        /// Keep running and reinstall when the Gemfile or path gems change
+        #[arg(long, conflicts_with = "standalone")]
+        watch: bool,
+
+        /// Show what would be downloaded and built without changing anything
+        #[arg(long, conflicts_with_all = ["watch", "standalone"])]
+        dry_run: bool,
+
+        /// Continue an interrupted install, skipping the gems it finished
+        #[arg(long, conflicts_with_all = ["watch", "dry_run", "redownload"])]
+        resume: bool,
+
+        /// Print a per-phase and per-gem timing breakdown
+        #[arg(long, conflicts_with = "watch")]
+        profile: bool,
+
+        /// Also write the timings as Chrome trace JSON (implies --profile)
+        #[arg(long, value_name = "FILE", conflicts_with = "watch")]
+        profile_trace: Option<String>,
     },
 
     /// Update gems to their latest versions within constraints
@@ -125,10 +284,6 @@ enum Commands {
         #[arg(long, short = 'j')]
         jobs: Option<usize>,
 
-        /// Suppress all output except errors
-        #[arg(long)]
-        quiet: bool,
-
         /// Number of times to retry failed requests
         #[arg(long)]
         retry: Option<usize>,
@@ -180,6 +335,33 @@ enum Commands {
         /// Use full gem index instead of dependency API
         #[arg(long)]
         full_index: bool,
+
+        /// Only update gems with known advisories, each to its smallest
+        /// patched version the Gemfile allows
+        #[arg(long, conflicts_with_all = ["bot", "all", "conservative", "patch", "minor", "major"])]
+        security: bool,
+
+        /// Suggest which Gemfile requirements to relax so gems can update,
+        /// by resolving again without each one (changes nothing)
+        #[arg(long, conflicts_with_all = ["bot", "security"])]
+        suggest_relaxations: bool,
+
+        /// Commit grouped updates to branches and print a JSON report for
+        /// opening pull requests (leaves the working tree untouched)
+        #[arg(long)]
+        bot: bool,
+
+        /// How --bot splits updates into branches
+        #[arg(long, value_enum, default_value = "gem", requires = "bot")]
+        group_by: commands::update::BotGrouping,
+
+        /// Prefix for --bot branch names
+        #[arg(long, default_value = "lode/update/", requires = "bot")]
+        branch_prefix: String,
+
+        /// Write the --bot report to a file instead of stdout
+        #[arg(long, value_name = "FILE", requires = "bot")]
+        report: Option<String>,
     },
 
     /// Package your needed .gem files into vendor/cache
@@ -205,9 +387,8 @@ enum Commands {
         #[arg(long)]
         no_install: bool,
 
-        /// Only output warnings and errors
-        #[arg(long)]
-        quiet: bool,
+        #[command(subcommand)]
+        command: Option<CacheCommands>,
     },
 
     /// Run commands with lode-managed environment
@@ -219,6 +400,47 @@ enum Commands {
         /// Path to Gemfile
         #[arg(long)]
         gemfile: Option<String>,
+
+        /// Load only the gems these groups need (comma-separated or
+        /// repeated), from the manifests `lode install` writes
+        #[arg(long, value_name = "GROUP", value_delimiter = ',')]
+        only_group: Vec<String>,
+
+        /// Take the load path from the activation manifest `lode install`
+        /// writes, skipping the gem directory scan
+        #[arg(long, conflicts_with = "only_group")]
+        cached_setup: bool,
+
+        /// Start from a clean environment: drop inherited `GEM_HOME`, `GEM_PATH`,
+        /// `RUBYOPT`, `RUBYLIB` and the variables in the `exec_unset_env` config
+        #[arg(long)]
+        isolate: bool,
+    },
+
+    /// Run a command with the environment from before `lode exec` or
+    /// `lode shell` activated the bundle
+    WithUnbundledEnv {
+        /// Command to execute
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+
+    /// Check this machine's toolchain against `lode lock --emit-metadata`
+    VerifyEnv {
+        /// Path to Gemfile
+        #[arg(long)]
+        gemfile: Option<String>,
+    },
+
+    /// Spawn a subshell with the bundle environment activated
+    Shell {
+        /// Print `export` statements for `eval "$(lode shell --print-env)"` instead
+        #[arg(long)]
+        print_env: bool,
+
+        /// Path to Gemfile
+        #[arg(long)]
+        gemfile: Option<String>,
     },
 
     /// Get and set Bundler configuration options
@@ -245,6 +467,12 @@ enum Commands {
         local: bool,
     },
 
+    /// List the group profiles `lode install --group-profile` can apply
+    Profile {
+        /// Show only this profile
+        name: Option<String>,
+    },
+
     /// Add gems to Gemfile
     Add {
         /// Name of the gem to add
@@ -298,13 +526,25 @@ enum Commands {
         #[arg(long, conflicts_with = "strict")]
         optimistic: bool,
 
-        /// Suppress progress output
-        #[arg(long)]
-        quiet: bool,
+        /// Pin at the version in Gemfile.lock, as when promoting a transitive dependency
+        #[arg(long, conflicts_with = "version")]
+        version_from_lockfile: bool,
+
+        /// Segments kept in an inferred `~>` constraint (`minor` gives ~> X.Y, `patch` ~> X.Y.Z)
+        #[arg(long, value_enum, default_value = "minor")]
+        pessimistic_depth: commands::add::PessimisticDepth,
 
         /// Skip running `bundle install` after adding (for Bundler compatibility)
         #[arg(long)]
         skip_install: bool,
+
+        /// Add the gem even if its name looks like a typo of a popular gem
+        #[arg(long)]
+        yes: bool,
+
+        /// Re-resolve every gem instead of only those the new gem affects
+        #[arg(long)]
+        full_resolve: bool,
     },
 
     /// Generate binstubs for gem executables
@@ -327,6 +567,14 @@ enum Commands {
         /// Install binstubs for all platforms
         #[arg(long)]
         all_platforms: bool,
+
+        /// Generate stubs that load the standalone bundle instead of Bundler
+        #[arg(long)]
+        standalone: bool,
+
+        /// Directory to write binstubs to (remembered in .lode.toml)
+        #[arg(long)]
+        path: Option<String>,
     },
 
     /// Verify all gems are installed
@@ -335,11 +583,34 @@ enum Commands {
         #[arg(long)]
         gemfile: Option<String>,
 
+        /// Check every gemfile matching a glob (e.g. 'gemfiles/*.gemfile') against its lockfile
+        #[arg(long, value_name = "GLOB", conflicts_with_all = ["gemfile", "dry_run"])]
+        gemfiles: Option<String>,
+
         /// Show what would be checked without checking
         #[arg(long)]
         dry_run: bool,
     },
 
+    /// Generate Appraisal-style gemfiles and lockfiles from a dependency matrix
+    Matrix {
+        /// Matrix definition (TOML or YAML; defaults to matrix.toml, matrix.yml or matrix.yaml)
+        #[arg(long)]
+        file: Option<String>,
+
+        /// Base Gemfile the variants start from
+        #[arg(long)]
+        gemfile: Option<String>,
+
+        /// Directory to write the variant gemfiles to
+        #[arg(long, default_value = "gemfiles")]
+        output: String,
+
+        /// Only write the gemfiles, without locking them
+        #[arg(long)]
+        no_lock: bool,
+    },
+
     /// Show the source location of a gem
     Show {
         /// Name of the gem (optional when using --paths)
@@ -348,6 +619,42 @@ enum Commands {
         /// List all gem paths instead of showing a single gem
         #[arg(long)]
         paths: bool,
+
+        /// Audit the gem's provenance: source, lockfile checksum, installed
+        /// digest, signature and git revision
+        #[arg(long, requires = "gem", conflicts_with = "paths")]
+        verify: bool,
+    },
+
+    /// Report installed size per gem and per dependency tree
+    Size {
+        /// Path to Gemfile.lock
+        #[arg(long, default_value = "Gemfile.lock")]
+        lockfile: String,
+
+        /// Print the full report as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Number of gems and dependency trees to list
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+
+    /// Show cache sizes, hit rates and other statistics Lode has collected
+    Stats {
+        #[command(subcommand)]
+        command: Option<StatsCommands>,
+
+        /// Print the statistics as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Generate gem server indexes for a directory of gems
+    Index {
+        #[command(subcommand)]
+        command: IndexCommands,
     },
 
     /// List gems with newer versions available
@@ -356,10 +663,15 @@ enum Commands {
         #[arg(long, default_value = "Gemfile.lock")]
         lockfile: String,
 
-        /// Output in machine-readable format
+        /// Output in machine-readable format (tab-separated: name, current,
+        /// latest, groups, requirement, source, severity)
         #[arg(long)]
         parseable: bool,
 
+        /// Output format
+        #[arg(long, value_enum, default_value = "text")]
+        format: commands::outdated::OutputFormat,
+
         /// Only show gems with major version updates
         #[arg(long, conflicts_with_all = ["minor", "patch"])]
         major: bool,
@@ -379,6 +691,29 @@ enum Commands {
         /// Only check gems from a specific group
         #[arg(long)]
         group: Option<String>,
+
+        /// Exit nonzero when any gem is outdated (same as `--fail-level patch`)
+        #[arg(long, conflicts_with = "fail_level")]
+        exit_code: bool,
+
+        /// Exit nonzero when a gem is outdated at this level or above;
+        /// `security` fails only for locked versions with an advisory
+        #[arg(long, value_enum, value_name = "LEVEL")]
+        fail_level: Option<commands::outdated::FailLevel>,
+    },
+
+    /// Keep gems at their locked versions through `update` and `lock --update`
+    Hold {
+        /// Gems to hold (lists held gems when omitted)
+        gems: Vec<String>,
+
+        /// Why the gems are held, shown whenever they are skipped
+        #[arg(long, conflicts_with = "release")]
+        reason: Option<String>,
+
+        /// Release the holds on the given gems
+        #[arg(long, requires = "gems")]
+        release: bool,
     },
 
     /// Open a gem's source code in your editor
@@ -414,14 +749,14 @@ enum Commands {
         #[arg(long, num_args(0..))]
         update: Vec<String>,
 
+        /// Let held gems (`lode hold`) move to new versions
+        #[arg(long)]
+        release_hold: bool,
+
         /// Print lockfile to stdout instead of writing to file
         #[arg(long)]
         print: bool,
 
-        /// Enable verbose output
-        #[arg(short, long)]
-        verbose: bool,
-
         /// Prefer updating only to next patch version
         #[arg(long, conflicts_with_all = ["minor", "major"])]
         patch: bool,
@@ -442,6 +777,15 @@ enum Commands {
         #[arg(long)]
         conservative: bool,
 
+        /// Only re-resolve gems reachable from changed Gemfile requirements,
+        /// keeping the rest locked (falls back to full resolution on conflict)
+        #[arg(long, conflicts_with_all = ["update", "patch", "minor"])]
+        incremental: bool,
+
+        /// Which allowed versions to prefer when resolving
+        #[arg(long, value_enum, default_value_t = ResolutionStrategy::Latest)]
+        strategy: ResolutionStrategy,
+
         /// Do not attempt to connect to rubygems.org (use cached gems only)
         #[arg(long)]
         local: bool,
@@ -466,9 +810,43 @@ enum Commands {
         #[arg(long)]
         full_index: bool,
 
-        /// Quiet output (suppress messages)
-        #[arg(long, short = 'q')]
-        quiet: bool,
+        /// Record the build toolchain next to the lockfile (checked by `lode verify-env`)
+        #[arg(long)]
+        emit_metadata: bool,
+
+        /// Print a per-phase and per-gem timing breakdown
+        #[arg(long)]
+        profile: bool,
+
+        /// Also write the timings as Chrome trace JSON (implies --profile)
+        #[arg(long, value_name = "FILE")]
+        profile_trace: Option<String>,
+    },
+
+    /// Resolve a Gemfile and print the result without writing a lockfile
+    Resolve {
+        /// Path to Gemfile, or `-` to read it from stdin
+        gemfile: Option<String>,
+
+        /// Extra requirement to resolve with, e.g. 'rails >= 7.1' (repeatable)
+        #[arg(long = "add", value_name = "REQUIREMENT")]
+        add: Vec<String>,
+
+        /// Platform to resolve for (repeatable; defaults to the current one)
+        #[arg(long = "platform")]
+        platform: Vec<String>,
+
+        /// Allow prerelease versions
+        #[arg(long)]
+        pre: bool,
+
+        /// Use only cached metadata (no network requests)
+        #[arg(long)]
+        local: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
     },
 
     /// Create a new Gemfile
@@ -532,15 +910,46 @@ enum Commands {
         force: bool,
     },
 
+    /// Check the bundle for supply-chain risks
+    Audit {
+        /// Path to Gemfile
+        #[arg(long)]
+        gemfile: Option<String>,
+
+        /// Flag gems a public source could shadow (dependency confusion)
+        #[arg(long)]
+        sources: bool,
+    },
+
     /// Diagnose common Bundler problems
     Doctor {
         /// Path to Gemfile
         #[arg(long)]
         gemfile: Option<String>,
 
-        /// Only output warnings and errors
+        /// Check the lockfile against its sources instead: yanked versions,
+        /// registry checksums, published platforms and git revisions
+        #[arg(long)]
+        remote: bool,
+
+        /// Print the --remote findings as JSON
+        #[arg(long, requires = "remote")]
+        json: bool,
+    },
+
+    /// Replace this lode binary with the newest release
+    ///
+    /// The download is checked against the checksum published with the
+    /// release. That catches a corrupt download, but it is not a signature
+    /// and cannot detect a tampered release.
+    SelfUpdate {
+        /// Release channel to follow
+        #[arg(long, value_enum, default_value = "stable")]
+        channel: commands::self_update::Channel,
+
+        /// Only report whether an update is available
         #[arg(long)]
-        quiet: bool,
+        check: bool,
     },
 
     /// Remove gems from Gemfile
@@ -548,9 +957,9 @@ enum Commands {
         /// Name(s) of gem(s) to remove
         gems: Vec<String>,
 
-        /// Quiet output (suppress messages)
-        #[arg(long, short = 'q')]
-        quiet: bool,
+        /// Re-resolve every gem instead of dropping only the removed ones
+        #[arg(long)]
+        full_resolve: bool,
     },
 
     /// List all gems in the current bundle
@@ -584,6 +993,14 @@ enum Commands {
         /// Print gem version
         #[arg(long)]
         version: bool,
+
+        /// Print the locked version's full gemspec as JSON
+        #[arg(long, conflicts_with_all = ["path", "version", "spec_yaml"])]
+        spec_json: bool,
+
+        /// Print the locked version's full gemspec as YAML
+        #[arg(long, conflicts_with_all = ["path", "version"])]
+        spec_yaml: bool,
     },
 
     /// Search for gems on RubyGems.org
@@ -606,6 +1023,11 @@ enum Commands {
     Which {
         /// File name to search for (e.g., "rake", "rack.rb")
         file: String,
+
+        /// List every copy in every gem directory, including older versions
+        /// and ones shadowed by an earlier `GEM_PATH` entry
+        #[arg(long)]
+        all_paths: bool,
     },
 
     /// List all files in an installed gem
@@ -637,17 +1059,9 @@ enum Commands {
         #[arg(long = "show-install-dir")]
         show_install_dir: bool,
 
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress progress)
-        #[arg(short = 'q', long)]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long)]
-        silent: bool,
+        /// Compare installed files against the cached .gem (modified/missing/extra)
+        #[arg(long, conflicts_with = "show_install_dir")]
+        diff: bool,
 
         /// Config file path (overrides default)
         #[arg(long)]
@@ -687,18 +1101,6 @@ enum Commands {
         #[arg(short = 'P', long)]
         trust_policy: Option<String>,
 
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long, conflicts_with = "verbose")]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long, conflicts_with_all = ["verbose", "quiet"])]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long = "config-file")]
         config_file: Option<String>,
@@ -716,9 +1118,38 @@ enum Commands {
         norc: bool,
     },
 
+    /// Smoke test a built .gem before pushing it
+    ///
+    /// Installs the gem with its runtime dependencies into a throwaway
+    /// prefix, requires its files with the detected Ruby and runs its
+    /// executables with --version or --help.
+    TestInstall {
+        /// Gem file to test, as written by `lode gem-build`
+        gem: String,
+
+        /// Keep the temporary install prefix and print its location
+        #[arg(long)]
+        keep: bool,
+    },
+
     /// Show environment information
     Env,
 
+    /// Collect redacted diagnostics for a lode bug report
+    Issue {
+        /// Path to Gemfile
+        #[arg(long)]
+        gemfile: Option<String>,
+
+        /// Write the tarball here instead of ./lode-issue-<timestamp>.tar.gz
+        #[arg(short, long, conflicts_with = "url")]
+        output: Option<String>,
+
+        /// Print a pre-filled GitHub issue URL instead of writing a tarball
+        #[arg(long)]
+        url: bool,
+    },
+
     /// Restore gems to pristine condition
     Pristine {
         /// Specific gems to restore (restores all if not specified)
@@ -732,18 +1163,6 @@ enum Commands {
         #[arg(long)]
         vendor: Option<String>,
 
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long, conflicts_with = "verbose")]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long, conflicts_with_all = ["verbose", "quiet"])]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long = "config-file")]
         config_file: Option<String>,
@@ -761,6 +1180,16 @@ enum Commands {
         norc: bool,
     },
 
+    /// Show help for lode or one of its commands
+    Help {
+        /// Command to show help for
+        command: Option<String>,
+
+        /// Also show examples, config keys, and environment variables
+        #[arg(long)]
+        examples: bool,
+    },
+
     /// Generate shell completion scripts
     Completion {
         /// Shell to generate completion for
@@ -768,6 +1197,28 @@ enum Commands {
         shell: clap_complete::Shell,
     },
 
+    /// Generate man pages
+    Man {
+        /// Command whose page to print (default: the `lode` overview)
+        command: Option<String>,
+
+        /// Write every page into this directory instead of printing one
+        #[arg(long, value_name = "DIR", conflicts_with = "command")]
+        dir: Option<String>,
+    },
+
+    // Not `__complete`: clap_complete splits bash completion paths on `__`
+    /// Print dynamic completion candidates (used by completion scripts)
+    #[command(name = "complete-candidates", hide = true)]
+    Complete {
+        /// What to complete
+        #[arg(value_enum)]
+        kind: commands::complete::CompletionKind,
+
+        /// Only list candidates starting with this
+        prefix: Option<String>,
+    },
+
     /// Install a gem
     #[command(name = "gem-install")]
     GemInstall {
@@ -780,7 +1231,7 @@ enum Commands {
         #[arg(long)]
         platform: Option<String>,
 
-        /// Specify version of gem to install
+        /// Version or requirement to install (e.g. "~> 1.2")
         #[arg(short = 'v', long)]
         version: Option<String>,
 
@@ -925,6 +1376,10 @@ enum Commands {
         #[arg(long)]
         no_suggestions: bool,
 
+        /// Install even if a gem name looks like a typo of a popular gem
+        #[arg(long)]
+        yes: bool,
+
         /// rbconfig.rb for the deployment target platform
         #[arg(long)]
         target_rbconfig: Option<String>,
@@ -966,9 +1421,9 @@ enum Commands {
         #[arg(long)]
         clear_sources: bool,
 
-        /// Append URL to list of remote gem sources
-        #[arg(short = 's', long)]
-        source: Option<String>,
+        /// Append URL to list of remote gem sources (repeatable)
+        #[arg(short = 's', long, action = clap::ArgAction::Append)]
+        source: Vec<String>,
 
         /// Use HTTP proxy for remote operations (optional: specify URL or use environment variable)
         #[arg(short = 'p', long, num_args = 0..=1, default_missing_value = "", overrides_with = "no_http_proxy")]
@@ -979,18 +1434,6 @@ enum Commands {
         no_http_proxy: bool,
 
         // Common Options
-        /// Set the verbose level of output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Silence command progress meter
-        #[arg(short = 'q', long)]
-        quiet: bool,
-
-        /// Silence `RubyGems` output
-        #[arg(long)]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long)]
         config_file: Option<String>,
@@ -1028,9 +1471,13 @@ enum Commands {
         check_development: bool,
 
         /// Uninstall applicable executables without confirmation
-        #[arg(short = 'x', long)]
+        #[arg(short = 'x', long, overrides_with = "no_executables")]
         executables: bool,
 
+        /// Keep executables without confirmation (negation of --executables)
+        #[arg(long = "no-executables", overrides_with = "executables")]
+        no_executables: bool,
+
         /// Directory to uninstall gem from
         #[arg(short = 'i', long = "install-dir")]
         install_dir: Option<String>,
@@ -1071,7 +1518,7 @@ enum Commands {
         #[arg(long = "no-abort-on-dependent", overrides_with = "abort_on_dependent")]
         no_abort_on_dependent: bool,
 
-        /// Specify version of gem to uninstall
+        /// Version or requirement to uninstall (e.g. ">= 2.0")
         #[arg(short = 'v', long)]
         version: Option<String>,
 
@@ -1084,18 +1531,6 @@ enum Commands {
         vendor: bool,
 
         // Common flags
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long, conflicts_with = "verbose")]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long, conflicts_with_all = ["verbose", "quiet"])]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long = "config-file")]
         config_file: Option<String>,
@@ -1220,9 +1655,9 @@ enum Commands {
         #[arg(long)]
         clear_sources: bool,
 
-        /// Append URL to list of remote gem sources
-        #[arg(short = 's', long)]
-        source: Option<String>,
+        /// Append URL to list of remote gem sources (repeatable)
+        #[arg(short = 's', long, action = clap::ArgAction::Append)]
+        source: Vec<String>,
 
         /// Use HTTP proxy for remote operations (optional: specify URL or use environment variable)
         #[arg(short = 'p', long, num_args = 0..=1, default_missing_value = "", overrides_with = "no_http_proxy")]
@@ -1305,22 +1740,10 @@ enum Commands {
         no_suggestions: bool,
 
         // Common flags
-        /// Set the verbose level of output
-        #[arg(short = 'V', long, overrides_with = "no_verbose")]
-        verbose: bool,
-
         /// Do not set verbose output (negation of --verbose)
-        #[arg(long, hide = true)]
+        #[arg(long, hide = true, overrides_with = "verbose")]
         no_verbose: bool,
 
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long, conflicts_with = "verbose")]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long, conflicts_with_all = ["verbose", "quiet"])]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long = "config-file")]
         config_file: Option<String>,
@@ -1353,7 +1776,7 @@ enum Commands {
         #[arg(short = 'I', conflicts_with = "installed")]
         not_installed: bool,
 
-        /// Specify version of gem to check (use with --installed)
+        /// Version requirement to check (e.g. "~> 1.2")
         #[arg(short = 'v', long)]
         version: Option<String>,
 
@@ -1373,6 +1796,10 @@ enum Commands {
         #[arg(short = 'a', long)]
         all: bool,
 
+        /// Display every copy in every `GEM_PATH` directory and where it lives
+        #[arg(long)]
+        all_paths: bool,
+
         /// Exact name match (no partial matches)
         #[arg(short = 'e', long)]
         exact: bool,
@@ -1410,13 +1837,20 @@ enum Commands {
         #[arg(short = 'B', long = "bulk-threshold", default_value = "1000")]
         bulk_threshold: usize,
 
+        /// Narrow remote names with the search API before matching
+        ///
+        /// Fewer bytes than the names list for a rare word, but the search
+        /// API matches whole words only, so `ails` will not find `rails`.
+        #[arg(long)]
+        search_api: bool,
+
         /// Clear gem sources
         #[arg(long)]
         clear_sources: bool,
 
-        /// Append URL to list of remote gem sources
-        #[arg(short = 's', long)]
-        source: Option<String>,
+        /// Append URL to list of remote gem sources (repeatable)
+        #[arg(short = 's', long, action = clap::ArgAction::Append)]
+        source: Vec<String>,
 
         /// Use HTTP proxy for remote operations (optional: specify URL or use environment variable)
         #[arg(short = 'p', long = "http-proxy", num_args = 0..=1, default_missing_value = "", overrides_with = "no_http_proxy")]
@@ -1427,18 +1861,6 @@ enum Commands {
         no_http_proxy: bool,
 
         // Common flags
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long, conflicts_with = "verbose")]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long, conflicts_with_all = ["verbose", "quiet"])]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long = "config-file")]
         config_file: Option<String>,
@@ -1471,7 +1893,7 @@ enum Commands {
         #[arg(short = 'I', conflicts_with = "installed")]
         not_installed: bool,
 
-        /// Specify version of gem to search for use with --installed
+        /// Version requirement to check with --installed (e.g. "~> 1.2")
         #[arg(short = 'v', long)]
         version: Option<String>,
 
@@ -1532,9 +1954,9 @@ enum Commands {
         #[arg(long)]
         clear_sources: bool,
 
-        /// Append URL to list of remote gem sources
-        #[arg(short = 's', long)]
-        source: Option<String>,
+        /// Append URL to list of remote gem sources (repeatable)
+        #[arg(short = 's', long, action = clap::ArgAction::Append)]
+        source: Vec<String>,
 
         /// Use HTTP proxy for remote operations (optional: specify URL or use environment variable)
         #[arg(short = 'p', long = "http-proxy", num_args = 0..=1, default_missing_value = "", overrides_with = "no_http_proxy")]
@@ -1545,18 +1967,6 @@ enum Commands {
         no_http_proxy: bool,
 
         // Common flags
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long, conflicts_with = "verbose")]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long, conflicts_with_all = ["verbose", "quiet"])]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long = "config-file")]
         config_file: Option<String>,
@@ -1588,7 +1998,7 @@ enum Commands {
         #[arg(long)]
         force: bool,
 
-        /// Consider warnings as errors when validating the spec
+        /// Consider warnings as errors, and fail on gemspec files that are missing or untracked by git
         #[arg(long)]
         strict: bool,
 
@@ -1600,19 +2010,15 @@ enum Commands {
         #[arg(short = 'C')]
         directory: Option<String>,
 
-        // Common flags
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long, conflicts_with = "verbose")]
-        quiet: bool,
+        /// Directory of precompiled binaries to package into a --platform gem
+        #[arg(long)]
+        prebuilt: Option<String>,
 
-        /// Silent mode (no output)
-        #[arg(long, conflicts_with_all = ["verbose", "quiet"])]
-        silent: bool,
+        /// rbconfig.rb of the --platform target, for cross-compiling extensions
+        #[arg(long)]
+        target_rbconfig: Option<String>,
 
+        // Common flags
         /// Config file path (overrides default)
         #[arg(long = "config-file")]
         config_file: Option<String>,
@@ -1653,15 +2059,6 @@ enum Commands {
         /// Do not use HTTP proxy (negation of --http-proxy)
         #[arg(long)]
         no_http_proxy: bool,
-        /// Set the verbose level of output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-        /// Silence command progress meter
-        #[arg(short, long)]
-        quiet: bool,
-        /// Silence `RubyGems` output
-        #[arg(long)]
-        silent: bool,
         /// Config file path (overrides default)
         #[arg(long)]
         config_file: Option<String>,
@@ -1706,18 +2103,6 @@ enum Commands {
         #[arg(long, hide = true)]
         undo: bool,
 
-        /// Increase verbosity (enabled by default for yank output)
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Suppress all output except errors
-        #[arg(short, long, conflicts_with = "verbose")]
-        quiet: bool,
-
-        /// Suppress all `RubyGems` output
-        #[arg(long)]
-        silent: bool,
-
         /// Use the specified config file instead of default
         #[arg(long)]
         config_file: Option<String>,
@@ -1769,18 +2154,6 @@ enum Commands {
         #[arg(long, hide = true)]
         no_http_proxy: bool,
 
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long)]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long)]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long)]
         config_file: Option<String>,
@@ -1805,18 +2178,6 @@ enum Commands {
         #[arg(long)]
         host: Option<String>,
 
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long)]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long)]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long)]
         config_file: Option<String>,
@@ -1837,18 +2198,6 @@ enum Commands {
     /// Sign out from `RubyGems`
     #[command(name = "gem-signout")]
     GemSignout {
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long)]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long)]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long)]
         config_file: Option<String>,
@@ -1881,7 +2230,7 @@ enum Commands {
         #[arg(short = 'I', long = "no-installed", overrides_with = "installed")]
         no_installed: bool,
 
-        /// Gem version
+        /// Gem version or requirement (e.g. "~> 1.2")
         #[arg(short = 'v', long)]
         version: Option<String>,
 
@@ -1937,9 +2286,9 @@ enum Commands {
         #[arg(long)]
         clear_sources: bool,
 
-        /// Append URL to list of remote gem sources
-        #[arg(short = 's', long, value_name = "URL")]
-        source: Option<String>,
+        /// Append URL to list of remote gem sources (repeatable)
+        #[arg(short = 's', long, value_name = "URL", action = clap::ArgAction::Append)]
+        source: Vec<String>,
 
         /// Use HTTP proxy for remote operations
         #[arg(short = 'p', long = "http-proxy", num_args = 0..=1, default_missing_value = "", overrides_with = "no_http_proxy")]
@@ -1949,22 +2298,10 @@ enum Commands {
         #[arg(long, hide = true)]
         no_http_proxy: bool,
 
-        /// Set the verbose level of output
-        #[arg(short = 'V', long, overrides_with = "no_verbose")]
-        verbose: bool,
-
         /// Do not set verbose output
         #[arg(long = "no-verbose", overrides_with = "verbose")]
         no_verbose: bool,
 
-        /// Silence command progress meter
-        #[arg(short = 'q', long)]
-        quiet: bool,
-
-        /// Silence `RubyGems` output
-        #[arg(long)]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long, value_name = "FILE")]
         config_file: Option<String>,
@@ -2020,19 +2357,11 @@ enum Commands {
         #[arg(long = "no-show-install-dir", conflicts_with = "show_install_dir")]
         no_show_install_dir: bool,
 
-        // Common flags
-        /// Set the verbose level of output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Silence command progress meter
-        #[arg(short = 'q', long, conflicts_with = "verbose")]
-        quiet: bool,
-
-        /// Silence `RubyGems` output
-        #[arg(long, conflicts_with_all = ["verbose", "quiet"])]
-        silent: bool,
+        /// Compare installed files against the cached .gem (modified/missing/extra)
+        #[arg(long, conflicts_with = "show_install_dir")]
+        diff: bool,
 
+        // Common flags
         /// Config file path (overrides default)
         #[arg(long = "config-file")]
         config_file: Option<String>,
@@ -2056,7 +2385,7 @@ enum Commands {
         /// Gem name or pattern
         gem: String,
 
-        /// Specific version
+        /// Version or requirement (e.g. "~> 1.2")
         #[arg(short = 'v', long)]
         version: Option<String>,
 
@@ -2097,9 +2426,9 @@ enum Commands {
         #[arg(long = "clear-sources")]
         clear_sources: bool,
 
-        /// Append URL to list of remote gem sources
-        #[arg(short = 's', long)]
-        source: Option<String>,
+        /// Append URL to list of remote gem sources (repeatable)
+        #[arg(short = 's', long, action = clap::ArgAction::Append)]
+        source: Vec<String>,
 
         /// Use HTTP proxy for remote operations
         #[arg(short = 'p', long = "http-proxy", num_args = 0..=1, default_missing_value = "", overrides_with = "no_http_proxy")]
@@ -2110,18 +2439,6 @@ enum Commands {
         no_http_proxy: bool,
 
         // Common flags
-        /// Set the verbose level of output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Silence command progress meter
-        #[arg(short = 'q', long, conflicts_with = "verbose")]
-        quiet: bool,
-
-        /// Silence `RubyGems` output
-        #[arg(long, conflicts_with_all = ["verbose", "quiet"])]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long = "config-file")]
         config_file: Option<String>,
@@ -2154,18 +2471,6 @@ enum Commands {
         #[arg(short = 'g', long)]
         gems_first: bool,
 
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress progress meter)
-        #[arg(short, long)]
-        quiet: bool,
-
-        /// Silent mode (suppress all output)
-        #[arg(long)]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long)]
         config_file: Option<String>,
@@ -2189,8 +2494,8 @@ enum Commands {
         /// Gem name to download
         gem: String,
 
-        /// Gem version
-        #[arg(long)]
+        /// Gem version or requirement (e.g. '~> 2.0')
+        #[arg(short = 'v', long)]
         version: Option<String>,
 
         /// Download to specific directory
@@ -2221,25 +2526,13 @@ enum Commands {
         #[arg(long, hide = true)]
         no_http_proxy: bool,
 
-        /// Append URL to list of remote gem sources
-        #[arg(short = 's', long)]
-        source: Option<String>,
+        /// Append URL to list of remote gem sources (repeatable)
+        #[arg(short = 's', long, action = clap::ArgAction::Append)]
+        source: Vec<String>,
 
         /// Clear the gem sources
         #[arg(long)]
-        clear_sources: bool,
-
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long)]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long)]
-        silent: bool,
+        clear_sources: bool,
 
         /// Config file path (overrides default)
         #[arg(long)]
@@ -2262,18 +2555,6 @@ enum Commands {
     #[command(name = "gem-stale")]
     GemStale {
         // Common flags
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long, conflicts_with = "verbose")]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long, conflicts_with_all = ["verbose", "quiet"])]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long = "config-file")]
         config_file: Option<String>,
@@ -2310,18 +2591,6 @@ enum Commands {
         user_install: bool,
 
         // Common flags
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long, conflicts_with = "verbose")]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long, conflicts_with_all = ["verbose", "quiet"])]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long = "config-file")]
         config_file: Option<String>,
@@ -2386,18 +2655,6 @@ enum Commands {
         version: Option<String>,
 
         // Common flags
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long, conflicts_with = "verbose")]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long, conflicts_with_all = ["verbose", "quiet"])]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long = "config-file")]
         config_file: Option<String>,
@@ -2449,18 +2706,6 @@ enum Commands {
         #[arg(short = 'C')]
         working_dir: Option<String>,
 
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long)]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long)]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long)]
         config_file: Option<String>,
@@ -2522,18 +2767,6 @@ enum Commands {
         #[arg(long, hide = true)]
         no_http_proxy: bool,
 
-        /// Set verbose level of output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Silence command progress meter
-        #[arg(short = 'q', long)]
-        quiet: bool,
-
-        /// Silence `RubyGems` output
-        #[arg(long)]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long)]
         config_file: Option<String>,
@@ -2598,18 +2831,6 @@ enum Commands {
         #[arg(short = 'R', long)]
         re_sign: bool,
 
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long)]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long)]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long)]
         config_file: Option<String>,
@@ -2634,7 +2855,7 @@ enum Commands {
         gem: Option<String>,
 
         /// Generate RDoc/RI documentation for all installed gems
-        #[arg(long)]
+        #[arg(long, conflicts_with = "gem")]
         all: bool,
 
         /// Generate `RDoc` HTML
@@ -2665,18 +2886,6 @@ enum Commands {
         #[arg(short = 'v', long)]
         version: Option<String>,
 
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long)]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long)]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long)]
         config_file: Option<String>,
@@ -2701,18 +2910,6 @@ enum Commands {
         command: Option<String>,
 
         // Common flags
-        /// Set the verbose level of output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Silence command progress meter
-        #[arg(short = 'q', long, conflicts_with = "verbose")]
-        quiet: bool,
-
-        /// Silence `RubyGems` output
-        #[arg(long, conflicts_with_all = ["verbose", "quiet"])]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long = "config-file")]
         config_file: Option<String>,
@@ -2736,18 +2933,6 @@ enum Commands {
         /// Show specific variable (gemdir, gempath, version, remotesources, platform, etc.)
         variable: Option<String>,
 
-        /// Verbose output
-        #[arg(short = 'V', long)]
-        verbose: bool,
-
-        /// Quiet mode (suppress output)
-        #[arg(short = 'q', long, conflicts_with = "verbose")]
-        quiet: bool,
-
-        /// Silent mode (no output)
-        #[arg(long, conflicts_with_all = ["verbose", "quiet"])]
-        silent: bool,
-
         /// Config file path (overrides default)
         #[arg(long = "config-file")]
         config_file: Option<String>,
@@ -2766,6 +2951,36 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum CacheCommands {
+    /// Check cached gems for corruption and quarantine bad files
+    Verify {
+        /// Skip comparing against compact index checksums (no network)
+        #[arg(long)]
+        local: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum StatsCommands {
+    /// Historical native extension build times per gem, version and machine
+    Builds {
+        /// Print the history as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum IndexCommands {
+    /// Write specs.4.8.gz, quick gemspecs and compact index files for
+    /// DIR/gems/*.gem, like `gem generate_index`
+    Generate {
+        /// Directory whose gems/ subdirectory holds the .gem files
+        dir: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum PluginCommands {
     /// Install a plugin
@@ -2812,9 +3027,144 @@ enum PluginCommands {
     List,
 }
 
+/// Command-line arguments with `.gemrc` defaults added for `gem-*` commands
+///
+/// Like `RubyGems`, each default is kept only if the command accepts it, and
+/// goes before the user's own arguments so those still win.
+fn with_gemrc_args(mut args: Vec<OsString>) -> Vec<OsString> {
+    let Some((position, command)) = args.iter().enumerate().find_map(|(i, arg)| {
+        let command = arg.to_str()?.strip_prefix("gem-")?;
+        Some((i, command.to_string()))
+    }) else {
+        return args;
+    };
+
+    let rest: Vec<&str> = args
+        .iter()
+        .skip(position + 1)
+        .filter_map(|arg| arg.to_str())
+        .collect();
+    let norc = rest.contains(&"--norc");
+    let config_file = rest.iter().enumerate().find_map(|(i, arg)| {
+        arg.strip_prefix("--config-file=").map_or_else(
+            || {
+                (*arg == "--config-file")
+                    .then(|| rest.get(i + 1).copied())
+                    .flatten()
+            },
+            Some,
+        )
+    });
+    let gemrc = match lode::gemrc::Gemrc::load(config_file, norc) {
+        Ok(gemrc) => gemrc,
+        Err(e) => {
+            lode::warn!("{e:#}");
+            return args;
+        }
+    };
+
+    let mut insert_at = position + 1;
+    for group in gemrc.extra_args(&command) {
+        if !group.first().is_some_and(|flag| flag.starts_with('-')) {
+            continue;
+        }
+        let mut candidate = args.clone();
+        candidate.splice(insert_at..insert_at, group.iter().map(OsString::from));
+        if Cli::try_parse_from(&candidate).is_ok() {
+            insert_at += group.len();
+            args = candidate;
+        }
+    }
+    args
+}
+
+/// `-s` and `--clear-sources` of a `gem-*` command, in command-line order
+///
+/// The derived fields lose the interleaving, and `--clear-sources -s A`
+/// means something other than `-s A --clear-sources`.
+fn ordered_source_edits(matches: &clap::ArgMatches) -> Vec<lode::remote_sources::SourceEdit> {
+    use lode::remote_sources::SourceEdit;
+
+    let Some((
+        "gem-install" | "gem-update" | "gem-list" | "gem-search" | "gem-info" | "gem-dependency"
+        | "gem-fetch",
+        matches,
+    )) = matches.subcommand()
+    else {
+        return Vec::new();
+    };
+
+    let mut edits: Vec<(usize, SourceEdit)> = matches
+        .indices_of("source")
+        .into_iter()
+        .flatten()
+        .zip(matches.get_many::<String>("source").into_iter().flatten())
+        .map(|(index, url)| (index, SourceEdit::Add(url.clone())))
+        .collect();
+    if matches.get_flag("clear_sources") {
+        edits.extend(
+            matches
+                .indices_of("clear_sources")
+                .into_iter()
+                .flatten()
+                .map(|index| (index, SourceEdit::Clear)),
+        );
+    }
+    edits.sort_by_key(|(index, _)| *index);
+    edits.into_iter().map(|(_, edit)| edit).collect()
+}
+
+/// Final source list of a `gem-*` command: `.gemrc` sources edited by `edits`
+fn gem_sources(
+    edits: &[lode::remote_sources::SourceEdit],
+    config_file: Option<&str>,
+    norc: bool,
+) -> Vec<String> {
+    let gemrc = lode::gemrc::Gemrc::load(config_file, norc).unwrap_or_default();
+    lode::remote_sources::resolve(&gemrc.sources, edits)
+}
+
+/// Output level from `--verbose`, `--quiet` and `--silent`, wherever they
+/// were given on the command line, else from the environment and config
+fn command_verbosity(matches: &clap::ArgMatches) -> lode::reporter::Verbosity {
+    let mut levels = vec![matches];
+    while let Some((_, sub)) = levels.last().and_then(|m| m.subcommand()) {
+        levels.push(sub);
+    }
+    let flag = |id: &str| {
+        levels.iter().any(|m| {
+            m.try_get_one::<bool>(id)
+                .ok()
+                .flatten()
+                .copied()
+                .unwrap_or(false)
+        })
+    };
+    let (verbose, quiet, silent) = (flag("verbose"), flag("quiet"), flag("silent"));
+    // clap only checks conflicts within one level, so `lode -q install
+    // --verbose` gets through; reject it here the same way.
+    if u8::from(verbose) + u8::from(quiet) + u8::from(silent) > 1 {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--verbose, --quiet and --silent cannot be used together",
+            )
+            .exit();
+    }
+    lode::reporter::Verbosity::from_flags(
+        verbose,
+        quiet,
+        silent,
+        lode::reporter::Verbosity::from_env(lode::BundleConfig::load().ok().as_ref()),
+    )
+}
+
 #[tokio::main]
 async fn main() {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches_from(with_gemrc_args(std::env::args_os().collect()));
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let source_edits = ordered_source_edits(&matches);
+    lode::reporter::init_verbosity(command_verbosity(&matches));
 
     // Extract debug and backtrace flags before consuming cli.command
     let (debug, backtrace) = match &cli.command {
@@ -2842,12 +3192,47 @@ async fn main() {
         _ => (false, false),
     };
 
-    // Initialize debug mode
-    lode::init_debug(debug);
+    // Initialize logging and colors (`--debug` is the highest verbosity)
+    let log_file = cli.log_file.clone().or_else(lode::env_vars::lode_log_file);
+    let log_format = cli.log_format.map_or_else(
+        || {
+            if lode::env_vars::lode_log_json() {
+                lode::debug::LogFormat::Json
+            } else {
+                lode::debug::LogFormat::Text
+            }
+        },
+        Into::into,
+    );
+    if let Err(e) = lode::init_logging(lode::debug::LogOptions {
+        debug,
+        filter: lode::env_vars::lode_log(),
+        file: log_file.clone().map(std::path::PathBuf::from),
+        format: log_format,
+    }) {
+        lode::warn!(
+            "Could not open log file {}: {e}",
+            log_file.unwrap_or_default()
+        );
+    }
+    lode::reporter::init_color(cli.color.into());
+    let config = lode::Config::load().ok();
+    lode::messages::init_locale(lode::messages::Locale::detect(config.as_ref()));
+    lode::rate_limit::set_max_requests_per_second(
+        cli.max_requests_per_second
+            .or_else(|| lode::config::max_requests_per_second(config.as_ref())),
+    );
 
     // Setup backtrace
     setup_backtrace(backtrace);
 
+    // Every command reads the one level, so `--silent` implies `--quiet` and
+    // `LODE_VERBOSITY` applies even to commands given no flag
+    let verbosity = lode::reporter::verbosity();
+    let verbose = verbosity.is_verbose();
+    let quiet = verbosity.is_quiet();
+    let silent = verbosity == lode::reporter::Verbosity::Silent;
+
     let result = match cli.command {
         Commands::Init { path, gemspec } => commands::init::run(&path, gemspec),
         Commands::Add {
@@ -2864,8 +3249,11 @@ async fn main() {
             path,
             strict,
             optimistic,
-            quiet,
+            version_from_lockfile,
+            pessimistic_depth,
             skip_install,
+            yes,
+            full_resolve,
         } => {
             commands::add::run(
                 &gem,
@@ -2881,19 +3269,24 @@ async fn main() {
                 path.as_deref(),
                 strict,
                 optimistic,
+                version_from_lockfile,
+                pessimistic_depth,
                 quiet,
                 !skip_install,
+                yes,
+                !full_resolve,
             )
             .await
         }
-        Commands::Remove { gems, quiet } => commands::remove::run(&gems, quiet).await,
+        Commands::Remove { gems, full_resolve } => {
+            commands::remove::run(&gems, !full_resolve, quiet).await
+        }
         Commands::Update {
             gems,
             all,
             conservative,
             gemfile,
             jobs,
-            quiet,
             retry,
             patch,
             minor,
@@ -2907,6 +3300,12 @@ async fn main() {
             bundler,
             redownload,
             full_index,
+            security,
+            suggest_relaxations,
+            bot,
+            group_by,
+            branch_prefix,
+            report,
         } => {
             let bundle_config = lode::BundleConfig::load().unwrap_or_default();
 
@@ -2923,69 +3322,121 @@ async fn main() {
                 || bundle_config.force.unwrap_or(false)
                 || lode::env_vars::bundle_force();
 
-            commands::update::run(
-                &gems,
-                all,
-                conservative,
-                gemfile.as_deref(),
-                jobs_merged,
-                quiet,
-                retry_merged,
-                patch,
-                minor,
-                major,
-                strict,
-                local_merged,
-                pre,
-                group.as_deref(),
-                source.as_deref(),
-                ruby,
-                bundler.as_deref(),
-                redownload_merged,
-                full_index,
-            )
-            .await
+            if bot {
+                let options = commands::update::BotOptions {
+                    grouping: group_by,
+                    branch_prefix: &branch_prefix,
+                    output: report.as_deref(),
+                };
+                commands::update::run_bot(
+                    &gems,
+                    gemfile.as_deref(),
+                    options,
+                    jobs_merged,
+                    local_merged,
+                    pre,
+                )
+                .await
+            } else if suggest_relaxations {
+                commands::update::run_suggest_relaxations(
+                    &gems,
+                    gemfile.as_deref(),
+                    local_merged,
+                    pre,
+                    quiet,
+                )
+                .await
+            } else if security {
+                commands::update::run_security(
+                    &gems,
+                    gemfile.as_deref(),
+                    jobs_merged,
+                    local_merged,
+                    pre,
+                    quiet,
+                )
+                .await
+            } else {
+                commands::update::run(
+                    &gems,
+                    all,
+                    conservative,
+                    gemfile.as_deref(),
+                    jobs_merged,
+                    quiet,
+                    retry_merged,
+                    patch,
+                    minor,
+                    major,
+                    strict,
+                    local_merged,
+                    pre,
+                    group.as_deref(),
+                    source.as_deref(),
+                    ruby,
+                    bundler.as_deref(),
+                    redownload_merged,
+                    full_index,
+                )
+                .await
+            }
         }
         Commands::Outdated {
             lockfile,
             parseable,
+            format,
             major,
             minor,
             patch,
             pre,
             group,
+            exit_code,
+            fail_level,
         } => {
+            let fail_level =
+                fail_level.or_else(|| exit_code.then_some(commands::outdated::FailLevel::Patch));
             commands::outdated::run(
                 &lockfile,
                 parseable,
+                format,
                 major,
                 minor,
                 patch,
                 pre,
                 group.as_deref(),
+                fail_level,
             )
             .await
         }
+        Commands::Hold {
+            gems,
+            reason,
+            release,
+        } => commands::hold::run(&gems, reason.as_deref(), release),
         Commands::Lock {
             gemfile,
             lockfile,
             add_platform,
             remove_platform,
             update,
+            release_hold,
             print,
-            verbose,
             patch,
             minor,
             major,
             strict,
             conservative,
+            incremental,
+            strategy,
             local,
             pre,
             bundler,
             normalize_platforms,
             add_checksums,
             full_index,
-            quiet,
+            emit_metadata,
+            profile,
+            profile_trace,
         } => {
             let bundle_config = lode::BundleConfig::load().unwrap_or_default();
 
@@ -2996,12 +3447,17 @@ async fn main() {
             let local_merged =
                 local || bundle_config.local.unwrap_or(false) || lode::env_vars::bundle_local();
 
-            commands::lock::run(
+            let profiler = lode::Profiler::new(profile || profile_trace.is_some());
+
+            let result = commands::lock::run(
                 &gemfile,
                 lockfile.as_deref(),
                 &add_platform,
                 &remove_platform,
                 &update,
+                release_hold,
+                &std::collections::BTreeMap::new(),
+                strategy.into(),
                 print,
                 verbose_merged,
                 patch,
@@ -3009,6 +3465,7 @@ async fn main() {
                 major,
                 strict,
                 conservative,
+                incremental,
                 local_merged,
                 pre,
                 bundler.as_deref(),
@@ -3016,14 +3473,33 @@ async fn main() {
                 add_checksums,
                 full_index,
                 quiet,
+                &profiler,
             )
-            .await
+            .await;
+            let report = report_profile(&profiler, profile_trace.as_deref());
+
+            result.and(report).and_then(|()| {
+                if !emit_metadata || print {
+                    return Ok(());
+                }
+                let lockfile_path = lockfile.map_or_else(
+                    || lode::lockfile_for_gemfile(std::path::Path::new(&gemfile)),
+                    std::path::PathBuf::from,
+                );
+                commands::verify_env::emit(&lockfile_path, quiet)
+            })
         }
+        Commands::Resolve {
+            gemfile,
+            add,
+            platform,
+            pre,
+            local,
+            json,
+        } => commands::resolve::run(gemfile.as_deref(), &add, &platform, pre, local, json).await,
         Commands::Install {
             gemfile,
             redownload,
-            verbose,
-            quiet,
             jobs,
             local,
             prefer_local,
@@ -3032,7 +3508,19 @@ async fn main() {
             standalone,
             trust_policy,
             full_index,
+            frozen,
+            no_frozen,
             target_rbconfig,
+            arch,
+            path,
+            link_path_gems,
+            group_profile,
+            only,
+            watch,
+            dry_run,
+            resume,
+            profile,
+            profile_trace,
         } => {
             let lockfile_path = gemfile.as_ref().map_or_else(
                 || "Gemfile.lock".to_string(),
@@ -3067,16 +3555,22 @@ async fn main() {
             let silence_root_warning = bundle_config.silence_root_warning.unwrap_or(false)
                 || lode::env_vars::bundle_silence_root_warning();
             if lode::user::is_root() && !silence_root_warning && !quiet {
-                eprintln!(
-                    "Warning: Running as root user. Set BUNDLE_SILENCE_ROOT_WARNING=1 to silence this warning."
+                lode::warn!(
+                    "Running as root user. Set BUNDLE_SILENCE_ROOT_WARNING=1 to silence this warning."
                 );
             }
 
-            // Handle deployment mode: deployment = frozen + exclude dev/test
-            let deployment_mode = bundle_config.deployment.unwrap_or(false);
-            let frozen_merged = deployment_mode
-                || bundle_config.frozen.unwrap_or(false)
-                || lode::env_vars::bundle_frozen();
+            // Handle deployment mode: deployment = frozen + exclude dev/test,
+            // unless frozen is explicitly turned off
+            let deployment_mode = lode::config::deployment_enabled();
+            let frozen_cli = if frozen {
+                Some(true)
+            } else if no_frozen {
+                Some(false)
+            } else {
+                None
+            };
+            let frozen_merged = lode::config::frozen_enabled(frozen_cli);
 
             // Gather group filters from config (Config > Env > Default)
             let mut without_groups_merged = bundle_config
@@ -3089,6 +3583,16 @@ async fn main() {
                 .clone()
                 .or_else(lode::env_vars::bundle_with)
                 .unwrap_or_default();
+            // Only groups take precedence over with/without (CLI > Config > Env)
+            let only_groups_merged = if only.is_empty() {
+                bundle_config
+                    .only
+                    .clone()
+                    .or_else(lode::env_vars::bundle_only)
+                    .unwrap_or_default()
+            } else {
+                only
+            };
 
             // Deployment mode automatically excludes development and test groups
             if deployment_mode {
@@ -3103,8 +3607,9 @@ async fn main() {
             // Auto-clean after install if BUNDLE_CLEAN is enabled
             let auto_clean = bundle_config.clean.unwrap_or(false) || lode::env_vars::bundle_clean();
 
-            commands::install::run(commands::install::InstallOptions {
+            let options = commands::install::InstallOptions {
                 lockfile_path: &lockfile_path,
+                gemfile: gemfile.as_deref().map(std::path::Path::new),
                 redownload: force_merged,
                 verbose: verbose_merged,
                 quiet,
@@ -3117,12 +3622,40 @@ async fn main() {
                 trust_policy: trust_policy.as_deref(),
                 full_index,
                 target_rbconfig: target_rbconfig.as_deref(),
+                arch: arch.as_deref(),
+                path: path.as_deref(),
                 frozen: frozen_merged,
+                deployment: deployment_mode,
                 without_groups: without_groups_merged,
                 with_groups: with_groups_merged,
+                only_groups: only_groups_merged,
+                group_profile: group_profile.as_deref(),
                 auto_clean,
-            })
-            .await
+                link_path_gems,
+                profiler: lode::Profiler::new(profile || profile_trace.is_some()),
+                dry_run,
+                resume,
+            };
+
+            if let Some(pattern) = gemfile.as_deref().filter(|path| lode::paths::is_glob(path)) {
+                if watch {
+                    Err(anyhow::anyhow!(
+                        "--watch takes a single --gemfile, not a glob"
+                    ))
+                } else {
+                    commands::install::run_gemfiles(options, pattern).await
+                }
+            } else if watch {
+                let gemfile_path = gemfile
+                    .as_deref()
+                    .map_or_else(lode::paths::find_gemfile, std::path::PathBuf::from);
+                commands::install::watch(options, &gemfile_path).await
+            } else {
+                let profiler = options.profiler.clone();
+                let result = commands::install::run(options).await;
+                let report = report_profile(&profiler, profile_trace.as_deref());
+                result.and(report)
+            }
         }
         Commands::Binstubs {
             gems,
@@ -3130,6 +3663,8 @@ async fn main() {
             force,
             all,
             all_platforms,
+            standalone,
+            path,
         } => {
             let bundle_config = lode::BundleConfig::load().unwrap_or_default();
             let shebang_merged = shebang
@@ -3144,9 +3679,17 @@ async fn main() {
                 force_merged,
                 all,
                 all_platforms,
+                standalone,
+                path.as_deref(),
             )
         }
-        Commands::Check { gemfile, dry_run } => {
+        Commands::Check {
+            gemfiles: Some(pattern),
+            ..
+        } => commands::check::run_matrix(&pattern),
+        Commands::Check {
+            gemfile, dry_run, ..
+        } => {
             let lockfile_path = gemfile.as_ref().map_or_else(
                 || "Gemfile.lock".to_string(),
                 |gemfile_path| format!("{gemfile_path}.lock"),
@@ -3165,13 +3708,61 @@ async fn main() {
             only_group.as_deref(),
             without_group.as_deref(),
         ),
-        Commands::Show { gem, paths } => commands::show::run(gem.as_deref(), paths, "Gemfile.lock"),
-        Commands::Info { gem, path, version } => commands::info::run(&gem, path, version).await,
+        Commands::Show { gem, paths, verify } => {
+            commands::show::run(gem.as_deref(), paths, verify, "Gemfile.lock")
+        }
+        Commands::Matrix {
+            file,
+            gemfile,
+            output,
+            no_lock,
+        } => {
+            commands::matrix::run(
+                file.as_deref(),
+                gemfile.as_deref(),
+                &output,
+                !no_lock,
+                quiet,
+            )
+            .await
+        }
+        Commands::Size {
+            lockfile,
+            json,
+            top,
+        } => commands::size::run(&lockfile, json, top),
+        Commands::Index {
+            command: IndexCommands::Generate { dir },
+        } => commands::index::generate(&dir),
+        Commands::Stats {
+            command: Some(StatsCommands::Builds { json }),
+            ..
+        } => commands::stats::builds(json),
+        Commands::Stats {
+            command: None,
+            json,
+        } => commands::stats::overview(json),
+        Commands::Info {
+            gem,
+            path,
+            version,
+            spec_json,
+            spec_yaml,
+        } => {
+            let spec_format = if spec_json {
+                Some(commands::info::SpecFormat::Json)
+            } else if spec_yaml {
+                Some(commands::info::SpecFormat::Yaml)
+            } else {
+                None
+            };
+            commands::info::run(&gem, path, version, spec_format).await
+        }
         Commands::Search { query } => commands::search::run(&query).await,
         Commands::Specification { gem, version } => {
             commands::specification::run(&gem, version.as_deref()).await
         }
-        Commands::Which { file } => commands::which::run(&file),
+        Commands::Which { file, all_paths } => commands::which::run(&file, all_paths),
         Commands::Contents {
             gems,
             version,
@@ -3180,9 +3771,7 @@ async fn main() {
             lib_only,
             prefix,
             show_install_dir,
-            verbose: _,
-            quiet: _,
-            silent: _,
+            diff,
             config_file: _,
             backtrace: _,
             debug: _,
@@ -3193,6 +3782,7 @@ async fn main() {
                 lib_only,
                 prefix,
                 show_install_dir,
+                diff,
             };
             commands::contents::run(&gems, version.as_deref(), &spec_dir, &options)
         }
@@ -3201,25 +3791,59 @@ async fn main() {
             version,
             target,
             spec: _,
-            trust_policy: _,
-            verbose: _,
-            quiet: _,
-            silent: _,
+            trust_policy,
             config_file: _,
             backtrace: _,
             debug: _,
             norc: _,
-        } => commands::unpack::run(&gem, version.as_deref(), target.as_deref()).await,
+        } => {
+            commands::unpack::run(
+                &gem,
+                version.as_deref(),
+                target.as_deref(),
+                trust_policy.as_deref(),
+            )
+            .await
+        }
         Commands::Env => {
             commands::env::run();
             Ok(())
         }
-        Commands::Exec { command, gemfile } => {
+        Commands::TestInstall { gem, keep } => {
+            commands::test_install::run(&gem, keep, verbose).await
+        }
+        Commands::Issue {
+            gemfile,
+            output,
+            url,
+        } => commands::issue::run(gemfile.as_deref(), output.as_deref(), url),
+        Commands::Exec {
+            command,
+            gemfile,
+            only_group,
+            cached_setup,
+            isolate,
+        } => {
+            let lockfile_path = gemfile.as_ref().map_or_else(
+                || "Gemfile.lock".to_string(),
+                |gemfile_path| format!("{gemfile_path}.lock"),
+            );
+            commands::exec::run(&command, &lockfile_path, &only_group, cached_setup, isolate)
+        }
+        Commands::WithUnbundledEnv { command } => commands::with_unbundled_env::run(&command),
+        Commands::VerifyEnv { gemfile } => {
+            let lockfile_path = gemfile.as_ref().map_or_else(
+                || std::path::PathBuf::from("Gemfile.lock"),
+                |gemfile_path| lode::lockfile_for_gemfile(std::path::Path::new(gemfile_path)),
+            );
+            commands::verify_env::run(&lockfile_path)
+        }
+        Commands::Shell { print_env, gemfile } => {
             let lockfile_path = gemfile.as_ref().map_or_else(
                 || "Gemfile.lock".to_string(),
                 |gemfile_path| format!("{gemfile_path}.lock"),
             );
-            commands::exec::run(&command, &lockfile_path)
+            commands::shell::run(&lockfile_path, print_env)
         }
         Commands::Clean {
             vendor,
@@ -3232,12 +3856,16 @@ async fn main() {
 
             commands::clean::run(vendor.as_deref(), dry_run, force_merged)
         }
+        Commands::Cache {
+            command: Some(CacheCommands::Verify { local }),
+            ..
+        } => commands::cache::verify(local, quiet).await,
         Commands::Cache {
             all_platforms,
             cache_path,
             gemfile,
             no_install,
-            quiet,
+            command: None,
         } => {
             let bundle_config = lode::BundleConfig::load().unwrap_or_default();
 
@@ -3262,14 +3890,12 @@ async fn main() {
             gems,
             lockfile,
             vendor,
-            verbose: _,
-            quiet: _,
-            silent: _,
             config_file: _,
             backtrace: _,
             debug: _,
             norc: _,
         } => commands::pristine::run(&gems, &lockfile, vendor.as_deref()),
+        Commands::Profile { name } => commands::profile::run(name.as_deref()),
         Commands::Config {
             key,
             value,
@@ -3312,9 +3938,26 @@ async fn main() {
             }
             PluginCommands::List => commands::plugin::list(),
         },
+        Commands::Help { command, examples } => commands::help::run(command.as_deref(), examples),
         Commands::Completion { shell } => commands::completion::run(shell),
+        Commands::Man { command, dir } => commands::man::run(command.as_deref(), dir.as_deref()),
+        Commands::Complete { kind, prefix } => commands::complete::run(kind, prefix.as_deref()),
         Commands::Open { gem, path } => commands::open::run(&gem, path.as_deref()),
-        Commands::Doctor { gemfile, quiet } => commands::doctor::run(gemfile.as_deref(), quiet),
+        Commands::Audit { gemfile, sources } => {
+            commands::audit::run(gemfile.as_deref(), sources, quiet).await
+        }
+        Commands::Doctor {
+            gemfile,
+            remote,
+            json,
+        } => {
+            if remote {
+                commands::doctor::run_remote(gemfile.as_deref(), json).await
+            } else {
+                commands::doctor::run(gemfile.as_deref(), quiet)
+            }
+        }
+        Commands::SelfUpdate { channel, check } => commands::self_update::run(channel, check).await,
         Commands::Gem {
             name,
             exe,
@@ -3329,21 +3972,22 @@ async fn main() {
             strict,
             output,
             directory,
-            verbose: _,
-            quiet: _,
-            silent: _,
+            prebuilt,
+            target_rbconfig,
             config_file: _,
             backtrace: _,
             debug: _,
             norc: _,
-        } => commands::gem_build::run_with_options(
-            gemspec.as_deref(),
-            platform.as_deref(),
+        } => commands::gem_build::run_with_options(&commands::gem_build::BuildOptions {
+            gemspec: gemspec.as_deref(),
+            platform: platform.as_deref(),
             force,
             strict,
-            output.as_deref(),
-            directory.as_deref(),
-        ),
+            output: output.as_deref(),
+            directory: directory.as_deref(),
+            prebuilt: prebuilt.as_deref(),
+            target_rbconfig: target_rbconfig.as_deref(),
+        }),
         Commands::GemCert {
             build,
             add,
@@ -3356,9 +4000,6 @@ async fn main() {
             key_algorithm,
             days,
             re_sign,
-            verbose: _,
-            quiet: _,
-            silent: _,
             config_file: _,
             backtrace: _,
             debug: _,
@@ -3384,9 +4025,6 @@ async fn main() {
             dry_run,
             check_development,
             user_install,
-            verbose,
-            quiet,
-            silent: _,
             config_file,
             backtrace: _,
             debug: _,
@@ -3414,9 +4052,7 @@ async fn main() {
             no_prefix,
             show_install_dir,
             no_show_install_dir,
-            verbose,
-            quiet,
-            silent,
+            diff,
             config_file: _,
             backtrace: _,
             debug: _,
@@ -3434,6 +4070,7 @@ async fn main() {
                 } else {
                     show_install_dir
                 },
+                diff,
                 verbose,
                 quiet,
                 silent,
@@ -3451,17 +4088,14 @@ async fn main() {
             remote,
             both,
             bulk_threshold,
-            clear_sources,
-            source,
+            clear_sources: _,
+            source: _,
             http_proxy,
             no_http_proxy: _,
-            verbose,
-            quiet,
-            silent,
-            config_file: _,
+            config_file,
             backtrace: _,
             debug: _,
-            norc: _,
+            norc,
         } => {
             let opts = commands::gem_dependency::DependencyOptions {
                 gem_pattern: gem.clone(),
@@ -3474,8 +4108,7 @@ async fn main() {
                 remote,
                 both,
                 bulk_threshold,
-                clear_sources,
-                source: source.clone(),
+                sources: gem_sources(&source_edits, config_file.as_deref(), norc),
                 http_proxy: http_proxy.clone(),
                 verbose,
                 quiet,
@@ -3487,27 +4120,38 @@ async fn main() {
             gem,
             version,
             output_dir,
-            platform: _,
-            prerelease: _,
-            suggestions: _,
+            platform,
+            prerelease,
+            suggestions,
             bulk_threshold: _,
-            http_proxy: _,
-            no_http_proxy: _,
+            http_proxy,
+            no_http_proxy,
             source: _,
             clear_sources: _,
-            verbose: _,
-            quiet: _,
-            silent: _,
-            config_file: _,
+            config_file,
             backtrace: _,
             debug: _,
-            norc: _,
-        } => commands::gem_fetch::run(&gem, version.as_deref(), output_dir.as_deref()).await,
+            norc,
+        } => {
+            commands::gem_fetch::run(&commands::gem_fetch::FetchOptions {
+                gem: &gem,
+                version: version.as_deref(),
+                output_dir: output_dir.as_deref(),
+                platform: platform.as_deref(),
+                prerelease,
+                suggestions,
+                sources: &gem_sources(&source_edits, config_file.as_deref(), norc),
+                http_proxy: if no_http_proxy {
+                    None
+                } else {
+                    http_proxy.as_deref()
+                },
+                quiet: quiet || silent,
+            })
+            .await
+        }
         Commands::GemHelp {
             command,
-            verbose: _,
-            quiet: _,
-            silent: _,
             config_file: _,
             backtrace: _,
             debug: _,
@@ -3515,17 +4159,16 @@ async fn main() {
         } => commands::gem_help::run(command.as_deref()),
         Commands::GemEnvironment {
             variable,
-            verbose,
-            quiet,
-            silent: _,
-            config_file: _,
+            config_file,
             backtrace: _,
             debug: _,
-            norc: _,
+            norc,
         } => commands::gem_environment::run(commands::gem_environment::EnvironmentOptions {
             variable,
             verbose,
-            quiet,
+            quiet: quiet || silent,
+            config_file,
+            norc,
         }),
         Commands::GemInfo {
             gem,
@@ -3544,13 +4187,10 @@ async fn main() {
             remote,
             both,
             bulk_threshold,
-            clear_sources,
-            source,
+            clear_sources: _,
+            source: _,
             http_proxy,
-            verbose,
             no_verbose: _,
-            quiet,
-            silent,
             config_file,
             backtrace,
             debug,
@@ -3570,8 +4210,7 @@ async fn main() {
                 remote,
                 both,
                 bulk_threshold,
-                clear_sources,
-                source,
+                sources: gem_sources(&source_edits, config_file.as_deref(), norc),
                 http_proxy,
                 verbose,
                 quiet,
@@ -3622,6 +4261,7 @@ async fn main() {
             lock,
             suggestions,
             no_suggestions: _,
+            yes,
             target_rbconfig,
             default: _,
             build_flags: _,
@@ -3631,13 +4271,10 @@ async fn main() {
             remote,
             both,
             bulk_threshold,
-            clear_sources,
-            source,
+            clear_sources: _,
+            source: _,
             http_proxy,
             no_http_proxy: _,
-            verbose,
-            quiet,
-            silent,
             config_file,
             backtrace,
             debug,
@@ -3677,8 +4314,7 @@ async fn main() {
                 remote,
                 both,
                 bulk_threshold,
-                clear_sources,
-                source: source.clone(),
+                sources: gem_sources(&source_edits, config_file.as_deref(), norc),
                 http_proxy: http_proxy.clone(),
                 verbose,
                 quiet,
@@ -3687,6 +4323,7 @@ async fn main() {
                 backtrace,
                 debug,
                 norc,
+                yes,
             };
             commands::gem_install::run(options).await
         }
@@ -3699,6 +4336,7 @@ async fn main() {
             no_details: _,
             versions,
             all,
+            all_paths,
             exact,
             prerelease,
             no_prerelease: _,
@@ -3708,13 +4346,11 @@ async fn main() {
             remote,
             both,
             bulk_threshold,
-            clear_sources,
-            source,
+            search_api,
+            clear_sources: _,
+            source: _,
             http_proxy,
             no_http_proxy: _,
-            verbose,
-            quiet,
-            silent,
             config_file,
             backtrace,
             debug,
@@ -3733,6 +4369,7 @@ async fn main() {
                 details,
                 versions,
                 all,
+                all_paths,
                 exact,
                 prerelease,
                 update_sources,
@@ -3740,8 +4377,8 @@ async fn main() {
                 remote,
                 both,
                 bulk_threshold,
-                clear_sources,
-                source: source.as_deref(),
+                search_api,
+                sources: gem_sources(&source_edits, config_file.as_deref(), norc),
                 http_proxy: http_proxy.as_deref(),
                 verbose,
                 quiet,
@@ -3762,9 +4399,6 @@ async fn main() {
             host,
             http_proxy,
             no_http_proxy: _,
-            verbose: _,
-            quiet: _,
-            silent: _,
             config_file: _,
             backtrace: _,
             debug: _,
@@ -3823,9 +4457,6 @@ async fn main() {
             install_dir,
             bindir,
             version,
-            verbose,
-            quiet,
-            silent: _,
             config_file,
             backtrace: _,
             debug: _,
@@ -3858,9 +4489,6 @@ async fn main() {
             attestation: _,
             http_proxy: _,
             no_http_proxy: _,
-            verbose: _,
-            quiet: _,
-            silent: _,
             config_file: _,
             backtrace: _,
             debug: _,
@@ -3876,22 +4504,29 @@ async fn main() {
         }
         Commands::GemRdoc {
             gem,
-            all: _,
+            all,
             rdoc: _,
-            no_rdoc: _,
+            no_rdoc,
             ri: _,
-            no_ri: _,
-            overwrite: _,
+            no_ri,
+            overwrite,
             no_overwrite: _,
-            version: _,
-            verbose: _,
-            quiet: _,
-            silent: _,
+            version,
             config_file: _,
             backtrace: _,
             debug: _,
             norc: _,
-        } => commands::gem_rdoc::run(gem.as_deref()),
+        } => commands::gem_rdoc::run(&commands::gem_rdoc::RdocOptions {
+            gem,
+            all,
+            version,
+            policy: lode::docs::DocPolicy {
+                rdoc: !no_rdoc,
+                ri: !no_ri,
+            },
+            overwrite,
+            quiet: quiet || silent,
+        }),
         Commands::GemRebuild {
             gem,
             diff: _,
@@ -3901,9 +4536,6 @@ async fn main() {
             original: _,
             gemspec: _,
             working_dir: _,
-            verbose: _,
-            quiet: _,
-            silent: _,
             config_file: _,
             backtrace: _,
             debug: _,
@@ -3927,13 +4559,10 @@ async fn main() {
             remote,
             both,
             bulk_threshold,
-            clear_sources,
-            source,
+            clear_sources: _,
+            source: _,
             http_proxy,
             no_http_proxy: _,
-            verbose,
-            quiet,
-            silent,
             config_file,
             backtrace,
             debug,
@@ -3959,8 +4588,7 @@ async fn main() {
                 remote,
                 both,
                 bulk_threshold,
-                clear_sources,
-                source,
+                sources: gem_sources(&source_edits, config_file.as_deref(), norc),
                 http_proxy,
                 verbose,
                 quiet,
@@ -3974,18 +4602,12 @@ async fn main() {
         }
         Commands::GemSignin {
             host,
-            verbose: _,
-            quiet: _,
-            silent: _,
             config_file: _,
             backtrace: _,
             debug: _,
             norc: _,
         } => commands::gem_signin::run(host.as_deref()).await,
         Commands::GemSignout {
-            verbose,
-            quiet,
-            silent,
             config_file: _,
             backtrace: _,
             debug: _,
@@ -4009,9 +4631,6 @@ async fn main() {
             force,
             http_proxy,
             no_http_proxy: _,
-            verbose,
-            quiet,
-            silent,
             config_file: _,
             backtrace: _,
             debug: _,
@@ -4035,9 +4654,6 @@ async fn main() {
         }
 
         Commands::GemStale {
-            verbose,
-            quiet,
-            silent,
             config_file: _,
             backtrace: _,
             debug: _,
@@ -4056,6 +4672,7 @@ async fn main() {
             ignore_dependencies,
             check_development,
             executables,
+            no_executables,
             install_dir,
             bindir,
             user_install: _,
@@ -4069,9 +4686,6 @@ async fn main() {
             version,
             platform,
             vendor,
-            verbose: _,
-            quiet: _,
-            silent: _,
             config_file,
             backtrace: _,
             debug: _,
@@ -4085,7 +4699,7 @@ async fn main() {
                 all,
                 ignore_dependencies,
                 check_development,
-                executables,
+                executables: (executables || no_executables).then_some(executables),
                 install_dir,
                 bindir,
                 user_install: user_install_final,
@@ -4126,8 +4740,8 @@ async fn main() {
             remote,
             both,
             bulk_threshold,
-            clear_sources,
-            source,
+            clear_sources: _,
+            source: _,
             force,
             no_force: _,
             ignore_dependencies,
@@ -4148,10 +4762,7 @@ async fn main() {
             no_suggestions: _,
             http_proxy,
             no_http_proxy: _,
-            verbose,
             no_verbose: _,
-            quiet,
-            silent,
             config_file,
             backtrace,
             debug,
@@ -4191,8 +4802,7 @@ async fn main() {
                 remote,
                 both,
                 bulk_threshold,
-                clear_sources,
-                source,
+                sources: gem_sources(&source_edits, config_file.as_deref(), norc),
                 http_proxy,
                 verbose,
                 quiet,
@@ -4208,9 +4818,6 @@ async fn main() {
             files,
             all,
             gems_first,
-            verbose,
-            quiet,
-            silent,
             config_file: _,
             backtrace: _,
             debug: _,
@@ -4233,9 +4840,6 @@ async fn main() {
             host,
             key,
             undo,
-            verbose: _,
-            quiet: _,
-            silent: _,
             config_file: _,
             backtrace: _,
             debug: _,
@@ -4257,7 +4861,8 @@ async fn main() {
     if let Err(e) = result {
         // Display error with formatting
         display_error(&e, backtrace);
-        process::exit(1);
+        lode::diagnostics::record_failure(&std::env::args().collect::<Vec<_>>(), &e);
+        process::exit(lode::exit_code::ExitCode::classify(&e).code());
     }
 }
 
@@ -0,0 +1,1201 @@
+//! Ruby Marshal (format 4.8) reader and writer
+//!
+//! Legacy gem servers publish their indexes as Marshal dumps: `specs.4.8.gz`
+//! lists `[name, Gem::Version, platform]` triples and
+//! `quick/Marshal.4.8/<gem>.gemspec.rz` holds one deflated
+//! `Gem::Specification`. This module reads the subset of Marshal those files
+//! use, and every other type a dump can contain, into a [`Value`] tree so
+//! Lode needs no Ruby process to understand them. Input is untrusted: lengths
+//! are checked against the remaining bytes, nesting depth is bounded, and
+//! links may not copy out more than [`MAX_EXPANSION`] times the dump's size.
+//!
+//! [`dump`] writes a [`Value`] back out for `lode index generate`, the way
+//! Ruby would: UTF-8 strings carry their encoding and repeated symbols are
+//! links.
+
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+/// Major and minor version written at the start of every dump
+const VERSION: [u8; 2] = [4, 8];
+
+/// Deepest nesting accepted, far beyond what gem indexes use
+const MAX_DEPTH: usize = 64;
+
+/// Decoded values and string bytes allowed per byte of input
+///
+/// Links copy the value they point to, so a small dump of arrays that each
+/// link twice to the one before would otherwise expand exponentially.
+const MAX_EXPANSION: usize = 16;
+
+/// Decoding budget for dumps too small for [`MAX_EXPANSION`] to matter
+const MIN_BUDGET: usize = 1 << 12;
+
+/// Integers outside this range are written as bignums, as Ruby does
+const FIXNUM_RANGE: std::ops::Range<i64> = -(1 << 30)..(1 << 30);
+
+/// Errors from reading a Marshal dump
+#[derive(Debug, Clone, Copy, Error, PartialEq, Eq)]
+pub enum MarshalError {
+    #[error("Unsupported Marshal version {0}.{1} (expected 4.8)")]
+    Version(u8, u8),
+
+    #[error("Marshal data ends early at byte {0}")]
+    UnexpectedEnd(usize),
+
+    #[error("Unknown Marshal type byte {byte:#04x} at byte {offset}")]
+    UnknownType { byte: u8, offset: usize },
+
+    #[error("Invalid {what} at byte {offset}")]
+    Invalid { what: &'static str, offset: usize },
+
+    #[error("Marshal data nests deeper than {MAX_DEPTH} levels")]
+    TooDeep,
+
+    #[error("Marshal data links expand to more than {MAX_EXPANSION} times its size")]
+    TooLarge,
+
+    #[error("{0} trailing bytes after Marshal data")]
+    TrailingBytes(usize),
+}
+
+/// A value read from a Marshal dump
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Integer(i64),
+    /// Integers beyond 64 bits, as their sign and little-endian magnitude
+    Bignum {
+        negative: bool,
+        magnitude: Vec<u8>,
+    },
+    Float(f64),
+    /// String bytes; the encoding instance variable is dropped
+    String(Vec<u8>),
+    Symbol(String),
+    Array(Vec<Self>),
+    /// Entries in dump order, with the default value of a `Hash.new(x)`
+    Hash {
+        entries: Vec<(Self, Self)>,
+        default: Option<Box<Self>>,
+    },
+    Regexp {
+        source: Vec<u8>,
+        options: u8,
+    },
+    /// Reference to a class or module by name
+    Class(String),
+    /// Plain object with its instance variables (names keep their `@`)
+    Object {
+        class: String,
+        ivars: BTreeMap<String, Self>,
+    },
+    Struct {
+        class: String,
+        members: Vec<(String, Self)>,
+    },
+    /// Object dumped with `_dump`, as the raw string it returned
+    UserDefined {
+        class: String,
+        data: Vec<u8>,
+    },
+    /// Object dumped with `marshal_dump`, as the value it returned
+    UserMarshal {
+        class: String,
+        data: Box<Self>,
+    },
+}
+
+impl Value {
+    /// String contents as UTF-8, for strings and symbols
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(bytes) => std::str::from_utf8(bytes).ok(),
+            Self::Symbol(name) => Some(name.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Raw bytes of a string or symbol, whatever their encoding
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::String(bytes) => Some(bytes),
+            Self::Symbol(name) => Some(name.as_bytes()),
+            _ => None,
+        }
+    }
+
+    /// Elements of an array
+    #[must_use]
+    pub fn as_array(&self) -> Option<&[Self]> {
+        match self {
+            Self::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Integer value, for integers that fit in 64 bits
+    #[must_use]
+    pub const fn as_integer(&self) -> Option<i64> {
+        match self {
+            Self::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Instance variable `name` (with its `@`) of a plain object
+    #[must_use]
+    pub fn ivar(&self, name: &str) -> Option<&Self> {
+        match self {
+            Self::Object { ivars, .. } => ivars.get(name),
+            _ => None,
+        }
+    }
+
+    /// Class name of an object, struct or user-dumped value
+    #[must_use]
+    pub fn class_name(&self) -> Option<&str> {
+        match self {
+            Self::Object { class, .. }
+            | Self::Struct { class, .. }
+            | Self::UserDefined { class, .. }
+            | Self::UserMarshal { class, .. } => Some(class.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Version string of a `Gem::Version`
+    ///
+    /// `Gem::Version` dumps as `[version]` through `marshal_dump`; a bare
+    /// string is accepted too, as some generators write one.
+    #[must_use]
+    pub fn gem_version(&self) -> Option<&str> {
+        match self {
+            Self::UserMarshal { data, .. } => data.as_array()?.first()?.as_str(),
+            Self::Object { .. } => self.ivar("@version")?.as_str(),
+            _ => self.as_str(),
+        }
+    }
+
+    /// Requirement strings (`">= 1.0"`) of a `Gem::Requirement`
+    ///
+    /// `Gem::Requirement` dumps as `[[[op, Gem::Version], ...]]`.
+    #[must_use]
+    pub fn gem_requirements(&self) -> Vec<String> {
+        let pairs = match self {
+            Self::UserMarshal { data, .. } => data.as_array().and_then(|fields| fields.first()),
+            Self::Object { .. } => self.ivar("@requirements"),
+            _ => None,
+        };
+        pairs
+            .and_then(Self::as_array)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|pair| match pair.as_array()? {
+                [op, version] => Some(format!("{} {}", op.as_str()?, version.gem_version()?)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Platform string of a `Gem::Platform` or a plain platform string
+    ///
+    /// `Gem::Platform` dumps its `@cpu`, `@os` and `@version`, which join
+    /// with dashes into the familiar `x86_64-linux` form.
+    #[must_use]
+    pub fn gem_platform(&self) -> Option<String> {
+        if let Some(platform) = self.as_str() {
+            return Some(platform.to_string());
+        }
+        let parts: Vec<&str> = ["@cpu", "@os", "@version"]
+            .iter()
+            .filter_map(|name| self.ivar(name)?.as_str())
+            .collect();
+        (!parts.is_empty()).then(|| parts.join("-"))
+    }
+}
+
+/// Read a complete Marshal dump
+///
+/// # Errors
+///
+/// Returns an error if the data is not Marshal 4.8, is truncated or
+/// malformed, or has bytes left over after the value.
+pub fn load(data: &[u8]) -> Result<Value, MarshalError> {
+    let mut reader = Reader::new(data);
+    let value = reader.read_dump()?;
+    match data.len() - reader.offset {
+        0 => Ok(value),
+        extra => Err(MarshalError::TrailingBytes(extra)),
+    }
+}
+
+/// Cursor over a dump, with the symbol and object tables links refer to
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    symbols: Vec<String>,
+    /// Values by object index; `None` while an object is still being read
+    objects: Vec<Option<Value>>,
+    /// What copying each object costs, or the cost decoded before it while
+    /// it is still being read
+    costs: Vec<usize>,
+    depth: usize,
+    /// Values and string bytes decoded so far, links counted in full
+    decoded: usize,
+    budget: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: 0,
+            symbols: Vec::new(),
+            objects: Vec::new(),
+            costs: Vec::new(),
+            depth: 0,
+            decoded: 0,
+            budget: data.len().saturating_mul(MAX_EXPANSION).max(MIN_BUDGET),
+        }
+    }
+
+    fn read_dump(&mut self) -> Result<Value, MarshalError> {
+        let major = self.byte()?;
+        let minor = self.byte()?;
+        if [major, minor] != VERSION {
+            return Err(MarshalError::Version(major, minor));
+        }
+        self.value()
+    }
+
+    fn byte(&mut self) -> Result<u8, MarshalError> {
+        let byte = *self
+            .data
+            .get(self.offset)
+            .ok_or(MarshalError::UnexpectedEnd(self.offset))?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], MarshalError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(MarshalError::UnexpectedEnd(self.data.len()))?;
+        let bytes = self
+            .data
+            .get(self.offset..end)
+            .ok_or(MarshalError::UnexpectedEnd(self.offset))?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    /// A Marshal `long`: small values inline, larger ones as 1-4 bytes
+    fn long(&mut self) -> Result<i64, MarshalError> {
+        let tag = i8::from_ne_bytes([self.byte()?]);
+        Ok(match tag {
+            0 => 0,
+            5..=127 => i64::from(tag) - 5,
+            -128..=-5 => i64::from(tag) + 5,
+            1..=4 => {
+                let mut value = 0_i64;
+                for (index, byte) in self.bytes(tag as usize)?.iter().enumerate() {
+                    value |= i64::from(*byte) << (8 * index);
+                }
+                value
+            }
+            _ => {
+                let len = usize::from(tag.unsigned_abs());
+                let mut value = -1_i64;
+                for (index, byte) in self.bytes(len)?.iter().enumerate() {
+                    value &= !(0xff << (8 * index));
+                    value |= i64::from(*byte) << (8 * index);
+                }
+                value
+            }
+        })
+    }
+
+    /// A non-negative length, bounded by the bytes left since every element
+    /// takes at least one
+    fn length(&mut self) -> Result<usize, MarshalError> {
+        let offset = self.offset;
+        let len = self.long()?;
+        usize::try_from(len)
+            .ok()
+            .filter(|len| *len <= self.data.len() - self.offset)
+            .ok_or(MarshalError::Invalid {
+                what: "length",
+                offset,
+            })
+    }
+
+    fn byte_string(&mut self) -> Result<Vec<u8>, MarshalError> {
+        let len = self.length()?;
+        self.charge(len)?;
+        Ok(self.bytes(len)?.to_vec())
+    }
+
+    /// Count `cost` against the decoding budget
+    fn charge(&mut self, cost: usize) -> Result<(), MarshalError> {
+        self.decoded = self.decoded.saturating_add(cost);
+        if self.decoded > self.budget {
+            return Err(MarshalError::TooLarge);
+        }
+        Ok(())
+    }
+
+    /// A symbol (`:`) or a link to an earlier one (`;`)
+    fn symbol(&mut self) -> Result<String, MarshalError> {
+        let offset = self.offset;
+        match self.byte()? {
+            b':' => self.new_symbol(),
+            b';' => self.symbol_link(),
+            // Symbols with an encoding are wrapped in an ivar block
+            b'I' => {
+                let offset = self.offset;
+                if self.byte()? != b':' {
+                    return Err(MarshalError::Invalid {
+                        what: "symbol",
+                        offset,
+                    });
+                }
+                let symbol = self.new_symbol()?;
+                self.skip_ivars()?;
+                Ok(symbol)
+            }
+            _ => Err(MarshalError::Invalid {
+                what: "symbol",
+                offset,
+            }),
+        }
+    }
+
+    fn new_symbol(&mut self) -> Result<String, MarshalError> {
+        let offset = self.offset;
+        let bytes = self.byte_string()?;
+        let symbol = String::from_utf8(bytes).map_err(|_| MarshalError::Invalid {
+            what: "symbol",
+            offset,
+        })?;
+        self.symbols.push(symbol.clone());
+        Ok(symbol)
+    }
+
+    fn symbol_link(&mut self) -> Result<String, MarshalError> {
+        let offset = self.offset;
+        let index = self.long()?;
+        let symbol = usize::try_from(index)
+            .ok()
+            .and_then(|index| self.symbols.get(index))
+            .cloned()
+            .ok_or(MarshalError::Invalid {
+                what: "symbol link",
+                offset,
+            })?;
+        self.charge(symbol.len())?;
+        Ok(symbol)
+    }
+
+    /// Instance variables of an `I` block, which only carry encodings for
+    /// the strings Lode reads
+    fn skip_ivars(&mut self) -> Result<(), MarshalError> {
+        let count = self.length()?;
+        for _ in 0..count {
+            self.symbol()?;
+            self.value()?;
+        }
+        Ok(())
+    }
+
+    /// Reserve the next object index; links to it resolve once it is stored
+    fn reserve(&mut self) -> usize {
+        self.objects.push(None);
+        self.costs.push(self.decoded);
+        self.objects.len() - 1
+    }
+
+    fn store(&mut self, index: usize, value: Value) -> Value {
+        if let Some(slot) = self.objects.get_mut(index) {
+            *slot = Some(value.clone());
+        }
+        if let Some(cost) = self.costs.get_mut(index) {
+            *cost = self.decoded - *cost;
+        }
+        value
+    }
+
+    /// The object at a link's index, charged as if it were decoded again
+    fn link(&mut self, offset: usize) -> Result<Value, MarshalError> {
+        let index = self.long()?;
+        let invalid = MarshalError::Invalid {
+            what: "object link",
+            offset,
+        };
+        let index = usize::try_from(index).map_err(|_| invalid)?;
+        if !matches!(self.objects.get(index), Some(Some(_))) {
+            return Err(invalid);
+        }
+        // Charge before cloning, so an oversized copy is never made
+        self.charge(self.costs.get(index).copied().unwrap_or_default())?;
+        self.objects.get(index).cloned().flatten().ok_or(invalid)
+    }
+
+    fn value(&mut self) -> Result<Value, MarshalError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(MarshalError::TooDeep);
+        }
+        let value = self.charge(1).and_then(|()| self.value_inner());
+        self.depth -= 1;
+        value
+    }
+
+    fn value_inner(&mut self) -> Result<Value, MarshalError> {
+        let offset = self.offset;
+        let value = match self.byte()? {
+            b'0' => Value::Nil,
+            b'T' => Value::Bool(true),
+            b'F' => Value::Bool(false),
+            b'i' => Value::Integer(self.long()?),
+            b':' => Value::Symbol(self.new_symbol()?),
+            b';' => Value::Symbol(self.symbol_link()?),
+            b'@' => self.link(offset)?,
+            b'I' => {
+                let value = self.value()?;
+                self.skip_ivars()?;
+                value
+            }
+            // Extended with a module, or an instance of a String/Array/Hash
+            // subclass: the module or class name does not matter here
+            b'e' | b'C' => {
+                self.symbol()?;
+                self.value()?
+            }
+            b'"' => {
+                let index = self.reserve();
+                let bytes = self.byte_string()?;
+                self.store(index, Value::String(bytes))
+            }
+            b'f' => {
+                let index = self.reserve();
+                let float = self.float()?;
+                self.store(index, Value::Float(float))
+            }
+            b'l' => {
+                let index = self.reserve();
+                let bignum = self.bignum()?;
+                self.store(index, bignum)
+            }
+            b'/' => {
+                let index = self.reserve();
+                let source = self.byte_string()?;
+                let options = self.byte()?;
+                self.store(index, Value::Regexp { source, options })
+            }
+            b'[' => {
+                let index = self.reserve();
+                let len = self.length()?;
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push(self.value()?);
+                }
+                self.store(index, Value::Array(items))
+            }
+            type_byte @ (b'{' | b'}') => {
+                let index = self.reserve();
+                let len = self.length()?;
+                let mut entries = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let key = self.value()?;
+                    entries.push((key, self.value()?));
+                }
+                let default = if type_byte == b'}' {
+                    Some(Box::new(self.value()?))
+                } else {
+                    None
+                };
+                self.store(index, Value::Hash { entries, default })
+            }
+            b'c' | b'm' | b'M' => {
+                let index = self.reserve();
+                let name =
+                    String::from_utf8(self.byte_string()?).map_err(|_| MarshalError::Invalid {
+                        what: "class name",
+                        offset,
+                    })?;
+                self.store(index, Value::Class(name))
+            }
+            b'o' => {
+                let index = self.reserve();
+                let class = self.symbol()?;
+                let count = self.length()?;
+                let mut ivars = BTreeMap::new();
+                for _ in 0..count {
+                    let name = self.symbol()?;
+                    ivars.insert(name, self.value()?);
+                }
+                self.store(index, Value::Object { class, ivars })
+            }
+            b'S' => {
+                let index = self.reserve();
+                let class = self.symbol()?;
+                let count = self.length()?;
+                let mut members = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let name = self.symbol()?;
+                    members.push((name, self.value()?));
+                }
+                self.store(index, Value::Struct { class, members })
+            }
+            b'u' => {
+                let class = self.symbol()?;
+                let index = self.reserve();
+                let data = self.byte_string()?;
+                self.store(index, Value::UserDefined { class, data })
+            }
+            b'U' => {
+                let index = self.reserve();
+                let class = self.symbol()?;
+                let data = Box::new(self.value()?);
+                self.store(index, Value::UserMarshal { class, data })
+            }
+            byte => return Err(MarshalError::UnknownType { byte, offset }),
+        };
+        Ok(value)
+    }
+
+    fn float(&mut self) -> Result<f64, MarshalError> {
+        let offset = self.offset;
+        let bytes = self.byte_string()?;
+        let float = match std::str::from_utf8(&bytes).ok() {
+            Some("nan") => Some(f64::NAN),
+            Some("inf") => Some(f64::INFINITY),
+            Some("-inf") => Some(f64::NEG_INFINITY),
+            // Older Rubies append mantissa bytes after a NUL
+            Some(text) => text.split('\0').next().and_then(|n| n.parse().ok()),
+            None => None,
+        };
+        float.ok_or(MarshalError::Invalid {
+            what: "float",
+            offset,
+        })
+    }
+
+    fn bignum(&mut self) -> Result<Value, MarshalError> {
+        let offset = self.offset;
+        let negative = match self.byte()? {
+            b'-' => true,
+            b'+' => false,
+            _ => {
+                return Err(MarshalError::Invalid {
+                    what: "bignum sign",
+                    offset,
+                });
+            }
+        };
+        // The length counts 16-bit words
+        let words = self.length()?;
+        let magnitude = self.bytes(words.saturating_mul(2))?.to_vec();
+
+        let significant = magnitude
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map_or(0, |i| i + 1);
+        if significant <= 8 {
+            let mut value = 0_u64;
+            for (index, byte) in magnitude.iter().take(significant).enumerate() {
+                value |= u64::from(*byte) << (8 * index);
+            }
+            let value = if negative {
+                0_i64.checked_sub_unsigned(value)
+            } else {
+                i64::try_from(value).ok()
+            };
+            if let Some(value) = value {
+                return Ok(Value::Integer(value));
+            }
+        }
+        Ok(Value::Bignum {
+            negative,
+            magnitude,
+        })
+    }
+}
+
+/// Write `value` as a complete Marshal dump
+#[must_use]
+pub fn dump(value: &Value) -> Vec<u8> {
+    let mut writer = Writer::default();
+    writer.out.extend_from_slice(&VERSION);
+    writer.value(value);
+    writer.out
+}
+
+/// Output buffer and the symbols already written, which later uses link to
+#[derive(Default)]
+struct Writer {
+    out: Vec<u8>,
+    symbols: HashMap<String, usize>,
+}
+
+impl Writer {
+    /// A Marshal `long`; lengths and fixnums always fit in four bytes
+    fn long(&mut self, n: i64) {
+        match n {
+            0 => self.out.push(0),
+            1..=122 => self.out.push((n + 5) as u8),
+            -123..=-1 => self.out.push((n - 5) as u8),
+            _ => {
+                let mut bytes = Vec::with_capacity(4);
+                let mut rest = n;
+                loop {
+                    bytes.push(rest as u8);
+                    rest >>= 8;
+                    if rest == 0 || rest == -1 {
+                        break;
+                    }
+                }
+                let len = bytes.len() as u8;
+                self.out.push(if n > 0 { len } else { len.wrapping_neg() });
+                self.out.extend_from_slice(&bytes);
+            }
+        }
+    }
+
+    fn length(&mut self, len: usize) {
+        self.long(i64::try_from(len).unwrap_or(i64::MAX));
+    }
+
+    fn byte_string(&mut self, bytes: &[u8]) {
+        self.length(bytes.len());
+        self.out.extend_from_slice(bytes);
+    }
+
+    fn symbol(&mut self, name: &str) {
+        if let Some(index) = self.symbols.get(name).copied() {
+            self.out.push(b';');
+            self.length(index);
+        } else {
+            self.symbols.insert(name.to_string(), self.symbols.len());
+            self.out.push(b':');
+            self.byte_string(name.as_bytes());
+        }
+    }
+
+    fn value(&mut self, value: &Value) {
+        match value {
+            Value::Nil => self.out.push(b'0'),
+            Value::Bool(true) => self.out.push(b'T'),
+            Value::Bool(false) => self.out.push(b'F'),
+            Value::Integer(n) if FIXNUM_RANGE.contains(n) => {
+                self.out.push(b'i');
+                self.long(*n);
+            }
+            Value::Integer(n) => {
+                let magnitude = n.unsigned_abs().to_le_bytes();
+                let significant = magnitude
+                    .iter()
+                    .rposition(|byte| *byte != 0)
+                    .map_or(0, |i| i + 1);
+                self.bignum(*n < 0, magnitude.get(..significant).unwrap_or_default());
+            }
+            Value::Bignum {
+                negative,
+                magnitude,
+            } => self.bignum(*negative, magnitude),
+            Value::Float(float) => self.float(*float),
+            Value::String(bytes) => self.string(bytes),
+            Value::Symbol(name) => self.symbol(name),
+            Value::Array(items) => {
+                self.out.push(b'[');
+                self.length(items.len());
+                for item in items {
+                    self.value(item);
+                }
+            }
+            Value::Hash { entries, default } => {
+                self.out.push(if default.is_some() { b'}' } else { b'{' });
+                self.length(entries.len());
+                for (key, value) in entries {
+                    self.value(key);
+                    self.value(value);
+                }
+                if let Some(default) = default {
+                    self.value(default);
+                }
+            }
+            Value::Regexp { source, options } => {
+                self.out.push(b'/');
+                self.byte_string(source);
+                self.out.push(*options);
+            }
+            Value::Class(name) => {
+                self.out.push(b'c');
+                self.byte_string(name.as_bytes());
+            }
+            Value::Object { class, ivars } => {
+                self.out.push(b'o');
+                self.symbol(class);
+                self.length(ivars.len());
+                for (name, value) in ivars {
+                    self.symbol(name);
+                    self.value(value);
+                }
+            }
+            Value::Struct { class, members } => {
+                self.out.push(b'S');
+                self.symbol(class);
+                self.length(members.len());
+                for (name, value) in members {
+                    self.symbol(name);
+                    self.value(value);
+                }
+            }
+            Value::UserDefined { class, data } => {
+                self.out.push(b'u');
+                self.symbol(class);
+                self.byte_string(data);
+            }
+            Value::UserMarshal { class, data } => {
+                self.out.push(b'U');
+                self.symbol(class);
+                self.value(data);
+            }
+        }
+    }
+
+    fn float(&mut self, float: f64) {
+        self.out.push(b'f');
+        let text = if float.is_nan() {
+            "nan".to_string()
+        } else if float.is_infinite() {
+            if float > 0.0 { "inf" } else { "-inf" }.to_string()
+        } else {
+            format!("{float:?}")
+        };
+        self.byte_string(text.as_bytes());
+    }
+
+    /// Ruby tags UTF-8 strings with `E: true`; anything else is left as
+    /// binary
+    fn string(&mut self, bytes: &[u8]) {
+        let utf8 = std::str::from_utf8(bytes).is_ok();
+        if utf8 {
+            self.out.push(b'I');
+        }
+        self.out.push(b'"');
+        self.byte_string(bytes);
+        if utf8 {
+            self.length(1);
+            self.symbol("E");
+            self.out.push(b'T');
+        }
+    }
+
+    /// Sign, 16-bit word count, then the little-endian magnitude padded to
+    /// whole words
+    fn bignum(&mut self, negative: bool, magnitude: &[u8]) {
+        self.out.push(b'l');
+        self.out.push(if negative { b'-' } else { b'+' });
+        self.length(magnitude.len().div_ceil(2));
+        self.out.extend_from_slice(magnitude);
+        if magnitude.len() % 2 == 1 {
+            self.out.push(0);
+        }
+    }
+}
+
+/// Minimal Marshal writer for building fixtures in tests
+#[cfg(test)]
+pub(crate) mod encode {
+    use std::io::Write;
+
+    /// A Marshal `long`, for values up to 65535
+    pub(crate) fn long(n: usize) -> Vec<u8> {
+        match n {
+            0 => vec![0],
+            1..=122 => vec![n as u8 + 5],
+            123..=255 => vec![1, n as u8],
+            _ => vec![2, n as u8, (n >> 8) as u8],
+        }
+    }
+
+    pub(crate) fn string(s: &str) -> Vec<u8> {
+        [&[b'"'][..], &long(s.len()), s.as_bytes()].concat()
+    }
+
+    pub(crate) fn symbol(s: &str) -> Vec<u8> {
+        [&[b':'][..], &long(s.len()), s.as_bytes()].concat()
+    }
+
+    pub(crate) fn array(items: &[Vec<u8>]) -> Vec<u8> {
+        [vec![b'['], long(items.len()), items.concat()].concat()
+    }
+
+    pub(crate) fn gem_version(version: &str) -> Vec<u8> {
+        [
+            vec![b'U'],
+            symbol("Gem::Version"),
+            array(&[string(version)]),
+        ]
+        .concat()
+    }
+
+    pub(crate) fn gem_requirement(op: &str, version: &str) -> Vec<u8> {
+        let pair = array(&[string(op), gem_version(version)]);
+        [
+            vec![b'U'],
+            symbol("Gem::Requirement"),
+            array(&[array(&[pair])]),
+        ]
+        .concat()
+    }
+
+    /// Deflated `Marshal.dump(spec)` as written to `quick/Marshal.4.8/`,
+    /// with `(name, requirement)` runtime dependencies
+    pub(crate) fn quick_spec(name: &str, version: &str, deps: &[(&str, &str)]) -> Vec<u8> {
+        let deps: Vec<Vec<u8>> = deps
+            .iter()
+            .map(|(dep, requirement)| {
+                let (op, dep_version) = requirement.split_once(' ').unwrap_or(("=", requirement));
+                [
+                    vec![b'o'],
+                    symbol("Gem::Dependency"),
+                    long(3),
+                    symbol("@name"),
+                    string(dep),
+                    symbol("@requirement"),
+                    gem_requirement(op, dep_version),
+                    symbol("@type"),
+                    symbol("runtime"),
+                ]
+                .concat()
+            })
+            .collect();
+        let fields = array(&[
+            string("3.5.0"),
+            b"i\x09".to_vec(),
+            string(name),
+            gem_version(version),
+            b"0".to_vec(),
+            string("A widget"),
+            gem_requirement(">=", "3.1"),
+            gem_requirement(">=", "0"),
+            string("ruby"),
+            array(&deps),
+            string(""),
+            b"0".to_vec(),
+            array(&[string("Ann Author")]),
+            b"0".to_vec(),
+            string("https://example.com"),
+            b"T".to_vec(),
+            string("ruby"),
+            array(&[string("MIT")]),
+            b"{\x00".to_vec(),
+        ]);
+        let inner = [&[4, 8][..], &fields].concat();
+        let dump = [
+            &[4, 8, b'u'][..],
+            &symbol("Gem::Specification"),
+            &long(inner.len()),
+            &inner,
+        ]
+        .concat();
+
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&dump).expect("deflate");
+        encoder.finish().expect("deflate")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Dump bytes as written by Ruby, without the version header
+    fn load_body(body: &[u8]) -> Result<Value, MarshalError> {
+        let mut data = VERSION.to_vec();
+        data.extend_from_slice(body);
+        load(&data)
+    }
+
+    fn string(s: &str) -> Value {
+        Value::String(s.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn integers() {
+        // Marshal.dump(n) for boundary values of each long encoding
+        let cases: &[(&[u8], i64)] = &[
+            (b"i\x00", 0),
+            (b"i\x06", 1),
+            (b"i\x7f", 122),
+            (b"i\x01\x7b", 123),
+            (b"i\x01\xff", 255),
+            (b"i\x02\x00\x01", 256),
+            (b"i\xfa", -1),
+            (b"i\x80", -123),
+            (b"i\xff\x84", -124),
+            (b"i\xfe\x00\xff", -256),
+            (b"i\x04\xff\xff\xff\x3f", 1_073_741_823),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(load_body(body), Ok(Value::Integer(*expected)), "{body:?}");
+        }
+        // 2**64 does not fit
+        assert_eq!(
+            load_body(b"l+\x0a\x00\x00\x00\x00\x00\x00\x00\x00\x01\x00"),
+            Ok(Value::Bignum {
+                negative: false,
+                magnitude: vec![0, 0, 0, 0, 0, 0, 0, 0, 1, 0],
+            })
+        );
+        // 2**40 does
+        assert_eq!(
+            load_body(b"l+\x08\x00\x00\x00\x00\x00\x01"),
+            Ok(Value::Integer(1 << 40))
+        );
+    }
+
+    #[test]
+    fn strings_symbols_and_links() {
+        // x = "x"; Marshal.dump(["rack", :ruby, :ruby, x, x])
+        let value = load_body(b"[\x0aI\"\x09rack\x06:\x06ET:\x09ruby;\x06\"\x06x@\x07").unwrap();
+        assert_eq!(
+            value,
+            Value::Array(vec![
+                string("rack"),
+                Value::Symbol("ruby".to_string()),
+                Value::Symbol("ruby".to_string()),
+                string("x"),
+                string("x"),
+            ])
+        );
+    }
+
+    #[test]
+    fn hashes_floats_and_objects() {
+        let value = load_body(b"{\x07:\x06af\x081.5:\x06b0").unwrap();
+        assert_eq!(
+            value,
+            Value::Hash {
+                entries: vec![
+                    (Value::Symbol("a".to_string()), Value::Float(1.5)),
+                    (Value::Symbol("b".to_string()), Value::Nil),
+                ],
+                default: None,
+            }
+        );
+
+        // Gem::Platform.new("x86_64-linux")
+        let platform = load_body(
+            b"o:\x12Gem::Platform\x08:\x09@cpu\"\x0bx86_64:\x08@os\"\x0alinux:\x0d@version0",
+        )
+        .unwrap();
+        assert_eq!(platform.class_name(), Some("Gem::Platform"));
+        assert_eq!(platform.gem_platform().as_deref(), Some("x86_64-linux"));
+    }
+
+    #[test]
+    fn gem_versions_and_requirements() {
+        // Marshal.dump([Gem::Version.new("1.2"), Gem::Requirement.new("~> 1.2")])
+        let value = load_body(
+            b"[\x07U:\x11Gem::Version[\x06\"\x081.2\
+              U:\x15Gem::Requirement[\x06[\x06[\x07\"\x07~>@\x06",
+        )
+        .unwrap();
+        let items = value.as_array().unwrap();
+        assert_eq!(items.first().and_then(Value::gem_version), Some("1.2"));
+        assert_eq!(
+            items.get(1).map(Value::gem_requirements),
+            Some(vec!["~> 1.2".to_string()])
+        );
+    }
+
+    #[test]
+    fn user_defined_dumps_keep_their_bytes() {
+        let value =
+            load_body(b"Iu:\x09Time\x0d\x01\x02\x03\x04\x05\x06\x07\x08\x06:\x06ET").unwrap();
+        assert_eq!(
+            value,
+            Value::UserDefined {
+                class: "Time".to_string(),
+                data: (1..=8).collect(),
+            }
+        );
+    }
+
+    #[test]
+    fn dump_matches_ruby() {
+        // Marshal.dump(["rack", :a, :a, 300, -129, nil])
+        let value = Value::Array(vec![
+            string("rack"),
+            Value::Symbol("a".to_string()),
+            Value::Symbol("a".to_string()),
+            Value::Integer(300),
+            Value::Integer(-129),
+            Value::Nil,
+        ]);
+        assert_eq!(
+            dump(&value),
+            b"\x04\x08[\x0bI\"\x09rack\x06:\x06ET:\x06a;\x06i\x02,\x01i\xff\x7f0"
+        );
+
+        // Marshal.dump(2**40)
+        assert_eq!(
+            dump(&Value::Integer(1 << 40)),
+            b"\x04\x08l+\x08\x00\x00\x00\x00\x00\x01"
+        );
+    }
+
+    #[test]
+    fn dump_round_trips() {
+        let mut ivars = BTreeMap::new();
+        ivars.insert("@name".to_string(), string("widget"));
+        ivars.insert("@type".to_string(), Value::Symbol("runtime".to_string()));
+        let value = Value::Array(vec![
+            Value::Bool(true),
+            Value::Integer(-(1 << 40)),
+            Value::Integer(i64::MAX),
+            Value::Integer(1 << 29),
+            Value::Float(1.5),
+            Value::Float(f64::NEG_INFINITY),
+            Value::String(vec![0xff, 0xfe]),
+            Value::Hash {
+                entries: vec![(string("a"), Value::Integer(1))],
+                default: Some(Box::new(Value::Nil)),
+            },
+            Value::Regexp {
+                source: b"^a+$".to_vec(),
+                options: 1,
+            },
+            Value::Class("Gem::Version".to_string()),
+            Value::Object {
+                class: "Gem::Dependency".to_string(),
+                ivars,
+            },
+            Value::Struct {
+                class: "Point".to_string(),
+                members: vec![("x".to_string(), Value::Integer(1))],
+            },
+            Value::UserDefined {
+                class: "Gem::Specification".to_string(),
+                data: vec![4, 8, b'0'],
+            },
+            Value::UserMarshal {
+                class: "Gem::Version".to_string(),
+                data: Box::new(Value::Array(vec![string("1.0")])),
+            },
+        ]);
+        assert_eq!(load(&dump(&value)), Ok(value));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert_eq!(load(b"\x04\x07["), Err(MarshalError::Version(4, 7)));
+        assert_eq!(load(b"\x04\x08"), Err(MarshalError::UnexpectedEnd(2)));
+        assert!(matches!(
+            load(b"\x04\x08[\x7f"),
+            Err(MarshalError::Invalid { what: "length", .. })
+        ));
+        assert!(matches!(
+            load(b"\x04\x08@\x06"),
+            Err(MarshalError::Invalid {
+                what: "object link",
+                ..
+            })
+        ));
+        assert_eq!(load(b"\x04\x080T"), Err(MarshalError::TrailingBytes(1)));
+        assert!(matches!(
+            load(b"\x04\x08X"),
+            Err(MarshalError::UnknownType { byte: b'X', .. })
+        ));
+
+        let mut nested = VERSION.to_vec();
+        nested.extend(std::iter::repeat_n([b'[', 6], 10_000).flatten());
+        nested.push(b'0');
+        assert_eq!(load(&nested), Err(MarshalError::TooDeep));
+    }
+
+    /// Arrays that each link twice to the one before stay shallow but would
+    /// double in size with every level if links were copied unchecked
+    fn link_bomb(levels: u8) -> Vec<u8> {
+        let mut data = VERSION.to_vec();
+        data.extend([b'[', levels + 6, b'[', 0]);
+        for index in 1..=levels {
+            data.extend([b'[', 7, b'@', index + 5, b'@', index + 5]);
+        }
+        data
+    }
+
+    #[test]
+    fn rejects_exponential_links() {
+        let started = std::time::Instant::now();
+        assert_eq!(load(&link_bomb(30)), Err(MarshalError::TooLarge));
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+
+        // A few levels of sharing are fine
+        assert!(matches!(
+            load(&link_bomb(3)),
+            Ok(Value::Array(levels)) if levels.len() == 4
+        ));
+    }
+
+    /// xorshift64*, so the fuzz corpus is reproducible without extra crates
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+    }
+
+    #[test]
+    fn fuzz_random_and_mutated_input_never_panics() {
+        let seeds: &[&[u8]] = &[
+            b"\x04\x08[\x07U:\x11Gem::Version[\x06\"\x081.2U:\x15Gem::Requirement[\x06[\x06[\x07\"\x07~>@\x06",
+            b"\x04\x08o:\x12Gem::Platform\x08:\x09@cpu\"\x0bx86_64:\x08@os\"\x0alinux:\x0d@version0",
+            b"\x04\x08}\x06i\x06[\x07f\x08nanl-\x06\x01\x00:\x06x",
+            b"\x04\x08[\x0aI\"\x09rack\x06:\x06ET:\x09ruby;\x06\"\x06x@\x07",
+            &link_bomb(24),
+        ];
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..20_000 {
+            let mut data = seeds.get(rng.below(seeds.len())).unwrap().to_vec();
+            for _ in 0..=rng.below(8) {
+                let position = rng.below(data.len() + 1);
+                match rng.below(4) {
+                    0 if let Some(byte) = data.get_mut(position) => *byte = rng.next() as u8,
+                    1 => data.insert(position, rng.next() as u8),
+                    2 if position < data.len() => {
+                        data.remove(position);
+                    }
+                    _ => data.truncate(position),
+                }
+            }
+            // Any result is fine; panicking or hanging is not
+            drop(load(&data));
+        }
+
+        for len in 0..2_000 {
+            let mut data = VERSION.to_vec();
+            data.extend((0..len % 64).map(|_| rng.next() as u8));
+            drop(load(&data));
+        }
+    }
+}
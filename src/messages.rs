@@ -0,0 +1,305 @@
+//! Localized user-facing messages
+//!
+//! Messages are looked up by [`Message`] key in the active [`Locale`], set
+//! once at startup from `lode config locale` or the `LC_ALL`,
+//! `LC_MESSAGES` and `LANG` environment variables. Templates name their
+//! arguments (`{count}`), so translations can reorder them; fill them with
+//! [`format`] or the [`tr!`](crate::tr) macro. Until initialized, messages
+//! are English.
+
+use crate::config::Config;
+use std::fmt;
+use std::sync::OnceLock;
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// Supported message languages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    English,
+    Japanese,
+}
+
+impl Locale {
+    /// Every supported locale
+    pub const ALL: [Self; 2] = [Self::English, Self::Japanese];
+
+    /// Locale for a tag like `ja`, `ja-JP` or `ja_JP.UTF-8`
+    ///
+    /// Returns `None` for languages without a translation.
+    #[must_use]
+    pub fn parse(tag: &str) -> Option<Self> {
+        let language = tag
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Self::English),
+            "ja" => Some(Self::Japanese),
+            _ => None,
+        }
+    }
+
+    /// Locale from lode config, else the environment, else English
+    #[must_use]
+    pub fn detect(config: Option<&Config>) -> Self {
+        config
+            .and_then(|c| c.locale.clone())
+            .or_else(crate::env_vars::locale)
+            .as_deref()
+            .and_then(Self::parse)
+            .unwrap_or_default()
+    }
+
+    /// Tag accepted by `lode config locale`
+    #[must_use]
+    pub const fn tag(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::Japanese => "ja",
+        }
+    }
+}
+
+/// Set the locale used by [`text`] and [`format`]
+pub fn init_locale(locale: Locale) {
+    let _ = LOCALE.set(locale);
+}
+
+/// The active locale
+#[must_use]
+pub fn current() -> Locale {
+    LOCALE.get().copied().unwrap_or_default()
+}
+
+/// Keys of the message catalog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    ErrorLabel,
+    CausedBy,
+    WarningLabel,
+    NoGemsInLockfile,
+    NoGemsAfterFiltering,
+    InstallingGems,
+    AllGemsInstalled,
+    InstallSummary,
+    DownloadSummary,
+    DeploymentGuidance,
+    DeploymentRefused,
+    DeploymentNeedsLockfile,
+    DeploymentGemfileChanged,
+    AllGemsUpToDate,
+    RunUpdateHint,
+}
+
+impl Message {
+    /// Every message, for checking that each locale covers the catalog
+    pub const ALL: [Self; 15] = [
+        Self::ErrorLabel,
+        Self::CausedBy,
+        Self::WarningLabel,
+        Self::NoGemsInLockfile,
+        Self::NoGemsAfterFiltering,
+        Self::InstallingGems,
+        Self::AllGemsInstalled,
+        Self::InstallSummary,
+        Self::DownloadSummary,
+        Self::DeploymentGuidance,
+        Self::DeploymentRefused,
+        Self::DeploymentNeedsLockfile,
+        Self::DeploymentGemfileChanged,
+        Self::AllGemsUpToDate,
+        Self::RunUpdateHint,
+    ];
+
+    /// Template for this message in `locale`
+    #[must_use]
+    pub const fn template(self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::English => english(self),
+            Locale::Japanese => japanese(self),
+        }
+    }
+}
+
+const fn english(message: Message) -> &'static str {
+    match message {
+        Message::ErrorLabel => "error:",
+        Message::CausedBy => "caused by:",
+        Message::WarningLabel => "Warning:",
+        Message::NoGemsInLockfile => "No gems found in lockfile.",
+        Message::NoGemsAfterFiltering => "No gems to install after filtering.",
+        Message::InstallingGems => "Installing {count} gems...",
+        Message::AllGemsInstalled => "All gems already installed!",
+        Message::InstallSummary => {
+            "Installed {count} gems ({skipped} skipped) to {path} in {seconds}s"
+        }
+        Message::DownloadSummary => "Downloaded {downloaded} ({cached} from cache)",
+        Message::DeploymentGuidance => {
+            "Run `lode install` elsewhere and add the updated Gemfile.lock to version control.\n\n\
+             If this is a development machine, remove BUNDLE_DEPLOYMENT from .bundle/config \
+             or the environment."
+        }
+        Message::DeploymentRefused => {
+            "`lode {command}` would change the Gemfile or Gemfile.lock, \
+             which is not allowed in deployment mode."
+        }
+        Message::DeploymentNeedsLockfile => {
+            "The deployment setting requires a lockfile, but {lockfile} was not found. \
+             Make sure you have checked your Gemfile.lock into version control before deploying."
+        }
+        Message::DeploymentGemfileChanged => {
+            "You are trying to install in deployment mode after changing your Gemfile.\n\
+             {guidance}\n\n\
+             The Gemfile lists gems the lockfile does not satisfy: {gems}"
+        }
+        Message::AllGemsUpToDate => "All gems are up to date!",
+        Message::RunUpdateHint => "Run `lode update` to update gems to their latest versions.",
+    }
+}
+
+const fn japanese(message: Message) -> &'static str {
+    match message {
+        Message::ErrorLabel => "エラー:",
+        Message::CausedBy => "原因:",
+        Message::WarningLabel => "警告:",
+        Message::NoGemsInLockfile => "ロックファイルに gem がありません。",
+        Message::NoGemsAfterFiltering => {
+            "グループの絞り込み後、インストールする gem がありません。"
+        }
+        Message::InstallingGems => "{count} 個の gem をインストールしています...",
+        Message::AllGemsInstalled => "すべての gem はインストール済みです。",
+        Message::InstallSummary => {
+            "{count} 個の gem を {path} にインストールしました (スキップ {skipped} 個、{seconds} 秒)"
+        }
+        Message::DownloadSummary => "{downloaded} をダウンロードしました (キャッシュから {cached})",
+        Message::DeploymentGuidance => {
+            "別の環境で `lode install` を実行し、更新された Gemfile.lock をバージョン管理に追加してください。\n\n\
+             開発用のマシンであれば、.bundle/config または環境変数から BUNDLE_DEPLOYMENT を削除してください。"
+        }
+        Message::DeploymentRefused => {
+            "`lode {command}` は Gemfile または Gemfile.lock を変更するため、\
+             デプロイモードでは実行できません。"
+        }
+        Message::DeploymentNeedsLockfile => {
+            "デプロイ設定にはロックファイルが必要ですが、{lockfile} が見つかりません。\
+             デプロイ前に Gemfile.lock をバージョン管理に追加してください。"
+        }
+        Message::DeploymentGemfileChanged => {
+            "Gemfile の変更後にデプロイモードでインストールしようとしています。\n\
+             {guidance}\n\n\
+             ロックファイルが満たしていない Gemfile の gem: {gems}"
+        }
+        Message::AllGemsUpToDate => "すべての gem は最新です。",
+        Message::RunUpdateHint => "`lode update` を実行すると gem を最新バージョンに更新できます。",
+    }
+}
+
+/// Text of `message` in the active locale
+#[must_use]
+pub fn text(message: Message) -> &'static str {
+    message.template(current())
+}
+
+/// Fill the named `{arguments}` of `message` in the active locale
+///
+/// Placeholders without a matching argument are left as written.
+#[must_use]
+pub fn format(message: Message, args: &[(&str, &dyn fmt::Display)]) -> String {
+    fill(text(message), args)
+}
+
+fn fill(template: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let (before, after) = rest.split_at(start);
+        out.push_str(before);
+        let value = after.find('}').and_then(|end| {
+            let name = after.get(1..end)?;
+            let (_, value) = args.iter().find(|(key, _)| *key == name)?;
+            Some((value, end))
+        });
+        if let Some((value, end)) = value {
+            out.push_str(&value.to_string());
+            rest = after.get(end + 1..).unwrap_or_default();
+        } else {
+            out.push('{');
+            rest = after.get(1..).unwrap_or_default();
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Macro for localized messages with named arguments
+///
+/// Usage: `tr!(Message::InstallingGems, count = gems.len())`
+#[macro_export]
+macro_rules! tr {
+    ($message:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::messages::format(
+            $message,
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*],
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `{name}` placeholders of a template, sorted
+    fn placeholders(template: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = template
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn every_locale_has_every_message_with_the_same_arguments() {
+        for message in Message::ALL {
+            let english = placeholders(message.template(Locale::English));
+            for locale in Locale::ALL {
+                let template = message.template(locale);
+                assert!(!template.is_empty(), "{message:?} missing in {locale:?}");
+                assert_eq!(placeholders(template), english, "{message:?} in {locale:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn locale_tags_follow_posix_and_bcp47() {
+        assert_eq!(Locale::parse("ja_JP.UTF-8"), Some(Locale::Japanese));
+        assert_eq!(Locale::parse("ja-JP"), Some(Locale::Japanese));
+        assert_eq!(Locale::parse("C.UTF-8"), Some(Locale::English));
+        assert_eq!(Locale::parse("en_US"), Some(Locale::English));
+        assert_eq!(Locale::parse("fr_FR.UTF-8"), None);
+    }
+
+    #[test]
+    #[allow(
+        clippy::literal_string_with_formatting_args,
+        reason = "catalog templates use named placeholders"
+    )]
+    fn fill_replaces_named_arguments_only() {
+        let count = 3;
+        assert_eq!(
+            fill(
+                "{count} gems in {path} ({unknown})",
+                &[("count", &count), ("path", &"vendor")]
+            ),
+            "3 gems in vendor ({unknown})"
+        );
+        assert_eq!(
+            fill(english(Message::InstallingGems), &[("count", &count)]),
+            "Installing 3 gems..."
+        );
+    }
+}
@@ -2,6 +2,8 @@
 //! Supports both traditional (Gemfile/Gemfile.lock) and modern (gems.rb/gems.locked) naming.
 
 use crate::env_vars;
+use regex::Regex;
+use std::fs;
 use std::path::{Path, PathBuf};
 
 /// Find the Gemfile in the current directory.
@@ -103,6 +105,68 @@ pub fn gemfile_for_lockfile(lockfile: &Path) -> PathBuf {
     lockfile.to_path_buf()
 }
 
+/// Whether `pattern` contains `*` or `?` wildcards.
+#[must_use]
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// Expand a shell-style glob such as `gemfiles/*.gemfile` into the existing
+/// paths it matches, sorted.
+/// `*` and `?` match within a single path component and skip dotfiles.
+#[must_use]
+pub fn expand_glob(pattern: &str) -> Vec<PathBuf> {
+    let mut matches = vec![PathBuf::new()];
+    for component in Path::new(pattern).components() {
+        let part = component.as_os_str();
+        let Some(matcher) = part.to_str().filter(|text| is_glob(text)).map(glob_regex) else {
+            for path in &mut matches {
+                path.push(part);
+            }
+            continue;
+        };
+        matches = matches
+            .iter()
+            .flat_map(|dir| {
+                let base = if dir.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    dir.as_path()
+                };
+                fs::read_dir(base)
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .filter(|entry| {
+                        entry
+                            .file_name()
+                            .to_str()
+                            .is_some_and(|name| !name.starts_with('.') && matcher.is_match(name))
+                    })
+                    .map(|entry| dir.join(entry.file_name()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+    }
+    matches.retain(|path| path.exists());
+    matches.sort();
+    matches
+}
+
+/// Anchored regex for one glob path component
+fn glob_regex(component: &str) -> Regex {
+    let mut pattern = String::from("^");
+    for c in component.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            other => pattern.push_str(&regex::escape(other.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).unwrap_or_else(|_| unreachable!("escaped glob is a valid regex"))
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used, reason = "Tests can panic")]
 mod tests {
@@ -231,4 +295,29 @@ mod tests {
             Path::new("custom/Gemfile")
         );
     }
+
+    #[test]
+    fn expand_glob_matches_files_in_order() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("gemfiles");
+        fs::create_dir(&dir).unwrap();
+        for name in [
+            "rails_8.gemfile",
+            "rails_7.gemfile",
+            "rails_7.gemfile.lock",
+            ".hidden.gemfile",
+        ] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let pattern = format!("{}/rails_?.gemfile", dir.display());
+        assert_eq!(
+            expand_glob(&pattern),
+            vec![dir.join("rails_7.gemfile"), dir.join("rails_8.gemfile")]
+        );
+        let pattern = format!("{}/*/*.gemfile", temp.path().display());
+        assert_eq!(expand_glob(&pattern).len(), 2);
+        assert!(expand_glob(&format!("{}/*.rb", dir.display())).is_empty());
+        assert!(!is_glob("gemfiles/rails_7.gemfile"));
+    }
 }
@@ -73,6 +73,10 @@ pub fn platform_matches(gem_platform: &Option<String>, current_platform: &str) -
         return true;
     }
 
+    if universal_matches(platform, current_platform) {
+        return true;
+    }
+
     // Platform variants - compare arch and OS components
     // Examples: arm64-darwin-24 matches arm64-darwin
     //           x86_64-linux-gnu matches x86_64-linux
@@ -85,6 +89,142 @@ pub fn platform_matches(gem_platform: &Option<String>, current_platform: &str) -
         && gem_parts.get(1) == current_parts.get(1)
 }
 
+/// Check if a `universal-*` gem platform covers `platform`
+///
+/// Universal gems ship fat binaries (arm64 and `x86_64` slices in one
+/// dylib), so `universal-darwin-23` runs on both `arm64-darwin` and
+/// `x86_64-darwin`, whatever the OS version suffix on either side.
+#[must_use]
+pub fn universal_matches(gem_platform: &str, platform: &str) -> bool {
+    let gem_platform = normalize_platform(gem_platform);
+    let platform = normalize_platform(platform);
+    let Some(("universal", gem_os)) = gem_platform.split_once('-') else {
+        return false;
+    };
+    platform.split_once('-').is_some_and(|(_, os)| os == gem_os)
+}
+
+/// The CPU architecture of the machine, in `RubyGems` spelling
+///
+/// Unlike the arch of the running Ruby this sees through Rosetta: an `x86_64`
+/// Ruby translated on Apple silicon still reports `arm64` here.
+#[must_use]
+pub fn machine_arch() -> String {
+    if env::consts::OS == "macos" && sysctl_flag("hw.optional.arm64") {
+        return "arm64".to_string();
+    }
+
+    Command::new("uname")
+        .arg("-m")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|arch| normalize_arch(arch.trim()))
+        .filter(|arch| !arch.is_empty())
+        .unwrap_or_else(|| normalize_arch(env::consts::ARCH))
+}
+
+/// Whether this process runs translated by Rosetta 2
+#[must_use]
+pub fn is_rosetta() -> bool {
+    env::consts::OS == "macos" && sysctl_flag("sysctl.proc_translated")
+}
+
+/// `(ruby_arch, machine_arch)` when Ruby was built for another architecture
+/// than the machine it runs on, as with an `x86_64` Ruby under Rosetta
+#[must_use]
+pub fn arch_mismatch() -> Option<(String, String)> {
+    let current = detect_current_platform();
+    let (ruby_arch, _) = current.split_once('-')?;
+    let ruby_arch = normalize_arch(ruby_arch);
+    if ruby_arch == "universal" {
+        return None;
+    }
+    let machine = machine_arch();
+    (ruby_arch != machine).then_some((ruby_arch, machine))
+}
+
+/// `ARCHFLAGS` for building extensions for `arch`
+///
+/// Accepts a single arch (`arm64`), a comma-separated list, or `universal`
+/// for a fat arm64 + `x86_64` build.
+#[must_use]
+pub fn archflags(arch: &str) -> String {
+    let arches: Vec<String> = if arch.trim() == "universal" {
+        vec!["arm64".to_string(), "x86_64".to_string()]
+    } else {
+        arch.split(',')
+            .map(str::trim)
+            .filter(|arch| !arch.is_empty())
+            .map(normalize_arch)
+            .collect()
+    };
+    arches
+        .iter()
+        .map(|arch| format!("-arch {arch}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `RubyGems` spelling of a CPU name (`aarch64` is `arm64`, `amd64` is `x86_64`)
+fn normalize_arch(arch: &str) -> String {
+    match arch {
+        "aarch64" => "arm64",
+        "amd64" | "x64" => "x86_64",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Whether a boolean `sysctl` key is set to 1
+fn sysctl_flag(key: &str) -> bool {
+    Command::new("sysctl")
+        .args(["-n", key])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .is_some_and(|output| String::from_utf8_lossy(&output.stdout).trim() == "1")
+}
+
+/// Canonicalize a platform string for lockfile comparison
+///
+/// Drops OS version suffixes and the default glibc ABI marker so equivalent
+/// platforms collapse to one spelling:
+///
+/// - `universal-darwin-23` → `universal-darwin`
+/// - `arm64-darwin23` → `arm64-darwin`
+/// - `x86_64-linux-gnu` → `x86_64-linux`
+///
+/// Distinct ABIs such as `x86_64-linux-musl` and `x64-mingw-ucrt` are kept.
+#[must_use]
+pub fn normalize_platform(platform: &str) -> String {
+    let platform = platform.trim();
+    if !platform.contains('-') {
+        return platform.to_string();
+    }
+
+    let mut parts: Vec<&str> = platform.split('-').collect();
+
+    // Trailing version segments ("-23", "-25.0.0") and the implicit glibc marker
+    while parts.len() > 2
+        && parts.last().is_some_and(|last| {
+            *last == "gnu" || last.chars().all(|c| c.is_ascii_digit() || c == '.')
+        })
+    {
+        parts.pop();
+    }
+
+    // Embedded darwin versions ("darwin23", "darwin25.0.0")
+    if let Some(os) = parts.get_mut(1)
+        && os.starts_with("darwin")
+    {
+        *os = "darwin";
+    }
+
+    parts.join("-")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -119,6 +259,47 @@ mod tests {
         assert!(platform_matches(&Some("ruby".to_string()), current));
     }
 
+    #[test]
+    fn platform_matches_universal() {
+        let universal = Some("universal-darwin".to_string());
+        assert!(platform_matches(&universal, "arm64-darwin"));
+        assert!(platform_matches(&universal, "x86_64-darwin23"));
+        assert!(platform_matches(
+            &Some("universal-darwin-23".to_string()),
+            "arm64-darwin-24"
+        ));
+        assert!(!platform_matches(&universal, "x86_64-linux"));
+        assert!(!universal_matches("arm64-darwin", "x86_64-darwin"));
+    }
+
+    #[test]
+    fn archflags_for_arches() {
+        assert_eq!(archflags("arm64"), "-arch arm64");
+        assert_eq!(archflags("aarch64"), "-arch arm64");
+        assert_eq!(archflags("universal"), "-arch arm64 -arch x86_64");
+        assert_eq!(archflags("arm64, x86_64"), "-arch arm64 -arch x86_64");
+    }
+
+    #[test]
+    fn normalize_platform_strips_versions() {
+        assert_eq!(
+            normalize_platform("universal-darwin-23"),
+            "universal-darwin"
+        );
+        assert_eq!(normalize_platform("arm64-darwin23"), "arm64-darwin");
+        assert_eq!(normalize_platform("arm64-darwin-25.0.0"), "arm64-darwin");
+        assert_eq!(normalize_platform("x86_64-linux-gnu"), "x86_64-linux");
+    }
+
+    #[test]
+    fn normalize_platform_keeps_distinct_abis() {
+        assert_eq!(normalize_platform("x86_64-linux-musl"), "x86_64-linux-musl");
+        assert_eq!(normalize_platform("x64-mingw-ucrt"), "x64-mingw-ucrt");
+        assert_eq!(normalize_platform("x86_64-mingw32"), "x86_64-mingw32");
+        assert_eq!(normalize_platform("ruby"), "ruby");
+        assert_eq!(normalize_platform("java"), "java");
+    }
+
     #[test]
     fn detect_platform() {
         let platform = detect_current_platform();
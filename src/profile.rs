@@ -0,0 +1,360 @@
+//! Timing breakdown for `install --profile` and `lock --profile`
+//!
+//! Spans are kept in memory and only ever printed or written to a local
+//! file: a flame-style summary on stderr, and optionally a Chrome trace
+//! (`chrome://tracing`, Perfetto) for a per-gem timeline.
+
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+/// Width of the bars in the summary
+const BAR_WIDTH: usize = 30;
+
+/// Gems listed under each phase in the summary
+const TOP_GEMS: usize = 5;
+
+/// Install or lock phase a span belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    /// Fetching gem metadata (versions and dependencies)
+    Metadata,
+    /// Dependency resolution
+    Resolution,
+    /// Downloading `.gem` files
+    Download,
+    /// Unpacking gems into the bundle
+    Extraction,
+    /// Building native extensions
+    Extensions,
+    /// Generating binstubs
+    Binstubs,
+}
+
+impl Phase {
+    /// Name used in the summary and trace
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Metadata => "metadata",
+            Self::Resolution => "resolution",
+            Self::Download => "download",
+            Self::Extraction => "extraction",
+            Self::Extensions => "extensions",
+            Self::Binstubs => "binstubs",
+        }
+    }
+}
+
+/// A finished span
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpanRecord {
+    pub phase: Phase,
+    /// Gem the span covers, or `None` for the phase as a whole
+    pub gem: Option<String>,
+    /// Offset from when profiling started
+    pub start: Duration,
+    pub duration: Duration,
+    /// Small per-run thread number, for trace lanes
+    pub thread: usize,
+}
+
+#[derive(Debug)]
+struct Recorder {
+    origin: Instant,
+    spans: Mutex<(Vec<SpanRecord>, Vec<ThreadId>)>,
+}
+
+/// Collects spans when profiling is enabled; a disabled profiler records
+/// nothing and costs a branch per span
+///
+/// Clones share the same recording, so a profiler can be handed to tokio
+/// tasks and rayon workers.
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    recorder: Option<Arc<Recorder>>,
+}
+
+/// Running span, recorded when dropped
+#[derive(Debug)]
+#[must_use = "the span is recorded when this guard is dropped"]
+pub struct SpanGuard {
+    recorder: Option<Arc<Recorder>>,
+    phase: Phase,
+    gem: Option<String>,
+    started: Instant,
+}
+
+impl Profiler {
+    /// Create a profiler that records only when `enabled`
+    #[must_use]
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            recorder: enabled.then(|| {
+                Arc::new(Recorder {
+                    origin: Instant::now(),
+                    spans: Mutex::new((Vec::new(), Vec::new())),
+                })
+            }),
+        }
+    }
+
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Time a whole phase
+    pub fn phase(&self, phase: Phase) -> SpanGuard {
+        self.span(phase, None)
+    }
+
+    /// Time one gem's share of a phase
+    pub fn gem(&self, phase: Phase, gem: &str) -> SpanGuard {
+        self.span(phase, self.is_enabled().then(|| gem.to_string()))
+    }
+
+    fn span(&self, phase: Phase, gem: Option<String>) -> SpanGuard {
+        SpanGuard {
+            recorder: self.recorder.clone(),
+            phase,
+            gem,
+            started: Instant::now(),
+        }
+    }
+
+    /// Spans recorded so far, in completion order
+    #[must_use]
+    pub fn spans(&self) -> Vec<SpanRecord> {
+        self.recorder
+            .as_ref()
+            .and_then(|recorder| recorder.spans.lock().ok().map(|spans| spans.0.clone()))
+            .unwrap_or_default()
+    }
+
+    /// Time since profiling started
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        self.recorder
+            .as_ref()
+            .map_or(Duration::ZERO, |recorder| recorder.origin.elapsed())
+    }
+
+    /// Flame-style breakdown: wall time per phase, then the slowest gems
+    /// within it
+    ///
+    /// Per-gem times within a phase can add up to more than the phase when
+    /// gems are processed in parallel.
+    #[must_use]
+    pub fn summary(&self) -> String {
+        summarize(&self.spans(), self.elapsed())
+    }
+
+    /// Write the spans as Chrome trace event JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn write_chrome_trace(&self, path: &Path) -> std::io::Result<()> {
+        let trace = chrome_trace(&self.spans());
+        let json = serde_json::to_string(&trace).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        let duration = self.started.elapsed();
+        let start = self.started.saturating_duration_since(recorder.origin);
+        let Ok(mut spans) = recorder.spans.lock() else {
+            return;
+        };
+        let (records, threads) = &mut *spans;
+        let current = std::thread::current().id();
+        let thread = threads
+            .iter()
+            .position(|id| *id == current)
+            .unwrap_or_else(|| {
+                threads.push(current);
+                threads.len() - 1
+            });
+        records.push(SpanRecord {
+            phase: self.phase,
+            gem: self.gem.take(),
+            start,
+            duration,
+            thread,
+        });
+    }
+}
+
+fn summarize(spans: &[SpanRecord], total: Duration) -> String {
+    let mut phases: BTreeMap<Phase, (Duration, BTreeMap<&str, Duration>)> = BTreeMap::new();
+    for span in spans {
+        let (wall, gems) = phases.entry(span.phase).or_default();
+        match &span.gem {
+            Some(gem) => *gems.entry(gem.as_str()).or_default() += span.duration,
+            None => *wall += span.duration,
+        }
+    }
+
+    let mut out = String::new();
+    writeln!(out, "Profile ({:.2}s total)", total.as_secs_f64()).ok();
+    for (phase, (wall, gems)) in &phases {
+        // Phases timed only per gem fall back to the sum of their gems
+        let wall = if wall.is_zero() {
+            gems.values().sum()
+        } else {
+            *wall
+        };
+        writeln!(
+            out,
+            "  {:<12} {:>8.2}s  {}",
+            phase.name(),
+            wall.as_secs_f64(),
+            bar(wall, total)
+        )
+        .ok();
+
+        let mut slowest: Vec<_> = gems.iter().collect();
+        slowest.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        for (gem, duration) in slowest.iter().take(TOP_GEMS) {
+            writeln!(
+                out,
+                "    {gem:<28} {:>8.2}s  {}",
+                duration.as_secs_f64(),
+                bar(**duration, total)
+            )
+            .ok();
+        }
+        if slowest.len() > TOP_GEMS {
+            writeln!(out, "    ... {} more", slowest.len() - TOP_GEMS).ok();
+        }
+    }
+    out
+}
+
+fn bar(duration: Duration, total: Duration) -> String {
+    if total.is_zero() {
+        return String::new();
+    }
+    let fraction = (duration.as_secs_f64() / total.as_secs_f64()).min(1.0);
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss,
+        reason = "fraction is clamped to 0..=1"
+    )]
+    let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+    "#".repeat(filled.max(1))
+}
+
+fn chrome_trace(spans: &[SpanRecord]) -> serde_json::Value {
+    let events: Vec<_> = spans
+        .iter()
+        .map(|span| {
+            json!({
+                "name": span.gem.as_deref().unwrap_or_else(|| span.phase.name()),
+                "cat": span.phase.name(),
+                "ph": "X",
+                "ts": span.start.as_micros(),
+                "dur": span.duration.as_micros(),
+                "pid": 1,
+                "tid": span.thread,
+            })
+        })
+        .collect();
+    json!({ "traceEvents": events, "displayTimeUnit": "ms" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(phase: Phase, gem: Option<&str>, millis: u64) -> SpanRecord {
+        SpanRecord {
+            phase,
+            gem: gem.map(str::to_string),
+            start: Duration::ZERO,
+            duration: Duration::from_millis(millis),
+            thread: 0,
+        }
+    }
+
+    #[test]
+    fn disabled_profiler_records_nothing() {
+        let profiler = Profiler::new(false);
+        drop(profiler.gem(Phase::Download, "rack"));
+        assert!(profiler.spans().is_empty());
+    }
+
+    #[test]
+    fn spans_are_shared_between_clones() {
+        let profiler = Profiler::new(true);
+        let worker = profiler.clone();
+        std::thread::spawn(move || drop(worker.gem(Phase::Extraction, "rack")))
+            .join()
+            .expect("worker");
+        drop(profiler.phase(Phase::Extraction));
+
+        let [worker, main] = profiler.spans().try_into().expect("two spans");
+        assert_eq!(worker.gem.as_deref(), Some("rack"));
+        assert_eq!(main.gem, None);
+        assert_ne!(worker.thread, main.thread);
+    }
+
+    #[test]
+    fn summary_orders_phases_and_slowest_gems() {
+        let spans = [
+            span(Phase::Extensions, Some("nokogiri"), 4000),
+            span(Phase::Download, None, 1000),
+            span(Phase::Download, Some("rack"), 200),
+            span(Phase::Download, Some("rails"), 600),
+            span(Phase::Extensions, Some("puma"), 1000),
+        ];
+        let summary = summarize(&spans, Duration::from_secs(6));
+        let lines: Vec<_> = summary
+            .lines()
+            .map(str::split_whitespace)
+            .map(|mut words| {
+                (
+                    words.next().unwrap_or_default(),
+                    words.next().unwrap_or_default(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                ("Profile", "(6.00s"),
+                ("download", "1.00s"),
+                ("rails", "0.60s"),
+                ("rack", "0.20s"),
+                ("extensions", "5.00s"),
+                ("nokogiri", "4.00s"),
+                ("puma", "1.00s"),
+            ]
+        );
+    }
+
+    #[test]
+    fn chrome_trace_uses_complete_events() {
+        let trace = chrome_trace(&[span(Phase::Download, Some("rack"), 5)]);
+        let event = trace
+            .get("traceEvents")
+            .and_then(|events| events.get(0))
+            .expect("event");
+        assert_eq!(event.get("name"), Some(&json!("rack")));
+        assert_eq!(event.get("cat"), Some(&json!("download")));
+        assert_eq!(event.get("ph"), Some(&json!("X")));
+        assert_eq!(event.get("dur"), Some(&json!(5000)));
+    }
+}
@@ -0,0 +1,296 @@
+//! Request budgeting for gem servers
+//!
+//! RubyGems.org throttles API clients and answers `429 Too Many Requests`
+//! once they go over. Every request to a host draws from a single budget
+//! shared by all clients and tasks in the process:
+//!
+//! - an optional ceiling (`--max-requests-per-second`,
+//!   `LODE_MAX_REQUESTS_PER_SECOND` or the `max_requests_per_second` config
+//!   key) spaces requests out, for proxies that allow only a few
+//! - `RateLimit-Remaining` and `RateLimit-Reset` headers (or their `X-`
+//!   forms) slow requests down as the server's window runs out
+//! - a 429 pauses every request to the host for `Retry-After` (or an
+//!   exponential backoff) before the request is retried
+
+use reqwest::header::HeaderMap;
+use reqwest::{StatusCode, Url};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Times a rate-limited request is retried before its 429 is returned
+pub const MAX_RATE_LIMITED_RETRIES: u32 = 5;
+
+/// Longest pause taken for a single 429
+const MAX_BACKOFF: Duration = Duration::from_mins(5);
+
+/// Remaining requests below which the rest of the window is spread out
+const LOW_REMAINING: u64 = 10;
+
+/// Process-wide ceiling, set once at startup
+static MAX_RATE: OnceLock<Option<f64>> = OnceLock::new();
+
+/// Budgets by `host:port`
+static BUDGETS: LazyLock<Mutex<HashMap<String, Arc<RequestBudget>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Set the ceiling on requests per second to each host
+///
+/// Only the first call counts. Zero, negative and non-finite rates mean no
+/// ceiling.
+pub fn set_max_requests_per_second(rate: Option<f64>) {
+    if MAX_RATE
+        .set(rate.filter(|rate| rate.is_finite() && *rate > 0.0))
+        .is_err()
+    {
+        crate::trace!("request rate ceiling already set, ignoring {rate:?}");
+    }
+}
+
+/// The budget shared by every request to `url`'s host
+#[must_use]
+pub fn budget_for(url: &Url) -> Arc<RequestBudget> {
+    let host = format!(
+        "{}:{}",
+        url.host_str().unwrap_or_default(),
+        url.port_or_known_default().unwrap_or_default()
+    );
+    let mut budgets = BUDGETS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    Arc::clone(budgets.entry(host).or_insert_with(|| {
+        Arc::new(RequestBudget::new(
+            url.host_str().unwrap_or_default(),
+            MAX_RATE.get().copied().flatten(),
+        ))
+    }))
+}
+
+/// Send `request` within its host's budget, retrying when rate limited
+///
+/// # Errors
+///
+/// Returns the error of the request itself. A request still rate limited
+/// after [`MAX_RATE_LIMITED_RETRIES`] retries returns its 429 response.
+pub async fn send(request: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let budget = budget_for(request.url());
+
+    for _ in 0..MAX_RATE_LIMITED_RETRIES {
+        // Requests with streamed bodies cannot be sent twice
+        let Some(attempt) = request.try_clone() else {
+            break;
+        };
+        budget.acquire().await;
+        let response = client.execute(attempt).await?;
+        if budget
+            .observe(response.status(), response.headers(), Instant::now())
+            .is_none()
+        {
+            return Ok(response);
+        }
+    }
+
+    budget.acquire().await;
+    let response = client.execute(request).await?;
+    budget.observe(response.status(), response.headers(), Instant::now());
+    Ok(response)
+}
+
+/// Request pacing for one host
+#[derive(Debug)]
+pub struct RequestBudget {
+    host: String,
+    /// Spacing from the configured ceiling
+    min_interval: Duration,
+    state: Mutex<BudgetState>,
+}
+
+#[derive(Debug)]
+struct BudgetState {
+    /// Earliest start of the next request
+    next_slot: Instant,
+    /// No request starts before this, after a 429 or an exhausted window
+    paused_until: Option<Instant>,
+    /// Spacing the server's remaining allowance calls for
+    adaptive_interval: Duration,
+    /// 429s in a row, for exponential backoff
+    consecutive_limits: u32,
+}
+
+impl RequestBudget {
+    /// Budget for `host`, allowing at most `max_rate` requests per second
+    #[must_use]
+    pub fn new(host: &str, max_rate: Option<f64>) -> Self {
+        Self {
+            host: host.to_string(),
+            min_interval: max_rate.map_or(Duration::ZERO, |rate| {
+                Duration::try_from_secs_f64(rate.recip()).unwrap_or(Duration::ZERO)
+            }),
+            state: Mutex::new(BudgetState {
+                next_slot: Instant::now(),
+                paused_until: None,
+                adaptive_interval: Duration::ZERO,
+                consecutive_limits: 0,
+            }),
+        }
+    }
+
+    /// Wait for this request's turn
+    pub async fn acquire(&self) {
+        let wait = self.reserve(Instant::now());
+        if !wait.is_zero() {
+            crate::trace!("waiting {}ms for {}", wait.as_millis(), self.host);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Claim the next slot, returning how long to wait for it
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let start = state
+            .next_slot
+            .max(state.paused_until.unwrap_or(now))
+            .max(now);
+        state.next_slot = start + self.min_interval.max(state.adaptive_interval);
+        drop(state);
+        start - now
+    }
+
+    /// Learn from a response, returning the pause if it was rate limited
+    fn observe(&self, status: StatusCode, headers: &HeaderMap, now: Instant) -> Option<Duration> {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let reset = header_number(headers, "ratelimit-reset").map(reset_delay);
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let backoff = Duration::from_secs(1 << state.consecutive_limits.min(8));
+            let delay = header_number(headers, "retry-after")
+                .map(Duration::from_secs)
+                .or(reset)
+                .unwrap_or(backoff)
+                .clamp(Duration::from_secs(1), MAX_BACKOFF);
+            state.consecutive_limits += 1;
+            state.paused_until = Some(now + delay);
+            drop(state);
+            crate::warn!(
+                "Rate limited by {}, backing off {}s",
+                self.host,
+                delay.as_secs()
+            );
+            return Some(delay);
+        }
+
+        state.consecutive_limits = 0;
+        state.adaptive_interval = Duration::ZERO;
+        if let (Some(remaining), Some(reset)) =
+            (header_number(headers, "ratelimit-remaining"), reset)
+        {
+            if remaining == 0 {
+                state.paused_until = Some(now + reset.min(MAX_BACKOFF));
+            } else if remaining < LOW_REMAINING {
+                state.adaptive_interval = reset / u32::try_from(remaining).unwrap_or(u32::MAX);
+            }
+        }
+        None
+    }
+}
+
+/// A numeric header, also accepted with an `x-` prefix
+fn header_number(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers
+        .get(name)
+        .or_else(|| headers.get(format!("x-{name}")))
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Time until a reset given in seconds, or as a Unix timestamp
+fn reset_delay(reset: u64) -> Duration {
+    // Windows are never this long; it must be a timestamp
+    const TIMESTAMP_FLOOR: u64 = 1_000_000_000;
+    if reset < TIMESTAMP_FLOOR {
+        return Duration::from_secs(reset);
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Duration::from_secs(reset.saturating_sub(now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn ceiling_spaces_requests_across_callers() {
+        let budget = RequestBudget::new("gems.example.com", Some(4.0));
+        let unlimited = RequestBudget::new("rubygems.org", None);
+        let now = Instant::now();
+        assert_eq!(budget.reserve(now), Duration::ZERO);
+        assert_eq!(budget.reserve(now), Duration::from_millis(250));
+        assert_eq!(budget.reserve(now), Duration::from_millis(500));
+
+        assert_eq!(unlimited.reserve(now), Duration::ZERO);
+        assert_eq!(unlimited.reserve(now), Duration::ZERO);
+    }
+
+    #[test]
+    fn too_many_requests_pauses_the_host() {
+        let budget = RequestBudget::new("rubygems.org", None);
+        let now = Instant::now();
+
+        let delay = budget.observe(
+            StatusCode::TOO_MANY_REQUESTS,
+            &headers(&[("retry-after", "7")]),
+            now,
+        );
+        assert_eq!(delay, Some(Duration::from_secs(7)));
+        assert_eq!(budget.reserve(now), Duration::from_secs(7));
+
+        // Without Retry-After, backoff doubles with each 429 in a row
+        let budget = RequestBudget::new("rubygems.org", None);
+        let empty = HeaderMap::new();
+        let first = budget.observe(StatusCode::TOO_MANY_REQUESTS, &empty, now);
+        let second = budget.observe(StatusCode::TOO_MANY_REQUESTS, &empty, now);
+        assert_eq!(first, Some(Duration::from_secs(1)));
+        assert_eq!(second, Some(Duration::from_secs(2)));
+        assert_eq!(budget.observe(StatusCode::OK, &empty, now), None);
+    }
+
+    #[test]
+    fn low_remaining_allowance_slows_requests() {
+        let budget = RequestBudget::new("rubygems.org", None);
+        let now = Instant::now();
+        budget.observe(
+            StatusCode::OK,
+            &headers(&[("x-ratelimit-remaining", "5"), ("x-ratelimit-reset", "10")]),
+            now,
+        );
+        assert_eq!(budget.reserve(now), Duration::ZERO);
+        assert_eq!(budget.reserve(now), Duration::from_secs(2));
+
+        budget.observe(
+            StatusCode::OK,
+            &headers(&[("ratelimit-remaining", "0"), ("ratelimit-reset", "30")]),
+            now,
+        );
+        assert!(budget.reserve(now) >= Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,118 @@
+//! Remote sources of the `lode gem-*` commands
+//!
+//! Like `Gem.sources`, the list starts from the `.gemrc` `:sources:` (or the
+//! default source) and the command line edits it in order: `-s URL` appends
+//! a source and `--clear-sources` resets the list to the default. A source
+//! given right after `--clear-sources` replaces the default instead of
+//! joining it, so `--clear-sources -s URL` queries only `URL`.
+
+/// A source option from the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceEdit {
+    /// `-s URL` / `--source URL`
+    Add(String),
+    /// `--clear-sources`
+    Clear,
+}
+
+/// Sources to query in priority order, after applying `edits` to the
+/// `.gemrc` sources
+///
+/// Never empty: with no `.gemrc` sources the default source stands in.
+#[must_use]
+pub fn resolve(gemrc_sources: &[String], edits: &[SourceEdit]) -> Vec<String> {
+    resolve_with(&default_source(), gemrc_sources, edits)
+}
+
+/// `GEM_SOURCE`, else `RUBYGEMS_HOST`, else rubygems.org
+#[must_use]
+pub fn default_source() -> String {
+    normalize(&crate::env_vars::gem_source().unwrap_or_else(crate::env_vars::rubygems_host))
+}
+
+fn resolve_with(default: &str, gemrc_sources: &[String], edits: &[SourceEdit]) -> Vec<String> {
+    let mut sources: Vec<String> = Vec::new();
+    for source in gemrc_sources.iter().map(|source| normalize(source)) {
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+    if sources.is_empty() {
+        sources.push(default.to_string());
+    }
+
+    let mut cleared = false;
+    for edit in edits {
+        match edit {
+            SourceEdit::Clear => {
+                sources = vec![default.to_string()];
+                cleared = true;
+            }
+            SourceEdit::Add(url) => {
+                let url = normalize(url);
+                if std::mem::take(&mut cleared) {
+                    sources = vec![url];
+                } else if !sources.contains(&url) {
+                    sources.push(url);
+                }
+            }
+        }
+    }
+    sources
+}
+
+/// Sources compare without their trailing slash
+fn normalize(url: &str) -> String {
+    url.trim_end_matches('/').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFAULT: &str = "https://rubygems.org";
+
+    fn add(url: &str) -> SourceEdit {
+        SourceEdit::Add(url.to_string())
+    }
+
+    #[test]
+    fn sources_append_in_order() {
+        let gemrc = vec!["https://gems.example.com/".to_string()];
+        assert_eq!(resolve_with(DEFAULT, &[], &[]), vec![DEFAULT]);
+        assert_eq!(
+            resolve_with(
+                DEFAULT,
+                &gemrc,
+                &[add("https://a.test"), add("https://a.test/")]
+            ),
+            vec!["https://gems.example.com", "https://a.test"]
+        );
+    }
+
+    #[test]
+    fn clear_sources_composes_with_later_sources() {
+        let gemrc = vec!["https://gems.example.com".to_string()];
+        assert_eq!(
+            resolve_with(DEFAULT, &gemrc, &[SourceEdit::Clear]),
+            vec![DEFAULT]
+        );
+        assert_eq!(
+            resolve_with(
+                DEFAULT,
+                &gemrc,
+                &[
+                    SourceEdit::Clear,
+                    add("https://a.test"),
+                    add("https://b.test")
+                ]
+            ),
+            vec!["https://a.test", "https://b.test"]
+        );
+        // A source before --clear-sources is dropped with the rest
+        assert_eq!(
+            resolve_with(DEFAULT, &gemrc, &[add("https://a.test"), SourceEdit::Clear]),
+            vec![DEFAULT]
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! Shared output styling
+//!
+//! Commands color their output through this module so `--color` and
+//! `NO_COLOR` are honored in one place: errors red, warnings yellow and
+//! versions cyan. Like [`crate::debug`], the choice is set once at startup.
+//!
+//! The output level lives here too. `--verbose`, `--quiet` and `--silent`
+//! are accepted by every command (before or after its name) and, without
+//! them, `LODE_VERBOSITY` or `BUNDLE_VERBOSE` set the level, so each command
+//! reads one [`Verbosity`] instead of combining its own flags.
+
+use crate::messages::{Message, text};
+use std::fmt;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+static COLOR: OnceLock<ColorChoice> = OnceLock::new();
+static VERBOSITY: OnceLock<Verbosity> = OnceLock::new();
+
+/// When to color output (`--color`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and `NO_COLOR` is unset
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Output stream a styled string is written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Initialize color from the `--color` flag
+///
+/// `auto` becomes `never` when `NO_COLOR` or `BUNDLE_NO_COLOR` is set; an
+/// explicit `always` or `never` wins over the environment.
+pub fn init_color(choice: ColorChoice) {
+    let choice = match choice {
+        ColorChoice::Auto if crate::env_vars::no_color() || crate::env_vars::bundle_no_color() => {
+            ColorChoice::Never
+        }
+        choice => choice,
+    };
+    let _ = COLOR.set(choice);
+}
+
+/// Whether text written to `stream` is colored
+#[must_use]
+pub fn color_enabled(stream: Stream) -> bool {
+    match COLOR.get().copied().unwrap_or_default() {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => match stream {
+            Stream::Stdout => std::io::stdout().is_terminal(),
+            Stream::Stderr => std::io::stderr().is_terminal(),
+        },
+    }
+}
+
+/// Text wrapped in an ANSI color when `stream` is colored
+#[derive(Debug, Clone, Copy)]
+pub struct Styled<T> {
+    text: T,
+    code: &'static str,
+    stream: Stream,
+}
+
+impl<T: fmt::Display> Styled<T> {
+    fn render(&self, color: bool) -> String {
+        if color {
+            format!("\x1b[{}m{}\x1b[0m", self.code, self.text)
+        } else {
+            self.text.to_string()
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Styled<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(color_enabled(self.stream)))
+    }
+}
+
+/// Style an error label, written to stderr
+pub const fn error<T: fmt::Display>(text: T) -> Styled<T> {
+    Styled {
+        text,
+        code: "1;31",
+        stream: Stream::Stderr,
+    }
+}
+
+/// Style a warning label, written to stderr
+pub const fn warning<T: fmt::Display>(text: T) -> Styled<T> {
+    Styled {
+        text,
+        code: "33",
+        stream: Stream::Stderr,
+    }
+}
+
+/// Style a version number, written to stdout
+pub const fn version<T: fmt::Display>(text: T) -> Styled<T> {
+    Styled {
+        text,
+        code: "36",
+        stream: Stream::Stdout,
+    }
+}
+
+/// How much commands print, from quietest to loudest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Errors only (`--silent`)
+    Silent,
+    /// Errors and warnings (`--quiet`)
+    Quiet,
+    #[default]
+    Normal,
+    /// Extra detail (`--verbose`)
+    Verbose,
+}
+
+impl Verbosity {
+    /// Level from command-line flags, or `fallback` when none is given
+    ///
+    /// Flags can come from both the global and the command's own position,
+    /// so combinations are allowed and the quietest wins.
+    #[must_use]
+    pub const fn from_flags(verbose: bool, quiet: bool, silent: bool, fallback: Self) -> Self {
+        if silent {
+            Self::Silent
+        } else if quiet {
+            Self::Quiet
+        } else if verbose {
+            Self::Verbose
+        } else {
+            fallback
+        }
+    }
+
+    /// Parse a `LODE_VERBOSITY` value
+    #[must_use]
+    pub fn parse(level: &str) -> Option<Self> {
+        match level.trim().to_ascii_lowercase().as_str() {
+            "silent" => Some(Self::Silent),
+            "quiet" => Some(Self::Quiet),
+            "normal" => Some(Self::Normal),
+            "verbose" => Some(Self::Verbose),
+            _ => None,
+        }
+    }
+
+    /// Level without command-line flags: `LODE_VERBOSITY`, else `Verbose`
+    /// when `BUNDLE_VERBOSE` is set in the environment or `config`
+    #[must_use]
+    pub fn from_env(config: Option<&crate::BundleConfig>) -> Self {
+        if let Some(level) = crate::env_vars::lode_verbosity() {
+            if let Some(verbosity) = Self::parse(&level) {
+                return verbosity;
+            }
+            crate::warn!(
+                "Ignoring LODE_VERBOSITY={level}: expected silent, quiet, normal or verbose"
+            );
+        }
+        if crate::env_vars::bundle_verbose()
+            || config.and_then(|config| config.verbose).unwrap_or(false)
+        {
+            Self::Verbose
+        } else {
+            Self::Normal
+        }
+    }
+
+    /// Whether extra detail is printed
+    #[must_use]
+    pub fn is_verbose(self) -> bool {
+        self >= Self::Verbose
+    }
+
+    /// Whether progress and informational output is suppressed
+    #[must_use]
+    pub fn is_quiet(self) -> bool {
+        self <= Self::Quiet
+    }
+}
+
+/// Set the output level for the rest of the process
+pub fn init_verbosity(verbosity: Verbosity) {
+    let _ = VERBOSITY.set(verbosity);
+}
+
+/// The output level set at startup
+#[must_use]
+pub fn verbosity() -> Verbosity {
+    VERBOSITY.get().copied().unwrap_or_default()
+}
+
+/// Print a warning to stderr with a colored, localized `Warning:` label
+///
+/// Nothing is printed under `--silent`.
+pub fn warn(args: fmt::Arguments<'_>) {
+    if verbosity() == Verbosity::Silent {
+        return;
+    }
+    eprintln!("{} {args}", warning(text(Message::WarningLabel)));
+}
+
+/// Macro for printing warnings
+///
+/// Usage: `warn!("could not read {}", path.display())`
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        $crate::reporter::warn(format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styled_text_is_wrapped_only_when_colored() {
+        let styled = version("1.2.3");
+        assert_eq!(styled.render(false), "1.2.3");
+        assert_eq!(styled.render(true), "\x1b[36m1.2.3\x1b[0m");
+        assert_eq!(error("error:").render(true), "\x1b[1;31merror:\x1b[0m");
+    }
+
+    #[test]
+    fn quietest_flag_wins() {
+        let normal = Verbosity::Normal;
+        assert_eq!(
+            Verbosity::from_flags(true, false, false, normal),
+            Verbosity::Verbose
+        );
+        assert_eq!(
+            Verbosity::from_flags(true, true, false, normal),
+            Verbosity::Quiet
+        );
+        assert_eq!(
+            Verbosity::from_flags(false, true, true, normal),
+            Verbosity::Silent
+        );
+        assert_eq!(
+            Verbosity::from_flags(false, false, false, Verbosity::Verbose),
+            Verbosity::Verbose
+        );
+        assert!(Verbosity::Silent.is_quiet());
+        assert!(!Verbosity::Normal.is_quiet() && !Verbosity::Normal.is_verbose());
+        assert_eq!(Verbosity::parse(" Quiet"), Some(Verbosity::Quiet));
+        assert_eq!(Verbosity::parse("loud"), None);
+    }
+}
@@ -0,0 +1,215 @@
+//! Persistent dependency resolution cache
+//!
+//! Stores successful resolutions keyed by a digest of the Gemfile content,
+//! gem sources, platforms, target Ruby and prerelease setting, so re-locking an unchanged
+//! Gemfile skips `PubGrub` and the metadata requests behind it. Entries record
+//! the remote index stamp (`ETag`/`Last-Modified` of the compact index) they
+//! were resolved against and are discarded once the index moves on.
+
+use crate::resolver::ResolvedGem;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever the entry format changes so stale files are ignored
+const FORMAT_VERSION: u32 = 1;
+
+/// A cached resolution on disk
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    format: u32,
+    index_stamp: Option<String>,
+    gems: Vec<ResolvedGem>,
+}
+
+/// Directory of cached resolutions (`<cache>/resolutions/<key>.json`)
+#[derive(Debug, Clone)]
+pub struct ResolutionCache {
+    dir: PathBuf,
+}
+
+impl ResolutionCache {
+    /// Use `<cache_dir>/resolutions` for cached resolutions
+    #[must_use]
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            dir: cache_dir.join("resolutions"),
+        }
+    }
+
+    /// Compute the cache key for a resolution request
+    ///
+    /// Platforms and sources are sorted so their order does not matter.
+    /// `runtime` identifies the Ruby/`RubyGems` versions candidates were
+    /// checked against, since those exclude gem versions.
+    #[must_use]
+    pub fn key(
+        gemfile_content: &str,
+        sources: &[String],
+        platforms: &[String],
+        runtime: Option<&str>,
+        pre: bool,
+    ) -> String {
+        let mut sources = sources.to_vec();
+        sources.sort();
+        let mut platforms = platforms.to_vec();
+        platforms.sort();
+
+        let mut hasher = Sha256::new();
+        hasher.update(gemfile_content.as_bytes());
+        for source in &sources {
+            hasher.update(b"\0source:");
+            hasher.update(source.as_bytes());
+        }
+        for platform in &platforms {
+            hasher.update(b"\0platform:");
+            hasher.update(platform.as_bytes());
+        }
+        if let Some(runtime) = runtime {
+            hasher.update(b"\0runtime:");
+            hasher.update(runtime.as_bytes());
+        }
+        hasher.update(if pre { b"\0pre" } else { b"\0rel" });
+
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Look up a cached resolution
+    ///
+    /// When both the cached and current index stamps are known and differ,
+    /// the entry is stale: it is removed and `None` is returned. An unknown
+    /// current stamp (offline, `--local`) trusts the cached entry.
+    #[must_use]
+    pub fn load(&self, key: &str, index_stamp: Option<&str>) -> Option<Vec<ResolvedGem>> {
+        let path = self.entry_path(key);
+        let entry: Entry = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+
+        let stale = entry.format != FORMAT_VERSION
+            || matches!(
+                (entry.index_stamp.as_deref(), index_stamp),
+                (Some(cached), Some(current)) if cached != current
+            );
+        if stale {
+            crate::debug!("discarding stale resolution {key}");
+            fs::remove_file(&path).ok();
+            return None;
+        }
+
+        crate::debug!("reusing cached resolution {key}");
+        Some(entry.gems)
+    }
+
+    /// Store a successful resolution
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory or entry cannot be written.
+    pub fn store(
+        &self,
+        key: &str,
+        index_stamp: Option<&str>,
+        gems: &[ResolvedGem],
+    ) -> std::io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let entry = Entry {
+            format: FORMAT_VERSION,
+            index_stamp: index_stamp.map(str::to_string),
+            gems: gems.to_vec(),
+        };
+        let json = serde_json::to_vec(&entry).map_err(std::io::Error::other)?;
+
+        // Write then rename so a concurrent reader never sees a partial entry
+        let tmp = self.dir.join(format!("{key}.json.tmp"));
+        fs::write(&tmp, json)?;
+        fs::rename(tmp, self.entry_path(key))
+    }
+
+    /// Remove every cached resolution
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory exists but cannot be removed.
+    pub fn clear(&self) -> std::io::Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::ResolvedDependency;
+
+    fn sample() -> Vec<ResolvedGem> {
+        vec![ResolvedGem {
+            name: "rack".to_string(),
+            version: "3.1.0".to_string(),
+            platform: "ruby".to_string(),
+            dependencies: vec![ResolvedDependency {
+                name: "base64".to_string(),
+                requirement: ">= 0".to_string(),
+            }],
+            ruby_version: None,
+        }]
+    }
+
+    #[test]
+    fn key_ignores_platform_order() {
+        let sources = vec!["https://rubygems.org".to_string()];
+        let a = ResolutionCache::key(
+            "gem 'rack'",
+            &sources,
+            &["ruby".to_string(), "x86_64-linux".to_string()],
+            None,
+            false,
+        );
+        let b = ResolutionCache::key(
+            "gem 'rack'",
+            &sources,
+            &["x86_64-linux".to_string(), "ruby".to_string()],
+            None,
+            false,
+        );
+        assert_eq!(a, b);
+        assert_ne!(
+            a,
+            ResolutionCache::key("gem 'rails'", &sources, &[], None, false)
+        );
+        assert_ne!(
+            ResolutionCache::key("gem 'rack'", &sources, &[], None, false),
+            ResolutionCache::key("gem 'rack'", &sources, &[], None, true)
+        );
+        assert_ne!(
+            ResolutionCache::key("gem 'rack'", &sources, &[], Some("3.3.0"), false),
+            ResolutionCache::key("gem 'rack'", &sources, &[], Some("3.4.0"), false)
+        );
+    }
+
+    #[test]
+    fn round_trip_and_invalidation() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let cache = ResolutionCache::new(temp.path());
+
+        cache
+            .store("abc", Some("etag-1"), &sample())
+            .expect("store");
+        assert_eq!(cache.load("abc", Some("etag-1")), Some(sample()));
+        assert_eq!(cache.load("abc", None), Some(sample()));
+
+        // A newer remote index invalidates the entry for good
+        assert_eq!(cache.load("abc", Some("etag-2")), None);
+        assert_eq!(cache.load("abc", None), None);
+
+        cache.store("abc", None, &sample()).expect("store");
+        cache.clear().expect("clear");
+        assert_eq!(cache.load("abc", None), None);
+        cache.clear().expect("clear twice");
+    }
+}
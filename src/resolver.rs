@@ -1,15 +1,21 @@
 //! Gem version resolution using the `PubGrub` algorithm.
 
+use crate::gem_utils::requirement_satisfied;
 use crate::gemfile::Gemfile;
+use crate::platform::universal_matches;
+use crate::profile::{Phase, Profiler};
 use crate::rubygems_client::{GemVersion, RubyGemsClient, RubyGemsError};
+use crate::source_provider::SourceRoutes;
 use anyhow::{Context, Result};
 use pubgrub::{
     DefaultStringReporter, Dependencies, DependencyConstraints, DependencyProvider,
     PackageResolutionStatistics, Ranges, Reporter, SemanticVersion,
 };
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::Infallible;
-use std::sync::Arc;
+use std::fmt;
+use std::sync::{Arc, RwLock};
 use thiserror::Error;
 
 /// Errors that can occur during dependency resolution
@@ -37,13 +43,31 @@ pub enum ResolverError {
         #[source]
         source: RubyGemsError,
     },
+
+    #[error("Source provider error while resolving '{gem}': {message}")]
+    SourceProviderError { gem: String, message: String },
+}
+
+impl ResolverError {
+    /// Whether a gem source could not be reached, rather than the gem or
+    /// its requirements being at fault
+    #[must_use]
+    pub const fn is_network(&self) -> bool {
+        matches!(
+            self,
+            Self::NetworkError {
+                source: RubyGemsError::NetworkError { .. } | RubyGemsError::HttpError { .. },
+                ..
+            }
+        )
+    }
 }
 
 /// A resolved gem with its final version
 ///
 /// Represents a single gem at a specific version chosen by the resolver
 /// (similar to `bundle lock` output).
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ResolvedGem {
     /// Gem name
     pub name: String,
@@ -62,7 +86,7 @@ pub struct ResolvedGem {
 }
 
 /// A dependency of a resolved gem
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ResolvedDependency {
     /// Dependency name
     pub name: String,
@@ -71,6 +95,88 @@ pub struct ResolvedDependency {
     pub requirement: String,
 }
 
+/// A gem version passed over because it does not support the target
+/// interpreter (`required_ruby_version` / `required_rubygems_version`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncompatibleVersion {
+    /// Gem name
+    pub gem: String,
+
+    /// Newest version that was skipped
+    pub version: String,
+
+    /// `"Ruby"` or `"RubyGems"`
+    pub runtime: &'static str,
+
+    /// The requirement that excluded it (e.g., ">= 3.1")
+    pub requirement: String,
+
+    /// The interpreter version it was checked against
+    pub current: String,
+}
+
+impl fmt::Display for IncompatibleVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} requires {} {}, but the current {} version is {}",
+            self.gem, self.version, self.runtime, self.requirement, self.runtime, self.current
+        )
+    }
+}
+
+/// Which version of each gem resolution prefers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResolutionStrategy {
+    /// Newest versions allowed by the requirements
+    #[default]
+    Latest,
+    /// Currently locked versions where still allowed, newest otherwise
+    Minimal,
+    /// Oldest versions allowed by the requirements, to test lower bounds
+    OldestCompatible,
+}
+
+impl ResolutionStrategy {
+    /// Strategy named `name`, as used by `lode lock --strategy`
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "latest" => Some(Self::Latest),
+            "minimal" => Some(Self::Minimal),
+            "oldest-compatible" => Some(Self::OldestCompatible),
+            _ => None,
+        }
+    }
+
+    /// Name as accepted by [`Self::parse`]
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Latest => "latest",
+            Self::Minimal => "minimal",
+            Self::OldestCompatible => "oldest-compatible",
+        }
+    }
+
+    /// Pick from `candidates`, sorted oldest first, given the locked version
+    fn pick(
+        self,
+        candidates: &[(SemanticVersion, &str)],
+        locked: Option<&str>,
+    ) -> Option<SemanticVersion> {
+        let newest = candidates.last().map(|(version, _)| *version);
+        match self {
+            Self::Latest => newest,
+            Self::OldestCompatible => candidates.first().map(|(version, _)| *version),
+            Self::Minimal => locked
+                .and_then(|locked| candidates.iter().find(|(_, number)| *number == locked))
+                .map(|(version, _)| *version)
+                .or(newest),
+        }
+    }
+}
+
 /// Dependency resolver using `PubGrub` algorithm
 ///
 /// Uses `PubGrub` instead of Bundler's Molinillo, providing clearer error
@@ -82,6 +188,33 @@ pub struct Resolver {
 
     /// Cache of version ranges parsed from gem version requirements
     range_cache: std::sync::RwLock<HashMap<String, Ranges<SemanticVersion>>>,
+
+    /// Ruby version candidates must support (`required_ruby_version`)
+    ruby_version: Option<String>,
+
+    /// `RubyGems` version candidates must support (`required_rubygems_version`)
+    rubygems_version: Option<String>,
+
+    /// Newest versions skipped for interpreter incompatibility, by gem
+    incompatible: Arc<RwLock<BTreeMap<String, IncompatibleVersion>>>,
+
+    /// Gems served by source providers rather than the `RubyGems` client
+    source_routes: Arc<SourceRoutes>,
+
+    /// Times metadata fetches for `lock --profile`
+    profiler: Profiler,
+
+    /// Extra requirements on gems, applied only if they are resolved at all
+    constraints: Arc<BTreeMap<String, String>>,
+
+    /// Which allowed version of each gem to prefer
+    strategy: ResolutionStrategy,
+
+    /// Currently locked versions, preferred by [`ResolutionStrategy::Minimal`]
+    locked: Arc<BTreeMap<String, String>>,
+
+    /// Gems resolved from their `ruby` platform variant only
+    ruby_platform: Arc<BTreeSet<String>>,
 }
 
 impl Resolver {
@@ -91,9 +224,89 @@ impl Resolver {
         Self {
             client: Arc::new(client),
             range_cache: std::sync::RwLock::new(HashMap::new()),
+            ruby_version: None,
+            rubygems_version: None,
+            incompatible: Arc::new(RwLock::new(BTreeMap::new())),
+            source_routes: Arc::new(SourceRoutes::default()),
+            profiler: Profiler::default(),
+            constraints: Arc::new(BTreeMap::new()),
+            strategy: ResolutionStrategy::default(),
+            locked: Arc::new(BTreeMap::new()),
+            ruby_platform: Arc::new(BTreeSet::new()),
         }
     }
 
+    /// Exclude gem versions whose `required_ruby_version` rejects `version`
+    #[must_use]
+    pub fn with_ruby_version(mut self, version: Option<String>) -> Self {
+        self.ruby_version = version;
+        self
+    }
+
+    /// Exclude gem versions whose `required_rubygems_version` rejects `version`
+    #[must_use]
+    pub fn with_rubygems_version(mut self, version: Option<String>) -> Self {
+        self.rubygems_version = version;
+        self
+    }
+
+    /// Fetch the versions of routed gems from their source providers
+    #[must_use]
+    pub fn with_source_routes(mut self, routes: Arc<SourceRoutes>) -> Self {
+        self.source_routes = routes;
+        self
+    }
+
+    /// Record metadata fetch times in `profiler`
+    #[must_use]
+    pub fn with_profiler(mut self, profiler: Profiler) -> Self {
+        self.profiler = profiler;
+        self
+    }
+
+    /// Only consider versions of a gem satisfying its requirement here
+    ///
+    /// Unlike a Gemfile dependency, a constraint does not pull the gem into
+    /// the resolution; `update --security` uses it to hold gems at chosen
+    /// versions, transitive ones included.
+    #[must_use]
+    pub fn with_constraints(mut self, constraints: BTreeMap<String, String>) -> Self {
+        self.constraints = Arc::new(constraints);
+        self
+    }
+
+    /// Prefer versions by `strategy`, with `locked` versions by gem name
+    /// for [`ResolutionStrategy::Minimal`]
+    #[must_use]
+    pub fn with_strategy(
+        mut self,
+        strategy: ResolutionStrategy,
+        locked: BTreeMap<String, String>,
+    ) -> Self {
+        self.strategy = strategy;
+        self.locked = Arc::new(locked);
+        self
+    }
+
+    /// Resolve `gems` from their `ruby` platform variant, ignoring
+    /// precompiled ones (`force_ruby_platform.GEM`)
+    #[must_use]
+    pub fn with_ruby_platform(mut self, gems: BTreeSet<String>) -> Self {
+        self.ruby_platform = Arc::new(gems);
+        self
+    }
+
+    /// Versions the last resolution skipped because they need a different
+    /// Ruby or `RubyGems`, limited to cases where the skipped version would
+    /// otherwise have been chosen
+    #[must_use]
+    pub fn incompatible_versions(&self) -> Vec<IncompatibleVersion> {
+        self.incompatible
+            .read()
+            .map(|skipped| skipped.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
     /// Resolve dependencies from a Gemfile.
     ///
     /// Similar to running `bundle lock`.
@@ -110,24 +323,37 @@ impl Resolver {
         platforms: &[&str],
         allow_prerelease: bool,
     ) -> Result<Vec<ResolvedGem>, ResolverError> {
+        crate::debug!(
+            "resolving {} dependencies for platforms {platforms:?}",
+            gemfile.gems.len()
+        );
+
         // Pre-fetch direct dependencies to warm the cache
         // This reduces blocking operations during PubGrub resolution
         let mut fetch_tasks = Vec::with_capacity(gemfile.gems.len());
         for gem in &gemfile.gems {
             let client = Arc::clone(&self.client);
+            let routes = Arc::clone(&self.source_routes);
+            let profiler = self.profiler.clone();
             let gem_name = gem.name.clone();
 
+            // Keep only network failures; the resolver reports the rest
             let task = tokio::spawn(async move {
-                // Ignore errors - cache will be empty if fetch fails
-                drop(client.fetch_versions(&gem_name).await);
+                fetch_versions(&client, &routes, &profiler, &gem_name)
+                    .await
+                    .err()
+                    .filter(ResolverError::is_network)
             });
 
             fetch_tasks.push(task);
         }
 
         // Wait for all pre-fetches to complete
+        let mut network_failure = None;
         for task in fetch_tasks {
-            drop(task.await);
+            if let Ok(Some(err)) = task.await {
+                network_failure.get_or_insert(err);
+            }
         }
 
         // Create dependency provider for PubGrub
@@ -138,6 +364,15 @@ impl Resolver {
                 .map(std::string::ToString::to_string)
                 .collect(),
             allow_prerelease,
+            ruby_version: self.ruby_version.clone(),
+            rubygems_version: self.rubygems_version.clone(),
+            incompatible: Arc::clone(&self.incompatible),
+            source_routes: Arc::clone(&self.source_routes),
+            profiler: self.profiler.clone(),
+            constraints: Arc::clone(&self.constraints),
+            strategy: self.strategy,
+            locked: Arc::clone(&self.locked),
+            ruby_platform: Arc::clone(&self.ruby_platform),
             cache: std::sync::RwLock::new(HashMap::new()),
             root_deps: std::sync::RwLock::new(HashMap::new()),
         };
@@ -170,8 +405,20 @@ impl Resolver {
         let resolved =
             pubgrub::resolve(&provider, root_package.clone(), root_version).map_err(|err| {
                 use pubgrub::PubGrubError;
+                // Gems that could not be fetched look like they have no
+                // versions, so blame the network rather than the Gemfile
+                if let Some(failure) = network_failure.take() {
+                    return failure;
+                }
                 let message = match err {
-                    PubGrubError::NoSolution(tree) => DefaultStringReporter::report(&tree),
+                    PubGrubError::NoSolution(tree) => {
+                        let mut message = DefaultStringReporter::report(&tree);
+                        for skipped in self.incompatible_versions() {
+                            message.push('\n');
+                            message.push_str(&skipped.to_string());
+                        }
+                        message
+                    }
                     PubGrubError::ErrorRetrievingDependencies {
                         package,
                         version,
@@ -192,21 +439,19 @@ impl Resolver {
             }
 
             // Fetch the gem version details
-            let versions = provider
-                .client
-                .fetch_versions(&package)
-                .await
-                .map_err(|e| ResolverError::NetworkError {
-                    gem: package.clone(),
-                    source: e,
-                })?;
+            let versions = fetch_versions(
+                &provider.client,
+                &provider.source_routes,
+                &provider.profiler,
+                &package,
+            )
+            .await?;
 
             let version_str = version.to_string();
 
             // Find the matching version
-            let gem_version = versions
-                .iter()
-                .find(|v| v.number == version_str)
+            let gem_version = provider
+                .variant(&package, &versions, &version_str)
                 .ok_or_else(|| ResolverError::GemNotFound {
                     gem: format!("{package}-{version_str}"),
                 })?;
@@ -227,6 +472,7 @@ impl Resolver {
                 ruby_version: gem_version.ruby_version.clone(),
             });
         }
+        crate::debug!("resolved {} gems", result.len());
 
         // Sort by name for consistent output
         result.sort_by(|a, b| a.name.cmp(&b.name));
@@ -392,6 +638,29 @@ impl Resolver {
     }
 }
 
+/// Versions of `gem` from its source provider, or from `RubyGems`
+async fn fetch_versions(
+    client: &RubyGemsClient,
+    routes: &SourceRoutes,
+    profiler: &Profiler,
+    gem: &str,
+) -> Result<Vec<GemVersion>, ResolverError> {
+    let _span = profiler.gem(Phase::Metadata, gem);
+    match routes.versions(gem).await {
+        Some(versions) => versions.map_err(|e| ResolverError::SourceProviderError {
+            gem: gem.to_string(),
+            message: format!("{e:#}"),
+        }),
+        None => client
+            .fetch_versions(gem)
+            .await
+            .map_err(|e| ResolverError::NetworkError {
+                gem: gem.to_string(),
+                source: e,
+            }),
+    }
+}
+
 /// `PubGrub` dependency provider for `RubyGems`
 ///
 /// This implements `PubGrub`'s `DependencyProvider` trait to fetch gem metadata
@@ -400,6 +669,15 @@ struct RubyGemsDependencyProvider {
     client: Arc<RubyGemsClient>,
     platforms: Vec<String>,
     allow_prerelease: bool,
+    ruby_version: Option<String>,
+    rubygems_version: Option<String>,
+    incompatible: Arc<RwLock<BTreeMap<String, IncompatibleVersion>>>,
+    source_routes: Arc<SourceRoutes>,
+    profiler: Profiler,
+    constraints: Arc<BTreeMap<String, String>>,
+    strategy: ResolutionStrategy,
+    locked: Arc<BTreeMap<String, String>>,
+    ruby_platform: Arc<BTreeSet<String>>,
     #[allow(
         dead_code,
         reason = "Cache for future optimization of dependency provider"
@@ -440,31 +718,45 @@ impl DependencyProvider for RubyGemsDependencyProvider {
         // Note: Direct dependencies are pre-fetched and cached, so this is typically fast.
         // Only transitive dependencies will require blocking network calls.
         let Ok(versions) = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current()
-                .block_on(async { self.client.fetch_versions(package).await })
+            tokio::runtime::Handle::current().block_on(fetch_versions(
+                &self.client,
+                &self.source_routes,
+                &self.profiler,
+                package,
+            ))
         }) else {
             return Ok(None);
         };
 
         // Filter by platform
+        let ruby_only = self.ruby_platform.contains(package);
         let compatible_versions: Vec<_> = versions
             .into_iter()
             .filter(|v| {
-                self.platforms.is_empty()
-                    || v.platform.is_empty()
-                    || v.platform == "ruby"
-                    || self.platforms.contains(&v.platform)
+                is_ruby_platform(v)
+                    || (!ruby_only
+                        && (self.platforms.is_empty()
+                            || self.platforms.contains(&v.platform)
+                            || self
+                                .platforms
+                                .iter()
+                                .any(|p| universal_matches(&v.platform, p))))
             })
             .collect();
 
-        // Find the highest version that matches the range
-        let mut matching_versions: Vec<SemanticVersion> = compatible_versions
+        // Find the versions that match the range
+        let mut in_range: Vec<(SemanticVersion, &GemVersion)> = compatible_versions
             .iter()
             .filter_map(|v| {
                 // Filter out prereleases unless explicitly allowed
                 if !self.allow_prerelease && is_prerelease(&v.number) {
                     return None;
                 }
+                if let Some(requirement) = self.constraints.get(package)
+                    && !requirement_satisfied(&v.number, requirement)
+                {
+                    return None;
+                }
 
                 let parts: Vec<&str> = v.number.split('.').collect();
                 let major = parts.first()?.parse::<u32>().ok()?;
@@ -472,16 +764,29 @@ impl DependencyProvider for RubyGemsDependencyProvider {
                 let patch = parts.get(2)?.parse::<u32>().ok().unwrap_or(0);
 
                 let sem_ver = SemanticVersion::new(major, minor, patch);
-                if range.contains(&sem_ver) {
-                    Some(sem_ver)
-                } else {
-                    None
-                }
+                range.contains(&sem_ver).then_some((sem_ver, v))
             })
             .collect();
+        in_range.sort_by_key(|(sem_ver, _)| *sem_ver);
+
+        // Skip versions that cannot run on the target interpreter, noting
+        // when that hides the version we would otherwise have picked
+        if let Some((_, newest)) = in_range.last()
+            && let Some(skipped) = self.incompatibility(package, newest)
+            && let Ok(mut incompatible) = self.incompatible.write()
+        {
+            incompatible.insert(package.clone(), skipped);
+        }
+        let matching_versions: Vec<(SemanticVersion, &str)> = in_range
+            .into_iter()
+            .filter(|(_, v)| self.incompatibility(package, v).is_none())
+            .map(|(sem_ver, v)| (sem_ver, v.number.as_str()))
+            .collect();
 
-        matching_versions.sort();
-        Ok(matching_versions.last().copied())
+        Ok(self.strategy.pick(
+            &matching_versions,
+            self.locked.get(package).map(String::as_str),
+        ))
     }
 
     fn get_dependencies(
@@ -508,8 +813,12 @@ impl DependencyProvider for RubyGemsDependencyProvider {
         // Fetch gem metadata using block_in_place to bridge sync trait with async client
         // Pre-fetching reduces the number of blocking calls needed here
         let versions = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current()
-                .block_on(async { self.client.fetch_versions(package).await })
+            tokio::runtime::Handle::current().block_on(fetch_versions(
+                &self.client,
+                &self.source_routes,
+                &self.profiler,
+                package,
+            ))
         })
         .ok();
 
@@ -522,7 +831,7 @@ impl DependencyProvider for RubyGemsDependencyProvider {
         let version_str = version.to_string();
 
         // Find the specific version
-        let gem_version = versions.iter().find(|v| v.number == version_str);
+        let gem_version = self.variant(package, &versions, &version_str);
 
         let Some(gem_version) = gem_version else {
             return Ok(Dependencies::Unavailable(format!(
@@ -545,6 +854,45 @@ impl DependencyProvider for RubyGemsDependencyProvider {
 }
 
 impl RubyGemsDependencyProvider {
+    /// The entry for `number` of `package`, its `ruby` variant when the gem
+    /// is forced to the `ruby` platform
+    fn variant<'a>(
+        &self,
+        package: &str,
+        versions: &'a [GemVersion],
+        number: &str,
+    ) -> Option<&'a GemVersion> {
+        let ruby_only = self.ruby_platform.contains(package);
+        versions
+            .iter()
+            .find(|v| v.number == number && (!ruby_only || is_ruby_platform(v)))
+    }
+
+    /// Why `version` cannot run on the target Ruby/`RubyGems`, if it cannot
+    fn incompatibility(&self, package: &str, version: &GemVersion) -> Option<IncompatibleVersion> {
+        let checks = [
+            ("Ruby", &self.ruby_version, &version.ruby_version),
+            (
+                "RubyGems",
+                &self.rubygems_version,
+                &version.rubygems_version,
+            ),
+        ];
+        checks
+            .into_iter()
+            .find_map(|(runtime, current, requirement)| {
+                let current = current.as_deref()?;
+                let requirement = requirement.as_deref()?;
+                (!requirement_satisfied(current, requirement)).then(|| IncompatibleVersion {
+                    gem: package.to_string(),
+                    version: version.number.clone(),
+                    runtime,
+                    requirement: requirement.to_string(),
+                    current: current.to_string(),
+                })
+            })
+    }
+
     /// Parse a Ruby gem version requirement
     ///
     /// Simplified wrapper around the full requirement parser.
@@ -600,6 +948,11 @@ impl RubyGemsDependencyProvider {
     }
 }
 
+/// Whether a version is the pure-Ruby (`ruby` platform) variant
+fn is_ruby_platform(version: &GemVersion) -> bool {
+    version.platform.is_empty() || version.platform == "ruby"
+}
+
 /// Check if a version string indicates a prerelease version
 ///
 /// Prerelease versions typically contain: alpha, beta, rc, pre, dev
@@ -776,4 +1129,131 @@ mod tests {
             assert!(v1 < v2);
         }
     }
+
+    mod strategy {
+        use super::*;
+
+        #[test]
+        fn picks_newest_oldest_or_locked_version() {
+            let candidates = [
+                (SemanticVersion::new(2, 2, 9), "2.2.9"),
+                (SemanticVersion::new(3, 0, 0), "3.0.0"),
+                (SemanticVersion::new(3, 1, 7), "3.1.7"),
+            ];
+            assert_eq!(
+                ResolutionStrategy::Latest.pick(&candidates, Some("3.0.0")),
+                Some(SemanticVersion::new(3, 1, 7))
+            );
+            assert_eq!(
+                ResolutionStrategy::OldestCompatible.pick(&candidates, None),
+                Some(SemanticVersion::new(2, 2, 9))
+            );
+            assert_eq!(
+                ResolutionStrategy::Minimal.pick(&candidates, Some("3.0.0")),
+                Some(SemanticVersion::new(3, 0, 0))
+            );
+            assert_eq!(
+                ResolutionStrategy::Minimal.pick(&candidates, Some("1.6.0")),
+                Some(SemanticVersion::new(3, 1, 7))
+            );
+            assert_eq!(ResolutionStrategy::Latest.pick(&[], None), None);
+        }
+
+        #[test]
+        fn parses_strategy_names() {
+            for strategy in [
+                ResolutionStrategy::Latest,
+                ResolutionStrategy::Minimal,
+                ResolutionStrategy::OldestCompatible,
+            ] {
+                assert_eq!(ResolutionStrategy::parse(strategy.as_str()), Some(strategy));
+            }
+            assert_eq!(ResolutionStrategy::parse("newest"), None);
+        }
+    }
+
+    mod interpreter_compatibility {
+        use super::*;
+
+        fn provider(ruby: &str, rubygems: &str) -> RubyGemsDependencyProvider {
+            RubyGemsDependencyProvider {
+                client: Arc::new(RubyGemsClient::new("https://rubygems.org").expect("client")),
+                platforms: Vec::new(),
+                allow_prerelease: false,
+                ruby_version: Some(ruby.to_string()),
+                rubygems_version: Some(rubygems.to_string()),
+                incompatible: Arc::new(RwLock::new(BTreeMap::new())),
+                source_routes: Arc::new(SourceRoutes::default()),
+                profiler: Profiler::default(),
+                constraints: Arc::new(BTreeMap::new()),
+                strategy: ResolutionStrategy::Latest,
+                locked: Arc::new(BTreeMap::new()),
+                ruby_platform: Arc::new(BTreeSet::from(["nokogiri".to_string()])),
+                cache: RwLock::new(HashMap::new()),
+                root_deps: RwLock::new(HashMap::new()),
+            }
+        }
+
+        fn gem_version(ruby: Option<&str>, rubygems: Option<&str>) -> GemVersion {
+            GemVersion {
+                number: "3.1.0".to_string(),
+                platform: "ruby".to_string(),
+                ruby_version: ruby.map(str::to_string),
+                rubygems_version: rubygems.map(str::to_string),
+                created_at: None,
+                downloads_count: None,
+                summary: None,
+                dependencies: crate::rubygems_client::Dependencies::default(),
+            }
+        }
+
+        #[test]
+        fn excludes_versions_requiring_newer_ruby() {
+            let provider = provider("3.0.6", "3.4.10");
+            let skipped = provider
+                .incompatibility("rack", &gem_version(Some(">= 3.1"), None))
+                .expect("incompatible");
+            assert_eq!(skipped.runtime, "Ruby");
+            assert_eq!(
+                skipped.to_string(),
+                "rack 3.1.0 requires Ruby >= 3.1, but the current Ruby version is 3.0.6"
+            );
+
+            let skipped = provider
+                .incompatibility("rack", &gem_version(Some(">= 2.7"), Some(">= 3.5")))
+                .expect("incompatible");
+            assert_eq!(skipped.runtime, "RubyGems");
+        }
+
+        #[test]
+        fn accepts_compatible_or_unconstrained_versions() {
+            let provider = provider("3.3.0", "3.5.3");
+            assert!(
+                provider
+                    .incompatibility("rack", &gem_version(Some(">= 2.4, < 4"), Some(">= 0")))
+                    .is_none()
+            );
+            assert!(
+                provider
+                    .incompatibility("rack", &gem_version(None, None))
+                    .is_none()
+            );
+        }
+
+        #[test]
+        fn forced_gems_use_their_ruby_variant() {
+            let provider = provider("3.3.0", "3.5.3");
+            let precompiled = GemVersion {
+                platform: "x86_64-linux".to_string(),
+                ..gem_version(None, None)
+            };
+            let versions = [precompiled, gem_version(None, None)];
+
+            let forced = provider.variant("nokogiri", &versions, "3.1.0");
+            assert_eq!(forced.map(|v| v.platform.as_str()), Some("ruby"));
+            let other = provider.variant("ffi", &versions, "3.1.0");
+            assert_eq!(other.map(|v| v.platform.as_str()), Some("x86_64-linux"));
+            assert!(provider.variant("nokogiri", &versions, "3.2.0").is_none());
+        }
+    }
 }
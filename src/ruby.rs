@@ -125,6 +125,24 @@ fn detect_engine_from_command() -> Option<RubyEngine> {
     }
 }
 
+/// Detect the running interpreter's `RUBY_VERSION` and `Gem::VERSION`
+///
+/// Returns `None` when no `ruby` is on `PATH`.
+#[must_use]
+pub fn detect_interpreter_versions() -> Option<(String, String)> {
+    let output = Command::new("ruby")
+        .args(["-e", "print RUBY_VERSION, ' ', Gem::VERSION"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (ruby, rubygems) = stdout.trim().split_once(' ')?;
+    Some((ruby.to_string(), rubygems.to_string()))
+}
+
 /// Detect engine from platform string (e.g., "java" -> `JRuby`)
 #[must_use]
 pub fn detect_engine_from_platform(platform: &str) -> RubyEngine {
@@ -332,6 +350,110 @@ pub fn get_standard_gem_paths(ruby_version: &str) -> Vec<PathBuf> {
     paths
 }
 
+/// Per-user gem directory (`Gem.user_dir`): `~/.gem/ruby/<ABI version>`
+#[must_use]
+pub fn user_gem_dir(ruby_version: &str) -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join(".gem")
+        .join("ruby")
+        .join(to_major_minor(ruby_version))
+}
+
+/// Ruby's own gem directory (`Gem.default_dir`), ignoring `GEM_HOME`
+///
+/// The first existing standard path other than the user gem directory,
+/// else the first standard path.
+#[must_use]
+pub fn default_gem_dir(ruby_version: &str) -> Option<PathBuf> {
+    let user_dir = user_gem_dir(ruby_version);
+    let candidates: Vec<PathBuf> = get_standard_gem_paths(&to_major_minor(ruby_version))
+        .into_iter()
+        .filter(|path| *path != user_dir)
+        .collect();
+    candidates
+        .iter()
+        .find(|path| path.is_dir())
+        .or_else(|| candidates.first())
+        .cloned()
+}
+
+/// Separator between `GEM_PATH` entries
+#[cfg(windows)]
+pub const GEM_PATH_SEPARATOR: &str = ";";
+/// Separator between `GEM_PATH` entries
+#[cfg(not(windows))]
+pub const GEM_PATH_SEPARATOR: &str = ":";
+
+/// `Gem.dir`: `GEM_HOME`, else Ruby's own gem directory
+#[must_use]
+pub fn gem_home(ruby_version: &str) -> PathBuf {
+    env_gem_home().unwrap_or_else(|| {
+        default_gem_dir(ruby_version).unwrap_or_else(|| user_gem_dir(ruby_version))
+    })
+}
+
+/// `GEM_HOME`, when set and not empty
+fn env_gem_home() -> Option<PathBuf> {
+    env::var("GEM_HOME")
+        .ok()
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// `Gem.default_path`: the user gem directory (when there is a home
+/// directory) and Ruby's own gem directory
+#[must_use]
+pub fn default_gem_path(ruby_version: &str) -> Vec<PathBuf> {
+    let user_dir = user_gem_dir(ruby_version);
+    let mut paths = Vec::new();
+    if dirs::home_dir().is_some_and(|home| home.exists()) {
+        paths.push(user_dir.clone());
+    }
+    paths.push(default_gem_dir(ruby_version).unwrap_or(user_dir));
+    paths
+}
+
+/// `Gem.path` for the current environment, in precedence order
+#[must_use]
+pub fn gem_path(ruby_version: &str) -> Vec<PathBuf> {
+    let defaults = default_gem_path(ruby_version);
+    // The last default is Ruby's own gem directory, `Gem.dir` without `GEM_HOME`
+    let home = env_gem_home()
+        .or_else(|| defaults.last().cloned())
+        .unwrap_or_else(|| user_gem_dir(ruby_version));
+    resolve_gem_path(env::var("GEM_PATH").ok().as_deref(), &home, &defaults)
+}
+
+/// `Gem.path`: `GEM_PATH` entries (plus the defaults when it ends with a
+/// separator), else the defaults, then `Gem.dir`, without duplicates
+#[must_use]
+pub fn resolve_gem_path(
+    gem_path_var: Option<&str>,
+    home: &Path,
+    default_path: &[PathBuf],
+) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = gem_path_var.filter(|var| !var.is_empty()).map_or_else(
+        || default_path.to_vec(),
+        |var| {
+            let mut paths: Vec<PathBuf> = var
+                .split(GEM_PATH_SEPARATOR)
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from)
+                .collect();
+            if var.ends_with(GEM_PATH_SEPARATOR) {
+                paths.extend(default_path.iter().cloned());
+            }
+            paths
+        },
+    );
+    paths.push(home.to_path_buf());
+
+    let mut seen = std::collections::HashSet::new();
+    paths.retain(|path| seen.insert(path.clone()));
+    paths
+}
+
 /// Get system gem directory: `GEM_HOME` env -> OS paths -> user gem dir -> gem command.
 #[must_use]
 pub fn get_system_gem_dir(ruby_version: &str) -> PathBuf {
@@ -1,8 +1,10 @@
 //! HTTP client for RubyGems.org API with cached metadata lookups.
 
+use crate::marshal;
+use crate::static_source::{self, StaticSource};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::io::Read;
 use std::sync::Arc;
 use std::time::Duration;
@@ -34,6 +36,13 @@ pub enum RubyGemsError {
         #[source]
         source: serde_json::Error,
     },
+
+    #[error("Failed to read the gem index at {url} for {gem}: {message}")]
+    IndexError {
+        gem: String,
+        url: String,
+        message: String,
+    },
 }
 
 /// Represents a gem version with its dependencies
@@ -53,6 +62,22 @@ pub struct GemVersion {
     #[serde(default)]
     pub ruby_version: Option<String>,
 
+    /// `RubyGems` version requirement (e.g., ">= 3.3.22")
+    #[serde(default)]
+    pub rubygems_version: Option<String>,
+
+    /// Release timestamp (e.g., "2024-01-15T12:34:56.789Z")
+    #[serde(default)]
+    pub created_at: Option<String>,
+
+    /// Downloads of this version
+    #[serde(default)]
+    pub downloads_count: Option<u64>,
+
+    /// One-line summary published with this version
+    #[serde(default)]
+    pub summary: Option<String>,
+
     /// Dependencies for this version
     #[serde(default)]
     pub dependencies: Dependencies,
@@ -96,6 +121,27 @@ pub struct BulkGemSpec {
     pub platform: String,
 }
 
+/// Gems per page of `/api/v1/search.json`
+pub const SEARCH_PAGE_SIZE: usize = 30;
+
+/// A gem found by `/api/v1/search.json`, at its latest release
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchHit {
+    /// Gem name
+    pub name: String,
+
+    /// Latest released version
+    pub version: String,
+
+    /// Platform of that release
+    #[serde(default = "ruby_platform")]
+    pub platform: String,
+}
+
+fn ruby_platform() -> String {
+    "ruby".to_string()
+}
+
 /// API response for gem versions endpoint
 #[derive(Debug, Deserialize)]
 #[allow(dead_code, reason = "Used for JSON deserialization")]
@@ -132,6 +178,10 @@ pub struct RubyGemsClient {
 
     /// Include prerelease versions (--pre mode)
     include_prerelease: bool,
+
+    /// The source as a static file tree, once known to be one
+    /// (`file://` sources, or servers with only `specs.4.8.gz`)
+    static_source: Arc<tokio::sync::OnceCell<Option<Arc<StaticSource>>>>,
 }
 
 impl RubyGemsClient {
@@ -254,6 +304,7 @@ impl RubyGemsClient {
             bulk_index_cache: Arc::new(tokio::sync::Mutex::new(None)),
             cache_only: false,
             include_prerelease: false,
+            static_source: Arc::new(tokio::sync::OnceCell::new()),
         })
     }
 
@@ -336,6 +387,11 @@ impl RubyGemsClient {
             }
         }
 
+        // A local file tree needs no network, even in cache-only mode
+        if static_source::is_file_url(&self.base_url) {
+            return self.fetch_static_versions(gem_name).await;
+        }
+
         if self.cache_only {
             return Err(RubyGemsError::GemNotFound {
                 gem: gem_name.to_string(),
@@ -343,19 +399,21 @@ impl RubyGemsClient {
         }
 
         let url = format!("{}/api/v1/versions/{}.json", self.base_url, gem_name);
+        crate::trace!("GET {url}");
 
-        let response =
-            self.client
-                .get(&url)
-                .send()
-                .await
-                .map_err(|e| RubyGemsError::NetworkError {
-                    gem: gem_name.to_string(),
-                    source: e,
-                })?;
+        let response = crate::rate_limit::send(self.client.get(&url))
+            .await
+            .map_err(|e| RubyGemsError::NetworkError {
+                gem: gem_name.to_string(),
+                source: e,
+            })?;
 
         let status = response.status();
         if status == reqwest::StatusCode::NOT_FOUND {
+            // Static servers have no API at all
+            if self.static_source().await.is_some() {
+                return self.fetch_static_versions(gem_name).await;
+            }
             return Err(RubyGemsError::GemNotFound {
                 gem: gem_name.to_string(),
             });
@@ -400,6 +458,160 @@ impl RubyGemsClient {
         Ok(result)
     }
 
+    /// The source as a static file tree, if it is one
+    ///
+    /// `file://` sources always are. An HTTP source is probed once, the
+    /// first time its API comes up empty.
+    async fn static_source(&self) -> Option<&Arc<StaticSource>> {
+        self.static_source
+            .get_or_init(|| async {
+                let is_static = static_source::is_file_url(&self.base_url)
+                    || StaticSource::probe(&self.client, &self.base_url).await;
+                is_static.then(|| {
+                    crate::debug!("{} is a static gem index", self.base_url);
+                    Arc::new(StaticSource::new(&self.base_url, self.client.clone()))
+                })
+            })
+            .await
+            .as_ref()
+    }
+
+    /// Versions of `gem_name` from a static file tree, cached like API
+    /// responses
+    async fn fetch_static_versions(
+        &self,
+        gem_name: &str,
+    ) -> Result<Vec<GemVersion>, RubyGemsError> {
+        let not_found = || RubyGemsError::GemNotFound {
+            gem: gem_name.to_string(),
+        };
+        let source = self.static_source().await.ok_or_else(not_found)?;
+        let versions = source
+            .versions(gem_name)
+            .await
+            .map_err(|e| RubyGemsError::IndexError {
+                gem: gem_name.to_string(),
+                url: self.base_url.clone(),
+                message: format!("{e:#}"),
+            })?;
+        if versions.is_empty() {
+            return Err(not_found());
+        }
+
+        let versions = Arc::new(versions);
+        self.cache
+            .write()
+            .await
+            .insert(gem_name.to_string(), Arc::clone(&versions));
+
+        let mut result = (*versions).clone();
+        if !self.include_prerelease {
+            result.retain(|v| !Self::is_prerelease(&v.number));
+        }
+        Ok(result)
+    }
+
+    /// Fingerprint of the remote compact index
+    ///
+    /// Issues a `HEAD` request for `/versions` and returns its `ETag` (or
+    /// `Last-Modified`), which changes whenever a gem is pushed or yanked.
+    /// Returns `None` in cache-only mode or when the server cannot tell us.
+    pub async fn index_stamp(&self) -> Option<String> {
+        if self.cache_only {
+            return None;
+        }
+
+        let url = format!("{}/versions", self.base_url.trim_end_matches('/'));
+        let response = self.client.head(&url).send().await.ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+
+        let headers = response.headers();
+        headers
+            .get(reqwest::header::ETAG)
+            .or_else(|| headers.get(reqwest::header::LAST_MODIFIED))
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    }
+
+    /// SHA-256 checksums of every published `.gem` for `gem_name`
+    ///
+    /// Read from the compact index (`/info/<gem>`) and keyed by
+    /// `version[-platform]`, matching the `.gem` file name after `<gem>-`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the gem does not exist.
+    pub async fn fetch_checksums(
+        &self,
+        gem_name: &str,
+    ) -> Result<BTreeMap<String, String>, RubyGemsError> {
+        if self.cache_only {
+            return Err(RubyGemsError::GemNotFound {
+                gem: gem_name.to_string(),
+            });
+        }
+
+        let url = format!("{}/info/{gem_name}", self.base_url.trim_end_matches('/'));
+        let network_error = |source| RubyGemsError::NetworkError {
+            gem: gem_name.to_string(),
+            source,
+        };
+        let response = crate::rate_limit::send(self.client.get(&url))
+            .await
+            .map_err(network_error)?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(RubyGemsError::GemNotFound {
+                gem: gem_name.to_string(),
+            });
+        }
+        if !status.is_success() {
+            return Err(RubyGemsError::HttpError {
+                gem: gem_name.to_string(),
+                status: status.as_u16(),
+                url,
+            });
+        }
+
+        let text = response.text().await.map_err(network_error)?;
+        Ok(parse_info_checksums(&text))
+    }
+
+    /// Names of every gem the source publishes
+    ///
+    /// Read from the compact index (`/names`), one request for the whole
+    /// source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the source has no compact
+    /// index.
+    pub async fn fetch_names(&self) -> Result<BTreeSet<String>, RubyGemsError> {
+        let url = format!("{}/names", self.base_url.trim_end_matches('/'));
+        let network_error = |source| RubyGemsError::NetworkError {
+            gem: "/names".to_string(),
+            source,
+        };
+        let response = crate::rate_limit::send(self.client.get(&url))
+            .await
+            .map_err(network_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(RubyGemsError::HttpError {
+                gem: "/names".to_string(),
+                status: status.as_u16(),
+                url,
+            });
+        }
+
+        let text = response.text().await.map_err(network_error)?;
+        Ok(parse_names(&text))
+    }
+
     /// Check if a version string is a prerelease
     ///
     /// Prerelease versions contain a hyphen (e.g., "1.0.0-alpha", "1.0.0-beta.1")
@@ -425,15 +637,12 @@ impl RubyGemsClient {
             self.base_url, gem_name, version
         );
 
-        let response =
-            self.client
-                .get(&url)
-                .send()
-                .await
-                .map_err(|e| RubyGemsError::NetworkError {
-                    gem: gem_name.to_string(),
-                    source: e,
-                })?;
+        let response = crate::rate_limit::send(self.client.get(&url))
+            .await
+            .map_err(|e| RubyGemsError::NetworkError {
+                gem: gem_name.to_string(),
+                source: e,
+            })?;
 
         let status = response.status();
         if status == reqwest::StatusCode::NOT_FOUND {
@@ -471,6 +680,87 @@ impl RubyGemsClient {
         })
     }
 
+    /// Fetch a gem's download counts from its API page
+    /// (`/api/v1/gems/<gem>.json`)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the gem does not exist or the request fails.
+    pub async fn fetch_gem_summary(&self, gem_name: &str) -> Result<GemSummary, RubyGemsError> {
+        let url = format!(
+            "{}/api/v1/gems/{gem_name}.json",
+            self.base_url.trim_end_matches('/')
+        );
+        let network_error = |source| RubyGemsError::NetworkError {
+            gem: gem_name.to_string(),
+            source,
+        };
+        let response = crate::rate_limit::send(self.client.get(&url))
+            .await
+            .map_err(network_error)?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(RubyGemsError::GemNotFound {
+                gem: gem_name.to_string(),
+            });
+        }
+        if !status.is_success() {
+            return Err(RubyGemsError::HttpError {
+                gem: gem_name.to_string(),
+                status: status.as_u16(),
+                url,
+            });
+        }
+
+        let text = response.text().await.map_err(network_error)?;
+        serde_json::from_str(&text).map_err(|e| RubyGemsError::ParseError {
+            gem: gem_name.to_string(),
+            source: e,
+        })
+    }
+
+    /// Fetch one page of gems matching `query` from the search API
+    /// (`/api/v1/search.json`)
+    ///
+    /// Pages start at 1 and hold up to [`SEARCH_PAGE_SIZE`] gems; a shorter
+    /// page is the last one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response is not a list
+    /// of gems.
+    pub async fn search(&self, query: &str, page: usize) -> Result<Vec<SearchHit>, RubyGemsError> {
+        let url = format!("{}/api/v1/search.json", self.base_url.trim_end_matches('/'));
+        crate::trace!("GET {url}?query={query}&page={page}");
+        let network_error = |source| RubyGemsError::NetworkError {
+            gem: query.to_string(),
+            source,
+        };
+        let response = crate::rate_limit::send(
+            self.client
+                .get(&url)
+                .query(&[("query", query), ("page", &page.to_string())]),
+        )
+        .await
+        .map_err(network_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(RubyGemsError::HttpError {
+                gem: query.to_string(),
+                status: status.as_u16(),
+                url,
+            });
+        }
+
+        let text = response.text().await.map_err(network_error)?;
+        serde_json::from_str(&text).map_err(|e| RubyGemsError::ParseError {
+            gem: query.to_string(),
+            source: e,
+        })
+    }
+
     /// Fetch the bulk gem index (`specs.4.8.gz` or `prerelease_specs.4.8.gz`).
     ///
     /// This downloads and parses the complete gem index, which contains basic
@@ -505,10 +795,7 @@ impl RubyGemsClient {
 
         let url = format!("{}/{}", self.base_url, index_file);
 
-        let response = self
-            .client
-            .get(&url)
-            .send()
+        let response = crate::rate_limit::send(self.client.get(&url))
             .await
             .context("Failed to download bulk gem index")?;
 
@@ -530,8 +817,7 @@ impl RubyGemsClient {
             .context("Failed to decompress bulk gem index")?;
 
         // Parse Marshal data
-        let marshal_value = alox_48::from_bytes(&decompressed)
-            .map_err(|e| anyhow::anyhow!("Failed to parse Marshal data: {e}"))?;
+        let marshal_value = marshal::load(&decompressed).context("Failed to parse Marshal data")?;
 
         // Convert Marshal array to Vec<BulkGemSpec>
         let specs = Self::parse_marshal_specs(&marshal_value)
@@ -550,7 +836,11 @@ impl RubyGemsClient {
     ///
     /// The Marshal data is an array of [name, version, platform] tuples.
     /// Example: `[["rails", "7.0.8", "ruby"], ["rack", "3.0.0", "ruby"], ...]`
-    fn parse_marshal_specs(value: &alox_48::Value) -> Result<Vec<BulkGemSpec>> {
+    fn parse_marshal_specs(value: &marshal::Value) -> Result<Vec<BulkGemSpec>> {
+        let utf8 = |bytes: &[u8], field: &str| {
+            String::from_utf8(bytes.to_vec()).with_context(|| format!("Invalid UTF-8 in {field}"))
+        };
+
         // The top level should be an array
         let specs_array = value
             .as_array()
@@ -560,73 +850,31 @@ impl RubyGemsClient {
 
         for spec_value in specs_array {
             // Each spec is an array: [name, version, platform]
-            let Some(spec_parts) = spec_value.as_array() else {
-                continue; // Skip malformed entries
+            let Some([name, version, platform, ..]) = spec_value.as_array() else {
+                continue; // Skip malformed or incomplete entries
             };
 
-            if spec_parts.len() < 3 {
-                continue; // Skip incomplete entries
-            }
-
-            // Extract name (String)
-            let Some(name) = spec_parts
-                .first()
-                .and_then(|v| v.as_string())
-                .map(|rb_str| String::from_utf8(rb_str.data.clone()))
-                .transpose()
-                .context("Invalid UTF-8 in gem name")?
-            else {
+            let Some(name) = name.as_bytes() else {
                 continue;
             };
+            let name = utf8(name, "gem name")?;
 
-            // Extract version (could be String or Array for Gem::Version)
-            let Some(version_value) = spec_parts.get(1) else {
+            // Version is a Gem::Version, or a plain string from older servers
+            let Some(version) = version
+                .as_bytes()
+                .or_else(|| version.gem_version().map(str::as_bytes))
+            else {
                 continue;
             };
+            let version = utf8(version, "version field")?;
 
-            let version = if let Some(rb_str) = version_value.as_string() {
-                String::from_utf8(rb_str.data.clone()).context("Invalid UTF-8 in version field")?
-            } else if let Some(arr) = version_value.as_array() {
-                // Gem::Version is represented as an array with version string as first element
-                let Some(version_str) = arr
-                    .first()
-                    .and_then(|v| v.as_string())
-                    .map(|rb_str| String::from_utf8(rb_str.data.clone()))
-                    .transpose()
-                    .context("Invalid UTF-8 in version array")?
-                else {
-                    continue;
-                };
-                version_str
-            } else if let Some(obj) = version_value.as_object() {
-                // Try common field names for version objects
-                let keys = ["__value", "@version", "version"];
-                let Some(version_str) = keys
-                    .iter()
-                    .find_map(|&key| {
-                        let symbol = alox_48::Symbol::from(key.to_string());
-                        obj.fields.get(&symbol).and_then(|v| v.as_string())
-                    })
-                    .map(|rb_str| String::from_utf8(rb_str.data.clone()))
-                    .transpose()
-                    .context("Invalid UTF-8 in version object")?
-                else {
-                    continue;
-                };
-                version_str
-            } else {
-                continue;
+            let platform = match platform.as_bytes() {
+                Some(bytes) => utf8(bytes, "platform field")?,
+                None => platform
+                    .gem_platform()
+                    .unwrap_or_else(|| "ruby".to_string()),
             };
 
-            // Extract platform (String)
-            let platform = spec_parts
-                .get(2)
-                .and_then(|v| v.as_string())
-                .map(|rb_str| String::from_utf8(rb_str.data.clone()))
-                .transpose()
-                .context("Invalid UTF-8 in platform field")?
-                .unwrap_or_else(|| "ruby".to_string());
-
             result.push(BulkGemSpec {
                 name,
                 version,
@@ -681,6 +929,36 @@ impl RubyGemsClient {
     }
 }
 
+/// Checksums from a compact index `info` file
+///
+/// Each line after `---` reads `VERSION[-PLATFORM] DEPS|REQUIREMENTS`, where
+/// the requirements include `checksum:<sha256>`.
+fn parse_info_checksums(text: &str) -> BTreeMap<String, String> {
+    text.lines()
+        .skip_while(|line| *line != "---")
+        .skip(1)
+        .filter_map(|line| {
+            let (version, rest) = line.split_once(' ')?;
+            let (_, requirements) = rest.split_once('|')?;
+            let checksum = requirements
+                .split(',')
+                .find_map(|requirement| requirement.strip_prefix("checksum:"))?;
+            Some((version.to_string(), checksum.to_string()))
+        })
+        .collect()
+}
+
+/// Gem names from a compact index `/names` response
+fn parse_names(text: &str) -> BTreeSet<String> {
+    text.lines()
+        .skip_while(|line| *line != "---")
+        .skip(1)
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 /// Detailed gem metadata (for gem info command)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GemMetadata {
@@ -703,6 +981,26 @@ pub struct GemMetadata {
     pub post_install_message: Option<String>,
 }
 
+/// Download counts for a gem (`/api/v1/gems/<gem>.json`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GemSummary {
+    pub name: String,
+    /// Latest version
+    pub version: String,
+    /// Downloads across all versions
+    pub downloads: u64,
+    /// Downloads of the latest version
+    #[serde(default)]
+    pub version_downloads: u64,
+    /// Release timestamp of the latest version
+    #[serde(default)]
+    pub version_created_at: Option<String>,
+    #[serde(default)]
+    pub changelog_uri: Option<String>,
+    #[serde(default)]
+    pub source_code_uri: Option<String>,
+}
+
 /// Cache statistics
 #[derive(Debug)]
 pub struct CacheStats {
@@ -717,6 +1015,33 @@ pub struct CacheStats {
 mod tests {
     use super::*;
 
+    #[test]
+    fn info_checksums_by_version_and_platform() {
+        let info = "---\n\
+            1.0.0 |checksum:aaa\n\
+            1.16.0 mini_portile2:~> 2.8.2,racc:~> 1.4|checksum:bbb,ruby:>= 3.0\n\
+            1.16.0-x86_64-linux racc:~> 1.4|checksum:ccc,ruby:>= 3.0, < 3.4.dev\n";
+
+        let checksums = parse_info_checksums(info);
+
+        assert_eq!(checksums.len(), 3);
+        assert_eq!(checksums.get("1.0.0").map(String::as_str), Some("aaa"));
+        assert_eq!(checksums.get("1.16.0").map(String::as_str), Some("bbb"));
+        assert_eq!(
+            checksums.get("1.16.0-x86_64-linux").map(String::as_str),
+            Some("ccc")
+        );
+    }
+
+    #[test]
+    fn names_skip_the_header() {
+        let names = parse_names("created_at: 2026-10-01\n---\n-\nacme-billing\nrack\n");
+        assert_eq!(
+            names.into_iter().collect::<Vec<_>>(),
+            vec!["-", "acme-billing", "rack"]
+        );
+    }
+
     #[test]
     fn client_creation() {
         let client = RubyGemsClient::new("https://rubygems.org")
@@ -922,7 +1247,7 @@ mod tests {
             0x22, 0x09, b'r', b'u', b'b', b'y', // "ruby"
         ];
 
-        if let Ok(value) = alox_48::from_bytes(&marshal_bytes) {
+        if let Ok(value) = marshal::load(&marshal_bytes) {
             let parse_result = RubyGemsClient::parse_marshal_specs(&value);
             assert!(parse_result.is_err(), "Expected error for invalid UTF-8");
             let err_msg = format!("{:?}", parse_result.unwrap_err());
@@ -0,0 +1,140 @@
+//! `RubyGems` plugin loaders
+//!
+//! A gem with `rubygems_plugin.rb` in one of its require paths hooks into
+//! `RubyGems` (new commands, install hooks). `RubyGems` finds these through
+//! loader files in `<gem_home>/plugins`: one `<name>_plugin.rb` per gem that
+//! requires the plugin of its newest installed version. That directory is
+//! the manifest of installed plugins, so installs and `gem-pristine` write a
+//! gem's loader and uninstalls repoint or remove it, as `RubyGems` does.
+
+use crate::gem_store::GemStore;
+use crate::gemspec::Gemspec;
+use anyhow::Result;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// File `RubyGems` loads from each gem's require paths
+pub const PLUGIN_FILE: &str = "rubygems_plugin.rb";
+
+/// Directory of plugin loaders in a gem home
+#[must_use]
+pub fn plugins_dir(gem_home: &Path) -> PathBuf {
+    gem_home.join("plugins")
+}
+
+/// Loader file for gem `name`
+#[must_use]
+pub fn loader_path(gem_home: &Path, name: &str) -> PathBuf {
+    plugins_dir(gem_home).join(format!("{name}_plugin.rb"))
+}
+
+/// Plugin files an installed gem ships, in require path order
+#[must_use]
+pub fn plugin_files(gem_dir: &Path) -> Vec<PathBuf> {
+    let require_paths = Gemspec::load_installed(gem_dir)
+        .map_or_else(|| vec!["lib".to_string()], |spec| spec.require_paths);
+    require_paths
+        .iter()
+        .map(|path| gem_dir.join(path).join(PLUGIN_FILE))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Point gem `name`'s loader at the plugin of its newest installed version
+///
+/// The loader is removed when that version has no plugin or no version is
+/// left. Returns the loader written, if any.
+///
+/// # Errors
+///
+/// Returns an error if the gem directory cannot be read or the loader
+/// cannot be written or removed.
+pub fn regenerate(gem_home: &Path, name: &str) -> Result<Option<PathBuf>> {
+    let latest = GemStore::with_path(gem_home.join("gems"))
+        .find_gem_by_name(name)?
+        .pop();
+    let loader = loader_path(gem_home, name);
+
+    let Some(plugin) = latest.and_then(|gem| plugin_files(&gem.path).into_iter().next()) else {
+        if let Err(e) = fs::remove_file(&loader)
+            && e.kind() != io::ErrorKind::NotFound
+        {
+            return Err(e.into());
+        }
+        return Ok(None);
+    };
+
+    fs::create_dir_all(plugins_dir(gem_home))?;
+    fs::write(&loader, loader_source(gem_home, &plugin))?;
+    crate::debug!("wrote RubyGems plugin loader {}", loader.display());
+    Ok(Some(loader))
+}
+
+/// `require_relative` from `plugins/` when the plugin is inside the gem
+/// home, so the home can move, else an absolute `require`
+fn loader_source(gem_home: &Path, plugin: &Path) -> String {
+    plugin.strip_prefix(gem_home).map_or_else(
+        |_| format!("require '{}'\n", plugin.display()),
+        |relative| {
+            let relative = relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            format!("require_relative '../{relative}'\n")
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn install(gem_home: &Path, full_name: &str, with_plugin: bool) {
+        let lib = gem_home.join("gems").join(full_name).join("lib");
+        fs::create_dir_all(&lib).unwrap();
+        if with_plugin {
+            fs::write(lib.join(PLUGIN_FILE), "").unwrap();
+        }
+    }
+
+    #[test]
+    fn loader_follows_the_newest_version() {
+        let temp = tempfile::tempdir().unwrap();
+        let home = temp.path();
+        install(home, "rubygems-tasks-0.2.5", true);
+        install(home, "rubygems-tasks-0.2.6", true);
+
+        let loader = regenerate(home, "rubygems-tasks").unwrap().unwrap();
+        assert_eq!(loader, home.join("plugins/rubygems-tasks_plugin.rb"));
+        assert_eq!(
+            fs::read_to_string(&loader).unwrap(),
+            "require_relative '../gems/rubygems-tasks-0.2.6/lib/rubygems_plugin.rb'\n"
+        );
+
+        fs::remove_dir_all(home.join("gems/rubygems-tasks-0.2.6")).unwrap();
+        regenerate(home, "rubygems-tasks").unwrap();
+        assert!(
+            fs::read_to_string(&loader)
+                .unwrap()
+                .contains("rubygems-tasks-0.2.5")
+        );
+
+        fs::remove_dir_all(home.join("gems/rubygems-tasks-0.2.5")).unwrap();
+        assert_eq!(regenerate(home, "rubygems-tasks").unwrap(), None);
+        assert!(!loader.exists());
+    }
+
+    #[test]
+    fn newest_version_without_plugin_removes_loader() {
+        let temp = tempfile::tempdir().unwrap();
+        let home = temp.path();
+        install(home, "hooks-1.0.0", true);
+        regenerate(home, "hooks").unwrap();
+        install(home, "hooks-2.0.0", false);
+
+        assert_eq!(regenerate(home, "hooks").unwrap(), None);
+        assert!(!loader_path(home, "hooks").exists());
+    }
+}
@@ -0,0 +1,265 @@
+//! Dependency confusion checks for `lode audit --sources`
+//!
+//! A gem resolved from a public source is suspect when a private source
+//! also publishes its name, or when its name falls in a namespace the
+//! config pins to a private source (`source_pins`): whoever registers that
+//! name publicly could have their gem installed in place of the internal
+//! one.
+
+use crate::gemfile::Gemfile;
+use crate::lockfile::Lockfile;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Why a locked gem was flagged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceConcern {
+    /// The name starts with `prefix`, which is pinned to `source`
+    Pinned { prefix: String, source: String },
+    /// `source`, a private source, also publishes the name
+    Shadowed { source: String },
+}
+
+/// A locked gem that could have come from the wrong source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFinding {
+    pub gem: String,
+    pub version: String,
+    /// Source the Gemfile resolves the gem from
+    pub resolved_from: String,
+    pub concern: SourceConcern,
+}
+
+impl fmt::Display for SourceFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}) resolves from {}",
+            self.gem, self.version, self.resolved_from
+        )?;
+        match &self.concern {
+            SourceConcern::Pinned { prefix, source } => {
+                write!(f, " but `{prefix}*` is pinned to {source}")
+            }
+            SourceConcern::Shadowed { source } => {
+                write!(f, " but {source} also publishes it")
+            }
+        }
+    }
+}
+
+/// Whether `source` is rubygems.org, where anyone can publish a name
+#[must_use]
+pub fn is_public_source(source: &str) -> bool {
+    let host = source
+        .split_once("://")
+        .map_or(source, |(_, rest)| rest)
+        .split(['/', ':'])
+        .next()
+        .unwrap_or_default();
+    host.eq_ignore_ascii_case("rubygems.org") || host.eq_ignore_ascii_case("www.rubygems.org")
+}
+
+/// Source a gem name is pinned to, with the matching prefix
+///
+/// The longest matching prefix wins.
+#[must_use]
+pub fn pinned_source<'a>(
+    pins: &'a BTreeMap<String, String>,
+    gem: &str,
+) -> Option<(&'a str, &'a str)> {
+    pins.iter()
+        .filter(|(prefix, _)| gem.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(prefix, source)| (prefix.as_str(), source.as_str()))
+}
+
+/// Source the Gemfile resolves `gem` from: its `source` block or `source:`
+/// option, else the global source
+#[must_use]
+pub fn resolved_source<'a>(gemfile: &'a Gemfile, gem: &str) -> &'a str {
+    gemfile
+        .gems
+        .iter()
+        .find(|dependency| dependency.name == gem)
+        .and_then(|dependency| dependency.source.as_deref())
+        .unwrap_or(&gemfile.source)
+}
+
+/// Non-public sources named in the Gemfile or the pins
+#[must_use]
+pub fn private_sources(gemfile: &Gemfile, pins: &BTreeMap<String, String>) -> BTreeSet<String> {
+    std::iter::once(&gemfile.source)
+        .chain(&gemfile.sources)
+        .chain(gemfile.gems.iter().filter_map(|gem| gem.source.as_ref()))
+        .chain(pins.values())
+        .filter(|source| !is_public_source(source))
+        .map(|source| source.trim_end_matches('/').to_string())
+        .collect()
+}
+
+/// Locked gems resolved from a source other than their pinned one
+///
+/// These are errors whether or not the other source is public.
+#[must_use]
+pub fn pin_violations(
+    lockfile: &Lockfile,
+    gemfile: &Gemfile,
+    pins: &BTreeMap<String, String>,
+) -> Vec<SourceFinding> {
+    audit(lockfile, gemfile, pins, &BTreeMap::new())
+}
+
+/// Check every locked gem against the pins and the names each private
+/// source publishes (`private_names`, keyed by source)
+#[must_use]
+pub fn audit(
+    lockfile: &Lockfile,
+    gemfile: &Gemfile,
+    pins: &BTreeMap<String, String>,
+    private_names: &BTreeMap<String, BTreeSet<String>>,
+) -> Vec<SourceFinding> {
+    let mut seen = BTreeSet::new();
+    let mut findings = Vec::new();
+    for spec in &lockfile.gems {
+        if !seen.insert(spec.name.as_str()) {
+            continue;
+        }
+        let resolved_from = resolved_source(gemfile, &spec.name);
+        let finding = |concern| SourceFinding {
+            gem: spec.name.clone(),
+            version: spec.version.clone(),
+            resolved_from: resolved_from.to_string(),
+            concern,
+        };
+
+        if let Some((prefix, source)) = pinned_source(pins, &spec.name)
+            && !same_source(source, resolved_from)
+        {
+            findings.push(finding(SourceConcern::Pinned {
+                prefix: prefix.to_string(),
+                source: source.to_string(),
+            }));
+            continue;
+        }
+
+        if !is_public_source(resolved_from) {
+            continue;
+        }
+        if let Some(source) = private_names
+            .iter()
+            .find(|(_, names)| names.contains(&spec.name))
+            .map(|(source, _)| source)
+        {
+            findings.push(finding(SourceConcern::Shadowed {
+                source: source.clone(),
+            }));
+        }
+    }
+    findings
+}
+
+fn same_source(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::GemSpec;
+
+    const PRIVATE: &str = "https://gems.acme.internal";
+
+    fn lockfile(names: &[&str]) -> Lockfile {
+        let mut lockfile = Lockfile::new();
+        lockfile.gems = names
+            .iter()
+            .map(|name| {
+                GemSpec::new(
+                    (*name).to_string(),
+                    "1.0.0".to_string(),
+                    None,
+                    Vec::new(),
+                    Vec::new(),
+                )
+            })
+            .collect();
+        lockfile
+    }
+
+    fn gemfile() -> Gemfile {
+        Gemfile::parse(&format!(
+            "source \"https://rubygems.org\"\n\
+             gem \"rails\"\n\
+             gem \"acme-billing\"\n\
+             source \"{PRIVATE}\" do\n  gem \"acme-auth\"\nend\n"
+        ))
+        .expect("gemfile")
+    }
+
+    #[test]
+    fn public_source_detection() {
+        assert!(is_public_source("https://rubygems.org"));
+        assert!(is_public_source("https://rubygems.org/"));
+        assert!(is_public_source("http://www.rubygems.org"));
+        assert!(!is_public_source(PRIVATE));
+        assert!(!is_public_source("https://rubygems.org.evil.example"));
+    }
+
+    #[test]
+    fn pinned_prefixes_must_resolve_from_their_source() {
+        let pins = BTreeMap::from([
+            ("acme-".to_string(), format!("{PRIVATE}/")),
+            (
+                "acme-b".to_string(),
+                "https://billing.acme.internal".to_string(),
+            ),
+        ]);
+        let findings = pin_violations(
+            &lockfile(&["rails", "acme-auth", "acme-billing"]),
+            &gemfile(),
+            &pins,
+        );
+
+        let [finding] = findings.try_into().expect("one finding");
+        assert_eq!(finding.gem, "acme-billing");
+        assert_eq!(
+            finding.concern,
+            SourceConcern::Pinned {
+                prefix: "acme-b".to_string(),
+                source: "https://billing.acme.internal".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn public_gems_published_privately_are_shadowed() {
+        let gemfile = gemfile();
+        let sources = private_sources(&gemfile, &BTreeMap::new());
+        assert_eq!(sources, BTreeSet::from([PRIVATE.to_string()]));
+
+        let names = BTreeMap::from([(
+            PRIVATE.to_string(),
+            BTreeSet::from(["acme-auth".to_string(), "acme-billing".to_string()]),
+        )]);
+        let findings = audit(
+            &lockfile(&["rails", "acme-auth", "acme-billing", "acme-billing"]),
+            &gemfile,
+            &BTreeMap::new(),
+            &names,
+        );
+
+        let gems: Vec<_> = findings
+            .iter()
+            .map(|finding| finding.gem.as_str())
+            .collect();
+        assert_eq!(gems, vec!["acme-billing"]);
+        assert_eq!(
+            findings.first().map(ToString::to_string).as_deref(),
+            Some(
+                "acme-billing (1.0.0) resolves from https://rubygems.org \
+                 but https://gems.acme.internal also publishes it"
+            )
+        );
+    }
+}
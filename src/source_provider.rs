@@ -0,0 +1,650 @@
+//! Pluggable gem sources
+//!
+//! A Gemfile can pull gems from somewhere other than a gem server by naming a
+//! source type:
+//!
+//! ```ruby
+//! source "https://github.com/acme/widgets", type: "github" do
+//!   gem "widgets"
+//! end
+//! ```
+//!
+//! Each type is served by a [`SourceProvider`], which lists the versions a
+//! source offers and fetches `.gem` files from it. Lode ships the `github`
+//! (release assets) and `directory` (a folder of `.gem` files) providers;
+//! other code can [`SourceProviders::register`] its own. Gems from these
+//! sources are locked under `PLUGIN SOURCE` sections of the lockfile.
+
+use crate::gemfile::Gemfile;
+use crate::gemspec::{Gemspec, GemspecDependency};
+use crate::lockfile::{GemSpec, Lockfile, PluginSourceSpec};
+use crate::rubygems_client::{Dependencies, DependencySpec, GemVersion};
+use anyhow::{Context, Result};
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use thiserror::Error;
+
+/// Errors from matching Gemfile or lockfile sources to providers
+#[derive(Debug, Error)]
+pub enum SourceProviderError {
+    #[error("Unknown source type '{kind}' for {remote} (available: {available})")]
+    UnknownType {
+        kind: String,
+        remote: String,
+        available: String,
+    },
+}
+
+/// An alternative gem source
+///
+/// `remote` is the URI given in the Gemfile's `source` line. Providers must
+/// be shareable across the resolver's worker threads.
+pub trait SourceProvider: Send + Sync {
+    /// Source type this provider serves (`type: "<name>"` in the Gemfile)
+    fn name(&self) -> &str;
+
+    /// Every version of `gem` available from `remote`, with runtime
+    /// dependencies filled in for resolution
+    fn versions<'a>(
+        &'a self,
+        remote: &'a str,
+        gem: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<GemVersion>>>;
+
+    /// Write the `.gem` file for `spec` from `remote` to `destination`
+    fn fetch<'a>(
+        &'a self,
+        remote: &'a str,
+        spec: &'a GemSpec,
+        destination: &'a Path,
+    ) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Source providers by type name
+#[derive(Clone, Default)]
+pub struct SourceProviders {
+    providers: BTreeMap<String, Arc<dyn SourceProvider>>,
+}
+
+impl fmt::Debug for SourceProviders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.providers.keys()).finish()
+    }
+}
+
+impl SourceProviders {
+    /// The providers Lode ships with
+    ///
+    /// Release assets downloaded by the `github` provider are kept under
+    /// `<cache_dir>/source_providers/github`.
+    #[must_use]
+    pub fn builtin(cache_dir: &Path) -> Self {
+        let mut providers = Self::default();
+        providers.register(Arc::new(DirectoryProvider));
+        providers.register(Arc::new(GithubReleasesProvider::new(
+            cache_dir.join("source_providers").join("github"),
+        )));
+        providers
+    }
+
+    /// Add a provider, replacing any existing provider of the same type
+    pub fn register(&mut self, provider: Arc<dyn SourceProvider>) {
+        self.providers.insert(provider.name().to_string(), provider);
+    }
+
+    /// Provider for a source type
+    #[must_use]
+    pub fn get(&self, kind: &str) -> Option<Arc<dyn SourceProvider>> {
+        self.providers.get(kind).cloned()
+    }
+
+    /// Registered source type names, sorted
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        self.providers.keys().map(String::as_str).collect()
+    }
+
+    fn lookup(
+        &self,
+        kind: &str,
+        remote: &str,
+    ) -> Result<Arc<dyn SourceProvider>, SourceProviderError> {
+        self.get(kind)
+            .ok_or_else(|| SourceProviderError::UnknownType {
+                kind: kind.to_string(),
+                remote: remote.to_string(),
+                available: self.names().join(", "),
+            })
+    }
+}
+
+/// Where a gem from a typed source comes from
+#[derive(Clone)]
+struct Route {
+    provider: Arc<dyn SourceProvider>,
+    kind: String,
+    remote: String,
+}
+
+/// Gems that are served by a source provider instead of a gem server
+///
+/// Version lists are cached for the lifetime of the routes, so a resolution
+/// asks each provider about each gem once.
+#[derive(Default)]
+pub struct SourceRoutes {
+    routes: BTreeMap<String, Route>,
+    versions: RwLock<BTreeMap<String, Vec<GemVersion>>>,
+}
+
+impl fmt::Debug for SourceRoutes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.routes
+                    .iter()
+                    .map(|(gem, route)| (gem, format!("{} ({})", route.remote, route.kind))),
+            )
+            .finish()
+    }
+}
+
+impl SourceRoutes {
+    /// Route the gems declared inside typed `source` blocks
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a source names a type no provider serves.
+    pub fn from_gemfile(
+        gemfile: &Gemfile,
+        providers: &SourceProviders,
+    ) -> Result<Self, SourceProviderError> {
+        let mut routes = BTreeMap::new();
+        for source in &gemfile.plugin_sources {
+            let provider = providers.lookup(&source.kind, &source.remote)?;
+            for gem in gemfile
+                .gems
+                .iter()
+                .filter(|gem| gem.source.as_deref() == Some(source.remote.as_str()))
+            {
+                routes.insert(
+                    gem.name.clone(),
+                    Route {
+                        provider: Arc::clone(&provider),
+                        kind: source.kind.clone(),
+                        remote: source.remote.clone(),
+                    },
+                );
+            }
+        }
+        Ok(Self {
+            routes,
+            versions: RwLock::default(),
+        })
+    }
+
+    /// Route the gems locked under `PLUGIN SOURCE` sections
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a section names a type no provider serves.
+    pub fn from_lockfile(
+        lockfile: &Lockfile,
+        providers: &SourceProviders,
+    ) -> Result<Self, SourceProviderError> {
+        let mut routes = BTreeMap::new();
+        for source in &lockfile.plugin_sources {
+            let provider = providers.lookup(&source.kind, &source.remote)?;
+            for gem in &source.gems {
+                routes.insert(
+                    gem.clone(),
+                    Route {
+                        provider: Arc::clone(&provider),
+                        kind: source.kind.clone(),
+                        remote: source.remote.clone(),
+                    },
+                );
+            }
+        }
+        Ok(Self {
+            routes,
+            versions: RwLock::default(),
+        })
+    }
+
+    /// Whether no gem is served by a provider
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Whether `gem` is served by a provider
+    #[must_use]
+    pub fn contains(&self, gem: &str) -> bool {
+        self.routes.contains_key(gem)
+    }
+
+    /// Versions of a routed gem, or `None` for gems from gem servers
+    pub async fn versions(&self, gem: &str) -> Option<Result<Vec<GemVersion>>> {
+        let route = self.routes.get(gem)?;
+        if let Some(cached) = self.versions.read().ok()?.get(gem) {
+            return Some(Ok(cached.clone()));
+        }
+
+        let versions = route
+            .provider
+            .versions(&route.remote, gem)
+            .await
+            .with_context(|| format!("Failed to list {gem} from {}", route.remote));
+        if let (Ok(versions), Ok(mut cache)) = (&versions, self.versions.write()) {
+            cache.insert(gem.to_string(), versions.clone());
+        }
+        Some(versions)
+    }
+
+    /// Fetch a routed gem into `cache_dir` the way the download manager
+    /// names cached gems, so installation finds it there
+    ///
+    /// Returns `None` for gems from gem servers.
+    pub async fn fetch_into_cache(
+        &self,
+        spec: &GemSpec,
+        cache_dir: &Path,
+    ) -> Option<Result<PathBuf>> {
+        let route = self.routes.get(&spec.name)?;
+        let destination = cache_dir.join(format!("{}.gem", spec.full_name_with_platform()));
+        if destination.exists() {
+            return Some(Ok(destination));
+        }
+
+        let fetched = async {
+            fs::create_dir_all(cache_dir)?;
+            route
+                .provider
+                .fetch(&route.remote, spec, &destination)
+                .await
+                .with_context(|| {
+                    format!("Failed to fetch {} from {}", spec.full_name(), route.remote)
+                })?;
+            Ok(destination)
+        };
+        Some(fetched.await)
+    }
+
+    /// Lockfile `PLUGIN SOURCE` sections for the routed gems in `gems`
+    #[must_use]
+    pub fn plugin_sources(
+        &self,
+        gems: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Vec<PluginSourceSpec> {
+        let mut sources: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+        for gem in gems {
+            let gem = gem.as_ref();
+            if let Some(route) = self.routes.get(gem) {
+                sources
+                    .entry((route.remote.clone(), route.kind.clone()))
+                    .or_default()
+                    .push(gem.to_string());
+            }
+        }
+        sources
+            .into_iter()
+            .map(|((remote, kind), mut gems)| {
+                gems.sort();
+                gems.dedup();
+                PluginSourceSpec { remote, kind, gems }
+            })
+            .collect()
+    }
+}
+
+/// Serves `.gem` files from a local directory (e.g. a mounted artifact store)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DirectoryProvider;
+
+impl DirectoryProvider {
+    /// Gemspecs of the `.gem` files for `gem` in `dir`, with their paths
+    fn gems_in(dir: &Path, gem: &str) -> Result<Vec<(Gemspec, PathBuf)>> {
+        let prefix = format!("{gem}-");
+        let mut gems = Vec::new();
+        for entry in
+            fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?
+        {
+            let path = entry?.path();
+            let is_candidate =
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name.starts_with(&prefix)
+                            && Path::new(name)
+                                .extension()
+                                .is_some_and(|ext| ext.eq_ignore_ascii_case("gem"))
+                    });
+            if !is_candidate {
+                continue;
+            }
+            let spec = Gemspec::from_gem_file(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            if spec.name == gem {
+                gems.push((spec, path));
+            }
+        }
+        Ok(gems)
+    }
+}
+
+impl SourceProvider for DirectoryProvider {
+    fn name(&self) -> &'static str {
+        "directory"
+    }
+
+    fn versions<'a>(
+        &'a self,
+        remote: &'a str,
+        gem: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<GemVersion>>> {
+        Box::pin(async move {
+            Ok(Self::gems_in(Path::new(remote), gem)?
+                .iter()
+                .map(|(spec, _)| gem_version(spec))
+                .collect())
+        })
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        remote: &'a str,
+        spec: &'a GemSpec,
+        destination: &'a Path,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let (_, path) = Self::gems_in(Path::new(remote), &spec.name)?
+                .into_iter()
+                .find(|(gemspec, _)| gemspec.full_name() == spec.full_name_with_platform())
+                .with_context(|| {
+                    format!("{} not found in {remote}", spec.full_name_with_platform())
+                })?;
+            fs::copy(&path, destination)?;
+            Ok(())
+        })
+    }
+}
+
+/// Serves `.gem` files attached to a GitHub repository's releases
+///
+/// `remote` is the repository URL (`https://github.com/owner/repo`).
+/// Assets are mirrored into a local directory and then served like a
+/// [`DirectoryProvider`]. Set `GITHUB_TOKEN` for private repositories.
+#[derive(Debug, Clone)]
+pub struct GithubReleasesProvider {
+    mirror_dir: PathBuf,
+    client: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    #[serde(default)]
+    assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseAsset {
+    name: String,
+    url: String,
+}
+
+impl GithubReleasesProvider {
+    /// Mirror release assets under `mirror_dir/<owner>/<repo>`
+    #[must_use]
+    pub fn new(mirror_dir: PathBuf) -> Self {
+        let client = reqwest::Client::builder()
+            .user_agent(format!("lode/{}", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default();
+        Self { mirror_dir, client }
+    }
+
+    /// `(owner, repo)` from a GitHub repository URL or `owner/repo`
+    fn repository(remote: &str) -> Result<(&str, &str)> {
+        let path = remote
+            .trim_start_matches("https://github.com/")
+            .trim_start_matches("http://github.com/")
+            .trim_end_matches('/')
+            .trim_end_matches(".git");
+        path.split_once('/')
+            .filter(|(owner, repo)| !owner.is_empty() && !repo.is_empty() && !repo.contains('/'))
+            .with_context(|| format!("Not a GitHub repository: {remote}"))
+    }
+
+    fn request(&self, url: &str) -> reqwest::RequestBuilder {
+        let request = self.client.get(url);
+        match std::env::var("GITHUB_TOKEN") {
+            Ok(token) if !token.is_empty() => request.bearer_auth(token),
+            _ => request,
+        }
+    }
+
+    /// Download `.gem` assets of every release that are not mirrored yet
+    async fn sync(&self, remote: &str) -> Result<PathBuf> {
+        let (owner, repo) = Self::repository(remote)?;
+        let dir = self.mirror_dir.join(owner).join(repo);
+        fs::create_dir_all(&dir)?;
+
+        let releases: Vec<Release> = self
+            .request(&format!(
+                "https://api.github.com/repos/{owner}/{repo}/releases?per_page=100"
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        for asset in releases.iter().flat_map(|release| &release.assets) {
+            let is_gem = Path::new(&asset.name)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("gem"));
+            let path = dir.join(&asset.name);
+            if !is_gem || asset.name.contains('/') || path.exists() {
+                continue;
+            }
+            let bytes = self
+                .request(&asset.url)
+                .header(reqwest::header::ACCEPT, "application/octet-stream")
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            let temp = tempfile::NamedTempFile::new_in(&dir)?;
+            fs::write(temp.path(), &bytes)?;
+            temp.persist(&path)?;
+        }
+
+        Ok(dir)
+    }
+}
+
+impl SourceProvider for GithubReleasesProvider {
+    fn name(&self) -> &'static str {
+        "github"
+    }
+
+    fn versions<'a>(
+        &'a self,
+        remote: &'a str,
+        gem: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<GemVersion>>> {
+        Box::pin(async move {
+            let dir = self.sync(remote).await?;
+            DirectoryProvider
+                .versions(&dir.to_string_lossy(), gem)
+                .await
+        })
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        remote: &'a str,
+        spec: &'a GemSpec,
+        destination: &'a Path,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let dir = self.sync(remote).await?;
+            DirectoryProvider
+                .fetch(&dir.to_string_lossy(), spec, destination)
+                .await
+        })
+    }
+}
+
+/// Resolver metadata for a gem read from its gemspec
+#[must_use]
+pub fn gem_version(spec: &Gemspec) -> GemVersion {
+    let requirement =
+        |requirements: &[String]| (!requirements.is_empty()).then(|| requirements.join(", "));
+    let dependency = |dep: &GemspecDependency| DependencySpec {
+        name: dep.name.clone(),
+        requirements: dep.requirement_string(),
+    };
+
+    GemVersion {
+        number: spec.version.clone(),
+        platform: if spec.platform.is_empty() {
+            "ruby".to_string()
+        } else {
+            spec.platform.clone()
+        },
+        ruby_version: requirement(&spec.required_ruby_version),
+        rubygems_version: requirement(&spec.required_rubygems_version),
+        created_at: None,
+        downloads_count: None,
+        summary: spec.summary.clone(),
+        dependencies: Dependencies {
+            runtime: spec.runtime_dependencies().map(dependency).collect(),
+            development: Vec::new(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_gems::write_gem;
+
+    #[tokio::test]
+    async fn directory_provider_lists_and_fetches_gems() {
+        let store = tempfile::tempdir().expect("tempdir");
+        write_gem(store.path(), "widgets", "1.0.0", &[]);
+        write_gem(store.path(), "widgets", "1.1.0", &[("rack", "2.0")]);
+        write_gem(store.path(), "widgets-pro", "9.0.0", &[]);
+        let remote = store.path().display().to_string();
+
+        let mut versions: Vec<GemVersion> = DirectoryProvider
+            .versions(&remote, "widgets")
+            .await
+            .expect("versions");
+        versions.sort_by(|a, b| a.number.cmp(&b.number));
+        let numbers: Vec<&str> = versions.iter().map(|v| v.number.as_str()).collect();
+        assert_eq!(numbers, ["1.0.0", "1.1.0"]);
+        let deps = &versions.last().expect("1.1.0").dependencies.runtime;
+        assert_eq!(
+            deps.first().map(|d| d.requirements.as_str()),
+            Some(">= 2.0")
+        );
+
+        let cache = tempfile::tempdir().expect("tempdir");
+        let gemfile = Gemfile::parse(&format!(
+            "source \"{remote}\", type: \"directory\" do\n  gem \"widgets\"\nend\ngem \"rack\"\n"
+        ))
+        .expect("parse Gemfile");
+        let routes = SourceRoutes::from_gemfile(&gemfile, &SourceProviders::builtin(cache.path()))
+            .expect("routes");
+        assert!(routes.contains("widgets"));
+        assert!(routes.versions("rack").await.is_none());
+
+        let spec = GemSpec::new(
+            "widgets".to_string(),
+            "1.1.0".to_string(),
+            None,
+            Vec::new(),
+            Vec::new(),
+        );
+        let fetched = routes
+            .fetch_into_cache(&spec, cache.path())
+            .await
+            .expect("routed")
+            .expect("fetch");
+        assert_eq!(fetched, cache.path().join("widgets-1.1.0.gem"));
+        assert!(fetched.exists());
+
+        assert_eq!(
+            routes.plugin_sources(["rack", "widgets"]),
+            vec![PluginSourceSpec {
+                remote,
+                kind: "directory".to_string(),
+                gems: vec!["widgets".to_string()],
+            }]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn resolver_uses_routed_versions() {
+        let store = tempfile::tempdir().expect("tempdir");
+        write_gem(store.path(), "widgets", "1.0.0", &[]);
+        write_gem(store.path(), "widgets", "2.0.0", &[]);
+        let gemfile = Gemfile::parse(&format!(
+            "source \"{}\", type: \"directory\" do\n  gem \"widgets\", \"~> 1.0\"\nend\n",
+            store.path().display()
+        ))
+        .expect("parse Gemfile");
+        let cache = tempfile::tempdir().expect("tempdir");
+        let routes = SourceRoutes::from_gemfile(&gemfile, &SourceProviders::builtin(cache.path()))
+            .expect("routes");
+
+        let resolver = crate::Resolver::new(
+            crate::RubyGemsClient::new("https://rubygems.invalid").expect("client"),
+        )
+        .with_source_routes(Arc::new(routes));
+        let resolved = resolver
+            .resolve(&gemfile, &[], false)
+            .await
+            .expect("resolve");
+
+        let versions: Vec<_> = resolved
+            .iter()
+            .map(|gem| (gem.name.as_str(), gem.version.as_str()))
+            .collect();
+        assert_eq!(versions, [("widgets", "1.0.0")]);
+    }
+
+    #[test]
+    fn unknown_source_types_are_errors() {
+        let gemfile = Gemfile::parse(
+            "source \"https://artifacts.example.com\", type: \"s3\" do\n  gem \"widgets\"\nend\n",
+        )
+        .expect("parse Gemfile");
+        let cache = tempfile::tempdir().expect("tempdir");
+
+        let error = SourceRoutes::from_gemfile(&gemfile, &SourceProviders::builtin(cache.path()))
+            .expect_err("s3 is not a built-in type");
+        assert_eq!(
+            error.to_string(),
+            "Unknown source type 's3' for https://artifacts.example.com (available: directory, github)"
+        );
+    }
+
+    #[test]
+    fn github_repository_from_remote() {
+        assert_eq!(
+            GithubReleasesProvider::repository("https://github.com/acme/widgets.git")
+                .expect("repository"),
+            ("acme", "widgets")
+        );
+        assert!(GithubReleasesProvider::repository("https://example.com/").is_err());
+    }
+}
@@ -1,5 +1,6 @@
 //! Create standalone bundles that work without `Bundler` or `RubyGems`.
 
+use crate::gemfile::Gemfile;
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -317,6 +318,63 @@ impl StandaloneBundle {
 
         Ok(())
     }
+
+    /// Generate bundle/bundler/require.rb
+    ///
+    /// Loads setup.rb and defines `Bundler.require(*groups)`, which requires
+    /// each Gemfile gem in the bundle the way Bundler does: in Gemfile
+    /// order, skipping `require: false`, using custom `require:` names, and
+    /// ignoring a missing default file (after trying `foo/bar` for
+    /// `foo-bar`). Apps calling `Bundler.require` can then load the bundle
+    /// with `ruby -r ./bundle/bundler/require.rb` instead of Bundler.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if require.rb cannot be written.
+    pub fn generate_require_rb(&self, gems: &[StandaloneGem], gemfile: &Gemfile) -> Result<()> {
+        use std::fmt::Write;
+
+        let mut manifest = String::from(REQUIRE_HEADER);
+        for dependency in gemfile
+            .gems
+            .iter()
+            .filter(|dependency| gems.iter().any(|gem| gem.name == dependency.name))
+        {
+            let groups = if dependency.groups.is_empty() {
+                vec!["default".to_string()]
+            } else {
+                dependency.groups.clone()
+            };
+            let list = |items: &[String]| {
+                items
+                    .iter()
+                    .map(|item| ruby_string(item))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            writeln!(
+                &mut manifest,
+                "    [{}, [{}], [{}], {}],",
+                ruby_string(&dependency.name),
+                list(&groups),
+                list(&dependency.require_paths()),
+                dependency.require.is_some()
+            )
+            .expect("writing to string should not fail");
+        }
+        manifest.push_str(REQUIRE_FOOTER);
+
+        let require_path = self.root.join("bundler").join("require.rb");
+        fs::write(&require_path, manifest)
+            .with_context(|| format!("Failed to write require.rb to {}", require_path.display()))?;
+
+        Ok(())
+    }
+}
+
+/// Single-quoted Ruby string literal for `value`
+fn ruby_string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
 }
 
 /// Header template for bundle/bundler/setup.rb
@@ -360,6 +418,48 @@ else
 end
 "##;
 
+/// Opening of bundle/bundler/require.rb, up to the gem table
+const REQUIRE_HEADER: &str = r#"require_relative "setup"
+
+unless defined?(Bundler)
+  module Bundler
+    # [gem, groups, files to require, whether the Gemfile named the files]
+    GEMFILE_REQUIRES = [
+"#;
+
+/// Close of the gem table and the `Bundler.require` definition
+const REQUIRE_FOOTER: &str = r#"  ].freeze
+
+    def self.setup(*)
+      self
+    end
+
+    def self.require(*groups)
+      groups = [:default] if groups.empty?
+      groups = groups.map(&:to_s)
+      GEMFILE_REQUIRES.each do |name, gem_groups, files, explicit|
+        next unless (gem_groups & groups).any?
+
+        files.each do |file|
+          Kernel.require file
+        rescue LoadError => e
+          raise if explicit || e.path != file
+
+          namespaced = name.tr("-", "/")
+          next if namespaced == name
+
+          begin
+            Kernel.require namespaced
+          rescue LoadError => e
+            raise if e.path != namespaced
+          end
+        end
+      end
+    end
+  end
+end
+"#;
+
 /// Recursively copy a directory and all its contents
 fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
     if !src.exists() {
@@ -485,4 +585,51 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn require_rb_generation() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let options = StandaloneOptions {
+            bundle_path: temp_dir.path().to_path_buf(),
+            groups: vec![],
+        };
+        let bundle = StandaloneBundle::new(options, "3.3.0", "ruby")?;
+        bundle.create_directories()?;
+
+        let gem = |name: &str| StandaloneGem {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            platform: None,
+            extracted_path: PathBuf::from("/tmp").join(name),
+            extension_path: None,
+            has_extensions: false,
+        };
+        let gemfile = Gemfile::parse(
+            r#"gem "rack"
+gem "bootsnap", require: false
+gem "sprockets-rails", require: "sprockets/railtie"
+gem "rspec", group: :test
+gem "not-in-bundle"
+"#,
+        )?;
+        bundle.generate_require_rb(
+            &[
+                gem("rack"),
+                gem("bootsnap"),
+                gem("sprockets-rails"),
+                gem("rspec"),
+            ],
+            &gemfile,
+        )?;
+
+        let content = fs::read_to_string(temp_dir.path().join("bundler/require.rb"))?;
+        assert!(content.starts_with("require_relative \"setup\""));
+        assert!(content.contains("['rack', ['default'], ['rack'], false],"));
+        assert!(content.contains("['bootsnap', ['default'], [], true],"));
+        assert!(content.contains("['sprockets-rails', ['default'], ['sprockets/railtie'], true],"));
+        assert!(content.contains("['rspec', ['test'], ['rspec'], false],"));
+        assert!(!content.contains("not-in-bundle"));
+
+        Ok(())
+    }
 }
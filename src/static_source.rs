@@ -0,0 +1,329 @@
+//! Gem sources served as plain file trees
+//!
+//! `gem generate_index` output is nothing but files: the Marshal indexes
+//! `specs.4.8.gz` and `prerelease_specs.4.8.gz` next to a `gems/` directory
+//! of archives. Many internal mirrors are such a tree, either on disk
+//! (`source "file:///var/gems"`) or behind a static HTTP server with no API.
+//! Versions are listed from the indexes and each version's dependencies are
+//! read from its `quick/Marshal.4.8/` gemspec, falling back to the
+//! `metadata.gz` of its archive when the tree has no quick specs.
+
+use crate::full_index::{FullIndex, IndexGemSpec};
+use crate::gemspec::Gemspec;
+use crate::rubygems_client::GemVersion;
+use crate::source_provider::gem_version;
+use anyhow::{Context, Result};
+use futures_util::future::join_all;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Index of released versions
+const SPECS: &str = "specs.4.8.gz";
+
+/// Index of prerelease versions, which servers may leave out
+const PRERELEASE_SPECS: &str = "prerelease_specs.4.8.gz";
+
+/// Where a static source's files live
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Root {
+    Dir(PathBuf),
+    Url(String),
+}
+
+/// A gem source made of `specs.4.8.gz` and `gems/*.gem` files
+#[derive(Debug)]
+pub struct StaticSource {
+    root: Root,
+    client: reqwest::Client,
+    /// Released and prerelease indexes, read on first use
+    indexes: tokio::sync::OnceCell<Vec<FullIndex>>,
+}
+
+impl StaticSource {
+    /// Source rooted at `url`: a `file://` URL or an HTTP(S) base URL
+    #[must_use]
+    pub fn new(url: &str, client: reqwest::Client) -> Self {
+        let root = file_url_path(url).map_or_else(
+            || Root::Url(url.trim_end_matches('/').to_string()),
+            Root::Dir,
+        );
+        Self {
+            root,
+            client,
+            indexes: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    /// Whether the HTTP server at `base_url` only serves static index files
+    ///
+    /// True when it has `specs.4.8.gz` but no compact index (`/versions`),
+    /// which every gem server with an API provides.
+    pub async fn probe(client: &reqwest::Client, base_url: &str) -> bool {
+        let base = base_url.trim_end_matches('/');
+        let found = |path: String| async move {
+            client
+                .head(path)
+                .send()
+                .await
+                .is_ok_and(|response| response.status().is_success())
+        };
+        found(format!("{base}/{SPECS}")).await && !found(format!("{base}/versions")).await
+    }
+
+    /// Every version of `gem` in the indexes, with dependencies from the
+    /// archives
+    ///
+    /// Versions listed in an index whose archive is missing are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `specs.4.8.gz` is missing or unreadable, or an
+    /// archive cannot be read.
+    pub async fn versions(&self, gem: &str) -> Result<Vec<GemVersion>> {
+        let specs: Vec<IndexGemSpec> = self
+            .indexes()
+            .await?
+            .iter()
+            .filter_map(|index| index.find_gem(gem))
+            .flatten()
+            .collect();
+
+        let gemspecs = join_all(specs.iter().map(|spec| self.gemspec(spec))).await;
+        let mut versions = Vec::with_capacity(gemspecs.len());
+        for (spec, gemspec) in specs.iter().zip(gemspecs) {
+            if let Some(gemspec) = gemspec? {
+                versions.push(gem_version(&gemspec));
+            } else {
+                crate::debug!("{} is indexed but has no archive", spec.full_name());
+            }
+        }
+        Ok(versions)
+    }
+
+    /// The released and prerelease indexes
+    async fn indexes(&self) -> Result<&Vec<FullIndex>> {
+        self.indexes
+            .get_or_try_init(|| async {
+                let specs = self
+                    .read(SPECS)
+                    .await?
+                    .with_context(|| format!("No {SPECS} in {}", self.display()))?;
+                let mut indexes = vec![FullIndex::parse_gzipped(&specs)?];
+                if let Some(prerelease) = self.read(PRERELEASE_SPECS).await? {
+                    indexes.push(FullIndex::parse_gzipped(&prerelease)?);
+                }
+                Ok::<_, anyhow::Error>(indexes)
+            })
+            .await
+    }
+
+    /// Gemspec for `spec` from its quick spec or archive, if either exists
+    async fn gemspec(&self, spec: &IndexGemSpec) -> Result<Option<Gemspec>> {
+        let quick = format!("quick/Marshal.4.8/{}.gemspec.rz", spec.full_name());
+        if let Some(deflated) = self.read(&quick).await? {
+            return Gemspec::from_quick_spec(&deflated)
+                .map(Some)
+                .with_context(|| format!("Failed to read {quick} from {}", self.display()));
+        }
+
+        let filename = format!("{}.gem", spec.full_name());
+        let Some(archive) = self.read(&format!("gems/{filename}")).await? else {
+            return Ok(None);
+        };
+        Gemspec::from_gem_bytes(&archive, &filename)
+            .map(Some)
+            .with_context(|| format!("Failed to read {filename} from {}", self.display()))
+    }
+
+    /// Contents of the file at `path` under the root, or `None` if missing
+    async fn read(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        match &self.root {
+            Root::Dir(dir) => {
+                let file = dir.join(path);
+                match fs::read(&file) {
+                    Ok(bytes) => Ok(Some(bytes)),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                    Err(e) => Err(e).with_context(|| format!("Failed to read {}", file.display())),
+                }
+            }
+            Root::Url(base) => {
+                let url = format!("{base}/{path}");
+                crate::trace!("GET {url}");
+                let response = self
+                    .client
+                    .get(&url)
+                    .send()
+                    .await
+                    .with_context(|| format!("Failed to fetch {url}"))?;
+                if response.status() == reqwest::StatusCode::NOT_FOUND {
+                    return Ok(None);
+                }
+                let bytes = response
+                    .error_for_status()
+                    .with_context(|| format!("Failed to fetch {url}"))?
+                    .bytes()
+                    .await
+                    .with_context(|| format!("Failed to read {url}"))?;
+                Ok(Some(bytes.to_vec()))
+            }
+        }
+    }
+
+    fn display(&self) -> String {
+        match &self.root {
+            Root::Dir(dir) => dir.display().to_string(),
+            Root::Url(url) => url.clone(),
+        }
+    }
+}
+
+/// Whether `url` names a local directory (`file:///var/gems`)
+#[must_use]
+pub fn is_file_url(url: &str) -> bool {
+    url.get(..7)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("file://"))
+}
+
+/// Directory a `file://` URL points at
+///
+/// Accepts `file:///path` and `file://localhost/path`; other hosts name
+/// remote machines and are rejected.
+#[must_use]
+pub fn file_url_path(url: &str) -> Option<PathBuf> {
+    if !is_file_url(url) {
+        return None;
+    }
+    let rest = url.get(7..)?;
+    let path = rest.strip_prefix("localhost").unwrap_or(rest);
+    if !path.starts_with('/') {
+        return None;
+    }
+    let trimmed = path.trim_end_matches('/');
+    Some(PathBuf::from(if trimmed.is_empty() {
+        "/"
+    } else {
+        trimmed
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_gems::write_gem;
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    #[test]
+    fn file_urls() {
+        assert_eq!(
+            file_url_path("file:///var/gems/"),
+            Some(PathBuf::from("/var/gems"))
+        );
+        assert_eq!(
+            file_url_path("FILE://localhost/srv/gems"),
+            Some(PathBuf::from("/srv/gems"))
+        );
+        assert_eq!(file_url_path("file://mirror/gems"), None);
+        assert_eq!(file_url_path("https://rubygems.org"), None);
+        assert!(!is_file_url("https://rubygems.org"));
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes).expect("gzip");
+        encoder.finish().expect("gzip")
+    }
+
+    /// Marshal of `[[name, version, "ruby"], ...]`
+    fn marshal_specs(specs: &[(&str, &str)]) -> Vec<u8> {
+        let length = |n: usize| u8::try_from(n).expect("small") + 5;
+        let mut data = vec![4, 8, b'[', length(specs.len())];
+        for fields in specs
+            .iter()
+            .map(|(name, version)| [*name, *version, "ruby"])
+        {
+            data.extend_from_slice(&[b'[', length(3)]);
+            for field in fields {
+                data.extend_from_slice(&[b'"', length(field.len())]);
+                data.extend_from_slice(field.as_bytes());
+            }
+        }
+        data
+    }
+
+    #[tokio::test]
+    async fn versions_from_a_file_tree() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        write_gem(
+            &temp.path().join("gems"),
+            "widget",
+            "1.0.0",
+            &[("rack", "2")],
+        );
+        write_gem(&temp.path().join("gems"), "rack", "3.0.0", &[]);
+        fs::write(
+            temp.path().join(SPECS),
+            gzip(&marshal_specs(&[
+                ("widget", "1.0.0"),
+                ("widget", "2.0.0"),
+                ("rack", "3.0.0"),
+            ])),
+        )
+        .expect("write");
+
+        let url = format!("file://{}", temp.path().display());
+        let source = StaticSource::new(&url, reqwest::Client::new());
+        let versions = source.versions("widget").await.expect("versions");
+
+        // 2.0.0 is indexed without an archive
+        assert_eq!(versions.len(), 1);
+        let version = versions.first().expect("version");
+        assert_eq!(version.number, "1.0.0");
+        let deps = &version.dependencies.runtime;
+        assert_eq!(deps.len(), 1);
+        assert_eq!(deps.first().expect("dependency").name, "rack");
+        assert!(
+            source
+                .versions("missing")
+                .await
+                .expect("versions")
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn quick_specs_are_preferred_to_archives() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let quick = temp.path().join("quick/Marshal.4.8");
+        fs::create_dir_all(&quick).expect("mkdir");
+        fs::write(
+            quick.join("widget-1.0.0.gemspec.rz"),
+            crate::marshal::encode::quick_spec("widget", "1.0.0", &[("rack", ">= 2")]),
+        )
+        .expect("write");
+        fs::write(
+            temp.path().join(SPECS),
+            gzip(&marshal_specs(&[("widget", "1.0.0")])),
+        )
+        .expect("write");
+
+        let url = format!("file://{}", temp.path().display());
+        let source = StaticSource::new(&url, reqwest::Client::new());
+        let versions = source.versions("widget").await.expect("versions");
+
+        assert_eq!(versions.len(), 1);
+        let version = versions.first().expect("version");
+        let dep = version.dependencies.runtime.first().expect("dependency");
+        assert_eq!(dep.name, "rack");
+    }
+
+    #[tokio::test]
+    async fn missing_index_is_an_error() {
+        let temp = tempfile::tempdir().expect("tempdir");
+        let url = format!("file://{}", temp.path().display());
+        let source = StaticSource::new(&url, reqwest::Client::new());
+        assert!(source.versions("rack").await.is_err());
+    }
+}
@@ -0,0 +1,50 @@
+//! Minimal `.gem` archives for tests that read gems from disk.
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Write `dir/name-version.gem` holding only `metadata.gz`
+///
+/// Each dependency is a `(name, minimum version)` runtime requirement.
+pub(crate) fn write_gem(
+    dir: &Path,
+    name: &str,
+    version: &str,
+    dependencies: &[(&str, &str)],
+) -> PathBuf {
+    let mut yaml = format!(
+        "--- !ruby/object:Gem::Specification\nname: {name}\nversion: !ruby/object:Gem::Version\n  version: {version}\nplatform: ruby\ndependencies:"
+    );
+    if dependencies.is_empty() {
+        yaml.push_str(" []\n");
+    }
+    for (dependency, minimum) in dependencies {
+        write!(
+            yaml,
+            "\n- !ruby/object:Gem::Dependency\n  name: {dependency}\n  requirement: !ruby/object:Gem::Requirement\n    requirements:\n    - - \">=\"\n      - !ruby/object:Gem::Version\n        version: '{minimum}'\n  type: :runtime\n"
+        )
+        .expect("format dependency");
+    }
+
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    gz.write_all(yaml.as_bytes()).expect("gzip metadata");
+    let metadata = gz.finish().expect("finish metadata");
+
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(metadata.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, "metadata.gz", metadata.as_slice())
+        .expect("append metadata");
+
+    fs::create_dir_all(dir).expect("create gem directory");
+    let path = dir.join(format!("{name}-{version}.gem"));
+    fs::write(&path, builder.into_inner().expect("finish gem")).expect("write gem");
+    path
+}
@@ -116,8 +116,7 @@ pub mod options {
             remote: false,
             both: false,
             bulk_threshold: None,
-            clear_sources: false,
-            source: None,
+            sources: Vec::new(),
             http_proxy: None,
             verbose: false,
             quiet: false,
@@ -126,6 +125,7 @@ pub mod options {
             backtrace: false,
             debug: false,
             norc: false,
+            yes: false,
         }
     }
 }
@@ -135,7 +135,9 @@ pub mod assertions {
     /// Assert that an error message contains a specific substring
     pub fn assert_error_contains(error_msg: &str, expected_text: &str) {
         assert!(
-            error_msg.to_lowercase().contains(&expected_text.to_lowercase()),
+            error_msg
+                .to_lowercase()
+                .contains(&expected_text.to_lowercase()),
             "Error message '{}' does not contain '{}'",
             error_msg,
             expected_text
@@ -11,14 +11,26 @@ use thiserror::Error;
 use x509_cert::Certificate;
 use x509_verify::{Signature, VerifyInfo, VerifyingKey};
 
+/// Archive entries a signed gem carries a `.sig` signature for
+const SIGNED_ENTRIES: [&str; 3] = ["metadata.gz", "data.tar.gz", "checksums.yaml.gz"];
+
+/// How to make a signer trusted, appended to verification errors
+const ADD_CERT_HINT: &str =
+    "add the signer's certificate to ~/.gem/trust with `lode gem-cert --add CERT.pem`";
+
 /// Trust policy levels for gem signature verification
+///
+/// Mirrors `Gem::Security`: `HighSecurity` only installs gems signed by a
+/// trusted certificate, `MediumSecurity` also accepts unsigned gems but
+/// still rejects signed ones it cannot verify, and `LowSecurity` only warns.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TrustPolicy {
-    /// All gems must be signed and verified
+    /// All gems must be signed by a trusted certificate
     HighSecurity,
-    /// All signed gems must be verified (allows unsigned gems)
+    /// Signed gems must be signed by a trusted certificate; unsigned gems
+    /// are allowed
     MediumSecurity,
-    /// Warns about unsigned gems but allows them
+    /// Warns about unsigned gems and untrusted signers but allows them
     LowSecurity,
     /// No verification (default)
     NoSecurity,
@@ -77,13 +89,16 @@ impl std::fmt::Display for TrustPolicy {
 /// Errors that can occur during gem signature verification
 #[derive(Debug, Error)]
 pub enum VerificationError {
-    #[error("Gem is not signed: {gem_path}")]
+    #[error(
+        "Gem is not signed: {gem_path} (HighSecurity only installs signed gems; \
+         use -P MediumSecurity to also allow unsigned ones)"
+    )]
     UnsignedGem { gem_path: String },
 
     #[error("Invalid signature for {gem_path}: {reason}")]
     InvalidSignature { gem_path: String, reason: String },
 
-    #[error("No trusted certificate found for {gem_path}")]
+    #[error("No trusted certificate found for {gem_path} ({hint})", hint = ADD_CERT_HINT)]
     NoTrustedCertificate { gem_path: String },
 
     #[error("Failed to load certificate from {path}: {source}")]
@@ -117,8 +132,15 @@ impl GemVerifier {
     /// Returns an error if the trust directory cannot be accessed or certificates cannot be loaded.
     pub fn new(policy: TrustPolicy) -> Result<Self> {
         let home = dirs::home_dir().context("Failed to find home directory")?;
-        let trust_dir = home.join(".gem").join("trust");
+        Self::with_trust_dir(policy, home.join(".gem").join("trust"))
+    }
 
+    /// Create a gem verifier trusting the certificates in `trust_dir`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the trust directory cannot be accessed or certificates cannot be loaded.
+    pub fn with_trust_dir(policy: TrustPolicy, trust_dir: PathBuf) -> Result<Self> {
         let mut verifier = Self {
             policy,
             trust_dir,
@@ -179,11 +201,15 @@ impl GemVerifier {
 
     /// Verify a gem file according to the trust policy.
     ///
+    /// A signed gem must carry a signature for each of its metadata, data
+    /// and checksums entries, and every signature must verify against a
+    /// trusted certificate. `LowSecurity` only warns when that fails.
+    ///
     /// # Errors
     ///
     /// Returns an error if:
     /// - The gem is unsigned and the policy requires signatures
-    /// - The gem has an invalid signature
+    /// - The gem has an invalid or missing signature
     /// - No trusted certificate is found for the gem
     pub fn verify_gem(&self, gem_path: &Path) -> Result<(), VerificationError> {
         // NoSecurity policy: skip all verification
@@ -200,19 +226,21 @@ impl GemVerifier {
                 TrustPolicy::HighSecurity => Err(VerificationError::UnsignedGem {
                     gem_path: gem_path_str,
                 }),
-                TrustPolicy::MediumSecurity | TrustPolicy::LowSecurity => {
-                    if self.policy == TrustPolicy::LowSecurity {
-                        eprintln!("  Warning: Gem is not signed: {gem_path_str}");
-                    }
+                TrustPolicy::LowSecurity => {
+                    eprintln!("  Warning: Gem is not signed: {gem_path_str}");
                     Ok(())
                 }
-                TrustPolicy::NoSecurity => Ok(()),
+                TrustPolicy::MediumSecurity | TrustPolicy::NoSecurity => Ok(()),
             };
         }
 
-        self.verify_signature(gem_path)?;
-
-        Ok(())
+        match self.verify_signatures(gem_path) {
+            Err(e) if self.policy == TrustPolicy::LowSecurity => {
+                eprintln!("  Warning: {e}");
+                Ok(())
+            }
+            result => result,
+        }
     }
 
     /// Check if a gem file is signed
@@ -258,11 +286,11 @@ impl GemVerifier {
         Ok(false)
     }
 
-    /// Verify the signature of a signed gem using X.509 certificates
+    /// Verify each signed entry of a gem against the trusted certificates
     ///
-    /// Extracts signature files from the gem archive and verifies them against
-    /// trusted certificates using RSA/SHA256 verification.
-    fn verify_signature(&self, gem_path: &Path) -> Result<(), VerificationError> {
+    /// Extracts the signed entries and their `.sig` files from the gem
+    /// archive and verifies them using RSA/SHA256 verification.
+    fn verify_signatures(&self, gem_path: &Path) -> Result<(), VerificationError> {
         let gem_path_str = gem_path.display().to_string();
 
         if self.certificates.is_empty() {
@@ -271,64 +299,69 @@ impl GemVerifier {
             });
         }
 
-        let (data_content, sig_content) =
-            Self::extract_gem_signature_files(gem_path).map_err(|e| {
-                VerificationError::InvalidSignature {
-                    gem_path: gem_path_str.clone(),
-                    reason: format!("Failed to extract signature files: {e}"),
-                }
-            })?;
+        let entries = Self::extract_signed_entries(gem_path).map_err(|e| {
+            VerificationError::InvalidSignature {
+                gem_path: gem_path_str.clone(),
+                reason: format!("Failed to extract signature files: {e}"),
+            }
+        })?;
 
-        let mut last_error = None;
-        for (cert_name, cert_pem) in &self.certificates {
-            match Self::verify_with_certificate(&data_content, &sig_content, cert_pem) {
-                Ok(()) => return Ok(()),
-                Err(e) => {
-                    last_error = Some(format!("Certificate '{cert_name}': {e}"));
+        for name in SIGNED_ENTRIES {
+            let Some(data) = entries.get(name) else {
+                continue;
+            };
+            let Some(signature) = entries.get(&format!("{name}.sig")) else {
+                return Err(VerificationError::InvalidSignature {
+                    gem_path: gem_path_str,
+                    reason: format!("missing signature for {name}"),
+                });
+            };
+
+            let mut last_error = None;
+            for (cert_name, cert_pem) in &self.certificates {
+                match Self::verify_with_certificate(data, signature, cert_pem) {
+                    Ok(()) => {
+                        last_error = None;
+                        break;
+                    }
+                    Err(e) => {
+                        last_error = Some(format!("Certificate '{cert_name}': {e}"));
+                    }
                 }
             }
+            if let Some(reason) = last_error {
+                return Err(VerificationError::InvalidSignature {
+                    gem_path: gem_path_str,
+                    reason: format!("{name}: {reason} (if you trust the signer, {ADD_CERT_HINT})"),
+                });
+            }
         }
 
-        Err(VerificationError::InvalidSignature {
-            gem_path: gem_path_str,
-            reason: last_error.unwrap_or_else(|| "No matching certificate found".to_string()),
-        })
+        Ok(())
     }
 
-    /// Extract data.tar.gz and data.tar.gz.sig from gem archive
-    fn extract_gem_signature_files(gem_path: &Path) -> Result<(Vec<u8>, Vec<u8>)> {
+    /// Extract the signed entries and their signatures from a gem archive
+    fn extract_signed_entries(gem_path: &Path) -> Result<HashMap<String, Vec<u8>>> {
         let file = File::open(gem_path)
             .with_context(|| format!("Failed to open gem file: {}", gem_path.display()))?;
         let mut archive = Archive::new(file);
 
-        let mut data_content = None;
-        let mut sig_content = None;
-
+        let mut entries = HashMap::new();
         for entry_result in archive.entries()? {
             let mut entry = entry_result?;
-            let path = entry.path()?;
-            let path_str = path.to_string_lossy();
-
-            if path_str == "data.tar.gz" {
-                let mut content = Vec::new();
-                entry.read_to_end(&mut content)?;
-                data_content = Some(content);
-            } else if path_str == "data.tar.gz.sig" {
+            let path = entry.path()?.to_string_lossy().into_owned();
+            let name = path.strip_suffix(".sig").unwrap_or(&path);
+            if SIGNED_ENTRIES.contains(&name) {
                 let mut content = Vec::new();
                 entry.read_to_end(&mut content)?;
-                sig_content = Some(content);
-            }
-
-            if data_content.is_some() && sig_content.is_some() {
-                break;
+                entries.insert(path, content);
             }
         }
 
-        match (data_content, sig_content) {
-            (Some(data), Some(sig)) => Ok((data, sig)),
-            (None, _) => anyhow::bail!("data.tar.gz not found in gem archive"),
-            (_, None) => anyhow::bail!("data.tar.gz.sig not found in gem archive"),
+        if !entries.contains_key("data.tar.gz") {
+            anyhow::bail!("data.tar.gz not found in gem archive");
         }
+        Ok(entries)
     }
 
     /// Verify signature using a specific certificate
@@ -601,5 +634,53 @@ mod tests {
             assert!(!GemVerifier::is_gem_signed(&gem_path)?);
             Ok(())
         }
+
+        #[test]
+        fn unsigned_gems_follow_policy() -> Result<()> {
+            let temp = TempDir::new()?;
+            let gem_path = create_test_gem_unsigned(&temp)?;
+            let trust_dir = temp.path().join("trust");
+
+            let high = GemVerifier::with_trust_dir(TrustPolicy::HighSecurity, trust_dir.clone())?;
+            assert!(matches!(
+                high.verify_gem(&gem_path),
+                Err(VerificationError::UnsignedGem { .. })
+            ));
+            let medium = GemVerifier::with_trust_dir(TrustPolicy::MediumSecurity, trust_dir)?;
+            assert!(medium.verify_gem(&gem_path).is_ok());
+            Ok(())
+        }
+
+        #[test]
+        fn untrusted_signer_rejected_unless_low_security() -> Result<()> {
+            let temp = TempDir::new()?;
+            let gem_path = temp.path().join("signed-1.0.0.gem");
+            let mut builder = Builder::new(fs::File::create(&gem_path)?);
+            for (name, content) in [
+                ("metadata.gz", &b"metadata"[..]),
+                ("metadata.gz.sig", b"signature"),
+                ("data.tar.gz", b"data"),
+                ("data.tar.gz.sig", b"signature"),
+            ] {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(content.len() as u64);
+                builder.append_data(&mut header, name, Cursor::new(content))?;
+            }
+            builder.finish()?;
+            let trust_dir = temp.path().join("trust");
+
+            let medium =
+                GemVerifier::with_trust_dir(TrustPolicy::MediumSecurity, trust_dir.clone())?;
+            let err = medium.verify_gem(&gem_path).unwrap_err();
+            assert!(matches!(
+                err,
+                VerificationError::NoTrustedCertificate { .. }
+            ));
+            assert!(err.to_string().contains("lode gem-cert --add"));
+
+            let low = GemVerifier::with_trust_dir(TrustPolicy::LowSecurity, trust_dir)?;
+            assert!(low.verify_gem(&gem_path).is_ok());
+            Ok(())
+        }
     }
 }
@@ -0,0 +1,245 @@
+//! Near-miss detection for gem names
+//!
+//! Typosquatting gems take a name one or two keystrokes away from a popular
+//! gem (`nokogirl` for `nokogiri`) and wait for a mistyped `lode add`. Names
+//! are compared against a bundled list of the most downloaded gems, so the
+//! check works offline; download counts are looked up separately.
+
+/// Most downloaded gems on rubygems.org, the names worth squatting
+const POPULAR_GEMS: &[&str] = &[
+    "actioncable",
+    "actionmailbox",
+    "actionmailer",
+    "actionpack",
+    "actiontext",
+    "actionview",
+    "activejob",
+    "activemodel",
+    "activerecord",
+    "activestorage",
+    "activesupport",
+    "addressable",
+    "ast",
+    "autoprefixer-rails",
+    "aws-eventstream",
+    "aws-partitions",
+    "aws-sdk-core",
+    "aws-sdk-kms",
+    "aws-sdk-s3",
+    "aws-sigv4",
+    "bcrypt",
+    "bigdecimal",
+    "bootsnap",
+    "builder",
+    "bundler",
+    "byebug",
+    "capybara",
+    "coderay",
+    "concurrent-ruby",
+    "connection_pool",
+    "crass",
+    "date",
+    "devise",
+    "diff-lcs",
+    "docile",
+    "dotenv",
+    "erubi",
+    "excon",
+    "factory_bot",
+    "faker",
+    "faraday",
+    "faraday-net_http",
+    "ffi",
+    "globalid",
+    "google-protobuf",
+    "grpc",
+    "hashie",
+    "i18n",
+    "io-console",
+    "irb",
+    "jbuilder",
+    "jmespath",
+    "json",
+    "jwt",
+    "kaminari",
+    "loofah",
+    "mail",
+    "marcel",
+    "method_source",
+    "mime-types",
+    "mini_mime",
+    "mini_portile2",
+    "minitest",
+    "msgpack",
+    "multi_json",
+    "multipart-post",
+    "mustermann",
+    "mysql2",
+    "net-http",
+    "net-imap",
+    "net-pop",
+    "net-protocol",
+    "net-smtp",
+    "nio4r",
+    "nokogiri",
+    "oauth2",
+    "oj",
+    "parallel",
+    "parser",
+    "pg",
+    "pry",
+    "psych",
+    "public_suffix",
+    "puma",
+    "racc",
+    "rack",
+    "rack-test",
+    "rails",
+    "rails-dom-testing",
+    "rails-html-sanitizer",
+    "railties",
+    "rainbow",
+    "rake",
+    "rb-fsevent",
+    "rb-inotify",
+    "rdoc",
+    "redis",
+    "regexp_parser",
+    "reline",
+    "representable",
+    "rest-client",
+    "rexml",
+    "rspec",
+    "rspec-core",
+    "rspec-expectations",
+    "rspec-mocks",
+    "rspec-rails",
+    "rspec-support",
+    "rubocop",
+    "rubocop-ast",
+    "rubocop-rails",
+    "ruby-progressbar",
+    "rubyzip",
+    "sass",
+    "sassc",
+    "selenium-webdriver",
+    "sidekiq",
+    "simplecov",
+    "sinatra",
+    "sprockets",
+    "sprockets-rails",
+    "sqlite3",
+    "stringio",
+    "thor",
+    "tilt",
+    "timeout",
+    "tzinfo",
+    "unicode-display_width",
+    "uri",
+    "webmock",
+    "webrick",
+    "websocket-driver",
+    "websocket-extensions",
+    "yard",
+    "zeitwerk",
+];
+
+/// Names shorter than this are not checked; two- and three-letter gems are
+/// all a keystroke or two from each other (`ox`, `oj`, `pg`)
+const MIN_NAME_LEN: usize = 4;
+
+/// Names this short only count as near-misses at distance 1; at distance 2
+/// most short names are near something
+const SHORT_NAME: usize = 4;
+
+/// Downloads above which a gem is established in its own right, however
+/// close its name is to a popular one (`rackup`, `erubis`, `oauth`)
+pub const ESTABLISHED_DOWNLOADS: u64 = 1_000_000;
+
+/// Popular gems `name` is one or two edits away from, closest first
+///
+/// Empty when `name` is itself one of the popular gems or shorter than
+/// [`MIN_NAME_LEN`].
+#[must_use]
+pub fn lookalikes(name: &str) -> Vec<&'static str> {
+    let name = name.to_ascii_lowercase();
+    if name.chars().count() < MIN_NAME_LEN || POPULAR_GEMS.contains(&name.as_str()) {
+        return Vec::new();
+    }
+    let max_distance = if name.chars().count() <= SHORT_NAME {
+        1
+    } else {
+        2
+    };
+
+    let mut matches: Vec<(usize, &'static str)> = POPULAR_GEMS
+        .iter()
+        .filter(|popular| popular.len().abs_diff(name.len()) <= max_distance)
+        .map(|popular| (levenshtein(&name, popular), *popular))
+        .filter(|(distance, _)| (1..=max_distance).contains(distance))
+        .collect();
+    matches.sort_unstable();
+    matches.into_iter().map(|(_, popular)| popular).collect()
+}
+
+/// Edit distance between two strings (insertions, deletions, substitutions)
+#[must_use]
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = previous.first().copied().unwrap_or_default();
+        let mut left = i + 1;
+        if let Some(first) = current.first_mut() {
+            *first = left;
+        }
+        for (j, b_char) in b.iter().enumerate() {
+            let above = previous.get(j + 1).copied().unwrap_or_default();
+            let cost = usize::from(a_char != *b_char);
+            let distance = (diagonal + cost).min(above + 1).min(left + 1);
+            if let Some(cell) = current.get_mut(j + 1) {
+                *cell = distance;
+            }
+            diagonal = above;
+            left = distance;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous.last().copied().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(levenshtein("nokogiri", "nokogiri"), 0);
+        assert_eq!(levenshtein("nokogirl", "nokogiri"), 1);
+        assert_eq!(levenshtein("nokogri", "nokogiri"), 1);
+        assert_eq!(levenshtein("rails", "rials"), 2);
+        assert_eq!(levenshtein("", "rack"), 4);
+    }
+
+    #[test]
+    fn near_misses_of_popular_gems() {
+        assert_eq!(lookalikes("nokogirl"), vec!["nokogiri"]);
+        assert_eq!(lookalikes("Nokogirl"), vec!["nokogiri"]);
+        assert_eq!(lookalikes("activesupprot"), vec!["activesupport"]);
+        assert_eq!(lookalikes("rspec_rails"), vec!["rspec-rails"]);
+    }
+
+    #[test]
+    fn popular_and_distant_names_pass() {
+        assert!(lookalikes("nokogiri").is_empty());
+        assert!(lookalikes("rack").is_empty());
+        assert!(lookalikes("my-internal-gem").is_empty());
+        // Short names only match at distance 1, and the shortest not at all
+        assert!(lookalikes("thro").is_empty());
+        assert!(lookalikes("rakk").contains(&"rake"));
+        assert!(lookalikes("ox").is_empty());
+        assert!(lookalikes("pgx").is_empty());
+    }
+}
@@ -182,6 +182,59 @@ fn exec_environment_variables() {
     );
 }
 
+/// Test 9: lode exec --cached-setup with a fresh activation manifest
+///
+/// The load path comes straight from the manifest: the Gemfile asks for a
+/// version that is not locked and its source is unreachable, so any attempt
+/// to resolve would fail or rewrite the lockfile, and no gem directory
+/// exists to scan.
+#[test]
+fn exec_cached_setup_skips_resolution() {
+    let temp = TempDir::new().unwrap();
+    fs::write(
+        temp.path().join("Gemfile"),
+        "source 'http://127.0.0.1:9'\n\ngem 'rake', '99.0'\n",
+    )
+    .unwrap();
+    let lockfile_path = create_test_lockfile(&temp, &[("rake", "13.0.6")]);
+    let lockfile = fs::read_to_string(&lockfile_path).unwrap();
+    let rake_lib = temp.path().join("elsewhere/rake-13.0.6/lib");
+    lode::activation::write(
+        &temp.path().join(lode::activation::MANIFEST_FILE),
+        &lode::activation::lockfile_digest(&lockfile),
+        std::slice::from_ref(&rake_lib),
+    )
+    .unwrap();
+
+    let output = Command::new(get_lode_binary())
+        .current_dir(temp.path())
+        .env("BUNDLE_PATH", temp.path().join("vendor"))
+        .env("LODE_LOG", "debug")
+        .env_remove("RUBYLIB")
+        .env_remove("RUBYOPT")
+        .args([
+            "exec",
+            "--cached-setup",
+            "sh",
+            "-c",
+            "echo \"$RUBYLIB|$RUBYOPT\"",
+        ])
+        .output()
+        .expect("Failed to execute lode exec --cached-setup");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(
+        stdout.contains(rake_lib.to_string_lossy().as_ref()),
+        "RUBYLIB should hold the manifest paths. stdout: {stdout}"
+    );
+    assert!(!stdout.contains("bundler/setup"), "stdout: {stdout}");
+    assert!(stderr.contains("(activation manifest)"), "stderr: {stderr}");
+    assert!(!stderr.contains("resolv"), "stderr: {stderr}");
+    assert_eq!(fs::read_to_string(&lockfile_path).unwrap(), lockfile);
+}
+
 // ============================================================================
 // search command Tests - Search for gems on RubyGems.org
 // ============================================================================
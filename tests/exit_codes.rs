@@ -0,0 +1,159 @@
+mod common;
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+use common::get_lode_binary;
+use common::helpers::{create_test_gemfile, create_test_lockfile};
+
+// ============================================================================
+// Exit codes - distinct codes scripts can branch on
+// ============================================================================
+
+/// Run lode in `dir` with its caches, config and bundle kept inside `dir`
+fn lode(dir: &TempDir, args: &[&str], env: &[(&str, &str)]) -> Output {
+    Command::new(get_lode_binary())
+        .current_dir(dir.path())
+        .args(args)
+        .env("HOME", dir.path())
+        .env("XDG_CACHE_HOME", dir.path().join(".cache"))
+        .env("XDG_CONFIG_HOME", dir.path().join(".config"))
+        .env("BUNDLE_SILENCE_ROOT_WARNING", "1")
+        .env("BUNDLE_PATH", dir.path().join("vendor/bundle"))
+        .env_remove("GEM_SOURCE")
+        .envs(env.iter().copied())
+        .output()
+        .expect("Failed to run lode")
+}
+
+/// Serve `/api/v1/versions/<gem>.json` bodies from `gems`, 404 otherwise
+fn serve_versions(gems: &'static [(&'static str, &'static str)]) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut request_line = String::new();
+            let mut reader = BufReader::new(&stream);
+            reader.read_line(&mut request_line).ok();
+            let mut header = String::new();
+            while reader.read_line(&mut header).is_ok_and(|n| n > 2) {
+                header.clear();
+            }
+            let body = gems.iter().find_map(|(gem, body)| {
+                request_line
+                    .contains(&format!("/api/v1/versions/{gem}.json"))
+                    .then_some(*body)
+            });
+            let response = body.map_or_else(
+                || "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+                |body| {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                },
+            );
+            stream.write_all(response.as_bytes()).ok();
+        }
+    });
+    url
+}
+
+#[test]
+fn exec_passes_on_the_child_exit_code() {
+    let temp = TempDir::new().unwrap();
+    create_test_gemfile(&temp, &[("rake", "13.0.6")]);
+    create_test_lockfile(&temp, &[("rake", "13.0.6")]);
+
+    let output = lode(&temp, &["exec", "--", "sh", "-c", "exit 42"], &[]);
+    assert_eq!(output.status.code(), Some(42));
+}
+
+#[cfg(unix)]
+#[test]
+fn exec_reports_a_signalled_child_like_a_shell() {
+    let temp = TempDir::new().unwrap();
+    create_test_gemfile(&temp, &[("rake", "13.0.6")]);
+    create_test_lockfile(&temp, &[("rake", "13.0.6")]);
+
+    let output = lode(&temp, &["exec", "--", "sh", "-c", "kill -TERM $$"], &[]);
+    assert_eq!(output.status.code(), Some(128 + 15));
+}
+
+#[test]
+fn install_without_a_lockfile_exits_16() {
+    let temp = TempDir::new().unwrap();
+    create_test_gemfile(&temp, &[("rake", "13.0.6")]);
+
+    let output = lode(&temp, &["install"], &[]);
+    assert_eq!(
+        output.status.code(),
+        Some(16),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn deployment_with_a_stale_lockfile_exits_16() {
+    let temp = TempDir::new().unwrap();
+    create_test_gemfile(&temp, &[("rake", "13.0.6"), ("rack", "3.1.7")]);
+    create_test_lockfile(&temp, &[("rake", "13.0.6")]);
+
+    let output = lode(&temp, &["install"], &[("BUNDLE_DEPLOYMENT", "true")]);
+    assert_eq!(
+        output.status.code(),
+        Some(16),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn lock_against_an_unreachable_source_exits_17() {
+    let temp = TempDir::new().unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let source = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    std::fs::write(
+        temp.path().join("Gemfile"),
+        format!("source \"{source}\"\ngem \"rack\"\n"),
+    )
+    .unwrap();
+
+    let output = lode(&temp, &["lock"], &[]);
+    assert_eq!(
+        output.status.code(),
+        Some(17),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn lock_with_conflicting_requirements_exits_6() {
+    static GEMS: &[(&str, &str)] = &[
+        (
+            "app",
+            r#"[{"number":"1.0.0","platform":"ruby","dependencies":{"runtime":[{"name":"dep","requirements":"= 1.0.0"}]}}]"#,
+        ),
+        ("dep", r#"[{"number":"2.0.0","platform":"ruby"}]"#),
+    ];
+    let source = serve_versions(GEMS);
+    let temp = TempDir::new().unwrap();
+    std::fs::write(
+        temp.path().join("Gemfile"),
+        format!("source \"{source}\"\ngem \"app\"\n"),
+    )
+    .unwrap();
+
+    let output = lode(&temp, &["lock"], &[]);
+    assert_eq!(
+        output.status.code(),
+        Some(6),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -500,6 +500,32 @@ mod gem_owner_cli_tests {
         );
     }
 
+    #[test]
+    fn gem_sources_compose_in_command_line_order() {
+        let output = Command::new("target/debug/lode")
+            .args([
+                "gem-info",
+                "rake",
+                "--norc",
+                "--debug",
+                "-s",
+                "https://a.test",
+                "--clear-sources",
+                "-s",
+                "https://b.test",
+                "--source",
+                "https://c.test/",
+            ])
+            .output()
+            .expect("Failed to execute lode gem-info with sources");
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("DEBUG: Sources: https://b.test, https://c.test"),
+            "--clear-sources should drop earlier sources. stderr: {stderr}"
+        );
+    }
+
     #[test]
     fn gem_dependency_accepts_http_proxy_flag() {
         let output = Command::new("target/debug/lode")
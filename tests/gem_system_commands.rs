@@ -81,6 +81,41 @@ fn gem_uninstall_executables_flag() {
     );
 }
 
+/// Test gem-uninstall asks which version to remove when several are installed
+#[test]
+fn gem_uninstall_picks_version_from_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let temp = tempfile::tempdir().unwrap();
+    let gems = temp.path().join("gems");
+    for version in ["12.3.3", "13.1.0"] {
+        std::fs::create_dir_all(gems.join(format!("rake-{version}"))).unwrap();
+    }
+
+    let mut child = Command::new(get_lode_binary())
+        .args([
+            "gem-uninstall",
+            "rake",
+            "--ignore-dependencies",
+            "--install-dir",
+        ])
+        .arg(&gems)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute lode gem-uninstall");
+    child.stdin.take().unwrap().write_all(b"1\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains(" 1. rake-12.3.3\n 2. rake-13.1.0\n 3. All versions"));
+    assert!(!gems.join("rake-12.3.3").exists());
+    assert!(gems.join("rake-13.1.0").exists());
+}
+
 /// Test gem-uninstall --ignore-dependencies flag
 #[test]
 fn gem_uninstall_ignore_dependencies() {
@@ -438,3 +438,102 @@ fn install_no_lockfile() {
         "error should mention missing lockfile. stderr: {stderr}"
     );
 }
+
+/// Write a minimal `rake-13.0.6.gem` into lode's download cache under `home`
+fn cache_fake_rake_gem(home: &std::path::Path) {
+    fn tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *contents).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    let metadata = gzip(
+        b"--- !ruby/object:Gem::Specification\nname: rake\nversion: !ruby/object:Gem::Version\n  version: 13.0.6\nplatform: ruby\nrequire_paths:\n- lib\n",
+    );
+    let data = gzip(&tar(&[("lib/rake.rb", b"module Rake; end\n")]));
+    let cache = home.join(".cache/lode/gems");
+    fs::create_dir_all(&cache).unwrap();
+    fs::write(
+        cache.join("rake-13.0.6.gem"),
+        tar(&[("metadata.gz", &metadata), ("data.tar.gz", &data)]),
+    )
+    .unwrap();
+}
+
+/// Test 21: Install --resume reinstalls a gem an interrupted install left half extracted
+#[test]
+fn install_resume_redoes_unfinished_gems() {
+    let temp = TempDir::new().unwrap();
+    create_test_gemfile(&temp, &[("rake", "13.0.6")]);
+    create_test_lockfile(&temp, &[("rake", "13.0.6")]);
+    cache_fake_rake_gem(temp.path());
+
+    let install = |args: &[&str]| {
+        Command::new(get_lode_binary())
+            .current_dir(temp.path())
+            .args(args)
+            .env("HOME", temp.path())
+            .env("XDG_CACHE_HOME", temp.path().join(".cache"))
+            .env("XDG_CONFIG_HOME", temp.path().join(".config"))
+            .env("BUNDLE_PATH", temp.path().join("vendor"))
+            .env("BUNDLE_SILENCE_ROOT_WARNING", "1")
+            .output()
+            .expect("Failed to execute lode install")
+    };
+
+    let output = install(&["install", "--local"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let journal = temp.path().join(".lode/state/install.json");
+    assert!(!journal.exists(), "a finished install removes its journal");
+
+    // Interrupt the install mid-extraction: rake is pending, its directory partial
+    let gem_dir = fs::read_dir(temp.path().join("vendor/ruby"))
+        .unwrap()
+        .flatten()
+        .next()
+        .unwrap()
+        .path()
+        .join("gems/rake-13.0.6");
+    fs::remove_dir_all(gem_dir.join("lib")).unwrap();
+    let lockfile_sha256 = format!(
+        "{:x}",
+        <sha2::Sha256 as sha2::Digest>::digest(fs::read(temp.path().join("Gemfile.lock")).unwrap())
+    );
+    let interrupted = format!(
+        r#"{{"format":1,"lockfile_sha256":"{lockfile_sha256}","pending":["rake-13.0.6"],"completed":{{}}}}"#
+    );
+    fs::create_dir_all(journal.parent().unwrap()).unwrap();
+    fs::write(&journal, &interrupted).unwrap();
+
+    let output = install(&["install", "--local"]);
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("lode install --resume"),
+        "a plain install points at --resume"
+    );
+    assert!(!gem_dir.join("lib/rake.rb").exists());
+
+    fs::write(&journal, &interrupted).unwrap();
+    let output = install(&["install", "--local", "--resume"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(gem_dir.join("lib/rake.rb").exists());
+    assert!(!journal.exists());
+}
@@ -193,3 +193,29 @@ fn command_help_short_flag() {
         "Help should describe install command"
     );
 }
+
+/// Test that the global -V/-q flags work after any subcommand
+#[test]
+fn global_verbosity_flags_after_subcommands() {
+    for command in ["install", "lock", "update", "outdated", "exec", "gem-list"] {
+        for flag in ["-V", "--verbose", "-q", "--silent"] {
+            let output = Command::new(get_lode_binary())
+                .args([command, flag, "--help"])
+                .output()
+                .expect("Failed to execute lode");
+
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            assert!(
+                output.status.success(),
+                "lode {command} {flag} should be accepted. stderr: {stderr}"
+            );
+        }
+    }
+
+    // -v is only the version, never a subcommand's verbose flag
+    let output = Command::new(get_lode_binary())
+        .args(["lock", "-v", "--help"])
+        .output()
+        .expect("Failed to execute lode lock -v");
+    assert!(!output.status.success(), "lode lock -v should be rejected");
+}
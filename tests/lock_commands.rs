@@ -360,3 +360,42 @@ fn lock_basic_creation() {
         "Lockfile should contain PLATFORMS section"
     );
 }
+
+/// Test 16: lode lock --update refuses to move a held gem
+#[test]
+fn lock_update_refuses_held_gem() {
+    let temp = TempDir::new().unwrap();
+    let gemfile = create_test_gemfile(&temp, &[("rake", ">= 13.0")]);
+    let lockfile = create_test_lockfile(&temp, &[("rake", "13.0.6")]);
+
+    let output = Command::new(get_lode_binary())
+        .current_dir(temp.path())
+        .args(["hold", "rake", "--reason", "13.1 breaks our tasks"])
+        .output()
+        .expect("Failed to execute lode hold");
+    assert!(output.status.success(), "lode hold should succeed");
+    let config = fs::read_to_string(temp.path().join(".lode.toml")).unwrap();
+    assert!(config.contains("rake = \"13.1 breaks our tasks\""));
+
+    let output = Command::new(get_lode_binary())
+        .current_dir(temp.path())
+        .args([
+            "lock",
+            "--gemfile",
+            &gemfile,
+            "--lockfile",
+            &lockfile,
+            "--update",
+            "rake",
+        ])
+        .output()
+        .expect("Failed to execute lode lock");
+
+    assert!(!output.status.success(), "a held gem should not be updated");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("rake is held (13.1 breaks our tasks)"),
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains("--release-hold"));
+}
@@ -647,3 +647,63 @@ fn info_multiple_flags() {
         "info should accept multiple flags"
     );
 }
+
+/// Test 20: lode show --verify compares the cached .gem with the lockfile checksum
+#[test]
+fn show_verify_reports_checksum_mismatch() {
+    let temp = TempDir::new().unwrap();
+    // SHA-256 of "hello"
+    let lockfile_content = r"GEM
+  remote: https://rubygems.org/
+  specs:
+    rake (13.0.6)
+    rspec (3.12.0)
+
+PLATFORMS
+  ruby
+
+DEPENDENCIES
+  rake
+  rspec
+
+CHECKSUMS
+  rake (13.0.6) sha256=2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824
+  rspec (3.12.0) sha256=2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824
+
+RUBY VERSION
+   ruby 3.2.0
+
+BUNDLED WITH
+   2.4.6
+";
+    create_test_lockfile(&temp, lockfile_content);
+    create_mock_gem_dirs(&temp, &[("rake", "13.0.6"), ("rspec", "3.12.0")]);
+    let cache = temp.path().join("cache");
+    fs::create_dir_all(&cache).unwrap();
+    fs::write(cache.join("rake-13.0.6.gem"), "hello").unwrap();
+    fs::write(cache.join("rspec-3.12.0.gem"), "tampered").unwrap();
+
+    let verify = |gem: &str| {
+        Command::new(get_lode_binary())
+            .current_dir(temp.path())
+            .env("BUNDLE_PATH", temp.path().join("vendor"))
+            .env("BUNDLE_USER_CACHE", &cache)
+            .env("HOME", temp.path())
+            .args(["show", "--verify", gem])
+            .output()
+            .expect("Failed to execute lode show --verify")
+    };
+
+    let output = verify("rake");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("Source:    https://rubygems.org"));
+    assert!(stdout.contains("(matches lockfile)"));
+    assert!(stdout.contains("Signature: "));
+    assert!(stdout.contains("Installed: sha256="));
+
+    let output = verify("rspec");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("(DOES NOT MATCH lockfile)"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Provenance check failed"));
+}